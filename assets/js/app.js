@@ -37,6 +37,11 @@ topbar.config({barColors: {0: "#29d"}, shadowColor: "rgba(0, 0, 0, .3)"})
 window.addEventListener("phx:page-loading-start", _info => topbar.show(300))
 window.addEventListener("phx:page-loading-stop", _info => topbar.hide())
 
+// Inside the desktop app, opened configurations and trains go to the OS recent documents
+window.addEventListener("phx:recent_workspace", ({detail}) => {
+  window.__TSW__?.addRecentWorkspace(detail.route, detail.title)
+})
+
 // connect if there are any LiveViews on the page
 liveSocket.connect()
 
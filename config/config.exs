@@ -48,6 +48,13 @@ config :logger, :default_formatter,
   format: "$time $metadata[$level] $message\n",
   metadata: [:request_id]
 
+# Desktop releases link SQLCipher instead of the bundled SQLite, so a database
+# can be encrypted (see scripts/build-desktop.sh). The precompiled NIF has no
+# encryption, it is built against the system library then.
+if System.get_env("EXQLITE_USE_SYSTEM") do
+  config :exqlite, force_build: true
+end
+
 # Use Jason for JSON parsing in Phoenix
 config :phoenix, :json_library, Jason
 
@@ -22,6 +22,44 @@ if System.get_env("PHX_SERVER") || System.get_env("BURRITO") do
   config :tsw_io, TswIoWeb.Endpoint, server: true
 end
 
+# Air-gapped mode of the desktop shell, nothing may contact the internet
+config :tsw_io, :offline, System.get_env("TSW_IO_OFFLINE") == "1"
+
+# Update channel picked in the desktop shell, beta also checks for pre-releases
+update_channel = if System.get_env("TSW_IO_UPDATE_CHANNEL") == "beta", do: :beta, else: :stable
+config :tsw_io, :update_channel, update_channel
+
+# Proxy and root certificates of networks that inspect TLS, see TswIo.Network
+config :tsw_io, :http_proxy, System.get_env("TSW_IO_HTTP_PROXY")
+config :tsw_io, :ca_certificates, System.get_env("TSW_IO_CA_CERTS")
+config :tsw_io, :os_certificates, System.get_env("TSW_IO_OS_CERTS") != "0"
+
+# Content-Security-Policy the desktop shell wants for its main window
+config :tsw_io, :content_security_policy, System.get_env("TSW_IO_CSP")
+
+# The desktop shell waits for a line on stdout instead of polling /api/health
+config :tsw_io, :ready_signal, System.get_env("TSW_IO_READY_SIGNAL") == "1"
+
+# Feature flags the desktop shell turned on, see TswIo.Shell.feature_enabled?/1
+config :tsw_io,
+       :shell_features,
+       String.split(System.get_env("TSW_IO_FEATURES", ""), ",", trim: true)
+
+# Single sign-on: the shell names the cookie pages get the token in, the key
+# the identity provider signs tokens with is set up with the backend, see TswIo.Sso
+config :tsw_io, :sso,
+  cookie: System.get_env("TSW_IO_SSO_COOKIE"),
+  secret: System.get_env("TSW_IO_SSO_SECRET"),
+  public_key: System.get_env("TSW_IO_SSO_PUBLIC_KEY")
+
+# Token the desktop shell's event bridge connects with, a new one every launch
+config :tsw_io, :shell_token, System.get_env("TSW_IO_SHELL_TOKEN")
+
+# Where clients reach the backend through the desktop shell's proxy, which then
+# forwards their address, see TswIoWeb.Plugs.ForwardedClient
+public_url = System.get_env("TSW_IO_PUBLIC_URL")
+config :tsw_io, :behind_proxy, public_url != nil
+
 if config_env() == :prod do
   # Determine platform-specific data directory for the database.
   # Can be overridden with DATABASE_PATH environment variable.
@@ -63,13 +101,40 @@ if config_env() == :prod do
          Path.join(data_dir, "#{app_name_lower}.db")
        end).()
 
+  # The desktop shell passes the platform log directory so backend logs
+  # end up next to its own, rotated at 10 MB with 5 files kept.
+  if log_dir = System.get_env("TSW_IO_LOG_DIR") do
+    File.mkdir_p!(log_dir)
+
+    config :logger, :default_handler,
+      config: [
+        file: String.to_charlist(Path.join(log_dir, "backend.log")),
+        max_no_bytes: 10_000_000,
+        max_no_files: 5
+      ]
+  end
+
+  # Safe mode, offered by the desktop shell after repeated crashes, opens
+  # the database read-only so nothing can make things worse.
+  database_mode = if System.get_env("TSW_IO_SAFE_MODE") == "1", do: :readonly, else: :readwrite
+
   config :tsw_io, TswIo.Repo,
     database: database_path,
-    pool_size: String.to_integer(System.get_env("POOL_SIZE") || "5")
+    pool_size: String.to_integer(System.get_env("POOL_SIZE") || "5"),
+    mode: database_mode
+
+  # An encrypted database is opened with the passphrase the desktop shell
+  # asked for. It is removed from the environment so nothing else sees it.
+  # Only a release built against SQLCipher can, see config/config.exs.
+  if database_key = System.get_env("TSW_IO_DB_KEY") do
+    System.delete_env("TSW_IO_DB_KEY")
+    config :tsw_io, TswIo.Repo, key: database_key
+  end
 
   # The secret key base is used to sign/encrypt cookies and other secrets.
-  # For desktop releases, we generate a stable key based on machine identity.
-  # For server deployments, use the SECRET_KEY_BASE environment variable.
+  # The desktop shell passes one kept in the OS keychain in SECRET_KEY_BASE,
+  # as do server deployments. Without it, we fall back to a stable key based
+  # on machine identity.
   secret_key_base =
     System.get_env("SECRET_KEY_BASE") ||
       (fn ->
@@ -83,18 +148,45 @@ if config_env() == :prod do
   host = System.get_env("PHX_HOST") || "localhost"
   port = String.to_integer(System.get_env("PORT") || "4000")
 
+  # The desktop shell passes 127.0.0.1 explicitly, the server must never be
+  # reachable from other machines unless someone asks for it.
+  {:ok, bind_address} =
+    (System.get_env("TSW_IO_BIND_ADDRESS") || "127.0.0.1")
+    |> String.to_charlist()
+    |> :inet.parse_address()
+
   config :tsw_io, :dns_cluster_query, System.get_env("DNS_CLUSTER_QUERY")
 
   config :tsw_io, TswIoWeb.Endpoint,
-    url: [host: host, port: port, scheme: "http"],
-    http: [
-      ip: {127, 0, 0, 1},
-      port: port
-    ],
     secret_key_base: secret_key_base,
     check_origin: false,
     server: true
 
+  # The desktop shell can ask for HTTPS with a certificate it generated and
+  # pinned, so other users on the machine can't read the local traffic.
+  case {System.get_env("TSW_IO_TLS_CERT"), System.get_env("TSW_IO_TLS_KEY")} do
+    {certfile, keyfile} when is_binary(certfile) and is_binary(keyfile) ->
+      config :tsw_io, TswIoWeb.Endpoint,
+        url: [host: host, port: port, scheme: "https"],
+        http: false,
+        https: [ip: bind_address, port: port, certfile: certfile, keyfile: keyfile]
+
+    _ ->
+      # Behind the proxy, generated URLs name the address clients see
+      url =
+        case public_url && URI.parse(public_url) do
+          %URI{scheme: public_scheme, host: public_host, port: public_port} ->
+            [host: public_host, port: public_port, scheme: public_scheme]
+
+          _ ->
+            [host: host, port: port, scheme: "http"]
+        end
+
+      config :tsw_io, TswIoWeb.Endpoint,
+        url: url,
+        http: [ip: bind_address, port: port]
+  end
+
   # ## SSL Support
   #
   # To get SSL working, you will need to add the `https` key
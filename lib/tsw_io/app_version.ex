@@ -85,9 +85,44 @@ defmodule TswIo.AppVersion do
   """
   @spec fetch_latest_version() :: {:ok, String.t()} | {:error, term()}
   def fetch_latest_version do
-    url = "https://api.github.com/repos/#{@github_repo}/releases/latest"
+    if TswIo.Network.offline?(), do: {:error, :offline}, else: request_latest_version()
+  end
+
+  @doc """
+  Get the update channel the desktop shell follows.
+
+  Set by the shell through `TSW_IO_UPDATE_CHANNEL`, `:beta` also offers pre-releases.
+  """
+  @spec update_channel() :: :stable | :beta
+  def update_channel do
+    Application.get_env(:tsw_io, :update_channel, :stable)
+  end
+
+  @doc """
+  Pick the newest release of a GitHub releases listing, newest first, that isn't a draft.
+  """
+  @spec newest_release([map()]) :: {:ok, String.t()} | {:error, :no_releases}
+  def newest_release(releases) do
+    case Enum.find(releases, &(!&1["draft"])) do
+      nil -> {:error, :no_releases}
+      release -> {:ok, parse_version(release["tag_name"])}
+    end
+  end
+
+  defp request_latest_version do
+    case update_channel() do
+      :stable -> request_release("releases/latest")
+      :beta -> request_release("releases?per_page=10")
+    end
+  end
+
+  defp request_release(path) do
+    url = "https://api.github.com/repos/#{@github_repo}/#{path}"
+
+    case Req.get(url, [headers: github_headers()] ++ TswIo.Network.req_options()) do
+      {:ok, %{status: 200, body: releases}} when is_list(releases) ->
+        newest_release(releases)
 
-    case Req.get(url, headers: github_headers()) do
       {:ok, %{status: 200, body: body}} ->
         version = parse_version(body["tag_name"])
         {:ok, version}
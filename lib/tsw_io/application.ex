@@ -22,6 +22,9 @@ defmodule TswIo.Application do
         TswIo.Firmware.UploadManager,
         TswIo.Train.Detection,
         TswIo.Train.Calibration.SessionSupervisor,
+        TswIo.Pairing,
+        TswIo.Shell.Nonces,
+        TswIo.Badge,
         # Start to serve requests, typically the last entry
         TswIoWeb.Endpoint
       ] ++
@@ -33,7 +36,12 @@ defmodule TswIo.Application do
     # See https://hexdocs.pm/elixir/Supervisor.html
     # for other strategies and supported options
     opts = [strategy: :one_for_one, name: TswIo.Supervisor]
-    Supervisor.start_link(children, opts)
+
+    with {:ok, pid} <- Supervisor.start_link(children, opts) do
+      # Migrations ran and the endpoint listens once every child started
+      TswIo.Readiness.announce()
+      {:ok, pid}
+    end
   end
 
   # Tell Phoenix to update the endpoint configuration
@@ -48,7 +56,13 @@ defmodule TswIo.Application do
     # Skip migrations in dev/test (when not in a release).
     # Run migrations automatically when using a release (including Burrito desktop builds).
     # RELEASE_NAME is set by Mix releases, BURRITO is set by the Tauri sidecar launcher.
-    System.get_env("RELEASE_NAME") == nil and System.get_env("BURRITO") == nil
+    # The shell's safe mode, offered after repeated crashes, never migrates.
+    safe_mode?() or
+      (System.get_env("RELEASE_NAME") == nil and System.get_env("BURRITO") == nil)
+  end
+
+  defp safe_mode?() do
+    System.get_env("TSW_IO_SAFE_MODE") == "1"
   end
 
   # Returns the Simulator.Connection child spec only in non-test environments.
@@ -0,0 +1,31 @@
+defmodule TswIo.Badge do
+  @moduledoc """
+  The count the desktop shell shows on the app icon.
+
+  Setting it pushes it to the shell over the bridge, see `TswIo.Shell`. The
+  shell can also be set to poll it instead, from `GET /api/admin/badge`.
+  """
+
+  use Agent
+
+  alias TswIo.Shell
+
+  def start_link(_opts) do
+    Agent.start_link(fn -> 0 end, name: __MODULE__)
+  end
+
+  @doc """
+  The current count, 0 when there is nothing to show.
+  """
+  @spec count() :: non_neg_integer()
+  def count, do: Agent.get(__MODULE__, & &1)
+
+  @doc """
+  Change the count and tell the shell about it, 0 clears the badge.
+  """
+  @spec set(non_neg_integer()) :: :ok
+  def set(count) when is_integer(count) and count >= 0 do
+    Agent.update(__MODULE__, fn _ -> count end)
+    Shell.emit("badge", %{count: count})
+  end
+end
@@ -0,0 +1,52 @@
+defmodule TswIo.Export do
+  @moduledoc """
+  Every record in the database as plain data, for the desktop shell's
+  "Export everything" archive.
+
+  Rows keep their ids and foreign keys, so the export holds the same
+  relations as the database. Secrets like the simulator API key are left out.
+  """
+
+  alias TswIo.Repo
+
+  @format_version 1
+
+  # Parents before children
+  @schemas [
+    TswIo.Hardware.Device,
+    TswIo.Hardware.Input,
+    TswIo.Hardware.Input.Calibration,
+    TswIo.Train.Train,
+    TswIo.Train.Element,
+    TswIo.Train.LeverConfig,
+    TswIo.Train.Notch,
+    TswIo.Train.LeverInputBinding,
+    TswIo.Simulator.Config,
+    TswIo.Firmware.FirmwareRelease,
+    TswIo.Firmware.FirmwareFile,
+    TswIo.Firmware.UpdateCheck,
+    TswIo.Firmware.UploadHistory
+  ]
+
+  @secret_fields %{TswIo.Simulator.Config => [:api_key]}
+
+  @doc """
+  The rows of every table, keyed by table name.
+  """
+  @spec everything() :: map()
+  def everything do
+    tables =
+      Map.new(@schemas, fn schema ->
+        fields = schema.__schema__(:fields) -- Map.get(@secret_fields, schema, [])
+        rows = schema |> Repo.all() |> Enum.map(&Map.take(&1, fields))
+        {schema.__schema__(:source), rows}
+      end)
+
+    %{
+      format_version: @format_version,
+      backend_version: TswIo.AppVersion.current_version(),
+      exported_at: DateTime.utc_now(),
+      tables: tables
+    }
+  end
+end
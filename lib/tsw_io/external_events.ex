@@ -0,0 +1,47 @@
+defmodule TswIo.ExternalEvents do
+  @moduledoc """
+  Events other tools on the user's machine send, like a browser extension
+  clipping a page.
+
+  They POST to the desktop shell's webhook receiver, which checks its own
+  token and forwards them here. Nothing is stored: whatever wants them
+  subscribes and gets `{:external_event, event}` messages.
+  """
+
+  @pubsub_topic "external_events"
+  @name_pattern ~r/\A[a-z0-9][a-z0-9_.-]{0,63}\z/
+
+  @type event :: %{
+          name: String.t(),
+          source: String.t(),
+          payload: term(),
+          received_at: DateTime.t()
+        }
+
+  @doc """
+  Subscribe to external events.
+  """
+  @spec subscribe() :: :ok | {:error, term()}
+  def subscribe do
+    Phoenix.PubSub.subscribe(TswIo.PubSub, @pubsub_topic)
+  end
+
+  @doc """
+  Hand an event to the subscribers.
+
+  Names are lowercase words like `clip` or `page.clipped`, anything else is
+  refused with `{:error, :invalid}`.
+  """
+  @spec publish(String.t(), String.t(), term()) :: {:ok, event()} | {:error, :invalid}
+  def publish(name, source, payload) when is_binary(name) and is_binary(source) do
+    if Regex.match?(@name_pattern, name) do
+      event = %{name: name, source: source, payload: payload, received_at: DateTime.utc_now()}
+      Phoenix.PubSub.broadcast(TswIo.PubSub, @pubsub_topic, {:external_event, event})
+      {:ok, event}
+    else
+      {:error, :invalid}
+    end
+  end
+
+  def publish(_name, _source, _payload), do: {:error, :invalid}
+end
@@ -57,9 +57,13 @@ defmodule TswIo.Firmware.Downloader do
   # GitHub API
 
   defp fetch_github_releases do
+    if TswIo.Network.offline?(), do: {:error, :offline}, else: request_github_releases()
+  end
+
+  defp request_github_releases do
     Logger.info("Fetching firmware releases from GitHub")
 
-    case Req.get(@github_api_url, headers: github_headers()) do
+    case Req.get(@github_api_url, [headers: github_headers()] ++ TswIo.Network.req_options()) do
       {:ok, %{status: 200, body: body}} ->
         {:ok, body}
 
@@ -176,9 +180,15 @@ defmodule TswIo.Firmware.Downloader do
   # File download
 
   defp download_file(url, destination) do
+    if TswIo.Network.offline?(), do: {:error, :offline}, else: request_file(url, destination)
+  end
+
+  defp request_file(url, destination) do
     Logger.info("Downloading firmware from #{url}")
 
-    case Req.get(url, into: File.stream!(destination), decode_body: false) do
+    options = [into: File.stream!(destination), decode_body: false] ++ TswIo.Network.req_options()
+
+    case Req.get(url, options) do
       {:ok, %{status: 200}} ->
         Logger.info("Downloaded firmware to #{destination}")
         {:ok, destination}
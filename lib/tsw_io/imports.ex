@@ -0,0 +1,266 @@
+defmodule TswIo.Imports do
+  @moduledoc """
+  Trains from spreadsheets the desktop shell uploads, see `TswIo.Uploads`.
+
+  CSV files and the first sheet of Excel (`.xlsx`) workbooks are read. Their
+  first row names the columns: `name` and `identifier` are required,
+  `description` is optional and any other column is ignored. Trains whose
+  identifier is already known are skipped rather than changed.
+  """
+
+  alias TswIo.{Train, Uploads}
+
+  @required_columns ["name", "identifier"]
+
+  @type failure :: %{row: pos_integer(), errors: [String.t()]}
+
+  @type summary :: %{
+          filename: String.t(),
+          created: non_neg_integer(),
+          skipped: non_neg_integer(),
+          failed: [failure()]
+        }
+
+  @doc """
+  Import the trains in a completed upload, which is removed once it was read.
+
+  Rows are numbered as in the spreadsheet, the header being row 1.
+  """
+  @spec import_upload(String.t()) ::
+          {:ok, summary()}
+          | {:error,
+             :not_found
+             | :incomplete
+             | :unsupported_format
+             | :invalid_file
+             | {:missing_columns, [String.t()]}}
+  def import_upload(upload_id) do
+    with {:ok, %{complete: true} = status} <- Uploads.status(upload_id),
+         {:ok, rows} <- read_rows(Uploads.path(upload_id), status.filename),
+         {:ok, summary} <- import_rows(rows) do
+      Uploads.discard(upload_id)
+      {:ok, Map.put(summary, :filename, status.filename)}
+    else
+      {:ok, %{complete: false}} -> {:error, :incomplete}
+      error -> error
+    end
+  end
+
+  defp read_rows(path, filename) do
+    case filename |> Path.extname() |> String.downcase() do
+      ".csv" -> path |> File.read!() |> parse_csv()
+      ".xlsx" -> parse_xlsx(path)
+      _ -> {:error, :unsupported_format}
+    end
+  end
+
+  defp import_rows([]), do: {:error, {:missing_columns, @required_columns}}
+
+  defp import_rows([header | rows]) do
+    columns = Enum.map(header, &(&1 |> String.trim() |> String.downcase()))
+
+    case @required_columns -- columns do
+      [] ->
+        summary =
+          rows
+          |> Enum.with_index(2)
+          |> Enum.reduce(%{created: 0, skipped: 0, failed: []}, fn {row, number}, summary ->
+            import_row(columns, row, number, summary)
+          end)
+
+        {:ok, %{summary | failed: Enum.reverse(summary.failed)}}
+
+      missing ->
+        {:error, {:missing_columns, missing}}
+    end
+  end
+
+  defp import_row(columns, row, number, summary) do
+    attrs =
+      columns
+      |> Enum.zip(Enum.map(row, &String.trim/1))
+      |> Map.new()
+      |> Map.take(["name", "identifier", "description"])
+      |> Map.reject(fn {_column, value} -> value == "" end)
+
+    cond do
+      attrs == %{} ->
+        summary
+
+      match?({:ok, _train}, Train.get_train_by_identifier(attrs["identifier"] || "")) ->
+        %{summary | skipped: summary.skipped + 1}
+
+      true ->
+        case Train.create_train(attrs) do
+          {:ok, _train} ->
+            %{summary | created: summary.created + 1}
+
+          {:error, changeset} ->
+            %{summary | failed: [%{row: number, errors: errors(changeset)} | summary.failed]}
+        end
+    end
+  end
+
+  defp errors(changeset) do
+    changeset
+    |> Ecto.Changeset.traverse_errors(fn {message, opts} ->
+      Enum.reduce(opts, message, fn {key, value}, message ->
+        String.replace(message, "%{#{key}}", to_string(value))
+      end)
+    end)
+    |> Enum.flat_map(fn {field, messages} -> Enum.map(messages, &"#{field} #{&1}") end)
+  end
+
+  # CSV
+
+  defp parse_csv(contents) do
+    contents = String.trim_leading(contents, "\uFEFF")
+    # Excel writes `;` where the decimal separator is a comma
+    [first_line | _] = String.split(contents, "\n", parts: 2)
+    separator = if count(first_line, ";") > count(first_line, ","), do: ?;, else: ?,
+
+    csv(contents, separator, "", [], [])
+  end
+
+  defp count(line, character), do: length(String.split(line, character)) - 1
+
+  defp csv(<<>>, _separator, "", [], rows), do: {:ok, Enum.reverse(rows)}
+  defp csv(<<>>, _separator, field, row, rows), do: {:ok, Enum.reverse([Enum.reverse([field | row]) | rows])}
+
+  defp csv(<<?", rest::binary>>, separator, "", row, rows), do: quoted(rest, separator, "", row, rows)
+
+  defp csv(<<"\r\n", rest::binary>>, separator, field, row, rows),
+    do: csv(rest, separator, "", [], [Enum.reverse([field | row]) | rows])
+
+  defp csv(<<?\n, rest::binary>>, separator, field, row, rows),
+    do: csv(rest, separator, "", [], [Enum.reverse([field | row]) | rows])
+
+  defp csv(<<byte, rest::binary>>, separator, field, row, rows) when byte == separator,
+    do: csv(rest, separator, "", [field | row], rows)
+
+  defp csv(<<byte, rest::binary>>, separator, field, row, rows),
+    do: csv(rest, separator, <<field::binary, byte>>, row, rows)
+
+  defp quoted(<<>>, _separator, _field, _row, _rows), do: {:error, :invalid_file}
+
+  defp quoted(<<?", ?", rest::binary>>, separator, field, row, rows),
+    do: quoted(rest, separator, <<field::binary, ?">>, row, rows)
+
+  defp quoted(<<?", rest::binary>>, separator, field, row, rows),
+    do: csv(rest, separator, field, row, rows)
+
+  defp quoted(<<byte, rest::binary>>, separator, field, row, rows),
+    do: quoted(rest, separator, <<field::binary, byte>>, row, rows)
+
+  # Excel
+
+  defp parse_xlsx(path) do
+    with {:ok, files} <- :zip.unzip(String.to_charlist(path), [:memory]),
+         files = Map.new(files, fn {name, contents} -> {to_string(name), contents} end),
+         {:ok, sheet} <- Map.fetch(files, "xl/worksheets/sheet1.xml") do
+      shared = files |> Map.get("xl/sharedStrings.xml", "") |> shared_strings()
+      {:ok, sheet_rows(sheet, shared)}
+    else
+      _ -> {:error, :invalid_file}
+    end
+  end
+
+  defp shared_strings(xml) do
+    ~r{<si>(.*?)</si>}s
+    |> Regex.scan(xml, capture: :all_but_first)
+    |> Enum.map(fn [item] -> text(item) end)
+    |> List.to_tuple()
+  end
+
+  # Rich text splits a string into runs, each with its own <t>
+  defp text(xml) do
+    ~r{<t(?:\s[^>]*)?>(.*?)</t>}s
+    |> Regex.scan(xml, capture: :all_but_first)
+    |> Enum.map_join(fn [text] -> unescape(text) end)
+  end
+
+  defp sheet_rows(xml, shared) do
+    # Rows without values are left out or written as <row/>, put them back to keep the numbering
+    {rows, _next} =
+      ~r{<row(\s[^>]*)?(?<!/)>(.*?)</row>}s
+      |> Regex.scan(xml, capture: :all_but_first)
+      |> Enum.reduce({[], 1}, fn [attributes, row], {rows, next} ->
+        number =
+          case Integer.parse(to_string(attribute(attributes, "r"))) do
+            {number, ""} -> max(number, next)
+            _ -> next
+          end
+
+        {[fill_columns(cells(row, shared)) | List.duplicate([], number - next) ++ rows], number + 1}
+      end)
+
+    Enum.reverse(rows)
+  end
+
+  defp cells(row, shared) do
+    ~r{<c\s([^>]*?)(?:/>|>(.*?)</c>)}s
+    |> Regex.scan(row, capture: :all_but_first)
+    |> Enum.map(fn
+      [attributes] -> {column(attributes), ""}
+      [attributes, contents] -> {column(attributes), value(attribute(attributes, "t"), contents, shared)}
+    end)
+  end
+
+  defp value("s", contents, shared) do
+    with [_, index] <- Regex.run(~r{<v>(\d+)</v>}, contents),
+         {index, ""} <- Integer.parse(index),
+         true <- index < tuple_size(shared) do
+      elem(shared, index)
+    else
+      _ -> ""
+    end
+  end
+
+  defp value("inlineStr", contents, _shared), do: text(contents)
+
+  defp value(_type, contents, _shared) do
+    case Regex.run(~r{<v>(.*?)</v>}s, contents) do
+      [_, value] -> unescape(value)
+      nil -> ""
+    end
+  end
+
+  defp attribute(attributes, name) do
+    case Regex.run(~r{(?:^|\s)#{name}="([^"]*)"}, attributes) do
+      [_, value] -> value
+      nil -> nil
+    end
+  end
+
+  # Zero-based column of a reference like `B2`, cells without one follow the previous cell
+  defp column(attributes) do
+    case attributes |> attribute("r") |> to_string() |> String.replace(~r/\d/, "") do
+      "" -> nil
+      letters -> letters |> String.to_charlist() |> Enum.reduce(0, &(&2 * 26 + &1 - ?A + 1)) |> Kernel.-(1)
+    end
+  end
+
+  # Empty cells are left out of the sheet, put them back so the values stay under their header
+  defp fill_columns(cells) do
+    {values, _next} =
+      Enum.reduce(cells, {[], 0}, fn {column, value}, {values, next} ->
+        column = max(column || next, next)
+        {[value | List.duplicate("", column - next) ++ values], column + 1}
+      end)
+
+    Enum.reverse(values)
+  end
+
+  defp unescape(text) do
+    text
+    |> String.replace(~r/&#(x?)([0-9a-fA-F]+);/, fn match ->
+      [_, hex, code] = Regex.run(~r/&#(x?)([0-9a-fA-F]+);/, match)
+      <<String.to_integer(code, if(hex == "x", do: 16, else: 10))::utf8>>
+    end)
+    |> String.replace("&lt;", "<")
+    |> String.replace("&gt;", ">")
+    |> String.replace("&quot;", "\"")
+    |> String.replace("&apos;", "'")
+    |> String.replace("&amp;", "&")
+  end
+end
@@ -0,0 +1,73 @@
+defmodule TswIo.Maintenance do
+  @moduledoc """
+  Database and cache housekeeping the desktop shell can ask for.
+
+  Tasks run one at a time on the caller's process. The shell makes sure
+  nothing like a backup runs at the same time.
+  """
+
+  alias TswIo.Firmware.FilePath
+  alias TswIo.Repo
+
+  @type task :: :vacuum | :reindex | :clear_cache
+
+  @tasks %{"vacuum" => :vacuum, "reindex" => :reindex, "clear_cache" => :clear_cache}
+
+  @doc """
+  The task a name from the shell stands for.
+  """
+  @spec parse_task(String.t()) :: {:ok, task()} | :error
+  def parse_task(name), do: Map.fetch(@tasks, name)
+
+  @doc """
+  Names of every task, as the shell sends them.
+  """
+  @spec task_names() :: [String.t()]
+  def task_names, do: Map.keys(@tasks)
+
+  @doc """
+  Run a maintenance task, returning what it did.
+  """
+  @spec run(task()) :: {:ok, String.t()} | {:error, term()}
+  def run(:vacuum) do
+    # Rebuilds the file without the pages deleted rows left behind
+    with {:ok, _result} <- Repo.query("VACUUM", [], timeout: :infinity) do
+      {:ok, "Compacted the database"}
+    end
+  end
+
+  def run(:reindex) do
+    with {:ok, _result} <- Repo.query("REINDEX", [], timeout: :infinity) do
+      {:ok, "Rebuilt the database indexes"}
+    end
+  end
+
+  def run(:clear_cache) do
+    # Firmware files are downloaded again when they are next needed
+    removed =
+      FilePath.list_downloaded()
+      |> Enum.count(fn {_version, _board_type, path} -> File.rm(path) == :ok end)
+
+    {:ok, "Removed #{removed} downloaded firmware files"}
+  end
+
+  @doc """
+  Write a consistent copy of the database to `path`, which must not exist yet.
+  """
+  @spec backup(String.t()) :: {:ok, String.t()} | {:error, term()}
+  def backup(path) when is_binary(path) do
+    cond do
+      Path.type(path) != :absolute ->
+        {:error, :relative_path}
+
+      File.exists?(path) ->
+        {:error, :already_exists}
+
+      true ->
+        # Unlike copying the file, this includes what is still in the write-ahead log
+        with {:ok, _result} <- Repo.query("VACUUM INTO ?", [path], timeout: :infinity) do
+          {:ok, "Backed up the database to #{path}"}
+        end
+    end
+  end
+end
@@ -0,0 +1,85 @@
+defmodule TswIo.Network do
+  @moduledoc """
+  Outbound network access.
+
+  The desktop shell's air-gapped mode sets `TSW_IO_OFFLINE=1`, after which
+  nothing may contact the internet: no update checks and no firmware downloads.
+
+  Behind a TLS-inspecting proxy the shell also passes the proxy and the
+  company's root certificates, see `req_options/0`.
+  """
+
+  require Logger
+
+  @doc """
+  Whether outbound network access is turned off.
+  """
+  @spec offline?() :: boolean()
+  def offline? do
+    Application.get_env(:tsw_io, :offline, false)
+  end
+
+  @doc """
+  Options for `Req` requests to the internet, through the configured proxy and
+  trusting the configured root certificates.
+
+  Empty without either, leaving Req to its defaults. A certificate file that
+  can't be read is logged and left out.
+  """
+  @spec req_options() :: keyword()
+  def req_options do
+    connect_options =
+      Enum.reject(
+        [proxy: proxy(), transport_opts: transport_opts()],
+        fn {_key, value} -> value in [nil, []] end
+      )
+
+    if connect_options == [], do: [], else: [connect_options: connect_options]
+  end
+
+  defp proxy do
+    case Application.get_env(:tsw_io, :http_proxy) do
+      nil ->
+        nil
+
+      url ->
+        case URI.parse(url) do
+          %URI{scheme: "http", host: host, port: port} when is_binary(host) ->
+            {:http, host, port, []}
+
+          %URI{scheme: "https", host: host, port: port} when is_binary(host) ->
+            {:https, host, port, []}
+
+          _ ->
+            Logger.warning("Ignoring the proxy #{url}, it isn't an http:// or https:// URL")
+            nil
+        end
+    end
+  end
+
+  defp transport_opts do
+    path = Application.get_env(:tsw_io, :ca_certificates)
+    os_certificates? = Application.get_env(:tsw_io, :os_certificates, true)
+
+    # Req trusts the OS store by default
+    if is_nil(path) and os_certificates? do
+      []
+    else
+      os = if os_certificates?, do: :public_key.cacerts_get(), else: []
+      [cacerts: os ++ certificates(path)]
+    end
+  end
+
+  defp certificates(nil), do: []
+
+  defp certificates(path) do
+    case File.read(path) do
+      {:ok, pem} ->
+        for {:Certificate, der, :not_encrypted} <- :public_key.pem_decode(pem), do: der
+
+      {:error, reason} ->
+        Logger.warning("Failed to read the certificates in #{path}: #{inspect(reason)}")
+        []
+    end
+  end
+end
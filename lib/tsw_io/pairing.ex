@@ -0,0 +1,164 @@
+defmodule TswIo.Pairing do
+  @moduledoc """
+  Pairing mobile devices with this backend.
+
+  The desktop shell asks for a one-time code and shows it as a QR code. A
+  phone that redeems it in time is stored as a `TswIo.Pairing.Device` and
+  gets a device token for it, signed with the endpoint's secret so it
+  survives restarts. Requests from other machines must carry one, see
+  `TswIoWeb.Plugs.LanAuth`. A token stops working when its device is
+  unpaired, or after 90 days, when the phone has to pair again.
+
+  Browsers on other machines get a guest token from the desktop shell
+  instead, which stops working after 12 hours.
+  """
+
+  use Agent
+
+  import Ecto.Query
+
+  alias TswIo.Pairing.Device
+  alias TswIo.Repo
+
+  # How long a pairing code can be redeemed, in seconds
+  @code_max_age 5 * 60
+  @token_salt "paired device"
+  # How long a guest browser stays let in, in seconds
+  @guest_max_age 12 * 60 * 60
+  # How long a paired device stays let in, in seconds
+  @device_max_age 90 * 24 * 60 * 60
+  # How often a device's last visit is recorded, in seconds
+  @seen_interval 60 * 60
+
+  def start_link(_opts) do
+    Agent.start_link(fn -> nil end, name: __MODULE__)
+  end
+
+  @doc """
+  Make a new pairing code, replacing any earlier one.
+
+  Returns the code and the Unix time it expires at.
+  """
+  @spec start() :: {:ok, String.t(), integer()}
+  def start do
+    code = 16 |> :crypto.strong_rand_bytes() |> Base.url_encode64(padding: false)
+    expires_at = System.os_time(:second) + @code_max_age
+    Agent.update(__MODULE__, fn _ -> {code, expires_at} end)
+    {:ok, code, expires_at}
+  end
+
+  @doc """
+  Trade the current pairing code for a device token, only once.
+  """
+  @spec redeem(term(), String.t()) :: {:ok, String.t()} | {:error, :invalid | :expired}
+  def redeem(code, device_name) when is_binary(code) and is_binary(device_name) do
+    redeemed =
+      Agent.get_and_update(__MODULE__, fn
+        {expected, expires_at} = pending ->
+          cond do
+            not Plug.Crypto.secure_compare(code, expected) -> {{:error, :invalid}, pending}
+            System.os_time(:second) > expires_at -> {{:error, :expired}, nil}
+            true -> {:ok, nil}
+          end
+
+        nil ->
+          {{:error, :invalid}, nil}
+      end)
+
+    with :ok <- redeemed, do: pair(device_name)
+  end
+
+  def redeem(_code, _device_name), do: {:error, :invalid}
+
+  @doc """
+  A token for a browser on another machine, only valid for a while.
+  """
+  @spec guest_token() :: String.t()
+  def guest_token do
+    # Kept short, it goes into a QR code
+    Phoenix.Token.sign(TswIoWeb.Endpoint, @token_salt, %{"guest" => true})
+  end
+
+  @doc """
+  Whether `token` was handed out by `redeem/2` for a device still paired, or
+  by `guest_token/0` recently enough.
+  """
+  @spec valid_device_token?(term()) :: boolean()
+  def valid_device_token?(token) when is_binary(token) do
+    case Phoenix.Token.verify(TswIoWeb.Endpoint, @token_salt, token, max_age: @device_max_age) do
+      {:ok, %{"guest" => true}} ->
+        match?({:ok, _}, Phoenix.Token.verify(TswIoWeb.Endpoint, @token_salt, token, max_age: @guest_max_age))
+
+      {:ok, %{"device" => id}} ->
+        paired?(id)
+
+      _ ->
+        false
+    end
+  end
+
+  def valid_device_token?(_token), do: false
+
+  @doc """
+  The devices paired with this backend, the most recently paired first.
+  """
+  @spec list_devices() :: [Device.t()]
+  def list_devices do
+    now = DateTime.utc_now()
+
+    Device
+    |> where([d], d.expires_at > ^now)
+    |> order_by(desc: :inserted_at, desc: :id)
+    |> Repo.all()
+  end
+
+  @doc """
+  Revoke a device's token, it has to pair again to get back in.
+  """
+  @spec unpair(integer()) :: :ok | {:error, :not_found}
+  def unpair(id) do
+    case Repo.delete_all(from(d in Device, where: d.id == ^id)) do
+      {0, _} -> {:error, :not_found}
+      {_, _} -> :ok
+    end
+  end
+
+  defp pair(device_name) do
+    now = DateTime.utc_now(:second)
+
+    attrs = %{
+      name: String.slice(device_name, 0, 100),
+      expires_at: DateTime.add(now, @device_max_age),
+      last_seen_at: now
+    }
+
+    case %Device{} |> Device.changeset(attrs) |> Repo.insert() do
+      {:ok, device} ->
+        {:ok, Phoenix.Token.sign(TswIoWeb.Endpoint, @token_salt, %{"device" => device.id})}
+
+      {:error, _changeset} ->
+        {:error, :invalid}
+    end
+  end
+
+  defp paired?(id) do
+    now = DateTime.utc_now(:second)
+
+    case Repo.get(Device, id) do
+      %Device{expires_at: expires_at} = device ->
+        valid = DateTime.after?(expires_at, now)
+        if valid, do: touch(device, now)
+        valid
+
+      nil ->
+        false
+    end
+  end
+
+  # Only now and then, not with every request
+  defp touch(%Device{last_seen_at: last_seen_at} = device, now) do
+    if is_nil(last_seen_at) or DateTime.diff(now, last_seen_at) >= @seen_interval do
+      device |> Ecto.Changeset.change(last_seen_at: now) |> Repo.update()
+    end
+  end
+end
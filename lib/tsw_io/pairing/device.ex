@@ -0,0 +1,39 @@
+defmodule TswIo.Pairing.Device do
+  @moduledoc """
+  Schema for a device paired with this backend.
+
+  Its device token only lets it in while the row is there and hasn't expired,
+  unpairing deletes it.
+  """
+
+  use Ecto.Schema
+  import Ecto.Changeset
+
+  @type t :: %__MODULE__{
+          id: integer() | nil,
+          name: String.t() | nil,
+          expires_at: DateTime.t() | nil,
+          last_seen_at: DateTime.t() | nil,
+          inserted_at: DateTime.t() | nil,
+          updated_at: DateTime.t() | nil
+        }
+
+  schema "paired_devices" do
+    field :name, :string
+    field :expires_at, :utc_datetime
+    field :last_seen_at, :utc_datetime
+
+    timestamps(type: :utc_datetime)
+  end
+
+  @doc """
+  Creates a changeset for pairing a device.
+  """
+  @spec changeset(t(), map()) :: Ecto.Changeset.t()
+  def changeset(%__MODULE__{} = device, attrs) do
+    device
+    |> cast(attrs, [:name, :expires_at, :last_seen_at])
+    |> validate_required([:name, :expires_at])
+    |> validate_length(:name, max: 100)
+  end
+end
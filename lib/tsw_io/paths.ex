@@ -6,6 +6,9 @@ defmodule TswIo.Paths do
   - macOS: ~/Library/Application Support/TswIo
   - Windows: %APPDATA%/TswIo
   - Linux: ~/.local/share/tsw_io (or $XDG_DATA_HOME/tsw_io)
+
+  The desktop shell passes the directory it resolved in `TSW_IO_DATA_DIR`,
+  which takes precedence over the platform default.
   """
 
   @app_name "TswIo"
@@ -34,6 +37,13 @@ defmodule TswIo.Paths do
   end
 
   defp get_data_dir do
+    case System.get_env("TSW_IO_DATA_DIR") do
+      dir when is_binary(dir) and dir != "" -> dir
+      _ -> platform_data_dir()
+    end
+  end
+
+  defp platform_data_dir do
     case :os.type() do
       {:unix, :darwin} ->
         # macOS: ~/Library/Application Support/TswIo
@@ -0,0 +1,30 @@
+defmodule TswIo.Readiness do
+  @moduledoc """
+  Tells the desktop shell the moment the app can serve requests.
+
+  When the shell sets `TSW_IO_READY_SIGNAL=1`, a single marker line is
+  printed to stdout once the application has started, so the shell doesn't
+  have to poll `/api/health` to find out. The shell still falls back to
+  polling if the line never arrives.
+  """
+
+  @marker "TSW_IO_READY"
+
+  @doc """
+  The line printed once the app is ready.
+  """
+  @spec marker() :: String.t()
+  def marker, do: @marker
+
+  @doc """
+  Print the ready marker if the shell asked for it.
+  """
+  @spec announce() :: :ok
+  def announce do
+    if Application.get_env(:tsw_io, :ready_signal, false) do
+      IO.puts(@marker)
+    end
+
+    :ok
+  end
+end
@@ -0,0 +1,86 @@
+defmodule TswIo.SearchIndex do
+  @moduledoc """
+  A lightweight index of the records, for the desktop shell to hand to the
+  OS search (Spotlight, Windows Search).
+
+  Every entry has what the OS shows in its results and the page the app opens
+  when one is picked.
+  """
+
+  alias TswIo.{Hardware, Train}
+
+  @snippet_length 200
+
+  @type entry :: %{
+          id: String.t(),
+          kind: :configuration | :train,
+          title: String.t(),
+          snippet: String.t(),
+          route: String.t()
+        }
+
+  @doc """
+  Every configuration and train, configurations first.
+  """
+  @spec entries() :: [entry()]
+  def entries do
+    configurations =
+      for device <- Hardware.list_configurations() do
+        %{
+          id: "configuration-#{device.config_id}",
+          kind: :configuration,
+          title: device.name,
+          snippet: snippet(device.description, "Hardware configuration"),
+          route: "/configurations/#{device.config_id}"
+        }
+      end
+
+    trains =
+      for train <- Train.list_trains() do
+        %{
+          id: "train-#{train.id}",
+          kind: :train,
+          title: train.name,
+          snippet: snippet(train.description, "Train #{train.identifier}"),
+          route: "/trains/#{train.id}"
+        }
+      end
+
+    configurations ++ trains
+  end
+
+  @doc """
+  The entries matching `query` as typed, best first, at most `limit` of them.
+
+  Every word of the query must appear in the title or snippet, ignoring case.
+  Titles starting with the query come first, then other title matches.
+  """
+  @spec search(String.t(), pos_integer()) :: [entry()]
+  def search(query, limit \\ 20) when is_binary(query) do
+    query = query |> String.trim() |> String.downcase()
+    words = String.split(query)
+
+    entries()
+    |> Enum.map(fn entry ->
+      title = String.downcase(entry.title)
+      text = title <> " " <> String.downcase(entry.snippet)
+      {entry, title, Enum.all?(words, &String.contains?(text, &1))}
+    end)
+    |> Enum.filter(fn {_entry, _title, matches} -> matches end)
+    |> Enum.with_index()
+    |> Enum.sort_by(fn {{_entry, title, _matches}, index} -> {rank(title, query, words), index} end)
+    |> Enum.take(limit)
+    |> Enum.map(fn {{entry, _title, _matches}, _index} -> entry end)
+  end
+
+  defp rank(title, query, words) do
+    cond do
+      String.starts_with?(title, query) -> 0
+      Enum.all?(words, &String.contains?(title, &1)) -> 1
+      true -> 2
+    end
+  end
+
+  defp snippet(description, fallback) when description in [nil, ""], do: fallback
+  defp snippet(description, _fallback), do: String.slice(description, 0, @snippet_length)
+end
@@ -0,0 +1,149 @@
+defmodule TswIo.Shell do
+  @moduledoc """
+  Events exchanged with the desktop shell.
+
+  The shell keeps a channel open on `TswIoWeb.ShellSocket`. Events emitted
+  here reach it as Tauri events, which is how the backend asks for native
+  features like notifications or the tray status. Events the shell sends
+  are broadcast on PubSub to whoever subscribed.
+  """
+
+  alias Phoenix.PubSub
+  alias TswIo.Shell.Nonces
+
+  @topic "shell"
+  @incoming_topic "shell:incoming"
+  # How long a biometric confirmation stays valid, in seconds
+  @confirmation_max_age 60
+
+  @doc """
+  The channel topic the shell joins.
+  """
+  @spec topic() :: String.t()
+  def topic, do: @topic
+
+  @doc """
+  Send an event to the desktop shell.
+
+  Does nothing when no shell is connected, as when running in a browser.
+  """
+  @spec emit(String.t(), map()) :: :ok
+  def emit(event, payload \\ %{}) when is_binary(event) and is_map(payload) do
+    TswIoWeb.Endpoint.broadcast(@topic, event, payload)
+  end
+
+  @doc """
+  Show a native notification through the desktop shell.
+
+  ## Options
+
+    * `:body` - text under the title
+    * `:id` - sent back with the chosen action, needed to hear back at all
+    * `:target` - backend page opened when the notification is clicked, like `"/trains/1"`
+    * `:actions` - buttons, as maps with an `:id`, a `:title` and an optional `:target`
+
+  The shell shows at most three buttons. Clicking one, or the notification
+  itself, reaches `subscribe/0` subscribers as a `"notification_action"`
+  event with the notification's `"id"` and the button's id as `"action"`,
+  `"default"` when the notification itself was clicked. Where the OS can't
+  show buttons, only the title and body are shown.
+  """
+  @spec notify(String.t(), keyword()) :: :ok
+  def notify(title, opts \\ []) when is_binary(title) do
+    actions =
+      opts
+      |> Keyword.get(:actions, [])
+      |> Enum.map(fn action ->
+        action |> Map.new() |> Map.take([:id, :title, :target]) |> reject_nil()
+      end)
+
+    payload =
+      %{title: title, body: opts[:body], id: opts[:id], target: opts[:target]}
+      |> reject_nil()
+      |> Map.put(:actions, actions)
+
+    emit("notification", payload)
+  end
+
+  defp reject_nil(map), do: map |> Enum.reject(fn {_key, value} -> is_nil(value) end) |> Map.new()
+
+  @doc """
+  Receive the events the shell sends, as `{:shell_event, event, payload}` messages.
+  """
+  @spec subscribe() :: :ok | {:error, term()}
+  def subscribe do
+    PubSub.subscribe(TswIo.PubSub, @incoming_topic)
+  end
+
+  @doc false
+  @spec received(String.t(), map()) :: :ok
+  def received(event, payload) do
+    PubSub.broadcast(TswIo.PubSub, @incoming_topic, {:shell_event, event, payload})
+  end
+
+  @doc """
+  Whether `token` is the one the shell was started with.
+  """
+  @spec valid_token?(term()) :: boolean()
+  def valid_token?(token) when is_binary(token) do
+    case Application.get_env(:tsw_io, :shell_token) do
+      expected when is_binary(expected) -> Plug.Crypto.secure_compare(token, expected)
+      _ -> false
+    end
+  end
+
+  def valid_token?(_token), do: false
+
+  @doc """
+  Whether the desktop shell turned the feature flag `name` on for this run.
+
+  The shell resolves its built-in, remote and configured flags at launch and
+  passes the enabled ones in `TSW_IO_FEATURES`. Without a shell none are on.
+  """
+  @spec feature_enabled?(String.t()) :: boolean()
+  def feature_enabled?(name) when is_binary(name) do
+    name in Application.get_env(:tsw_io, :shell_features, [])
+  end
+
+  @doc """
+  Check a confirmation from the shell's `confirm_with_biometrics` command.
+
+  It must be signed with the shell token, be for `reason`, be at most a
+  minute old and not have been used before. Check it right before the
+  destructive action it confirms.
+  """
+  @spec verify_confirmation(map(), String.t()) :: :ok | {:error, :invalid | :expired}
+  def verify_confirmation(
+        %{
+          "reason" => reason,
+          "issued_at" => issued_at,
+          "nonce" => nonce,
+          "signature" => signature
+        },
+        reason
+      )
+      when is_integer(issued_at) and is_binary(nonce) and is_binary(signature) do
+    with token when is_binary(token) and token != "" <- Application.get_env(:tsw_io, :shell_token),
+         expected = confirmation_signature(token, reason, issued_at, nonce),
+         true <- Plug.Crypto.secure_compare(String.downcase(signature), expected) do
+      age = System.os_time(:second) - issued_at
+
+      cond do
+        age not in 0..@confirmation_max_age -> {:error, :expired}
+        Nonces.claim(nonce, issued_at + @confirmation_max_age) -> :ok
+        true -> {:error, :invalid}
+      end
+    else
+      _ -> {:error, :invalid}
+    end
+  end
+
+  def verify_confirmation(_confirmation, _reason), do: {:error, :invalid}
+
+  @doc false
+  @spec confirmation_signature(String.t(), String.t(), integer(), String.t()) :: String.t()
+  def confirmation_signature(token, reason, issued_at, nonce) do
+    :crypto.mac(:hmac, :sha256, token, "#{reason}\n#{issued_at}\n#{nonce}")
+    |> Base.encode16(case: :lower)
+  end
+end
@@ -0,0 +1,46 @@
+defmodule TswIo.Shell.Nonces do
+  @moduledoc """
+  Nonces of the shell confirmations already used, so none is accepted twice.
+
+  Each is kept until the confirmation it came with expires, after which
+  `TswIo.Shell.verify_confirmation/2` refuses it for its age anyway.
+  """
+
+  use GenServer
+
+  @table __MODULE__
+  @sweep_interval_ms 60_000
+
+  def start_link(_opts) do
+    GenServer.start_link(__MODULE__, :ok, name: __MODULE__)
+  end
+
+  @doc """
+  Record `nonce` as used until the Unix time `expires_at`.
+
+  Returns `false` when it was already used.
+  """
+  @spec claim(String.t(), integer()) :: boolean()
+  def claim(nonce, expires_at) when is_binary(nonce) and is_integer(expires_at) do
+    :ets.insert_new(@table, {nonce, expires_at})
+  end
+
+  @impl true
+  def init(:ok) do
+    :ets.new(@table, [:named_table, :public, :set, write_concurrency: true])
+    schedule_sweep()
+    {:ok, nil}
+  end
+
+  @impl true
+  def handle_info(:sweep, state) do
+    now = System.os_time(:second)
+    :ets.select_delete(@table, [{{:_, :"$1"}, [{:<, :"$1", now}], [true]}])
+    schedule_sweep()
+    {:noreply, state}
+  end
+
+  defp schedule_sweep do
+    Process.send_after(self(), :sweep, @sweep_interval_ms)
+  end
+end
@@ -0,0 +1,103 @@
+defmodule TswIo.Sso do
+  @moduledoc """
+  Checks the tokens the desktop shell's single sign-on hands the pages.
+
+  The shell signs in through the identity provider in the system browser and
+  puts the token it gets in the cookie named by `TSW_IO_SSO_COOKIE`. Tokens
+  are JWTs, signed with HS256 and the secret in `TSW_IO_SSO_SECRET`, or with
+  RS256 and the PEM public key in `TSW_IO_SSO_PUBLIC_KEY`. Without a cookie
+  name single sign-on is off and pages are served as before.
+  """
+
+  require Logger
+
+  @doc """
+  The cookie the shell puts the token in, nil when single sign-on is off.
+  """
+  @spec cookie_name() :: String.t() | nil
+  def cookie_name do
+    case config()[:cookie] do
+      name when is_binary(name) and name != "" -> name
+      _ -> nil
+    end
+  end
+
+  @doc """
+  Whether pages need a token at all.
+  """
+  @spec enabled?() :: boolean()
+  def enabled?, do: cookie_name() != nil
+
+  @doc """
+  Check `token`, returning its claims when it is signed with the configured
+  key and within its `nbf` and `exp`.
+  """
+  @spec verify(term()) :: {:ok, map()} | {:error, :invalid | :expired}
+  def verify(token) when is_binary(token) do
+    with [encoded_header, encoded_claims, encoded_signature] <- String.split(token, "."),
+         {:ok, header} <- decode_json(encoded_header),
+         {:ok, claims} when is_map(claims) <- decode_json(encoded_claims),
+         {:ok, signature} <- Base.url_decode64(encoded_signature, padding: false),
+         true <- signed?(header["alg"], encoded_header <> "." <> encoded_claims, signature) do
+      check_time(claims, System.os_time(:second))
+    else
+      _ -> {:error, :invalid}
+    end
+  end
+
+  def verify(_token), do: {:error, :invalid}
+
+  defp decode_json(encoded) do
+    with {:ok, json} <- Base.url_decode64(encoded, padding: false) do
+      Jason.decode(json)
+    end
+  end
+
+  # The algorithm goes with the key that is set up, a token can't pick another
+  defp signed?("HS256", signed, signature) do
+    case config()[:secret] do
+      secret when is_binary(secret) and secret != "" ->
+        Plug.Crypto.secure_compare(:crypto.mac(:hmac, :sha256, secret, signed), signature)
+
+      _ ->
+        no_key("HS256")
+    end
+  end
+
+  defp signed?("RS256", signed, signature) do
+    case public_key() do
+      nil -> no_key("RS256")
+      key -> :public_key.verify(signed, :sha256, signature, key)
+    end
+  end
+
+  defp signed?(_alg, _signed, _signature), do: false
+
+  defp public_key do
+    with pem when is_binary(pem) and pem != "" <- config()[:public_key],
+         [entry | _] <- :public_key.pem_decode(pem) do
+      :public_key.pem_entry_decode(entry)
+    else
+      _ -> nil
+    end
+  rescue
+    error ->
+      Logger.warning("Invalid single sign-on public key: #{Exception.message(error)}")
+      nil
+  end
+
+  defp no_key(alg) do
+    Logger.warning("Single sign-on token signed with #{alg}, but no key for it is set up")
+    false
+  end
+
+  defp check_time(claims, now) do
+    cond do
+      is_integer(claims["exp"]) and claims["exp"] <= now -> {:error, :expired}
+      is_integer(claims["nbf"]) and claims["nbf"] > now -> {:error, :invalid}
+      true -> {:ok, claims}
+    end
+  end
+
+  defp config, do: Application.get_env(:tsw_io, :sso, [])
+end
@@ -0,0 +1,76 @@
+defmodule TswIo.Summary do
+  @moduledoc """
+  Today's key numbers, for the desktop shell's menu bar extra.
+
+  Each stat says what it is, its value as shown and the page it opens on.
+  Headline stats are the ones the menu bar shows next to its icon, the rest
+  are only listed in its menu.
+  """
+
+  import Ecto.Query
+
+  alias TswIo.{Hardware, Repo, Serial, Simulator}
+  alias TswIo.Train.Detection
+  alias TswIo.Train.Train
+
+  @type stat :: %{
+          id: String.t(),
+          label: String.t(),
+          value: String.t(),
+          route: String.t(),
+          headline: boolean()
+        }
+
+  @doc """
+  The stats as of now, headline ones first.
+  """
+  @spec today() :: %{generated_at: DateTime.t(), stats: [stat()]}
+  def today do
+    %{
+      generated_at: DateTime.utc_now(),
+      stats: [active_train(), devices(), simulator(), trains_today(), configurations()]
+    }
+  end
+
+  defp active_train do
+    case Detection.get_active_train() do
+      %Train{} = train ->
+        stat("active_train", "Active train", train.name, "/trains/#{train.id}", true)
+
+      nil ->
+        stat("active_train", "Active train", "None", "/trains", true)
+    end
+  end
+
+  defp devices do
+    count = length(Serial.Connection.connected_devices())
+    stat("devices", "Connected devices", Integer.to_string(count), "/", true)
+  end
+
+  defp simulator do
+    value =
+      case Simulator.Connection.get_status().status do
+        :connected -> "Connected"
+        :connecting -> "Connecting"
+        :needs_config -> "Not set up"
+        _ -> "Not connected"
+      end
+
+    stat("simulator", "Simulator", value, "/simulator/config", false)
+  end
+
+  defp trains_today do
+    since = DateTime.new!(Date.utc_today(), ~T[00:00:00], "Etc/UTC")
+    count = Repo.aggregate(from(train in Train, where: train.inserted_at >= ^since), :count)
+    stat("trains_today", "Trains added today", Integer.to_string(count), "/trains", false)
+  end
+
+  defp configurations do
+    count = length(Hardware.list_configurations())
+    stat("configurations", "Configurations", Integer.to_string(count), "/", false)
+  end
+
+  defp stat(id, label, value, route, headline) do
+    %{id: id, label: label, value: value, route: route, headline: headline}
+  end
+end
@@ -0,0 +1,114 @@
+defmodule TswIo.SyncedFolders do
+  @moduledoc """
+  Files from folders the desktop shell watches, uploaded whenever they change.
+
+  Every watched folder has a directory of its own under `synced/` in the data
+  directory, mirroring the files in it. With each file the shell sends the hash
+  of the version it last saw here, so a file that changed on both sides is kept
+  next to the other as a conflict copy instead of replacing it.
+  """
+
+  alias TswIo.{Paths, Uploads}
+
+  @type result :: %{
+          status: :created | :updated | :unchanged | :conflict,
+          path: String.t(),
+          hash: String.t(),
+          current: String.t() | nil
+        }
+
+  @doc """
+  File the completed upload `upload_id` as `path` in the watched folder `folder`.
+
+  `base_hash` is the hash of the version the shell last saw, `nil` for a file
+  new to it. The result says where the file went and, for a conflict, the hash
+  of the version that was kept at `path`.
+  """
+  @spec ingest(String.t(), String.t(), String.t(), String.t() | nil) ::
+          {:ok, result()} | {:error, :invalid | :not_found | :incomplete | File.posix()}
+  def ingest(upload_id, folder, path, base_hash) do
+    with {:ok, folder} <- safe_folder(folder),
+         {:ok, path} <- safe_path(path),
+         {:ok, %{complete: true}} <- Uploads.status(upload_id) do
+      hash = hash(Uploads.path(upload_id))
+      dir = Path.join(dir(), folder)
+
+      case current_hash(Path.join(dir, path)) do
+        nil ->
+          store(upload_id, dir, path, %{status: :created, path: path, hash: hash, current: nil})
+
+        ^hash ->
+          Uploads.discard(upload_id)
+          {:ok, %{status: :unchanged, path: path, hash: hash, current: hash}}
+
+        ^base_hash ->
+          store(upload_id, dir, path, %{status: :updated, path: path, hash: hash, current: nil})
+
+        current ->
+          copy = conflict_path(path)
+          store(upload_id, dir, copy, %{status: :conflict, path: copy, hash: hash, current: current})
+      end
+    else
+      {:ok, %{complete: false}} -> {:error, :incomplete}
+      error -> error
+    end
+  end
+
+  @doc """
+  Where the files of the watched folder `folder` are kept.
+  """
+  @spec folder_dir(String.t()) :: String.t()
+  def folder_dir(folder), do: Path.join(dir(), folder)
+
+  defp store(upload_id, dir, path, result) do
+    with :ok <- Uploads.move(upload_id, Path.join(dir, path)) do
+      {:ok, result}
+    end
+  end
+
+  defp safe_folder(folder) when is_binary(folder) do
+    if folder == Path.basename(folder) and folder not in ["", ".", ".."],
+      do: {:ok, folder},
+      else: {:error, :invalid}
+  end
+
+  defp safe_folder(_folder), do: {:error, :invalid}
+
+  defp safe_path(path) when is_binary(path) do
+    case Path.safe_relative(path) do
+      {:ok, safe} when safe not in ["", "."] -> {:ok, safe}
+      _ -> {:error, :invalid}
+    end
+  end
+
+  defp safe_path(_path), do: {:error, :invalid}
+
+  # `notes.txt` becomes `notes (conflict 20261014-093000).txt`
+  defp conflict_path(path) do
+    stamp = Calendar.strftime(DateTime.utc_now(), "%Y%m%d-%H%M%S")
+    extension = Path.extname(path)
+    "#{Path.rootname(path, extension)} (conflict #{stamp})#{extension}"
+  end
+
+  defp current_hash(path) do
+    if File.regular?(path), do: hash(path)
+  end
+
+  defp hash(path) do
+    File.open!(path, [:read, :binary], fn file ->
+      file
+      |> hash_chunks(:crypto.hash_init(:sha256))
+      |> :crypto.hash_final()
+      |> Base.encode16(case: :lower)
+    end)
+  end
+
+  defp hash_chunks(file, state) do
+    case IO.binread(file, 1024 * 1024) do
+      :eof -> state
+      chunk -> hash_chunks(file, :crypto.hash_update(state, chunk))
+    end
+  end
+
+  defp dir, do: Path.join(Paths.data_dir(), "synced")
+end
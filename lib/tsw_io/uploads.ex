@@ -0,0 +1,155 @@
+defmodule TswIo.Uploads do
+  @moduledoc """
+  Large files the desktop shell uploads in chunks.
+
+  An upload is created with the file's name and size, then receives its bytes
+  in order. Every chunk says at which offset it starts, so an interrupted
+  upload resumes from the `received` count of `status/1`. Once all bytes have
+  arrived the upload is complete and `path/1` returns the file.
+  """
+
+  alias TswIo.Paths
+
+  @type status :: %{
+          id: String.t(),
+          filename: String.t(),
+          size: non_neg_integer(),
+          received: non_neg_integer(),
+          complete: boolean()
+        }
+
+  @doc """
+  Start an upload of a file named `filename` of `size` bytes.
+  """
+  @spec create(String.t(), non_neg_integer()) :: {:ok, status()} | {:error, :invalid}
+  def create(filename, size) when is_binary(filename) and is_integer(size) and size >= 0 do
+    case Path.basename(filename) do
+      name when name in ["", ".", ".."] ->
+        {:error, :invalid}
+
+      name ->
+        id = Base.encode16(:crypto.strong_rand_bytes(16), case: :lower)
+        File.write!(meta_path(id), Jason.encode!(%{filename: name, size: size}))
+        File.write!(part_path(id), "")
+        if size == 0, do: File.rename!(part_path(id), path(id))
+        status(id)
+    end
+  end
+
+  def create(_filename, _size), do: {:error, :invalid}
+
+  @doc """
+  How far an upload got.
+  """
+  @spec status(String.t()) :: {:ok, status()} | {:error, :not_found}
+  def status(id) do
+    with true <- valid_id?(id),
+         {:ok, contents} <- File.read(meta_path(id)),
+         {:ok, %{"filename" => filename, "size" => size}} <- Jason.decode(contents) do
+      {received, complete} =
+        case File.stat(path(id)) do
+          {:ok, _stat} -> {size, true}
+          {:error, _} -> {part_size(id), false}
+        end
+
+      {:ok, %{id: id, filename: filename, size: size, received: received, complete: complete}}
+    else
+      _ -> {:error, :not_found}
+    end
+  end
+
+  @doc """
+  Append a chunk to an upload, starting at `offset`.
+
+  The offset must be where the upload got to, anything else is refused with
+  how far it actually got.
+  """
+  @spec append(String.t(), non_neg_integer(), binary()) ::
+          {:ok, status()}
+          | {:error, :not_found | :complete | :too_large | {:offset_mismatch, non_neg_integer()}}
+  def append(id, offset, chunk) when is_binary(chunk) do
+    with {:ok, %{complete: false} = status} <- status(id),
+         :ok <- check_offset(status, offset),
+         :ok <- write_chunk(id, status, chunk) do
+      finish(id)
+    else
+      {:ok, %{complete: true}} -> {:error, :complete}
+      error -> error
+    end
+  end
+
+  @doc """
+  Move a completed upload to `destination` and forget about it.
+  """
+  @spec move(String.t(), String.t()) :: :ok | {:error, :not_found | :incomplete | File.posix()}
+  def move(id, destination) do
+    case status(id) do
+      {:ok, %{complete: true}} ->
+        File.mkdir_p!(Path.dirname(destination))
+
+        with :ok <- File.rename(path(id), destination) do
+          File.rm(meta_path(id))
+          :ok
+        end
+
+      {:ok, _status} ->
+        {:error, :incomplete}
+
+      error ->
+        error
+    end
+  end
+
+  @doc """
+  Delete an upload and everything received for it.
+  """
+  @spec discard(String.t()) :: :ok
+  def discard(id) do
+    if valid_id?(id), do: Enum.each([path(id), part_path(id), meta_path(id)], &File.rm/1)
+    :ok
+  end
+
+  @doc """
+  Where a completed upload is stored.
+  """
+  @spec path(String.t()) :: String.t()
+  def path(id), do: Path.join(dir(), id)
+
+  defp check_offset(%{received: offset}, offset), do: :ok
+  defp check_offset(%{received: received}, _offset), do: {:error, {:offset_mismatch, received}}
+
+  defp write_chunk(_id, %{received: received, size: size}, chunk)
+       when received + byte_size(chunk) > size,
+       do: {:error, :too_large}
+
+  defp write_chunk(id, _status, chunk), do: File.write(part_path(id), chunk, [:append])
+
+  defp finish(id) do
+    {:ok, status} = status(id)
+
+    if status.received == status.size do
+      File.rename!(part_path(id), path(id))
+      status(id)
+    else
+      {:ok, status}
+    end
+  end
+
+  defp part_size(id) do
+    case File.stat(part_path(id)) do
+      {:ok, %{size: size}} -> size
+      {:error, _} -> 0
+    end
+  end
+
+  defp valid_id?(id), do: is_binary(id) and String.match?(id, ~r/\A[0-9a-f]{32}\z/)
+
+  defp dir do
+    dir = Path.join(Paths.data_dir(), "uploads")
+    File.mkdir_p!(dir)
+    dir
+  end
+
+  defp meta_path(id), do: Path.join(dir(), "#{id}.json")
+  defp part_path(id), do: Path.join(dir(), "#{id}.part")
+end
@@ -0,0 +1,25 @@
+defmodule TswIoWeb.ShellChannel do
+  @moduledoc """
+  Channel between the backend and the desktop shell.
+
+  Events broadcast on the topic by `TswIo.Shell.emit/2` are pushed to the
+  shell as they are, events the shell pushes are handed to `TswIo.Shell`.
+  """
+
+  use Phoenix.Channel
+
+  @impl true
+  def join("shell", _payload, socket) do
+    {:ok, socket}
+  end
+
+  @impl true
+  def handle_in(event, payload, socket) when is_map(payload) do
+    TswIo.Shell.received(event, payload)
+    {:noreply, socket}
+  end
+
+  def handle_in(event, _payload, socket) do
+    {:reply, {:error, %{reason: "#{event} needs an object payload"}}, socket}
+  end
+end
@@ -0,0 +1,20 @@
+defmodule TswIoWeb.ShellSocket do
+  @moduledoc """
+  Socket the desktop shell connects to with the token it started the backend with.
+  """
+
+  use Phoenix.Socket
+
+  channel "shell", TswIoWeb.ShellChannel
+
+  @impl true
+  def connect(%{"token" => token}, socket, _connect_info) do
+    if TswIo.Shell.valid_token?(token), do: {:ok, socket}, else: :error
+  end
+
+  def connect(_params, _socket, _connect_info), do: :error
+
+  # Only one shell runs against a backend
+  @impl true
+  def id(_socket), do: "shell_socket"
+end
@@ -0,0 +1,13 @@
+defmodule TswIoWeb.BadgeController do
+  @moduledoc """
+  The app icon badge for desktop shells set to poll it, see `TswIo.Badge`.
+  """
+
+  use TswIoWeb, :controller
+
+  alias TswIo.Badge
+
+  def show(conn, _params) do
+    json(conn, %{count: Badge.count()})
+  end
+end
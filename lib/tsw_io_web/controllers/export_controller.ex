@@ -0,0 +1,20 @@
+defmodule TswIoWeb.ExportController do
+  @moduledoc """
+  The full export the desktop shell packs into an archive, see `TswIo.Export`.
+  """
+
+  use TswIoWeb, :controller
+
+  require Logger
+
+  alias TswIo.Export
+
+  def show(conn, _params) do
+    export = Export.everything()
+    Logger.info("Exporting #{map_size(export.tables)} tables")
+
+    conn
+    |> put_resp_header("x-tsw-io-version", export.backend_version)
+    |> json(export)
+  end
+end
@@ -0,0 +1,33 @@
+defmodule TswIoWeb.ExternalEventController do
+  @moduledoc """
+  Events from local tools, forwarded by the desktop shell's webhook receiver,
+  see `TswIo.ExternalEvents`.
+  """
+
+  use TswIoWeb, :controller
+
+  require Logger
+
+  alias TswIo.ExternalEvents
+
+  def create(conn, %{"event" => name} = params) do
+    source = Map.get(params, "source", "local")
+
+    case ExternalEvents.publish(name, source, params["payload"]) do
+      {:ok, event} ->
+        Logger.info("External event #{event.name} from #{event.source}")
+        json(conn, %{event: event.name, received_at: event.received_at})
+
+      {:error, :invalid} ->
+        invalid(conn)
+    end
+  end
+
+  def create(conn, _params), do: invalid(conn)
+
+  defp invalid(conn) do
+    conn
+    |> put_status(422)
+    |> json(%{error: "invalid_event"})
+  end
+end
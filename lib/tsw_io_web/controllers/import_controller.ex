@@ -0,0 +1,51 @@
+defmodule TswIoWeb.ImportController do
+  @moduledoc """
+  Spreadsheets of trains the desktop shell's import uploads, see `TswIo.Imports`.
+
+  The shell uploads each file through `TswIoWeb.UploadController`, then asks
+  for the completed upload to be imported here.
+  """
+
+  use TswIoWeb, :controller
+
+  require Logger
+
+  alias TswIo.Imports
+
+  def create(conn, %{"upload_id" => upload_id}) when is_binary(upload_id) do
+    case Imports.import_upload(upload_id) do
+      {:ok, summary} ->
+        Logger.info(
+          "Imported #{summary.filename}: #{summary.created} created, " <>
+            "#{summary.skipped} skipped, #{length(summary.failed)} failed"
+        )
+
+        json(conn, summary)
+
+      {:error, :not_found} ->
+        error(conn, 404, "not_found")
+
+      {:error, :incomplete} ->
+        error(conn, 422, "incomplete_upload")
+
+      {:error, :unsupported_format} ->
+        error(conn, 422, "unsupported_format")
+
+      {:error, :invalid_file} ->
+        error(conn, 422, "invalid_file")
+
+      {:error, {:missing_columns, columns}} ->
+        conn
+        |> put_status(422)
+        |> json(%{error: "missing_columns", columns: columns})
+    end
+  end
+
+  def create(conn, _params), do: error(conn, 422, "missing_upload")
+
+  defp error(conn, status, error) do
+    conn
+    |> put_status(status)
+    |> json(%{error: error})
+  end
+end
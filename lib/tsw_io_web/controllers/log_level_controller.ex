@@ -0,0 +1,27 @@
+defmodule TswIoWeb.LogLevelController do
+  @moduledoc """
+  Runtime log level adjustment.
+
+  Used by the desktop shell to raise the backend's log verbosity while
+  capturing logs for a bug report, without restarting the app.
+  """
+
+  use TswIoWeb, :controller
+
+  @levels ~w(error warning info debug)
+
+  def show(conn, _params) do
+    json(conn, %{level: to_string(Logger.level())})
+  end
+
+  def update(conn, %{"level" => level}) when level in @levels do
+    Logger.configure(level: String.to_existing_atom(level))
+    json(conn, %{level: level})
+  end
+
+  def update(conn, _params) do
+    conn
+    |> put_status(422)
+    |> json(%{error: "invalid_level", valid_levels: @levels})
+  end
+end
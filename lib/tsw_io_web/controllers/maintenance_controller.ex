@@ -0,0 +1,62 @@
+defmodule TswIoWeb.MaintenanceController do
+  @moduledoc """
+  Maintenance tasks the desktop shell runs on the user's request.
+  """
+
+  use TswIoWeb, :controller
+
+  require Logger
+
+  alias TswIo.Maintenance
+
+  def run(conn, %{"task" => name}) do
+    case Maintenance.parse_task(name) do
+      {:ok, task} ->
+        Logger.info("Running maintenance task #{name}")
+
+        case Maintenance.run(task) do
+          {:ok, message} ->
+            json(conn, %{task: name, message: message})
+
+          {:error, reason} ->
+            Logger.error("Maintenance task #{name} failed: #{inspect(reason)}")
+
+            conn
+            |> put_status(500)
+            |> json(%{error: "task_failed", task: name, reason: inspect(reason)})
+        end
+
+      :error ->
+        conn
+        |> put_status(422)
+        |> json(%{error: "unknown_task", valid_tasks: Maintenance.task_names()})
+    end
+  end
+
+  def backup(conn, %{"path" => path}) when is_binary(path) do
+    Logger.info("Backing up the database to #{path}")
+
+    case Maintenance.backup(path) do
+      {:ok, message} ->
+        json(conn, %{path: path, message: message})
+
+      {:error, reason} when reason in [:relative_path, :already_exists] ->
+        conn
+        |> put_status(422)
+        |> json(%{error: Atom.to_string(reason)})
+
+      {:error, reason} ->
+        Logger.error("Backup to #{path} failed: #{inspect(reason)}")
+
+        conn
+        |> put_status(500)
+        |> json(%{error: "backup_failed", reason: inspect(reason)})
+    end
+  end
+
+  def backup(conn, _params) do
+    conn
+    |> put_status(422)
+    |> json(%{error: "missing_path"})
+  end
+end
@@ -0,0 +1,102 @@
+defmodule TswIoWeb.PairingController do
+  @moduledoc """
+  Pairing the mobile app, see `TswIo.Pairing`.
+  """
+
+  use TswIoWeb, :controller
+
+  require Logger
+
+  alias TswIo.Pairing
+
+  @doc """
+  A new pairing code, for the desktop shell to show.
+  """
+  def start(conn, _params) do
+    {:ok, code, expires_at} = Pairing.start()
+    json(conn, %{code: code, expires_at: expires_at})
+  end
+
+  @doc """
+  A guest token, for the desktop shell to share as a link with a browser.
+  """
+  def guest(conn, _params) do
+    json(conn, %{token: Pairing.guest_token()})
+  end
+
+  @doc """
+  The paired devices, for the desktop shell's settings.
+  """
+  def devices(conn, _params) do
+    devices =
+      Enum.map(Pairing.list_devices(), fn device ->
+        %{
+          id: device.id,
+          name: device.name,
+          paired_at: device.inserted_at,
+          last_seen_at: device.last_seen_at,
+          expires_at: device.expires_at
+        }
+      end)
+
+    json(conn, %{devices: devices})
+  end
+
+  @doc """
+  Unpair a device, its token stops working right away.
+  """
+  def unpair(conn, %{"id" => id}) do
+    with {id, ""} <- Integer.parse(id),
+         :ok <- Pairing.unpair(id) do
+      Logger.info("Unpaired device #{id}")
+      send_resp(conn, 204, "")
+    else
+      _ ->
+        conn
+        |> put_status(404)
+        |> json(%{error: "not_found"})
+    end
+  end
+
+  def redeem(conn, %{"code" => code} = params) do
+    name = Map.get(params, "name", "Mobile device")
+
+    case Pairing.redeem(code, name) do
+      {:ok, token} ->
+        Logger.info("Paired #{name}")
+        json(conn, %{token: token})
+
+      {:error, reason} ->
+        conn
+        |> put_status(401)
+        |> json(%{error: Atom.to_string(reason)})
+    end
+  end
+
+  def redeem(conn, _params) do
+    conn
+    |> put_status(422)
+    |> json(%{error: "missing_code"})
+  end
+
+  @doc """
+  Keep a device token in the session, so the webview's page loads carry it.
+  """
+  def session(conn, %{"token" => token}) do
+    if Pairing.valid_device_token?(token) do
+      conn
+      |> put_session(:device_token, token)
+      |> redirect(to: ~p"/")
+    else
+      conn
+      |> put_status(401)
+      |> json(%{error: "not_paired"})
+    end
+  end
+
+  def session(conn, _params) do
+    conn
+    |> put_status(422)
+    |> json(%{error: "missing_token"})
+  end
+end
@@ -0,0 +1,19 @@
+defmodule TswIoWeb.SearchIndexController do
+  @moduledoc """
+  The records the desktop shell feeds to the OS search, see `TswIo.SearchIndex`.
+
+  With `q`, only those matching it, for the shell's command palette.
+  """
+
+  use TswIoWeb, :controller
+
+  alias TswIo.SearchIndex
+
+  def index(conn, %{"q" => query}) when is_binary(query) do
+    json(conn, %{entries: SearchIndex.search(query)})
+  end
+
+  def index(conn, _params) do
+    json(conn, %{entries: SearchIndex.entries()})
+  end
+end
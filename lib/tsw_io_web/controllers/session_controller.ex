@@ -0,0 +1,19 @@
+defmodule TswIoWeb.SessionController do
+  @moduledoc """
+  Session expiry for the desktop shell.
+
+  Every browser session shares one LiveView socket id, so the shell can
+  disconnect all pages at once when the user has been idle for too long.
+  """
+
+  use TswIoWeb, :controller
+
+  @live_socket_id "desktop_session"
+
+  def live_socket_id, do: @live_socket_id
+
+  def expire(conn, _params) do
+    TswIoWeb.Endpoint.broadcast(@live_socket_id, "disconnect", %{})
+    json(conn, %{status: "expired"})
+  end
+end
@@ -0,0 +1,13 @@
+defmodule TswIoWeb.SummaryController do
+  @moduledoc """
+  Today's key numbers for the desktop shell's menu bar extra, see `TswIo.Summary`.
+  """
+
+  use TswIoWeb, :controller
+
+  alias TswIo.Summary
+
+  def show(conn, _params) do
+    json(conn, Summary.today())
+  end
+end
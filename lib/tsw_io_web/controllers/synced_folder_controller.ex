@@ -0,0 +1,54 @@
+defmodule TswIoWeb.SyncedFolderController do
+  @moduledoc """
+  Files from the folders the desktop shell watches, see `TswIo.SyncedFolders`.
+
+  The shell uploads a changed file through `TswIoWeb.UploadController`, then
+  files the completed upload here.
+  """
+
+  use TswIoWeb, :controller
+
+  require Logger
+
+  alias TswIo.SyncedFolders
+
+  def ingest(conn, %{"upload_id" => upload_id, "folder" => folder, "path" => path} = params) do
+    case SyncedFolders.ingest(upload_id, folder, path, params["base_hash"]) do
+      {:ok, %{status: :conflict} = result} ->
+        Logger.warning("#{path} in #{folder} changed on both sides, kept as #{result.path}")
+        json(conn, result)
+
+      {:ok, result} ->
+        Logger.info("Synced #{path} in #{folder} (#{result.status})")
+        json(conn, result)
+
+      {:error, :invalid} ->
+        invalid(conn)
+
+      {:error, :not_found} ->
+        conn
+        |> put_status(404)
+        |> json(%{error: "not_found"})
+
+      {:error, :incomplete} ->
+        conn
+        |> put_status(422)
+        |> json(%{error: "incomplete_upload"})
+
+      {:error, reason} ->
+        Logger.error("Failed to sync #{path} in #{folder}: #{inspect(reason)}")
+
+        conn
+        |> put_status(500)
+        |> json(%{error: "write_failed", reason: inspect(reason)})
+    end
+  end
+
+  def ingest(conn, _params), do: invalid(conn)
+
+  defp invalid(conn) do
+    conn
+    |> put_status(422)
+    |> json(%{error: "invalid_file"})
+  end
+end
@@ -0,0 +1,101 @@
+defmodule TswIoWeb.UploadController do
+  @moduledoc """
+  Chunked uploads of large files from the desktop shell.
+
+  The shell creates an upload, then `PUT`s the file's bytes in order as
+  `application/octet-stream` bodies with the `offset` they start at.
+  """
+
+  use TswIoWeb, :controller
+
+  require Logger
+
+  alias TswIo.Uploads
+
+  # Largest chunk accepted in one request, the shell sends 8 MB
+  @max_chunk 16 * 1024 * 1024
+
+  def create(conn, %{"filename" => filename, "size" => size}) do
+    case Uploads.create(filename, size) do
+      {:ok, status} ->
+        Logger.info("Receiving upload #{status.id} of #{status.size} bytes")
+
+        conn
+        |> put_status(201)
+        |> json(status)
+
+      {:error, :invalid} ->
+        invalid(conn)
+    end
+  end
+
+  def create(conn, _params), do: invalid(conn)
+
+  def show(conn, %{"id" => id}) do
+    case Uploads.status(id) do
+      {:ok, status} -> json(conn, status)
+      {:error, :not_found} -> not_found(conn)
+    end
+  end
+
+  def append(conn, %{"id" => id, "offset" => offset}) do
+    with {offset, ""} <- Integer.parse(offset),
+         {:ok, chunk, conn} <- read_chunk(conn) do
+      case Uploads.append(id, offset, chunk) do
+        {:ok, status} ->
+          if status.complete, do: Logger.info("Upload #{id} complete")
+          json(conn, status)
+
+        {:error, :not_found} ->
+          not_found(conn)
+
+        {:error, {:offset_mismatch, received}} ->
+          conn
+          |> put_status(409)
+          |> json(%{error: "offset_mismatch", received: received})
+
+        {:error, reason} when reason in [:complete, :too_large] ->
+          conn
+          |> put_status(422)
+          |> json(%{error: Atom.to_string(reason)})
+
+        {:error, reason} ->
+          Logger.error("Failed to write upload #{id}: #{inspect(reason)}")
+
+          conn
+          |> put_status(500)
+          |> json(%{error: "write_failed", reason: inspect(reason)})
+      end
+    else
+      {:error, :too_large, conn} ->
+        conn
+        |> put_status(413)
+        |> json(%{error: "chunk_too_large", max: @max_chunk})
+
+      _ ->
+        invalid(conn)
+    end
+  end
+
+  def append(conn, _params), do: invalid(conn)
+
+  defp read_chunk(conn) do
+    case read_body(conn, length: @max_chunk) do
+      {:ok, chunk, conn} -> {:ok, chunk, conn}
+      {:more, _data, conn} -> {:error, :too_large, conn}
+      {:error, reason} -> {:error, reason}
+    end
+  end
+
+  defp invalid(conn) do
+    conn
+    |> put_status(422)
+    |> json(%{error: "invalid_upload"})
+  end
+
+  defp not_found(conn) do
+    conn
+    |> put_status(404)
+    |> json(%{error: "not_found"})
+  end
+end
@@ -15,6 +15,11 @@ defmodule TswIoWeb.Endpoint do
     websocket: [connect_info: [session: @session_options]],
     longpoll: [connect_info: [session: @session_options]]
 
+  # Event bridge to the desktop shell, see TswIo.Shell
+  socket "/shell", TswIoWeb.ShellSocket,
+    websocket: true,
+    longpoll: false
+
   # Serve at "/" the static files from "priv/static" directory.
   #
   # When code reloading is disabled (e.g., in production),
@@ -41,6 +46,7 @@ defmodule TswIoWeb.Endpoint do
       cookie_key: "request_logger"
   end
 
+  plug TswIoWeb.Plugs.ForwardedClient
   plug Plug.RequestId
   plug Plug.Telemetry, event_prefix: [:phoenix, :endpoint]
 
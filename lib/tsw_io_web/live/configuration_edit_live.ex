@@ -95,7 +95,11 @@ defmodule TswIoWeb.ConfigurationEditLive do
          |> assign(:calibrating_input, nil)
          |> assign(:calibration_session_state, nil)
          |> assign(:show_apply_modal, false)
-         |> assign(:show_delete_modal, false)}
+         |> assign(:show_delete_modal, false)
+         |> push_event("recent_workspace", %{
+           route: ~p"/configurations/#{device.config_id}",
+           title: device.name
+         })}
 
       {:error, :not_found} ->
         {:ok,
@@ -0,0 +1,47 @@
+defmodule TswIoWeb.TodayWidgetLive do
+  @moduledoc """
+  Today's key numbers, compact enough for a desktop widget.
+
+  The desktop shell shows it in a small frameless window, so there is no
+  navigation and the whole page can be used to drag the window around.
+  """
+
+  use TswIoWeb, :live_view
+
+  alias TswIo.Summary
+
+  @refresh_interval :timer.seconds(30)
+
+  @impl true
+  def mount(_params, _session, socket) do
+    if connected?(socket) do
+      :timer.send_interval(@refresh_interval, :refresh)
+    end
+
+    {:ok, socket |> assign(:page_title, "Today") |> assign_stats()}
+  end
+
+  @impl true
+  def handle_info(:refresh, socket) do
+    {:noreply, assign_stats(socket)}
+  end
+
+  defp assign_stats(socket) do
+    assign(socket, :stats, Summary.today().stats)
+  end
+
+  @impl true
+  def render(assigns) do
+    ~H"""
+    <div data-tauri-drag-region class="h-screen p-3 bg-base-200 select-none cursor-move">
+      <h1 data-tauri-drag-region class="text-xs font-semibold uppercase opacity-60 mb-2">Today</h1>
+      <dl data-tauri-drag-region class="space-y-1 text-sm">
+        <div :for={stat <- @stats} data-tauri-drag-region class="flex justify-between gap-4">
+          <dt data-tauri-drag-region class="opacity-70">{stat.label}</dt>
+          <dd data-tauri-drag-region class="font-medium">{stat.value}</dd>
+        </div>
+      </dl>
+    </div>
+    """
+  end
+end
@@ -103,7 +103,8 @@ defmodule TswIoWeb.TrainEditLive do
          |> assign(:binding_element, nil)
          |> assign(:available_inputs, [])
          |> assign(:notch_mapping_wizard_element, nil)
-         |> assign(:notch_mapping_state, nil)}
+         |> assign(:notch_mapping_state, nil)
+         |> push_event("recent_workspace", %{route: ~p"/trains/#{train.id}", title: train.name})}
 
       {:error, :not_found} ->
         {:ok,
@@ -0,0 +1,27 @@
+defmodule TswIoWeb.Plugs.ContentSecurityPolicy do
+  @moduledoc """
+  Sends the Content-Security-Policy chosen by the desktop shell.
+
+  The shell passes the policy in `TSW_IO_CSP`. Every `{nonce}` in it is replaced
+  with a fresh nonce, which is also assigned as `:csp_nonce` so inline scripts in
+  the layout can carry it. Without a policy, nothing is sent.
+  """
+
+  import Plug.Conn
+
+  def init(opts), do: opts
+
+  def call(conn, _opts) do
+    case Application.get_env(:tsw_io, :content_security_policy) do
+      policy when is_binary(policy) and policy != "" ->
+        nonce = 16 |> :crypto.strong_rand_bytes() |> Base.encode64()
+
+        conn
+        |> assign(:csp_nonce, nonce)
+        |> put_resp_header("content-security-policy", String.replace(policy, "{nonce}", nonce))
+
+      _ ->
+        conn
+    end
+  end
+end
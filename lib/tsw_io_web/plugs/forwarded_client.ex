@@ -0,0 +1,30 @@
+defmodule TswIoWeb.Plugs.ForwardedClient do
+  @moduledoc """
+  Takes the client's address from the desktop shell's proxy.
+
+  Behind the proxy every request comes from this machine, so
+  `TswIoWeb.Plugs.LanAuth` would take other machines for local ones. The proxy
+  sends the real address in `x-forwarded-for`, replacing any the client sent.
+  Only requests from this machine are trusted with it, and only while the
+  shell started the backend behind the proxy.
+  """
+
+  import Plug.Conn
+
+  def init(opts), do: opts
+
+  def call(conn, _opts) do
+    with true <- Application.get_env(:tsw_io, :behind_proxy, false),
+         true <- local?(conn.remote_ip),
+         [forwarded] <- get_req_header(conn, "x-forwarded-for"),
+         {:ok, ip} <- forwarded |> String.trim() |> String.to_charlist() |> :inet.parse_address() do
+      %{conn | remote_ip: ip}
+    else
+      _ -> conn
+    end
+  end
+
+  defp local?({127, _, _, _}), do: true
+  defp local?({0, 0, 0, 0, 0, 0, 0, 1}), do: true
+  defp local?(_ip), do: false
+end
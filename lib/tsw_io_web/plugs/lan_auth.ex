@@ -0,0 +1,50 @@
+defmodule TswIoWeb.Plugs.LanAuth do
+  @moduledoc """
+  Only lets paired devices in from other machines.
+
+  Requests from this machine pass as before. Others need a device token from
+  `TswIo.Pairing`, as a bearer token or in the session, where
+  `/pairing/session` puts it for the mobile app's webview. The health check
+  and pairing itself stay open, or a phone could never pair.
+  """
+
+  import Plug.Conn
+
+  @open_paths ["/api/health", "/api/pairing", "/pairing/session"]
+
+  def init(opts), do: opts
+
+  def call(conn, _opts) do
+    cond do
+      local?(conn.remote_ip) -> conn
+      conn.request_path in @open_paths -> conn
+      TswIo.Pairing.valid_device_token?(device_token(conn)) -> conn
+      true -> reject(conn)
+    end
+  end
+
+  defp local?({127, _, _, _}), do: true
+  defp local?({0, 0, 0, 0, 0, 0, 0, 1}), do: true
+  defp local?({0, 0, 0, 0, 0, 0xFFFF, a, _}), do: div(a, 256) == 127
+  defp local?(_ip), do: false
+
+  defp device_token(conn) do
+    case get_req_header(conn, "authorization") do
+      ["Bearer " <> token] -> token
+      _ -> session_token(conn)
+    end
+  end
+
+  # The API pipeline doesn't fetch the session
+  defp session_token(%Plug.Conn{private: %{plug_session: _}} = conn),
+    do: get_session(conn, :device_token)
+
+  defp session_token(_conn), do: nil
+
+  defp reject(conn) do
+    conn
+    |> put_status(401)
+    |> Phoenix.Controller.json(%{error: "not_paired"})
+    |> halt()
+  end
+end
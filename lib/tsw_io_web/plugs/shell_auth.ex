@@ -0,0 +1,25 @@
+defmodule TswIoWeb.Plugs.ShellAuth do
+  @moduledoc """
+  Only lets the desktop shell through.
+
+  The shell sends the token it started the backend with as a bearer token,
+  see `TswIo.Shell.valid_token?/1`.
+  """
+
+  import Plug.Conn
+
+  def init(opts), do: opts
+
+  def call(conn, _opts) do
+    with ["Bearer " <> token] <- get_req_header(conn, "authorization"),
+         true <- TswIo.Shell.valid_token?(token) do
+      conn
+    else
+      _ ->
+        conn
+        |> put_status(401)
+        |> Phoenix.Controller.json(%{error: "unauthorized"})
+        |> halt()
+    end
+  end
+end
@@ -0,0 +1,42 @@
+defmodule TswIoWeb.Plugs.SsoAuth do
+  @moduledoc """
+  Only serves pages to those signed in with single sign-on, when it is on.
+
+  The token comes in the cookie the desktop shell sets, see `TswIo.Sso`.
+  Its claims are assigned as `:sso_claims`. The mobile app's pairing session
+  stays open, paired devices are let in by `TswIoWeb.Plugs.LanAuth`.
+  """
+
+  import Plug.Conn
+
+  @open_paths ["/pairing/session"]
+
+  def init(opts), do: opts
+
+  def call(conn, _opts) do
+    with name when is_binary(name) <- TswIo.Sso.cookie_name(),
+         false <- conn.request_path in @open_paths do
+      conn = fetch_cookies(conn)
+
+      case TswIo.Sso.verify(conn.req_cookies[name]) do
+        {:ok, claims} -> assign(conn, :sso_claims, claims)
+        {:error, reason} -> reject(conn, reason)
+      end
+    else
+      _ -> conn
+    end
+  end
+
+  defp reject(conn, reason) do
+    message =
+      case reason do
+        :expired -> "Your sign-in has expired, sign in again from the app"
+        :invalid -> "Sign in from the app to continue"
+      end
+
+    conn
+    |> put_status(401)
+    |> Phoenix.Controller.text(message)
+    |> halt()
+  end
+end
@@ -8,10 +8,20 @@ defmodule TswIoWeb.Router do
     plug :put_root_layout, html: {TswIoWeb.Layouts, :root}
     plug :protect_from_forgery
     plug :put_secure_browser_headers
+    plug TswIoWeb.Plugs.ContentSecurityPolicy
+    plug TswIoWeb.Plugs.LanAuth
+    plug TswIoWeb.Plugs.SsoAuth
+    plug :put_live_socket_id
   end
 
   pipeline :api do
     plug :accepts, ["json"]
+    plug TswIoWeb.Plugs.LanAuth
+  end
+
+  pipeline :shell do
+    plug :accepts, ["json"]
+    plug TswIoWeb.Plugs.ShellAuth
   end
 
   # Health check endpoint - returns 200 only when app is fully ready
@@ -19,11 +29,50 @@ defmodule TswIoWeb.Router do
     pipe_through :api
 
     get "/health", HealthController, :index
+
+    # Called by the mobile app with the code from the desktop's QR code
+    post "/pairing", PairingController, :redeem
+  end
+
+  # Only the desktop shell may call these, with the token it started the backend with
+  scope "/api/admin", TswIoWeb do
+    pipe_through :shell
+
+    # Used by the desktop shell to change backend verbosity at runtime
+    get "/log_level", LogLevelController, :show
+    put "/log_level", LogLevelController, :update
+
+    # Called by the desktop shell when the user has been idle for too long
+    post "/session/expire", SessionController, :expire
+
+    post "/maintenance/:task", MaintenanceController, :run
+    post "/backup", MaintenanceController, :backup
+    post "/pairing", PairingController, :start
+    post "/pairing/guest", PairingController, :guest
+    get "/paired_devices", PairingController, :devices
+    delete "/paired_devices/:id", PairingController, :unpair
+
+    post "/uploads", UploadController, :create
+    get "/uploads/:id", UploadController, :show
+    put "/uploads/:id", UploadController, :append
+
+    post "/synced_folders/ingest", SyncedFolderController, :ingest
+    post "/imports", ImportController, :create
+
+    get "/search_index", SearchIndexController, :index
+    get "/export", ExportController, :show
+
+    post "/external_events", ExternalEventController, :create
+    get "/badge", BadgeController, :show
+    get "/summary", SummaryController, :show
   end
 
   scope "/", TswIoWeb do
     pipe_through :browser
 
+    # Where the mobile app sends its webview after pairing
+    get "/pairing/session", PairingController, :session
+
     live_session :default, on_mount: TswIoWeb.NavHook do
       live "/", ConfigurationListLive
       live "/configurations/:config_id", ConfigurationEditLive
@@ -32,10 +81,20 @@ defmodule TswIoWeb.Router do
       live "/trains/:train_id", TrainEditLive
       live "/firmware", FirmwareLive
     end
+
+    # Compact pages the desktop shell shows as widgets, without the navigation
+    live_session :widgets do
+      live "/widgets/today", TodayWidgetLive
+    end
   end
 
   # Other scopes may use custom stacks.
   # scope "/api", TswIoWeb do
   #   pipe_through :api
   # end
+
+  # Lets the desktop shell disconnect every LiveView at once
+  defp put_live_socket_id(conn, _opts) do
+    put_session(conn, :live_socket_id, TswIoWeb.SessionController.live_socket_id())
+  end
 end
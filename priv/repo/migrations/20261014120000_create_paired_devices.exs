@@ -0,0 +1,13 @@
+defmodule TswIo.Repo.Migrations.CreatePairedDevices do
+  use Ecto.Migration
+
+  def change do
+    create table(:paired_devices) do
+      add :name, :string, null: false
+      add :expires_at, :utc_datetime, null: false
+      add :last_seen_at, :utc_datetime
+
+      timestamps(type: :utc_datetime)
+    end
+  end
+end
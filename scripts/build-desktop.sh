@@ -30,9 +30,30 @@ detect_platform() {
 PLATFORM=$(detect_platform)
 echo "Building for platform: $PLATFORM"
 
+# Link exqlite against SQLCipher, so an encrypted database can be opened with its passphrase
+sqlcipher_env() {
+    case "$(uname -s)" in
+        Darwin)
+            local prefix
+            prefix="$(brew --prefix sqlcipher)"
+            export EXQLITE_SYSTEM_CFLAGS="-I$prefix/include/sqlcipher -DSQLITE_HAS_CODEC"
+            export EXQLITE_SYSTEM_LDFLAGS="-L$prefix/lib -lsqlcipher"
+            ;;
+        *)
+            export EXQLITE_SYSTEM_CFLAGS="$(pkg-config --cflags sqlcipher) -DSQLITE_HAS_CODEC"
+            export EXQLITE_SYSTEM_LDFLAGS="$(pkg-config --libs sqlcipher)"
+            ;;
+    esac
+    export EXQLITE_USE_SYSTEM=1
+}
+sqlcipher_env
+
 cd "$PROJECT_DIR"
 
-# Step 1: Build Phoenix assets
+# Step 1: Build exqlite against SQLCipher and the Phoenix assets
+echo "==> Building exqlite against SQLCipher..."
+MIX_ENV=prod mix deps.compile exqlite --force
+
 echo "==> Building Phoenix assets..."
 MIX_ENV=prod mix assets.deploy
 
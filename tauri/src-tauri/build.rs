@@ -1,3 +1,124 @@
+/// Every command of the desktop and mobile apps
+const COMMANDS: &[&str] = &[
+    "activate_license",
+    "add_account",
+    "add_recent_workspace",
+    "add_watched_folder",
+    "cancel_download",
+    "change_port",
+    "clear_downloads",
+    "complete_setup",
+    "confirm_with_biometrics",
+    "connect_server",
+    "copy_debug_info",
+    "discover_servers",
+    "dismiss_capture",
+    "dismiss_palette",
+    "export_archive",
+    "export_page_to_pdf",
+    "export_settings",
+    "forget_server",
+    "get_accounts",
+    "get_app_info",
+    "get_audit_log",
+    "get_bug_report_preview",
+    "get_diagnostics",
+    "get_feature_flags",
+    "get_integrations",
+    "get_lan_access",
+    "get_license",
+    "get_log_level",
+    "get_machine_id",
+    "get_metrics_history",
+    "get_offline_copy",
+    "get_paired_devices",
+    "get_passphrase_prompt",
+    "get_recovery_reason",
+    "get_schedule_history",
+    "get_schedules",
+    "get_server",
+    "get_service_status",
+    "get_setup_defaults",
+    "get_startup_failure",
+    "get_watched_folders",
+    "get_widgets",
+    "import_license",
+    "import_settings",
+    "import_spreadsheets",
+    "list_downloads",
+    "notify",
+    "notify_progress",
+    "open_download",
+    "open_downloads_window",
+    "open_external",
+    "open_log_dir",
+    "open_pairing_window",
+    "open_palette_result",
+    "pair_server",
+    "pause_download",
+    "pick_backup_file",
+    "pick_data_dir",
+    "pick_folder",
+    "pick_upload_files",
+    "quit_app",
+    "register_integration",
+    "relocate_data_dir",
+    "remove_account",
+    "remove_license",
+    "remove_schedule",
+    "remove_watched_folder",
+    "remove_widget",
+    "restore_and_restart",
+    "resume_download",
+    "retry_startup",
+    "reveal_download",
+    "reveal_path",
+    "run_maintenance",
+    "run_schedule_now",
+    "run_self_test",
+    "save_file",
+    "save_schedule",
+    "save_widget",
+    "search_palette",
+    "set_backend_service",
+    "set_lan_access",
+    "set_log_level",
+    "set_tray_status",
+    "share_lan_access",
+    "sso_sign_in",
+    "sso_sign_out",
+    "start_download",
+    "start_pairing",
+    "start_safe_mode",
+    "submit_bug_report",
+    "submit_capture",
+    "submit_passphrase",
+    "switch_account",
+    "unlock_app",
+    "unpair_device",
+    "unregister_integration",
+    "upload_file",
+    "window_control",
+];
+
 fn main() {
-    tauri_build::build()
+    // The sidecar is released from the Elixir project at the repository root
+    println!("cargo:rerun-if-changed=../../mix.exs");
+    let backend_version = std::fs::read_to_string("../../mix.exs")
+        .ok()
+        .and_then(|mix| {
+            mix.lines().find_map(|line| {
+                let rest = line.trim().strip_prefix("version: \"")?;
+                rest.split('"').next().map(str::to_string)
+            })
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=TSW_IO_BACKEND_VERSION={}", backend_version);
+
+    // Pages only get the commands their capability grants, see `capabilities/`
+    tauri_build::try_build(
+        tauri_build::Attributes::new()
+            .app_manifest(tauri_build::AppManifest::new().commands(COMMANDS)),
+    )
+    .expect("Failed to run tauri-build")
 }
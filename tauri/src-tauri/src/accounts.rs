@@ -0,0 +1,271 @@
+use keyring::Entry;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, Wry};
+
+use crate::api;
+use crate::config::{self, ConfigState};
+use crate::lock;
+use crate::profile::ProfileState;
+use crate::supervisor::{self, SupervisorState};
+use crate::tray;
+
+const WINDOW_LABEL: &str = "accounts";
+/// Service the device tokens are filed under in the OS credential store, one account each
+const SERVICE: &str = "tsw_io";
+const TOKEN_PREFIX: &str = "account_token:";
+/// Webview storage of each account, in the app's local data directory
+const STORAGE_DIR: &str = "accounts";
+/// How long the old main window gets to go away before the new one is built
+const DESTROY_TIMEOUT: Duration = Duration::from_secs(2);
+const DESTROY_POLL: Duration = Duration::from_millis(50);
+
+/// Set while the main window is rebuilt, when there may be no window for a moment
+static SWITCHING: AtomicBool = AtomicBool::new(false);
+
+/// An account as the manager shows it
+#[derive(Debug, Serialize)]
+pub struct AccountStatus {
+    pub name: String,
+    pub active: bool,
+    /// Whether its device token is in the credential store, backends on other machines want one
+    pub signed_in: bool,
+}
+
+/// Whether the app must not exit because the main window closed, see `switch`
+pub fn switching() -> bool {
+    SWITCHING.load(Ordering::SeqCst)
+}
+
+/// Tells the account's storage apart, the same for a name on every run
+fn storage_id(name: &str) -> [u8; 16] {
+    let digest = Sha256::digest(name.as_bytes());
+    let mut id = [0u8; 16];
+    id.copy_from_slice(&digest[..16]);
+    id
+}
+
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+fn storage_dir(handle: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let id: String = storage_id(name).iter().map(|byte| format!("{:02x}", byte)).collect();
+    handle
+        .path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(STORAGE_DIR).join(id))
+        .map_err(|e| format!("Failed to find the app data directory: {}", e))
+}
+
+fn token_entry(name: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE, &format!("{}{}", TOKEN_PREFIX, name))
+        .map_err(|e| format!("Failed to open credential store: {}", e))
+}
+
+fn stored_token(name: &str) -> Option<String> {
+    match token_entry(name).and_then(|entry| entry.get_password().map_err(|e| e.to_string())) {
+        Ok(token) => Some(token),
+        Err(e) => {
+            tracing::debug!(account = name, "No device token stored: {}", e);
+            None
+        }
+    }
+}
+
+/// The account the main window shows, `None` for the shared storage
+pub fn active(handle: &AppHandle) -> Option<String> {
+    let config = handle.state::<ConfigState>().get();
+    config.active_account.filter(|name| config.accounts.contains(name))
+}
+
+/// Give the main window the storage of the active account
+///
+/// WKWebView keeps data stores by identifier, from macOS 14 on, the other webviews by directory.
+pub fn isolate<'a>(
+    handle: &AppHandle,
+    builder: WebviewWindowBuilder<'a, Wry, AppHandle>,
+) -> WebviewWindowBuilder<'a, Wry, AppHandle> {
+    let Some(name) = active(handle) else {
+        return builder;
+    };
+    #[cfg(target_os = "macos")]
+    {
+        builder.data_store_identifier(storage_id(&name))
+    }
+    #[cfg(not(target_os = "macos"))]
+    match storage_dir(handle, &name) {
+        Ok(dir) => builder.data_directory(dir),
+        Err(e) => {
+            tracing::warn!("Failed to keep the storage of {} apart: {}", name, e);
+            builder
+        }
+    }
+}
+
+/// Where the main window starts, signing the active account in with its device token
+///
+/// Its session cookie keeps it signed in afterwards, so switching back doesn't ask again.
+pub fn entry_url(handle: &AppHandle, base_url: &str) -> String {
+    match active(handle).and_then(|name| stored_token(&name)) {
+        Some(token) => api::backend_url(
+            base_url,
+            &format!("/pairing/session?token={}", urlencoding::encode(&token)),
+        ),
+        None => base_url.to_string(),
+    }
+}
+
+/// Show `name`'s account in the main window, `None` for the shared storage
+///
+/// A webview can't change storage, so the main window is built again. Blocks until the
+/// old one is gone, keep it off the event loop.
+pub fn switch(handle: &AppHandle, name: Option<String>) -> Result<(), String> {
+    let config = handle.state::<ConfigState>().get();
+    if let Some(name) = name.as_ref().filter(|name| !config.accounts.contains(name)) {
+        return Err(format!("No account named {}", name));
+    }
+    if active(handle) == name {
+        crate::open_main_window(handle);
+        return Ok(());
+    }
+    config::update(handle, |config| config.active_account = name.clone())?;
+    tracing::info!(account = name.as_deref().unwrap_or("shared"), "Switched account");
+
+    SWITCHING.store(true, Ordering::SeqCst);
+    let rebuilt = rebuild_main_window(handle);
+    SWITCHING.store(false, Ordering::SeqCst);
+    tray::sync_accounts(handle);
+    rebuilt
+}
+
+fn rebuild_main_window(handle: &AppHandle) -> Result<(), String> {
+    if let Some(window) = handle.get_webview_window("main") {
+        window
+            .destroy()
+            .map_err(|e| format!("Failed to close the main window: {}", e))?;
+        let started = Instant::now();
+        while handle.get_webview_window("main").is_some() {
+            if started.elapsed() > DESTROY_TIMEOUT {
+                return Err("The main window didn't close, restart the app to switch".to_string());
+            }
+            std::thread::sleep(DESTROY_POLL);
+        }
+    }
+    crate::open_main_window(handle);
+    // The new storage has none of the account's cookies yet
+    if crate::sso::inject(handle) {
+        if let Some(window) = handle.get_webview_window("main") {
+            let _ = window.reload();
+        }
+    }
+    Ok(())
+}
+
+/// Every account, with the one the main window shows
+pub fn statuses(handle: &AppHandle) -> Vec<AccountStatus> {
+    let active = active(handle);
+    handle
+        .state::<ConfigState>()
+        .get()
+        .accounts
+        .into_iter()
+        .map(|name| AccountStatus {
+            active: active.as_ref() == Some(&name),
+            signed_in: stored_token(&name).is_some(),
+            name,
+        })
+        .collect()
+}
+
+/// Show the account manager, or focus it if it is already open
+pub fn open(handle: &AppHandle) -> tauri::Result<()> {
+    if !lock::may_show(handle) {
+        return Ok(());
+    }
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        return window.set_focus();
+    }
+    WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("accounts.html".into()))
+        .title("TSW IO Accounts")
+        .inner_size(480.0, 480.0)
+        .build()?;
+    Ok(())
+}
+
+/// Pair a new account with the backend and keep its device token
+///
+/// Uses up any pairing code a phone was about to redeem.
+fn add(handle: &AppHandle, name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Give the account a name".to_string());
+    }
+    if handle.state::<ConfigState>().get().accounts.iter().any(|existing| existing == name) {
+        return Err(format!("There is already an account named {}", name));
+    }
+    if supervisor::state(handle) != SupervisorState::Running {
+        return Err("The backend is not running".to_string());
+    }
+    let base_url = handle.state::<ProfileState>().get().base_url();
+    let pairing = api::start_pairing(&base_url)?;
+    let token = api::redeem_pairing(&base_url, &pairing.code, &format!("tsw_io desktop ({})", name))?;
+    token_entry(name)?
+        .set_password(&token)
+        .map_err(|e| format!("Failed to store the device token: {}", e))?;
+    config::update(handle, |config| config.accounts.push(name.to_string()))?;
+    tray::sync_accounts(handle);
+    tracing::info!(account = name, "Added account");
+    Ok(())
+}
+
+/// Forget an account, its device token and, where the webview allows it, its storage
+fn remove(handle: &AppHandle, name: &str) -> Result<(), String> {
+    if active(handle).as_deref() == Some(name) {
+        switch(handle, None)?;
+    }
+    config::update(handle, |config| config.accounts.retain(|existing| existing != name))?;
+    match token_entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => tracing::warn!("Failed to remove the device token of {}: {}", name, e),
+    }
+    // WKWebView data stores can only be removed while no webview uses them, they stay behind
+    #[cfg(not(target_os = "macos"))]
+    if let Ok(dir) = storage_dir(handle, name) {
+        if dir.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&dir) {
+                tracing::warn!("Failed to remove {}: {}", dir.display(), e);
+            }
+        }
+    }
+    tray::sync_accounts(handle);
+    tracing::info!(account = name, "Removed account");
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_accounts(app: AppHandle) -> Vec<AccountStatus> {
+    statuses(&app)
+}
+
+#[tauri::command]
+pub async fn add_account(app: AppHandle, name: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    tauri::async_runtime::spawn_blocking(move || add(&app, &name))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn remove_account(app: AppHandle, name: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || remove(&app, &name))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Switch the main window to `name`, `None` for the shared storage
+#[tauri::command]
+pub async fn switch_account(app: AppHandle, name: Option<String>) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || switch(&app, name))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,536 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::config::ScheduledTask;
+use crate::logging::LogLevel;
+use crate::maintenance::MaintenanceTask;
+use crate::{secrets, tls};
+
+/// Compacting a large database can take minutes
+const MAINTENANCE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// A chunk is several megabytes, more than the default timeout allows on a slow disk
+const CHUNK_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+
+pub fn backend_url(base_url: &str, path: &str) -> String {
+    format!("{}{}", base_url.trim_end_matches('/'), path)
+}
+
+/// Change the backend's Logger level at runtime
+pub fn set_backend_log_level(base_url: &str, level: LogLevel) -> Result<(), String> {
+    let response = tls::backend_client()?
+        .put(backend_url(base_url, "/api/admin/log_level"))
+        .bearer_auth(secrets::shell_token())
+        .json(&json!({ "level": level.backend_name() }))
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Backend rejected log level change: {}", response.status()))
+    }
+}
+
+/// Disconnect every page from the backend so nothing typed before going idle survives
+pub fn expire_backend_session(base_url: &str) -> Result<(), String> {
+    let response = tls::backend_client()?
+        .post(backend_url(base_url, "/api/admin/session/expire"))
+        .bearer_auth(secrets::shell_token())
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Backend rejected session expiry: {}", response.status()))
+    }
+}
+
+/// Run a maintenance task on the backend, returning what it did
+pub fn run_maintenance(base_url: &str, task: MaintenanceTask) -> Result<String, String> {
+    let response = tls::backend_client()?
+        .post(backend_url(base_url, &format!("/api/admin/maintenance/{}", task.as_str())))
+        .bearer_auth(secrets::shell_token())
+        .timeout(MAINTENANCE_TIMEOUT)
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    let status = response.status();
+    let body: serde_json::Value = response
+        .json()
+        .map_err(|e| format!("Invalid backend response: {}", e))?;
+    if status.is_success() {
+        Ok(body["message"].as_str().unwrap_or("Done").to_string())
+    } else {
+        Err(format!(
+            "Backend rejected {}: {}",
+            task.as_str(),
+            body["reason"].as_str().or(body["error"].as_str()).unwrap_or(status.as_str())
+        ))
+    }
+}
+
+/// Have the backend write a copy of its database to `path`, on this machine
+pub fn backup(base_url: &str, path: &Path) -> Result<String, String> {
+    let response = tls::backend_client()?
+        .post(backend_url(base_url, "/api/admin/backup"))
+        .bearer_auth(secrets::shell_token())
+        .json(&json!({ "path": path }))
+        .timeout(MAINTENANCE_TIMEOUT)
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    let status = response.status();
+    let body: serde_json::Value = response
+        .json()
+        .map_err(|e| format!("Invalid backend response: {}", e))?;
+    if status.is_success() {
+        Ok(body["message"].as_str().unwrap_or("Done").to_string())
+    } else {
+        Err(format!(
+            "Backend rejected the backup: {}",
+            body["reason"].as_str().or(body["error"].as_str()).unwrap_or(status.as_str())
+        ))
+    }
+}
+
+/// Call the endpoint of a scheduled task, returning what the backend said it did
+pub fn run_scheduled(base_url: &str, task: &ScheduledTask) -> Result<String, String> {
+    let method = reqwest::Method::from_bytes(task.method.to_uppercase().as_bytes())
+        .map_err(|_| format!("Invalid method {}", task.method))?;
+    let mut request = tls::backend_client()?
+        .request(method, backend_url(base_url, &task.path))
+        .timeout(MAINTENANCE_TIMEOUT);
+    if task.path.starts_with("/api/admin/") {
+        request = request.bearer_auth(secrets::shell_token());
+    }
+    if let Some(body) = &task.body {
+        request = request
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+    }
+    let response = request.send().map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    let status = response.status();
+    let body: serde_json::Value = response.json().unwrap_or_default();
+    if status.is_success() {
+        Ok(body["message"].as_str().unwrap_or("Done").to_string())
+    } else {
+        Err(format!(
+            "Backend rejected {}: {}",
+            task.name,
+            body["reason"].as_str().or(body["error"].as_str()).unwrap_or(status.as_str())
+        ))
+    }
+}
+
+/// How far an upload to the backend got, as `/api/admin/uploads` reports it
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadStatus {
+    pub id: String,
+    pub size: u64,
+    pub received: u64,
+    pub complete: bool,
+}
+
+/// What the backend says about a chunk it didn't take
+pub enum ChunkError {
+    /// The upload is elsewhere than the chunk assumed, resume from here
+    Offset(u64),
+    Failed(String),
+}
+
+fn upload_response(response: reqwest::blocking::Response) -> Result<UploadStatus, String> {
+    let status = response.status();
+    if status.is_success() {
+        return response
+            .json()
+            .map_err(|e| format!("Invalid backend response: {}", e));
+    }
+    let body: serde_json::Value = response.json().unwrap_or_default();
+    Err(format!(
+        "Backend rejected upload: {}",
+        body["error"].as_str().unwrap_or(status.as_str())
+    ))
+}
+
+/// Start a chunked upload of a file named `filename`
+pub fn create_upload(base_url: &str, filename: &str, size: u64) -> Result<UploadStatus, String> {
+    let response = tls::backend_client()?
+        .post(backend_url(base_url, "/api/admin/uploads"))
+        .bearer_auth(secrets::shell_token())
+        .json(&json!({ "filename": filename, "size": size }))
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+    upload_response(response)
+}
+
+pub fn upload_status(base_url: &str, id: &str) -> Result<UploadStatus, String> {
+    let response = tls::backend_client()?
+        .get(backend_url(base_url, &format!("/api/admin/uploads/{}", urlencoding::encode(id))))
+        .bearer_auth(secrets::shell_token())
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+    upload_response(response)
+}
+
+/// Send the bytes of an upload starting at `offset`
+pub fn upload_chunk(base_url: &str, id: &str, offset: u64, chunk: Vec<u8>) -> Result<UploadStatus, ChunkError> {
+    let path = format!("/api/admin/uploads/{}?offset={}", urlencoding::encode(id), offset);
+    let response = tls::backend_client()
+        .map_err(ChunkError::Failed)?
+        .put(backend_url(base_url, &path))
+        .bearer_auth(secrets::shell_token())
+        .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+        .timeout(CHUNK_TIMEOUT)
+        .body(chunk)
+        .send()
+        .map_err(|e| ChunkError::Failed(format!("Failed to reach backend: {}", e)))?;
+
+    if response.status() == reqwest::StatusCode::CONFLICT {
+        let body: serde_json::Value = response.json().unwrap_or_default();
+        return match body["received"].as_u64() {
+            Some(received) => Err(ChunkError::Offset(received)),
+            None => Err(ChunkError::Failed("Backend lost track of the upload".to_string())),
+        };
+    }
+    upload_response(response).map_err(ChunkError::Failed)
+}
+
+/// Where the backend filed a file of a watched folder, see `folders`
+#[derive(Debug, Clone, Deserialize)]
+pub struct IngestResult {
+    /// `created`, `updated`, `unchanged` or `conflict`
+    pub status: String,
+    /// Relative to the folder, a conflict copy's name for a conflict
+    pub path: String,
+    pub hash: String,
+    /// Hash of the version kept at the file's own path, for a conflict
+    pub current: Option<String>,
+}
+
+/// File a completed upload as `path` in the watched folder `folder`
+///
+/// `base_hash` is the backend's version the shell last saw, it decides whether changes
+/// on both sides conflict.
+pub fn ingest(
+    base_url: &str,
+    upload_id: &str,
+    folder: &str,
+    path: &str,
+    base_hash: Option<&str>,
+) -> Result<IngestResult, String> {
+    let response = tls::backend_client()?
+        .post(backend_url(base_url, "/api/admin/synced_folders/ingest"))
+        .bearer_auth(secrets::shell_token())
+        .json(&json!({ "upload_id": upload_id, "folder": folder, "path": path, "base_hash": base_hash }))
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return response
+            .json()
+            .map_err(|e| format!("Invalid backend response: {}", e));
+    }
+    let body: serde_json::Value = response.json().unwrap_or_default();
+    Err(format!(
+        "Backend rejected {}: {}",
+        path,
+        body["error"].as_str().unwrap_or(status.as_str())
+    ))
+}
+
+/// A spreadsheet row the backend couldn't import, numbered as in the spreadsheet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportFailure {
+    pub row: u64,
+    pub errors: Vec<String>,
+}
+
+/// What the backend made of an imported spreadsheet, see `imports`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub filename: String,
+    /// Trains created
+    pub created: u64,
+    /// Rows of trains the backend knew already
+    pub skipped: u64,
+    pub failed: Vec<ImportFailure>,
+}
+
+/// Import the trains in a completed upload of a spreadsheet
+pub fn import_upload(base_url: &str, upload_id: &str) -> Result<ImportSummary, String> {
+    let response = tls::backend_client()?
+        .post(backend_url(base_url, "/api/admin/imports"))
+        .bearer_auth(secrets::shell_token())
+        .json(&json!({ "upload_id": upload_id }))
+        .timeout(MAINTENANCE_TIMEOUT)
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return response
+            .json()
+            .map_err(|e| format!("Invalid backend response: {}", e));
+    }
+    let body: serde_json::Value = response.json().unwrap_or_default();
+    Err(match body["error"].as_str() {
+        Some("missing_columns") => format!(
+            "The first row is missing the columns {}",
+            body["columns"]
+                .as_array()
+                .map(|columns| columns.iter().filter_map(|column| column.as_str()).collect::<Vec<_>>().join(", "))
+                .unwrap_or_default()
+        ),
+        Some("unsupported_format") => "Only CSV and Excel (.xlsx) files can be imported".to_string(),
+        Some("invalid_file") => "The file can't be read as a spreadsheet".to_string(),
+        error => format!("Backend rejected the import: {}", error.unwrap_or(status.as_str())),
+    })
+}
+
+/// A record the OS search shows, see `search`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchEntry {
+    pub id: String,
+    /// `configuration` or `train`
+    pub kind: String,
+    pub title: String,
+    pub snippet: String,
+    /// Backend page the result opens
+    pub route: String,
+}
+
+#[derive(Deserialize)]
+struct SearchIndex {
+    entries: Vec<SearchEntry>,
+}
+
+/// Everything the backend wants in the OS search
+pub fn search_index(base_url: &str) -> Result<Vec<SearchEntry>, String> {
+    search_entries(base_url, None)
+}
+
+/// The entries matching what was typed in the command palette, best first
+pub fn search(base_url: &str, query: &str) -> Result<Vec<SearchEntry>, String> {
+    search_entries(base_url, Some(query))
+}
+
+fn search_entries(base_url: &str, query: Option<&str>) -> Result<Vec<SearchEntry>, String> {
+    let mut request = tls::backend_client()?
+        .get(backend_url(base_url, "/api/admin/search_index"))
+        .bearer_auth(secrets::shell_token());
+    if let Some(query) = query {
+        request = request.query(&[("q", query)]);
+    }
+    let response = request
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Backend rejected the search index request: {}", response.status()));
+    }
+    response
+        .json::<SearchIndex>()
+        .map(|index| index.entries)
+        .map_err(|e| format!("Invalid backend response: {}", e))
+}
+
+/// Everything in the backend's database, as a response to read while it arrives, see `archive`
+pub fn export(base_url: &str) -> Result<reqwest::blocking::Response, String> {
+    let response = tls::backend_client()?
+        .get(backend_url(base_url, "/api/admin/export"))
+        .bearer_auth(secrets::shell_token())
+        .timeout(MAINTENANCE_TIMEOUT)
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Backend rejected the export: {}", response.status()));
+    }
+    Ok(response)
+}
+
+/// The count for the app icon badge, for when it is polled rather than pushed, see `badge`
+pub fn badge_count(base_url: &str) -> Result<i64, String> {
+    let response = tls::backend_client()?
+        .get(backend_url(base_url, "/api/admin/badge"))
+        .bearer_auth(secrets::shell_token())
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Backend rejected the badge request: {}", response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .map_err(|e| format!("Invalid badge response: {}", e))?;
+    body["count"]
+        .as_i64()
+        .ok_or_else(|| "Invalid badge response: no count".to_string())
+}
+
+/// One of today's numbers in the menu bar extra, see `menubar`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SummaryStat {
+    pub id: String,
+    pub label: String,
+    /// Already formatted for display
+    pub value: String,
+    /// Backend page the stat opens
+    pub route: String,
+    /// Shown next to the icon, not only in the menu
+    pub headline: bool,
+}
+
+#[derive(Deserialize)]
+struct Summary {
+    stats: Vec<SummaryStat>,
+}
+
+/// Today's key numbers, headline ones first
+pub fn summary(base_url: &str) -> Result<Vec<SummaryStat>, String> {
+    let response = tls::backend_client()?
+        .get(backend_url(base_url, "/api/admin/summary"))
+        .bearer_auth(secrets::shell_token())
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Backend rejected the summary request: {}", response.status()));
+    }
+    let summary: Summary = response
+        .json()
+        .map_err(|e| format!("Invalid summary response: {}", e))?;
+    Ok(summary.stats)
+}
+
+/// Hand an event a local tool posted to the webhook receiver to the backend, see `webhooks`
+pub fn forward_event(base_url: &str, event: &str, source: &str, payload: &serde_json::Value) -> Result<(), String> {
+    let response = tls::backend_client()?
+        .post(backend_url(base_url, "/api/admin/external_events"))
+        .bearer_auth(secrets::shell_token())
+        .json(&json!({ "event": event, "source": source, "payload": payload }))
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Backend rejected the {} event: {}", event, response.status()));
+    }
+    Ok(())
+}
+
+/// One-time code a phone trades for a device token, see `pairing`
+#[derive(Debug, Deserialize)]
+pub struct PairingCode {
+    pub code: String,
+    /// Unix seconds
+    pub expires_at: i64,
+}
+
+/// Ask the backend for a new pairing code, replacing any earlier one
+pub fn start_pairing(base_url: &str) -> Result<PairingCode, String> {
+    let response = tls::backend_client()?
+        .post(backend_url(base_url, "/api/admin/pairing"))
+        .bearer_auth(secrets::shell_token())
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Backend rejected pairing: {}", response.status()));
+    }
+    response
+        .json()
+        .map_err(|e| format!("Invalid backend response: {}", e))
+}
+
+/// Trade a pairing code for a device token, as the mobile app does
+pub fn redeem_pairing(base_url: &str, code: &str, device_name: &str) -> Result<String, String> {
+    let response = tls::backend_client()?
+        .post(backend_url(base_url, "/api/pairing"))
+        .json(&serde_json::json!({ "code": code, "name": device_name }))
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Backend rejected the pairing code: {}", response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .map_err(|e| format!("Invalid backend response: {}", e))?;
+    body["token"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "Invalid backend response: no token".to_string())
+}
+
+/// A device paired with the backend, see `pairing`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairedDevice {
+    pub id: i64,
+    pub name: String,
+    /// ISO 8601, as the backend sends all times
+    pub paired_at: String,
+    pub last_seen_at: Option<String>,
+    /// When the device has to pair again
+    pub expires_at: String,
+}
+
+/// The devices paired with the backend, the most recently paired first
+pub fn paired_devices(base_url: &str) -> Result<Vec<PairedDevice>, String> {
+    #[derive(Deserialize)]
+    struct Devices {
+        devices: Vec<PairedDevice>,
+    }
+
+    let response = tls::backend_client()?
+        .get(backend_url(base_url, "/api/admin/paired_devices"))
+        .bearer_auth(secrets::shell_token())
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Backend rejected listing the paired devices: {}", response.status()));
+    }
+    response
+        .json::<Devices>()
+        .map(|body| body.devices)
+        .map_err(|e| format!("Invalid backend response: {}", e))
+}
+
+/// Revoke the token of the paired device `id`
+pub fn unpair_device(base_url: &str, id: i64) -> Result<(), String> {
+    let response = tls::backend_client()?
+        .delete(backend_url(base_url, &format!("/api/admin/paired_devices/{}", id)))
+        .bearer_auth(secrets::shell_token())
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Backend rejected unpairing the device: {}", response.status()));
+    }
+    Ok(())
+}
+
+/// A token that lets a browser on another machine in for a while
+pub fn guest_token(base_url: &str) -> Result<String, String> {
+    let response = tls::backend_client()?
+        .post(backend_url(base_url, "/api/admin/pairing/guest"))
+        .bearer_auth(secrets::shell_token())
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Backend rejected the guest link: {}", response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .map_err(|e| format!("Invalid backend response: {}", e))?;
+    body["token"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "Invalid backend response: no token".to_string())
+}
@@ -0,0 +1,248 @@
+use chrono::{Datelike, Local, Timelike};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::downloads::{self, DownloadKind};
+use crate::profile::ProfileState;
+use crate::{api, config, maintenance, recent};
+
+/// Bumped when the layout of the archive changes
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// `manifest.json`, the last file in the archive, describing the others
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format_version: u32,
+    /// RFC 3339, local time
+    created_at: String,
+    app_version: String,
+    backend_version: Option<String>,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestFile {
+    name: String,
+    size: u64,
+    sha256: String,
+}
+
+/// Deflated, as zip64 so a large database export fits, dated now
+fn file_options() -> SimpleFileOptions {
+    let now = Local::now();
+    let modified = zip::DateTime::from_date_and_time(
+        now.year().clamp(1980, 2107) as u16,
+        now.month() as u8,
+        now.day() as u8,
+        now.hour() as u8,
+        now.minute() as u8,
+        now.second().min(59) as u8,
+    )
+    .unwrap_or_default();
+    SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true)
+        .last_modified_time(modified)
+}
+
+/// Stream `contents` into the archive as `name`, for the manifest
+fn add<W: Write + std::io::Seek>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    contents: &mut dyn Read,
+) -> Result<ManifestFile, String> {
+    let failed = |e: std::io::Error| format!("Failed to write {} to the archive: {}", name, e);
+    zip.start_file(name, file_options())
+        .map_err(|e| format!("Failed to write {} to the archive: {}", name, e))?;
+    let (mut sha256, mut size) = (Sha256::new(), 0u64);
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = contents
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        if read == 0 {
+            break;
+        }
+        sha256.update(&buffer[..read]);
+        size += read as u64;
+        zip.write_all(&buffer[..read]).map_err(failed)?;
+    }
+    Ok(ManifestFile {
+        name: name.to_string(),
+        size,
+        sha256: sha256.finalize().iter().map(|byte| format!("{:02x}", byte)).collect(),
+    })
+}
+
+/// Read the archive back: every file must inflate to what its checksum and the manifest say
+///
+/// Returns how many files the manifest lists.
+fn verify(path: &Path) -> Result<usize, String> {
+    let invalid = |what: &str| format!("The archive is damaged: {}", what);
+    let file = File::open(path).map_err(|e| format!("Failed to read the archive: {}", e))?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| invalid(&e.to_string()))?;
+
+    let mut checked = Vec::with_capacity(archive.len());
+    let mut manifest = None;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(|e| invalid(&e.to_string()))?;
+        let name = file.name().to_string();
+        let (mut sha256, mut read_size) = (Sha256::new(), 0u64);
+        let mut contents = Vec::new();
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            // Fails at the end when the contents don't match their checksum
+            let read = file
+                .read(&mut buffer)
+                .map_err(|e| invalid(&format!("{} doesn't inflate: {}", name, e)))?;
+            if read == 0 {
+                break;
+            }
+            sha256.update(&buffer[..read]);
+            read_size += read as u64;
+            if name == MANIFEST_NAME {
+                contents.extend_from_slice(&buffer[..read]);
+            }
+        }
+        if read_size != file.size() {
+            return Err(invalid(&format!("{} doesn't match its size", name)));
+        }
+        if name == MANIFEST_NAME {
+            manifest = Some(
+                serde_json::from_slice::<Manifest>(&contents)
+                    .map_err(|e| invalid(&format!("unreadable manifest: {}", e)))?,
+            );
+        } else {
+            let sha256: String = sha256.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+            checked.push((name, read_size, sha256));
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| invalid("no manifest"))?;
+    for listed in &manifest.files {
+        let matches = checked
+            .iter()
+            .any(|(name, size, sha256)| *name == listed.name && *size == listed.size && *sha256 == listed.sha256);
+        if !matches {
+            return Err(invalid(&format!("{} doesn't match the manifest", listed.name)));
+        }
+    }
+    Ok(manifest.files.len())
+}
+
+/// Write everything the backend holds and the shell settings to a zip at `path`
+///
+/// The archive is built next to `path` and only moved there once it reads back fine.
+/// Returns how many files it holds besides the manifest.
+pub fn export_to(handle: &AppHandle, path: &Path) -> Result<usize, String> {
+    let _exclusive = maintenance::exclusive("An export")?;
+    let part = PathBuf::from(format!("{}.part", path.display()));
+    let result = write(handle, &part).and_then(|()| verify(&part));
+    let files = match result {
+        Ok(files) => files,
+        Err(e) => {
+            let _ = std::fs::remove_file(&part);
+            return Err(e);
+        }
+    };
+    std::fs::rename(&part, path).map_err(|e| format!("Failed to move the archive to {}: {}", path.display(), e))?;
+    tracing::info!(files, "Exported everything to {}", path.display());
+    downloads::record(handle, DownloadKind::Export, path);
+    recent::add_file(handle, path);
+    Ok(files)
+}
+
+fn write(handle: &AppHandle, path: &Path) -> Result<(), String> {
+    let base_url = handle.state::<ProfileState>().get().base_url();
+    let mut export = api::export(&base_url)?;
+    let backend_version = export
+        .headers()
+        .get("x-tsw-io-version")
+        .and_then(|version| version.to_str().ok())
+        .map(str::to_string);
+    let settings = config::load(&config::config_path(handle))?.exportable();
+    let settings = toml::to_string_pretty(&settings).map_err(|e| format!("Failed to write the settings: {}", e))?;
+
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let mut files = Vec::new();
+    for (name, contents) in [
+        ("backend.json", &mut export as &mut dyn Read),
+        ("settings.toml", &mut settings.as_bytes()),
+    ] {
+        files.push(add(&mut zip, name, contents)?);
+    }
+    let manifest = Manifest {
+        format_version: FORMAT_VERSION,
+        created_at: Local::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        backend_version,
+        files,
+    };
+    let manifest = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    add(&mut zip, MANIFEST_NAME, &mut manifest.as_slice())?;
+    let mut file = zip
+        .finish()
+        .map_err(|e| format!("Failed to write the archive: {}", e))?;
+    let failed = |e: std::io::Error| format!("Failed to write the archive: {}", e);
+    file.flush().map_err(failed)?;
+    file.get_ref().sync_all().map_err(failed)
+}
+
+/// Ask where to put the archive and export to it, `None` if the user cancelled
+fn pick_and_export(handle: &AppHandle) -> Result<Option<(PathBuf, usize)>, String> {
+    let name = format!("tsw_io-export-{}.zip", Local::now().format("%Y-%m-%d"));
+    let path = handle
+        .dialog()
+        .file()
+        .set_title("Export everything")
+        .set_file_name(&name)
+        .add_filter("Archive", &["zip"])
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok());
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    export_to(handle, &path).map(|files| Some((path, files)))
+}
+
+/// Export from the File menu, telling the user how it went
+pub fn export_from_menu(handle: &AppHandle) {
+    let handle = handle.clone();
+    // The dialog and the download block, keep them off the event loop
+    std::thread::spawn(move || {
+        let (message, kind) = match pick_and_export(&handle) {
+            Ok(None) => return,
+            Ok(Some((path, files))) => (
+                format!("Exported everything to {}, {} files checked.", path.display(), files),
+                MessageDialogKind::Info,
+            ),
+            Err(e) => {
+                tracing::warn!("{}", e);
+                (e, MessageDialogKind::Error)
+            }
+        };
+        handle
+            .dialog()
+            .message(message)
+            .title("TSW IO")
+            .kind(kind)
+            .show(|_| {});
+    });
+}
+
+/// Export everything to a zip the user picks, returning where it went
+#[tauri::command]
+pub async fn export_archive(app: AppHandle) -> Result<Option<PathBuf>, String> {
+    tauri::async_runtime::spawn_blocking(move || pick_and_export(&app).map(|done| done.map(|(path, _)| path)))
+        .await
+        .map_err(|e| e.to_string())?
+}
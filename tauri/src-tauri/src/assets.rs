@@ -0,0 +1,108 @@
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Where the backend serves its static files from, see `TswIoWeb.static_paths/0`
+const STATIC_DIRS: &[&str] = &["/assets/", "/fonts/", "/images/"];
+/// Length of the MD5 `phx.digest` puts into file names
+const DIGEST_LEN: usize = 32;
+/// Bigger files are passed through, nothing the UI loads comes close
+pub const MAX_ASSET_BYTES: usize = 10 * 1024 * 1024;
+/// The oldest files go once the cache grows past this, as it does over backend versions
+const MAX_CACHE_BYTES: u64 = 100 * 1024 * 1024;
+
+/// The backend's fingerprinted static assets, kept on disk by the proxy
+///
+/// A fingerprinted file's contents only change along with its name, so copies never go
+/// stale and are served without asking the backend, even while it restarts.
+pub struct AssetCache {
+    dir: PathBuf,
+}
+
+/// Whether the last segment of `path` carries a digest, like `app-<md5>.js`
+fn fingerprinted(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let Some((stem, _extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    stem.rsplit_once('-').is_some_and(|(_, digest)| {
+        digest.len() == DIGEST_LEN && digest.bytes().all(|byte| byte.is_ascii_hexdigit())
+    })
+}
+
+fn file_name(path: &str) -> String {
+    let digest = Sha256::digest(path.as_bytes());
+    digest[..16].iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl AssetCache {
+    pub fn open(dir: PathBuf) -> Self {
+        prune(&dir);
+        AssetCache { dir }
+    }
+
+    /// File the copy of `path` is kept in, if it names a fingerprinted asset
+    pub fn key(&self, path: &str) -> Option<PathBuf> {
+        // `?vsn=d` and the like don't change the contents
+        let path = path.split('?').next().unwrap_or(path);
+        if !STATIC_DIRS.iter().any(|dir| path.starts_with(dir)) || !fingerprinted(path) {
+            return None;
+        }
+        Some(self.dir.join(file_name(path)))
+    }
+
+    /// Content type and body kept for `key`
+    pub async fn get(&self, key: &Path) -> Option<(String, Vec<u8>)> {
+        let mut contents = tokio::fs::read(key).await.ok()?;
+        let newline = contents.iter().position(|byte| *byte == b'\n')?;
+        let content_type = String::from_utf8(contents[..newline].to_vec()).ok()?;
+        contents.drain(..=newline);
+        Some((content_type, contents))
+    }
+
+    /// Keep a copy, the content type on the first line
+    pub async fn put(&self, key: &Path, content_type: &str, body: &[u8]) {
+        if content_type.contains('\n') {
+            return;
+        }
+        let mut contents = Vec::with_capacity(content_type.len() + 1 + body.len());
+        contents.extend_from_slice(content_type.as_bytes());
+        contents.push(b'\n');
+        contents.extend_from_slice(body);
+        // Renamed into place, so a copy is never read half written
+        let partial = key.with_extension("partial");
+        let saved = async {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            tokio::fs::write(&partial, &contents).await?;
+            tokio::fs::rename(&partial, key).await
+        };
+        if let Err(e) = saved.await {
+            tracing::debug!("Failed to cache an asset: {}", e);
+            let _ = tokio::fs::remove_file(&partial).await;
+        }
+    }
+}
+
+/// Remove the oldest copies until the cache fits in `MAX_CACHE_BYTES`
+fn prune(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), entry.path()))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort();
+    for (_, len, path) in files {
+        if total <= MAX_CACHE_BYTES {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= len;
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::paths::AppPaths;
+
+const AUDIT_FILE: &str = "audit.jsonl";
+/// Entries the diagnostics window shows, newest first
+const SHOWN_ENTRIES: usize = 200;
+
+/// Serializes appends so concurrent entries don't interleave
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Shell operation that changes what the backend runs with or on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    BackendRestart,
+    /// The bundled backend swapped for another version while running, see `upgrade`
+    Upgrade { from: String, to: String },
+    Backup,
+    Restore,
+    ConfigChange,
+    DataDirMove,
+    Maintenance,
+    Confirmation,
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    pub action: AuditAction,
+    pub detail: String,
+}
+
+fn audit_path(handle: &AppHandle) -> PathBuf {
+    handle.state::<AppPaths>().log_dir.join(AUDIT_FILE)
+}
+
+/// Append an entry to the audit log
+///
+/// The file is only ever appended to, it is not rotated or compacted like the other logs.
+pub fn record(handle: &AppHandle, action: AuditAction, detail: impl Into<String>) {
+    let entry = AuditEntry {
+        at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        action,
+        detail: detail.into(),
+    };
+    if let Err(e) = append(&audit_path(handle), &entry) {
+        tracing::warn!("Failed to record audit entry: {}", e);
+    }
+}
+
+fn append(path: &Path, entry: &AuditEntry) -> Result<(), String> {
+    let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    let _guard = WRITE_LOCK.lock().unwrap();
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn recent(path: &Path) -> Vec<AuditEntry> {
+    let mut entries: Vec<AuditEntry> = std::fs::read_to_string(path)
+        .map(|contents| {
+            contents
+                .lines()
+                // A line cut short by a crash is skipped rather than failing the whole file
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.reverse();
+    entries.truncate(SHOWN_ENTRIES);
+    entries
+}
+
+#[tauri::command]
+pub async fn get_audit_log(app: AppHandle) -> Result<Vec<AuditEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || recent(&audit_path(&app)))
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,348 @@
+use tauri::AppHandle;
+
+/// Make the app scriptable by the OS: AppleScript and Shortcuts on macOS, COM on Windows
+///
+/// Both offer activate, open a document, quit and backup. Opened documents go to the
+/// backend as an `open_document` event.
+pub fn start(handle: &AppHandle) {
+    platform::start(handle);
+}
+
+/// Show the app and hand `path` to the backend
+pub(crate) fn open_document(handle: &AppHandle, path: &std::path::Path) -> Result<(), String> {
+    if !path.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    crate::open_main_window(handle);
+    tracing::info!("Opening {}", path.display());
+    crate::bridge::push("open_document", serde_json::json!({ "path": path }))?;
+    crate::recent::add_file(handle, path);
+    Ok(())
+}
+
+/// Documents and links macOS asked the app to open, from Finder, an AppleScript `open`, the
+/// recent documents, where pages are kept as links, or a browser
+#[cfg(target_os = "macos")]
+pub fn opened(handle: &AppHandle, urls: Vec<tauri::Url>) {
+    for url in urls {
+        if let Some(target) = crate::cli::link_target(url.as_str()) {
+            crate::companion::open(handle, target);
+            continue;
+        }
+        let Ok(path) = url.to_file_path() else {
+            continue;
+        };
+        if let Some(route) = crate::recent::link_route(&path) {
+            crate::companion::open(handle, crate::cli::Target::Route(route));
+            continue;
+        }
+        if let Err(e) = open_document(handle, &path) {
+            tracing::warn!("Failed to open {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Cocoa handles activate, open and quit from the dictionary in `tsw_io.sdef`; backup
+/// is the `TSWBackupCommand` class it names
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2::{define_class, ClassType, Message};
+    use objc2_foundation::{NSScriptCommand, NSString};
+    use std::path::PathBuf;
+    use std::sync::OnceLock;
+    use tauri::AppHandle;
+
+    use crate::maintenance;
+
+    /// Generic AppleScript error number, the message says what went wrong
+    const SCRIPT_ERROR: isize = -2700;
+
+    static HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+    /// A suspended command, only touched again on the main thread
+    struct Suspended(Retained<BackupCommand>);
+    unsafe impl Send for Suspended {}
+
+    impl Suspended {
+        /// Taking `self` whole keeps closures from capturing just the non-Send field
+        fn into_inner(self) -> Retained<BackupCommand> {
+            self.0
+        }
+    }
+
+    define_class!(
+        // SAFETY: NSScriptCommand has no subclassing requirements and this doesn't implement Drop
+        #[unsafe(super(NSScriptCommand))]
+        #[name = "TSWBackupCommand"]
+        struct BackupCommand;
+
+        impl BackupCommand {
+            #[unsafe(method_id(performDefaultImplementation))]
+            fn perform_default_implementation(&self) -> Option<Retained<AnyObject>> {
+                backup(self);
+                None
+            }
+        }
+    );
+
+    /// Answer once the backup is written, without blocking the main thread until then
+    fn backup(command: &BackupCommand) {
+        let Some(handle) = HANDLE.get().cloned() else {
+            return;
+        };
+        let path = command
+            .evaluatedArguments()
+            .and_then(|arguments| arguments.objectForKey(&NSString::from_str("path")))
+            .and_then(|path| path.downcast::<NSString>().ok())
+            .map(|path| PathBuf::from(path.to_string()));
+
+        command.suspendExecution();
+        let suspended = Suspended(command.retain());
+        std::thread::spawn(move || {
+            let result = maintenance::backup(&handle, path);
+            let _ = handle.run_on_main_thread(move || {
+                let command = suspended.into_inner();
+                match result {
+                    Ok(path) => {
+                        let path = NSString::from_str(&path.to_string_lossy());
+                        unsafe { command.resumeExecutionWithResult(Some(&path)) };
+                    }
+                    Err(e) => {
+                        command.setScriptErrorNumber(SCRIPT_ERROR);
+                        command.setScriptErrorString(Some(&NSString::from_str(&e)));
+                        unsafe { command.resumeExecutionWithResult(None) };
+                    }
+                }
+            });
+        });
+    }
+
+    pub fn start(handle: &AppHandle) {
+        let _ = HANDLE.set(handle.clone());
+        // Registers the class, so Cocoa finds it by the name in the dictionary
+        let _ = BackupCommand::class();
+    }
+}
+
+/// A `TswIo.Application` COM object, for PowerShell's `New-Object -ComObject`
+#[cfg(windows)]
+mod platform {
+    use std::ffi::c_void;
+    use std::mem::ManuallyDrop;
+    use std::path::PathBuf;
+    use std::sync::OnceLock;
+    use tauri::AppHandle;
+    use windows::core::{implement, IUnknown, Interface, Ref, BOOL, BSTR, GUID, PCWSTR};
+    use windows::Win32::Foundation::{
+        CLASS_E_NOAGGREGATION, DISP_E_EXCEPTION, DISP_E_MEMBERNOTFOUND, DISP_E_TYPEMISMATCH,
+        DISP_E_UNKNOWNNAME, E_FAIL, E_NOTIMPL,
+    };
+    use windows::Win32::System::Com::{
+        CoInitializeEx, CoRegisterClassObject, IClassFactory, IClassFactory_Impl, IDispatch, IDispatch_Impl,
+        ITypeInfo, CLSCTX_LOCAL_SERVER, COINIT_MULTITHREADED, DISPATCH_FLAGS, DISPPARAMS, EXCEPINFO,
+        REGCLS_MULTIPLEUSE,
+    };
+    use windows::Win32::System::Variant::{VARIANT, VT_BSTR};
+
+    use crate::maintenance;
+
+    const CLSID: GUID = GUID::from_u128(0x6f1c3b2a_8d4e_4a7b_9c1d_2e5f7a9b3c41);
+    const CLSID_KEY: &str = "{6F1C3B2A-8D4E-4A7B-9C1D-2E5F7A9B3C41}";
+    const PROG_ID: &str = "TswIo.Application";
+    /// Methods of the object, their dispatch ids start at 1
+    const METHODS: [&str; 4] = ["Activate", "OpenDocument", "Quit", "Backup"];
+    const DISPID_UNKNOWN: i32 = -1;
+
+    static HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+    #[implement(IDispatch)]
+    struct Application;
+
+    impl IDispatch_Impl for Application_Impl {
+        fn GetTypeInfoCount(&self) -> windows::core::Result<u32> {
+            Ok(0)
+        }
+
+        fn GetTypeInfo(&self, _itinfo: u32, _lcid: u32) -> windows::core::Result<ITypeInfo> {
+            Err(E_NOTIMPL.into())
+        }
+
+        fn GetIDsOfNames(
+            &self,
+            _riid: *const GUID,
+            names: *const PCWSTR,
+            count: u32,
+            _lcid: u32,
+            ids: *mut i32,
+        ) -> windows::core::Result<()> {
+            let mut all_known = true;
+            for i in 0..count as usize {
+                let name = unsafe { (*names.add(i)).to_string() }.unwrap_or_default();
+                let id = METHODS
+                    .iter()
+                    .position(|method| method.eq_ignore_ascii_case(&name))
+                    .map_or(DISPID_UNKNOWN, |index| index as i32 + 1);
+                all_known &= id != DISPID_UNKNOWN;
+                unsafe { *ids.add(i) = id };
+            }
+            if all_known {
+                Ok(())
+            } else {
+                Err(DISP_E_UNKNOWNNAME.into())
+            }
+        }
+
+        fn Invoke(
+            &self,
+            id: i32,
+            _riid: *const GUID,
+            _lcid: u32,
+            _flags: DISPATCH_FLAGS,
+            params: *const DISPPARAMS,
+            result: *mut VARIANT,
+            exception: *mut EXCEPINFO,
+            _arg_error: *mut u32,
+        ) -> windows::core::Result<()> {
+            let Some(handle) = HANDLE.get() else {
+                return Err(E_FAIL.into());
+            };
+            let argument = unsafe { string_argument(params) }?;
+            let outcome = match id {
+                1 => {
+                    crate::open_main_window(handle);
+                    Ok(None)
+                }
+                2 => match argument {
+                    Some(path) => super::open_document(handle, &PathBuf::from(path)).map(|_| None),
+                    None => Err("OpenDocument needs the path of a file".to_string()),
+                },
+                3 => {
+                    tracing::info!("Quitting on request of an automation client");
+                    handle.exit(0);
+                    Ok(None)
+                }
+                4 => maintenance::backup(handle, argument.map(PathBuf::from))
+                    .map(|path| Some(path.to_string_lossy().into_owned())),
+                _ => return Err(DISP_E_MEMBERNOTFOUND.into()),
+            };
+
+            match outcome {
+                Ok(value) => {
+                    if let (Some(value), false) = (value, result.is_null()) {
+                        unsafe { *result = bstr_variant(&value) };
+                    }
+                    Ok(())
+                }
+                Err(e) => {
+                    if !exception.is_null() {
+                        unsafe {
+                            *exception = EXCEPINFO {
+                                bstrSource: ManuallyDrop::new(BSTR::from(PROG_ID)),
+                                bstrDescription: ManuallyDrop::new(BSTR::from(e.as_str())),
+                                scode: E_FAIL.0,
+                                ..Default::default()
+                            }
+                        };
+                    }
+                    Err(DISP_E_EXCEPTION.into())
+                }
+            }
+        }
+    }
+
+    /// The first argument, every method takes at most one string
+    unsafe fn string_argument(params: *const DISPPARAMS) -> windows::core::Result<Option<String>> {
+        let Some(params) = params.as_ref().filter(|params| params.cArgs > 0) else {
+            return Ok(None);
+        };
+        // Arguments come last to first
+        let variant = &*params.rgvarg.add(params.cArgs as usize - 1);
+        if variant.Anonymous.Anonymous.vt != VT_BSTR {
+            return Err(DISP_E_TYPEMISMATCH.into());
+        }
+        Ok(Some(variant.Anonymous.Anonymous.Anonymous.bstrVal.to_string()))
+    }
+
+    fn bstr_variant(value: &str) -> VARIANT {
+        let mut variant = VARIANT::default();
+        unsafe {
+            let inner = &mut *variant.Anonymous.Anonymous;
+            inner.vt = VT_BSTR;
+            inner.Anonymous.bstrVal = ManuallyDrop::new(BSTR::from(value));
+        }
+        variant
+    }
+
+    #[implement(IClassFactory)]
+    struct Factory;
+
+    impl IClassFactory_Impl for Factory_Impl {
+        fn CreateInstance(
+            &self,
+            outer: Ref<'_, IUnknown>,
+            riid: *const GUID,
+            object: *mut *mut c_void,
+        ) -> windows::core::Result<()> {
+            if !outer.is_null() {
+                return Err(CLASS_E_NOAGGREGATION.into());
+            }
+            let application: IDispatch = Application.into();
+            unsafe { application.query(riid, object).ok() }
+        }
+
+        fn LockServer(&self, _lock: BOOL) -> windows::core::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Point `TswIo.Application` at this executable for the current user
+    fn register_class() -> Result<(), String> {
+        use winreg::enums::HKEY_CURRENT_USER;
+
+        let exe = std::env::current_exe().map_err(|e| format!("Failed to find the app executable: {}", e))?;
+        let classes = winreg::RegKey::predef(HKEY_CURRENT_USER);
+        let set = |path: String, value: String| {
+            classes
+                .create_subkey(format!(r"Software\Classes\{}", path))
+                .and_then(|(key, _)| key.set_value("", &value))
+                .map_err(|e| format!("Failed to write HKCU\\Software\\Classes\\{}: {}", path, e))
+        };
+        set(PROG_ID.to_string(), "TSW IO".to_string())?;
+        set(format!(r"{}\CLSID", PROG_ID), CLSID_KEY.to_string())?;
+        set(format!(r"CLSID\{}", CLSID_KEY), "TSW IO".to_string())?;
+        set(format!(r"CLSID\{}\ProgID", CLSID_KEY), PROG_ID.to_string())?;
+        set(format!(r"CLSID\{}\LocalServer32", CLSID_KEY), format!("\"{}\"", exe.display()))
+    }
+
+    pub fn start(handle: &AppHandle) {
+        let _ = HANDLE.set(handle.clone());
+        if let Err(e) = register_class() {
+            tracing::warn!("{}", e);
+            return;
+        }
+        // Calls arrive on COM's own threads, this one only keeps the registration alive
+        std::thread::spawn(|| {
+            let factory: IClassFactory = Factory.into();
+            let registered = unsafe {
+                CoInitializeEx(None, COINIT_MULTITHREADED)
+                    .ok()
+                    .and_then(|_| CoRegisterClassObject(&CLSID, &factory, CLSCTX_LOCAL_SERVER, REGCLS_MULTIPLEUSE))
+            };
+            match registered {
+                Ok(_) => loop {
+                    std::thread::park();
+                },
+                Err(e) => tracing::warn!("Failed to register the automation object: {}", e),
+            }
+        });
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+mod platform {
+    use tauri::AppHandle;
+
+    pub fn start(_handle: &AppHandle) {}
+}
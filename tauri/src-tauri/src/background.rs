@@ -0,0 +1,35 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager};
+
+/// Launched with `--background`, as at login: only the tray icon shows until the user opens the app
+pub struct Background {
+    started: bool,
+    /// No window was asked for yet, so none gets built
+    deferred: AtomicBool,
+}
+
+impl Background {
+    pub fn new(started: bool) -> Self {
+        Background {
+            started,
+            deferred: AtomicBool::new(started),
+        }
+    }
+}
+
+/// Whether the app keeps running in the tray when its last window closes
+pub fn started(handle: &AppHandle) -> bool {
+    handle.try_state::<Background>().is_some_and(|background| background.started)
+}
+
+/// Whether launching the backend should skip the splash screen and the main window
+pub fn is_deferred(handle: &AppHandle) -> bool {
+    handle.state::<Background>().deferred.load(Ordering::SeqCst)
+}
+
+/// Build windows as usual from now on
+pub fn leave(handle: &AppHandle) {
+    if handle.state::<Background>().deferred.swap(false, Ordering::SeqCst) {
+        tracing::info!("Leaving background mode");
+    }
+}
@@ -0,0 +1,136 @@
+use serde_json::Value;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::api;
+use crate::config::{BadgeConfig, BadgeSource, ConfigState};
+use crate::profile::ProfileState;
+use crate::supervisor::{self, SupervisorState};
+
+/// Polling more often than this only keeps the machine awake
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How often a change of strategy or power source is looked for while polling is off
+const IDLE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Show `count` on the app icon, `None` clears it
+fn set(handle: &AppHandle, count: Option<i64>) {
+    let Some(window) = handle.get_webview_window("main") else {
+        return;
+    };
+    if let Err(e) = window.set_badge_count(count.filter(|count| *count > 0)) {
+        tracing::debug!("Failed to set badge: {}", e);
+    }
+}
+
+/// Whether the badge is left as it is for now to save power or data
+fn paused(config: &BadgeConfig) -> bool {
+    (config.pause_on_battery && platform::on_battery()) || (config.pause_on_metered && platform::metered())
+}
+
+/// A `badge` event from the backend, used only when the badge follows the bridge
+pub fn pushed(handle: &AppHandle, payload: &Value) {
+    let config = handle.state::<ConfigState>().get().badge;
+    if config.source != BadgeSource::Bridge || paused(&config) {
+        return;
+    }
+    set(handle, payload["count"].as_i64());
+}
+
+/// Poll the badge count while the config asks for it, see `BadgeConfig`
+pub fn start(handle: AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_source = None;
+        loop {
+            let config = handle.state::<ConfigState>().get().badge;
+            if last_source != Some(config.source) && config.source == BadgeSource::Off {
+                set(&handle, None);
+            }
+            last_source = Some(config.source);
+
+            if config.source != BadgeSource::Polling {
+                std::thread::sleep(IDLE_INTERVAL);
+                continue;
+            }
+            if supervisor::state(&handle) == SupervisorState::Running && !paused(&config) {
+                let base_url = handle.state::<ProfileState>().get().base_url();
+                match api::badge_count(&base_url) {
+                    Ok(count) => set(&handle, Some(count)),
+                    Err(e) => tracing::debug!("Failed to poll the badge count: {}", e),
+                }
+            }
+            std::thread::sleep(Duration::from_secs(config.poll_interval_secs).max(MIN_POLL_INTERVAL));
+        }
+    });
+}
+
+/// `pmset` tells which power source is in use, there's no telling whether a network is metered
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::process::Command;
+
+    pub fn on_battery() -> bool {
+        Command::new("pmset")
+            .args(["-g", "batt"])
+            .output()
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("'Battery Power'"))
+    }
+
+    pub fn metered() -> bool {
+        false
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use windows::Networking::Connectivity::{NetworkCostType, NetworkInformation};
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    /// What `ACLineStatus` is while unplugged
+    const AC_OFFLINE: u8 = 0;
+
+    pub fn on_battery() -> bool {
+        let mut status = SYSTEM_POWER_STATUS::default();
+        unsafe { GetSystemPowerStatus(&mut status) }.is_ok() && status.ACLineStatus == AC_OFFLINE
+    }
+
+    /// Whether the connection is billed by the amount of data, as Windows' metered setting says
+    pub fn metered() -> bool {
+        NetworkInformation::GetInternetConnectionProfile()
+            .and_then(|profile| profile.GetConnectionCost())
+            .and_then(|cost| cost.NetworkCostType())
+            .is_ok_and(|cost| cost == NetworkCostType::Fixed || cost == NetworkCostType::Variable)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use gtk::gio;
+    use gtk::prelude::*;
+
+    /// Discharging batteries, machines without one are always plugged in
+    pub fn on_battery() -> bool {
+        let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else {
+            return false;
+        };
+        supplies.flatten().any(|supply| {
+            let read = |name: &str| std::fs::read_to_string(supply.path().join(name)).unwrap_or_default();
+            read("type").trim() == "Battery" && read("status").trim() == "Discharging"
+        })
+    }
+
+    /// What NetworkManager says through GLib, other network setups are never metered
+    pub fn metered() -> bool {
+        gio::NetworkMonitor::default().is_network_metered()
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+mod platform {
+    pub fn on_battery() -> bool {
+        false
+    }
+
+    pub fn metered() -> bool {
+        false
+    }
+}
@@ -0,0 +1,41 @@
+use crate::config::BeamConfig;
+
+/// Bytes in a VM word, `+hmax` counts words
+const WORD_SIZE: u64 = 8;
+
+/// Command line flags for the Erlang VM from the tuning config
+pub fn flags(config: &BeamConfig) -> Vec<String> {
+    let mut flags = Vec::new();
+    if let Some(schedulers) = config.schedulers.filter(|count| *count > 0) {
+        flags.push(format!("+S {}:{}", schedulers, schedulers));
+    }
+    if let Some(schedulers) = config.dirty_cpu_schedulers.filter(|count| *count > 0) {
+        flags.push(format!("+SDcpu {}:{}", schedulers, schedulers));
+    }
+    if let Some(schedulers) = config.dirty_io_schedulers.filter(|count| *count > 0) {
+        flags.push(format!("+SDio {}", schedulers));
+    }
+    if let Some(mb) = config.max_process_heap_mb.filter(|mb| *mb > 0) {
+        flags.push(format!("+hmax {} +hmaxk true", mb * 1024 * 1024 / WORD_SIZE));
+    }
+    if config.busy_wait == Some(false) {
+        flags.push("+sbwt none +sbwtdcpu none +sbwtdio none".to_string());
+    }
+    let extra = config.extra_flags.as_deref().map(str::trim);
+    if let Some(extra) = extra.filter(|extra| !extra.is_empty()) {
+        flags.push(extra.to_string());
+    }
+    flags
+}
+
+/// Environment passing the tuning flags to the sidecar's VM
+///
+/// Uses `ERL_FLAGS`, so the `ERL_AFLAGS` of developer mode still apply.
+pub fn sidecar_env(config: &BeamConfig) -> Vec<(&'static str, String)> {
+    let flags = flags(config);
+    if flags.is_empty() {
+        return Vec::new();
+    }
+    tracing::info!(flags = %flags.join(" "), "Tuning the backend VM");
+    vec![("ERL_FLAGS", flags.join(" "))]
+}
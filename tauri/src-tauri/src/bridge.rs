@@ -0,0 +1,230 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+use crate::badge;
+use crate::loopback;
+use crate::notifications;
+use crate::secrets;
+use crate::profile::{Profile, ProfileState};
+use crate::supervisor::{self, SupervisorState};
+use crate::tls;
+use crate::tray::{self, TrayStatus};
+
+/// Channel topic the backend talks to the shell on
+const TOPIC: &str = "shell";
+/// Prefix of the Tauri events backend events are emitted as
+const EVENT_PREFIX: &str = "backend:";
+/// Tauri event webviews send to have its `{ event, payload }` pushed to the backend
+const SEND_EVENT: &str = "bridge:send";
+/// Phoenix closes sockets that stay silent for 60 seconds
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long a read waits before the outbox gets a turn
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Events waiting to be pushed to the backend
+static OUTBOX: Mutex<VecDeque<(String, Value)>> = Mutex::new(VecDeque::new());
+/// Whether the channel is joined, so pushed events have somewhere to go
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+type Socket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+/// Push an event to the backend, it arrives in `TswIo.Shell.subscribe/0`
+pub fn push(event: &str, payload: Value) -> Result<(), String> {
+    if !CONNECTED.load(Ordering::SeqCst) {
+        return Err("Not connected to the backend".to_string());
+    }
+    OUTBOX.lock().unwrap().push_back((event.to_string(), payload));
+    Ok(())
+}
+
+/// What webviews send on `bridge:send`
+#[derive(Debug, Deserialize)]
+struct Outgoing {
+    event: String,
+    #[serde(default)]
+    payload: Value,
+}
+
+/// Keep a channel open to the bundled backend while it runs
+///
+/// Backend events become Tauri events named `backend:<event>`, a few also trigger native
+/// features directly. Webviews push events the other way with `bridge:send`.
+pub fn start(handle: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    handle.listen_any(SEND_EVENT, |event| {
+        let result = serde_json::from_str::<Outgoing>(event.payload())
+            .map_err(|e| format!("Invalid {} payload: {}", SEND_EVENT, e))
+            .and_then(|outgoing| push(&outgoing.event, outgoing.payload));
+        if let Err(e) = result {
+            tracing::debug!("Dropped event for the backend: {}", e);
+        }
+    });
+
+    std::thread::spawn(move || loop {
+        let profile = handle.state::<ProfileState>().get();
+        // An external backend doesn't know the token
+        if profile.backend_url.is_some() || supervisor::state(&handle) != SupervisorState::Running {
+            std::thread::sleep(RECONNECT_DELAY);
+            continue;
+        }
+        if let Err(e) = session(&handle, &profile) {
+            tracing::debug!("Backend event bridge disconnected: {}", e);
+        }
+        CONNECTED.store(false, Ordering::SeqCst);
+        OUTBOX.lock().unwrap().clear();
+        std::thread::sleep(RECONNECT_DELAY);
+    });
+}
+
+fn connect(profile: &Profile) -> Result<Socket, String> {
+    let base_url = profile.base_url();
+    let url = format!(
+        "{}/shell/websocket?vsn=2.0.0&token={}",
+        base_url.replacen("http", "ws", 1),
+        secrets::shell_token()
+    );
+    let address = format!("{}:{}", loopback::BIND_ADDRESS, profile.port)
+        .parse()
+        .map_err(|e| format!("Invalid backend address: {}", e))?;
+    let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+        .map_err(|e| format!("Failed to connect to backend: {}", e))?;
+    stream
+        .set_read_timeout(Some(CONNECT_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    // Kept to shorten the timeout once the handshake is done
+    let raw = stream.try_clone().map_err(|e| e.to_string())?;
+
+    let connector = if base_url.starts_with("https") {
+        tungstenite::Connector::NativeTls(tls::websocket_connector()?)
+    } else {
+        tungstenite::Connector::Plain
+    };
+    let (socket, _) = tungstenite::client_tls_with_config(url, stream, None, Some(connector))
+        .map_err(|e| format!("Failed to open backend WebSocket: {}", e))?;
+    raw.set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+/// Phoenix message in the V2 JSON format: `[join_ref, ref, topic, event, payload]`
+fn send(
+    socket: &mut Socket,
+    join_ref: Option<&str>,
+    message_ref: u64,
+    topic: &str,
+    event: &str,
+    payload: Value,
+) -> Result<(), String> {
+    let message = json!([join_ref, message_ref.to_string(), topic, event, payload]);
+    socket
+        .send(Message::text(message.to_string()))
+        .map_err(|e| format!("Failed to send to backend: {}", e))
+}
+
+/// Join the channel and relay events both ways until the connection drops
+fn session(handle: &AppHandle, profile: &Profile) -> Result<(), String> {
+    const JOIN_REF: &str = "1";
+
+    let mut socket = connect(profile)?;
+    // The join goes first, so its reply carries the join ref
+    let mut message_ref: u64 = 1;
+    send(&mut socket, Some(JOIN_REF), message_ref, TOPIC, "phx_join", json!({}))?;
+    let mut last_heartbeat = Instant::now();
+
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let Ok((_, reply_ref, topic, event, payload)) =
+                    serde_json::from_str::<(Option<String>, Option<String>, String, String, Value)>(&text)
+                else {
+                    tracing::debug!("Ignored malformed backend message");
+                    continue;
+                };
+                match event.as_str() {
+                    "phx_reply" if topic == TOPIC && reply_ref.as_deref() == Some(JOIN_REF) => {
+                        if payload["status"] != "ok" {
+                            return Err(format!("Backend refused the shell channel: {}", payload));
+                        }
+                        if !CONNECTED.swap(true, Ordering::SeqCst) {
+                            tracing::info!("Backend event bridge connected");
+                        }
+                    }
+                    // Replies to pushes are errors the backend already logged
+                    "phx_reply" => {}
+                    "phx_close" | "phx_error" => return Err(format!("Channel {}", event)),
+                    _ if topic == TOPIC => dispatch(handle, &event, payload),
+                    _ => {}
+                }
+            }
+            Ok(Message::Close(_)) => return Err("Backend closed the connection".to_string()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+
+        let outgoing: Vec<_> = OUTBOX.lock().unwrap().drain(..).collect();
+        for (event, payload) in outgoing {
+            message_ref += 1;
+            send(&mut socket, Some(JOIN_REF), message_ref, TOPIC, &event, payload)?;
+        }
+        if last_heartbeat.elapsed() >= HEARTBEAT_INTERVAL {
+            message_ref += 1;
+            send(&mut socket, None, message_ref, "phoenix", "heartbeat", json!({}))?;
+            last_heartbeat = Instant::now();
+        }
+    }
+}
+
+/// Characters Tauri allows in event names
+fn valid_event_name(event: &str) -> bool {
+    !event.is_empty()
+        && event
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '/' | ':' | '_'))
+}
+
+/// Act on an event from the backend and pass it on to the webviews
+fn dispatch(handle: &AppHandle, event: &str, payload: Value) {
+    if !valid_event_name(event) {
+        tracing::warn!(event, "Ignored backend event with an invalid name");
+        return;
+    }
+    tracing::trace!(event, "Backend event");
+
+    match event {
+        "notification" => notifications::show(handle, payload.clone()),
+        "badge" => badge::pushed(handle, &payload),
+        "tray_status" => {
+            match serde_json::from_value::<TrayStatus>(payload["state"].clone()) {
+                Ok(status) => {
+                    if let Err(e) = tray::set_status(handle, status, payload["tooltip"].as_str()) {
+                        tracing::warn!("{}", e);
+                    }
+                }
+                Err(e) => tracing::warn!("Invalid tray status from the backend: {}", e),
+            }
+        }
+        _ => {}
+    }
+
+    let name = format!("{}{}", EVENT_PREFIX, event);
+    if let Err(e) = handle.emit(&name, payload) {
+        tracing::warn!("Failed to emit {}: {}", name, e);
+    }
+}
@@ -0,0 +1,86 @@
+use serde_json::json;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+use crate::api;
+use crate::config::ConfigState;
+use crate::hotkeys;
+use crate::lock;
+use crate::profile::ProfileState;
+
+const WINDOW_LABEL: &str = "capture";
+/// External event the entry reaches the backend as, see `TswIo.ExternalEvents`
+const CAPTURE_EVENT: &str = "capture";
+const CAPTURE_SOURCE: &str = "quick_capture";
+
+/// Summon the capture window with the hotkey in the config, see `capture_hotkey`
+pub fn start(handle: &AppHandle) {
+    let Some(text) = handle.state::<ConfigState>().get().capture_hotkey else {
+        return;
+    };
+    match hotkeys::register(handle, &text, show) {
+        Ok(()) => tracing::info!(hotkey = text, "Registered the capture hotkey"),
+        Err(e) => tracing::warn!("Failed to set up the capture hotkey: {}", e),
+    }
+}
+
+/// Show the capture window over whatever has the focus, leaving the main window where it is
+pub fn show(handle: &AppHandle) {
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        lock::reveal(handle, &window);
+        return;
+    }
+    if !lock::may_show(handle) {
+        return;
+    }
+    let result = WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("capture.html".into()))
+        .title("Quick capture")
+        .inner_size(440.0, 150.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .focused(true)
+        .build();
+    match result {
+        Ok(window) => {
+            let closing = window.clone();
+            // Clicking elsewhere dismisses it, like Spotlight
+            window.on_window_event(move |event| {
+                if let WindowEvent::Focused(false) = event {
+                    let _ = closing.close();
+                }
+            });
+        }
+        Err(e) => tracing::warn!("Failed to open the capture window: {}", e),
+    }
+}
+
+fn close(handle: &AppHandle) {
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+}
+
+/// Post the entry to the backend and dismiss the window
+#[tauri::command]
+pub async fn submit_capture(app: AppHandle, text: String) -> Result<(), String> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("Type something to capture".to_string());
+    }
+    let base_url = app.state::<ProfileState>().get().base_url();
+    let sent = tauri::async_runtime::spawn_blocking(move || {
+        api::forward_event(&base_url, CAPTURE_EVENT, CAPTURE_SOURCE, &json!({ "text": text }))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    sent?;
+    close(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn dismiss_capture(app: AppHandle) {
+    close(&app);
+}
@@ -0,0 +1,241 @@
+use std::path::PathBuf;
+
+use crate::recent;
+
+/// Command line flags understood by the shell
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    /// Profile selected with `--env <name>` (or `TSW_IO_ENV`)
+    pub profile: Option<String>,
+    pub config_dir: Option<PathBuf>,
+    pub data_dir: Option<PathBuf>,
+    pub log_dir: Option<PathBuf>,
+    /// `--self-test`: check the installation, print a report and exit
+    pub self_test: bool,
+    /// `--version`: print the shell's and the bundled backend's versions and exit
+    pub version: bool,
+    /// `--print-config`: print the resolved configuration, paths and profile and exit
+    pub print_config: bool,
+    /// `--background`: start the backend with only the tray icon, as autostart does
+    pub background: bool,
+    /// `--headless`: start the backend without any window, for CI and end-to-end tests
+    pub headless: bool,
+    /// `--mock-backend[=<scenario>]`: talk to a stub server in the shell instead of the Elixir backend
+    pub mock_backend: Option<String>,
+    /// `--dev-backend[=<port>]`: attach to a running `mix phx.server` instead of the sidecar
+    pub dev_backend: Option<String>,
+    /// Subcommand run from a terminal, see `companion`
+    pub command: Option<Command>,
+    /// Files and links the app was opened with, when there is no subcommand
+    pub targets: Vec<Target>,
+}
+
+/// Scheme of links to backend pages, `tsw-io://trains/1` opens `/trains/1`
+pub const LINK_SCHEME: &str = "tsw-io";
+
+/// Something the OS asked the app to open
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// A backend page, from a `tsw-io://` link
+    Route(String),
+    /// A file, handed to the backend to import
+    Document(PathBuf),
+    /// The end of a single sign-on in the system browser, the query of its link, see `sso`
+    SsoCallback(String),
+}
+
+/// What a `tsw-io://` link opens, `None` for anything else
+pub fn link_target(link: &str) -> Option<Target> {
+    let rest = link.strip_prefix(LINK_SCHEME)?.strip_prefix("://")?;
+    if let Some(query) = rest.strip_prefix("sso/callback") {
+        return Some(Target::SsoCallback(query.trim_start_matches('?').to_string()));
+    }
+    Some(Target::Route(format!("/{}", rest.trim_start_matches('/'))))
+}
+
+/// Links and existing files in `words`, anything else is left alone
+fn targets(words: &[String]) -> Vec<Target> {
+    words
+        .iter()
+        .filter_map(|word| {
+            if let Some(target) = link_target(word) {
+                return Some(target);
+            }
+            let path = PathBuf::from(word);
+            if let Some(route) = recent::link_route(&path) {
+                return Some(Target::Route(route));
+            }
+            // Relative to where the app was started, the running app has its own directory
+            path.is_file()
+                .then(|| Target::Document(std::path::absolute(&path).unwrap_or(path)))
+        })
+        .collect()
+}
+
+/// Subcommands that manage the backend from a terminal, without the GUI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// `backend start`: run the backend without windows until it is stopped
+    BackendStart,
+    /// `backend stop`: quit the running app along with its backend
+    BackendStop,
+    /// `backend status`: whether the app runs and how to reach its backend
+    BackendStatus,
+    /// `backup <path>`: copy the database to `path`
+    Backup(PathBuf),
+    /// `doctor`: check the setup and the running app
+    Doctor,
+    /// `capture`: show the quick capture window of the running app
+    Capture,
+    /// `palette`: show the command palette of the running app
+    Palette,
+    /// `service install`: run the backend as a service of the user's session
+    ServiceInstall,
+    /// `service uninstall`: stop running the backend as a service, `service stop` stops it
+    ServiceUninstall,
+    /// `service start`: start the installed service now instead of at the next login
+    ServiceStart,
+}
+
+/// The subcommand in `words`, `None` if they aren't one (the OS passes files and links too)
+fn command(words: &[String]) -> Option<Command> {
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    match words.as_slice() {
+        ["backend", "start"] => Some(Command::BackendStart),
+        ["backend", "stop"] => Some(Command::BackendStop),
+        ["backend", "status"] => Some(Command::BackendStatus),
+        ["backup", path] => Some(Command::Backup(PathBuf::from(path))),
+        ["doctor"] => Some(Command::Doctor),
+        ["capture"] => Some(Command::Capture),
+        ["palette"] => Some(Command::Palette),
+        ["service", "install"] => Some(Command::ServiceInstall),
+        ["service", "uninstall"] => Some(Command::ServiceUninstall),
+        ["service", "start"] => Some(Command::ServiceStart),
+        ["service", "stop"] => Some(Command::BackendStop),
+        _ => None,
+    }
+}
+
+/// Parse the process arguments, ignoring anything unknown (the OS can add its own)
+pub fn parse() -> CliArgs {
+    parse_from(std::env::args().skip(1), std::env::var("TSW_IO_ENV").ok())
+}
+
+/// `parse` for `arguments` without the program name, `env_profile` being `TSW_IO_ENV`
+fn parse_from(arguments: impl IntoIterator<Item = String>, env_profile: Option<String>) -> CliArgs {
+    let mut args = CliArgs {
+        profile: env_profile.filter(|v| !v.is_empty()),
+        ..CliArgs::default()
+    };
+
+    let mut words = Vec::new();
+    let mut iter = arguments.into_iter();
+    while let Some(arg) = iter.next() {
+        if !arg.starts_with('-') {
+            words.push(arg);
+            continue;
+        }
+        // Accept both `--flag value` and `--flag=value`
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        let mut value = || inline_value.clone().or_else(|| iter.next());
+
+        match flag.as_str() {
+            "--env" => args.profile = value(),
+            "--config-dir" => args.config_dir = value().map(PathBuf::from),
+            "--data-dir" => args.data_dir = value().map(PathBuf::from),
+            "--log-dir" => args.log_dir = value().map(PathBuf::from),
+            "--self-test" => args.self_test = true,
+            "--version" => args.version = true,
+            "--print-config" => args.print_config = true,
+            "--background" => args.background = true,
+            "--headless" => args.headless = true,
+            // Only inline, so these flags alone don't swallow the next argument
+            "--mock-backend" => args.mock_backend = Some(inline_value.clone().unwrap_or_default()),
+            "--dev-backend" => args.dev_backend = Some(inline_value.clone().unwrap_or_default()),
+            _ => {}
+        }
+    }
+    args.command = command(&words);
+    if args.command.is_none() {
+        args.targets = targets(&words);
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_words(words: &[&str]) -> CliArgs {
+        parse_from(words.iter().map(|word| word.to_string()), None)
+    }
+
+    #[test]
+    fn reads_flags_with_and_without_equals() {
+        let args = parse_words(&["--env", "staging", "--data-dir=/tmp/data", "--headless"]);
+
+        assert_eq!(args.profile.as_deref(), Some("staging"));
+        assert_eq!(args.data_dir, Some(PathBuf::from("/tmp/data")));
+        assert!(args.headless);
+        assert!(!args.background);
+    }
+
+    #[test]
+    fn takes_the_profile_from_the_environment_unless_given() {
+        let words = || vec!["--version".to_string()];
+
+        assert_eq!(parse_from(words(), Some("dev".into())).profile.as_deref(), Some("dev"));
+        assert_eq!(parse_from(words(), Some(String::new())).profile, None);
+        let args = parse_from(vec!["--env=prod".to_string()], Some("dev".into()));
+        assert_eq!(args.profile.as_deref(), Some("prod"));
+    }
+
+    #[test]
+    fn backend_flags_only_take_inline_values() {
+        let args = parse_words(&["--mock-backend", "backend", "status"]);
+
+        assert_eq!(args.mock_backend.as_deref(), Some(""));
+        assert_eq!(args.command, Some(Command::BackendStatus));
+        assert_eq!(parse_words(&["--dev-backend=4001"]).dev_backend.as_deref(), Some("4001"));
+    }
+
+    #[test]
+    fn ignores_unknown_flags() {
+        let args = parse_words(&["-psn_0_12345", "--unknown", "doctor"]);
+
+        assert_eq!(args.command, Some(Command::Doctor));
+    }
+
+    #[test]
+    fn reads_subcommands() {
+        assert_eq!(
+            parse_words(&["backup", "out.db"]).command,
+            Some(Command::Backup(PathBuf::from("out.db")))
+        );
+        assert_eq!(parse_words(&["service", "stop"]).command, Some(Command::BackendStop));
+        assert_eq!(parse_words(&["service"]).command, None);
+        assert_eq!(parse_words(&["backend", "start", "now"]).command, None);
+    }
+
+    #[test]
+    fn opens_links() {
+        let args = parse_words(&["tsw-io://trains/1"]);
+
+        assert_eq!(args.command, None);
+        assert_eq!(args.targets, vec![Target::Route("/trains/1".to_string())]);
+        assert_eq!(link_target("tsw-io:///"), Some(Target::Route("/".to_string())));
+        assert_eq!(
+            link_target("tsw-io://sso/callback?code=abc&state=def"),
+            Some(Target::SsoCallback("code=abc&state=def".to_string()))
+        );
+        assert_eq!(link_target("https://example.com"), None);
+    }
+
+    #[test]
+    fn leaves_missing_files_alone() {
+        assert!(parse_words(&["no-such-file.csv"]).targets.is_empty());
+    }
+}
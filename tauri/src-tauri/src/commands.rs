@@ -0,0 +1,279 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::api;
+use crate::config::{self, ConfigState, NetworkConfig, ShellConfig};
+use crate::downloads::{self, DownloadKind};
+use crate::logging::{self, LogLevel};
+use crate::paths::AppPaths;
+use crate::profile::ProfileState;
+use crate::secrets;
+use crate::timing::{self, StartupTimings};
+use crate::tray;
+use crate::upgrade;
+
+/// Folders the user picked this run, by the handle the page was given for them
+///
+/// Watching a folder takes a handle, so pages can't have the shell read any other.
+static PICKED: Mutex<Option<HashMap<String, PathBuf>>> = Mutex::new(None);
+
+/// A folder the user picked, as the page sees it
+#[derive(Debug, Clone, Serialize)]
+pub struct PickedFolder {
+    /// What to pass `add_watched_folder`
+    pub handle: String,
+    /// Where the backend finds it, as for an export target
+    pub path: PathBuf,
+    pub name: String,
+}
+
+/// The folder picked as `handle`
+pub(crate) fn picked_folder(handle: &str) -> Option<PathBuf> {
+    PICKED
+        .lock()
+        .ok()?
+        .as_ref()
+        .and_then(|picked| picked.get(handle).cloned())
+}
+
+/// Where and what the app runs as, for the backend UI to show
+#[derive(Debug, Serialize)]
+pub struct AppInfo {
+    /// Shell version
+    pub version: &'static str,
+    /// Version of the backend the shell runs, bundled or upgraded, an external one may differ
+    pub backend_version: String,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub profile: String,
+    pub port: u16,
+    pub data_dir: PathBuf,
+    pub log_dir: PathBuf,
+    pub config_dir: PathBuf,
+    /// How long the current launch took to reach each stage
+    pub startup: StartupTimings,
+}
+
+/// Switch the shell's and the backend's log level without restarting
+pub(crate) fn apply_log_level(handle: &AppHandle, level: LogLevel) -> Result<(), String> {
+    logging::set_level(level);
+    handle.state::<ConfigState>().0.write().unwrap().log_level = level;
+    let base_url = handle.state::<ProfileState>().get().base_url();
+    tray::sync_log_level(handle, level);
+    tracing::info!(level = level.as_str(), "Log level changed");
+
+    api::set_backend_log_level(&base_url, level)
+}
+
+#[tauri::command]
+pub fn get_app_info(app: AppHandle) -> AppInfo {
+    let profile = app.state::<ProfileState>().get();
+    let app_paths = app.state::<AppPaths>();
+    AppInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        backend_version: upgrade::backend_version(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        profile: profile.name,
+        port: profile.port,
+        data_dir: profile.data_dir,
+        log_dir: app_paths.log_dir.clone(),
+        config_dir: app_paths.config_dir.clone(),
+        startup: timing::timings(&app),
+    }
+}
+
+#[tauri::command]
+pub fn get_log_level() -> LogLevel {
+    logging::level()
+}
+
+#[tauri::command]
+pub async fn set_log_level(app: AppHandle, level: LogLevel) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || apply_log_level(&app, level))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Save the shell settings to a user-chosen file, returning where they went
+#[tauri::command]
+pub async fn export_settings(app: AppHandle) -> Result<Option<PathBuf>, String> {
+    let path = match app
+        .dialog()
+        .file()
+        .set_title("Export settings")
+        .set_file_name("tsw_io-settings.toml")
+        .add_filter("Settings", &["toml"])
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+    {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    export_settings_to(&app, &path)?;
+    Ok(Some(path))
+}
+
+/// Save the shell settings to `path`, without machine-specific paths, credentials or accounts
+pub(crate) fn export_settings_to(handle: &AppHandle, path: &Path) -> Result<(), String> {
+    let current = config::load(&config::config_path(handle))?;
+    config::save(path, &current.exportable())?;
+    tracing::info!("Exported settings to {}", path.display());
+    downloads::record(handle, DownloadKind::Export, path);
+    Ok(())
+}
+
+/// Replace the shell settings with ones exported from another machine
+#[tauri::command]
+pub async fn import_settings(app: AppHandle) -> Result<bool, String> {
+    let path = match app
+        .dialog()
+        .file()
+        .set_title("Import settings")
+        .add_filter("Settings", &["toml"])
+        .blocking_pick_file()
+        .and_then(|path| path.into_path().ok())
+    {
+        Some(path) => path,
+        None => return Ok(false),
+    };
+
+    let imported = config::load(&path)?;
+    let config_path = config::config_path(&app);
+    let current = config::load(&config_path)?;
+
+    // Local paths, accounts and sign-in stay as they are, the config watcher applies the rest
+    let mut profiles = imported.profiles;
+    for (name, overrides) in profiles.iter_mut() {
+        overrides.data_dir = current
+            .profiles
+            .get(name)
+            .and_then(|local| local.data_dir.clone());
+    }
+    // The same proxy as the local one keeps the local user name and password
+    let http_proxy = if imported.network.http_proxy == current.network.shareable_proxy() {
+        current.network.http_proxy
+    } else {
+        imported.network.http_proxy
+    };
+    let merged = ShellConfig {
+        data_dir: current.data_dir,
+        log_dir: current.log_dir,
+        watched_folders: current.watched_folders,
+        network: NetworkConfig {
+            http_proxy,
+            ca_certificates: current.network.ca_certificates,
+            ..imported.network
+        },
+        accounts: current.accounts,
+        active_account: current.active_account,
+        sso: current.sso,
+        profiles,
+        ..imported
+    };
+    config::save(&config_path, &merged)?;
+    tracing::info!("Imported settings from {}", path.display());
+    Ok(true)
+}
+
+/// Whether the resolved `path` is in, or contains, the data, config or log directory
+pub(crate) fn holds_own_files(app: &AppHandle, path: &Path) -> bool {
+    let app_paths = app.state::<AppPaths>();
+    let own = [
+        app.state::<ProfileState>().get().data_dir,
+        app_paths.config_dir.clone(),
+        app_paths.log_dir.clone(),
+    ];
+    own.iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .any(|dir| path.starts_with(&dir) || dir.starts_with(path))
+}
+
+/// Show a file in the OS file manager, if it is in the data or the downloads directory
+///
+/// The backend UI asks for exported files and backups, nothing outside those can be revealed.
+#[tauri::command]
+pub fn reveal_path(app: AppHandle, path: PathBuf) -> Result<(), String> {
+    // Resolving links and `..` first, so the path can't point out of an allowed directory
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to find {}: {}", path.display(), e))?;
+    let allowed = [
+        Some(app.state::<ProfileState>().get().data_dir),
+        app.path().download_dir().ok(),
+    ];
+    let revealable = allowed
+        .into_iter()
+        .flatten()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .any(|dir| path.starts_with(dir));
+    if !revealable {
+        tracing::warn!(path = %path.display(), "Refused to reveal a path outside the app's directories");
+        return Err(format!("{} can't be shown", path.display()));
+    }
+
+    tauri_plugin_opener::reveal_item_in_dir(&path)
+        .map_err(|e| format!("Failed to reveal {}: {}", path.display(), e))
+}
+
+/// Let the user choose a folder for the backend, like a watched directory or an export target
+///
+/// Returns `None` when the user cancelled, or a handle to watch the folder with. The app's
+/// own directories are refused, and with `writable` so is any folder the app can't write to.
+#[tauri::command]
+pub async fn pick_folder(
+    app: AppHandle,
+    title: Option<String>,
+    writable: bool,
+) -> Result<Option<PickedFolder>, String> {
+    let Some(path) = app
+        .dialog()
+        .file()
+        .set_title(title.as_deref().unwrap_or("Choose a folder"))
+        .blocking_pick_folder()
+        .and_then(|path| path.into_path().ok())
+    else {
+        return Ok(None);
+    };
+
+    // Compared resolved, but handed back as picked: Windows resolves to `\\?\` paths
+    let resolved = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    if !resolved.is_dir() {
+        return Err(format!("{} is not a folder", path.display()));
+    }
+
+    // The backend must not read or write the files the app manages itself
+    if holds_own_files(&app, &resolved) {
+        return Err(format!("{} holds TSW IO's own files, pick another folder", path.display()));
+    }
+
+    if writable {
+        let probe = path.join(".tsw_io-write-test");
+        std::fs::write(&probe, b"")
+            .map_err(|e| format!("Can't write to {}: {}", path.display(), e))?;
+        let _ = std::fs::remove_file(&probe);
+    }
+
+    tracing::info!(path = %path.display(), "Folder picked");
+    let picked = PickedFolder {
+        handle: secrets::random_hex(16)?,
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string()),
+        path,
+    };
+    PICKED
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get_or_insert_with(HashMap::new)
+        .insert(picked.handle.clone(), picked.path.clone());
+    Ok(Some(picked))
+}
@@ -0,0 +1,439 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::cli::{CliArgs, Command, Target};
+use crate::automation;
+use crate::config::{self, ShellConfig};
+use crate::control;
+use crate::maintenance;
+use crate::paths::{self, AppPaths};
+use crate::policy;
+use crate::profile::{self, Profile};
+use crate::selftest::{self, SelfTestReport};
+use crate::service;
+use crate::sso;
+
+/// Status calls only wait this long before the app counts as not running
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+/// As long as the shell waits for the backend to back up a large database
+const BACKUP_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// How long `backend stop` waits for the app to be gone
+const STOP_TIMEOUT: Duration = Duration::from_secs(15);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Exit status of `backend status` while nothing runs, as for init scripts
+const NOT_RUNNING: i32 = 3;
+
+/// Backup for the app started by `backup` to make once its backend is ready
+static PENDING_BACKUP: Mutex<Option<PathBuf>> = Mutex::new(None);
+/// Files and links the app was started with, opened once its backend is ready
+static PENDING_TARGETS: Mutex<Vec<Target>> = Mutex::new(Vec::new());
+
+/// Where the running app's control endpoint listens, from `control.json`
+#[derive(Debug, Deserialize)]
+struct Endpoint {
+    url: String,
+    token: String,
+    /// Left out by versions before the backend service
+    #[serde(default)]
+    shell_token: Option<String>,
+}
+
+/// The app running with the same config directory, reached over its control endpoint
+struct Running {
+    endpoint: Endpoint,
+    status: Value,
+}
+
+impl Running {
+    /// The running app, `None` when there is none or it doesn't answer
+    fn find(config_dir: &Path) -> Option<Running> {
+        let contents = std::fs::read_to_string(config_dir.join(control::CONTROL_FILE_NAME)).ok()?;
+        let endpoint: Endpoint = serde_json::from_str(&contents).ok()?;
+        // Left behind by an app that didn't quit cleanly if nothing answers
+        let status = request(&endpoint, reqwest::Method::GET, "/status", STATUS_TIMEOUT).ok()?;
+        Some(Running { endpoint, status })
+    }
+
+    fn field(&self, name: &str) -> String {
+        match &self.status[name] {
+            Value::String(value) => value.clone(),
+            Value::Null => "-".to_string(),
+            value => value.to_string(),
+        }
+    }
+}
+
+/// A backend another tsw_io process runs without windows, for the app to use instead of its own
+#[derive(Debug, Clone)]
+pub struct RunningBackend {
+    pub url: String,
+    pub shell_token: Option<String>,
+    pub version: String,
+    pub backend_version: String,
+}
+
+/// The backend of `tsw_io backend start` with the same config directory
+///
+/// `None` for an app with windows, as its backend goes away once it is closed.
+pub fn running_backend(config_dir: &Path) -> Option<RunningBackend> {
+    let running = Running::find(config_dir)?;
+    if running.status["headless"] != Value::Bool(true) {
+        return None;
+    }
+    Some(RunningBackend {
+        url: running.status["backend_url"].as_str()?.to_string(),
+        version: running.status["app"]["version"].as_str()?.to_string(),
+        backend_version: running.status["app"]["backend_version"].as_str()?.to_string(),
+        shell_token: running.endpoint.shell_token,
+    })
+}
+
+/// Quit the app with the same config directory, as `backend stop`
+pub fn stop_running(config_dir: &Path) -> Result<(), String> {
+    stop(Running::find(config_dir))
+}
+
+fn request(endpoint: &Endpoint, method: reqwest::Method, path: &str, timeout: Duration) -> Result<Value, String> {
+    let response = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| format!("Failed to reach tsw_io: {}", e))?
+        .request(method, format!("{}{}", endpoint.url, path))
+        .bearer_auth(&endpoint.token)
+        .send()
+        .map_err(|e| format!("Failed to reach tsw_io: {}", e))?;
+    let status = response.status();
+    let body: Value = response
+        .json()
+        .map_err(|e| format!("Failed to read the answer of tsw_io: {}", e))?;
+    if !status.is_success() {
+        let error = body["error"].as_str().unwrap_or_default();
+        return Err(format!("tsw_io refused: {} ({})", error, status));
+    }
+    Ok(body)
+}
+
+/// Run the subcommand or `--version`/`--print-config` in `args` if it doesn't need the app
+/// itself, returning the exit status
+///
+/// Files and links go to the app already running with windows, see `forward`.
+/// `backend start`, and `backup` with nothing running, return `None`: the app then starts
+/// without windows, with the same setup and supervisor as the GUI. It stays up for
+/// `backend start` until stopped, and quits once the backup is written for `backup`.
+pub fn run(args: &CliArgs) -> Option<i32> {
+    if args.version || args.print_config {
+        platform::attach_console();
+        if args.version {
+            println!("tsw_io {} (backend {})", env!("CARGO_PKG_VERSION"), env!("TSW_IO_BACKEND_VERSION"));
+            return Some(0);
+        }
+        return Some(match print_config(args) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("{}", e);
+                1
+            }
+        });
+    }
+    let Some(command) = &args.command else {
+        return forward(args);
+    };
+    platform::attach_console();
+    let config_dir = paths::config_dir(args);
+    let running = Running::find(&config_dir);
+
+    let result = match (command, running) {
+        (Command::BackendStart, Some(running)) => {
+            println!("tsw_io is already running, its backend at {}", running.field("backend_url"));
+            Ok(())
+        }
+        (Command::BackendStart, None) => return None,
+        (Command::Backup(path), None) => {
+            *PENDING_BACKUP.lock().unwrap() = Some(absolute(path));
+            return None;
+        }
+        (Command::Backup(path), Some(running)) => backup(&running, &absolute(path)),
+        (Command::BackendStop, running) => stop(running),
+        (Command::BackendStatus, running) => return Some(status(running)),
+        (Command::Doctor, running) => return Some(doctor(args, running)),
+        (Command::Capture, running) => show_window(running, "/capture"),
+        (Command::Palette, running) => show_window(running, "/palette"),
+        (Command::ServiceInstall, _) => install_service(args),
+        (Command::ServiceUninstall, _) => service::uninstall(),
+        (Command::ServiceStart, Some(_)) => {
+            println!("tsw_io is already running");
+            Ok(())
+        }
+        (Command::ServiceStart, None) => service::start(),
+    };
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
+
+/// Hand the files and links to the app already running with windows, if there is one
+///
+/// Only an app with the control endpoint on can be found. Without one, the app starts and
+/// opens them itself once its backend is ready.
+fn forward(args: &CliArgs) -> Option<i32> {
+    if args.targets.is_empty() {
+        return None;
+    }
+    // A backend without windows has nowhere to show them
+    let running = Running::find(&paths::config_dir(args))
+        .filter(|running| running.status["headless"] != Value::Bool(true));
+    let Some(running) = running else {
+        PENDING_TARGETS.lock().unwrap().extend(args.targets.iter().cloned());
+        return None;
+    };
+    let mut code = 0;
+    for target in &args.targets {
+        let path = match target {
+            Target::Route(route) => format!("/open-route?route={}", urlencoding::encode(route)),
+            Target::Document(path) => {
+                format!("/open-document?path={}", urlencoding::encode(&path.to_string_lossy()))
+            }
+            Target::SsoCallback(query) => format!("/sso-callback?query={}", urlencoding::encode(query)),
+        };
+        if let Err(e) = request(&running.endpoint, reqwest::Method::POST, &path, STATUS_TIMEOUT) {
+            eprintln!("Failed to hand {:?} to the running tsw_io: {}", target, e);
+            code = 1;
+        }
+    }
+    Some(code)
+}
+
+fn open_now(handle: &AppHandle, target: &Target) {
+    let opened = match target {
+        Target::Route(route) => control::open_route(handle, route),
+        Target::Document(path) => automation::open_document(handle, path),
+        Target::SsoCallback(query) => {
+            sso::complete(handle, query.clone());
+            Ok(())
+        }
+    };
+    if let Err(e) = opened {
+        tracing::warn!("Failed to open {:?}: {}", target, e);
+    }
+}
+
+/// Open `target` from the OS, right away or once the backend is ready if it is still starting
+#[cfg(target_os = "macos")]
+pub fn open(handle: &AppHandle, target: Target) {
+    use crate::supervisor::{self, SupervisorState};
+
+    if supervisor::state(handle) != SupervisorState::Running {
+        PENDING_TARGETS.lock().unwrap().push(target);
+        return;
+    }
+    open_now(handle, &target);
+}
+
+/// Open what the app was started with, and make the backup `backup` started it for then quit
+pub fn ready(handle: &AppHandle) {
+    let targets = std::mem::take(&mut *PENDING_TARGETS.lock().unwrap());
+    for target in targets {
+        open_now(handle, &target);
+    }
+    let Some(path) = PENDING_BACKUP.lock().unwrap().take() else {
+        return;
+    };
+    let code = match maintenance::backup(handle, Some(path)) {
+        Ok(path) => {
+            println!("Backed up the database to {}", path.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    };
+    handle.exit(code);
+}
+
+/// The control endpoint only takes absolute paths, it resolves nothing against its own directory
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn backup(running: &Running, path: &Path) -> Result<(), String> {
+    let target = format!("/backup?path={}", urlencoding::encode(&path.to_string_lossy()));
+    let body = request(&running.endpoint, reqwest::Method::POST, &target, BACKUP_TIMEOUT)?;
+    println!("Backed up the database to {}", body["path"].as_str().unwrap_or_default());
+    Ok(())
+}
+
+/// Have the running app show one of its floating windows, for desktops without global hotkeys
+fn show_window(running: Option<Running>, path: &str) -> Result<(), String> {
+    let Some(running) = running else {
+        return Err("tsw_io is not running, or runs without its control endpoint (control_api)".to_string());
+    };
+    request(&running.endpoint, reqwest::Method::POST, path, STATUS_TIMEOUT).map(|_| ())
+}
+
+fn stop(running: Option<Running>) -> Result<(), String> {
+    let Some(running) = running else {
+        println!("tsw_io is not running");
+        return Ok(());
+    };
+    request(&running.endpoint, reqwest::Method::POST, "/quit", STATUS_TIMEOUT)?;
+    let started_at = Instant::now();
+    while request(&running.endpoint, reqwest::Method::GET, "/status", POLL_INTERVAL).is_ok() {
+        if started_at.elapsed() > STOP_TIMEOUT {
+            return Err("tsw_io did not quit".to_string());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    println!("Stopped tsw_io and its backend");
+    Ok(())
+}
+
+fn status(running: Option<Running>) -> i32 {
+    let Some(running) = running else {
+        println!("tsw_io is not running, or runs without its control endpoint (control_api)");
+        return NOT_RUNNING;
+    };
+    println!("State: {}", running.field("state"));
+    println!("Backend: {}", running.field("backend_url"));
+    println!("Backend pid: {}", running.field("backend_pid"));
+    0
+}
+
+fn install_service(args: &CliArgs) -> Result<(), String> {
+    let resolved = resolve(args);
+    let profile = resolved.profile?;
+    if profile.backend_url.is_some() {
+        return Err("Only the server bundled with tsw_io can run as a service".to_string());
+    }
+    service::install(&profile, &resolved.app_paths)?;
+    println!("Installed the backend service, it starts with your session");
+    Ok(())
+}
+
+/// Config, paths and profile as the app resolves them when it starts
+///
+/// Without `--mock-backend` and `--dev-backend`, which start or look for a server.
+struct Resolved {
+    config_path: PathBuf,
+    /// Whether `config_path` could be read, the defaults are used if not
+    config_check: Result<String, String>,
+    shell_config: ShellConfig,
+    app_paths: AppPaths,
+    profile: Result<Profile, String>,
+}
+
+fn resolve(args: &CliArgs) -> Resolved {
+    let config_dir = paths::config_dir(args);
+    let config_path = config_dir.join(config::CONFIG_FILE_NAME);
+    let (config_check, mut shell_config) = match config::load_existing(&config_path) {
+        Ok(Some(shell_config)) => (Ok(format!("Read {}", config_path.display())), shell_config),
+        Ok(None) => (Ok("Not set up yet, using the defaults".to_string()), ShellConfig::default()),
+        Err(e) => (Err(e), ShellConfig::default()),
+    };
+    policy::load().apply(&mut shell_config);
+    let app_paths = AppPaths::resolve(args, config_dir, &shell_config);
+    let profile_name = args.profile.as_deref().unwrap_or(profile::DEFAULT_PROFILE);
+    let profile = profile::resolve(&shell_config, profile_name, &app_paths);
+    Resolved {
+        config_path,
+        config_check,
+        shell_config,
+        app_paths,
+        profile,
+    }
+}
+
+/// What `--print-config` prints
+#[derive(Debug, Serialize)]
+struct PrintedConfig<'a> {
+    version: &'static str,
+    backend_version: &'static str,
+    config_file: &'a Path,
+    /// Why the config file couldn't be read, the defaults apply then
+    config_error: Option<&'a str>,
+    paths: &'a AppPaths,
+    profile: Option<&'a Profile>,
+    profile_error: Option<&'a str>,
+    backend_url: Option<String>,
+    /// After the administrator-managed settings
+    config: &'a ShellConfig,
+}
+
+fn print_config(args: &CliArgs) -> Result<(), String> {
+    let resolved = resolve(args);
+    let printed = PrintedConfig {
+        version: env!("CARGO_PKG_VERSION"),
+        backend_version: env!("TSW_IO_BACKEND_VERSION"),
+        config_file: &resolved.config_path,
+        config_error: resolved.config_check.as_ref().err().map(String::as_str),
+        paths: &resolved.app_paths,
+        profile: resolved.profile.as_ref().ok(),
+        profile_error: resolved.profile.as_ref().err().map(String::as_str),
+        backend_url: resolved.profile.as_ref().ok().map(Profile::base_url),
+        config: &resolved.shell_config,
+    };
+    let printed = serde_json::to_string_pretty(&printed).map_err(|e| format!("Failed to print the config: {}", e))?;
+    println!("{}", printed);
+    Ok(())
+}
+
+/// The self-test, with the config and the running app checked first
+fn doctor(args: &CliArgs, running: Option<Running>) -> i32 {
+    let resolved = resolve(args);
+    let app = match &running {
+        Some(running) if running.field("state") == "crash_loop" => {
+            Err("Running, but the backend keeps crashing".to_string())
+        }
+        Some(running) => Ok(format!(
+            "Running, backend {} at {}",
+            running.field("state"),
+            running.field("backend_url")
+        )),
+        None => Ok("Not running".to_string()),
+    };
+    let setup = resolved
+        .profile
+        .as_ref()
+        .map(|profile| format!("{}, data in {}", profile.name, profile.data_dir.display()))
+        .map_err(String::clone);
+
+    let mut checks = vec![
+        selftest::check("Config", resolved.config_check),
+        selftest::check("Profile", setup),
+        selftest::check("App", app),
+    ];
+    if let Ok(profile) = &resolved.profile {
+        checks.extend(selftest::run(profile, running.is_some()).checks);
+    }
+    let report = SelfTestReport { checks };
+    print!("{}", report.render());
+    if report.passed() {
+        0
+    } else {
+        1
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    /// Print to the terminal the command was typed in, the release build has no console
+    pub fn attach_console() {
+        // Fails when started from Explorer, where there is nobody to read it anyway
+        let _ = unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    pub fn attach_console() {}
+}
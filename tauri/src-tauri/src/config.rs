@@ -0,0 +1,681 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+
+use crate::audit::{self, AuditAction};
+use crate::commands;
+use crate::crash;
+use crate::logging::LogLevel;
+use crate::network;
+use crate::paths::AppPaths;
+use crate::policy::ManagedPolicy;
+use crate::profile::ProfileOverrides;
+use crate::tls;
+use crate::tray;
+use crate::vault;
+
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+pub const DEFAULT_PORT: u16 = 4000;
+const DEFAULT_HEARTBEAT_INTERVAL_SECS: u64 = 5;
+const WATCH_INTERVAL_MS: u64 = 1000;
+
+/// Shell settings persisted in `config.toml` in the app config directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellConfig {
+    /// Port the backend listens on (restart required)
+    pub port: u16,
+    /// Use an already running backend at this URL instead of the bundled one (restart required)
+    pub backend_url: Option<String>,
+    /// Directory holding the backend database (restart required)
+    pub data_dir: Option<PathBuf>,
+    /// Directory for shell and backend logs (restart required)
+    pub log_dir: Option<PathBuf>,
+    pub log_level: LogLevel,
+    /// Seconds between backend health checks once the app is running
+    pub heartbeat_interval_secs: u64,
+    pub notifications: NotificationConfig,
+    pub badge: BadgeConfig,
+    pub update_channel: UpdateChannel,
+    /// Air-gapped mode: no telemetry, crash or bug report uploads, remote feature flags,
+    /// update checks or firmware downloads (the backend picks it up after a restart)
+    pub offline: bool,
+    /// Proxy and root certificates for reaching the internet, for networks that inspect TLS
+    /// (the backend picks it up after a restart)
+    pub network: NetworkConfig,
+    /// Whether the user opted in to anonymous telemetry
+    pub telemetry: bool,
+    /// Where telemetry goes, instead of the endpoint built into release builds
+    pub telemetry_url: Option<String>,
+    /// Whether the user agreed to send crash reports
+    pub crash_reports: bool,
+    /// Where crash reports go, instead of the endpoint built into release builds
+    pub crash_report_url: Option<String>,
+    /// Where bug reports go, instead of the endpoint built into release builds
+    ///
+    /// Without either, reports are saved to a file the user can email.
+    pub bug_report_url: Option<String>,
+    /// License server to activate and revalidate with, instead of the one built into release builds
+    pub license_url: Option<String>,
+    /// Ask for Touch ID, Windows Hello or the account password before showing the app,
+    /// at launch and after the machine wakes
+    pub app_lock: bool,
+    /// Minutes without keyboard or mouse input after which the app locks and the
+    /// backend session expires
+    pub idle_lock_minutes: Option<u64>,
+    /// Restart the bundled backend, at a moment the user is away, once it uses more memory than this
+    pub memory_limit_mb: Option<u64>,
+    /// Folders whose new and changed files are uploaded to the backend, see `folders`
+    pub watched_folders: Vec<PathBuf>,
+    /// Backend endpoints called on a schedule, see `scheduler`
+    pub schedules: Vec<ScheduledTask>,
+    /// Small backend pages kept on the desktop, see `widgets`
+    pub widgets: Vec<DesktopWidget>,
+    /// Names of the sessions kept side by side on the backend, each with its own webview
+    /// storage and device token, see `accounts`
+    pub accounts: Vec<String>,
+    /// Which of `accounts` the main window shows, `None` for the storage shared by every window
+    pub active_account: Option<String>,
+    /// Sign in through the backend's identity provider in the system browser, see `sso`
+    pub sso: Option<SsoConfig>,
+    /// List configurations and trains in Spotlight or Windows Search, see `search`
+    pub os_search: bool,
+    /// Show today's key numbers from the backend in the macOS menu bar, next to the tray icon,
+    /// see `menubar`
+    pub menu_bar_stats: bool,
+    /// Keep the data directory encrypted, unlocking it when the backend starts (restart required)
+    pub encrypt_data_dir: bool,
+    /// Serve the bundled backend over HTTPS with a certificate made for this machine
+    /// (restart required, macOS and Windows only)
+    pub tls: bool,
+    /// Put the shell's reverse proxy in front of the bundled backend, which then only listens
+    /// on an internal port (restart required)
+    pub proxy: bool,
+    /// Content-Security-Policy for the main window instead of the strict default,
+    /// an empty one turns it off for development (restart required)
+    pub content_security_policy: Option<String>,
+    /// Render with the GPU, `None` to turn it off only where it is known to show a black
+    /// window (restart required)
+    pub hardware_acceleration: Option<bool>,
+    /// Erlang VM tuning for the bundled backend (restart required)
+    pub beam: BeamConfig,
+    /// Start the backend as a node an IEx console can attach to (restart required)
+    pub developer_mode: bool,
+    /// Take commands from scripts on a local port, see `control.json` in the config
+    /// directory (restart required)
+    pub control_api: bool,
+    /// Loopback port where other local tools, like a browser extension, post events for the
+    /// backend, see `webhook.json` in the config directory (restart required)
+    pub webhook_port: Option<u16>,
+    /// Global hotkey summoning the quick capture window, like `CmdOrCtrl+Shift+Space`
+    /// (restart required, on Linux bind `tsw-io capture` to a desktop shortcut instead)
+    pub capture_hotkey: Option<String>,
+    /// Global hotkey summoning the command palette, like `CmdOrCtrl+Shift+K`
+    /// (restart required, on Linux bind `tsw-io palette` to a desktop shortcut instead)
+    pub palette_hotkey: Option<String>,
+    /// Let phones paired through "Connect mobile device" reach the bundled backend over the
+    /// local network (restart required)
+    pub lan_access: bool,
+    /// Overrides for the environments selectable with `--env` (restart required)
+    pub profiles: BTreeMap<String, ProfileOverrides>,
+    /// Feature flags, taking precedence over the remote flag file except for the kill switches it
+    /// turns off (restart required)
+    pub features: BTreeMap<String, bool>,
+    /// JSON file of `{"flag": bool}` used to roll features out gradually, instead of the one built
+    /// into release builds
+    pub feature_flags_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    /// Notify when the backend stops responding or comes back
+    pub backend_status: bool,
+    /// Start of the quiet hours in local time, as `HH:MM` like `20:00`
+    ///
+    /// Notifications are held back until they end and then summed up in one.
+    pub quiet_from: Option<String>,
+    /// End of the quiet hours, as `HH:MM` like `08:00`, the next day when before the start
+    pub quiet_until: Option<String>,
+    /// Keep quiet all Saturday and Sunday
+    pub quiet_weekends: bool,
+    /// Keep quiet while the OS's Focus or do-not-disturb is on
+    pub follow_os_focus: bool,
+}
+
+/// Where the app icon badge gets its count from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BadgeSource {
+    /// Pushed by the backend over the event bridge as it changes
+    Bridge,
+    /// Asked from the backend every `poll_interval_secs`
+    Polling,
+    /// No badge
+    Off,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BadgeConfig {
+    pub source: BadgeSource,
+    pub poll_interval_secs: u64,
+    /// Stop updating the badge while the machine runs on battery
+    pub pause_on_battery: bool,
+    /// Stop updating the badge while the network connection is metered
+    pub pause_on_metered: bool,
+}
+
+impl Default for BadgeConfig {
+    fn default() -> Self {
+        Self {
+            source: BadgeSource::Bridge,
+            poll_interval_secs: 60,
+            pause_on_battery: false,
+            pause_on_metered: false,
+        }
+    }
+}
+
+/// Erlang VM flags for the bundled backend, unset ones keep the VM's defaults
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BeamConfig {
+    /// Scheduler threads, the VM starts one per core
+    pub schedulers: Option<u32>,
+    pub dirty_cpu_schedulers: Option<u32>,
+    pub dirty_io_schedulers: Option<u32>,
+    /// Largest heap a single Erlang process may grow to before it is killed
+    pub max_process_heap_mb: Option<u64>,
+    /// Let idle schedulers spin before sleeping, turning it off saves CPU on small machines
+    pub busy_wait: Option<bool>,
+    /// Further flags passed to the VM as they are, e.g. `+A 32`
+    pub extra_flags: Option<String>,
+}
+
+/// A backend endpoint the shell calls on a schedule, like a nightly backup
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduledTask {
+    /// Tells the task apart in the settings and the history
+    pub name: String,
+    /// When it runs in local time, as `minute hour day month weekday` or `@daily`, `@weekly`...
+    pub cron: String,
+    pub method: String,
+    /// Path on the backend, `/api/...`
+    pub path: String,
+    /// JSON sent along with the request
+    pub body: Option<String>,
+    /// Run once when the app notices it missed a run, as after the machine slept
+    pub catch_up: bool,
+}
+
+impl Default for ScheduledTask {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            cron: String::new(),
+            method: "POST".to_string(),
+            path: String::new(),
+            body: None,
+            catch_up: true,
+        }
+    }
+}
+
+/// A compact backend page shown in a small frameless window on the desktop
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DesktopWidget {
+    /// Tells the widget apart in the settings
+    pub name: String,
+    /// Backend page it shows, as `/widgets/today`
+    pub route: String,
+    /// In logical pixels
+    pub width: f64,
+    pub height: f64,
+    /// Where it was last left, in logical pixels from the top left of the main screen,
+    /// `None` until it is first moved
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    /// Let clicks through to what is under it, which also keeps it from being moved
+    pub click_through: bool,
+}
+
+impl Default for DesktopWidget {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            route: "/widgets/today".to_string(),
+            width: 260.0,
+            height: 200.0,
+            x: None,
+            y: None,
+            click_through: false,
+        }
+    }
+}
+
+/// An external identity provider, signed in to with OAuth's authorization code flow and PKCE
+///
+/// URLs starting with `/` are on the backend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SsoConfig {
+    /// Page the login starts on, given `redirect_uri`, `state` and `code_challenge`
+    pub authorize_url: String,
+    /// Where the code the login ends with is traded for a token, given `code` and `code_verifier`
+    pub token_url: String,
+    /// Identifies the app to the identity provider, for those that want it
+    pub client_id: Option<String>,
+    /// Cookie the backend pages get the token in
+    pub cookie_name: String,
+}
+
+/// How the shell, and the backend's update checks and firmware downloads, reach the internet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Proxy for every request to the internet, like `http://proxy.corp:3128`, instead of the system's
+    pub http_proxy: Option<String>,
+    /// PEM file with the root certificates of the company, trusted on top of the OS store
+    pub ca_certificates: Option<PathBuf>,
+    /// Trust the root certificates of the OS, off to trust only `ca_certificates`
+    pub os_certificates: bool,
+}
+
+impl NetworkConfig {
+    /// `http_proxy` without the user name and password it may carry
+    pub fn shareable_proxy(&self) -> Option<String> {
+        let url = self.http_proxy.as_ref()?;
+        match Url::parse(url) {
+            Ok(mut parsed) if !parsed.username().is_empty() || parsed.password().is_some() => {
+                let _ = parsed.set_username("");
+                let _ = parsed.set_password(None);
+                Some(parsed.to_string())
+            }
+            _ => Some(url.clone()),
+        }
+    }
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            http_proxy: None,
+            ca_certificates: None,
+            os_certificates: true,
+        }
+    }
+}
+
+impl Default for SsoConfig {
+    fn default() -> Self {
+        Self {
+            authorize_url: "/auth/sso".to_string(),
+            token_url: "/auth/sso/token".to_string(),
+            client_id: None,
+            cookie_name: "tsw_io_sso".to_string(),
+        }
+    }
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_PORT,
+            backend_url: None,
+            data_dir: None,
+            log_dir: None,
+            log_level: LogLevel::Info,
+            heartbeat_interval_secs: DEFAULT_HEARTBEAT_INTERVAL_SECS,
+            notifications: NotificationConfig::default(),
+            badge: BadgeConfig::default(),
+            update_channel: UpdateChannel::Stable,
+            offline: false,
+            network: NetworkConfig::default(),
+            telemetry: false,
+            telemetry_url: None,
+            crash_reports: false,
+            crash_report_url: None,
+            bug_report_url: None,
+            license_url: None,
+            app_lock: false,
+            idle_lock_minutes: None,
+            memory_limit_mb: None,
+            watched_folders: Vec::new(),
+            schedules: Vec::new(),
+            widgets: Vec::new(),
+            accounts: Vec::new(),
+            active_account: None,
+            sso: None,
+            os_search: true,
+            menu_bar_stats: false,
+            encrypt_data_dir: false,
+            tls: false,
+            proxy: false,
+            content_security_policy: None,
+            hardware_acceleration: None,
+            beam: BeamConfig::default(),
+            developer_mode: false,
+            control_api: false,
+            webhook_port: None,
+            capture_hotkey: None,
+            palette_hotkey: None,
+            lan_access: false,
+            profiles: BTreeMap::new(),
+            features: BTreeMap::new(),
+            feature_flags_url: None,
+        }
+    }
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            backend_status: true,
+            quiet_from: None,
+            quiet_until: None,
+            quiet_weekends: false,
+            follow_os_focus: false,
+        }
+    }
+}
+
+impl ShellConfig {
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_interval_secs.max(1))
+    }
+
+    /// Refuse settings this platform can't honour, rather than pretending to
+    pub fn validate(&self) -> Result<(), String> {
+        if self.encrypt_data_dir && !vault::SUPPORTED {
+            return Err("encrypt_data_dir is not supported on this platform".to_string());
+        }
+        if self.tls && !tls::SUPPORTED {
+            return Err(
+                "tls is not supported on this platform, the webview can't trust the certificate"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether switching from `self` to `other` needs the backend to be respawned
+    pub fn requires_restart(&self, other: &ShellConfig) -> bool {
+        self.port != other.port
+            || self.backend_url != other.backend_url
+            || self.data_dir != other.data_dir
+            || self.log_dir != other.log_dir
+            || self.profiles != other.profiles
+            || self.features != other.features
+            || self.hardware_acceleration != other.hardware_acceleration
+            || self.beam != other.beam
+            || self.developer_mode != other.developer_mode
+            || self.control_api != other.control_api
+            || self.webhook_port != other.webhook_port
+            || self.capture_hotkey != other.capture_hotkey
+            || self.palette_hotkey != other.palette_hotkey
+            || self.lan_access != other.lan_access
+            || self.tls != other.tls
+            || self.proxy != other.proxy
+            || self.encrypt_data_dir != other.encrypt_data_dir
+            || self.content_security_policy != other.content_security_policy
+    }
+
+    /// Copy the settings that can be applied without a restart from `other`
+    fn apply_hot(&mut self, other: &ShellConfig) {
+        self.log_level = other.log_level;
+        self.heartbeat_interval_secs = other.heartbeat_interval_secs;
+        self.notifications = other.notifications.clone();
+        self.badge = other.badge.clone();
+        self.update_channel = other.update_channel;
+        self.offline = other.offline;
+        self.network = other.network.clone();
+        self.telemetry = other.telemetry;
+        self.telemetry_url = other.telemetry_url.clone();
+        self.crash_reports = other.crash_reports;
+        self.crash_report_url = other.crash_report_url.clone();
+        self.bug_report_url = other.bug_report_url.clone();
+        self.license_url = other.license_url.clone();
+        self.app_lock = other.app_lock;
+        self.idle_lock_minutes = other.idle_lock_minutes;
+        self.memory_limit_mb = other.memory_limit_mb;
+        self.watched_folders = other.watched_folders.clone();
+        self.schedules = other.schedules.clone();
+        self.widgets = other.widgets.clone();
+        self.accounts = other.accounts.clone();
+        self.active_account = other.active_account.clone();
+        self.sso = other.sso.clone();
+        self.os_search = other.os_search;
+        self.menu_bar_stats = other.menu_bar_stats;
+    }
+
+    /// Names of the top-level settings that differ between `self` and `other`
+    pub fn changed_settings(&self, other: &ShellConfig) -> Vec<String> {
+        let table = |config: &ShellConfig| match toml::Value::try_from(config) {
+            Ok(toml::Value::Table(table)) => table,
+            _ => toml::Table::new(),
+        };
+        let (before, after) = (table(self), table(other));
+        let mut keys: Vec<String> = before
+            .keys()
+            .chain(after.keys())
+            .filter(|key| before.get(*key) != after.get(*key))
+            .cloned()
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Copy that can be shared with another machine, without local paths or secrets
+    ///
+    /// The accounts and sign-in are the user's own, so they are left out too.
+    pub fn exportable(&self) -> ShellConfig {
+        let mut profiles = self.profiles.clone();
+        for overrides in profiles.values_mut() {
+            overrides.data_dir = None;
+        }
+        ShellConfig {
+            data_dir: None,
+            log_dir: None,
+            watched_folders: Vec::new(),
+            network: NetworkConfig {
+                http_proxy: self.network.shareable_proxy(),
+                ca_certificates: None,
+                os_certificates: self.network.os_certificates,
+            },
+            accounts: Vec::new(),
+            active_account: None,
+            sso: None,
+            profiles,
+            ..self.clone()
+        }
+    }
+}
+
+/// The configuration the running shell is using
+pub struct ConfigState(pub RwLock<ShellConfig>);
+
+impl ConfigState {
+    pub fn get(&self) -> ShellConfig {
+        self.0.read().unwrap().clone()
+    }
+}
+
+pub fn config_path(handle: &AppHandle) -> PathBuf {
+    handle.state::<AppPaths>().config_file()
+}
+
+/// Apply the same change to the running config and the config file
+///
+/// The file is written without managed policy values, so removing a policy later
+/// brings the user's own settings back.
+pub fn update(handle: &AppHandle, change: impl Fn(&mut ShellConfig)) -> Result<ShellConfig, String> {
+    // Update the running config first so the watcher doesn't ask for a restart
+    let (running, changed) = {
+        let state = handle.state::<ConfigState>();
+        let mut current = state.0.write().unwrap();
+        let previous = current.clone();
+        change(&mut current);
+        handle.state::<ManagedPolicy>().apply(&mut current);
+        crash::configure(&current);
+        network::configure(&current);
+        (current.clone(), previous.changed_settings(&current))
+    };
+    tray::sync_crash_reports(handle);
+    record_change(handle, &changed, "the app");
+
+    let path = config_path(handle);
+    let mut file_config = load_existing(&path)?.unwrap_or_default();
+    change(&mut file_config);
+    save(&path, &file_config)?;
+    Ok(running)
+}
+
+/// Load the config file, or `None` on first run when it doesn't exist yet
+pub fn load_existing(path: &Path) -> Result<Option<ShellConfig>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    load(path).map(Some)
+}
+
+pub fn load(path: &Path) -> Result<ShellConfig, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let config: ShellConfig =
+        toml::from_str(&contents).map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+    config
+        .validate()
+        .map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+    Ok(config)
+}
+
+pub fn save(path: &Path, config: &ShellConfig) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let contents =
+        toml::to_string_pretty(config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Poll the config file and apply changes while the app runs
+pub fn watch(handle: AppHandle, path: PathBuf) {
+    std::thread::spawn(move || {
+        let mut last_modified = modified_at(&path);
+        // Only prompt once per distinct restart-requiring config
+        let mut prompted_for: Option<ShellConfig> = None;
+
+        loop {
+            std::thread::sleep(Duration::from_millis(WATCH_INTERVAL_MS));
+
+            let modified = modified_at(&path);
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+
+            let new_config = match load(&path) {
+                Ok(mut config) => {
+                    handle.state::<ManagedPolicy>().apply(&mut config);
+                    config
+                }
+                Err(e) => {
+                    // Keep running with the previous settings until the file is fixed
+                    tracing::warn!("Ignoring config change: {}", e);
+                    continue;
+                }
+            };
+
+            let state = handle.state::<ConfigState>();
+            let (needs_restart, level_changed, changed) = {
+                let mut current = state.0.write().unwrap();
+                let level_changed = current.log_level != new_config.log_level;
+                let changed = current.changed_settings(&new_config);
+                current.apply_hot(&new_config);
+                crash::configure(&current);
+                network::configure(&current);
+                (current.requires_restart(&new_config), level_changed, changed)
+            };
+            tracing::info!("Applied config changes");
+            tray::sync_crash_reports(&handle);
+            record_change(&handle, &changed, "the config file");
+
+            if level_changed {
+                if let Err(e) = commands::apply_log_level(&handle, new_config.log_level) {
+                    tracing::warn!("{}", e);
+                }
+            }
+
+            if needs_restart && prompted_for.as_ref() != Some(&new_config) {
+                prompted_for = Some(new_config);
+                prompt_restart(&handle);
+            }
+        }
+    });
+}
+
+fn record_change(handle: &AppHandle, changed: &[String], source: &str) {
+    if !changed.is_empty() {
+        audit::record(
+            handle,
+            AuditAction::ConfigChange,
+            format!("Changed {} in {}", changed.join(", "), source),
+        );
+    }
+}
+
+fn prompt_restart(handle: &AppHandle) {
+    if crate::headless::enabled() {
+        tracing::info!("Changed settings take effect after a restart");
+        return;
+    }
+    let restart_handle = handle.clone();
+    handle
+        .dialog()
+        .message("Some of the changed settings only take effect after tsw_io restarts.")
+        .title("Restart required")
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Restart now".to_string(),
+            "Later".to_string(),
+        ))
+        .show(move |restart| {
+            if restart {
+                audit::record(
+                    &restart_handle,
+                    AuditAction::BackendRestart,
+                    "Restarted for changed settings",
+                );
+                crate::stop_backend(&restart_handle);
+                restart_handle.restart();
+            }
+        });
+}
@@ -0,0 +1,66 @@
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+use crate::audit::{self, AuditAction};
+use crate::{lock, secrets};
+
+/// Proof that the user authenticated to confirm `reason`, for the backend to check
+///
+/// The signature is an HMAC-SHA256 keyed with the shell token over the reason, the time
+/// and the nonce joined by newlines, so only this run of the shell can make one.
+#[derive(Debug, Clone, Serialize)]
+pub struct Confirmation {
+    pub reason: String,
+    /// Unix time in seconds
+    pub issued_at: u64,
+    pub nonce: String,
+    pub signature: String,
+}
+
+fn sign(reason: &str, issued_at: u64, nonce: &str) -> Result<String, String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secrets::shell_token().as_bytes())
+        .map_err(|e| format!("Failed to sign confirmation: {}", e))?;
+    mac.update(format!("{}\n{}\n{}", reason, issued_at, nonce).as_bytes());
+    Ok(mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Have the user confirm a sensitive action with Touch ID or Windows Hello
+///
+/// Returns nothing when they cancel. The backend verifies the confirmation with
+/// `TswIo.Shell.verify_confirmation/2` before going ahead.
+#[tauri::command]
+pub async fn confirm_with_biometrics(app: AppHandle, reason: String) -> Result<Option<Confirmation>, String> {
+    if reason.trim().is_empty() {
+        return Err("Say what is being confirmed".to_string());
+    }
+    let prompt = reason.clone();
+    let authenticated = tauri::async_runtime::spawn_blocking(move || lock::authenticate(&prompt))
+        .await
+        .map_err(|e| e.to_string())??;
+    if !authenticated {
+        tracing::info!("Confirmation cancelled or failed");
+        return Ok(None);
+    }
+
+    let issued_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let nonce = secrets::random_hex(16)?;
+    let signature = sign(&reason, issued_at, &nonce)?;
+    audit::record(&app, AuditAction::Confirmation, reason.clone());
+    Ok(Some(Confirmation {
+        reason,
+        issued_at,
+        nonce,
+        signature,
+    }))
+}
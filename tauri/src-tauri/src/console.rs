@@ -0,0 +1,94 @@
+use std::process::Command;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+
+use crate::config::ShellConfig;
+use crate::profile::ProfileState;
+use crate::secrets;
+
+/// Distributed node name of the backend in developer mode
+const BACKEND_NODE: &str = "tsw_io@127.0.0.1";
+
+static COOKIE: OnceLock<String> = OnceLock::new();
+
+/// Random cookie shared by the backend and the consoles of this run
+fn cookie() -> &'static str {
+    COOKIE.get_or_init(|| {
+        secrets::random_hex(16).unwrap_or_else(|e| {
+            tracing::warn!("{}", e);
+            format!("tsw_io_{}", std::process::id())
+        })
+    })
+}
+
+/// Extra sidecar environment that starts the backend as a named node in developer mode
+///
+/// Distribution and the port mapper only listen on loopback, so the node can't be
+/// reached from other machines.
+pub fn sidecar_env(config: &ShellConfig) -> Vec<(&'static str, String)> {
+    if !config.developer_mode {
+        return Vec::new();
+    }
+    vec![
+        ("RELEASE_DISTRIBUTION", "name".to_string()),
+        ("RELEASE_NODE", BACKEND_NODE.to_string()),
+        ("RELEASE_COOKIE", cookie().to_string()),
+        ("ERL_EPMD_ADDRESS", "127.0.0.1".to_string()),
+        ("ERL_AFLAGS", "-kernel inet_dist_use_interface {127,0,0,1}".to_string()),
+    ]
+}
+
+/// Command line of an IEx shell attached to the running backend
+fn iex_command() -> String {
+    let iex = if cfg!(windows) { "iex.bat" } else { "iex" };
+    format!(
+        "{} --name console-{}@127.0.0.1 --cookie {} --remsh {}",
+        iex,
+        std::process::id(),
+        cookie(),
+        BACKEND_NODE
+    )
+}
+
+/// Open a terminal with IEx attached to the backend
+///
+/// Needs Elixir on the `PATH`. The error includes the command so it can be run by hand.
+pub fn open(handle: &AppHandle) -> Result<(), String> {
+    if handle.state::<ProfileState>().get().backend_url.is_some() {
+        return Err("The backend console is only available for the bundled backend".to_string());
+    }
+    if !crate::backend_running(handle) {
+        return Err("The backend is not running".to_string());
+    }
+
+    let command = iex_command();
+    let result = if cfg!(target_os = "macos") {
+        Command::new("osascript")
+            .arg("-e")
+            .arg(format!("tell application \"Terminal\" to do script \"{}\"", command))
+            .arg("-e")
+            .arg("tell application \"Terminal\" to activate")
+            .spawn()
+    } else if cfg!(windows) {
+        Command::new("cmd")
+            .args(["/C", "start", "TSW IO console", "cmd", "/K"])
+            .arg(&command)
+            .spawn()
+    } else {
+        Command::new("x-terminal-emulator")
+            .args(["-e", "sh", "-c"])
+            .arg(&command)
+            .spawn()
+    };
+
+    match result {
+        Ok(_) => {
+            tracing::info!("Opened backend console");
+            Ok(())
+        }
+        Err(e) => Err(format!(
+            "Failed to open a terminal ({}). Run this instead: {}",
+            e, command
+        )),
+    }
+}
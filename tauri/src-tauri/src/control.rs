@@ -0,0 +1,227 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, Url};
+
+use crate::commands::{self, AppInfo};
+use crate::localhttp::{self, Request};
+use crate::paths::AppPaths;
+use crate::profile::ProfileState;
+use crate::supervisor::{self, SupervisorState};
+use crate::{automation, headless, loopback, maintenance, secrets, tls, upgrade};
+
+/// Tells scripts where the control endpoint listens and the token it wants
+pub const CONTROL_FILE_NAME: &str = "control.json";
+/// Random bytes in the token
+const TOKEN_BYTES: usize = 32;
+
+/// Set once this process wrote `control.json`, it isn't the app's to remove otherwise
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Contents of `control.json`, only readable by the user
+#[derive(Debug, Serialize)]
+struct ControlFile<'a> {
+    url: String,
+    token: &'a str,
+    pid: u32,
+    /// For the app to use the backend when this runs as the backend service
+    shell_token: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct Status {
+    state: SupervisorState,
+    backend_url: String,
+    backend_pid: Option<u32>,
+    /// Started with `backend start`, so its backend doesn't go away with a window
+    headless: bool,
+    app: AppInfo,
+}
+
+fn control_file(handle: &AppHandle) -> PathBuf {
+    handle.state::<AppPaths>().config_dir.join(CONTROL_FILE_NAME)
+}
+
+/// Accept commands from scripts on a loopback port, separate from the backend
+///
+/// Scripts read the URL and token from `control.json` in the config directory and call:
+///
+/// - `GET /status`
+/// - `POST /open-route?route=/trains/1`
+/// - `POST /open-document?path=<file>`: hand a file to the backend to import
+/// - `POST /export?path=<file>`: the shell settings
+/// - `POST /backup[?path=<file>]`: the database, to the downloads directory by default
+/// - `POST /upgrade-backend?path=<sidecar>&version=<version>`: swap in a new backend signed in `<sidecar>.sig`, see `upgrade`
+/// - `POST /capture`: show the quick capture window, see `capture`
+/// - `POST /palette`: show the command palette, see `palette`
+/// - `POST /sso-callback?query=<query>`: finish a single sign-on, see `sso`
+/// - `POST /quit`
+///
+/// with `Authorization: Bearer <token>`. A new token is made every run.
+pub fn start(handle: &AppHandle) -> Result<(), String> {
+    let listener = TcpListener::bind((loopback::BIND_ADDRESS, 0))
+        .map_err(|e| format!("Failed to start the control endpoint: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to start the control endpoint: {}", e))?
+        .port();
+    let token = secrets::random_hex(TOKEN_BYTES)?;
+    let path = control_file(handle);
+    let contents = ControlFile {
+        url: format!("http://{}:{}", loopback::BIND_ADDRESS, port),
+        token: &token,
+        pid: std::process::id(),
+        shell_token: secrets::shell_token(),
+    };
+    let contents = serde_json::to_string_pretty(&contents).map_err(|e| e.to_string())?;
+    tls::write_private(&path, &contents)?;
+    STARTED.store(true, Ordering::SeqCst);
+    tracing::info!(port, "Control endpoint listening, see {}", path.display());
+
+    let handle = handle.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let handle = handle.clone();
+            let token = token.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = serve(&handle, &token, stream) {
+                    tracing::debug!("Control request failed: {}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+/// Remove `control.json`, so scripts don't try a port nothing listens on anymore
+pub fn stop(handle: &AppHandle) {
+    if !STARTED.load(Ordering::SeqCst) {
+        return;
+    }
+    let path = control_file(handle);
+    if path.exists() {
+        if let Err(e) = std::fs::remove_file(&path) {
+            tracing::warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn serve(handle: &AppHandle, token: &str, stream: TcpStream) -> std::io::Result<()> {
+    let request = localhttp::read_request(&stream)?;
+    // Browsers send an Origin, scripts don't: pages can't drive the app even with a leaked token
+    if request.header("origin").is_some() {
+        return reply(stream, 403, json!({ "error": "forbidden" }));
+    }
+    let authorized = request
+        .header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| secrets::constant_time_eq(given, token));
+    if !authorized {
+        return reply(stream, 401, json!({ "error": "unauthorized" }));
+    }
+
+    let quit = request.method == "POST" && request.path() == "/quit";
+    let (status, body) = match run(handle, &request) {
+        Ok(body) => (200, body),
+        Err((status, e)) => (status, json!({ "error": e })),
+    };
+    reply(stream, status, body)?;
+    if quit && status == 200 {
+        tracing::info!("Quitting on request of the control endpoint");
+        handle.exit(0);
+    }
+    Ok(())
+}
+
+fn reply(stream: TcpStream, status: u16, body: Value) -> std::io::Result<()> {
+    localhttp::respond(stream, status, "application/json", &body.to_string())
+}
+
+fn run(handle: &AppHandle, request: &Request) -> Result<Value, (u16, String)> {
+    let query = |name: &str| {
+        Url::parse(&format!("http://localhost{}", request.target))
+            .ok()?
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let path_arg = |name: &str| -> Result<Option<PathBuf>, (u16, String)> {
+        match query(name).map(PathBuf::from) {
+            Some(path) if !path.is_absolute() => Err((400, format!("{} must be an absolute path", name))),
+            path => Ok(path),
+        }
+    };
+
+    match (request.method.as_str(), request.path()) {
+        ("GET", "/status") => {
+            let status = Status {
+                state: supervisor::state(handle),
+                backend_url: handle.state::<ProfileState>().get().base_url(),
+                backend_pid: crate::backend_pid(handle),
+                headless: headless::enabled(),
+                app: commands::get_app_info(handle.clone()),
+            };
+            serde_json::to_value(status).map_err(|e| (500, e.to_string()))
+        }
+        ("POST", "/open-route") => {
+            let route = query("route").unwrap_or_else(|| "/".to_string());
+            open_route(handle, &route).map_err(|e| (400, e))?;
+            Ok(json!({ "route": route }))
+        }
+        ("POST", "/open-document") => {
+            let path = path_arg("path")?.ok_or((400, "path is missing".to_string()))?;
+            automation::open_document(handle, &path).map_err(|e| (400, e))?;
+            Ok(json!({ "path": path }))
+        }
+        ("POST", "/export") => {
+            let path = path_arg("path")?.ok_or((400, "path is missing".to_string()))?;
+            commands::export_settings_to(handle, &path).map_err(|e| (500, e))?;
+            Ok(json!({ "path": path }))
+        }
+        ("POST", "/backup") => {
+            let path = maintenance::backup(handle, path_arg("path")?).map_err(|e| (500, e))?;
+            Ok(json!({ "path": path }))
+        }
+        ("POST", "/upgrade-backend") => {
+            let path = path_arg("path")?.ok_or((400, "path is missing".to_string()))?;
+            let version = query("version").ok_or((400, "version is missing".to_string()))?;
+            upgrade::upgrade(handle, &path, &version).map_err(|e| (500, e))?;
+            Ok(json!({ "backend_version": version }))
+        }
+        ("POST", "/capture") => {
+            crate::capture::show(handle);
+            Ok(json!({ "shown": true }))
+        }
+        ("POST", "/palette") => {
+            crate::palette::show(handle);
+            Ok(json!({ "shown": true }))
+        }
+        ("POST", "/sso-callback") => {
+            crate::sso::complete(handle, query("query").unwrap_or_default());
+            Ok(json!({ "received": true }))
+        }
+        ("POST", "/quit") => Ok(json!({ "quitting": true })),
+        _ => Err((404, format!("No command {} {}", request.method, request.path()))),
+    }
+}
+
+/// Show the main window on a backend page
+pub(crate) fn open_route(handle: &AppHandle, route: &str) -> Result<(), String> {
+    if !route.starts_with('/') || route.starts_with("//") {
+        return Err("Only backend pages can be opened".to_string());
+    }
+    if supervisor::state(handle) != SupervisorState::Running {
+        return Err("The backend is not running".to_string());
+    }
+    let url = crate::api::backend_url(&handle.state::<ProfileState>().get().base_url(), route);
+    let url = url.parse().map_err(|e| format!("Invalid route {}: {}", route, e))?;
+    crate::open_main_window(handle);
+    let window = handle
+        .get_webview_window("main")
+        .ok_or_else(|| "The main window is not open".to_string())?;
+    window
+        .navigate(url)
+        .map_err(|e| format!("Failed to open {}: {}", route, e))
+}
@@ -0,0 +1,379 @@
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::config::ShellConfig;
+
+const OPEN_LOGS: &str = "Open logs";
+/// Names the crash log of a panic for the next launch to show, in the log directory
+const LAST_CRASH_FILE: &str = "last-crash";
+/// Endpoint release builds report to when the config doesn't name one
+const BUILT_IN_REPORT_URL: Option<&str> = option_env!("TSW_IO_CRASH_REPORT_URL");
+const REPORT_TIMEOUT_SECS: u64 = 10;
+/// Lines of log output attached to a report
+const REPORT_LOG_LINES: usize = 200;
+/// Around words rather than part of them, as in JSON or a list
+const PUNCTUATION: &[char] = &['"', '\'', '`', ',', ';', '(', ')', '[', ']', '{', '}', '<', '>'];
+/// What reports have instead of the secrets `sanitize` strips
+const REDACTED: &str = "<redacted>";
+/// Names of values that are secrets, as in `name=value`, `"name":"value"` or a query
+const SECRET_NAMES: &[&str] = &["token", "password", "passphrase", "secret", "key", "authorization", "cookie", "signature"];
+/// Opaque words this long or longer are taken for tokens or keys, see `opaque`
+const MIN_SECRET_LEN: usize = 24;
+/// Names only hosts on the local network have
+const NETWORK_SUFFIXES: &[&str] = &[".local", ".lan", ".home", ".internal", ".localdomain"];
+
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+/// Where to send reports, `None` unless the user opted in
+static REPORT_URL: RwLock<Option<String>> = RwLock::new(None);
+
+/// Write a crash log instead of vanishing when the shell panics, shown at the next launch
+///
+/// The panicking thread may hold locks the event loop or a dialog needs, so it only records
+/// the crash and exits, see `show_last_crash`.
+pub fn install() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if let Ok(crash_log) = record(&describe(info)) {
+            let _ = std::fs::write(log_dir().join(LAST_CRASH_FILE), crash_log.to_string_lossy().as_bytes());
+        }
+        crate::logging::flush();
+        // A panic on a worker thread would otherwise leave the app stuck on the splash
+        std::process::exit(1);
+    }));
+}
+
+/// Where crash logs go once the log directory is known
+pub fn set_log_dir(log_dir: &Path) {
+    let _ = LOG_DIR.set(log_dir.to_path_buf());
+}
+
+/// Follow the crash report consent and endpoint in `config`
+pub fn configure(config: &ShellConfig) {
+    let url = if config.crash_reports && !config.offline {
+        config
+            .crash_report_url
+            .clone()
+            .or_else(|| BUILT_IN_REPORT_URL.map(str::to_string))
+    } else {
+        None
+    };
+    if let Ok(mut current) = REPORT_URL.write() {
+        *current = url;
+    }
+}
+
+fn describe(info: &PanicHookInfo) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Unknown error".to_string());
+    match info.location() {
+        Some(location) => format!("{} at {}:{}", message, location.file(), location.line()),
+        None => message,
+    }
+}
+
+/// Log, keep and send a fatal error, returning the crash log
+fn record(message: &str) -> Result<PathBuf, String> {
+    tracing::error!("Fatal error: {}", message);
+    let crash_log = write_crash_log(message);
+    send_report("panic", message, shell_log_tail());
+
+    if crate::headless::enabled() {
+        crate::headless::abort(message);
+    }
+    crash_log
+}
+
+/// Record a fatal error and tell the user about it, on the main thread
+pub fn report(message: &str) {
+    let crash_log = record(message);
+    let description = match &crash_log {
+        Ok(path) => format!(
+            "TSW IO ran into a problem and has to close.\n\n{}\n\nDetails were saved to {}",
+            message,
+            path.display()
+        ),
+        Err(_) => format!("TSW IO ran into a problem and has to close.\n\n{}", message),
+    };
+
+    // The event loop may already be gone, so this does not go through the dialog plugin
+    let result = MessageDialog::new()
+        .set_level(MessageLevel::Error)
+        .set_title("TSW IO")
+        .set_description(description)
+        .set_buttons(MessageButtons::OkCancelCustom(
+            OPEN_LOGS.to_string(),
+            "Close".to_string(),
+        ))
+        .show();
+
+    if result == MessageDialogResult::Custom(OPEN_LOGS.to_string()) {
+        let target = crash_log.unwrap_or_else(|_| log_dir());
+        let _ = tauri_plugin_opener::reveal_item_in_dir(target);
+    }
+}
+
+/// Tell the user the shell crashed the last time it ran, if it did
+pub fn show_last_crash(handle: &AppHandle) {
+    let marker = log_dir().join(LAST_CRASH_FILE);
+    let Ok(crash_log) = std::fs::read_to_string(&marker) else {
+        return;
+    };
+    let _ = std::fs::remove_file(&marker);
+    let crash_log = PathBuf::from(crash_log);
+    // The first line after the header is the panic message
+    let message = std::fs::read_to_string(&crash_log)
+        .ok()
+        .and_then(|contents| contents.lines().nth(2).map(str::to_string))
+        .unwrap_or_default();
+    if crate::headless::enabled() {
+        tracing::warn!("The last run crashed: {}", message);
+        return;
+    }
+    handle
+        .dialog()
+        .message(format!(
+            "TSW IO ran into a problem and closed the last time it ran.\n\n{}\n\nDetails were saved to {}",
+            message,
+            crash_log.display()
+        ))
+        .title("TSW IO")
+        .kind(MessageDialogKind::Error)
+        .buttons(MessageDialogButtons::OkCancelCustom(OPEN_LOGS.to_string(), "Close".to_string()))
+        .show(move |open| {
+            if open {
+                let _ = tauri_plugin_opener::reveal_item_in_dir(crash_log);
+            }
+        });
+}
+
+fn log_dir() -> PathBuf {
+    LOG_DIR.get().cloned().unwrap_or_else(std::env::temp_dir)
+}
+
+fn write_crash_log(message: &str) -> Result<PathBuf, String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let dir = log_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let path = dir.join(format!("crash-{}.log", timestamp));
+    let contents = format!(
+        "tsw_io {} crashed\n\n{}\n\nBacktrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        message,
+        std::backtrace::Backtrace::force_capture()
+    );
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("Failed to write crash log: {}", e))?;
+    Ok(path)
+}
+
+/// Report a backend that exited without being asked to, with its last output
+pub fn report_backend_exit(code: Option<i32>, signal: Option<i32>, output: Vec<String>) {
+    let message = match (code, signal) {
+        (Some(code), _) => format!("Backend exited with code {}", code),
+        (None, Some(signal)) => format!("Backend killed by signal {}", signal),
+        (None, None) => "Backend exited".to_string(),
+    };
+    tracing::error!("{}", message);
+    send_report("backend_exit", &message, output);
+}
+
+/// Send a crash report if the user opted in, giving up after a short timeout
+fn send_report(kind: &str, message: &str, logs: Vec<String>) {
+    let Some(url) = REPORT_URL.read().ok().and_then(|url| url.clone()) else {
+        return;
+    };
+
+    let body = json!({
+        "kind": kind,
+        "message": sanitize(message),
+        "version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "logs": logs.iter().map(|line| sanitize(line)).collect::<Vec<_>>(),
+    });
+
+    // The blocking client can't run on an async runtime thread, which a panic may be on
+    let sender = std::thread::spawn(move || {
+        crate::network::client().and_then(|client| {
+            client
+                .post(url)
+                .timeout(Duration::from_secs(REPORT_TIMEOUT_SECS))
+                .json(&body)
+                .send()
+        })
+    });
+    match sender.join() {
+        Ok(Ok(response)) if response.status().is_success() => {}
+        Ok(Ok(response)) => tracing::warn!("Crash report rejected: {}", response.status()),
+        Ok(Err(e)) => tracing::warn!("Failed to send crash report: {}", e),
+        Err(_) => tracing::warn!("Failed to send crash report"),
+    }
+}
+
+/// Strip what could identify anyone or let others in from reports: the user's home
+/// directory, email addresses, keys, tokens and passwords, and addresses on the network
+pub(crate) fn sanitize(text: &str) -> String {
+    let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE"));
+    let text = match home {
+        Ok(home) if !home.is_empty() => text.replace(&home, "~"),
+        _ => text.to_string(),
+    };
+    let text = redact_pem(&text);
+    // `Authorization: Bearer <token>` has the token in the next word
+    let mut after_scheme = false;
+    text.split_inclusive(char::is_whitespace)
+        .map(|piece| {
+            let word = piece.trim_end();
+            if word.is_empty() {
+                return piece.to_string();
+            }
+            let redacted = if after_scheme {
+                Some(REDACTED.to_string())
+            } else {
+                redact(word)
+            };
+            after_scheme = word.eq_ignore_ascii_case("bearer") || word.eq_ignore_ascii_case("basic");
+            match redacted {
+                Some(redacted) => format!("{}{}", redacted, &piece[word.len()..]),
+                None => piece.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// `word` with what `sanitize` strips replaced, `None` if there is nothing to strip
+fn redact(word: &str) -> Option<String> {
+    let core = word.trim_matches(PUNCTUATION);
+    if core.is_empty() {
+        return None;
+    }
+    if core.contains("://") {
+        return redact_url(core).map(|url| word.replacen(core, &url, 1));
+    }
+    if let Some((user, domain)) = core.split_once('@') {
+        if !user.is_empty() && domain.contains('.') {
+            return Some("<email>".to_string());
+        }
+    }
+    if let Some(separator) = core.find(['=', ':']) {
+        let name = core[..separator]
+            .trim_matches(|c: char| !c.is_ascii_alphanumeric())
+            .to_ascii_lowercase();
+        let value = core[separator + 1..].trim_matches(PUNCTUATION);
+        if SECRET_NAMES.iter().any(|secret| name.ends_with(secret)) && !value.is_empty() {
+            return Some(word.replacen(value, REDACTED, 1));
+        }
+        if let Some(redacted) = redact(value) {
+            return Some(word.replacen(value, &redacted, 1));
+        }
+    }
+    let address = core.parse::<SocketAddr>().map(|address| address.ip()).or_else(|_| core.parse::<IpAddr>());
+    let host = core.split(':').next().unwrap_or(core);
+    if address.is_ok_and(|ip| !ip.is_loopback()) || NETWORK_SUFFIXES.iter().any(|suffix| host.ends_with(suffix)) {
+        return Some(word.replacen(core, "<address>", 1));
+    }
+    opaque(core).then(|| word.replacen(core, REDACTED, 1))
+}
+
+/// `url` without a host on the network or secrets in its query
+fn redact_url(url: &str) -> Option<String> {
+    let mut parsed = reqwest::Url::parse(url).ok()?;
+    if parsed.host_str().is_some_and(network_host) {
+        return Some("<network-url>".to_string());
+    }
+    let secret = parsed.query_pairs().any(|(name, _)| {
+        let name = name.to_ascii_lowercase();
+        SECRET_NAMES.iter().any(|secret| name.ends_with(secret))
+    });
+    if !secret && parsed.password().is_none() {
+        return None;
+    }
+    let _ = parsed.set_password(None);
+    if !secret {
+        return Some(parsed.to_string());
+    }
+    parsed.set_query(None);
+    Some(format!("{}?{}", parsed, REDACTED))
+}
+
+/// Whether `host` is another machine on the network rather than this one or a public name
+fn network_host(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    match host.parse::<IpAddr>() {
+        Ok(ip) => !ip.is_loopback(),
+        Err(_) => host != "localhost" && (!host.contains('.') || NETWORK_SUFFIXES.iter().any(|suffix| host.ends_with(suffix))),
+    }
+}
+
+/// Whether `word` looks like a token or key: a long run of hex, or of base64 with every kind
+/// of character in it, which paths and names don't have
+fn opaque(word: &str) -> bool {
+    if word.len() < MIN_SECRET_LEN {
+        return false;
+    }
+    if word.chars().all(|c| c.is_ascii_hexdigit()) {
+        return true;
+    }
+    word.chars().all(|c| c.is_ascii_alphanumeric() || "+/=-_.".contains(c))
+        && word.chars().any(|c| c.is_ascii_uppercase())
+        && word.chars().any(|c| c.is_ascii_lowercase())
+        && word.chars().any(|c| c.is_ascii_digit())
+}
+
+/// `text` with PEM blocks, as for the local certificate's key, left out
+fn redact_pem(text: &str) -> String {
+    const BEGIN: &str = "-----BEGIN";
+    const END: &str = "-----END";
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(BEGIN) {
+        redacted.push_str(&rest[..start]);
+        redacted.push_str("<key>");
+        // The end line's closing dashes, or everything after an unfinished block
+        rest = rest[start..]
+            .find(END)
+            .map(|end| start + end + END.len())
+            .and_then(|end| rest[end..].find("-----").map(|close| &rest[end + close + 5..]))
+            .unwrap_or("");
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// Last lines of the most recent shell log file
+pub(crate) fn shell_log_tail() -> Vec<String> {
+    let latest = std::fs::read_dir(log_dir()).ok().and_then(|entries| {
+        entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("shell.") && name.ends_with(".log"))
+            })
+            .max()
+    });
+    let Some(contents) = latest.and_then(|path| std::fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+
+    let lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let start = lines.len().saturating_sub(REPORT_LOG_LINES);
+    lines[start..].to_vec()
+}
@@ -0,0 +1,30 @@
+use crate::config::ShellConfig;
+
+/// Policy for the main window, `{nonce}` is replaced by the backend on every response
+///
+/// Scripts only come from the backend or carry the per-response nonce, and nothing
+/// can be framed.
+pub const DEFAULT_POLICY: &str = "default-src 'self'; \
+    script-src 'self' 'nonce-{nonce}'; \
+    style-src 'self' 'unsafe-inline'; \
+    img-src 'self' data: blob:; \
+    font-src 'self' data:; \
+    connect-src 'self' {websocket}; \
+    frame-src 'none'; \
+    frame-ancestors 'none'; \
+    object-src 'none'; \
+    base-uri 'self'; \
+    form-action 'self'";
+
+/// Content-Security-Policy the backend sends for pages shown in the main window
+///
+/// Empty when turned off in the config, which only makes sense during development.
+pub fn policy(config: &ShellConfig, base_url: &str) -> String {
+    let policy = config
+        .content_security_policy
+        .as_deref()
+        .unwrap_or(DEFAULT_POLICY);
+    // Not every webview counts the LiveView socket as 'self'
+    let websocket = base_url.replacen("http", "ws", 1);
+    policy.replace("{websocket}", websocket.trim_end_matches('/'))
+}
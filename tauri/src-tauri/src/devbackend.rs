@@ -0,0 +1,51 @@
+use std::net::{SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::loopback;
+
+/// Where `mix phx.server` listens unless `PORT` says otherwise
+pub const DEFAULT_PORT: u16 = 4000;
+/// Debug builds check for a dev server this long before spawning the sidecar instead
+const DETECT_TIMEOUT_MS: u64 = 200;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the shell attached to a dev server instead of spawning the sidecar
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// URL of the dev server to attach to, if any
+///
+/// `--dev-backend[=<port>]` attaches to one on that port. Debug builds also attach when
+/// something already listens on the default port, unless `TSW_IO_DEV_BACKEND=0`.
+pub fn resolve(flag: Option<&str>) -> Result<Option<String>, String> {
+    let port = match flag {
+        Some("") => DEFAULT_PORT,
+        Some(port) => port
+            .parse()
+            .map_err(|_| format!("Invalid dev backend port '{}'", port))?,
+        None if cfg!(debug_assertions) && std::env::var("TSW_IO_DEV_BACKEND").as_deref() != Ok("0") => {
+            if !listening(DEFAULT_PORT) {
+                return Ok(None);
+            }
+            DEFAULT_PORT
+        }
+        None => return Ok(None),
+    };
+    ENABLED.store(true, Ordering::SeqCst);
+    tracing::info!(port, "Attaching to the dev backend");
+    Ok(Some(format!("http://localhost:{}", port)))
+}
+
+fn listening(port: u16) -> bool {
+    let Ok(ip) = loopback::BIND_ADDRESS.parse() else {
+        return false;
+    };
+    TcpStream::connect_timeout(
+        &SocketAddr::new(ip, port),
+        Duration::from_millis(DETECT_TIMEOUT_MS),
+    )
+    .is_ok()
+}
@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::beam;
+use crate::config::ConfigState;
+use crate::features::FeatureFlags;
+use crate::gpu;
+use crate::lock;
+use crate::logging;
+use crate::monitor::{self, DiskUsage, HealthSample, ProcessUsage, RestartEvent};
+use crate::paths::AppPaths;
+use crate::profile::ProfileState;
+
+const WINDOW_LABEL: &str = "diagnostics";
+
+/// Snapshot shown in the diagnostics window
+#[derive(Debug, Serialize)]
+pub struct Diagnostics {
+    pub backend_url: String,
+    pub port: u16,
+    /// Whether the shell runs the backend itself rather than using an external one
+    pub managed_backend: bool,
+    pub backend_running: bool,
+    pub last_check: Option<HealthSample>,
+    pub process: Option<ProcessUsage>,
+    pub data_dir: PathBuf,
+    pub disk: DiskUsage,
+    pub restarts: Vec<RestartEvent>,
+}
+
+/// Show the diagnostics window, or focus it if it is already open
+pub fn open(handle: &AppHandle) -> tauri::Result<()> {
+    if !lock::may_show(handle) {
+        return Ok(());
+    }
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        return window.set_focus();
+    }
+    WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("diagnostics.html".into()))
+        .title("TSW IO Diagnostics")
+        .inner_size(520.0, 760.0)
+        .build()?;
+    Ok(())
+}
+
+fn collect(handle: &AppHandle) -> Diagnostics {
+    let profile = handle.state::<ProfileState>().get();
+    let process = crate::backend_pid(handle).and_then(|pid| monitor::process_usage(handle, pid));
+
+    Diagnostics {
+        backend_url: profile.base_url(),
+        port: profile.port,
+        managed_backend: profile.backend_url.is_none(),
+        backend_running: crate::backend_running(handle),
+        last_check: monitor::last_check(handle),
+        process,
+        disk: monitor::disk_usage(&profile.data_dir),
+        data_dir: profile.data_dir,
+        restarts: monitor::restarts(handle),
+    }
+}
+
+#[tauri::command]
+pub async fn get_diagnostics(app: AppHandle) -> Result<Diagnostics, String> {
+    // Walking the data directory and the process list can take a moment
+    tauri::async_runtime::spawn_blocking(move || collect(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Plain text summary of the installation and its state, for pasting into bug reports
+pub fn debug_info(handle: &AppHandle) -> String {
+    let config = handle.state::<ConfigState>().get();
+    let profile = handle.state::<ProfileState>().get();
+    let app_paths = handle.state::<AppPaths>();
+    let webview = tauri::webview_version().unwrap_or_else(|e| format!("unavailable ({})", e));
+    let backend_status = match monitor::last_check(handle) {
+        _ if profile.backend_url.is_none() && !crate::backend_running(handle) => "stopped",
+        Some(sample) if sample.healthy => "healthy",
+        Some(_) => "not responding",
+        None => "starting",
+    };
+
+    // Writing to a String can't fail
+    let mut info = String::new();
+    let _ = writeln!(info, "tsw_io {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(info, "Bundled backend {}", env!("TSW_IO_BACKEND_VERSION"));
+    let _ = writeln!(info, "Tauri {}", tauri::VERSION);
+    let _ = writeln!(info, "OS: {} ({})", std::env::consts::OS, std::env::consts::ARCH);
+    let _ = writeln!(info, "Webview: {}", webview);
+    let _ = writeln!(info);
+    let _ = writeln!(info, "Profile: {}", profile.name);
+    let _ = writeln!(info, "Backend: {} ({})", profile.base_url(), backend_status);
+    let _ = writeln!(
+        info,
+        "Backend source: {}",
+        if profile.backend_url.is_some() { "external" } else { "bundled" }
+    );
+    let _ = writeln!(info, "Data dir: {}", profile.data_dir.display());
+    let _ = writeln!(info, "Log dir: {}", app_paths.log_dir.display());
+    let _ = writeln!(info);
+    let _ = writeln!(info, "Log level: {}", config.log_level.as_str());
+    let _ = writeln!(info, "Update channel: {}", config.update_channel.as_str());
+    let _ = writeln!(info, "Offline: {}", config.offline);
+    let _ = writeln!(info, "Telemetry: {}", config.telemetry);
+    let _ = writeln!(info, "Crash reports: {}", config.crash_reports);
+    let _ = writeln!(info, "Developer mode: {}", config.developer_mode);
+    let _ = writeln!(
+        info,
+        "Hardware acceleration: {}",
+        if gpu::acceleration_disabled(&config) { "off" } else { "on" }
+    );
+    let _ = writeln!(info, "VM flags: {}", beam::flags(&config.beam).join(" "));
+    let _ = writeln!(info, "Features: {}", handle.state::<FeatureFlags>().to_env());
+    let _ = writeln!(info);
+    let _ = writeln!(
+        info,
+        "Last error: {}",
+        logging::last_error().as_deref().unwrap_or("none")
+    );
+    info
+}
+
+/// Put the debug info on the clipboard and return it
+#[tauri::command]
+pub fn copy_debug_info(app: AppHandle) -> Result<String, String> {
+    let info = debug_info(&app);
+    app.clipboard()
+        .write_text(info.clone())
+        .map_err(|e| format!("Failed to copy debug info: {}", e))?;
+    Ok(info)
+}
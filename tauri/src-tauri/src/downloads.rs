@@ -0,0 +1,462 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::api;
+use crate::{lock, preview, recent};
+use crate::profile::ProfileState;
+use crate::{secrets, tls};
+
+const WINDOW_LABEL: &str = "downloads";
+const DOWNLOADS_FILE: &str = "downloads.json";
+/// Tauri event every change to a download is emitted as
+const CHANGED_EVENT: &str = "downloads://changed";
+/// Oldest downloads are forgotten past this many
+const KEPT_DOWNLOADS: usize = 200;
+/// Large exports stream for a while, a paused download resumes where it stopped
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// What a saved file is, for the downloads list to tell them apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadKind {
+    Export,
+    Backup,
+    Attachment,
+    Report,
+    #[default]
+    File,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadStatus {
+    InProgress,
+    Paused,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+/// A file saved through the shell
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Download {
+    pub id: u64,
+    pub kind: DownloadKind,
+    pub name: String,
+    pub path: PathBuf,
+    /// Backend path the file is downloaded from, files the shell wrote at once have none
+    pub source: Option<String>,
+    pub received: u64,
+    pub total: Option<u64>,
+    pub status: DownloadStatus,
+    pub error: Option<String>,
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+}
+
+/// Every download, newest last, and the stop flags of the ones transferring
+pub struct Downloads {
+    file: PathBuf,
+    list: Mutex<Vec<Download>>,
+    running: Mutex<HashMap<u64, Arc<AtomicBool>>>,
+}
+
+impl Downloads {
+    /// Load the downloads of earlier sessions, those cut off by quitting can be resumed
+    pub fn load(config_dir: &Path) -> Self {
+        let file = config_dir.join(DOWNLOADS_FILE);
+        let mut list: Vec<Download> = std::fs::read_to_string(&file)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        for download in list.iter_mut() {
+            if download.status == DownloadStatus::InProgress {
+                download.status = DownloadStatus::Paused;
+            }
+        }
+        Downloads {
+            file,
+            list: Mutex::new(list),
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn save(&self, list: &[Download]) {
+        let result = serde_json::to_string(list)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| std::fs::write(&self.file, contents).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            tracing::warn!("Failed to write {}: {}", self.file.display(), e);
+        }
+    }
+
+    fn get(&self, id: u64) -> Result<Download, String> {
+        self.list
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|download| download.id == id)
+            .cloned()
+            .ok_or_else(|| format!("No download {}", id))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+/// Where a download is written until it is complete
+fn part_path(path: &Path) -> PathBuf {
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+fn add(handle: &AppHandle, mut download: Download) -> Download {
+    let downloads = handle.state::<Downloads>();
+    let mut list = downloads.list.lock().unwrap();
+    download.id = list.iter().map(|existing| existing.id).max().unwrap_or(0) + 1;
+    list.push(download.clone());
+    let excess = list.len().saturating_sub(KEPT_DOWNLOADS);
+    list.drain(..excess);
+    downloads.save(&list);
+    drop(list);
+    emit(handle, &download);
+    download
+}
+
+/// Change a download, saving the list when its status changed
+fn update(handle: &AppHandle, id: u64, change: impl FnOnce(&mut Download)) -> Option<Download> {
+    let downloads = handle.state::<Downloads>();
+    let mut list = downloads.list.lock().unwrap();
+    let download = list.iter_mut().find(|download| download.id == id)?;
+    let status = download.status;
+    change(download);
+    let download = download.clone();
+    if download.status != status {
+        downloads.save(&list);
+    }
+    drop(list);
+    emit(handle, &download);
+    Some(download)
+}
+
+fn emit(handle: &AppHandle, download: &Download) {
+    if let Err(e) = handle.emit(CHANGED_EVENT, download) {
+        tracing::warn!("Failed to emit {}: {}", CHANGED_EVENT, e);
+    }
+}
+
+/// Add a file the shell just wrote to the downloads list
+pub fn record(handle: &AppHandle, kind: DownloadKind, path: &Path) {
+    let size = std::fs::metadata(path).map(|metadata| metadata.len()).ok();
+    add(
+        handle,
+        Download {
+            id: 0,
+            kind,
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path: path.to_path_buf(),
+            source: None,
+            received: size.unwrap_or(0),
+            total: size,
+            status: DownloadStatus::Completed,
+            error: None,
+            started_at: now(),
+        },
+    );
+}
+
+/// Run a download on its own thread until it completes, fails or is stopped
+fn spawn(handle: &AppHandle, id: u64) {
+    let stop = Arc::new(AtomicBool::new(false));
+    handle
+        .state::<Downloads>()
+        .running
+        .lock()
+        .unwrap()
+        .insert(id, stop.clone());
+    update(handle, id, |download| {
+        download.status = DownloadStatus::InProgress;
+        download.error = None;
+    });
+
+    let handle = handle.clone();
+    std::thread::spawn(move || {
+        let result = transfer(&handle, id, &stop);
+        handle.state::<Downloads>().running.lock().unwrap().remove(&id);
+        match result {
+            Ok(()) if stop.load(Ordering::SeqCst) => {
+                // Paused or cancelled, only a cancelled one loses what it got
+                let stopped = handle.state::<Downloads>().get(id).ok();
+                if let Some(download) = stopped.filter(|download| download.status == DownloadStatus::Cancelled) {
+                    let _ = std::fs::remove_file(part_path(&download.path));
+                }
+            }
+            Ok(()) => {
+                tracing::info!(id, "Download complete");
+                update(&handle, id, |download| download.status = DownloadStatus::Completed);
+                if let Ok(download) = handle.state::<Downloads>().get(id) {
+                    preview::attach(&handle, &download.path);
+                    recent::add_file(&handle, &download.path);
+                }
+            }
+            Err(e) => {
+                tracing::warn!(id, "Download failed: {}", e);
+                update(&handle, id, |download| {
+                    download.status = DownloadStatus::Failed;
+                    download.error = Some(e);
+                });
+            }
+        }
+    });
+}
+
+/// Stream a download into its partial file, continuing from what is already there
+fn transfer(handle: &AppHandle, id: u64, stop: &AtomicBool) -> Result<(), String> {
+    let download = handle.state::<Downloads>().get(id)?;
+    let source = download.source.as_deref().ok_or("Only backend files can be downloaded again")?;
+    let part = part_path(&download.path);
+    let offset = std::fs::metadata(&part).map(|metadata| metadata.len()).unwrap_or(0);
+
+    let base_url = handle.state::<ProfileState>().get().base_url();
+    let mut request = tls::backend_client()?
+        .get(api::backend_url(&base_url, source))
+        .bearer_auth(secrets::shell_token())
+        .timeout(DOWNLOAD_TIMEOUT);
+    if offset > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request
+        .send()
+        .map_err(|e| format!("Failed to reach backend: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Backend refused the download: {}", status));
+    }
+
+    // A backend that ignores the range sends the whole file again
+    let resumed = status == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut received = if resumed { offset } else { 0 };
+    let total = response.content_length().map(|len| len + received);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&part)
+        .map_err(|e| format!("Failed to create {}: {}", part.display(), e))?;
+    update(handle, id, |download| {
+        download.received = received;
+        download.total = total;
+    });
+
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut last_progress = Instant::now();
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        let read = response
+            .read(&mut buffer)
+            .map_err(|e| format!("Download interrupted: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])
+            .map_err(|e| format!("Failed to write {}: {}", part.display(), e))?;
+        received += read as u64;
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            update(handle, id, |download| download.received = received);
+            last_progress = Instant::now();
+        }
+    }
+    if total.is_some_and(|total| received < total) {
+        return Err("Download ended early".to_string());
+    }
+
+    file.sync_all()
+        .map_err(|e| format!("Failed to write {}: {}", part.display(), e))?;
+    drop(file);
+    std::fs::rename(&part, &download.path)
+        .map_err(|e| format!("Failed to save {}: {}", download.path.display(), e))?;
+    update(handle, id, |download| {
+        download.received = received;
+        download.total = Some(received);
+    });
+    Ok(())
+}
+
+/// Set the stop flag of a running download
+fn stop(handle: &AppHandle, id: u64) {
+    if let Some(stop) = handle.state::<Downloads>().running.lock().unwrap().get(&id) {
+        stop.store(true, Ordering::SeqCst);
+    }
+}
+
+fn completed(handle: &AppHandle, id: u64) -> Result<PathBuf, String> {
+    let download = handle.state::<Downloads>().get(id)?;
+    if download.status != DownloadStatus::Completed {
+        return Err(format!("{} isn't downloaded yet", download.name));
+    }
+    if !download.path.exists() {
+        return Err(format!("{} was moved or deleted", download.path.display()));
+    }
+    Ok(download.path)
+}
+
+/// Show the downloads window, or focus it if it is already open
+pub fn open(handle: &AppHandle) -> tauri::Result<()> {
+    if !lock::may_show(handle) {
+        return Ok(());
+    }
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        return window.set_focus();
+    }
+    WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("downloads.html".into()))
+        .title("TSW IO Downloads")
+        .inner_size(560.0, 480.0)
+        .build()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn open_downloads_window(app: AppHandle) -> Result<(), String> {
+    open(&app).map_err(|e| format!("Failed to open downloads: {}", e))
+}
+
+/// Every download, newest first
+#[tauri::command]
+pub fn list_downloads(app: AppHandle) -> Vec<Download> {
+    let mut list = app.state::<Downloads>().list.lock().unwrap().clone();
+    list.reverse();
+    list
+}
+
+/// Download a file from the backend to where the user picks
+///
+/// `source` is a path on the backend, like `/exports/1.json`. Returns nothing when the
+/// user cancels the save dialog.
+#[tauri::command]
+pub async fn start_download(
+    app: AppHandle,
+    source: String,
+    name: String,
+    kind: Option<DownloadKind>,
+) -> Result<Option<Download>, String> {
+    if !source.starts_with('/') || source.starts_with("//") {
+        return Err("Only files from the backend can be downloaded".to_string());
+    }
+    let Some(path) = app
+        .dialog()
+        .file()
+        .set_title("Save download")
+        .set_file_name(&name)
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+    else {
+        return Ok(None);
+    };
+
+    let download = add(
+        &app,
+        Download {
+            id: 0,
+            kind: kind.unwrap_or_default(),
+            name,
+            path,
+            source: Some(source),
+            received: 0,
+            total: None,
+            status: DownloadStatus::InProgress,
+            error: None,
+            started_at: now(),
+        },
+    );
+    tracing::info!(id = download.id, "Downloading to {}", download.path.display());
+    spawn(&app, download.id);
+    Ok(Some(download))
+}
+
+/// Stop a download, keeping what it got so far
+#[tauri::command]
+pub fn pause_download(app: AppHandle, id: u64) -> Result<(), String> {
+    if app.state::<Downloads>().get(id)?.status != DownloadStatus::InProgress {
+        return Ok(());
+    }
+    update(&app, id, |download| download.status = DownloadStatus::Paused);
+    stop(&app, id);
+    Ok(())
+}
+
+/// Continue a paused or failed download where it stopped
+#[tauri::command]
+pub fn resume_download(app: AppHandle, id: u64) -> Result<(), String> {
+    let download = app.state::<Downloads>().get(id)?;
+    if download.source.is_none() || !matches!(download.status, DownloadStatus::Paused | DownloadStatus::Failed) {
+        return Err(format!("{} can't be resumed", download.name));
+    }
+    if app.state::<Downloads>().running.lock().unwrap().contains_key(&id) {
+        return Err(format!("{} is still stopping, try again in a moment", download.name));
+    }
+    spawn(&app, id);
+    Ok(())
+}
+
+/// Stop a download and remove what it got so far
+#[tauri::command]
+pub fn cancel_download(app: AppHandle, id: u64) -> Result<(), String> {
+    let download = app.state::<Downloads>().get(id)?;
+    match download.status {
+        DownloadStatus::InProgress => {
+            update(&app, id, |download| download.status = DownloadStatus::Cancelled);
+            // The transfer removes the partial file once it stopped writing to it
+            stop(&app, id);
+        }
+        DownloadStatus::Paused | DownloadStatus::Failed => {
+            update(&app, id, |download| download.status = DownloadStatus::Cancelled);
+            let _ = std::fs::remove_file(part_path(&download.path));
+        }
+        DownloadStatus::Completed | DownloadStatus::Cancelled => {}
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn open_download(app: AppHandle, id: u64) -> Result<(), String> {
+    let path = completed(&app, id)?;
+    tauri_plugin_opener::open_path(&path, None::<&str>)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+#[tauri::command]
+pub fn reveal_download(app: AppHandle, id: u64) -> Result<(), String> {
+    let path = completed(&app, id)?;
+    tauri_plugin_opener::reveal_item_in_dir(&path)
+        .map_err(|e| format!("Failed to reveal {}: {}", path.display(), e))
+}
+
+/// Forget finished downloads, the files stay where they are
+#[tauri::command]
+pub fn clear_downloads(app: AppHandle) {
+    let downloads = app.state::<Downloads>();
+    let mut list = downloads.list.lock().unwrap();
+    list.retain(|download| matches!(download.status, DownloadStatus::InProgress | DownloadStatus::Paused));
+    downloads.save(&list);
+}
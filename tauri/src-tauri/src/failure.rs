@@ -0,0 +1,283 @@
+use serde::Serialize;
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::audit::{self, AuditAction};
+use crate::monitor::{self, BackendExit};
+use crate::paths::AppPaths;
+use crate::profile::ProfileState;
+
+const WINDOW_LABEL: &str = "failure";
+/// Output lines shown under the next steps
+const DETAIL_LINES: usize = 20;
+
+/// Why the backend didn't start, as far as its output and exit status tell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    PortInUse,
+    DatabaseLocked,
+    MigrationFailed,
+    MissingSharedLibrary,
+    CorruptedInstall,
+    /// The encrypted data directory couldn't be unlocked
+    DataLocked,
+    /// SQLite doesn't recognize the database file, restoring a backup is the way out
+    DatabaseCorrupted,
+    /// Another machine runs a backend against the shared data directory
+    DataDirInUse,
+    /// Exited for a reason we don't recognize
+    Crashed,
+    /// Still running but never became ready
+    Timeout,
+}
+
+/// Output fragments (lowercase) that identify each failure, checked in order
+const PATTERNS: &[(FailureKind, &[&str])] = &[
+    (FailureKind::PortInUse, &["eaddrinuse", "address already in use"]),
+    (
+        FailureKind::DatabaseLocked,
+        &["database is locked", "database_busy", "sqlite_busy"],
+    ),
+    (
+        FailureKind::DatabaseCorrupted,
+        &[
+            "file is not a database",
+            "sqlite_notadb",
+            "database disk image is malformed",
+            "sqlite_corrupt",
+        ],
+    ),
+    (
+        FailureKind::MissingSharedLibrary,
+        &[
+            "error while loading shared libraries",
+            "cannot open shared object",
+            "library not loaded",
+            ".dll was not found",
+        ],
+    ),
+    (
+        FailureKind::MigrationFailed,
+        &["migrationerror", "migration failed", "could not run migrations"],
+    ),
+    (
+        FailureKind::CorruptedInstall,
+        &["exec format error", "bad cpu type", "failed to extract", "checksum mismatch"],
+    ),
+];
+
+pub fn classify(output: &[String], exit: Option<&BackendExit>) -> FailureKind {
+    let output = output.join("\n").to_lowercase();
+    if let Some((kind, _)) = PATTERNS
+        .iter()
+        .find(|(_, fragments)| fragments.iter().any(|fragment| output.contains(fragment)))
+    {
+        return *kind;
+    }
+
+    match exit.and_then(|exit| exit.code) {
+        // The shell couldn't run the binary it was given
+        Some(126) | Some(127) => FailureKind::CorruptedInstall,
+        _ if exit.is_some() => FailureKind::Crashed,
+        _ => FailureKind::Timeout,
+    }
+}
+
+impl FailureKind {
+    pub fn title(self) -> &'static str {
+        match self {
+            FailureKind::PortInUse => "Another program is using the TSW IO port",
+            FailureKind::DatabaseLocked => "The database is in use",
+            FailureKind::MigrationFailed => "The database could not be updated",
+            FailureKind::MissingSharedLibrary => "A system library is missing",
+            FailureKind::CorruptedInstall => "The installation looks damaged",
+            FailureKind::DataLocked => "Your data could not be unlocked",
+            FailureKind::DatabaseCorrupted => "The database is damaged",
+            FailureKind::DataDirInUse => "Your data is in use on another computer",
+            FailureKind::Crashed => "The server stopped unexpectedly",
+            FailureKind::Timeout => "The server took too long to start",
+        }
+    }
+
+    pub fn next_steps(self, port: u16) -> Vec<String> {
+        match self {
+            FailureKind::PortInUse => vec![
+                format!("Close any other program using port {}, including another copy of TSW IO.", port),
+                "Or set a different port in config.toml and try again.".to_string(),
+            ],
+            FailureKind::DatabaseLocked => vec![
+                "Make sure no other copy of TSW IO is running, also on other accounts.".to_string(),
+                "Close database tools or sync clients that may have the database open.".to_string(),
+            ],
+            FailureKind::MigrationFailed => vec![
+                "Try again, a migration can fail if the app was closed during an update.".to_string(),
+                "If it keeps failing, restore a backup or report the problem with the logs.".to_string(),
+            ],
+            FailureKind::MissingSharedLibrary => vec![
+                "Install the latest system updates.".to_string(),
+                "On Linux, install the libraries named in the details below.".to_string(),
+            ],
+            FailureKind::CorruptedInstall => vec![
+                "Reinstall TSW IO from the latest download.".to_string(),
+                "Check that your antivirus did not quarantine any of its files.".to_string(),
+            ],
+            FailureKind::DataLocked => vec![
+                "Try again and confirm it's you when asked.".to_string(),
+                "Keep the TSW IO entry in your keychain, the data can't be read without it.".to_string(),
+            ],
+            FailureKind::DatabaseCorrupted => vec![
+                "Restore the most recent backup of your data.".to_string(),
+                "Or start in safe mode to export what can still be read.".to_string(),
+            ],
+            FailureKind::DataDirInUse => vec![
+                "Quit TSW IO on the other computer using the same data folder and try again.".to_string(),
+                "If TSW IO crashed there, try again in a few minutes.".to_string(),
+                "To use TSW IO on several computers, give each its own local data folder.".to_string(),
+            ],
+            FailureKind::Crashed | FailureKind::Timeout => vec![
+                "Try again.".to_string(),
+                "If it keeps happening, report the problem and attach the logs.".to_string(),
+            ],
+        }
+    }
+}
+
+/// What the failure window shows
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupFailure {
+    pub kind: FailureKind,
+    pub title: &'static str,
+    pub next_steps: Vec<String>,
+    /// Last lines of backend output
+    pub details: Vec<String>,
+}
+
+/// The failure of the last launch, if it failed
+#[derive(Default)]
+pub struct LastFailure(pub RwLock<Option<StartupFailure>>);
+
+/// Record why the backend didn't start and open the failure window
+pub fn show(handle: &AppHandle, kind: FailureKind) {
+    let port = handle.state::<ProfileState>().get().port;
+    let output = monitor::recent_output(handle);
+    let start = output.len().saturating_sub(DETAIL_LINES);
+    *handle.state::<LastFailure>().0.write().unwrap() = Some(StartupFailure {
+        kind,
+        title: kind.title(),
+        next_steps: kind.next_steps(port),
+        details: output[start..].to_vec(),
+    });
+    if crate::headless::enabled() {
+        crate::headless::fail(handle, Some(kind), kind.title());
+        return;
+    }
+
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+    let result = WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("failure.html".into()))
+        .title("TSW IO")
+        .inner_size(560.0, 480.0)
+        .resizable(false)
+        .center()
+        .build();
+    if let Err(e) = result {
+        // Without a window there is nothing left to show, so fall back to the crash dialog
+        crate::crash::report(&format!("{}: {}", kind.title(), e));
+        crate::logging::flush();
+        std::process::exit(1);
+    }
+}
+
+#[tauri::command]
+pub fn get_startup_failure(failure: tauri::State<'_, LastFailure>) -> Option<StartupFailure> {
+    failure.0.read().unwrap().clone()
+}
+
+#[tauri::command]
+pub async fn retry_startup(app: AppHandle) -> Result<(), String> {
+    audit::record(&app, AuditAction::BackendRestart, "Retried after a failed start");
+    crate::stop_backend(&app);
+    crate::launch(&app).map_err(|e| e.to_string())?;
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn open_log_dir(app: AppHandle) -> Result<(), String> {
+    let log_dir = app.state::<AppPaths>().log_dir.clone();
+    app.opener()
+        .open_path(log_dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open {}: {}", log_dir.display(), e))
+}
+
+#[tauri::command]
+pub fn quit_app(app: AppHandle) {
+    crate::stop_backend(&app);
+    app.exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(output: &[&str]) -> Vec<String> {
+        output.iter().map(|line| line.to_string()).collect()
+    }
+
+    fn exit(code: i32) -> BackendExit {
+        BackendExit {
+            code: Some(code),
+            signal: None,
+        }
+    }
+
+    #[test]
+    fn recognizes_the_output() {
+        let output = lines(&["Running TswIoWeb.Endpoint", "** (Bandit) EADDRINUSE on port 4000"]);
+
+        assert_eq!(classify(&output, Some(&exit(1))), FailureKind::PortInUse);
+        assert_eq!(
+            classify(&lines(&["Exqlite.Error: database is locked"]), None),
+            FailureKind::DatabaseLocked
+        );
+        assert_eq!(
+            classify(&lines(&["error: file is not a database"]), Some(&exit(1))),
+            FailureKind::DatabaseCorrupted
+        );
+        assert_eq!(
+            classify(&lines(&["error while loading shared libraries: libncurses.so.6"]), Some(&exit(127))),
+            FailureKind::MissingSharedLibrary
+        );
+    }
+
+    #[test]
+    fn takes_the_first_pattern_that_matches() {
+        let output = lines(&["could not run migrations", "database is locked"]);
+
+        assert_eq!(classify(&output, Some(&exit(1))), FailureKind::DatabaseLocked);
+    }
+
+    #[test]
+    fn falls_back_on_the_exit_status() {
+        let output = lines(&["something else went wrong"]);
+
+        assert_eq!(classify(&output, Some(&exit(126))), FailureKind::CorruptedInstall);
+        assert_eq!(classify(&output, Some(&exit(127))), FailureKind::CorruptedInstall);
+        assert_eq!(classify(&output, Some(&exit(1))), FailureKind::Crashed);
+        let killed = BackendExit {
+            code: None,
+            signal: Some(9),
+        };
+        assert_eq!(classify(&output, Some(&killed)), FailureKind::Crashed);
+    }
+
+    #[test]
+    fn times_out_while_still_running() {
+        assert_eq!(classify(&[], None), FailureKind::Timeout);
+    }
+}
@@ -0,0 +1,120 @@
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_shell::process::Command;
+use tauri_plugin_shell::ShellExt;
+
+use crate::localhttp::{self, respond};
+use crate::{loopback, monitor};
+
+const SCRIPT_FLAG: &str = "--fake-sidecar=";
+
+static HEALTHY: AtomicBool = AtomicBool::new(false);
+
+/// Command running the fake in place of the sidecar, when a script was given
+///
+/// With `TSW_IO_FAKE_SIDECAR=<script>` set, the shell spawns itself with `--fake-sidecar=<script>`
+/// instead of the Burrito release, so tests control when it gets ready, what it prints and how it
+/// dies.
+pub fn command(handle: &AppHandle) -> Option<Result<Command, String>> {
+    let script = std::env::var("TSW_IO_FAKE_SIDECAR").ok().filter(|script| !script.is_empty())?;
+    tracing::warn!(%script, "Using a fake backend sidecar");
+    Some(
+        std::env::current_exe()
+            .map(|exe| handle.shell().command(exe).arg(format!("{}{}", SCRIPT_FLAG, script)))
+            .map_err(|e| format!("Failed to find the shell executable: {}", e)),
+    )
+}
+
+/// Script to run when this process was started as the fake
+pub fn script_arg() -> Option<String> {
+    std::env::args().find_map(|arg| arg.strip_prefix(SCRIPT_FLAG).map(str::to_string))
+}
+
+/// Run `script` as the fake sidecar, never returning
+///
+/// The fake gets the sidecar's environment and runs the script, one step per line (`#` starts a
+/// comment):
+///
+/// - `print <text>` / `stderr <text>`: write a line of output, like `eaddrinuse` to fake a failure
+/// - `sleep <ms>`: wait before the next step
+/// - `listen`: accept connections on `PORT`, `/api/health` answers 503 until `healthy`
+/// - `healthy` / `unhealthy`: make `/api/health` answer 200 or 503
+/// - `ready`: print the ready signal, when the shell asked for one
+/// - `exit <code>`: exit with that code
+/// - `crash`: abort, so the process dies from a signal where there are signals
+///
+/// Once the script ran out, the fake keeps serving until the shell stops it.
+pub fn run(script: &str) -> ! {
+    let steps = match std::fs::read_to_string(script) {
+        Ok(steps) => steps,
+        Err(e) => {
+            eprintln!("Failed to read fake sidecar script {}: {}", script, e);
+            std::process::exit(2);
+        }
+    };
+
+    for line in steps.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (step, argument) = line.split_once(' ').unwrap_or((line, ""));
+        match step {
+            "print" => println!("{}", argument),
+            "stderr" => eprintln!("{}", argument),
+            "sleep" => std::thread::sleep(Duration::from_millis(argument.parse().unwrap_or(0))),
+            "listen" => listen(),
+            "healthy" => HEALTHY.store(true, Ordering::SeqCst),
+            "unhealthy" => HEALTHY.store(false, Ordering::SeqCst),
+            "ready" if std::env::var("TSW_IO_READY_SIGNAL").as_deref() == Ok("1") => {
+                println!("{}", monitor::READY_MARKER)
+            }
+            "ready" => {}
+            "exit" => std::process::exit(argument.parse().unwrap_or(1)),
+            "crash" => std::process::abort(),
+            other => {
+                eprintln!("Unknown fake sidecar step: {}", other);
+                std::process::exit(2);
+            }
+        }
+        let _ = std::io::stdout().flush();
+    }
+
+    loop {
+        std::thread::sleep(Duration::from_secs(60));
+    }
+}
+
+fn listen() {
+    let port: u16 = std::env::var("PORT")
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(4000);
+    let listener = match TcpListener::bind((loopback::BIND_ADDRESS, port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            // Worded like the real backend, so the shell classifies it the same way
+            eprintln!("Failed to listen on port {}: eaddrinuse ({})", port, e);
+            std::process::exit(1);
+        }
+    };
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let _ = serve(stream);
+            });
+        }
+    });
+}
+
+fn serve(stream: TcpStream) -> std::io::Result<()> {
+    match localhttp::read_request(&stream)?.path() {
+        "/api/health" if HEALTHY.load(Ordering::SeqCst) => {
+            respond(stream, 200, "application/json", r#"{"status":"ok"}"#)
+        }
+        "/api/health" => respond(stream, 503, "application/json", r#"{"status":"starting"}"#),
+        _ => respond(stream, 200, "text/html", "<!DOCTYPE html><title>Fake sidecar</title>"),
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::paths::AppPaths;
+
+const MARKER_FILE: &str = "clean_shutdown.json";
+
+/// Left behind when the app quits with the backend running fine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CleanShutdown {
+    version: String,
+    data_dir: PathBuf,
+    /// Whether the data directory was found to be shared, so it needed the lock
+    shared: bool,
+}
+
+/// What the previous run found, while the first launch of this run may still rely on it
+#[derive(Default)]
+pub struct FastPath(Mutex<Option<CleanShutdown>>);
+
+impl FastPath {
+    /// Take the marker of the previous run, if it quit cleanly with this version
+    ///
+    /// The marker is removed right away, so a crash during this run means a full startup next time.
+    pub fn load(log_dir: &Path) -> Self {
+        let path = log_dir.join(MARKER_FILE);
+        let marker = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<CleanShutdown>(&contents).ok())
+            .filter(|marker| marker.version == env!("CARGO_PKG_VERSION"));
+        let _ = std::fs::remove_file(&path);
+        FastPath(Mutex::new(marker))
+    }
+}
+
+/// What launch checks may be skipped
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Skips {
+    /// Don't look for a network share or cloud folder, the directory wasn't one
+    pub storage_detection: bool,
+    /// Don't check that the backend is only reachable on loopback
+    pub exposure_scan: bool,
+    /// Poll the backend in short steps, it started fine before and likely will again
+    pub fast_polling: bool,
+}
+
+/// Checks the first launch can skip because the previous run passed them against `data_dir`
+///
+/// Only the first launch of a run can: relaunches follow a crash or a change.
+pub fn take(handle: &AppHandle, data_dir: &Path) -> Skips {
+    let Some(marker) = handle.state::<FastPath>().0.lock().unwrap().take() else {
+        return Skips::default();
+    };
+    if marker.data_dir != data_dir {
+        return Skips::default();
+    }
+    tracing::info!("Previous run shut down cleanly, skipping launch checks");
+    Skips {
+        storage_detection: !marker.shared,
+        exposure_scan: true,
+        fast_polling: true,
+    }
+}
+
+/// Remember that this run quits cleanly, so the next one can start faster
+pub fn record_clean_shutdown(handle: &AppHandle, data_dir: &Path, shared: bool) {
+    let marker = CleanShutdown {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        data_dir: data_dir.to_path_buf(),
+        shared,
+    };
+    let path = handle.state::<AppPaths>().log_dir.join(MARKER_FILE);
+    let result = serde_json::to_string(&marker)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| std::fs::write(&path, contents).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        tracing::warn!("Failed to write {}: {}", path.display(), e);
+    }
+}
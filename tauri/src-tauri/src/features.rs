@@ -0,0 +1,161 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::config::ShellConfig;
+
+/// Flag file release builds fetch when the config doesn't name one
+const BUILT_IN_FLAGS_URL: Option<&str> = option_env!("TSW_IO_FEATURE_FLAGS_URL");
+/// Cached copy of the remote flag file, applied on the next launch
+const REMOTE_CACHE_FILE: &str = "feature-flags.json";
+const REMOTE_FETCH_TIMEOUT_SECS: u64 = 10;
+
+/// Flags the shell knows about, with their default state
+const BUILTIN_FLAGS: &[(&str, bool)] = &[("tray", true), ("telemetry", true)];
+/// Flags the remote flag file can switch off whatever the config says
+const KILL_SWITCHES: &[&str] = &["telemetry"];
+
+/// The remote flag file fetched during this run, see `FeatureFlags::is_enabled_now`
+static FETCHED: RwLock<Option<BTreeMap<String, bool>>> = RwLock::new(None);
+
+/// Feature flags in effect for this run
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureFlags(pub BTreeMap<String, bool>);
+
+impl FeatureFlags {
+    /// Built-in defaults, overridden by the remote flag file, overridden by the config, see `merge`
+    pub fn resolve(config: &ShellConfig, config_dir: &Path) -> FeatureFlags {
+        FeatureFlags(merge(&load_remote_cache(&cache_path(config_dir)), &config.features))
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.0.get(name).copied().unwrap_or(false)
+    }
+
+    /// `is_enabled`, with the flag file fetched during this run in place of the cached one
+    ///
+    /// For the flags that can take effect without a restart, like the telemetry kill switch.
+    pub fn is_enabled_now(&self, name: &str, config: &ShellConfig) -> bool {
+        let fetched = FETCHED.read().ok().and_then(|fetched| fetched.clone());
+        let Some(remote) = fetched else {
+            return self.is_enabled(name);
+        };
+        merge(&remote, &config.features).get(name).copied().unwrap_or(false)
+    }
+
+    /// Enabled flags as a comma separated list, for the sidecar environment
+    pub fn to_env(&self) -> String {
+        self.0
+            .iter()
+            .filter(|(_, enabled)| **enabled)
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Built-in defaults, overridden by `remote`, overridden by `configured`, except for kill
+/// switches `remote` turns off
+fn merge(remote: &BTreeMap<String, bool>, configured: &BTreeMap<String, bool>) -> BTreeMap<String, bool> {
+    let mut flags: BTreeMap<String, bool> = BUILTIN_FLAGS
+        .iter()
+        .map(|(name, enabled)| (name.to_string(), *enabled))
+        .collect();
+    flags.extend(remote.clone());
+    flags.extend(configured.clone());
+    for name in KILL_SWITCHES {
+        if remote.get(*name) == Some(&false) {
+            flags.insert(name.to_string(), false);
+        }
+    }
+    flags
+}
+
+fn cache_path(config_dir: &Path) -> PathBuf {
+    config_dir.join(REMOTE_CACHE_FILE)
+}
+
+fn load_remote_cache(path: &Path) -> BTreeMap<String, bool> {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return BTreeMap::new();
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring cached feature flags: {}", e);
+        BTreeMap::new()
+    })
+}
+
+/// The flag file to fetch, `None` when air-gapped or when there is none
+pub fn remote_url(config: &ShellConfig) -> Option<String> {
+    if config.offline {
+        return None;
+    }
+    config
+        .feature_flags_url
+        .clone()
+        .or_else(|| BUILT_IN_FLAGS_URL.map(str::to_string))
+}
+
+/// Download the remote flag file in the background so the next launch can use it
+///
+/// Flags read with `is_enabled_now` take it right away.
+pub fn refresh_remote(url: String, config_dir: PathBuf) {
+    std::thread::spawn(move || {
+        let result = crate::network::client_builder()
+            .timeout(Duration::from_secs(REMOTE_FETCH_TIMEOUT_SECS))
+            .build()
+            .and_then(|client| client.get(&url).send())
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.json::<BTreeMap<String, bool>>());
+
+        match result {
+            Ok(flags) => {
+                let contents = serde_json::to_string_pretty(&flags).unwrap_or_default();
+                if let Err(e) = std::fs::write(cache_path(&config_dir), contents) {
+                    tracing::warn!("Failed to cache feature flags: {}", e);
+                }
+                if let Ok(mut fetched) = FETCHED.write() {
+                    *fetched = Some(flags);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to fetch feature flags: {}", e),
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_feature_flags(flags: tauri::State<'_, FeatureFlags>) -> FeatureFlags {
+    flags.inner().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(entries: &[(&str, bool)]) -> BTreeMap<String, bool> {
+        entries.iter().map(|(name, enabled)| (name.to_string(), *enabled)).collect()
+    }
+
+    #[test]
+    fn config_wins_over_remote_wins_over_builtin() {
+        let merged = merge(&flags(&[("tray", false), ("widgets", true)]), &flags(&[("widgets", false)]));
+
+        assert_eq!(merged, flags(&[("tray", false), ("telemetry", true), ("widgets", false)]));
+    }
+
+    #[test]
+    fn remote_kill_switch_wins_over_config() {
+        let merged = merge(&flags(&[("telemetry", false)]), &flags(&[("telemetry", true)]));
+
+        assert_eq!(merged.get("telemetry"), Some(&false));
+    }
+
+    #[test]
+    fn config_can_still_turn_a_kill_switch_off() {
+        let merged = merge(&flags(&[("telemetry", true)]), &flags(&[("telemetry", false)]));
+
+        assert_eq!(merged.get("telemetry"), Some(&false));
+    }
+}
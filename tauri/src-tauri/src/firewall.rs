@@ -0,0 +1,200 @@
+use std::process::Command;
+
+/// Let other machines on the network through to the backend on `port`
+///
+/// Changing firewall rules needs administrator rights, so the system asks for them. A
+/// firewall that is off, or that the shell doesn't know, is left alone.
+pub fn open(port: u16) -> Result<(), String> {
+    platform::open(port)
+}
+
+/// Take back what `open` let through
+pub fn close(port: u16) -> Result<(), String> {
+    platform::close(port)
+}
+
+fn run(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} failed: {}", program, status))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::process::Command;
+
+    use super::run;
+
+    const RULE_NAME: &str = "tsw_io LAN access";
+    const MDNS_RULE_NAME: &str = "tsw_io mDNS";
+
+    fn rule_exists() -> bool {
+        Command::new("netsh")
+            .args(["advfirewall", "firewall", "show", "rule"])
+            .arg(format!("name={}", RULE_NAME))
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    /// netsh only changes rules as administrator, PowerShell shows the UAC prompt for it, once
+    /// for all of `commands`
+    fn elevated_netsh(commands: &str) -> Result<(), String> {
+        let script = format!(
+            "$cmd = Start-Process cmd -ArgumentList '/c {}' -Verb RunAs -Wait -WindowStyle Hidden -PassThru; exit $cmd.ExitCode",
+            commands.replace('\'', "''")
+        );
+        run("powershell", &["-NoProfile", "-NonInteractive", "-Command", &script])
+            .map_err(|e| format!("Failed to change the Windows Firewall rules: {}", e))
+    }
+
+    pub fn open(port: u16) -> Result<(), String> {
+        if rule_exists() {
+            return Ok(());
+        }
+        let exe = std::env::current_exe().map_err(|e| format!("Failed to find the app: {}", e))?;
+        // Only on private networks, never on public Wi-Fi. The shell answers mDNS queries itself
+        elevated_netsh(&format!(
+            "netsh advfirewall firewall add rule name=\"{}\" dir=in action=allow protocol=TCP localport={} profile=private && \
+             netsh advfirewall firewall add rule name=\"{}\" dir=in action=allow protocol=UDP localport=5353 program=\"{}\" profile=private",
+            RULE_NAME,
+            port,
+            MDNS_RULE_NAME,
+            exe.display()
+        ))
+    }
+
+    pub fn close(_port: u16) -> Result<(), String> {
+        if !rule_exists() {
+            return Ok(());
+        }
+        elevated_netsh(&format!(
+            "netsh advfirewall firewall delete rule name=\"{}\" & netsh advfirewall firewall delete rule name=\"{}\"",
+            MDNS_RULE_NAME, RULE_NAME
+        ))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    use super::run;
+
+    const SOCKETFILTERFW: &str = "/usr/libexec/ApplicationFirewall/socketfilterfw";
+
+    fn enabled() -> bool {
+        Command::new(SOCKETFILTERFW)
+            .arg("--getglobalstate")
+            .output()
+            .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("enabled"))
+    }
+
+    /// The application firewall filters by program: the backend listens for the app, the shell
+    /// answers mDNS queries
+    fn programs() -> Result<[PathBuf; 2], String> {
+        let exe = std::env::current_exe().map_err(|e| format!("Failed to find the app: {}", e))?;
+        Ok([exe.with_file_name("tsw_io_backend"), exe])
+    }
+
+    /// Run socketfilterfw as administrator, macOS asks for the password
+    fn elevated(args: &[&str]) -> Result<(), String> {
+        let programs = programs()?;
+        let commands = programs
+            .iter()
+            .flat_map(|program| args.iter().map(move |arg| format!("{} {} '{}'", SOCKETFILTERFW, arg, program.display())))
+            .collect::<Vec<_>>()
+            .join("; ");
+        let script = format!("do shell script \"{}\" with administrator privileges", commands);
+        run("osascript", &["-e", &script]).map_err(|e| format!("Failed to change the firewall settings: {}", e))
+    }
+
+    pub fn open(_port: u16) -> Result<(), String> {
+        if !enabled() {
+            return Ok(());
+        }
+        elevated(&["--add", "--unblockapp"])
+    }
+
+    pub fn close(_port: u16) -> Result<(), String> {
+        if !enabled() {
+            return Ok(());
+        }
+        elevated(&["--remove"])
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::process::Command;
+
+    use super::run;
+
+    const MDNS_RULE: &str = "5353/udp";
+
+    fn firewalld_running() -> bool {
+        Command::new("firewall-cmd")
+            .arg("--state")
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    fn ufw_active() -> bool {
+        Command::new("systemctl")
+            .args(["is-active", "--quiet", "ufw"])
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    /// Both need root, pkexec asks for the password
+    ///
+    /// mDNS is let in too, for the advertisement to be found. `close` leaves it open, other
+    /// programs may rely on it.
+    pub fn open(port: u16) -> Result<(), String> {
+        let rule = format!("{}/tcp", port);
+        if firewalld_running() {
+            // Not permanent, the port closes again at the latest on reboot
+            run(
+                "pkexec",
+                &["firewall-cmd", &format!("--add-port={}", rule), "--add-service=mdns"],
+            )
+        } else if ufw_active() {
+            // One command, for one password prompt
+            let script = format!("ufw allow {} && ufw allow {}", rule, MDNS_RULE);
+            run("pkexec", &["sh", "-c", &script])
+        } else {
+            Ok(())
+        }
+        .map_err(|e| format!("Failed to open port {} in the firewall: {}", port, e))
+    }
+
+    pub fn close(port: u16) -> Result<(), String> {
+        let rule = format!("{}/tcp", port);
+        if firewalld_running() {
+            run("pkexec", &["firewall-cmd", &format!("--remove-port={}", rule)])
+        } else if ufw_active() {
+            run("pkexec", &["ufw", "delete", "allow", &rule])
+        } else {
+            Ok(())
+        }
+        .map_err(|e| format!("Failed to close port {} in the firewall: {}", port, e))
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+mod platform {
+    pub fn open(_port: u16) -> Result<(), String> {
+        tracing::warn!("Opening the firewall is not supported on this platform");
+        Ok(())
+    }
+
+    pub fn close(_port: u16) -> Result<(), String> {
+        Ok(())
+    }
+}
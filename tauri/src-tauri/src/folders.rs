@@ -0,0 +1,390 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::api;
+use crate::commands;
+use crate::config::{self, ConfigState};
+use crate::paths::AppPaths;
+use crate::profile::ProfileState;
+use crate::supervisor::{self, SupervisorState};
+use crate::tray;
+use crate::upload;
+
+/// What was synced from each folder, kept over restarts
+const STATE_FILE: &str = "watched-folders.json";
+/// How often the folders are looked through, there is no file system watcher to tell
+const SCAN_INTERVAL: Duration = Duration::from_secs(10);
+/// Files changed more recently are left for the next scan, they may still be written to
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+/// A folder that failed is tried again after this, doubling up to `MAX_RETRY_DELAY`
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30 * 60);
+/// Conflict copies shown per folder
+const MAX_CONFLICTS: usize = 10;
+
+/// How each watched folder is doing, for the settings and the tray
+static STATUS: Mutex<BTreeMap<PathBuf, FolderStatus>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FolderState {
+    /// Not looked through yet, as while the backend starts
+    Waiting,
+    UpToDate,
+    Syncing,
+    /// Something failed, tried again at `retry_at`
+    Retrying,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderStatus {
+    pub path: PathBuf,
+    pub state: FolderState,
+    /// Files synced to the backend
+    pub files: usize,
+    /// Files changed since, waiting to be uploaded
+    pub pending: usize,
+    /// Names the backend gave files that changed on both sides, the latest last
+    pub conflicts: Vec<String>,
+    pub last_error: Option<String>,
+    /// Seconds since the Unix epoch
+    pub last_synced_at: Option<u64>,
+    pub retry_at: Option<u64>,
+}
+
+impl FolderStatus {
+    fn new(path: &Path) -> Self {
+        FolderStatus {
+            path: path.to_path_buf(),
+            state: FolderState::Waiting,
+            files: 0,
+            pending: 0,
+            conflicts: Vec::new(),
+            last_error: None,
+            last_synced_at: None,
+            retry_at: None,
+        }
+    }
+
+    /// One line for the tray menu
+    pub fn summary(&self) -> String {
+        let name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.path.display().to_string());
+        let state = match self.state {
+            FolderState::Waiting => "waiting for the server".to_string(),
+            FolderState::UpToDate if !self.conflicts.is_empty() => {
+                format!("up to date, {} conflicts", self.conflicts.len())
+            }
+            FolderState::UpToDate => "up to date".to_string(),
+            FolderState::Syncing => format!("syncing {} files", self.pending),
+            FolderState::Retrying => "failed, trying again".to_string(),
+        };
+        format!("{}: {}", name, state)
+    }
+}
+
+/// A file as it was when it was last synced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SyncedFile {
+    size: u64,
+    /// Milliseconds since the Unix epoch
+    modified: u64,
+    hash: String,
+    /// Hash of the backend's version, which the next change is based on
+    remote: Option<String>,
+}
+
+/// Synced files of each folder, by path relative to it
+type SyncState = BTreeMap<PathBuf, BTreeMap<String, SyncedFile>>;
+
+fn load_state(path: &Path) -> SyncState {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(path: &Path, state: &SyncState) {
+    let result = serde_json::to_string(state)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| std::fs::write(path, contents).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        tracing::warn!("Failed to write {}: {}", path.display(), e);
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Name of the folder on the backend, told apart from others of the same name by its path
+fn folder_key(folder: &Path) -> String {
+    let name: String = folder
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let digest = Sha256::digest(folder.to_string_lossy().as_bytes());
+    let suffix: String = digest[..4].iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}-{}", name, suffix)
+}
+
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            read => hasher.update(&buffer[..read]),
+        }
+    }
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Files under `dir` by their path relative to `root`, with `/` between the parts
+///
+/// Hidden files and editors' backups are left out, and symbolic links aren't followed.
+fn scan(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf, std::fs::Metadata)>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') || name.ends_with('~') {
+            continue;
+        }
+        let path = entry.path();
+        let metadata = std::fs::symlink_metadata(&path)?;
+        if metadata.is_dir() {
+            scan(root, &path, files)?;
+        } else if metadata.is_file() {
+            let relative: Vec<String> = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            files.push((relative.join("/"), path, metadata));
+        }
+    }
+    Ok(())
+}
+
+/// Upload the new and changed files of `folder`, stopping at the first that fails
+///
+/// The files synced until then are recorded in `synced`, the scan after the retry carries on.
+fn sync_folder(
+    handle: &AppHandle,
+    base_url: &str,
+    folder: &Path,
+    synced: &mut BTreeMap<String, SyncedFile>,
+    status: &mut FolderStatus,
+) -> Result<(), String> {
+    let mut files = Vec::new();
+    scan(folder, folder, &mut files).map_err(|e| format!("Failed to read {}: {}", folder.display(), e))?;
+    // Deleting files isn't synced, the backend keeps what it got
+    synced.retain(|relative, _| files.iter().any(|(name, _, _)| name == relative));
+
+    let settled_before = millis(SystemTime::now() - SETTLE_TIME);
+    let changed: Vec<_> = files
+        .into_iter()
+        .filter(|(relative, _, metadata)| {
+            let modified = metadata.modified().map(millis).unwrap_or_default();
+            synced
+                .get(relative)
+                .is_none_or(|file| file.size != metadata.len() || file.modified != modified)
+        })
+        .collect();
+    status.files = synced.len();
+    status.pending = changed.len();
+    if !changed.is_empty() {
+        status.state = FolderState::Syncing;
+        set_status(handle, status);
+    }
+
+    let key = folder_key(folder);
+    for (relative, path, metadata) in changed {
+        let modified = metadata.modified().map(millis).unwrap_or_default();
+        if modified > settled_before {
+            continue;
+        }
+        let hash = hash_file(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let previous = synced.get(&relative).cloned();
+        let base_hash = match previous {
+            // Touched without changes
+            Some(file) if file.hash == hash => {
+                synced.insert(relative, SyncedFile { size: metadata.len(), modified, ..file });
+                status.pending -= 1;
+                continue;
+            }
+            Some(file) => file.remote,
+            None => None,
+        };
+
+        let upload_id = upload::upload(handle, &path, None, None)?;
+        let result = api::ingest(base_url, &upload_id, &key, &relative, base_hash.as_deref())?;
+        let remote = if result.status == "conflict" {
+            tracing::warn!("{} changed here and on the server, uploaded as {}", path.display(), result.path);
+            status.conflicts.push(result.path.clone());
+            let overflow = status.conflicts.len().saturating_sub(MAX_CONFLICTS);
+            status.conflicts.drain(..overflow);
+            result.current
+        } else {
+            tracing::info!("Synced {} ({})", path.display(), result.status);
+            Some(result.hash)
+        };
+        synced.insert(
+            relative,
+            SyncedFile {
+                size: metadata.len(),
+                modified,
+                hash,
+                remote,
+            },
+        );
+        status.files = synced.len();
+        status.pending -= 1;
+        set_status(handle, status);
+    }
+    Ok(())
+}
+
+fn set_status(handle: &AppHandle, status: &FolderStatus) {
+    STATUS.lock().unwrap().insert(status.path.clone(), status.clone());
+    tray::sync_folders(handle);
+}
+
+/// Current status of every watched folder, in the order of the config
+pub fn statuses(handle: &AppHandle) -> Vec<FolderStatus> {
+    let known = STATUS.lock().unwrap();
+    handle
+        .state::<ConfigState>()
+        .get()
+        .watched_folders
+        .iter()
+        .map(|folder| known.get(folder).cloned().unwrap_or_else(|| FolderStatus::new(folder)))
+        .collect()
+}
+
+/// Upload new and changed files of the watched folders to the backend while the app runs
+///
+/// The folders are looked through every `SCAN_INTERVAL` while the backend is running. Each
+/// file goes up as a chunked upload, then the backend files it under the folder, keeping a
+/// conflict copy when its own version changed since the last sync. A folder that fails is
+/// tried again later, waiting longer each time.
+pub fn start(handle: AppHandle) {
+    std::thread::spawn(move || {
+        let state_path = handle.state::<AppPaths>().config_dir.join(STATE_FILE);
+        let mut state = load_state(&state_path);
+        let mut retries: BTreeMap<PathBuf, (u32, Instant)> = BTreeMap::new();
+        loop {
+            std::thread::sleep(SCAN_INTERVAL);
+            let folders = handle.state::<ConfigState>().get().watched_folders;
+            STATUS.lock().unwrap().retain(|path, _| folders.contains(path));
+            if supervisor::state(&handle) != SupervisorState::Running {
+                continue;
+            }
+
+            let before = state.clone();
+            state.retain(|path, _| folders.contains(path));
+            let base_url = handle.state::<ProfileState>().get().base_url();
+            for folder in &folders {
+                if retries.get(folder).is_some_and(|(_, at)| Instant::now() < *at) {
+                    continue;
+                }
+                let mut status = STATUS
+                    .lock()
+                    .unwrap()
+                    .get(folder)
+                    .cloned()
+                    .unwrap_or_else(|| FolderStatus::new(folder));
+                let synced = state.entry(folder.clone()).or_default();
+                match sync_folder(&handle, &base_url, folder, synced, &mut status) {
+                    Ok(()) => {
+                        retries.remove(folder);
+                        status.state = if status.pending == 0 {
+                            FolderState::UpToDate
+                        } else {
+                            FolderState::Syncing
+                        };
+                        status.last_error = None;
+                        status.retry_at = None;
+                        status.last_synced_at = Some(now_secs());
+                    }
+                    Err(e) => {
+                        let attempts = retries.get(folder).map_or(1, |(attempts, _)| attempts + 1);
+                        let delay = (RETRY_DELAY * 2u32.saturating_pow(attempts - 1)).min(MAX_RETRY_DELAY);
+                        tracing::warn!(attempts, "Failed to sync {}: {}", folder.display(), e);
+                        retries.insert(folder.clone(), (attempts, Instant::now() + delay));
+                        status.state = FolderState::Retrying;
+                        status.last_error = Some(e);
+                        status.retry_at = Some(now_secs() + delay.as_secs());
+                    }
+                }
+                set_status(&handle, &status);
+            }
+            if state != before {
+                save_state(&state_path, &state);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_watched_folders(app: AppHandle) -> Vec<FolderStatus> {
+    statuses(&app)
+}
+
+/// Watch the folder picked with `pick_folder` as `folder`, from the next scan on
+#[tauri::command]
+pub fn add_watched_folder(app: AppHandle, folder: String) -> Result<Vec<FolderStatus>, String> {
+    let path = commands::picked_folder(&folder).ok_or_else(|| "Pick the folder to watch first".to_string())?;
+    // Checked again, the folder may have been replaced since it was picked
+    let resolved = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    if !resolved.is_dir() {
+        return Err(format!("{} is not a folder", path.display()));
+    }
+    if commands::holds_own_files(&app, &resolved) {
+        return Err(format!("{} holds TSW IO's own files, pick another folder", path.display()));
+    }
+    config::update(&app, |config| {
+        if !config.watched_folders.contains(&path) {
+            config.watched_folders.push(path.clone());
+        }
+    })?;
+    tracing::info!("Watching {}", path.display());
+    tray::sync_folders(&app);
+    Ok(statuses(&app))
+}
+
+/// Stop watching `path`, the files already synced stay on the backend
+#[tauri::command]
+pub fn remove_watched_folder(app: AppHandle, path: PathBuf) -> Result<Vec<FolderStatus>, String> {
+    config::update(&app, |config| config.watched_folders.retain(|folder| folder != &path))?;
+    STATUS.lock().unwrap().remove(&path);
+    tracing::info!("Stopped watching {}", path.display());
+    tray::sync_folders(&app);
+    Ok(statuses(&app))
+}
@@ -0,0 +1,66 @@
+use crate::config::ShellConfig;
+
+/// Product names of virtual machines whose emulated GPUs WebKitGTK renders black on
+#[cfg(target_os = "linux")]
+const VIRTUAL_MACHINES: &[&str] = &["virtualbox", "vmware", "qemu", "kvm", "parallels"];
+
+/// Whether the webviews should render without the GPU
+///
+/// `hardware_acceleration` in the config decides when set, otherwise it's turned off
+/// on setups known to show a black window.
+pub fn acceleration_disabled(config: &ShellConfig) -> bool {
+    match config.hardware_acceleration {
+        Some(enabled) => !enabled,
+        None => known_bad_setup().is_some(),
+    }
+}
+
+/// Turn off GPU rendering for every webview the app creates, if it should be
+///
+/// Must run before the first window is built, the webview reads these when it starts.
+pub fn configure(config: &ShellConfig) {
+    if !acceleration_disabled(config) {
+        return;
+    }
+    match config.hardware_acceleration {
+        Some(_) => tracing::info!("Hardware acceleration turned off in the config"),
+        None => tracing::warn!(
+            reason = known_bad_setup().unwrap_or_default(),
+            "Turning off hardware acceleration"
+        ),
+    }
+
+    if cfg!(target_os = "linux") {
+        std::env::set_var("WEBKIT_DISABLE_COMPOSITING_MODE", "1");
+        std::env::set_var("WEBKIT_DISABLE_DMABUF_RENDERER", "1");
+    } else if cfg!(windows) {
+        std::env::set_var("WEBVIEW2_ADDITIONAL_BROWSER_ARGUMENTS", "--disable-gpu");
+    } else {
+        tracing::warn!("Turning off hardware acceleration is not supported on this platform");
+    }
+}
+
+/// Why this machine is known to render webviews badly with the GPU
+#[cfg(target_os = "linux")]
+fn known_bad_setup() -> Option<&'static str> {
+    let product = ["/sys/class/dmi/id/product_name", "/sys/class/dmi/id/sys_vendor"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if VIRTUAL_MACHINES.iter().any(|name| product.contains(name)) {
+        return Some("virtual machine");
+    }
+    // The proprietary NVIDIA driver and WebKitGTK's DMA-BUF renderer don't get along on Wayland
+    let nvidia = std::path::Path::new("/proc/driver/nvidia/version").exists();
+    if nvidia && std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        return Some("NVIDIA driver on Wayland");
+    }
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+fn known_bad_setup() -> Option<&'static str> {
+    None
+}
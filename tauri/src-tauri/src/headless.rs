@@ -0,0 +1,114 @@
+use serde::Serialize;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager};
+
+use crate::failure::FailureKind;
+use crate::profile::ProfileState;
+use crate::secrets;
+use crate::supervisor::{self, SupervisorState};
+use crate::timing;
+
+/// Set by `--headless` before anything else runs
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// What a headless shell tells its caller, one JSON object per line on stdout
+///
+/// The backend's admin API and shell channel are the automation API: `url` and
+/// `token` are all a test needs to drive it.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    Ready {
+        url: String,
+        token: &'a str,
+        pid: Option<u32>,
+        ready_ms: Option<u64>,
+    },
+    Status {
+        state: SupervisorState,
+        pid: Option<u32>,
+    },
+    Failed {
+        failure: Option<FailureKind>,
+        message: &'a str,
+    },
+}
+
+/// Run without ever creating a window, for CI and end-to-end tests
+///
+/// Failures that would open a window print a `failed` event and exit instead. GTK
+/// still needs a display server on Linux, CI machines run the shell under `xvfb-run`.
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+fn print(event: &Event) {
+    let Ok(line) = serde_json::to_string(event) else {
+        return;
+    };
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}", line);
+    let _ = stdout.flush();
+}
+
+/// Tell the caller the backend answers and how to reach it
+pub fn ready(handle: &AppHandle) {
+    if !enabled() {
+        return;
+    }
+    print(&Event::Ready {
+        url: handle.state::<ProfileState>().get().base_url(),
+        token: secrets::shell_token(),
+        pid: crate::backend_pid(handle),
+        ready_ms: timing::timings(handle).ready_ms,
+    });
+}
+
+/// Report a startup failure and quit, there is no one to show it to
+pub fn fail(handle: &AppHandle, failure: Option<FailureKind>, message: &str) {
+    tracing::error!(?failure, "Headless startup failed: {}", message);
+    print(&Event::Failed { failure, message });
+    handle.exit(1);
+}
+
+/// Same as `fail` for when the event loop may already be gone
+pub fn abort(message: &str) -> ! {
+    print(&Event::Failed {
+        failure: None,
+        message,
+    });
+    crate::logging::flush();
+    std::process::exit(1);
+}
+
+/// Answer `status` and `quit` lines on stdin
+///
+/// End of input is ignored, CI often runs with stdin closed. Without `quit`, the
+/// shell runs until it is killed.
+pub fn listen(handle: AppHandle) {
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            match line.trim() {
+                "status" => print(&Event::Status {
+                    state: supervisor::state(&handle),
+                    pid: crate::backend_pid(&handle),
+                }),
+                "quit" => {
+                    tracing::info!("Quitting on request");
+                    handle.exit(0);
+                    break;
+                }
+                "" => {}
+                other => tracing::warn!("Unknown headless command: {}", other),
+            }
+        }
+    });
+}
@@ -0,0 +1,292 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::api;
+use crate::config::ConfigState;
+use crate::mock;
+use crate::monitor;
+use crate::overlay;
+use crate::profile::ProfileState;
+use crate::splash;
+use crate::timing::{self, Stage, StartupBudget};
+use crate::tls;
+
+/// While waiting for the ready signal, poll only every this many attempts in case it got lost
+const FALLBACK_POLL_EVERY: u32 = 4;
+const CHECK_TIMEOUT_MS: u64 = 2000;
+/// Startup attempts summarised in one log line
+const ATTEMPTS_PER_LOG_LINE: u32 = 10;
+
+/// Why a health check didn't find the backend ready
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckFailure {
+    /// Nothing answered within the check timeout
+    Timeout,
+    /// Nothing is listening on the port yet
+    Refused,
+    /// The server answered with an error, 503 while migrations run
+    Status(u16),
+    Other(String),
+}
+
+impl CheckFailure {
+    /// Short name used as a structured log field
+    pub fn reason(&self) -> &'static str {
+        match self {
+            CheckFailure::Timeout => "timeout",
+            CheckFailure::Refused => "refused",
+            CheckFailure::Status(503) => "unavailable",
+            CheckFailure::Status(_) => "status",
+            CheckFailure::Other(_) => "error",
+        }
+    }
+}
+
+impl std::fmt::Display for CheckFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckFailure::Timeout => write!(f, "timed out"),
+            CheckFailure::Refused => write!(f, "connection refused"),
+            CheckFailure::Status(status) => write!(f, "HTTP {}", status),
+            CheckFailure::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Check if the backend is fully ready (migrations complete) by checking health endpoint
+pub fn check_backend_ready(base_url: &str) -> Result<(), CheckFailure> {
+    let url = api::backend_url(base_url, "/api/health");
+    let response = tls::backend_client()
+        .map_err(CheckFailure::Other)?
+        .get(&url)
+        .timeout(Duration::from_millis(CHECK_TIMEOUT_MS))
+        .send()
+        .map_err(|e| {
+            if e.is_timeout() {
+                CheckFailure::Timeout
+            } else if e.is_connect() {
+                CheckFailure::Refused
+            } else {
+                CheckFailure::Other(e.to_string())
+            }
+        })?;
+    // The shell's proxy answers for a backend that isn't listening yet
+    if response.status() == reqwest::StatusCode::BAD_GATEWAY {
+        return Err(CheckFailure::Refused);
+    }
+    // Server is up but not ready (e.g., migrations running)
+    if !response.status().is_success() {
+        return Err(CheckFailure::Status(response.status().as_u16()));
+    }
+    Ok(())
+}
+
+/// Startup health checks since the last log line
+#[derive(Default)]
+struct AttemptStats {
+    attempts: u32,
+    timeout: u32,
+    refused: u32,
+    unavailable: u32,
+    other: u32,
+    total: Duration,
+    min: Option<Duration>,
+    max: Duration,
+    last_failure: Option<CheckFailure>,
+}
+
+impl AttemptStats {
+    fn record(&mut self, latency: Duration, failure: &CheckFailure) {
+        self.attempts += 1;
+        self.total += latency;
+        self.min = Some(self.min.map_or(latency, |min| min.min(latency)));
+        self.max = self.max.max(latency);
+        match failure {
+            CheckFailure::Timeout => self.timeout += 1,
+            CheckFailure::Refused => self.refused += 1,
+            CheckFailure::Status(503) => self.unavailable += 1,
+            CheckFailure::Status(_) | CheckFailure::Other(_) => self.other += 1,
+        }
+        self.last_failure = Some(failure.clone());
+    }
+
+    /// Log a summary of the attempts so far and start over
+    fn flush(&mut self, attempt: u32, max_attempts: u32) {
+        if self.attempts == 0 {
+            return;
+        }
+        let stats = std::mem::take(self);
+        let last_failure = stats.last_failure.map(|failure| failure.to_string());
+        tracing::info!(
+            attempt,
+            max = max_attempts,
+            timeout = stats.timeout,
+            refused = stats.refused,
+            unavailable = stats.unavailable,
+            other = stats.other,
+            min_ms = stats.min.unwrap_or_default().as_millis() as u64,
+            avg_ms = (stats.total / stats.attempts).as_millis() as u64,
+            max_ms = stats.max.as_millis() as u64,
+            last_failure = last_failure.as_deref().unwrap_or(""),
+            "Waiting for backend"
+        );
+    }
+}
+
+/// Wait for the backend to become fully ready, returning the attempts it took either way
+///
+/// With `signaled`, the sidecar prints a line once it is ready and health checks only
+/// confirm it, otherwise the backend is polled.
+pub fn wait_for_backend(
+    handle: &AppHandle,
+    base_url: &str,
+    signaled: bool,
+    budget: &StartupBudget,
+) -> Result<u32, u32> {
+    // Get the splash window to update status
+    let splash_window = handle.get_webview_window("splash");
+    let mut stats = AttemptStats::default();
+    let mut ready_signal = false;
+    let max_attempts = (budget.timeout.as_millis() / budget.poll_interval.as_millis()).max(1) as u32;
+    let started_at = Instant::now();
+    tracing::debug!(
+        timeout_secs = budget.timeout.as_secs(),
+        expected_ms = budget.expected.map(|expected| expected.as_millis() as u64),
+        poll_interval_ms = budget.poll_interval.as_millis() as u64,
+        "Startup budget"
+    );
+
+    for attempt in 1..=max_attempts {
+        let poll = !signaled || ready_signal || attempt % FALLBACK_POLL_EVERY == 0;
+        if poll {
+            let checked_at = Instant::now();
+            let result = check_backend_ready(base_url);
+            let latency = checked_at.elapsed();
+            match result {
+                Ok(()) => {
+                    stats.flush(attempt - 1, max_attempts);
+                    timing::mark(handle, Stage::FirstResponse);
+                    timing::mark(handle, Stage::Ready);
+                    tracing::info!(
+                        attempt,
+                        latency_ms = latency.as_millis() as u64,
+                        ready_signal,
+                        "Backend ready"
+                    );
+                    return Ok(attempt);
+                }
+                Err(failure) => {
+                    // Any HTTP answer means the server itself is up
+                    if let CheckFailure::Status(_) = failure {
+                        timing::mark(handle, Stage::FirstResponse);
+                    }
+                    tracing::trace!(attempt, reason = failure.reason(), "Backend not ready: {}", failure);
+                    stats.record(latency, &failure);
+                }
+            }
+        }
+
+        // No point waiting out the budget for a sidecar that already exited
+        if monitor::backend_exit(handle).is_some() {
+            stats.flush(attempt, max_attempts);
+            tracing::warn!(attempt, "Backend exited during startup");
+            return Err(attempt);
+        }
+
+        // Update splash screen status
+        if let Some(ref window) = splash_window {
+            let status = match budget.expected {
+                // Launches of this version took about this long before
+                Some(expected) => match expected.checked_sub(started_at.elapsed()) {
+                    Some(left) if left.as_secs() > 0 => {
+                        format!("Starting server... about {} s left", left.as_secs())
+                    }
+                    _ => "Almost ready...".to_string(),
+                },
+                // First launch of this version, likely migrating the database
+                None if started_at.elapsed() < Duration::from_secs(5) => {
+                    "Starting server...".to_string()
+                }
+                None if started_at.elapsed() < Duration::from_secs(15) => {
+                    "Running database migrations...".to_string()
+                }
+                None => "Almost ready...".to_string(),
+            };
+            splash::set_status(window, &status);
+        }
+
+        if attempt % ATTEMPTS_PER_LOG_LINE == 0 {
+            stats.flush(attempt, max_attempts);
+        }
+        if signaled {
+            // Returns as soon as the sidecar says it is ready
+            ready_signal =
+                monitor::wait_for_ready_signal(handle, budget.poll_interval);
+        } else {
+            std::thread::sleep(budget.poll_interval);
+        }
+    }
+    stats.flush(max_attempts, max_attempts);
+    tracing::warn!(
+        attempts = max_attempts,
+        timeout_secs = budget.timeout.as_secs(),
+        "Backend did not become ready in time"
+    );
+    Err(max_attempts)
+}
+
+static HEARTBEAT_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Keep checking the backend after startup and report when it stops responding
+pub fn start_heartbeat(handle: AppHandle) {
+    // The backend can be relaunched, but one heartbeat covers all of its lifetimes
+    if HEARTBEAT_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        let mut healthy = true;
+        loop {
+            // Re-read every tick so interval and notification changes apply live
+            let config = handle.state::<ConfigState>().get();
+            std::thread::sleep(config.heartbeat_interval());
+
+            // Stopped on purpose or still starting up, nothing to report
+            let running = crate::backend_running(&handle) || mock::enabled();
+            if !running || splash::is_open(&handle) || overlay::is_open(&handle) {
+                continue;
+            }
+
+            // Read every tick too, the port can change while running
+            let base_url = handle.state::<ProfileState>().get().base_url();
+            let checked_at = Instant::now();
+            let result = check_backend_ready(&base_url);
+            let is_healthy = result.is_ok();
+            monitor::record_check(&handle, is_healthy, checked_at.elapsed());
+            if is_healthy == healthy {
+                continue;
+            }
+            healthy = is_healthy;
+
+            let message = if healthy {
+                tracing::info!("Backend heartbeat restored");
+                "The backend is responding again."
+            } else {
+                let failure = result.err().map(|failure| failure.to_string());
+                tracing::warn!(reason = failure.as_deref().unwrap_or(""), "Backend heartbeat lost");
+                "The backend stopped responding."
+            };
+
+            if config.notifications.backend_status && !crate::quiet::hold(&handle, message) {
+                let _ = handle
+                    .notification()
+                    .builder()
+                    .title("tsw_io")
+                    .body(message)
+                    .show();
+            }
+        }
+    });
+}
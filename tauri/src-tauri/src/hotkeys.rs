@@ -0,0 +1,299 @@
+use tauri::AppHandle;
+
+/// What a hotkey does when it is pressed
+pub type Action = fn(&AppHandle);
+
+/// A key with the modifiers held down with it, as in `CmdOrCtrl+Shift+Space`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+struct Hotkey {
+    command: bool,
+    control: bool,
+    alt: bool,
+    shift: bool,
+    key: Key,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+enum Key {
+    /// `A` to `Z`
+    Letter(u8),
+    /// `0` to `9`
+    Digit(u8),
+    Space,
+    /// `F1` to `F12`
+    Function(u8),
+}
+
+impl Hotkey {
+    fn parse(text: &str) -> Result<Hotkey, String> {
+        let invalid = || format!("Invalid hotkey {:?}, it should look like CmdOrCtrl+Shift+Space", text);
+        let mut hotkey = Hotkey {
+            command: false,
+            control: false,
+            alt: false,
+            shift: false,
+            key: Key::Space,
+        };
+        let mut key = None;
+        for part in text.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "cmdorctrl" | "commandorcontrol" if cfg!(target_os = "macos") => hotkey.command = true,
+                "cmdorctrl" | "commandorcontrol" => hotkey.control = true,
+                "cmd" | "command" | "super" | "meta" => hotkey.command = true,
+                "ctrl" | "control" => hotkey.control = true,
+                "alt" | "option" => hotkey.alt = true,
+                "shift" => hotkey.shift = true,
+                "space" => key = Some(Key::Space),
+                name => {
+                    let bytes = name.to_ascii_uppercase().into_bytes();
+                    key = Some(match bytes.as_slice() {
+                        [letter @ b'A'..=b'Z'] => Key::Letter(*letter),
+                        [digit @ b'0'..=b'9'] => Key::Digit(*digit),
+                        [b'F', number @ ..] => match std::str::from_utf8(number).ok().and_then(|n| n.parse().ok()) {
+                            Some(number @ 1..=12) => Key::Function(number),
+                            _ => return Err(invalid()),
+                        },
+                        _ => return Err(invalid()),
+                    });
+                }
+            }
+        }
+        hotkey.key = key.ok_or_else(invalid)?;
+        // A bare key would be taken from every other app
+        if !(hotkey.command || hotkey.control || hotkey.alt) {
+            return Err(invalid());
+        }
+        Ok(hotkey)
+    }
+}
+
+/// Run `action` whenever `text`, like `CmdOrCtrl+Shift+Space`, is pressed in any app
+///
+/// Desktops on Linux have no shared hotkey API, subcommands like `tsw-io capture` can be bound
+/// to a desktop shortcut there.
+pub fn register(handle: &AppHandle, text: &str, action: Action) -> Result<(), String> {
+    platform::register(handle, Hotkey::parse(text)?, action)
+}
+
+/// Carbon hot keys, the only kind macOS has that needs no accessibility permission
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+    use std::sync::{Mutex, Once, OnceLock};
+    use tauri::AppHandle;
+
+    use super::{Action, Hotkey, Key};
+
+    type OSStatus = i32;
+    type EventHandler = extern "C" fn(*mut c_void, *mut c_void, *mut c_void) -> OSStatus;
+
+    #[repr(C)]
+    struct EventHotKeyID {
+        signature: u32,
+        id: u32,
+    }
+
+    #[repr(C)]
+    struct EventTypeSpec {
+        event_class: u32,
+        event_kind: u32,
+    }
+
+    const EVENT_CLASS_KEYBOARD: u32 = u32::from_be_bytes(*b"keyb");
+    const EVENT_HOT_KEY_PRESSED: u32 = 5;
+    const EVENT_PARAM_DIRECT_OBJECT: u32 = u32::from_be_bytes(*b"----");
+    const TYPE_EVENT_HOT_KEY_ID: u32 = u32::from_be_bytes(*b"hkid");
+    const SIGNATURE: u32 = u32::from_be_bytes(*b"tswh");
+    const CMD_KEY: u32 = 1 << 8;
+    const SHIFT_KEY: u32 = 1 << 9;
+    const OPTION_KEY: u32 = 1 << 11;
+    const CONTROL_KEY: u32 = 1 << 12;
+    /// Virtual key codes of `A` to `Z` on an ANSI keyboard
+    const LETTERS: [u32; 26] = [
+        0x00, 0x0B, 0x08, 0x02, 0x0E, 0x03, 0x05, 0x04, 0x22, 0x26, 0x28, 0x25, 0x2E, 0x2D, 0x1F, 0x23, 0x0C,
+        0x0F, 0x01, 0x11, 0x20, 0x09, 0x0D, 0x07, 0x10, 0x06,
+    ];
+    const DIGITS: [u32; 10] = [0x1D, 0x12, 0x13, 0x14, 0x15, 0x17, 0x16, 0x1A, 0x1C, 0x19];
+    const FUNCTION_KEYS: [u32; 12] = [0x7A, 0x78, 0x63, 0x76, 0x60, 0x61, 0x62, 0x64, 0x65, 0x6D, 0x67, 0x6F];
+    const SPACE: u32 = 0x31;
+
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        fn GetApplicationEventTarget() -> *mut c_void;
+        fn InstallEventHandler(
+            target: *mut c_void,
+            handler: EventHandler,
+            count: u32,
+            types: *const EventTypeSpec,
+            user_data: *mut c_void,
+            handler_ref: *mut *mut c_void,
+        ) -> OSStatus;
+        fn GetEventParameter(
+            event: *mut c_void,
+            name: u32,
+            desired_type: u32,
+            actual_type: *mut u32,
+            size: usize,
+            actual_size: *mut usize,
+            data: *mut c_void,
+        ) -> OSStatus;
+        fn RegisterEventHotKey(
+            key_code: u32,
+            modifiers: u32,
+            id: EventHotKeyID,
+            target: *mut c_void,
+            options: u32,
+            hot_key_ref: *mut *mut c_void,
+        ) -> OSStatus;
+    }
+
+    static HANDLE: OnceLock<AppHandle> = OnceLock::new();
+    /// What each hotkey does, its Carbon id is its index plus one
+    static ACTIONS: Mutex<Vec<Action>> = Mutex::new(Vec::new());
+    static HANDLER: Once = Once::new();
+
+    extern "C" fn pressed(_call: *mut c_void, event: *mut c_void, _data: *mut c_void) -> OSStatus {
+        let mut id = EventHotKeyID { signature: 0, id: 0 };
+        let status = unsafe {
+            GetEventParameter(
+                event,
+                EVENT_PARAM_DIRECT_OBJECT,
+                TYPE_EVENT_HOT_KEY_ID,
+                std::ptr::null_mut(),
+                std::mem::size_of::<EventHotKeyID>(),
+                std::ptr::null_mut(),
+                &mut id as *mut EventHotKeyID as *mut c_void,
+            )
+        };
+        if status != 0 || id.signature != SIGNATURE {
+            return status;
+        }
+        let action = ACTIONS.lock().unwrap().get(id.id as usize - 1).copied();
+        if let (Some(action), Some(handle)) = (action, HANDLE.get()) {
+            action(handle);
+        }
+        0
+    }
+
+    fn key_code(key: Key) -> u32 {
+        match key {
+            Key::Letter(letter) => LETTERS[usize::from(letter - b'A')],
+            Key::Digit(digit) => DIGITS[usize::from(digit - b'0')],
+            Key::Space => SPACE,
+            Key::Function(number) => FUNCTION_KEYS[usize::from(number - 1)],
+        }
+    }
+
+    pub fn register(handle: &AppHandle, hotkey: Hotkey, action: Action) -> Result<(), String> {
+        let _ = HANDLE.set(handle.clone());
+        let modifiers = [
+            (hotkey.command, CMD_KEY),
+            (hotkey.control, CONTROL_KEY),
+            (hotkey.alt, OPTION_KEY),
+            (hotkey.shift, SHIFT_KEY),
+        ]
+        .iter()
+        .filter(|(held, _)| *held)
+        .fold(0, |modifiers, (_, flag)| modifiers | flag);
+        let code = key_code(hotkey.key);
+        let id = {
+            let mut actions = ACTIONS.lock().unwrap();
+            actions.push(action);
+            actions.len() as u32
+        };
+        // Carbon events arrive on the main thread's run loop
+        handle
+            .run_on_main_thread(move || unsafe {
+                let target = GetApplicationEventTarget();
+                HANDLER.call_once(|| {
+                    let spec = EventTypeSpec {
+                        event_class: EVENT_CLASS_KEYBOARD,
+                        event_kind: EVENT_HOT_KEY_PRESSED,
+                    };
+                    let mut handler_ref = std::ptr::null_mut();
+                    let status = InstallEventHandler(target, pressed, 1, &spec, std::ptr::null_mut(), &mut handler_ref);
+                    if status != 0 {
+                        tracing::warn!("Failed to listen for hotkeys: {}", status);
+                    }
+                });
+                let id = EventHotKeyID { signature: SIGNATURE, id };
+                let mut hot_key_ref = std::ptr::null_mut();
+                let status = RegisterEventHotKey(code, modifiers, id, target, 0, &mut hot_key_ref);
+                if status != 0 {
+                    tracing::warn!("Failed to register a hotkey, another app may use it: {}", status);
+                }
+            })
+            .map_err(|e| format!("Failed to register the hotkey: {}", e))
+    }
+}
+
+/// A hot key posted to a thread of its own, which waits for it
+#[cfg(windows)]
+mod platform {
+    use tauri::AppHandle;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        RegisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, MOD_SHIFT, MOD_WIN,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetMessageW, MSG, WM_HOTKEY};
+
+    use super::{Action, Hotkey, Key};
+
+    const VK_SPACE: u32 = 0x20;
+    const VK_F1: u32 = 0x70;
+
+    fn virtual_key(key: Key) -> u32 {
+        match key {
+            // Letters and digits are their own ASCII codes
+            Key::Letter(character) | Key::Digit(character) => u32::from(character),
+            Key::Space => VK_SPACE,
+            Key::Function(number) => VK_F1 + u32::from(number - 1),
+        }
+    }
+
+    pub fn register(handle: &AppHandle, hotkey: Hotkey, action: Action) -> Result<(), String> {
+        let modifiers = [
+            (hotkey.command, MOD_WIN),
+            (hotkey.control, MOD_CONTROL),
+            (hotkey.alt, MOD_ALT),
+            (hotkey.shift, MOD_SHIFT),
+        ]
+        .iter()
+        .filter(|(held, _)| *held)
+        .fold(MOD_NOREPEAT, |modifiers, (_, flag)| HOT_KEY_MODIFIERS(modifiers.0 | flag.0));
+        let key = virtual_key(hotkey.key);
+        let (registered, result) = std::sync::mpsc::channel();
+        let handle = handle.clone();
+        std::thread::spawn(move || unsafe {
+            // Without a window, presses are posted to the thread that registered the key
+            let outcome = RegisterHotKey(None, 1, modifiers, key)
+                .map_err(|e| format!("Failed to register the hotkey, another app may use it: {}", e));
+            let failed = outcome.is_err();
+            let _ = registered.send(outcome);
+            if failed {
+                return;
+            }
+            let mut message = MSG::default();
+            while GetMessageW(&mut message, None, 0, 0).as_bool() {
+                if message.message == WM_HOTKEY {
+                    action(&handle);
+                }
+            }
+        });
+        result
+            .recv()
+            .map_err(|e| format!("Failed to register the hotkey: {}", e))?
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use tauri::AppHandle;
+
+    use super::{Action, Hotkey};
+
+    pub fn register(_handle: &AppHandle, _hotkey: Hotkey, _action: Action) -> Result<(), String> {
+        Err("Global hotkeys aren't supported on this desktop, bind the `tsw-io` command to a shortcut instead".to_string())
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::api;
+use crate::config::ConfigState;
+use crate::lock;
+use crate::profile::ProfileState;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Lock the app and expire the backend session once the user has been away too long
+pub fn start(handle: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        // Act once per absence, not on every check while still away
+        let mut away = false;
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            // Re-read every tick so the timeout can be changed live
+            let Some(minutes) = handle.state::<ConfigState>().get().idle_lock_minutes else {
+                away = false;
+                continue;
+            };
+            let Some(idle) = idle_time() else {
+                continue;
+            };
+
+            let is_away = idle >= Duration::from_secs(minutes.max(1) * 60);
+            if is_away && !away {
+                tracing::info!(idle_secs = idle.as_secs(), "User idle, locking");
+                expire(&handle);
+            }
+            away = is_away;
+        }
+    });
+}
+
+fn expire(handle: &AppHandle) {
+    if let Some(window) = handle.get_webview_window("main") {
+        // Drops the session cookie, the pages get a fresh one once unlocked
+        if let Err(e) = window.clear_all_browsing_data() {
+            tracing::warn!("Failed to clear browsing data: {}", e);
+        }
+    }
+    if crate::backend_running(handle) {
+        let base_url = handle.state::<ProfileState>().get().base_url();
+        if let Err(e) = api::expire_backend_session(&base_url) {
+            tracing::warn!("{}", e);
+        }
+    }
+    lock::lock(handle);
+}
+
+/// Time since the last keyboard or mouse input anywhere on the system
+#[cfg(target_os = "macos")]
+pub(crate) fn idle_time() -> Option<Duration> {
+    use objc2_core_graphics::{CGEventSource, CGEventSourceStateID, CGEventType};
+
+    // kCGAnyInputEventType
+    let any_input = CGEventType(u32::MAX);
+    let seconds =
+        CGEventSource::seconds_since_last_event_type(CGEventSourceStateID::HIDSystemState, any_input);
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+#[cfg(windows)]
+pub(crate) fn idle_time() -> Option<Duration> {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    if !unsafe { GetLastInputInfo(&mut info) }.as_bool() {
+        return None;
+    }
+    let idle_ms = unsafe { GetTickCount() }.wrapping_sub(info.dwTime);
+    Some(Duration::from_millis(idle_ms.into()))
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+pub(crate) fn idle_time() -> Option<Duration> {
+    None
+}
@@ -0,0 +1,208 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::api::{self, ImportSummary};
+use crate::profile::ProfileState;
+use crate::{progress, upload};
+
+/// Spreadsheets the backend reads trains from, see `TswIo.Imports`
+const EXTENSIONS: [&str; 2] = ["csv", "xlsx"];
+/// Id of the progress notification, one import runs at a time
+const PROGRESS_ID: &str = "spreadsheet-import";
+const PROGRESS_TITLE: &str = "Importing spreadsheets";
+/// Failed rows listed per file in the summary, the rest are counted
+const SHOWN_FAILURES: usize = 3;
+
+/// How the import of one file went
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportOutcome {
+    pub path: PathBuf,
+    pub summary: Option<ImportSummary>,
+    pub error: Option<String>,
+}
+
+impl ImportOutcome {
+    fn line(&self) -> String {
+        let name = self
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let Some(summary) = &self.summary else {
+            return format!("✗ {}: {}", name, self.error.as_deref().unwrap_or("failed"));
+        };
+        let mut line = format!(
+            "{} {}: {} created, {} already known",
+            if summary.failed.is_empty() { "✓" } else { "!" },
+            name,
+            summary.created,
+            summary.skipped
+        );
+        if !summary.failed.is_empty() {
+            line.push_str(&format!(", {} failed", summary.failed.len()));
+        }
+        for failure in summary.failed.iter().take(SHOWN_FAILURES) {
+            line.push_str(&format!("\n    row {}: {}", failure.row, failure.errors.join(", ")));
+        }
+        if summary.failed.len() > SHOWN_FAILURES {
+            line.push_str(&format!("\n    and {} more rows", summary.failed.len() - SHOWN_FAILURES));
+        }
+        line
+    }
+
+    fn succeeded(&self) -> bool {
+        self.summary.as_ref().is_some_and(|summary| summary.failed.is_empty())
+    }
+}
+
+/// Show how far the import got on the taskbar or Dock icon and in a notification
+struct Progress<'a> {
+    handle: &'a AppHandle,
+    total: u64,
+    percent: Option<u8>,
+}
+
+impl Progress<'_> {
+    fn set(&mut self, done: u64) {
+        let percent = (done.saturating_mul(100) / self.total.max(1)).min(99) as u8;
+        if self.percent == Some(percent) {
+            return;
+        }
+        self.percent = Some(percent);
+        self.bar(ProgressBarStatus::Normal, Some(percent as u64));
+        let notified = progress::notify_progress(
+            self.handle.clone(),
+            PROGRESS_ID.to_string(),
+            PROGRESS_TITLE.to_string(),
+            percent,
+        );
+        if let Err(e) = notified {
+            tracing::debug!("Failed to show the import progress: {}", e);
+        }
+    }
+
+    fn bar(&self, status: ProgressBarStatus, progress: Option<u64>) {
+        let Some(window) = self.handle.get_webview_window("main") else {
+            return;
+        };
+        if let Err(e) = window.set_progress_bar(ProgressBarState {
+            status: Some(status),
+            progress,
+        }) {
+            tracing::debug!("Failed to show the import progress: {}", e);
+        }
+    }
+
+    fn finish(self) {
+        self.bar(ProgressBarStatus::None, None);
+        // Replaces the progress notification with one saying it's done
+        let _ = progress::notify_progress(
+            self.handle.clone(),
+            PROGRESS_ID.to_string(),
+            PROGRESS_TITLE.to_string(),
+            100,
+        );
+    }
+}
+
+/// Upload each spreadsheet in chunks and have the backend import its trains
+///
+/// A file that fails doesn't stop the others.
+pub fn import_files(handle: &AppHandle, paths: &[PathBuf]) -> Vec<ImportOutcome> {
+    let base_url = handle.state::<ProfileState>().get().base_url();
+    let sizes: Vec<u64> = paths
+        .iter()
+        .map(|path| std::fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0))
+        .collect();
+    let mut progress = Progress {
+        handle,
+        total: sizes.iter().sum(),
+        percent: None,
+    };
+    let mut done = 0;
+    let mut outcomes = Vec::with_capacity(paths.len());
+    for (path, size) in paths.iter().zip(sizes) {
+        progress.set(done);
+        let result = upload::upload_reporting(handle, path, None, None, &mut |sent| progress.set(done + sent))
+            .and_then(|upload_id| api::import_upload(&base_url, &upload_id));
+        done += size;
+        match &result {
+            Ok(summary) => tracing::info!(
+                created = summary.created,
+                skipped = summary.skipped,
+                failed = summary.failed.len(),
+                "Imported {}",
+                path.display()
+            ),
+            Err(e) => tracing::warn!("Failed to import {}: {}", path.display(), e),
+        }
+        let (summary, error) = match result {
+            Ok(summary) => (Some(summary), None),
+            Err(e) => (None, Some(e)),
+        };
+        outcomes.push(ImportOutcome {
+            path: path.clone(),
+            summary,
+            error,
+        });
+    }
+    progress.finish();
+    outcomes
+}
+
+/// Pick spreadsheets and import them, then show how each went, empty if the user cancelled
+fn pick_and_import(handle: &AppHandle) -> Vec<ImportOutcome> {
+    let paths: Vec<PathBuf> = handle
+        .dialog()
+        .file()
+        .set_title("Import spreadsheets")
+        .add_filter("Spreadsheets", &EXTENSIONS)
+        .blocking_pick_files()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| path.into_path().ok())
+        .collect();
+    if paths.is_empty() {
+        return Vec::new();
+    }
+    let outcomes = import_files(handle, &paths);
+    show_summary(handle, &outcomes);
+    outcomes
+}
+
+fn show_summary(handle: &AppHandle, outcomes: &[ImportOutcome]) {
+    let failed = outcomes.iter().filter(|outcome| !outcome.succeeded()).count();
+    let title = match failed {
+        0 => "Import finished".to_string(),
+        failed if failed == outcomes.len() => "Import failed".to_string(),
+        failed => format!("{} of {} files had problems", failed, outcomes.len()),
+    };
+    let lines: Vec<String> = outcomes.iter().map(ImportOutcome::line).collect();
+    handle
+        .dialog()
+        .message(lines.join("\n"))
+        .title(title)
+        .kind(if failed == 0 { MessageDialogKind::Info } else { MessageDialogKind::Warning })
+        .show(|_| {});
+}
+
+/// Import from the File menu
+pub fn import_from_menu(handle: &AppHandle) {
+    let handle = handle.clone();
+    // The picker and the uploads block, keep them off the event loop
+    std::thread::spawn(move || {
+        pick_and_import(&handle);
+    });
+}
+
+/// Import spreadsheets the user picks, returning how each went
+#[tauri::command]
+pub async fn import_spreadsheets(app: AppHandle) -> Result<Vec<ImportOutcome>, String> {
+    tauri::async_runtime::spawn_blocking(move || pick_and_import(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
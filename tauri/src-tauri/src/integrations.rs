@@ -0,0 +1,409 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// File extension of exported configurations, without the dot
+const FILE_EXTENSION: &str = "tswx";
+/// Scheme of links that open in the app
+const URL_SCHEME: &str = "tsw-io";
+
+/// Ways the OS can hand things to the app
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Integration {
+    /// Opening `.tswx` files
+    FileAssociation,
+    /// Following `tsw-io://` links
+    UrlScheme,
+}
+
+impl Integration {
+    const ALL: [Integration; 2] = [Integration::FileAssociation, Integration::UrlScheme];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Integration::FileAssociation => "file_association",
+            Integration::UrlScheme => "url_scheme",
+        }
+    }
+}
+
+/// What the settings show for an integration
+#[derive(Debug, Serialize)]
+pub struct IntegrationStatus {
+    pub integration: Integration,
+    /// Whether this app is registered for it for the current user
+    pub registered: bool,
+    /// Whether the app can undo the registration, macOS only lets the user pick another app
+    pub can_unregister: bool,
+}
+
+/// Executable the OS should start, the AppImage rather than its mounted contents
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+fn executable() -> Result<PathBuf, String> {
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+    std::env::current_exe().map_err(|e| format!("Failed to find the app executable: {}", e))
+}
+
+#[tauri::command]
+pub fn get_integrations(app: AppHandle) -> Vec<IntegrationStatus> {
+    Integration::ALL
+        .iter()
+        .map(|integration| IntegrationStatus {
+            integration: *integration,
+            registered: is_registered(&app, *integration),
+            can_unregister: CAN_UNREGISTER,
+        })
+        .collect()
+}
+
+/// Make this app open `.tswx` files or `tsw-io://` links for the current user
+#[tauri::command]
+pub async fn register_integration(app: AppHandle, integration: Integration) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || register(&app, integration))
+        .await
+        .map_err(|e| e.to_string())??;
+    tracing::info!(integration = integration.as_str(), "Registered integration");
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unregister_integration(app: AppHandle, integration: Integration) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || unregister(&app, integration))
+        .await
+        .map_err(|e| e.to_string())??;
+    tracing::info!(integration = integration.as_str(), "Unregistered integration");
+    Ok(())
+}
+
+#[cfg(windows)]
+const CAN_UNREGISTER: bool = true;
+
+/// Program id the `.tswx` extension points to
+#[cfg(windows)]
+const PROG_ID: &str = "TswIo.tswx";
+
+/// Per-user classes, which take precedence over the machine-wide ones
+#[cfg(windows)]
+fn classes() -> Result<winreg::RegKey, String> {
+    use winreg::enums::{HKEY_CURRENT_USER, KEY_ALL_ACCESS};
+    winreg::RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey_with_flags(r"Software\Classes", KEY_ALL_ACCESS)
+        .map_err(|e| format!("Failed to open HKCU\\Software\\Classes: {}", e))
+}
+
+#[cfg(windows)]
+fn command_key(integration: Integration) -> String {
+    match integration {
+        Integration::FileAssociation => format!(r"{}\shell\open\command", PROG_ID),
+        Integration::UrlScheme => format!(r"{}\shell\open\command", URL_SCHEME),
+    }
+}
+
+#[cfg(windows)]
+fn is_registered(_handle: &AppHandle, integration: Integration) -> bool {
+    let (Ok(classes), Ok(exe)) = (classes(), executable()) else {
+        return false;
+    };
+    classes
+        .open_subkey(command_key(integration))
+        .and_then(|key| key.get_value::<String, _>(""))
+        .is_ok_and(|command| command.contains(&*exe.to_string_lossy()))
+}
+
+#[cfg(windows)]
+fn register(_handle: &AppHandle, integration: Integration) -> Result<(), String> {
+    let classes = classes()?;
+    let exe = executable()?;
+    let set = |path: &str, name: &str, value: String| {
+        classes
+            .create_subkey(path)
+            .and_then(|(key, _)| key.set_value(name, &value))
+            .map_err(|e| format!("Failed to write HKCU\\Software\\Classes\\{}: {}", path, e))
+    };
+
+    match integration {
+        Integration::FileAssociation => {
+            set(&format!(".{}", FILE_EXTENSION), "", PROG_ID.to_string())?;
+            set(PROG_ID, "", "TSW IO configuration".to_string())?;
+            set(&format!(r"{}\DefaultIcon", PROG_ID), "", format!("\"{}\",0", exe.display()))?;
+        }
+        Integration::UrlScheme => {
+            set(URL_SCHEME, "", "URL:TSW IO".to_string())?;
+            set(URL_SCHEME, "URL Protocol", String::new())?;
+        }
+    }
+    set(&command_key(integration), "", format!("\"{}\" \"%1\"", exe.display()))?;
+    notify_association_change();
+    Ok(())
+}
+
+#[cfg(windows)]
+fn unregister(_handle: &AppHandle, integration: Integration) -> Result<(), String> {
+    let classes = classes()?;
+    let delete = |path: &str| match classes.delete_subkey_all(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove HKCU\\Software\\Classes\\{}: {}", path, e))
+        }
+        _ => Ok(()),
+    };
+
+    match integration {
+        Integration::FileAssociation => {
+            // The extension may have been handed to another app since
+            let extension = format!(".{}", FILE_EXTENSION);
+            let ours = classes
+                .open_subkey(&extension)
+                .and_then(|key| key.get_value::<String, _>(""))
+                .is_ok_and(|prog_id| prog_id == PROG_ID);
+            if ours {
+                delete(&extension)?;
+            }
+            delete(PROG_ID)?;
+        }
+        Integration::UrlScheme => delete(URL_SCHEME)?,
+    }
+    notify_association_change();
+    Ok(())
+}
+
+/// Make Explorer pick up the change without signing out
+#[cfg(windows)]
+fn notify_association_change() {
+    use windows::Win32::UI::Shell::{SHChangeNotify, SHCNE_ASSOCCHANGED, SHCNF_IDLIST};
+    unsafe { SHChangeNotify(SHCNE_ASSOCCHANGED, SHCNF_IDLIST, None, None) };
+}
+
+#[cfg(target_os = "linux")]
+const CAN_UNREGISTER: bool = true;
+
+/// MIME type of `.tswx` files, declared by the app
+#[cfg(target_os = "linux")]
+const TSWX_MIME_TYPE: &str = "application/x-tswx";
+
+#[cfg(target_os = "linux")]
+fn xdg_data_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+}
+
+/// Desktop entry registering the app for one integration
+#[cfg(target_os = "linux")]
+fn desktop_file(integration: Integration) -> Option<(PathBuf, String)> {
+    let (name, mime_type) = match integration {
+        Integration::FileAssociation => ("tsw-io-tswx.desktop", TSWX_MIME_TYPE.to_string()),
+        Integration::UrlScheme => {
+            ("tsw-io-url-handler.desktop", format!("x-scheme-handler/{}", URL_SCHEME))
+        }
+    };
+    Some((xdg_data_home()?.join("applications").join(name), mime_type))
+}
+
+#[cfg(target_os = "linux")]
+fn mime_package() -> Option<PathBuf> {
+    Some(xdg_data_home()?.join("mime/packages/tsw-io.xml"))
+}
+
+/// Run one of the xdg database tools, which not every desktop installs
+#[cfg(target_os = "linux")]
+fn run_tool(program: &str, args: &[&std::ffi::OsStr]) {
+    match std::process::Command::new(program).args(args).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => tracing::warn!("{} failed: {}", program, status),
+        Err(e) => tracing::debug!("Failed to run {}: {}", program, e),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_registered(_handle: &AppHandle, integration: Integration) -> bool {
+    desktop_file(integration).is_some_and(|(path, _)| path.exists())
+}
+
+#[cfg(target_os = "linux")]
+fn register(_handle: &AppHandle, integration: Integration) -> Result<(), String> {
+    let (path, mime_type) = desktop_file(integration)
+        .ok_or_else(|| "Failed to find the applications directory".to_string())?;
+    let exe = executable()?;
+    let field_code = match integration {
+        Integration::FileAssociation => "%f",
+        Integration::UrlScheme => "%u",
+    };
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=TSW IO\nExec=\"{}\" {}\nMimeType={};\nNoDisplay=true\nTerminal=false\n",
+        exe.display(),
+        field_code,
+        mime_type
+    );
+    let write = |path: &std::path::Path, contents: &str| {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    };
+    write(&path, &entry)?;
+
+    if integration == Integration::FileAssociation {
+        let package = mime_package().ok_or_else(|| "Failed to find the MIME directory".to_string())?;
+        write(
+            &package,
+            &format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <mime-info xmlns=\"http://www.freedesktop.org/standards/shared-mime-info\">\n\
+                 \x20 <mime-type type=\"{}\">\n\
+                 \x20   <comment>TSW IO configuration</comment>\n\
+                 \x20   <glob pattern=\"*.{}\"/>\n\
+                 \x20 </mime-type>\n\
+                 </mime-info>\n",
+                TSWX_MIME_TYPE, FILE_EXTENSION
+            ),
+        )?;
+        if let Some(mime_dir) = package.parent().and_then(|packages| packages.parent()) {
+            run_tool("update-mime-database", &[mime_dir.as_os_str()]);
+        }
+    }
+    if let Some(dir) = path.parent() {
+        run_tool("update-desktop-database", &[dir.as_os_str()]);
+    }
+    if let Some(name) = path.file_name() {
+        run_tool("xdg-mime", &["default".as_ref(), name, mime_type.as_ref()]);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn unregister(_handle: &AppHandle, integration: Integration) -> Result<(), String> {
+    let remove = |path: &std::path::Path| match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove {}: {}", path.display(), e))
+        }
+        _ => Ok(()),
+    };
+
+    if let Some((path, _)) = desktop_file(integration) {
+        remove(&path)?;
+        if let Some(dir) = path.parent() {
+            run_tool("update-desktop-database", &[dir.as_os_str()]);
+        }
+    }
+    if integration == Integration::FileAssociation {
+        if let Some(package) = mime_package() {
+            remove(&package)?;
+            if let Some(mime_dir) = package.parent().and_then(|packages| packages.parent()) {
+                run_tool("update-mime-database", &[mime_dir.as_os_str()]);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Launch Services can only switch the default to another app, not remove one
+#[cfg(target_os = "macos")]
+const CAN_UNREGISTER: bool = false;
+
+#[cfg(target_os = "macos")]
+mod launch_services {
+    use objc2_foundation::NSString;
+
+    /// Every role: viewer, editor and shell
+    pub const ROLES_ALL: u32 = 0xFFFF_FFFF;
+
+    #[link(name = "CoreServices", kind = "framework")]
+    extern "C" {
+        // NSString is toll-free bridged with CFString
+        pub fn LSSetDefaultHandlerForURLScheme(scheme: &NSString, bundle_id: &NSString) -> i32;
+        pub fn LSSetDefaultRoleHandlerForContentType(
+            content_type: &NSString,
+            role: u32,
+            bundle_id: &NSString,
+        ) -> i32;
+        pub fn LSCopyDefaultHandlerForURLScheme(scheme: &NSString) -> *mut NSString;
+        pub fn LSCopyDefaultRoleHandlerForContentType(content_type: &NSString, role: u32) -> *mut NSString;
+    }
+}
+
+/// Uniform type identifier Info.plist declares for `.tswx` files
+#[cfg(target_os = "macos")]
+fn content_type(handle: &AppHandle) -> String {
+    format!("{}.{}", handle.config().identifier, FILE_EXTENSION)
+}
+
+#[cfg(target_os = "macos")]
+fn is_registered(handle: &AppHandle, integration: Integration) -> bool {
+    use objc2::rc::Retained;
+    use objc2_foundation::NSString;
+
+    let handler = match integration {
+        Integration::FileAssociation => unsafe {
+            launch_services::LSCopyDefaultRoleHandlerForContentType(
+                &NSString::from_str(&content_type(handle)),
+                launch_services::ROLES_ALL,
+            )
+        },
+        Integration::UrlScheme => unsafe {
+            launch_services::LSCopyDefaultHandlerForURLScheme(&NSString::from_str(URL_SCHEME))
+        },
+    };
+    // The copy is ours to release
+    unsafe { Retained::from_raw(handler) }
+        .is_some_and(|handler| handler.to_string().eq_ignore_ascii_case(&handle.config().identifier))
+}
+
+#[cfg(target_os = "macos")]
+fn register(handle: &AppHandle, integration: Integration) -> Result<(), String> {
+    use objc2_foundation::NSString;
+
+    let bundle_id = NSString::from_str(&handle.config().identifier);
+    let status = match integration {
+        Integration::FileAssociation => unsafe {
+            launch_services::LSSetDefaultRoleHandlerForContentType(
+                &NSString::from_str(&content_type(handle)),
+                launch_services::ROLES_ALL,
+                &bundle_id,
+            )
+        },
+        Integration::UrlScheme => unsafe {
+            launch_services::LSSetDefaultHandlerForURLScheme(&NSString::from_str(URL_SCHEME), &bundle_id)
+        },
+    };
+    if status != 0 {
+        return Err(format!("Failed to register with Launch Services: error {}", status));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn unregister(_handle: &AppHandle, integration: Integration) -> Result<(), String> {
+    Err(match integration {
+        Integration::FileAssociation => format!(
+            "To stop opening .{} files with TSW IO, pick another app in their Get Info window",
+            FILE_EXTENSION
+        ),
+        Integration::UrlScheme => format!(
+            "macOS keeps opening {}:// links with TSW IO until another app registers for them",
+            URL_SCHEME
+        ),
+    })
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+const CAN_UNREGISTER: bool = false;
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+fn is_registered(_handle: &AppHandle, _integration: Integration) -> bool {
+    false
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+fn register(_handle: &AppHandle, _integration: Integration) -> Result<(), String> {
+    Err("File associations are not supported on this platform".to_string())
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+fn unregister(_handle: &AppHandle, _integration: Integration) -> Result<(), String> {
+    Err("File associations are not supported on this platform".to_string())
+}
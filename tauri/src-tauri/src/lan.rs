@@ -0,0 +1,142 @@
+use serde::Serialize;
+use std::net::{IpAddr, Ipv4Addr};
+use tauri::{AppHandle, Manager};
+
+use crate::audit::{self, AuditAction};
+use crate::config::{self, ConfigState};
+use crate::profile::ProfileState;
+use crate::{api, firewall, loopback, mdns, qr, restart, tray};
+
+const WINDOW_TITLE: &str = "TSW IO";
+const LAN_WINDOW_TITLE: &str = "TSW IO (LAN access on)";
+
+/// Whether other machines can reach the backend
+#[derive(Debug, Serialize)]
+pub struct LanAccess {
+    pub enabled: bool,
+    /// Where other machines reach it, `None` while it can't be reached from the network
+    pub url: Option<String>,
+    /// Why the firewall couldn't be opened, other machines may not get through
+    pub firewall_error: Option<String>,
+}
+
+/// A link that lets a browser on another machine in, without pairing
+#[derive(Debug, Serialize)]
+pub struct GuestLink {
+    pub link: String,
+    pub qr_svg: String,
+}
+
+pub fn enabled(handle: &AppHandle) -> bool {
+    handle.state::<ConfigState>().get().lan_access
+}
+
+/// Private IPv4 address other devices on the network reach the backend on
+fn address(port: u16) -> Option<Ipv4Addr> {
+    loopback::exposed_addresses(port).into_iter().find_map(|addr| match addr {
+        IpAddr::V4(v4) if v4.is_private() => Some(v4),
+        _ => None,
+    })
+}
+
+/// The backend's URL for other machines, once it listens on the network
+pub fn url(handle: &AppHandle) -> Option<String> {
+    if !enabled(handle) {
+        return None;
+    }
+    let port = handle.state::<ProfileState>().get().port;
+    address(port).map(|address| format!("http://{}:{}", address, port))
+}
+
+fn status(handle: &AppHandle, firewall_error: Option<String>) -> LanAccess {
+    LanAccess {
+        enabled: enabled(handle),
+        url: url(handle),
+        firewall_error,
+    }
+}
+
+/// Show whether LAN access is on in the main window's title and the tray, and advertise the
+/// backend over mDNS while it is
+pub fn indicate(handle: &AppHandle) {
+    let enabled = enabled(handle);
+    if let Some(window) = handle.get_webview_window("main") {
+        let _ = window.set_title(if enabled { LAN_WINDOW_TITLE } else { WINDOW_TITLE });
+    }
+    tray::sync_lan_access(handle, enabled);
+
+    let profile = handle.state::<ProfileState>().get();
+    match address(profile.port) {
+        Some(address) if enabled && profile.backend_url.is_none() => mdns::advertise(profile.port, address),
+        _ => mdns::stop(),
+    }
+}
+
+/// Restart the bundled backend listening on the network, or only on this machine again
+///
+/// Other machines still need a device token from pairing or a guest link to get past the
+/// backend, being on the network is not enough.
+pub fn set_enabled(handle: &AppHandle, enabled: bool) -> Result<LanAccess, String> {
+    let profile = handle.state::<ProfileState>().get();
+    if profile.backend_url.is_some() {
+        return Err("LAN access is only for the server bundled with tsw_io".to_string());
+    }
+    if enabled == self::enabled(handle) {
+        return Ok(status(handle, None));
+    }
+
+    config::update(handle, |config| config.lan_access = enabled)?;
+    let firewall = if enabled {
+        firewall::open(profile.port)
+    } else {
+        firewall::close(profile.port)
+    };
+    if let Err(e) = &firewall {
+        tracing::warn!("{}", e);
+    }
+
+    let detail = if enabled {
+        "Restarted to turn LAN access on"
+    } else {
+        "Restarted to turn LAN access off"
+    };
+    tracing::info!(enabled, "Changing LAN access");
+    audit::record(handle, AuditAction::BackendRestart, detail);
+    // The backend only picks its address when it starts
+    restart::restart(handle)?;
+    Ok(status(handle, firewall.err()))
+}
+
+fn guest_link(handle: &AppHandle) -> Result<GuestLink, String> {
+    let url = url(handle).ok_or_else(|| "Turn on LAN access to share the app with a browser".to_string())?;
+    let token = api::guest_token(&handle.state::<ProfileState>().get().base_url())?;
+    let link = format!("{}/pairing/session?token={}", url, urlencoding::encode(&token));
+    Ok(GuestLink {
+        qr_svg: qr::svg(&link)?,
+        link,
+    })
+}
+
+#[tauri::command]
+pub async fn get_lan_access(app: AppHandle) -> Result<LanAccess, String> {
+    // Finding the address scans the interfaces
+    tauri::async_runtime::spawn_blocking(move || status(&app, None))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_lan_access(app: AppHandle, enabled: bool) -> Result<LanAccess, String> {
+    // Waits for the firewall prompt and the backend restart
+    tauri::async_runtime::spawn_blocking(move || set_enabled(&app, enabled))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// A new guest link, valid for 12 hours or until LAN access is turned off
+#[tauri::command]
+pub async fn share_lan_access(app: AppHandle) -> Result<GuestLink, String> {
+    tauri::async_runtime::spawn_blocking(move || guest_link(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
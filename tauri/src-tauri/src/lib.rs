@@ -1,150 +1,1075 @@
+#[cfg(desktop)]
+use tauri::ipc::CapabilityBuilder;
+#[cfg(desktop)]
 use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+#[cfg(desktop)]
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+#[cfg(desktop)]
 use tauri_plugin_shell::ShellExt;
-use std::time::Duration;
-
-const BACKEND_PORT: u16 = 4000;
-const MAX_RETRIES: u32 = 120; // 2 minutes max wait
-const RETRY_DELAY_MS: u64 = 500;
-
-/// Check if the backend is fully ready (migrations complete) by checking health endpoint
-fn check_backend_ready() -> Result<bool, String> {
-    let url = format!("http://localhost:{}/api/health", BACKEND_PORT);
-    match reqwest::blocking::get(&url) {
-        Ok(response) => {
-            if response.status().is_success() {
-                Ok(true)
-            } else {
-                // Server is up but not ready (e.g., migrations running)
-                Ok(false)
-            }
-        }
-        Err(_) => {
-            // Server not yet responding
-            Ok(false)
+#[cfg(desktop)]
+use std::sync::{Mutex, RwLock};
+#[cfg(desktop)]
+use std::time::{Duration, Instant};
+
+#[cfg(desktop)]
+mod accounts;
+#[cfg(desktop)]
+mod api;
+#[cfg(desktop)]
+mod archive;
+#[cfg(desktop)]
+mod assets;
+#[cfg(desktop)]
+mod audit;
+#[cfg(desktop)]
+mod automation;
+#[cfg(desktop)]
+mod background;
+#[cfg(desktop)]
+mod badge;
+#[cfg(desktop)]
+mod beam;
+#[cfg(desktop)]
+mod bridge;
+#[cfg(desktop)]
+mod capture;
+#[cfg(desktop)]
+mod cli;
+#[cfg(desktop)]
+mod commands;
+#[cfg(desktop)]
+mod companion;
+#[cfg(desktop)]
+mod config;
+#[cfg(desktop)]
+mod confirm;
+#[cfg(desktop)]
+mod console;
+#[cfg(desktop)]
+mod control;
+#[cfg(desktop)]
+mod crash;
+#[cfg(desktop)]
+mod csp;
+#[cfg(desktop)]
+mod devbackend;
+#[cfg(desktop)]
+mod diagnostics;
+#[cfg(desktop)]
+mod downloads;
+#[cfg(desktop)]
+mod failure;
+#[cfg(all(desktop, feature = "test-support"))]
+mod fakesidecar;
+#[cfg(desktop)]
+mod fastpath;
+#[cfg(desktop)]
+mod features;
+#[cfg(desktop)]
+mod firewall;
+#[cfg(desktop)]
+mod folders;
+#[cfg(desktop)]
+mod gpu;
+#[cfg(desktop)]
+mod headless;
+#[cfg(desktop)]
+mod health;
+#[cfg(desktop)]
+mod hotkeys;
+#[cfg(desktop)]
+mod idle;
+#[cfg(desktop)]
+mod imports;
+#[cfg(desktop)]
+mod integrations;
+#[cfg(desktop)]
+mod lan;
+#[cfg(desktop)]
+mod license;
+#[cfg(desktop)]
+mod localhttp;
+#[cfg(desktop)]
+mod lock;
+#[cfg_attr(mobile, allow(dead_code))]
+mod logging;
+#[cfg(desktop)]
+mod loopback;
+#[cfg(desktop)]
+mod maintenance;
+mod mdns;
+#[cfg(desktop)]
+mod memory;
+#[cfg(desktop)]
+mod menu;
+#[cfg(desktop)]
+mod menubar;
+#[cfg(desktop)]
+mod metrics;
+#[cfg(desktop)]
+mod mock;
+#[cfg(mobile)]
+mod mobile;
+#[cfg(desktop)]
+mod monitor;
+#[cfg(desktop)]
+mod navigation;
+#[cfg(desktop)]
+mod network;
+#[cfg(desktop)]
+mod notifications;
+#[cfg(mobile)]
+mod offline;
+#[cfg(desktop)]
+mod overlay;
+#[cfg(desktop)]
+mod palette;
+#[cfg(desktop)]
+mod pairing;
+#[cfg(desktop)]
+mod passphrase;
+#[cfg(desktop)]
+mod paths;
+#[cfg(desktop)]
+mod pdf;
+#[cfg(desktop)]
+mod policy;
+#[cfg(desktop)]
+mod port;
+#[cfg(desktop)]
+mod preview;
+#[cfg(desktop)]
+mod profile;
+#[cfg(desktop)]
+mod progress;
+#[cfg(desktop)]
+mod proxy;
+#[cfg(desktop)]
+mod qr;
+#[cfg(desktop)]
+mod quiet;
+#[cfg(desktop)]
+mod recent;
+#[cfg(desktop)]
+mod recovery;
+#[cfg(desktop)]
+mod relocate;
+#[cfg(desktop)]
+mod report;
+#[cfg(desktop)]
+mod restart;
+#[cfg(desktop)]
+mod scheduler;
+#[cfg(desktop)]
+mod search;
+#[cfg(desktop)]
+mod secrets;
+#[cfg(desktop)]
+mod selftest;
+#[cfg(desktop)]
+mod service;
+#[cfg(desktop)]
+mod splash;
+#[cfg(desktop)]
+mod sso;
+#[cfg(desktop)]
+mod storage;
+#[cfg(desktop)]
+mod supervisor;
+#[cfg(desktop)]
+mod telemetry;
+#[cfg(desktop)]
+mod timing;
+#[cfg_attr(mobile, allow(dead_code))]
+mod tls;
+#[cfg(desktop)]
+mod tray;
+#[cfg(desktop)]
+mod upgrade;
+#[cfg(desktop)]
+mod upload;
+#[cfg(desktop)]
+mod vault;
+#[cfg(desktop)]
+mod watchdog;
+#[cfg(desktop)]
+mod webapi;
+#[cfg(desktop)]
+mod webhooks;
+#[cfg(desktop)]
+mod widgets;
+#[cfg(desktop)]
+mod wizard;
+
+#[cfg(desktop)]
+use background::Background;
+#[cfg(desktop)]
+use config::ConfigState;
+#[cfg(desktop)]
+use downloads::Downloads;
+#[cfg(desktop)]
+use failure::{FailureKind, LastFailure};
+#[cfg(desktop)]
+use fastpath::FastPath;
+#[cfg(desktop)]
+use features::FeatureFlags;
+#[cfg(desktop)]
+use lock::AppLock;
+#[cfg(desktop)]
+use metrics::MetricsHistory;
+#[cfg(desktop)]
+use monitor::{BackendExit, Monitor};
+#[cfg(desktop)]
+use passphrase::Passphrase;
+#[cfg(desktop)]
+use paths::AppPaths;
+#[cfg(desktop)]
+use profile::{Profile, ProfileState};
+#[cfg(desktop)]
+use storage::DataDirLock;
+#[cfg(desktop)]
+use supervisor::{Supervisor, SupervisorState};
+#[cfg(desktop)]
+use telemetry::{StartupOutcome, StartupReport};
+#[cfg(desktop)]
+use timing::{StartupBudget, StartupClock, Stage};
+
+/// Where the bundled backend, or the proxy in front of it, listens
+#[cfg(desktop)]
+pub(crate) fn listen_address(shell_config: &config::ShellConfig) -> &'static str {
+    if shell_config.lan_access {
+        loopback::LAN_BIND_ADDRESS
+    } else {
+        loopback::BIND_ADDRESS
+    }
+}
+
+/// How long a killed sidecar gets to go away before giving up on it
+#[cfg(desktop)]
+const BACKEND_EXIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Handle to the running backend sidecar
+#[cfg(desktop)]
+struct BackendProcess(Mutex<Option<CommandChild>>);
+
+/// Kill the backend sidecar if it is running
+#[cfg(desktop)]
+pub(crate) fn stop_backend(handle: &tauri::AppHandle) {
+    supervisor::backend_stopped(handle);
+    if let Some(child) = handle.state::<BackendProcess>().0.lock().unwrap().take() {
+        if let Err(e) = child.kill() {
+            tracing::error!("Failed to stop backend sidecar: {}", e);
         }
     }
+    // A proxy in front keeps listening, holding requests for the backend launched next
+    storage::release(handle);
 }
 
-/// Wait for the backend to become fully ready
-fn wait_for_backend(handle: &tauri::AppHandle) -> bool {
-    // Get the splash window to update status
-    let splash_window = handle.get_webview_window("splash");
-
-    for attempt in 1..=MAX_RETRIES {
-        match check_backend_ready() {
-            Ok(true) => {
-                println!("Backend ready after {} attempts", attempt);
-                return true;
-            }
-            Ok(false) => {
-                // Update splash screen status
-                if let Some(ref window) = splash_window {
-                    let status = if attempt < 10 {
-                        "Starting server..."
-                    } else if attempt < 30 {
-                        "Running database migrations..."
-                    } else {
-                        "Almost ready..."
-                    };
-                    let _ = window.eval(&format!(
-                        "document.getElementById('status').textContent = '{}'",
-                        status
-                    ));
-                }
-            }
-            Err(e) => {
-                eprintln!("Health check error: {}", e);
+/// Stop the backend sidecar and wait for the process to be gone, so its files and ports are free
+#[cfg(desktop)]
+pub(crate) fn stop_backend_and_wait(handle: &tauri::AppHandle) -> Result<(), String> {
+    supervisor::backend_stopped(handle);
+    let child = handle.state::<BackendProcess>().0.lock().unwrap().take();
+    let stopped = child.map(kill_and_wait).unwrap_or(Ok(()));
+    storage::release(handle);
+    stopped
+}
+
+/// Kill a sidecar and wait until its process is gone
+#[cfg(desktop)]
+pub(crate) fn kill_and_wait(child: CommandChild) -> Result<(), String> {
+    let pid = sysinfo::Pid::from_u32(child.pid());
+    child.kill().map_err(|e| e.to_string())?;
+    let deadline = Instant::now() + BACKEND_EXIT_TIMEOUT;
+    let mut system = sysinfo::System::new();
+    loop {
+        system.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[pid]), true);
+        // Gone, or only waiting to be reaped
+        match system.process(pid) {
+            None => return Ok(()),
+            Some(process) if process.status() == sysinfo::ProcessStatus::Zombie => return Ok(()),
+            Some(_) if Instant::now() >= deadline => {
+                return Err(format!("process {} is still running", pid));
             }
+            Some(_) => std::thread::sleep(Duration::from_millis(100)),
         }
-
-        println!("Waiting for backend... attempt {}/{}", attempt, MAX_RETRIES);
-        std::thread::sleep(Duration::from_millis(RETRY_DELAY_MS));
     }
-    false
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_shell::init())
-        .setup(|app| {
-            let handle = app.handle().clone();
-
-            // Create splash screen window first
-            let splash_html = include_str!("../splash.html");
-            let splash_url = format!("data:text/html,{}", urlencoding::encode(splash_html));
-
-            let splash_window = WebviewWindowBuilder::new(
-                &handle,
-                "splash",
-                WebviewUrl::External(splash_url.parse().unwrap()),
-            )
-            .title("TSW IO")
-            .inner_size(400.0, 300.0)
-            .resizable(false)
-            .decorations(false)
-            .center()
-            .build()
-            .expect("Failed to create splash window");
-
-            // Spawn the Elixir backend as a sidecar process
-            let sidecar = match handle.shell().sidecar("tsw_io_backend") {
+/// Whether a backend sidecar is currently supposed to be running
+#[cfg(desktop)]
+pub(crate) fn backend_running(handle: &tauri::AppHandle) -> bool {
+    handle.state::<BackendProcess>().0.lock().unwrap().is_some()
+}
+
+/// Process id of the backend sidecar, if the shell is running one
+#[cfg(desktop)]
+pub(crate) fn backend_pid(handle: &tauri::AppHandle) -> Option<u32> {
+    handle.state::<BackendProcess>().0.lock().unwrap().as_ref().map(|child| child.pid())
+}
+
+/// Take the backend sidecar out of the shell's hands without stopping it
+///
+/// Its output and exit are ignored from then on, until it is attached again.
+#[cfg(desktop)]
+pub(crate) fn detach_backend(handle: &tauri::AppHandle) -> Option<CommandChild> {
+    handle.state::<BackendProcess>().0.lock().unwrap().take()
+}
+
+/// Make a detached sidecar the shell's backend again
+#[cfg(desktop)]
+pub(crate) fn attach_backend(handle: &tauri::AppHandle, child: CommandChild) {
+    *handle.state::<BackendProcess>().0.lock().unwrap() = Some(child);
+    monitor::backend_reattached(handle);
+}
+
+/// Spawn the bundled Elixir backend for `profile`
+#[cfg(desktop)]
+pub(crate) fn spawn_sidecar(handle: &tauri::AppHandle, profile: &Profile) -> Result<(), Box<dyn std::error::Error>> {
+    let shell_config = handle.state::<ConfigState>().get();
+    let app_paths = handle.state::<AppPaths>();
+
+    #[cfg(feature = "test-support")]
+    let fake = fakesidecar::command(handle);
+    #[cfg(not(feature = "test-support"))]
+    let fake: Option<Result<_, String>> = None;
+    let sidecar = match fake {
+        Some(Ok(cmd)) => cmd,
+        Some(Err(e)) => {
+            tracing::error!("{}", e);
+            return Err(e.into());
+        }
+        // Replaced by an upgrade of the backend alone
+        None => match upgrade::sidecar() {
+            Some(program) => handle.shell().command(program),
+            None => match handle.shell().sidecar("tsw_io_backend") {
                 Ok(cmd) => cmd,
                 Err(e) => {
-                    eprintln!("Failed to create sidecar command: {}", e);
+                    tracing::error!("Failed to create sidecar command: {}", e);
                     return Err(Box::new(e));
                 }
-            };
+            },
+        },
+    };
 
-            let (mut _rx, _child) = match sidecar
-                .env("PORT", BACKEND_PORT.to_string())
-                .env("MIX_ENV", "prod")
-                .env("BURRITO", "1")
-                .spawn()
-            {
-                Ok(result) => result,
-                Err(e) => {
-                    eprintln!("Failed to spawn backend sidecar: {}", e);
-                    return Err(Box::new(e));
+    let certificate = if profile.tls {
+        Some(tls::ensure_certificate(&app_paths.config_dir.join("tls"))?)
+    } else {
+        None
+    };
+    let bind_address = listen_address(&shell_config);
+    // Behind the proxy the backend only listens on this machine, over plain HTTP
+    let (port, bind_address, tls_env, proxy_env) = if shell_config.proxy {
+        let assets_dir = handle.path().app_cache_dir().ok().map(|dir| dir.join("assets"));
+        let backend_port = proxy::start(bind_address, profile.port, certificate.as_ref(), assets_dir)?;
+        let public_url = vec![("TSW_IO_PUBLIC_URL", profile.base_url())];
+        (backend_port, loopback::BIND_ADDRESS, Vec::new(), public_url)
+    } else {
+        let tls_env = certificate.map(|certificate| certificate.sidecar_env()).unwrap_or_default();
+        (profile.port, bind_address, tls_env, Vec::new())
+    };
+
+    let (mut rx, child) = match sidecar
+        .env("PORT", port.to_string())
+        .env("TSW_IO_BIND_ADDRESS", bind_address)
+        .env("DATABASE_PATH", profile.data_dir.join(wizard::DATABASE_FILE_NAME))
+        .env("TSW_IO_DATA_DIR", &profile.data_dir)
+        .env("TSW_IO_LOG_DIR", &app_paths.log_dir)
+        .env("BURRITO", "1")
+        .env("TSW_IO_UPDATE_CHANNEL", shell_config.update_channel.as_str())
+        .env("TSW_IO_OFFLINE", if shell_config.offline { "1" } else { "0" })
+        .env("TSW_IO_FEATURES", handle.state::<FeatureFlags>().to_env())
+        .env("TSW_IO_CSP", csp::policy(&shell_config, &profile.base_url()))
+        .env("TSW_IO_READY_SIGNAL", "1")
+        .env("TSW_IO_SAFE_MODE", if supervisor::safe_mode(handle) { "1" } else { "0" })
+        .envs(console::sidecar_env(&shell_config))
+        .envs(beam::sidecar_env(&shell_config.beam))
+        .envs(secrets::sidecar_env())
+        .envs(tls_env)
+        .envs(proxy_env)
+        .envs(network::sidecar_env(&shell_config))
+        .envs(sso::sidecar_env(&shell_config))
+        .envs(passphrase::sidecar_env(handle))
+        .spawn()
+    {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::error!("Failed to spawn backend sidecar: {}", e);
+            proxy::stop(profile.port);
+            return Err(Box::new(e));
+        }
+    };
+    let pid = child.pid();
+    *handle.state::<BackendProcess>().0.lock().unwrap() = Some(child);
+    timing::mark(handle, Stage::Spawned);
+    monitor::backend_spawned(handle);
+
+    let handle = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            // A sidecar that was stopped, replaced or detached on purpose is not of interest,
+            // but a detached one may be attached again
+            if backend_pid(&handle) != Some(pid) {
+                continue;
+            }
+            match event {
+                CommandEvent::Stdout(line) | CommandEvent::Stderr(line) => {
+                    let line = String::from_utf8_lossy(&line).trim_end().to_string();
+                    monitor::record_output(&handle, line);
                 }
-            };
+                CommandEvent::Terminated(payload) => {
+                    // Decide on a restart before the startup wait notices the exit
+                    supervisor::backend_exited(&handle);
+                    monitor::record_exit(
+                        &handle,
+                        BackendExit {
+                            code: payload.code,
+                            signal: payload.signal,
+                        },
+                    );
+                    let output = monitor::recent_output(&handle);
+                    tauri::async_runtime::spawn_blocking(move || {
+                        crash::report_backend_exit(payload.code, payload.signal, output)
+                    });
+                    break;
+                }
+                _ => {}
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Stop the backend and lock its data before the process goes away
+#[cfg(desktop)]
+fn on_exit(handle: &tauri::AppHandle) {
+    // Setup may have failed before any state was managed
+    let (Some(config), Some(profile)) = (
+        handle.try_state::<ConfigState>(),
+        handle.try_state::<ProfileState>(),
+    ) else {
+        return;
+    };
+    // Only a backend that was running fine lets the next launch skip its checks
+    let clean = supervisor::state(handle) == SupervisorState::Running;
+    let shared = storage::is_locked(handle);
+    control::stop(handle);
+    mdns::stop();
+    stop_backend(handle);
+    proxy::stop_all();
+    // The backend service keeps the data open after the app is gone
+    if service::in_use() {
+        return;
+    }
+    if clean {
+        fastpath::record_clean_shutdown(handle, &profile.get().data_dir, shared);
+    }
+    vault::close(&config.get(), &profile.get().data_dir);
+    service::hand_over(handle);
+}
+
+/// Build the main window hidden and blank, ready to load the backend once it answers
+#[cfg(desktop)]
+fn prepare_main_window(
+    handle: &tauri::AppHandle,
+    base_url: &str,
+) -> tauri::Result<tauri::WebviewWindow> {
+    // Reused when relaunching after a crash, so a new one doesn't race the old one's teardown
+    if let Some(window) = handle.get_webview_window("main") {
+        let _ = window.hide();
+        let _ = window.navigate(navigation::BLANK_URL.parse().unwrap());
+        return Ok(window);
+    }
+    // Each account keeps its cookies and storage apart
+    let builder = accounts::isolate(
+        handle,
+        WebviewWindowBuilder::new(
+            handle,
+            "main",
+            WebviewUrl::External(navigation::BLANK_URL.parse().unwrap()),
+        ),
+    );
+    navigation::restrict(builder, base_url)
+    .initialization_script(webapi::SCRIPT)
+    .title("TSW IO")
+    .inner_size(1200.0, 800.0)
+    .min_inner_size(800.0, 600.0)
+    .visible(false)
+    .build()
+}
 
-            // Wait for backend to be ready in a separate thread
-            let splash_handle = splash_window;
-            std::thread::spawn(move || {
-                if wait_for_backend(&handle) {
-                    // Create the main window once backend is ready
-                    let url = format!("http://localhost:{}", BACKEND_PORT);
+/// Show the main window, building it first when the app started in the background
+#[cfg(desktop)]
+pub(crate) fn open_main_window(handle: &tauri::AppHandle) {
+    if headless::enabled() {
+        return;
+    }
+    background::leave(handle);
+    if let Some(window) = handle.get_webview_window("main") {
+        lock::reveal(handle, &window);
+        return;
+    }
 
-                    let main_window = WebviewWindowBuilder::new(
+    match supervisor::state(handle) {
+        SupervisorState::Running => {
+            let base_url = handle.state::<ProfileState>().get().base_url();
+            let url = accounts::entry_url(handle, &base_url);
+            let result = prepare_main_window(handle, &base_url)
+                .and_then(|window| window.navigate(url.parse().unwrap()).map(|_| window));
+            match result {
+                Ok(window) => lock::reveal(handle, &window),
+                Err(e) => tracing::error!("Failed to open main window: {}", e),
+            }
+        }
+        // The launch opens it once the backend is ready
+        SupervisorState::Starting | SupervisorState::Restarting => {
+            if let Err(e) = splash::open(handle) {
+                tracing::warn!("Failed to open splash window: {}", e);
+            }
+        }
+        // A failure or recovery window is already showing
+        SupervisorState::Idle | SupervisorState::CrashLoop => {}
+    }
+}
+
+/// Show the splash screen, spawn the backend and open the main window once it is ready
+#[cfg(desktop)]
+pub(crate) fn launch(handle: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    let handle = handle.clone();
+    let profile = handle.state::<ProfileState>().get();
+    let base_url = profile.base_url();
+    let span = tracing::info_span!("launch", profile = %profile.name, %base_url);
+    let _entered = span.enter();
+    timing::start(&handle);
+    supervisor::backend_launching(&handle);
+    let skips = fastpath::take(&handle, &profile.data_dir);
+
+    // Started in the background, nothing shows until the user opens the app from the tray
+    let background = background::is_deferred(&handle);
+    if !background {
+        let splash_window = splash::open(&handle).expect("Failed to create splash window");
+        splash::set_status(&splash_window, "Starting...");
+    }
+
+    match &profile.backend_url {
+        // Someone else runs the backend, only wait for it
+        Some(url) => tracing::info!(%url, "Using external backend"),
+        None => {
+            let shell_config = handle.state::<ConfigState>().get();
+            let config_dir = &handle.state::<AppPaths>().config_dir;
+            if let Err(e) = vault::open(&shell_config, &profile.data_dir, config_dir) {
+                tracing::error!("{}", e);
+                telemetry::report_startup(
+                    &handle,
+                    StartupReport {
+                        outcome: StartupOutcome::SpawnFailed,
+                        time_to_ready_ms: None,
+                        health_check_attempts: 0,
+                        failure: Some(FailureKind::DataLocked),
+                    },
+                );
+                splash::close(&handle);
+                failure::show(&handle, FailureKind::DataLocked);
+                return Ok(());
+            }
+            let shared = if skips.storage_detection {
+                None
+            } else {
+                storage::detect(&profile.data_dir)
+            };
+            if let Some(location) = shared {
+                storage::warn_shared(&handle, &profile.data_dir, &location);
+                if let Err(e) = storage::acquire(&handle, &profile.data_dir) {
+                    tracing::error!("{}", e);
+                    telemetry::report_startup(
                         &handle,
-                        "main",
-                        WebviewUrl::External(url.parse().unwrap()),
-                    )
-                    .title("TSW IO")
-                    .inner_size(1200.0, 800.0)
-                    .min_inner_size(800.0, 600.0)
-                    .build()
-                    .expect("Failed to create main window");
-
-                    // Close splash and show main window
-                    let _ = splash_handle.close();
-                    let _ = main_window.show();
-                } else {
-                    eprintln!("Backend failed to start after {} attempts", MAX_RETRIES);
-                    // Show error on splash screen before exiting
-                    let _ = splash_handle.eval(
-                        "document.getElementById('status').textContent = 'Failed to start. Please restart the app.';\
-                         document.getElementById('status').style.color = '#ef4444';"
+                        StartupReport {
+                            outcome: StartupOutcome::SpawnFailed,
+                            time_to_ready_ms: None,
+                            health_check_attempts: 0,
+                            failure: Some(FailureKind::DataDirInUse),
+                        },
                     );
-                    std::thread::sleep(Duration::from_secs(3));
-                    std::process::exit(1);
+                    splash::close(&handle);
+                    failure::show(&handle, FailureKind::DataDirInUse);
+                    return Ok(());
                 }
-            });
+            }
+            if recovery::database_damaged(&profile.data_dir) {
+                tracing::error!("The database is not a SQLite database");
+                splash::close(&handle);
+                recovery::show_damaged(&handle);
+                return Ok(());
+            }
+            if passphrase::needs_prompt(&handle, &profile.data_dir) {
+                splash::close(&handle);
+                passphrase::show(&handle);
+                return Ok(());
+            }
+            if let Err(e) = spawn_sidecar(&handle, &profile) {
+                tracing::error!("Backend could not be started: {}", e);
+                // The proxy couldn't take the port, otherwise the sidecar couldn't be run
+                let kind = match failure::classify(&[e.to_string()], None) {
+                    FailureKind::PortInUse => FailureKind::PortInUse,
+                    _ => FailureKind::CorruptedInstall,
+                };
+                telemetry::report_startup(
+                    &handle,
+                    StartupReport {
+                        outcome: StartupOutcome::SpawnFailed,
+                        time_to_ready_ms: None,
+                        health_check_attempts: 0,
+                        failure: Some(kind),
+                    },
+                );
+                splash::close(&handle);
+                failure::show(&handle, kind);
+                return Ok(());
+            }
+        }
+    }
+
+    // Creating a webview takes a while, so it happens while the backend starts
+    let main_window = (!background)
+        .then(|| prepare_main_window(&handle, &base_url).expect("Failed to create main window"));
+
+    // Wait for backend to be ready in a separate thread
+    let thread_span = span.clone();
+    std::thread::spawn(move || {
+        let _entered = thread_span.enter();
+        // Only the bundled backend's startup time is ours to learn from
+        let bundled = profile.backend_url.is_none();
+        let budget = match bundled {
+            true if skips.fast_polling => timing::budget(&handle).polling_fast(),
+            true => timing::budget(&handle),
+            false => StartupBudget::default(),
+        };
+        match health::wait_for_backend(&handle, &base_url, bundled, &budget) {
+            Ok(attempts) => {
+                // Reachable from the network on purpose with LAN access
+                let lan_access = handle.state::<ConfigState>().get().lan_access;
+                if bundled && !skips.exposure_scan && !lan_access {
+                    loopback::verify(&handle, profile.port);
+                }
+
+                // Also built now if the user asked for it from the tray while starting in the background
+                let main_window = main_window.or_else(|| {
+                    (!background::is_deferred(&handle)).then(|| {
+                        prepare_main_window(&handle, &base_url).expect("Failed to create main window")
+                    })
+                });
+
+                // Load the backend into the waiting main window
+                if let Some(main_window) = &main_window {
+                    // Session cookies are gone after a restart, the credential store keeps the token
+                    sso::inject(&handle);
+                    let url = accounts::entry_url(&handle, &base_url);
+                    if let Err(e) = main_window.navigate(url.parse().unwrap()) {
+                        tracing::error!("Failed to load the backend in the main window: {}", e);
+                    }
+                }
+
+                // Close splash and show main window, it stays hidden while the app is locked
+                splash::close(&handle);
+                if let Some(main_window) = &main_window {
+                    lock::reveal(&handle, main_window);
+                }
+                supervisor::backend_ready(&handle);
+                lan::indicate(&handle);
+                headless::ready(&handle);
+                companion::ready(&handle);
+                service::check_version(&handle);
+                timing::mark(&handle, Stage::MainWindow);
+                let timings = timing::timings(&handle);
+                if let Some(ready_ms) = timings.ready_ms.filter(|_| bundled) {
+                    timing::record_ready(&handle, ready_ms);
+                }
+                tracing::info!(
+                    spawned_ms = timings.spawned_ms,
+                    first_response_ms = timings.first_response_ms,
+                    ready_ms = timings.ready_ms,
+                    main_window_ms = timings.main_window_ms,
+                    "Main window opened"
+                );
+                telemetry::report_startup(
+                    &handle,
+                    StartupReport {
+                        outcome: StartupOutcome::Ready,
+                        time_to_ready_ms: timings.ready_ms,
+                        health_check_attempts: attempts,
+                        failure: None,
+                    },
+                );
+
+                watchdog::start(handle.clone());
+                health::start_heartbeat(handle);
+            }
+            Err(attempts) => {
+                let kind = failure::classify(
+                    &monitor::recent_output(&handle),
+                    monitor::backend_exit(&handle).as_ref(),
+                );
+                tracing::error!(attempts, ?kind, "Backend failed to start");
+                telemetry::report_startup(
+                    &handle,
+                    StartupReport {
+                        outcome: StartupOutcome::Failed,
+                        time_to_ready_ms: None,
+                        health_check_attempts: attempts,
+                        failure: Some(kind),
+                    },
+                );
+                splash::close(&handle);
+                // An encrypted database the passphrase didn't open looks damaged to SQLite
+                if kind == FailureKind::DatabaseCorrupted && passphrase::is_encrypted(&profile.data_dir) {
+                    passphrase::rejected(&handle);
+                } else if kind == FailureKind::DatabaseCorrupted {
+                    recovery::show_damaged(&handle);
+                } else if supervisor::state(&handle) != SupervisorState::CrashLoop {
+                    // A crash loop already brought up the recovery options
+                    failure::show(&handle, kind);
+                }
+                // Only now, so the app always has a window and doesn't quit
+                if let Some(main_window) = main_window {
+                    let _ = main_window.destroy();
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Read the configuration, resolve paths and the profile, then start the first window
+#[cfg(desktop)]
+fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
+    let handle = app.handle().clone();
+
+    let args = cli::parse();
+    // Left by `companion::run` for the app: run the backend without any window
+    let headless = args.headless || args.command.is_some();
+    if headless {
+        headless::enable();
+    }
+    let config_dir = paths::config_dir(&args);
+    let config_path = config_dir.join(config::CONFIG_FILE_NAME);
+    // A missing config file means this is the first launch
+    let config_result = config::load_existing(&config_path);
+    let existing_config = config_result
+        .clone()
+        .unwrap_or_else(|_| Some(config::ShellConfig::default()));
+    let first_run = existing_config.is_none();
+    let mut shell_config = existing_config.unwrap_or_default();
+
+    // Logging needs the log directory, so it starts once the config is read
+    let app_paths = AppPaths::resolve(&args, config_dir, &shell_config);
+    logging::init(&app_paths.log_dir, shell_config.log_level);
+    crash::set_log_dir(&app_paths.log_dir);
+    crash::show_last_crash(&handle);
+    if let Err(e) = config_result {
+        tracing::error!("{}, using defaults", e);
+    }
+
+    let managed_policy = policy::load();
+    managed_policy.apply(&mut shell_config);
+    crash::configure(&shell_config);
+    network::configure(&shell_config);
+    gpu::configure(&shell_config);
+    let profile_name = args.profile.as_deref().unwrap_or(profile::DEFAULT_PROFILE);
+    let mut active_profile = profile::resolve(&shell_config, profile_name, &app_paths)?;
+    upgrade::load(&handle);
+    // The backend the policy names wins over anything the command line or a service offers
+    if managed_policy.backend_url.is_some() && (args.mock_backend.is_some() || args.dev_backend.is_some()) {
+        return Err("The backend is set by policy, --mock-backend and --dev-backend can't replace it".into());
+    }
+    if let Some(scenario) = &args.mock_backend {
+        // Handled like an external backend from here on
+        active_profile.backend_url = Some(mock::start(mock::Scenario::parse(scenario)?)?);
+        active_profile.tls = false;
+    } else if active_profile.backend_url.is_none() || args.dev_backend.is_some() {
+        if let Some(url) = devbackend::resolve(args.dev_backend.as_deref())? {
+            active_profile.backend_url = Some(url);
+            active_profile.tls = false;
+        } else if !headless {
+            // Handled like an external backend too, it outlives the app
+            active_profile.backend_url = service::attach(&app_paths.config_dir);
+        }
+    }
+    tracing::info!(
+        profile = %active_profile.name,
+        data_dir = %active_profile.data_dir.display(),
+        log_dir = %app_paths.log_dir.display(),
+        "Starting tsw_io"
+    );
+
+    if args.self_test {
+        let report = selftest::run(&active_profile, false);
+        print!("{}", report.render());
+        logging::flush();
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
 
+    if let Some(url) = &active_profile.backend_url {
+        // The bundled capabilities only cover localhost, see `capabilities/backend.json`
+        app.add_capability(
+            CapabilityBuilder::new("external-backend")
+                .remote(url.clone())
+                .window("main")
+                .permission("core:default")
+                .permission("backend-pages"),
+        )?;
+        // Widgets are dragged around by their page, see `capabilities/widget-pages.json`
+        app.add_capability(
+            CapabilityBuilder::new("external-backend-widgets")
+                .remote(url.clone())
+                .window(format!("{}*", widgets::WINDOW_PREFIX))
+                .permission("core:default")
+                .permission("backend-pages")
+                .permission("core:window:allow-start-dragging"),
+        )?;
+    }
+
+    let feature_flags = FeatureFlags::resolve(&shell_config, &app_paths.config_dir);
+    if let Some(url) = features::remote_url(&shell_config) {
+        features::refresh_remote(url, app_paths.config_dir.clone());
+    }
+
+    // Nobody could unlock it without a window
+    app.manage(AppLock::new(shell_config.app_lock && !headless));
+    app.manage(ConfigState(RwLock::new(shell_config)));
+    app.manage(MetricsHistory::load(&app_paths.log_dir));
+    app.manage(FastPath::load(&app_paths.log_dir));
+    app.manage(Downloads::load(&app_paths.config_dir));
+    app.manage(app_paths);
+    app.manage(managed_policy);
+    app.manage(ProfileState(RwLock::new(active_profile)));
+    app.manage(BackendProcess(Mutex::new(None)));
+    app.manage(StartupClock::default());
+    app.manage(Monitor::default());
+    app.manage(LastFailure::default());
+    app.manage(Supervisor::default());
+    app.manage(Passphrase::default());
+    app.manage(DataDirLock::default());
+    app.manage(Background::new(args.background || headless));
+    // `backend stop` and `backend status` reach the app started by `backend start` through it
+    // Scripts reach the backend service's endpoint while the app uses it
+    let control_api = handle.state::<ConfigState>().get().control_api && !service::in_use();
+    if control_api || args.command == Some(cli::Command::BackendStart) {
+        // Scripts are a convenience, the app runs fine without them
+        if let Err(e) = control::start(&handle) {
+            tracing::error!("{}", e);
+        }
+    }
+    if let Some(port) = handle.state::<ConfigState>().get().webhook_port {
+        // Local tools are a convenience too
+        if let Err(e) = webhooks::start(&handle, port) {
+            tracing::error!("{}", e);
+        }
+    }
+    metrics::start_recording(handle.clone());
+    config::watch(handle.clone(), config_path);
+    lock::watch_wake(handle.clone());
+    idle::start(handle.clone());
+    memory::start(handle.clone());
+    folders::start(handle.clone());
+    scheduler::start(handle.clone());
+    license::start(handle.clone());
+    search::start(handle.clone());
+    bridge::start(handle.clone());
+    badge::start(handle.clone());
+    quiet::start(handle.clone());
+    if headless {
+        headless::listen(handle.clone());
+    } else {
+        automation::start(&handle);
+        capture::start(&handle);
+        palette::start(&handle);
+        menubar::start(handle.clone());
+        widgets::start(handle.clone());
+        menu::create(&handle)?;
+        // Started in the background, the tray icon is the only way to open the app
+        if feature_flags.is_enabled("tray") || args.background {
+            tray::create(&handle)?;
+        }
+    }
+    app.manage(feature_flags);
+
+    // Headless runs start with the defaults instead of the wizard
+    if first_run && !headless {
+        // The wizard launches the backend once the user is done
+        background::leave(&handle);
+        wizard::open(&handle)?;
+    } else {
+        launch(&handle)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(mobile)]
+#[tauri::mobile_entry_point]
+pub fn run() {
+    mobile::run()
+}
+
+#[cfg(desktop)]
+pub fn run() {
+    #[cfg(feature = "test-support")]
+    if let Some(script) = fakesidecar::script_arg() {
+        fakesidecar::run(&script);
+    }
+    crash::install();
+    let context = tauri::generate_context!();
+    paths::set_identifier(&context.config().identifier);
+    // Subcommands and flags for the terminal only start the app when they need the backend
+    if let Some(code) = companion::run(&cli::parse()) {
+        std::process::exit(code);
+    }
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_autostart::init(
+            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+            Some(vec!["--background"]),
+        ))
+        .invoke_handler(tauri::generate_handler![
+            commands::get_log_level,
+            commands::set_log_level,
+            commands::export_settings,
+            commands::import_settings,
+            archive::export_archive,
+            imports::import_spreadsheets,
+            capture::submit_capture,
+            capture::dismiss_capture,
+            palette::search_palette,
+            palette::open_palette_result,
+            palette::dismiss_palette,
+            commands::get_app_info,
+            commands::reveal_path,
+            commands::pick_folder,
+            maintenance::run_maintenance,
+            port::change_port,
+            integrations::get_integrations,
+            integrations::register_integration,
+            integrations::unregister_integration,
+            tray::set_tray_status,
+            progress::notify_progress,
+            confirm::confirm_with_biometrics,
+            webapi::open_external,
+            webapi::save_file,
+            webapi::add_recent_workspace,
+            webapi::notify,
+            webapi::window_control,
+            upload::pick_upload_files,
+            upload::upload_file,
+            folders::get_watched_folders,
+            folders::add_watched_folder,
+            folders::remove_watched_folder,
+            scheduler::get_schedules,
+            scheduler::save_schedule,
+            scheduler::remove_schedule,
+            scheduler::run_schedule_now,
+            scheduler::get_schedule_history,
+            widgets::get_widgets,
+            accounts::get_accounts,
+            accounts::add_account,
+            accounts::remove_account,
+            accounts::switch_account,
+            sso::sso_sign_in,
+            sso::sso_sign_out,
+            widgets::save_widget,
+            widgets::remove_widget,
+            downloads::open_downloads_window,
+            downloads::list_downloads,
+            downloads::start_download,
+            downloads::pause_download,
+            downloads::resume_download,
+            downloads::cancel_download,
+            downloads::open_download,
+            downloads::reveal_download,
+            downloads::clear_downloads,
+            pdf::export_page_to_pdf,
+            diagnostics::get_diagnostics,
+            diagnostics::copy_debug_info,
+            audit::get_audit_log,
+            metrics::get_metrics_history,
+            selftest::run_self_test,
+            service::get_service_status,
+            service::set_backend_service,
+            failure::get_startup_failure,
+            failure::retry_startup,
+            failure::open_log_dir,
+            failure::quit_app,
+            features::get_feature_flags,
+            lan::get_lan_access,
+            lan::set_lan_access,
+            lan::share_lan_access,
+            license::get_license,
+            license::activate_license,
+            license::import_license,
+            license::remove_license,
+            license::get_machine_id,
+            lock::unlock_app,
+            pairing::start_pairing,
+            pairing::get_paired_devices,
+            pairing::unpair_device,
+            pairing::open_pairing_window,
+            passphrase::get_passphrase_prompt,
+            passphrase::submit_passphrase,
+            recovery::get_recovery_reason,
+            recovery::start_safe_mode,
+            recovery::restore_and_restart,
+            relocate::relocate_data_dir,
+            report::get_bug_report_preview,
+            report::submit_bug_report,
+            wizard::get_setup_defaults,
+            wizard::pick_data_dir,
+            wizard::pick_backup_file,
+            wizard::complete_setup,
+        ])
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::CloseRequested { .. } => {
+                tracing::debug!(window = window.label(), "Window close requested")
+            }
+            tauri::WindowEvent::Destroyed => {
+                tracing::debug!(window = window.label(), "Window destroyed")
+            }
+            // Pages get handles for dropped files instead of their paths, see `upload`
+            #[cfg(desktop)]
+            tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) => {
+                upload::dropped(window, paths)
+            }
+            _ => {}
+        })
+        .setup(|app| {
+            // Tauri would panic with a generic message, report the actual error instead
+            if let Err(e) = setup(app) {
+                crash::report(&format!("Failed to start: {}", e));
+                logging::flush();
+                std::process::exit(1);
+            }
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("Error while running tsw_io");
+        .build(context)
+        .expect("Error while building tsw_io")
+        .run(|handle, event| match event {
+            // Closing the last window leaves the app in the tray when it started there, and
+            // switching accounts closes the main window to build it again
+            tauri::RunEvent::ExitRequested { code: None, api, .. }
+                if background::started(handle) || accounts::switching() =>
+            {
+                api.prevent_exit();
+            }
+            tauri::RunEvent::Exit => {
+                on_exit(handle);
+                logging::flush();
+            }
+            #[cfg(target_os = "macos")]
+            tauri::RunEvent::Opened { urls } => automation::opened(handle, urls),
+            _ => {}
+        });
 }
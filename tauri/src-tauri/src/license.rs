@@ -0,0 +1,505 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::config::ConfigState;
+use crate::lock;
+use crate::network;
+use crate::paths::AppPaths;
+use crate::secrets;
+
+const WINDOW_LABEL: &str = "license";
+/// The license as the license server signed it, in the config directory
+const LICENSE_FILE: &str = "license.json";
+/// Tells this machine apart to the license server where the OS has no id for it, made on
+/// first activation
+const MACHINE_FILE: &str = "machine-id";
+/// Hashed with the OS's machine id, so the id sent isn't the one other apps send
+const MACHINE_SALT: &str = "tsw_io-license";
+/// Base64 Ed25519 key licenses are signed with, builds without one don't check licenses
+const PUBLIC_KEY: Option<&str> = option_env!("TSW_IO_LICENSE_PUBLIC_KEY");
+/// License server release builds activate with when the config doesn't name one
+const BUILT_IN_LICENSE_URL: Option<&str> = option_env!("TSW_IO_LICENSE_URL");
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+/// How often the loop looks at the license, it goes online far less often
+const TICK: Duration = Duration::from_secs(60 * 60);
+/// A license not confirmed by the server for this long is checked again
+const REVALIDATE_AFTER: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// How long a license keeps working while the server can't be reached
+const GRACE_PERIOD: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// Sent whenever the entitlement changes, with the new `LicenseStatus`
+const CHANGED_EVENT: &str = "license://changed";
+
+/// Why the last revalidation failed, shown until one works
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+/// The state last sent with `CHANGED_EVENT`
+static LAST_STATE: Mutex<Option<LicenseState>> = Mutex::new(None);
+/// The stored license as last read and verified, `None` until it is read again, see `stored`
+static STORED: Mutex<Option<Stored>> = Mutex::new(None);
+/// This machine's id once worked out, see `machine_id`
+static MACHINE_ID: Mutex<Option<String>> = Mutex::new(None);
+
+/// The stored license, `None` when there is none
+type Stored = Option<Result<License, String>>;
+
+/// What the license server signs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct License {
+    pub id: String,
+    pub licensee: String,
+    /// What the license unlocks, the backend decides what each one means
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// The machine it was activated on, see `MACHINE_FILE`
+    pub machine: String,
+    /// When the server signed it, in seconds since the Unix epoch
+    pub issued_at: u64,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    /// Made for machines that never reach the server, it is never revalidated
+    #[serde(default)]
+    pub offline: bool,
+}
+
+/// A license as the server hands it out and the config directory keeps it
+///
+/// The payload stays as signed, base64 JSON, so nothing has to serialize it the same way again.
+#[derive(Debug, Serialize, Deserialize)]
+struct LicenseFile {
+    payload: String,
+    signature: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LicenseState {
+    /// This build doesn't check licenses, everything is unlocked
+    Unenforced,
+    Unlicensed,
+    Active,
+    /// Not confirmed by the server lately, it works until `grace_until`
+    Grace,
+    Expired,
+}
+
+/// The entitlement the backend pages and the activation window show
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseStatus {
+    pub state: LicenseState,
+    /// Whether the features of the license may be used
+    pub entitled: bool,
+    pub licensee: Option<String>,
+    pub features: Vec<String>,
+    /// Seconds since the Unix epoch
+    pub expires_at: Option<u64>,
+    /// When the server last confirmed the license, in seconds since the Unix epoch
+    pub validated_at: Option<u64>,
+    pub grace_until: Option<u64>,
+    /// Why the license isn't active, or the last revalidation failed
+    pub message: Option<String>,
+}
+
+impl LicenseStatus {
+    fn new(state: LicenseState, message: Option<String>) -> Self {
+        Self {
+            state,
+            entitled: state == LicenseState::Unenforced,
+            licensee: None,
+            features: Vec::new(),
+            expires_at: None,
+            validated_at: None,
+            grace_until: None,
+            message,
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn license_path(handle: &AppHandle) -> PathBuf {
+    handle.state::<AppPaths>().config_dir.join(LICENSE_FILE)
+}
+
+fn endpoint(handle: &AppHandle, path: &str) -> Result<String, String> {
+    let config = handle.state::<ConfigState>().get();
+    if config.offline {
+        return Err("The license server can't be reached in offline mode, use a license file".to_string());
+    }
+    let url = config
+        .license_url
+        .or_else(|| BUILT_IN_LICENSE_URL.map(str::to_string))
+        .ok_or_else(|| "No license server is set up, use a license file".to_string())?;
+    Ok(format!("{}/{}", url.trim_end_matches('/'), path))
+}
+
+/// This machine's id, from the one the OS gives it so a copied config directory doesn't
+/// carry the license along
+///
+/// Hashed, the license server never sees the OS's id. Only an OS without one gets an id made
+/// up and kept in `MACHINE_FILE`.
+fn machine_id(handle: &AppHandle) -> Result<String, String> {
+    let mut cached = MACHINE_ID.lock().unwrap();
+    if let Some(id) = cached.as_ref() {
+        return Ok(id.clone());
+    }
+    let id = match os_machine_id() {
+        Some(os_id) => {
+            let digest = Sha256::digest(format!("{}:{}", MACHINE_SALT, os_id).as_bytes());
+            digest[..16].iter().map(|byte| format!("{:02x}", byte)).collect()
+        }
+        None => {
+            tracing::warn!("The OS has no id for this machine, making one up");
+            made_up_machine_id(handle)?
+        }
+    };
+    *cached = Some(id.clone());
+    Ok(id)
+}
+
+#[cfg(target_os = "macos")]
+fn os_machine_id() -> Option<String> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .ok()?;
+    // `  "IOPlatformUUID" = "564D…"`
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("\"IOPlatformUUID\""))
+        .and_then(|line| line.rsplit('"').nth(1))
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(windows)]
+fn os_machine_id() -> Option<String> {
+    use winreg::enums::{HKEY_LOCAL_MACHINE, KEY_READ, KEY_WOW64_64KEY};
+    // The 64-bit view, a 32-bit build would otherwise read a key that isn't there
+    winreg::RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey_with_flags(r"SOFTWARE\Microsoft\Cryptography", KEY_READ | KEY_WOW64_64KEY)
+        .and_then(|key| key.get_value::<String, _>("MachineGuid"))
+        .ok()
+        .filter(|id| !id.is_empty())
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn os_machine_id() -> Option<String> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"].iter().find_map(|path| {
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+    })
+}
+
+/// The id kept in `MACHINE_FILE`, made the first time it is asked for
+fn made_up_machine_id(handle: &AppHandle) -> Result<String, String> {
+    let path = handle.state::<AppPaths>().config_dir.join(MACHINE_FILE);
+    if let Ok(id) = std::fs::read_to_string(&path) {
+        if !id.trim().is_empty() {
+            return Ok(id.trim().to_string());
+        }
+    }
+    let id = secrets::random_hex(16)?;
+    std::fs::write(&path, &id).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(id)
+}
+
+/// The license in `file` if the server signed it, for this machine
+fn verify(handle: &AppHandle, file: &LicenseFile) -> Result<License, String> {
+    let key = PUBLIC_KEY.ok_or_else(|| "This build doesn't check licenses".to_string())?;
+    let key = STANDARD
+        .decode(key)
+        .map_err(|e| format!("Invalid license key in this build: {}", e))?;
+    let payload = STANDARD
+        .decode(&file.payload)
+        .map_err(|e| format!("Invalid license: {}", e))?;
+    let signature = STANDARD
+        .decode(&file.signature)
+        .map_err(|e| format!("Invalid license signature: {}", e))?;
+    UnparsedPublicKey::new(&ED25519, key)
+        .verify(&payload, &signature)
+        .map_err(|_| "The license isn't signed by the license server".to_string())?;
+    let license: License =
+        serde_json::from_slice(&payload).map_err(|e| format!("Invalid license: {}", e))?;
+    if license.machine != machine_id(handle)? {
+        return Err("The license was activated on another machine".to_string());
+    }
+    Ok(license)
+}
+
+fn read(path: &Path) -> Result<LicenseFile, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Invalid license file {}: {}", path.display(), e))
+}
+
+/// The stored license, read and verified once until `forget_stored` after it changed
+fn stored(handle: &AppHandle) -> Stored {
+    let mut cached = STORED.lock().unwrap();
+    cached
+        .get_or_insert_with(|| {
+            let path = license_path(handle);
+            path.exists().then(|| read(&path).and_then(|file| verify(handle, &file)))
+        })
+        .clone()
+}
+
+fn forget_stored() {
+    *STORED.lock().unwrap() = None;
+}
+
+/// Verify `file` and keep it in the config directory, replacing the license there
+fn install(handle: &AppHandle, file: &LicenseFile) -> Result<License, String> {
+    let license = verify(handle, file)?;
+    let path = license_path(handle);
+    let contents = serde_json::to_string_pretty(file).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    forget_stored();
+    *LAST_ERROR.lock().unwrap() = None;
+    Ok(license)
+}
+
+/// The entitlement as of now
+pub fn status(handle: &AppHandle) -> LicenseStatus {
+    if PUBLIC_KEY.is_none() {
+        return LicenseStatus::new(LicenseState::Unenforced, None);
+    }
+    let license = match stored(handle) {
+        None => return LicenseStatus::new(LicenseState::Unlicensed, None),
+        Some(Err(e)) => return LicenseStatus::new(LicenseState::Unlicensed, Some(e)),
+        Some(Ok(license)) => license,
+    };
+    let now = now();
+    let grace_until = (!license.offline)
+        .then(|| license.issued_at + REVALIDATE_AFTER.as_secs() + GRACE_PERIOD.as_secs());
+    let last_error = LAST_ERROR.lock().unwrap().clone();
+    let (state, message) = if license.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        (LicenseState::Expired, Some("The license has expired".to_string()))
+    } else if grace_until.is_some_and(|grace_until| grace_until <= now) {
+        let message = "The license server couldn't confirm the license for too long".to_string();
+        (LicenseState::Expired, Some(last_error.unwrap_or(message)))
+    } else if !license.offline && now >= license.issued_at + REVALIDATE_AFTER.as_secs() {
+        (LicenseState::Grace, last_error)
+    } else {
+        (LicenseState::Active, None)
+    };
+    LicenseStatus {
+        state,
+        entitled: matches!(state, LicenseState::Active | LicenseState::Grace),
+        licensee: Some(license.licensee),
+        features: license.features,
+        expires_at: license.expires_at,
+        validated_at: Some(license.issued_at),
+        grace_until,
+        message,
+    }
+}
+
+/// Tell the backend pages and the activation window when the entitlement changed
+fn announce(handle: &AppHandle) {
+    let status = status(handle);
+    let mut last = LAST_STATE.lock().unwrap();
+    if *last == Some(status.state) {
+        return;
+    }
+    if last.is_some() {
+        tracing::info!(state = ?status.state, "License changed");
+    }
+    *last = Some(status.state);
+    drop(last);
+    if let Err(e) = handle.emit(CHANGED_EVENT, status) {
+        tracing::warn!("Failed to emit {}: {}", CHANGED_EVENT, e);
+    }
+}
+
+/// Why the license server gave no license
+enum RequestError {
+    /// It answered, the license isn't valid (any more)
+    Rejected(String),
+    /// It couldn't be asked, or answered something else
+    Failed(String),
+}
+
+impl From<RequestError> for String {
+    fn from(error: RequestError) -> Self {
+        match error {
+            RequestError::Rejected(e) | RequestError::Failed(e) => e,
+        }
+    }
+}
+
+/// Ask the license server for a license, at `path` with `body`
+fn request(handle: &AppHandle, path: &str, body: serde_json::Value) -> Result<LicenseFile, RequestError> {
+    let url = endpoint(handle, path).map_err(RequestError::Failed)?;
+    let response = network::client_builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .and_then(|client| client.post(&url).json(&body).send())
+        .map_err(|e| RequestError::Failed(format!("Failed to reach the license server: {}", e)))?;
+    let status = response.status();
+    if status.is_client_error() {
+        let reason = response
+            .json::<serde_json::Value>()
+            .ok()
+            .and_then(|body| body["error"].as_str().map(str::to_string))
+            .unwrap_or_else(|| status.to_string());
+        return Err(RequestError::Rejected(format!(
+            "The license server turned the license down: {}",
+            reason
+        )));
+    }
+    if !status.is_success() {
+        return Err(RequestError::Failed(format!("The license server failed: {}", status)));
+    }
+    response
+        .json()
+        .map_err(|e| RequestError::Failed(format!("Invalid response from the license server: {}", e)))
+}
+
+/// Trade a license key for a license signed for this machine
+fn activate(handle: &AppHandle, key: &str) -> Result<License, String> {
+    if key.is_empty() {
+        return Err("Enter the license key".to_string());
+    }
+    let body = serde_json::json!({
+        "key": key,
+        "machine": machine_id(handle)?,
+        "version": env!("CARGO_PKG_VERSION"),
+    });
+    let license = install(handle, &request(handle, "activate", body)?)?;
+    tracing::info!(license = license.id, "Activated license");
+    Ok(license)
+}
+
+/// Have the server confirm the license, and sign it again for another while
+///
+/// A license the server turns down is removed, one the server can't be asked about keeps
+/// working until its grace period is over.
+fn revalidate(handle: &AppHandle, license: &License) -> Result<(), String> {
+    let body = serde_json::json!({
+        "id": license.id,
+        "machine": license.machine,
+        "version": env!("CARGO_PKG_VERSION"),
+    });
+    match request(handle, "validate", body) {
+        Ok(file) => {
+            install(handle, &file)?;
+            tracing::info!(license = license.id, "Revalidated license");
+            Ok(())
+        }
+        Err(RequestError::Rejected(e)) => {
+            let path = license_path(handle);
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!("Failed to remove {}: {}", path.display(), e);
+            }
+            forget_stored();
+            Err(e)
+        }
+        Err(RequestError::Failed(e)) => Err(e),
+    }
+}
+
+/// Revalidate the license once it is due, and announce changes, see `REVALIDATE_AFTER`
+pub fn start(handle: AppHandle) {
+    if PUBLIC_KEY.is_none() {
+        return;
+    }
+    std::thread::spawn(move || loop {
+        if let Some(Ok(license)) = stored(&handle) {
+            let due = license.issued_at + REVALIDATE_AFTER.as_secs() <= now();
+            if due && !license.offline {
+                let result = revalidate(&handle, &license);
+                if let Err(e) = &result {
+                    tracing::warn!("Failed to revalidate the license: {}", e);
+                }
+                *LAST_ERROR.lock().unwrap() = result.err();
+            }
+        }
+        announce(&handle);
+        std::thread::sleep(TICK);
+    });
+}
+
+/// Show the activation window, or focus it if it is already open
+pub fn open(handle: &AppHandle) -> tauri::Result<()> {
+    if !lock::may_show(handle) {
+        return Ok(());
+    }
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        return window.set_focus();
+    }
+    WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("license.html".into()))
+        .title("TSW IO License")
+        .inner_size(480.0, 420.0)
+        .build()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_license(app: AppHandle) -> LicenseStatus {
+    status(&app)
+}
+
+#[tauri::command]
+pub async fn activate_license(app: AppHandle, key: String) -> Result<LicenseStatus, String> {
+    let key = key.trim().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        activate(&app, &key)?;
+        announce(&app);
+        Ok(status(&app))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Install a license file the license server made for a machine that can't reach it,
+/// `None` when the user cancelled
+#[tauri::command]
+pub async fn import_license(app: AppHandle) -> Result<Option<LicenseStatus>, String> {
+    let Some(path) = app
+        .dialog()
+        .file()
+        .set_title("Choose a license file")
+        .add_filter("TSW IO license", &["json"])
+        .blocking_pick_file()
+        .and_then(|path| path.into_path().ok())
+    else {
+        return Ok(None);
+    };
+    let license = install(&app, &read(&path)?)?;
+    tracing::info!(license = license.id, "Imported license from {}", path.display());
+    announce(&app);
+    Ok(Some(status(&app)))
+}
+
+#[tauri::command]
+pub fn remove_license(app: AppHandle) -> Result<(), String> {
+    let path = license_path(&app);
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    }
+    forget_stored();
+    *LAST_ERROR.lock().unwrap() = None;
+    tracing::info!("Removed the license");
+    announce(&app);
+    Ok(())
+}
+
+/// The id the license server needs to make a license file for this machine
+#[tauri::command]
+pub fn get_machine_id(app: AppHandle) -> Result<String, String> {
+    machine_id(&app)
+}
@@ -0,0 +1,105 @@
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::TcpStream;
+
+/// Headers past this many are ignored, nothing local sends more
+const MAX_HEADERS: usize = 64;
+
+/// A request to one of the shell's own loopback servers
+pub struct Request {
+    pub method: String,
+    /// Path and query, as sent
+    pub target: String,
+    headers: Vec<(String, String)>,
+    /// Empty unless read with `read_request_with_body`
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Path without the query
+    pub fn path(&self) -> &str {
+        self.target.split('?').next().unwrap_or_default()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Read a request up to its body, which most of these servers don't need
+pub fn read_request(stream: &TcpStream) -> std::io::Result<Request> {
+    read(stream, None)
+}
+
+/// Read a whole request, failing with `InvalidData` when its body is longer than `max_body`
+pub fn read_request_with_body(stream: &TcpStream, max_body: usize) -> std::io::Result<Request> {
+    read(stream, Some(max_body))
+}
+
+fn read(stream: &TcpStream, max_body: Option<usize>) -> std::io::Result<Request> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        if headers.len() < MAX_HEADERS {
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        line.clear();
+    }
+    let mut request = Request {
+        method,
+        target,
+        headers,
+        body: Vec::new(),
+    };
+    if let Some(max_body) = max_body {
+        let length = request
+            .header("content-length")
+            .map(|length| length.parse::<usize>())
+            .transpose()
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?
+            .unwrap_or(0);
+        if length > max_body {
+            return Err(std::io::Error::new(ErrorKind::InvalidData, "body too long"));
+        }
+        request.body = vec![0; length];
+        reader.read_exact(&mut request.body)?;
+    }
+    Ok(request)
+}
+
+/// Write a whole HTTP response and let the connection close
+pub fn respond(mut stream: TcpStream, status: u16, content_type: &str, body: &str) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        413 => "Content Too Large",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        _ => "Service Unavailable",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,197 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+use crate::config::ConfigState;
+
+const WINDOW_LABEL: &str = "lock";
+/// Shown by the OS in the authentication prompt
+const REASON: &str = "unlock TSW IO";
+const WAKE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Wall clock time passing this much faster than the monotonic clock means the machine slept
+const WAKE_GAP: Duration = Duration::from_secs(30);
+/// Windows that show nothing of the user's data, left alone while locked
+const UNGUARDED_WINDOWS: &[&str] = &[WINDOW_LABEL, "splash", "failure", "recovery", "setup", "passphrase"];
+
+/// Windows hidden by the lock, shown again once unlocked
+static HIDDEN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Whether the main window is kept hidden until the user authenticates
+pub struct AppLock(AtomicBool);
+
+impl AppLock {
+    /// Start locked when the app lock is on and the platform can authenticate
+    pub fn new(enabled: bool) -> Self {
+        if enabled && !SUPPORTED {
+            tracing::warn!("App lock is not supported on this platform");
+        }
+        AppLock(AtomicBool::new(enabled && SUPPORTED))
+    }
+}
+
+pub fn is_locked(handle: &AppHandle) -> bool {
+    handle.state::<AppLock>().0.load(Ordering::SeqCst)
+}
+
+/// Hide the main window until the user authenticates again
+pub fn lock(handle: &AppHandle) {
+    if !SUPPORTED {
+        return;
+    }
+    if !handle.state::<AppLock>().0.swap(true, Ordering::SeqCst) {
+        tracing::info!("App locked");
+    }
+    let mut hidden = HIDDEN.lock().unwrap();
+    for (label, window) in handle.webview_windows() {
+        if UNGUARDED_WINDOWS.contains(&label.as_str()) || !window.is_visible().unwrap_or(false) {
+            continue;
+        }
+        let _ = window.hide();
+        if !hidden.contains(&label) {
+            hidden.push(label);
+        }
+    }
+    drop(hidden);
+    show_lock_window(handle);
+}
+
+/// Whether a window may be shown or opened now, bringing up the lock screen instead if not
+///
+/// Everything that shows a window goes through here or `reveal`, the hotkeys, the tray and
+/// the menu bar included.
+pub fn may_show(handle: &AppHandle) -> bool {
+    if is_locked(handle) {
+        show_lock_window(handle);
+        return false;
+    }
+    true
+}
+
+/// Show and focus `window`, or the lock screen while locked
+pub fn reveal(handle: &AppHandle, window: &WebviewWindow) {
+    if may_show(handle) {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn show_lock_window(handle: &AppHandle) {
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        let _ = window.set_focus();
+        return;
+    }
+    let result = WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("lock.html".into()))
+        .title("TSW IO")
+        .inner_size(360.0, 280.0)
+        .resizable(false)
+        .center()
+        .build();
+    if let Err(e) = result {
+        tracing::error!("Failed to open lock screen: {}", e);
+    }
+}
+
+/// Ask the OS to authenticate the user and show the main window if they are who they claim
+#[tauri::command]
+pub async fn unlock_app(app: AppHandle) -> Result<bool, String> {
+    let authenticated = tauri::async_runtime::spawn_blocking(|| authenticate(REASON))
+        .await
+        .map_err(|e| e.to_string())??;
+    if !authenticated {
+        tracing::info!("Unlock cancelled or failed");
+        return Ok(false);
+    }
+
+    app.state::<AppLock>().0.store(false, Ordering::SeqCst);
+    tracing::info!("App unlocked");
+    let hidden = std::mem::take(&mut *HIDDEN.lock().unwrap());
+    for window in hidden.iter().filter_map(|label| app.get_webview_window(label)) {
+        let _ = window.show();
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+    Ok(true)
+}
+
+static WAKE_WATCH_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Lock again whenever the machine wakes from sleep
+pub fn watch_wake(handle: AppHandle) {
+    if WAKE_WATCH_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        let mut last_wall = SystemTime::now();
+        let mut last_tick = Instant::now();
+        loop {
+            std::thread::sleep(WAKE_CHECK_INTERVAL);
+            // The monotonic clock stops while asleep on most platforms, the wall clock doesn't
+            let wall = SystemTime::now()
+                .duration_since(last_wall)
+                .unwrap_or_default();
+            let slept = wall.saturating_sub(last_tick.elapsed());
+            last_wall = SystemTime::now();
+            last_tick = Instant::now();
+
+            if slept >= WAKE_GAP && handle.state::<ConfigState>().get().app_lock {
+                tracing::debug!(slept_secs = slept.as_secs(), "Woke from sleep");
+                lock(&handle);
+            }
+        }
+    });
+}
+
+#[cfg(any(target_os = "macos", windows))]
+const SUPPORTED: bool = true;
+#[cfg(not(any(target_os = "macos", windows)))]
+const SUPPORTED: bool = false;
+
+/// Touch ID or the account password
+#[cfg(target_os = "macos")]
+pub(crate) fn authenticate(reason: &str) -> Result<bool, String> {
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSError, NSString};
+    use objc2_local_authentication::{LAContext, LAPolicy};
+
+    let context = unsafe { LAContext::new() };
+    let reason = NSString::from_str(reason);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let reply = RcBlock::new(move |success: Bool, _error: *mut NSError| {
+        let _ = sender.send(success.as_bool());
+    });
+    unsafe {
+        context.evaluatePolicy_localizedReason_reply(
+            LAPolicy::DeviceOwnerAuthentication,
+            &reason,
+            &reply,
+        )
+    };
+    receiver
+        .recv()
+        .map_err(|e| format!("Failed to authenticate: {}", e))
+}
+
+/// Windows Hello, falling back to the account PIN or password
+#[cfg(windows)]
+pub(crate) fn authenticate(reason: &str) -> Result<bool, String> {
+    use windows::core::HSTRING;
+    use windows::Security::Credentials::UI::{UserConsentVerificationResult, UserConsentVerifier};
+
+    let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+        .and_then(|operation| operation.join())
+        .map_err(|e| format!("Failed to authenticate: {}", e))?;
+    Ok(result == UserConsentVerificationResult::Verified)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+pub(crate) fn authenticate(_reason: &str) -> Result<bool, String> {
+    Err("App lock is not supported on this platform".to_string())
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 4] = [
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+    ];
+
+    pub fn from_name(name: &str) -> Option<LogLevel> {
+        LogLevel::ALL.into_iter().find(|level| level.as_str() == name)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+        }
+    }
+
+    /// Name of the matching Elixir Logger level
+    pub fn backend_name(self) -> &'static str {
+        match self {
+            LogLevel::Warn => "warning",
+            level => level.as_str(),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LogLevel::Error => "Error",
+            LogLevel::Warn => "Warning",
+            LogLevel::Info => "Info",
+            LogLevel::Debug => "Debug",
+        }
+    }
+}
+
+/// Environment variable with a full `tracing` filter, e.g. `tsw_io_lib=trace,reqwest=debug`
+const FILTER_ENV: &str = "TSW_IO_LOG";
+const LOG_FILE_PREFIX: &str = "shell";
+const MAX_LOG_FILES: usize = 7;
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+// Dropping the guard writes out what is buffered and stops the background writer, see `flush`
+static FILE_GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
+
+static LAST_ERROR: RwLock<Option<String>> = RwLock::new(None);
+
+/// Remembers the most recent error event for debug reports
+struct LastErrorLayer;
+
+impl<S: Subscriber> Layer<S> for LastErrorLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if *event.metadata().level() != Level::ERROR {
+            return;
+        }
+        let mut visitor = MessageVisitor(None);
+        event.record(&mut visitor);
+        if let (Some(message), Ok(mut last)) = (visitor.0, LAST_ERROR.write()) {
+            *last = Some(message);
+        }
+    }
+}
+
+struct MessageVisitor(Option<String>);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+/// Write out the log lines still buffered for the file, call it right before the process exits
+///
+/// `process::exit` doesn't run destructors, so without it the last lines, the ones that tell
+/// why, would be lost. Later events no longer reach the file.
+pub fn flush() {
+    let guard = FILE_GUARD.lock().unwrap_or_else(|e| e.into_inner()).take();
+    drop(guard);
+}
+
+/// Message of the last error logged by the shell
+pub fn last_error() -> Option<String> {
+    LAST_ERROR.read().ok().and_then(|last| last.clone())
+}
+
+/// Filter for `level` on the shell's own events, keeping dependencies at warn
+fn filter_for(level: LogLevel) -> EnvFilter {
+    EnvFilter::new(format!("warn,tsw_io_lib={}", level.as_str()))
+}
+
+/// Log to stdout and to a daily rotated file in `log_dir`
+pub fn init(log_dir: &Path, level: LogLevel) {
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+
+    // An explicit filter in the environment wins over the config until changed at runtime
+    let filter = std::env::var(FILTER_ENV)
+        .ok()
+        .and_then(|directives| EnvFilter::try_new(directives).ok())
+        .unwrap_or_else(|| filter_for(level));
+    let (filter, filter_handle) = reload::Layer::new(filter);
+
+    let file_appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir);
+    let file_layer = match file_appender {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            *FILE_GUARD.lock().unwrap_or_else(|e| e.into_inner()) = Some(guard);
+            Some(fmt::layer().with_ansi(false).with_writer(writer))
+        }
+        Err(e) => {
+            eprintln!("Failed to open log file in {}: {}", log_dir.display(), e);
+            None
+        }
+    };
+
+    let _ = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(file_layer)
+        .with(LastErrorLayer)
+        .try_init();
+    let _ = FILTER_HANDLE.set(filter_handle);
+}
+
+pub fn level() -> LogLevel {
+    let value = MAX_LEVEL.load(Ordering::Relaxed);
+    LogLevel::ALL
+        .into_iter()
+        .find(|level| *level as u8 == value)
+        .unwrap_or(LogLevel::Info)
+}
+
+pub fn set_level(level: LogLevel) {
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+    if let Some(handle) = FILTER_HANDLE.get() {
+        if let Err(e) = handle.reload(filter_for(level)) {
+            tracing::warn!("Failed to change log filter: {}", e);
+        }
+    }
+}
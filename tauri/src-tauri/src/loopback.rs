@@ -0,0 +1,57 @@
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::time::Duration;
+use sysinfo::Networks;
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+/// Address the bundled backend is told to listen on
+pub const BIND_ADDRESS: &str = "127.0.0.1";
+/// Address it listens on with LAN access, where other machines need a paired device token
+pub const LAN_BIND_ADDRESS: &str = "0.0.0.0";
+const CONNECT_TIMEOUT_MS: u64 = 300;
+
+/// Addresses of this machine's non-loopback interfaces on which `port` accepts connections
+pub fn exposed_addresses(port: u16) -> Vec<IpAddr> {
+    Networks::new_with_refreshed_list()
+        .values()
+        .flat_map(|network| network.ip_networks().iter().map(|ip| ip.addr))
+        .filter(|addr| !addr.is_loopback() && !addr.is_unspecified())
+        .filter(|addr| {
+            TcpStream::connect_timeout(
+                &SocketAddr::new(*addr, port),
+                Duration::from_millis(CONNECT_TIMEOUT_MS),
+            )
+            .is_ok()
+        })
+        .collect()
+}
+
+/// Warn if the backend can be reached from other machines instead of only this one
+pub fn verify(handle: &AppHandle, port: u16) {
+    let exposed = exposed_addresses(port);
+    if exposed.is_empty() {
+        tracing::debug!(port, "Backend only reachable on loopback");
+        return;
+    }
+
+    let addresses = exposed
+        .iter()
+        .map(|addr| SocketAddr::new(*addr, port).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    tracing::error!(port, %addresses, "Backend is reachable from the network, it should only listen on {}", BIND_ADDRESS);
+    if crate::headless::enabled() {
+        return;
+    }
+    handle
+        .dialog()
+        .message(format!(
+            "The TSW IO server can be reached from other computers on your network at {}. \
+             It should only be reachable from this computer. Check that nothing else is \
+             listening on port {}, or pick another port in the settings.",
+            addresses, port
+        ))
+        .title("TSW IO")
+        .kind(MessageDialogKind::Warning)
+        .show(|_| {});
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::api;
+use crate::audit::{self, AuditAction};
+use crate::downloads::{self, DownloadKind};
+use crate::profile::ProfileState;
+
+/// Tauri event the maintenance progress is emitted as
+const PROGRESS_EVENT: &str = "maintenance://progress";
+
+/// What is currently done to the backend's data, only one thing at a time
+static BUSY: Mutex<Option<&'static str>> = Mutex::new(None);
+
+/// Housekeeping the backend can do on request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceTask {
+    /// Compact the database file
+    Vacuum,
+    /// Rebuild the database indexes
+    Reindex,
+    /// Remove downloaded firmware files
+    ClearCache,
+}
+
+impl MaintenanceTask {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MaintenanceTask::Vacuum => "vacuum",
+            MaintenanceTask::Reindex => "reindex",
+            MaintenanceTask::ClearCache => "clear_cache",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            MaintenanceTask::Vacuum => "Compacting the database",
+            MaintenanceTask::Reindex => "Rebuilding the database indexes",
+            MaintenanceTask::ClearCache => "Clearing the firmware cache",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceStatus {
+    Started,
+    Finished,
+    Failed,
+}
+
+/// Payload of `maintenance://progress`
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceProgress {
+    pub task: MaintenanceTask,
+    pub status: MaintenanceStatus,
+    pub message: String,
+}
+
+/// Held while something works on the backend's data, released when dropped
+pub struct Exclusive;
+
+impl Drop for Exclusive {
+    fn drop(&mut self) {
+        *BUSY.lock().unwrap() = None;
+    }
+}
+
+/// Claim the backend's data for `operation`, failing while something else works on it
+///
+/// Maintenance tasks, backups and moving the data directory must not overlap.
+pub fn exclusive(operation: &'static str) -> Result<Exclusive, String> {
+    let mut busy = BUSY.lock().unwrap();
+    if let Some(running) = *busy {
+        return Err(format!("{} is still in progress, try again once it's done", running));
+    }
+    *busy = Some(operation);
+    Ok(Exclusive)
+}
+
+fn emit(handle: &AppHandle, task: MaintenanceTask, status: MaintenanceStatus, message: String) {
+    let progress = MaintenanceProgress {
+        task,
+        status,
+        message,
+    };
+    if let Err(e) = handle.emit(PROGRESS_EVENT, progress) {
+        tracing::warn!("Failed to emit {}: {}", PROGRESS_EVENT, e);
+    }
+}
+
+/// Have the backend run a maintenance task, reporting progress on `maintenance://progress`
+#[tauri::command]
+pub async fn run_maintenance(app: AppHandle, task: MaintenanceTask) -> Result<String, String> {
+    let _exclusive = exclusive(task.description())?;
+    tracing::info!(task = task.as_str(), "Maintenance started");
+    emit(&app, task, MaintenanceStatus::Started, format!("{}...", task.description()));
+
+    let base_url = app.state::<ProfileState>().get().base_url();
+    // The blocking client can't run on an async runtime thread
+    let result = tauri::async_runtime::spawn_blocking(move || api::run_maintenance(&base_url, task))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match result {
+        Ok(message) => {
+            tracing::info!(task = task.as_str(), "{}", message);
+            audit::record(&app, AuditAction::Maintenance, message.clone());
+            emit(&app, task, MaintenanceStatus::Finished, message.clone());
+            Ok(message)
+        }
+        Err(e) => {
+            tracing::error!(task = task.as_str(), "Maintenance failed: {}", e);
+            emit(&app, task, MaintenanceStatus::Failed, e.clone());
+            Err(e)
+        }
+    }
+}
+
+/// Have the backend back up its database, to the downloads directory unless `path` is given
+///
+/// Blocks until the copy is written, returning where it went.
+pub fn backup(handle: &AppHandle, path: Option<PathBuf>) -> Result<PathBuf, String> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let dir = handle
+                .path()
+                .download_dir()
+                .map_err(|e| format!("Failed to find the downloads directory: {}", e))?;
+            let stamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default();
+            dir.join(format!("tsw_io-backup-{}.db", stamp))
+        }
+    };
+    let _exclusive = exclusive("A backup")?;
+    let base_url = handle.state::<ProfileState>().get().base_url();
+    let message = api::backup(&base_url, &path)?;
+    tracing::info!("{}", message);
+    audit::record(handle, AuditAction::Backup, message);
+    downloads::record(handle, DownloadKind::Backup, &path);
+    Ok(path)
+}
@@ -0,0 +1,166 @@
+/// DNS-SD service type the instances are advertised under
+const SERVICE_TYPE: &str = "_tsw-io._tcp.local.";
+
+#[cfg(desktop)]
+pub use responder::{advertise, stop};
+
+/// Answers queries for this instance while LAN access is on
+#[cfg(desktop)]
+mod responder {
+    use mdns_sd::{IfKind, ServiceDaemon, ServiceInfo};
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use super::SERVICE_TYPE;
+
+    /// How long stopping waits for browsers to be told the instance is gone
+    const GOODBYE_TIMEOUT: Duration = Duration::from_secs(1);
+
+    static ADVERTISER: Mutex<Option<Advertiser>> = Mutex::new(None);
+
+    struct Advertiser {
+        port: u16,
+        address: Ipv4Addr,
+        daemon: ServiceDaemon,
+        fullname: String,
+    }
+
+    /// Instance label, like `TSW IO on workstation`, and the host name it is found at
+    fn names() -> (String, String) {
+        let hostname = gethostname::gethostname().to_string_lossy().into_owned();
+        let hostname = hostname.split('.').next().unwrap_or_default();
+        let label: String = hostname
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+            .collect();
+        let instance = if hostname.is_empty() {
+            "TSW IO".to_string()
+        } else {
+            format!("TSW IO on {}", hostname)
+        };
+        // Its own host name, the machine's may already be announced with other addresses
+        (instance, format!("tsw-io-{}.local.", label.trim_matches('-')))
+    }
+
+    fn start(port: u16, address: Ipv4Addr) -> Result<Advertiser, mdns_sd::Error> {
+        let (instance, host) = names();
+        let properties = [
+            ("txtvers", "1"),
+            ("name", instance.as_str()),
+            ("version", env!("CARGO_PKG_VERSION")),
+            ("backend", env!("TSW_IO_BACKEND_VERSION")),
+        ];
+        let service = ServiceInfo::new(SERVICE_TYPE, &instance, &host, IpAddr::V4(address), port, &properties[..])?;
+        let daemon = ServiceDaemon::new()?;
+        // Only on the network the backend listens on, not every one the machine is on
+        daemon.disable_interface(IfKind::All)?;
+        daemon.enable_interface(IfKind::Addr(IpAddr::V4(address)))?;
+        let fullname = service.get_fullname().to_string();
+        daemon.register(service)?;
+        tracing::info!(%instance, %address, port, "Advertising over mDNS");
+        Ok(Advertiser {
+            port,
+            address,
+            daemon,
+            fullname,
+        })
+    }
+
+    /// Advertise the backend at `address`:`port` as `_tsw-io._tcp`, with the app and backend
+    /// versions in the TXT record
+    ///
+    /// Replaces an advertisement for another address, does nothing when it is unchanged.
+    pub fn advertise(port: u16, address: Ipv4Addr) {
+        let mut advertiser = ADVERTISER.lock().unwrap();
+        if advertiser
+            .as_ref()
+            .is_some_and(|running| running.port == port && running.address == address)
+        {
+            return;
+        }
+        if let Some(previous) = advertiser.take() {
+            previous.stop();
+        }
+        match start(port, address) {
+            Ok(started) => *advertiser = Some(started),
+            Err(e) => tracing::warn!("Failed to advertise over mDNS: {}", e),
+        }
+    }
+
+    impl Advertiser {
+        fn stop(self) {
+            // Lets browsers drop the instance right away instead of when the records expire
+            if let Ok(unregistered) = self.daemon.unregister(&self.fullname) {
+                let _ = unregistered.recv_timeout(GOODBYE_TIMEOUT);
+            }
+            if let Ok(stopped) = self.daemon.shutdown() {
+                let _ = stopped.recv_timeout(GOODBYE_TIMEOUT);
+            }
+        }
+    }
+
+    /// Stop advertising, telling browsers the instance is gone
+    pub fn stop() {
+        if let Some(advertiser) = ADVERTISER.lock().unwrap().take() {
+            tracing::info!("Stopped advertising over mDNS");
+            advertiser.stop();
+        }
+    }
+}
+
+#[cfg(mobile)]
+pub use browser::{browse, Instance};
+
+/// Finds the desktop instances advertised on the network
+#[cfg(mobile)]
+mod browser {
+    use mdns_sd::{ServiceDaemon, ServiceEvent};
+    use serde::Serialize;
+    use std::time::{Duration, Instant};
+
+    use super::SERVICE_TYPE;
+
+    /// A desktop instance that answered
+    #[derive(Debug, Clone, Serialize)]
+    pub struct Instance {
+        pub name: String,
+        pub url: String,
+        pub version: Option<String>,
+    }
+
+    /// Ask the network for `_tsw-io._tcp` instances and collect answers for `timeout`
+    ///
+    /// iOS only sends the query for apps with the multicast networking entitlement.
+    pub fn browse(timeout: Duration) -> Result<Vec<Instance>, String> {
+        let failed = |e: mdns_sd::Error| format!("Failed to look for servers: {}", e);
+        let daemon = ServiceDaemon::new().map_err(failed)?;
+        let events = daemon.browse(SERVICE_TYPE).map_err(failed)?;
+
+        let mut instances: Vec<Instance> = Vec::new();
+        let deadline = Instant::now() + timeout;
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            let service = match events.recv_timeout(left) {
+                Ok(ServiceEvent::ServiceResolved(service)) => service,
+                Ok(_) => continue,
+                Err(_) => break,
+            };
+            let Some(address) = service.get_addresses_v4().into_iter().next() else {
+                continue;
+            };
+            let url = format!("http://{}:{}", address, service.get_port());
+            if instances.iter().any(|known| known.url == url) {
+                continue;
+            }
+            let txt = |field: &str| service.txt_properties.get_property_val_str(field).map(str::to_string);
+            let label = service.get_fullname().split('.').next().unwrap_or_default();
+            instances.push(Instance {
+                name: txt("name").unwrap_or_else(|| label.to_string()),
+                url,
+                version: txt("version"),
+            });
+        }
+        let _ = daemon.shutdown();
+        Ok(instances)
+    }
+}
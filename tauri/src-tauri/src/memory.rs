@@ -0,0 +1,104 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::audit::{self, AuditAction};
+use crate::config::ConfigState;
+use crate::idle;
+use crate::monitor;
+use crate::profile::ProfileState;
+use crate::restart;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// No keyboard or mouse input for this long counts as a good moment to restart
+const IDLE_FOR: Duration = Duration::from_secs(2 * 60);
+/// Past this multiple of the ceiling the backend restarts without waiting for the user
+const HARD_LIMIT_FACTOR: u64 = 2;
+
+const MIB: u64 = 1024 * 1024;
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Restart the bundled backend once its memory grows past the configured ceiling
+///
+/// The restart waits until the user is away, unless the backend keeps growing to twice
+/// the ceiling.
+pub fn start(handle: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        // Warn once per backend that goes over, not on every check
+        let mut warned_pid = None;
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            // Re-read every tick so the ceiling can be changed live
+            let Some(limit_mb) = handle.state::<ConfigState>().get().memory_limit_mb else {
+                continue;
+            };
+            if handle.state::<ProfileState>().get().backend_url.is_some() {
+                continue;
+            }
+            let Some(pid) = crate::backend_pid(&handle) else {
+                continue;
+            };
+            let Some(usage) = monitor::process_usage(&handle, pid) else {
+                continue;
+            };
+
+            let limit = limit_mb.saturating_mul(MIB);
+            if usage.memory_bytes <= limit {
+                continue;
+            }
+            let used_mb = usage.memory_bytes / MIB;
+            if warned_pid != Some(pid) {
+                warned_pid = Some(pid);
+                tracing::warn!(used_mb, limit_mb, "Backend is over its memory ceiling");
+                notify(&handle, used_mb);
+            }
+
+            let hard_limit = usage.memory_bytes > limit.saturating_mul(HARD_LIMIT_FACTOR);
+            if hard_limit || user_away(&handle) {
+                restart(&handle, used_mb, limit_mb);
+            }
+        }
+    });
+}
+
+/// Whether restarting now would go unnoticed
+fn user_away(handle: &AppHandle) -> bool {
+    match idle::idle_time() {
+        Some(idle) => idle >= IDLE_FOR,
+        // Without system idle time, wait until the app is in the background
+        None => handle
+            .get_webview_window("main")
+            .is_none_or(|window| !window.is_focused().unwrap_or(false)),
+    }
+}
+
+fn notify(handle: &AppHandle, used_mb: u64) {
+    let _ = handle
+        .notification()
+        .builder()
+        .title("tsw_io")
+        .body(format!(
+            "The TSW IO server is using {} MB of memory. It will restart the next time you are away.",
+            used_mb
+        ))
+        .show();
+}
+
+fn restart(handle: &AppHandle, used_mb: u64, limit_mb: u64) {
+    tracing::warn!(used_mb, limit_mb, "Restarting backend to free memory");
+    let detail = format!(
+        "Restarted after using {} MB of memory, over the {} MB ceiling",
+        used_mb, limit_mb
+    );
+    monitor::record_restart(handle, detail.clone());
+    audit::record(handle, AuditAction::BackendRestart, detail);
+    if let Err(e) = restart::restart(handle) {
+        tracing::error!("Failed to restart backend: {}", e);
+    }
+}
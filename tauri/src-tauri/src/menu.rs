@@ -0,0 +1,88 @@
+use tauri::menu::{Menu, MenuItem, MenuItemKind, PredefinedMenuItem, HELP_SUBMENU_ID};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::config::ConfigState;
+use crate::{archive, console, diagnostics, imports, report};
+
+const IMPORT_SPREADSHEETS_ID: &str = "file:import_spreadsheets";
+const EXPORT_ARCHIVE_ID: &str = "file:export_archive";
+const DIAGNOSTICS_ID: &str = "help:diagnostics";
+const COPY_DEBUG_INFO_ID: &str = "help:copy_debug_info";
+const REPORT_PROBLEM_ID: &str = "help:report_problem";
+const BACKEND_CONSOLE_ID: &str = "help:backend_console";
+
+/// Install the platform default app menu with our entries added to File and Help
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let menu = Menu::default(app)?;
+    // The default File menu has no id to find it by
+    let file = menu.items()?.into_iter().find_map(|item| match item {
+        MenuItemKind::Submenu(submenu) if submenu.text().is_ok_and(|text| text == "File") => Some(submenu),
+        _ => None,
+    });
+    if let Some(file) = file {
+        file.prepend_items(&[
+            &MenuItem::with_id(app, IMPORT_SPREADSHEETS_ID, "Import spreadsheets…", true, None::<&str>)?,
+            &MenuItem::with_id(app, EXPORT_ARCHIVE_ID, "Export everything…", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+        ])?;
+    }
+    if let Some(help) = menu.get(HELP_SUBMENU_ID).and_then(|item| item.as_submenu().cloned()) {
+        help.append(&MenuItem::with_id(app, DIAGNOSTICS_ID, "Diagnostics", true, None::<&str>)?)?;
+        help.append(&MenuItem::with_id(
+            app,
+            COPY_DEBUG_INFO_ID,
+            "Copy debug info",
+            true,
+            None::<&str>,
+        )?)?;
+        help.append(&MenuItem::with_id(
+            app,
+            REPORT_PROBLEM_ID,
+            "Report a problem",
+            true,
+            None::<&str>,
+        )?)?;
+        if app.state::<ConfigState>().get().developer_mode {
+            help.append(&MenuItem::with_id(
+                app,
+                BACKEND_CONSOLE_ID,
+                "Open backend console",
+                true,
+                None::<&str>,
+            )?)?;
+        }
+    }
+    app.set_menu(menu)?;
+    app.on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    Ok(())
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    if id == IMPORT_SPREADSHEETS_ID {
+        imports::import_from_menu(app);
+    } else if id == EXPORT_ARCHIVE_ID {
+        archive::export_from_menu(app);
+    } else if id == DIAGNOSTICS_ID {
+        if let Err(e) = diagnostics::open(app) {
+            tracing::warn!("Failed to open diagnostics: {}", e);
+        }
+    } else if id == COPY_DEBUG_INFO_ID {
+        if let Err(e) = diagnostics::copy_debug_info(app.clone()) {
+            tracing::warn!("{}", e);
+        }
+    } else if id == REPORT_PROBLEM_ID {
+        if let Err(e) = report::open(app) {
+            tracing::warn!("Failed to open bug report: {}", e);
+        }
+    } else if id == BACKEND_CONSOLE_ID {
+        if let Err(e) = console::open(app) {
+            tracing::warn!("{}", e);
+            app.dialog()
+                .message(e)
+                .title("TSW IO")
+                .kind(MessageDialogKind::Error)
+                .show(|_| {});
+        }
+    }
+}
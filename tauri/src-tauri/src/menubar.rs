@@ -0,0 +1,137 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::api::{self, SummaryStat};
+use crate::config::ConfigState;
+use crate::lock;
+use crate::profile::ProfileState;
+use crate::supervisor::{self, SupervisorState};
+
+/// Separate from the tray icon, which keeps its own menu, see `tray`
+const TRAY_ID: &str = "stats";
+/// Stat items are this and their place in `ROUTES`
+const STAT_PREFIX: &str = "menubar:";
+const OPEN_ID: &str = "menubar_open";
+const REFRESH_ID: &str = "menubar_refresh";
+/// How often the numbers are fetched again while shown
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+/// How often the setting is looked at while the numbers are hidden
+const IDLE_INTERVAL: Duration = Duration::from_secs(15);
+/// Shown until the backend answers
+const PLACEHOLDER: &str = "tsw_io";
+
+/// The page each stat item opens on, in menu order
+static ROUTES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Whether the menu bar extra is shown, only macOS has a menu bar to put it in
+fn wanted(handle: &AppHandle) -> bool {
+    cfg!(target_os = "macos") && handle.state::<ConfigState>().get().menu_bar_stats
+}
+
+/// Keep today's numbers in the menu bar while the config asks for it, see `menu_bar_stats`
+pub fn start(handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        if !wanted(&handle) {
+            if handle.remove_tray_by_id(TRAY_ID).is_some() {
+                tracing::info!("Removed the menu bar stats");
+            }
+            std::thread::sleep(IDLE_INTERVAL);
+            continue;
+        }
+        refresh(&handle);
+        std::thread::sleep(REFRESH_INTERVAL);
+    });
+}
+
+/// Fetch the numbers and show them, creating the menu bar extra if needed
+fn refresh(handle: &AppHandle) {
+    if handle.tray_by_id(TRAY_ID).is_none() {
+        if let Err(e) = create(handle) {
+            tracing::warn!("Failed to create the menu bar stats: {}", e);
+            return;
+        }
+    }
+    // Today's numbers are the user's data too, only the placeholder shows while locked
+    if lock::is_locked(handle) {
+        if let Err(e) = show(handle, &[]) {
+            tracing::warn!("Failed to update the menu bar stats: {}", e);
+        }
+        return;
+    }
+    if supervisor::state(handle) != SupervisorState::Running {
+        return;
+    }
+    let base_url = handle.state::<ProfileState>().get().base_url();
+    match api::summary(&base_url) {
+        Ok(stats) => {
+            if let Err(e) = show(handle, &stats) {
+                tracing::warn!("Failed to update the menu bar stats: {}", e);
+            }
+        }
+        Err(e) => tracing::debug!("Failed to fetch today's summary: {}", e),
+    }
+}
+
+fn create(handle: &AppHandle) -> tauri::Result<()> {
+    TrayIconBuilder::with_id(TRAY_ID)
+        .title(PLACEHOLDER)
+        .tooltip("Today in tsw_io")
+        .menu(&menu(handle, &[])?)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()))
+        .build(handle)?;
+    Ok(())
+}
+
+/// One item per stat that opens its page, then the app and refresh items
+fn menu(handle: &AppHandle, stats: &[SummaryStat]) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(handle)?;
+    if stats.is_empty() {
+        menu.append(&MenuItem::new(handle, "Waiting for the backend", false, None::<&str>)?)?;
+    }
+    for (index, stat) in stats.iter().enumerate() {
+        let text = format!("{}: {}", stat.label, stat.value);
+        let id = format!("{}{}", STAT_PREFIX, index);
+        menu.append(&MenuItem::with_id(handle, id, text, true, None::<&str>)?)?;
+    }
+    menu.append(&PredefinedMenuItem::separator(handle)?)?;
+    menu.append(&MenuItem::with_id(handle, OPEN_ID, "Open tsw_io", true, None::<&str>)?)?;
+    menu.append(&MenuItem::with_id(handle, REFRESH_ID, "Refresh", true, None::<&str>)?)?;
+    Ok(menu)
+}
+
+/// The headline values next to the icon, all of them in the menu
+fn show(handle: &AppHandle, stats: &[SummaryStat]) -> tauri::Result<()> {
+    let Some(tray) = handle.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let headline: Vec<&str> = stats
+        .iter()
+        .filter(|stat| stat.headline)
+        .map(|stat| stat.value.as_str())
+        .collect();
+    let title = if headline.is_empty() { PLACEHOLDER.to_string() } else { headline.join(" · ") };
+    tray.set_title(Some(title))?;
+    tray.set_menu(Some(menu(handle, stats)?))?;
+    *ROUTES.lock().unwrap() = stats.iter().map(|stat| stat.route.clone()).collect();
+    Ok(())
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    if let Some(index) = id.strip_prefix(STAT_PREFIX).and_then(|index| index.parse::<usize>().ok()) {
+        let Some(route) = ROUTES.lock().unwrap().get(index).cloned() else {
+            return;
+        };
+        if let Err(e) = crate::control::open_route(app, &route) {
+            tracing::warn!("Failed to open {}: {}", route, e);
+        }
+    } else if id == OPEN_ID {
+        crate::open_main_window(app);
+    } else if id == REFRESH_ID {
+        // Talking to the backend blocks, keep it off the event loop
+        let app = app.clone();
+        std::thread::spawn(move || refresh(&app));
+    }
+}
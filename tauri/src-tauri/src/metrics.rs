@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::monitor;
+use crate::paths::AppPaths;
+
+const HISTORY_FILE: &str = "metrics.jsonl";
+const SAMPLE_INTERVAL_SECS: u64 = 60;
+/// How far back the history goes
+const RETENTION_SECS: u64 = 24 * 60 * 60;
+/// Rewrite the file without expired samples after this many appends
+const COMPACT_EVERY: usize = 60;
+
+/// One periodic measurement of the backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricSample {
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    /// `None` when no health check has run yet
+    pub healthy: Option<bool>,
+    pub latency_ms: Option<u64>,
+    pub cpu_percent: Option<f32>,
+    pub memory_bytes: Option<u64>,
+}
+
+/// The last 24 hours of samples, mirrored in a JSON lines file in the log directory
+pub struct MetricsHistory(pub Mutex<VecDeque<MetricSample>>);
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn history_path(handle: &AppHandle) -> PathBuf {
+    handle.state::<AppPaths>().log_dir.join(HISTORY_FILE)
+}
+
+impl MetricsHistory {
+    /// Load the samples still within the retention window
+    pub fn load(log_dir: &Path) -> MetricsHistory {
+        let cutoff = now_secs().saturating_sub(RETENTION_SECS);
+        let samples = std::fs::read_to_string(log_dir.join(HISTORY_FILE))
+            .map(|contents| {
+                contents
+                    .lines()
+                    // A line cut short by a crash is skipped rather than failing the whole file
+                    .filter_map(|line| serde_json::from_str::<MetricSample>(line).ok())
+                    .filter(|sample| sample.at >= cutoff)
+                    .collect()
+            })
+            .unwrap_or_default();
+        MetricsHistory(Mutex::new(samples))
+    }
+}
+
+fn append(path: &Path, sample: &MetricSample) -> Result<(), String> {
+    let line = serde_json::to_string(sample).map_err(|e| e.to_string())?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn rewrite(path: &Path, samples: &VecDeque<MetricSample>) -> Result<(), String> {
+    let contents: String = samples
+        .iter()
+        .filter_map(|sample| serde_json::to_string(sample).ok())
+        .map(|line| line + "\n")
+        .collect();
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn take_sample(handle: &AppHandle) -> MetricSample {
+    let last_check = monitor::last_check(handle);
+    let usage = crate::backend_pid(handle).and_then(|pid| monitor::process_usage(handle, pid));
+    MetricSample {
+        at: now_secs(),
+        healthy: last_check.as_ref().map(|sample| sample.healthy),
+        latency_ms: last_check.map(|sample| sample.latency_ms),
+        cpu_percent: usage.as_ref().map(|usage| usage.cpu_percent),
+        memory_bytes: usage.map(|usage| usage.memory_bytes),
+    }
+}
+
+/// Record a sample every minute for as long as the app runs
+pub fn start_recording(handle: AppHandle) {
+    std::thread::spawn(move || {
+        let path = history_path(&handle);
+        let mut appended = 0;
+        loop {
+            std::thread::sleep(Duration::from_secs(SAMPLE_INTERVAL_SECS));
+
+            let sample = take_sample(&handle);
+            let history = handle.state::<MetricsHistory>();
+            let mut samples = history.0.lock().unwrap();
+            let cutoff = sample.at.saturating_sub(RETENTION_SECS);
+            while samples.front().is_some_and(|oldest| oldest.at < cutoff) {
+                samples.pop_front();
+            }
+
+            let result = append(&path, &sample);
+            samples.push_back(sample);
+            appended += 1;
+            let result = result.and_then(|_| {
+                if appended % COMPACT_EVERY == 0 {
+                    rewrite(&path, &samples)
+                } else {
+                    Ok(())
+                }
+            });
+            if let Err(e) = result {
+                tracing::warn!("Failed to record metrics: {}", e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_metrics_history(app: AppHandle) -> Vec<MetricSample> {
+    app.state::<MetricsHistory>().0.lock().unwrap().iter().cloned().collect()
+}
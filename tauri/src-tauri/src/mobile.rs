@@ -0,0 +1,551 @@
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::webview::PageLoadEvent;
+use tauri::{AppHandle, Manager, Url, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+
+use crate::logging::{self, LogLevel};
+use crate::{mdns, offline, tls};
+
+const WINDOW_LABEL: &str = "main";
+const SERVER_FILE_NAME: &str = "server.json";
+/// Service and account the access token is filed under in the keychain or keystore
+const SERVICE: &str = "tsw_io";
+const TOKEN_ACCOUNT: &str = "remote_access_token";
+/// How long to wait for a server that answers but isn't ready, as while it migrates
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// How long to listen for computers answering over mDNS
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(2);
+/// Scheme and host of the links the desktop app shows as a QR code to pair with it
+const PAIR_LINK_SCHEME: &str = "tsw-io";
+const PAIR_LINK_HOST: &str = "pair";
+/// Away longer than this, the page is loaded fresh instead of reconnecting its LiveView, whose
+/// state the server has long dropped
+const SESSION_STALE_AFTER: Duration = Duration::from_secs(30 * 60);
+
+static HEARTBEAT_STARTED: AtomicBool = AtomicBool::new(false);
+/// When the app went to the background, `None` while it is in the foreground
+static SUSPENDED_AT: Mutex<Option<Instant>> = Mutex::new(None);
+/// Whether the credential store could be set up, see [`credential_store`]
+static CREDENTIAL_STORE: OnceLock<Result<(), String>> = OnceLock::new();
+
+/// Contents of `server.json`, the token is kept in the credential store instead
+#[derive(Debug, Serialize, Deserialize)]
+struct ServerFile {
+    url: String,
+    /// The token is a device token from pairing, which the webview trades for a session
+    #[serde(default)]
+    paired: bool,
+}
+
+/// Server the app connected to last
+#[derive(Debug, Serialize)]
+pub struct SavedServer {
+    pub url: String,
+    pub has_token: bool,
+}
+
+/// Why the server isn't ready
+enum NotReady {
+    /// It answers 503, as while it migrates
+    Starting,
+    Failed(String),
+}
+
+impl std::fmt::Display for NotReady {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotReady::Starting => write!(f, "the server is still starting"),
+            NotReady::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Start the mobile app, which can't run the backend and connects to one on another machine
+///
+/// The first screen asks for the server's URL and an optional access token, then waits for
+/// `/api/health` like the desktop app waits for an external backend before loading it.
+/// Later launches connect to the saved server right away. In the background the app stops
+/// checking and closes the page's socket, see [`resume`] for what happens on return.
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_opener::init())
+        .invoke_handler(tauri::generate_handler![
+            get_server,
+            connect_server,
+            pair_server,
+            discover_servers,
+            forget_server,
+            get_offline_copy
+        ])
+        .register_uri_scheme_protocol(offline::SCHEME, |context, request| {
+            offline::serve(context.app_handle(), &request)
+        })
+        .on_page_load(|webview, payload| {
+            if payload.event() != PageLoadEvent::Finished {
+                return;
+            }
+            if let Ok(Some(server)) = load_server(webview.app_handle()) {
+                offline::record(webview.app_handle(), payload.url(), &server.url, stored_token());
+            }
+        })
+        // Mobile apps only lose and regain focus when they go to the background and come back
+        .on_window_event(|window, event| match event {
+            WindowEvent::Focused(false) => suspend(window.app_handle()),
+            WindowEvent::Focused(true) => resume(window.app_handle()),
+            _ => {}
+        })
+        .setup(|app| {
+            let handle = app.handle();
+            if let Ok(log_dir) = handle.path().app_log_dir() {
+                logging::init(&log_dir, LogLevel::Info);
+            }
+            if let Err(e) = credential_store() {
+                tracing::error!("{}", e);
+            }
+            WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("connect.html".into())).build()?;
+            Ok(())
+        })
+        .build(tauri::generate_context!())
+        .expect("Error while building tsw_io")
+        .run(|handle, event| {
+            // Scanning the QR code with the camera app opens the pairing link here
+            if let tauri::RunEvent::Opened { urls } = event {
+                opened(handle, urls);
+            }
+        });
+}
+
+/// Show pairing links on the connect screen, which pairs and reports how it went
+fn opened(handle: &AppHandle, urls: Vec<Url>) {
+    let Some(link) = urls.into_iter().find(|url| url.scheme() == PAIR_LINK_SCHEME) else {
+        return;
+    };
+    let Some(window) = handle.get_webview_window(WINDOW_LABEL) else {
+        return;
+    };
+    let page = format!("/connect.html?pair={}", urlencoding::encode(link.as_str()));
+    let _ = window.navigate(bundled_url(&page));
+}
+
+fn server_file(handle: &AppHandle) -> Result<PathBuf, String> {
+    handle
+        .path()
+        .app_config_dir()
+        .map(|dir| dir.join(SERVER_FILE_NAME))
+        .map_err(|e| format!("Failed to find the config directory: {}", e))
+}
+
+/// Set up the store the token is kept in, once
+///
+/// keyring has no Android store of its own and would keep the token in memory only, so it is
+/// given one backed by the Android keystore. Without it tokens are refused rather than lost
+/// when the app closes.
+fn credential_store() -> Result<(), String> {
+    CREDENTIAL_STORE
+        .get_or_init(|| {
+            #[cfg(target_os = "android")]
+            android_keyring::set_android_keyring_credential_builder()
+                .map_err(|e| format!("Failed to open the Android keystore: {}", e))?;
+            Ok(())
+        })
+        .clone()
+}
+
+fn token_entry() -> Result<Entry, String> {
+    credential_store()?;
+    Entry::new(SERVICE, TOKEN_ACCOUNT).map_err(|e| format!("Failed to open credential store: {}", e))
+}
+
+fn stored_token() -> Option<String> {
+    let entry = match token_entry() {
+        Ok(entry) => entry,
+        Err(e) => {
+            tracing::warn!("{}", e);
+            return None;
+        }
+    };
+    match entry.get_password() {
+        Ok(token) => Some(token),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            tracing::warn!("Failed to read the access token: {}", e);
+            None
+        }
+    }
+}
+
+/// Accept only http(s) URLs to a host, without the trailing slash
+fn normalize(url: &str) -> Result<String, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("{} is not a valid URL: {}", url.trim(), e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err("The server URL must start with http:// or https://".to_string());
+    }
+    Ok(parsed.as_str().trim_end_matches('/').to_string())
+}
+
+/// Check that the server answers `/api/health` with success
+fn check_ready(url: &str, token: Option<&str>) -> Result<(), NotReady> {
+    let request = tls::backend_client()
+        .map_err(NotReady::Failed)?
+        .get(format!("{}/api/health", url))
+        .timeout(CHECK_TIMEOUT);
+    let request = match token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    };
+    let response = request.send().map_err(|e| {
+        NotReady::Failed(if e.is_timeout() {
+            "timed out".to_string()
+        } else if e.is_connect() {
+            "connection refused".to_string()
+        } else {
+            e.to_string()
+        })
+    })?;
+    match response.status().as_u16() {
+        503 => Err(NotReady::Starting),
+        401 | 403 => Err(NotReady::Failed("the server rejected the access token".to_string())),
+        status if !response.status().is_success() => Err(NotReady::Failed(format!("HTTP {}", status))),
+        _ => Ok(()),
+    }
+}
+
+/// Poll the server until it is ready, giving up early when it can't be reached at all
+fn wait_until_ready(url: &str, token: Option<&str>) -> Result<u32, String> {
+    let started_at = Instant::now();
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match check_ready(url, token) {
+            Ok(()) => {
+                tracing::info!(attempt, %url, "Server ready");
+                return Ok(attempt);
+            }
+            // Only a server that answers is worth waiting for, it may be migrating
+            Err(NotReady::Starting) if started_at.elapsed() < CONNECT_TIMEOUT => {
+                tracing::trace!(attempt, "Server not ready yet");
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => {
+                tracing::warn!(attempt, %url, "Server not ready: {}", e);
+                return Err(format!("Could not connect to {}: {}", url, e));
+            }
+        }
+    }
+}
+
+/// Go back to the connect screen when the server stops answering, it reconnects from there
+fn start_heartbeat(handle: AppHandle) {
+    if HEARTBEAT_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(move || loop {
+        std::thread::sleep(HEARTBEAT_INTERVAL);
+        // The OS may cut the network of a background app, that says nothing about the server
+        if is_suspended() {
+            continue;
+        }
+        if let Some((window, path)) = offline_window(&handle) {
+            back_online(&handle, &window, &path);
+            continue;
+        }
+        let Some((window, url)) = server_window(&handle) else {
+            continue;
+        };
+        if let Err(e) = check_ready(&url, stored_token().as_deref()) {
+            tracing::warn!("Server heartbeat lost: {}", e);
+            go_offline(&handle, &window, &url);
+        }
+    });
+}
+
+/// The main window and the server path it shows a copy of, while offline
+fn offline_window(handle: &AppHandle) -> Option<(WebviewWindow, String)> {
+    let window = handle.get_webview_window(WINDOW_LABEL)?;
+    let path = offline::live_path(&window.url().ok()?)?;
+    Some((window, path))
+}
+
+/// Show the copy of the current page, or the connect screen when there is none
+fn go_offline(handle: &AppHandle, window: &WebviewWindow, server_url: &str) {
+    let copy = window
+        .url()
+        .ok()
+        .and_then(|url| offline::server_path(&url, server_url))
+        .and_then(|path| offline::cached(handle, &path));
+    let _ = window.navigate(copy.unwrap_or_else(connect_url));
+}
+
+/// Load the live page again once the server answers
+fn back_online(handle: &AppHandle, window: &WebviewWindow, path: &str) {
+    let Ok(Some(server)) = load_server(handle) else {
+        return;
+    };
+    if check_ready(&server.url, stored_token().as_deref()).is_err() {
+        return;
+    }
+    tracing::info!("Server reachable again");
+    match format!("{}{}", server.url, path).parse() {
+        Ok(url) => {
+            let _ = window.navigate(url);
+        }
+        Err(e) => tracing::warn!("Invalid URL for {}: {}", path, e),
+    }
+}
+
+fn is_suspended() -> bool {
+    SUSPENDED_AT.lock().unwrap().is_some()
+}
+
+/// The main window and the saved server's URL, when it shows a page of that server
+fn server_window(handle: &AppHandle) -> Option<(WebviewWindow, String)> {
+    let server = load_server(handle).ok()??;
+    let window = handle.get_webview_window(WINDOW_LABEL)?;
+    let showing = window.url().is_ok_and(|url| url.as_str().starts_with(&server.url));
+    showing.then_some((window, server.url))
+}
+
+/// Close the page's LiveView socket while in the background instead of letting it time out
+fn suspend(handle: &AppHandle) {
+    let mut suspended_at = SUSPENDED_AT.lock().unwrap();
+    if suspended_at.is_some() {
+        return;
+    }
+    *suspended_at = Some(Instant::now());
+    drop(suspended_at);
+    tracing::debug!("Suspended");
+    if let Some((window, _)) = server_window(handle) {
+        let _ = window.eval("window.liveSocket && window.liveSocket.disconnect()");
+    }
+}
+
+/// Check the server is still there before reconnecting the page to it
+///
+/// After a long time away the page is loaded again, so it gets a new session rather than
+/// an error about the old one.
+fn resume(handle: &AppHandle) {
+    let Some(suspended_at) = SUSPENDED_AT.lock().unwrap().take() else {
+        return;
+    };
+    let away = suspended_at.elapsed();
+    tracing::debug!(away_secs = away.as_secs(), "Resumed");
+    let Some((window, url)) = server_window(handle) else {
+        return;
+    };
+    let handle = handle.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = check_ready(&url, stored_token().as_deref()) {
+            tracing::warn!("Server gone after resuming: {}", e);
+            go_offline(&handle, &window, &url);
+            return;
+        }
+        if away < SESSION_STALE_AFTER {
+            let _ = window.eval("window.liveSocket && window.liveSocket.connect()");
+            return;
+        }
+        tracing::info!(away_secs = away.as_secs(), "Session likely expired, reloading the page");
+        match window.url() {
+            Ok(current) => {
+                let _ = window.navigate(current);
+            }
+            Err(e) => tracing::warn!("Failed to reload the page: {}", e),
+        }
+    });
+}
+
+/// A page bundled with the app, served from a different origin on each platform
+pub(crate) fn bundled_url(path: &str) -> Url {
+    let origin = if cfg!(target_os = "android") {
+        "http://tauri.localhost"
+    } else {
+        "tauri://localhost"
+    };
+    Url::parse(&format!("{}{}", origin, path)).unwrap()
+}
+
+/// Where the main window goes to load the server, through the session route when paired
+fn entry_url(url: &str, token: Option<&str>, paired: bool) -> Result<Url, String> {
+    let entry = match token {
+        Some(token) if paired => format!("{}/pairing/session?token={}", url, urlencoding::encode(token)),
+        _ => url.to_string(),
+    };
+    entry.parse().map_err(|e| format!("Invalid server URL {}: {}", url, e))
+}
+
+fn connect_url() -> Url {
+    bundled_url("/connect.html?lost=1")
+}
+
+fn load_server(handle: &AppHandle) -> Result<Option<ServerFile>, String> {
+    let path = server_file(handle)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let server: ServerFile =
+        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    Ok(Some(server))
+}
+
+fn save_server(handle: &AppHandle, url: &str, paired: bool) -> Result<(), String> {
+    let path = server_file(handle)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let server = ServerFile {
+        url: url.to_string(),
+        paired,
+    };
+    let contents = serde_json::to_string_pretty(&server).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// The saved server, for the connect screen to connect to right away
+#[tauri::command]
+pub fn get_server(app: AppHandle) -> Option<SavedServer> {
+    let server = load_server(&app).unwrap_or_else(|e| {
+        tracing::warn!("{}", e);
+        None
+    })?;
+    Some(SavedServer {
+        url: server.url,
+        has_token: stored_token().is_some(),
+    })
+}
+
+/// Wait for the server to be ready, save it and load it in the main window
+///
+/// Without a `token`, the stored one is used.
+#[tauri::command]
+pub async fn connect_server(app: AppHandle, url: String, token: Option<String>) -> Result<(), String> {
+    let url = normalize(&url)?;
+    let token = token.filter(|token| !token.is_empty());
+    let given_token = token.is_some();
+    let token = token.or_else(stored_token);
+
+    // The blocking client must stay off the async runtime
+    let checked_url = url.clone();
+    let checked_token = token.clone();
+    tauri::async_runtime::spawn_blocking(move || wait_until_ready(&checked_url, checked_token.as_deref()))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    // A token typed in is an access token, not one from pairing
+    let paired = !given_token
+        && load_server(&app)
+            .ok()
+            .flatten()
+            .is_some_and(|saved| saved.paired && saved.url == url);
+    open_server(&app, &url, token.as_deref(), given_token, paired)
+}
+
+/// Save the server, store `token` when it is new, and load the server in the main window
+fn open_server(app: &AppHandle, url: &str, token: Option<&str>, new_token: bool, paired: bool) -> Result<(), String> {
+    save_server(app, url, paired)?;
+    if let (true, Some(token)) = (new_token, token) {
+        token_entry()?
+            .set_password(token)
+            .map_err(|e| format!("Failed to store the access token: {}", e))?;
+    }
+
+    let window = app
+        .get_webview_window(WINDOW_LABEL)
+        .ok_or_else(|| "The main window is not open".to_string())?;
+    window
+        .navigate(entry_url(url, token, paired)?)
+        .map_err(|e| format!("Failed to open {}: {}", url, e))?;
+    start_heartbeat(app.clone());
+    Ok(())
+}
+
+/// Server URL and one-time code of a `tsw-io://pair` link
+fn parse_pair_link(link: &str) -> Result<(String, String), String> {
+    let invalid = || "That is not a pairing link from TSW IO on a computer".to_string();
+    let link = Url::parse(link.trim()).map_err(|_| invalid())?;
+    if link.scheme() != PAIR_LINK_SCHEME || link.host_str() != Some(PAIR_LINK_HOST) {
+        return Err(invalid());
+    }
+    let param = |name: &str| {
+        link.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    match (param("url"), param("code")) {
+        (Some(url), Some(code)) => Ok((normalize(&url)?, code)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Trade the pairing code for a device token
+fn redeem(url: &str, code: &str) -> Result<String, String> {
+    let name = format!("tsw_io on {}", std::env::consts::OS);
+    let response = tls::backend_client()?
+        .post(format!("{}/api/pairing", url))
+        .json(&serde_json::json!({ "code": code, "name": name }))
+        .timeout(CHECK_TIMEOUT)
+        .send()
+        .map_err(|e| format!("Could not connect to {}: {}", url, e))?;
+    let status = response.status();
+    let body: serde_json::Value = response
+        .json()
+        .map_err(|e| format!("Invalid response from {}: {}", url, e))?;
+    match (status.is_success(), body["token"].as_str(), body["error"].as_str()) {
+        (true, Some(token), _) => Ok(token.to_string()),
+        (_, _, Some("expired")) => Err("The pairing code expired, make a new one on the computer".to_string()),
+        (_, _, Some("invalid")) => {
+            Err("The pairing code was already used or replaced, make a new one on the computer".to_string())
+        }
+        _ => Err(format!("Pairing failed: HTTP {}", status)),
+    }
+}
+
+/// Pair with the computer that showed `link`, then connect to it like [`connect_server`]
+#[tauri::command]
+pub async fn pair_server(app: AppHandle, link: String) -> Result<(), String> {
+    let (url, code) = parse_pair_link(&link)?;
+    let checked_url = url.clone();
+    let token = tauri::async_runtime::spawn_blocking(move || {
+        let token = redeem(&checked_url, &code)?;
+        wait_until_ready(&checked_url, Some(&token))?;
+        Ok::<_, String>(token)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    tracing::info!(%url, "Paired with the server");
+    open_server(&app, &url, Some(&token), true, true)
+}
+
+/// Computers on the network with LAN access on, as they advertise themselves over mDNS
+#[tauri::command]
+pub async fn discover_servers() -> Result<Vec<mdns::Instance>, String> {
+    tauri::async_runtime::spawn_blocking(|| mdns::browse(DISCOVERY_TIMEOUT))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Where to read the most recently viewed page while the server can't be reached
+#[tauri::command]
+pub fn get_offline_copy(app: AppHandle) -> Option<String> {
+    offline::latest(&app).map(String::from)
+}
+
+/// Forget the saved server, its access token and the pages kept from it
+#[tauri::command]
+pub fn forget_server(app: AppHandle) -> Result<(), String> {
+    offline::clear(&app);
+    let path = server_file(&app)?;
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    }
+    match token_entry()?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to remove the access token: {}", e)),
+    }
+}
@@ -0,0 +1,129 @@
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::localhttp::{self, respond};
+use crate::loopback;
+
+/// How long the `slow` scenario answers 503, enough for the splash to show migrations
+const SLOW_STARTUP: Duration = Duration::from_secs(10);
+
+static STARTED_AT: OnceLock<Instant> = OnceLock::new();
+static SCENARIO: OnceLock<Scenario> = OnceLock::new();
+/// Set through `POST /mock/down`, to test losing and regaining the backend
+static DOWN: AtomicBool = AtomicBool::new(false);
+
+/// How the mock backend behaves, picked with `--mock-backend=<scenario>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scenario {
+    /// Healthy right away
+    Ready,
+    /// Unavailable for a while first, as when migrating the database
+    Slow,
+    /// Never becomes ready, so the startup times out
+    Fail,
+}
+
+impl Scenario {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "" | "ready" => Ok(Scenario::Ready),
+            "slow" => Ok(Scenario::Slow),
+            "fail" => Ok(Scenario::Fail),
+            other => Err(format!("Unknown mock backend scenario '{}', expected ready, slow or fail", other)),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Scenario::Ready => "ready",
+            Scenario::Slow => "slow",
+            Scenario::Fail => "fail",
+        }
+    }
+}
+
+/// Whether the shell talks to the mock instead of the Elixir backend
+pub fn enabled() -> bool {
+    SCENARIO.get().is_some()
+}
+
+/// Serve a stand-in backend on a free loopback port, returning its URL
+///
+/// It answers `/api/health` and a few stub pages, so the shell's startup, failure and
+/// reconnect flows run without building the Elixir release. `POST /mock/down` and
+/// `POST /mock/up` make the health check fail and recover.
+pub fn start(scenario: Scenario) -> Result<String, String> {
+    let listener = TcpListener::bind((loopback::BIND_ADDRESS, 0))
+        .map_err(|e| format!("Failed to start the mock backend: {}", e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to start the mock backend: {}", e))?
+        .port();
+    let _ = SCENARIO.set(scenario);
+    let _ = STARTED_AT.set(Instant::now());
+    tracing::warn!(port, scenario = scenario.as_str(), "Using the mock backend");
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                if let Err(e) = serve(stream) {
+                    tracing::debug!("Mock backend request failed: {}", e);
+                }
+            });
+        }
+    });
+    Ok(format!("http://localhost:{}", port))
+}
+
+fn healthy() -> bool {
+    let elapsed = STARTED_AT.get().map(Instant::elapsed).unwrap_or_default();
+    !DOWN.load(Ordering::SeqCst)
+        && match SCENARIO.get() {
+            Some(Scenario::Ready) => true,
+            Some(Scenario::Slow) => elapsed >= SLOW_STARTUP,
+            Some(Scenario::Fail) | None => false,
+        }
+}
+
+fn serve(stream: TcpStream) -> std::io::Result<()> {
+    let request = localhttp::read_request(&stream)?;
+
+    let (status, content_type, body) = match (request.method.as_str(), request.path()) {
+        (_, "/api/health") if healthy() => (200, "application/json", r#"{"status":"ok"}"#.to_string()),
+        (_, "/api/health") => (503, "application/json", r#"{"status":"starting"}"#.to_string()),
+        ("POST", "/mock/down") => {
+            DOWN.store(true, Ordering::SeqCst);
+            (204, "text/plain", String::new())
+        }
+        ("POST", "/mock/up") => {
+            DOWN.store(false, Ordering::SeqCst);
+            (204, "text/plain", String::new())
+        }
+        ("GET", "/") => (200, "text/html", page("Mock backend", INDEX)),
+        ("GET", "/trains") => (200, "text/html", page("Trains", TRAINS)),
+        _ => (404, "text/html", page("Not found", "<p>The mock backend has no such page.</p>")),
+    };
+    respond(stream, status, content_type, &body)
+}
+
+const INDEX: &str = r#"<p>The shell is running against the mock backend.</p>
+<ul>
+  <li><a href="/trains">Trains</a></li>
+  <li><a href="/missing">A missing page</a></li>
+</ul>
+<p>
+  <button onclick="fetch('/mock/down', { method: 'POST' })">Go down</button>
+  <button onclick="fetch('/mock/up', { method: 'POST' })">Come back</button>
+</p>"#;
+
+const TRAINS: &str = r#"<ul><li>Class 101</li><li>Class 66</li></ul><p><a href="/">Back</a></p>"#;
+
+fn page(title: &str, content: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"UTF-8\"><title>{}</title></head>\
+         <body style=\"font-family: sans-serif; padding: 2rem\"><h1>{}</h1>{}</body></html>",
+        title, title, content
+    )
+}
@@ -0,0 +1,240 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::{Condvar, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use sysinfo::{Disks, Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager};
+
+/// Restarts kept for the diagnostics window
+const MAX_RESTART_EVENTS: usize = 20;
+/// Lines of backend output kept for error screens and crash reports
+const BACKEND_OUTPUT_LINES: usize = 200;
+/// Line the bundled backend prints once it serves requests, see `TswIo.Readiness`
+pub const READY_MARKER: &str = "TSW_IO_READY";
+
+/// Result of one heartbeat health check
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSample {
+    pub healthy: bool,
+    pub latency_ms: u64,
+    /// Seconds since the Unix epoch
+    pub at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestartEvent {
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    pub reason: String,
+}
+
+/// How the backend sidecar ended when nobody asked it to
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendExit {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessUsage {
+    /// Percentage of one core, so it can go above 100 on multi-core machines
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskUsage {
+    /// Space taken by the directory itself
+    pub used_bytes: u64,
+    /// Free space on the disk holding the directory
+    pub available_bytes: Option<u64>,
+}
+
+#[derive(Default)]
+struct Record {
+    last_check: Option<HealthSample>,
+    restarts: VecDeque<RestartEvent>,
+    /// Output of the current sidecar
+    output: VecDeque<String>,
+    /// Set when the current sidecar exited on its own
+    exit: Option<BackendExit>,
+}
+
+/// What the shell has observed about the backend while running
+#[derive(Default)]
+pub struct Monitor {
+    record: RwLock<Record>,
+    // CPU usage is measured between refreshes, so the same System is reused
+    system: Mutex<System>,
+    /// Whether the current sidecar printed the ready marker
+    ready: Mutex<bool>,
+    ready_signal: Condvar,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+pub fn record_check(handle: &AppHandle, healthy: bool, latency: Duration) {
+    handle.state::<Monitor>().record.write().unwrap().last_check = Some(HealthSample {
+        healthy,
+        latency_ms: latency.as_millis() as u64,
+        at: now_secs(),
+    });
+}
+
+pub fn record_restart(handle: &AppHandle, reason: impl Into<String>) {
+    let monitor = handle.state::<Monitor>();
+    let mut record = monitor.record.write().unwrap();
+    if record.restarts.len() == MAX_RESTART_EVENTS {
+        record.restarts.pop_front();
+    }
+    record.restarts.push_back(RestartEvent {
+        at: now_secs(),
+        reason: reason.into(),
+    });
+}
+
+/// Forget the previous sidecar's output and exit once a new one is started
+pub fn backend_spawned(handle: &AppHandle) {
+    {
+        let monitor = handle.state::<Monitor>();
+        let mut record = monitor.record.write().unwrap();
+        record.output.clear();
+        record.exit = None;
+        *monitor.ready.lock().unwrap() = false;
+    }
+    record_restart(handle, "Backend started");
+}
+
+/// Forget the exit of a backend that was given up on, when the one before it takes over again
+pub fn backend_reattached(handle: &AppHandle) {
+    handle.state::<Monitor>().record.write().unwrap().exit = None;
+}
+
+pub fn record_output(handle: &AppHandle, line: String) {
+    let monitor = handle.state::<Monitor>();
+    if line == READY_MARKER {
+        *monitor.ready.lock().unwrap() = true;
+        monitor.ready_signal.notify_all();
+    }
+    let mut record = monitor.record.write().unwrap();
+    if record.output.len() == BACKEND_OUTPUT_LINES {
+        record.output.pop_front();
+    }
+    record.output.push_back(line);
+}
+
+pub fn record_exit(handle: &AppHandle, exit: BackendExit) {
+    handle.state::<Monitor>().record.write().unwrap().exit = Some(exit);
+    record_restart(handle, "Backend exited unexpectedly");
+}
+
+/// Wait up to `timeout` for the current sidecar to say it is ready, `true` once it has
+pub fn wait_for_ready_signal(handle: &AppHandle, timeout: Duration) -> bool {
+    let monitor = handle.state::<Monitor>();
+    let ready = monitor.ready.lock().unwrap();
+    let (ready, _) = monitor
+        .ready_signal
+        .wait_timeout_while(ready, timeout, |ready| !*ready)
+        .unwrap();
+    *ready
+}
+
+/// Most recent output of the current sidecar, oldest line first
+pub fn recent_output(handle: &AppHandle) -> Vec<String> {
+    handle.state::<Monitor>().record.read().unwrap().output.iter().cloned().collect()
+}
+
+pub fn backend_exit(handle: &AppHandle) -> Option<BackendExit> {
+    handle.state::<Monitor>().record.read().unwrap().exit.clone()
+}
+
+pub fn last_check(handle: &AppHandle) -> Option<HealthSample> {
+    handle.state::<Monitor>().record.read().unwrap().last_check.clone()
+}
+
+/// Restarts, most recent first
+pub fn restarts(handle: &AppHandle) -> Vec<RestartEvent> {
+    let monitor = handle.state::<Monitor>();
+    let record = monitor.record.read().unwrap();
+    record.restarts.iter().rev().cloned().collect()
+}
+
+/// CPU and memory of `pid` together with its children, since the sidecar wraps the BEAM
+pub fn process_usage(handle: &AppHandle, pid: u32) -> Option<ProcessUsage> {
+    let monitor = handle.state::<Monitor>();
+    let mut system = monitor.system.lock().unwrap();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory(),
+    );
+
+    let root = Pid::from_u32(pid);
+    system.process(root)?;
+    let usage = system
+        .processes()
+        .values()
+        .filter(|process| is_descendant(&system, process.pid(), root))
+        .fold(
+            ProcessUsage {
+                cpu_percent: 0.0,
+                memory_bytes: 0,
+            },
+            |total, process| ProcessUsage {
+                cpu_percent: total.cpu_percent + process.cpu_usage(),
+                memory_bytes: total.memory_bytes + process.memory(),
+            },
+        );
+    Some(usage)
+}
+
+fn is_descendant(system: &System, mut pid: Pid, root: Pid) -> bool {
+    loop {
+        if pid == root {
+            return true;
+        }
+        match system.process(pid).and_then(|process| process.parent()) {
+            Some(parent) => pid = parent,
+            None => return false,
+        }
+    }
+}
+
+pub fn disk_usage(dir: &Path) -> DiskUsage {
+    DiskUsage {
+        used_bytes: dir_size(dir),
+        available_bytes: available_space(dir),
+    }
+}
+
+/// Free space on the disk holding `dir`
+pub fn available_space(dir: &Path) -> Option<u64> {
+    // The disk holding the directory is the one with the longest matching mount point
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| dir.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
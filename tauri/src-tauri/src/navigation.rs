@@ -0,0 +1,69 @@
+use std::sync::RwLock;
+use tauri::webview::NewWindowResponse;
+use tauri::{Manager, Runtime, Url, WebviewWindowBuilder};
+
+/// What the main window shows while it waits for the backend
+pub const BLANK_URL: &str = "about:blank";
+
+/// Origin of the backend the main window may show, it changes with the port
+static BACKEND_ORIGIN: RwLock<Option<String>> = RwLock::new(None);
+
+/// Let the main window show pages of the backend at `base_url`
+pub fn set_backend(base_url: &str) {
+    let origin = base_url
+        .parse::<Url>()
+        .map(|url| url.origin().ascii_serialization())
+        .ok();
+    if let Ok(mut current) = BACKEND_ORIGIN.write() {
+        *current = origin;
+    }
+}
+
+fn is_backend(url: &Url) -> bool {
+    BACKEND_ORIGIN
+        .read()
+        .ok()
+        .and_then(|origin| origin.clone())
+        .is_some_and(|origin| origin == url.origin().ascii_serialization())
+}
+
+/// Keep the main window on the backend, sending every other page to the system browser
+///
+/// The app chrome makes pages look trusted, so nothing else gets rendered in it.
+pub fn restrict<'a, R: Runtime, M: Manager<R>>(
+    builder: WebviewWindowBuilder<'a, R, M>,
+    base_url: &str,
+) -> WebviewWindowBuilder<'a, R, M> {
+    set_backend(base_url);
+    builder
+        .on_navigation(|url| {
+            let allowed = url.as_str() == BLANK_URL || is_backend(url);
+            if !allowed {
+                open_externally(url);
+            }
+            allowed
+        })
+        .on_new_window(|url, _features| {
+            // Even backend pages open in the main window, not in a popup without controls
+            if !is_backend(&url) {
+                open_externally(&url);
+            }
+            NewWindowResponse::Deny
+        })
+}
+
+fn open_externally(url: &Url) {
+    match open_external(url) {
+        Ok(()) => tracing::info!(%url, "Opened link in the system browser"),
+        Err(e) => tracing::warn!("{}", e),
+    }
+}
+
+/// Open a web or mail link with the system's default app, refusing every other scheme
+pub(crate) fn open_external(url: &Url) -> Result<(), String> {
+    match url.scheme() {
+        "http" | "https" | "mailto" => tauri_plugin_opener::open_url(url.as_str(), None::<&str>)
+            .map_err(|e| format!("Failed to open {}: {}", url, e)),
+        _ => Err(format!("Blocked opening {} outside the app", url)),
+    }
+}
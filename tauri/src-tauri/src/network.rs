@@ -0,0 +1,78 @@
+use std::path::Path;
+use std::sync::RwLock;
+
+use crate::config::{NetworkConfig, ShellConfig};
+
+/// The settings the shell's clients are built with, updated with the config
+static SETTINGS: RwLock<Option<NetworkConfig>> = RwLock::new(None);
+
+/// Take the proxy and certificates of `config` for the clients built from now on
+pub fn configure(config: &ShellConfig) {
+    if let Ok(mut current) = SETTINGS.write() {
+        *current = Some(config.network.clone());
+    }
+}
+
+fn settings() -> NetworkConfig {
+    SETTINGS
+        .read()
+        .ok()
+        .and_then(|settings| settings.clone())
+        .unwrap_or_default()
+}
+
+/// The root certificates in the PEM file at `path`
+fn load_certificates(path: &Path) -> Result<Vec<reqwest::Certificate>, String> {
+    let pem = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    reqwest::Certificate::from_pem_bundle(&pem)
+        .map_err(|e| format!("Invalid certificates in {}: {}", path.display(), e))
+}
+
+/// A client for the internet, through the configured proxy and trusting the configured roots
+///
+/// Without a proxy in the config, the system's is used, as `HTTPS_PROXY` says or, on macOS and
+/// Windows, as set in the network settings. The backend has its own client, see `tls`.
+pub fn client_builder() -> reqwest::blocking::ClientBuilder {
+    let settings = settings();
+    // The OS store is what native TLS trusts by default, enterprise roots get installed there
+    let mut builder =
+        reqwest::blocking::Client::builder().tls_built_in_root_certs(settings.os_certificates);
+    if let Some(path) = &settings.ca_certificates {
+        match load_certificates(path) {
+            Ok(certificates) => {
+                for certificate in certificates {
+                    builder = builder.add_root_certificate(certificate);
+                }
+            }
+            // Reaching fewer servers beats reaching none
+            Err(e) => tracing::warn!("{}", e),
+        }
+    }
+    if let Some(url) = &settings.http_proxy {
+        match reqwest::Proxy::all(url) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(e) => tracing::warn!("Ignoring the proxy {}: {}", url, e),
+        }
+    }
+    builder
+}
+
+pub fn client() -> reqwest::Result<reqwest::blocking::Client> {
+    client_builder().build()
+}
+
+/// The same trust settings for the backend's update checks and firmware downloads, see `TswIo.Network`
+pub fn sidecar_env(config: &ShellConfig) -> Vec<(&'static str, String)> {
+    let network = &config.network;
+    let mut env = vec![(
+        "TSW_IO_OS_CERTS",
+        if network.os_certificates { "1" } else { "0" }.to_string(),
+    )];
+    if let Some(path) = &network.ca_certificates {
+        env.push(("TSW_IO_CA_CERTS", path.to_string_lossy().into_owned()));
+    }
+    if let Some(url) = &network.http_proxy {
+        env.push(("TSW_IO_HTTP_PROXY", url.clone()));
+    }
+    env
+}
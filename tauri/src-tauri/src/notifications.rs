@@ -0,0 +1,195 @@
+use serde::Deserialize;
+use serde_json::Value;
+use tauri::AppHandle;
+
+/// Buttons shown at most, the rest of what the backend offers is left out
+const MAX_ACTIONS: usize = 3;
+/// Shell event the chosen action goes back to the backend as
+#[cfg(any(target_os = "linux", windows))]
+const ACTION_EVENT: &str = "notification_action";
+/// Action reported when the notification itself is clicked rather than a button
+const DEFAULT_ACTION: &str = "default";
+
+/// A button on a backend notification, like "Mark done" or "Snooze"
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationAction {
+    pub id: String,
+    pub title: String,
+    /// Backend page to open when it is chosen, like `/trains/1`
+    #[serde(default)]
+    pub target: Option<String>,
+}
+
+/// What the backend sends as the `notification` event, see `TswIo.Shell.notify/2`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackendNotification {
+    /// Sent back with the chosen action, notifications without one can't report back
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default = "default_title")]
+    pub title: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Backend page opened when the notification itself is clicked
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(default)]
+    pub actions: Vec<NotificationAction>,
+}
+
+fn default_title() -> String {
+    "tsw_io".to_string()
+}
+
+impl BackendNotification {
+    /// Buttons to show, without duplicates or the one reserved for clicking the notification
+    fn buttons(&self) -> Vec<NotificationAction> {
+        let mut buttons: Vec<NotificationAction> = Vec::new();
+        for action in &self.actions {
+            let id = action.id.trim();
+            if id.is_empty() || id == DEFAULT_ACTION || buttons.iter().any(|button| button.id == id) {
+                continue;
+            }
+            buttons.push(NotificationAction {
+                id: id.to_string(),
+                ..action.clone()
+            });
+        }
+        buttons.truncate(MAX_ACTIONS);
+        buttons
+    }
+}
+
+/// Show a notification from the backend
+pub fn show(handle: &AppHandle, payload: Value) {
+    match serde_json::from_value::<BackendNotification>(payload) {
+        Ok(notification) => {
+            if crate::quiet::hold(handle, &notification.title) {
+                return;
+            }
+            let buttons = notification.buttons();
+            if let Err(e) = platform::show(handle, &notification, &buttons) {
+                tracing::warn!("Failed to show notification: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Invalid notification from the backend: {}", e),
+    }
+}
+
+/// Open what the chosen action points at and tell the backend which one it was
+///
+/// `action` is `None` when the notification itself was clicked.
+#[cfg(any(target_os = "linux", windows))]
+fn chosen(handle: &AppHandle, notification: &BackendNotification, action: Option<&str>) {
+    let button = action.and_then(|id| notification.actions.iter().find(|button| button.id.trim() == id));
+    let target = match action {
+        None => notification.target.as_deref(),
+        Some(_) => button.and_then(|button| button.target.as_deref()),
+    };
+    if let Some(route) = target {
+        if let Err(e) = crate::control::open_route(handle, route) {
+            tracing::warn!("Failed to open {} from a notification: {}", route, e);
+        }
+    } else if action.is_none() {
+        crate::open_main_window(handle);
+    }
+
+    let Some(id) = &notification.id else {
+        return;
+    };
+    let action = action.unwrap_or(DEFAULT_ACTION);
+    tracing::debug!(id, action, "Notification action chosen");
+    if let Err(e) = crate::bridge::push(ACTION_EVENT, serde_json::json!({ "id": id, "action": action })) {
+        tracing::warn!("Failed to send the notification action to the backend: {}", e);
+    }
+}
+
+/// Notifications through the desktop's notification server, which draws the buttons
+#[cfg(target_os = "linux")]
+mod platform {
+    use notify_rust::Notification;
+    use tauri::AppHandle;
+
+    use super::{chosen, BackendNotification, NotificationAction, DEFAULT_ACTION};
+
+    /// What the server reports when the notification is dismissed
+    const CLOSED: &str = "__closed";
+
+    pub fn show(handle: &AppHandle, notification: &BackendNotification, buttons: &[NotificationAction]) -> Result<(), String> {
+        let mut native = Notification::new();
+        native.summary(&notification.title).auto_icon();
+        if let Some(body) = &notification.body {
+            native.body(body);
+        }
+        // Clicking the notification, servers don't draw this one as a button
+        native.action(DEFAULT_ACTION, "Open");
+        for button in buttons {
+            native.action(&button.id, &button.title);
+        }
+        let handle = handle.clone();
+        let notification = notification.clone();
+        // Waiting for the choice blocks until the notification is closed
+        std::thread::spawn(move || match native.show() {
+            Ok(shown) => shown.wait_for_action(|action| match action {
+                CLOSED => {}
+                DEFAULT_ACTION => chosen(&handle, &notification, None),
+                action => chosen(&handle, &notification, Some(action)),
+            }),
+            Err(e) => tracing::warn!("Failed to show notification: {}", e),
+        });
+        Ok(())
+    }
+}
+
+/// Toasts with a button per action
+#[cfg(windows)]
+mod platform {
+    use tauri::AppHandle;
+    use tauri_winrt_notification::Toast;
+
+    use super::{chosen, BackendNotification, NotificationAction};
+
+    pub fn show(handle: &AppHandle, notification: &BackendNotification, buttons: &[NotificationAction]) -> Result<(), String> {
+        // Only the installed app is registered under its identifier
+        let mut toast = if tauri::is_dev() {
+            Toast::new(Toast::POWERSHELL_APP_ID)
+        } else {
+            Toast::new(&handle.config().identifier)
+        };
+        toast = toast.title(&notification.title);
+        if let Some(body) = &notification.body {
+            toast = toast.text1(body);
+        }
+        for button in buttons {
+            toast = toast.add_button(&button.title, &button.id);
+        }
+        let handle = handle.clone();
+        let notification = notification.clone();
+        toast
+            .on_activated(move |action| {
+                // Clicking the toast itself activates it without arguments
+                let action = action.as_deref().filter(|action| !action.is_empty());
+                chosen(&handle, &notification, action);
+                Ok(())
+            })
+            .show()
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Plain notifications, which can't carry buttons or say they were clicked
+#[cfg(not(any(target_os = "linux", windows)))]
+mod platform {
+    use tauri::AppHandle;
+    use tauri_plugin_notification::NotificationExt;
+
+    use super::{BackendNotification, NotificationAction};
+
+    pub fn show(handle: &AppHandle, notification: &BackendNotification, _buttons: &[NotificationAction]) -> Result<(), String> {
+        let mut builder = handle.notification().builder().title(&notification.title);
+        if let Some(body) = &notification.body {
+            builder = builder.body(body);
+        }
+        builder.show().map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, Url};
+
+use crate::tls;
+
+/// URI scheme the cached pages are served under
+pub const SCHEME: &str = "offline";
+const INDEX_FILE_NAME: &str = "index.json";
+/// Pages kept, the least recently viewed go first
+const MAX_PAGES: usize = 20;
+/// Nothing can run or be submitted, the copy is only there to be read
+const CONTENT_SECURITY_POLICY: &str = "default-src 'self'; style-src 'self' 'unsafe-inline'; img-src 'self' data:; script-src 'none'; form-action 'none'";
+
+/// Writes to the index, fetches happen on their own threads
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// A page or stylesheet saved from the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    /// Path and query on the server
+    path: String,
+    content_type: String,
+    /// Unix seconds
+    fetched_at: u64,
+    /// Stylesheets are kept as long as the pages, they don't count towards `MAX_PAGES`
+    page: bool,
+}
+
+fn cache_dir(handle: &AppHandle) -> Option<PathBuf> {
+    handle.path().app_cache_dir().ok().map(|dir| dir.join("offline"))
+}
+
+fn file_name(path: &str) -> String {
+    let digest = Sha256::digest(path.as_bytes());
+    digest[..16].iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn read_index(dir: &Path) -> Vec<Entry> {
+    std::fs::read_to_string(dir.join(INDEX_FILE_NAME))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Path and query of `url` on the server at `server_url`
+pub fn server_path(url: &Url, server_url: &str) -> Option<String> {
+    let rest = url.as_str().strip_prefix(server_url)?;
+    let path = rest.split('#').next().unwrap_or_default();
+    match path {
+        "" => Some("/".to_string()),
+        path if path.starts_with('/') => Some(path.to_string()),
+        _ => None,
+    }
+}
+
+/// Save a copy of a page the main window loaded, and its stylesheets, for when the server is gone
+///
+/// The copy is fetched again by the shell rather than taken from the webview, which has no
+/// way to hand the page over.
+pub fn record(handle: &AppHandle, url: &Url, server_url: &str, token: Option<String>) {
+    let (Some(dir), Some(path)) = (cache_dir(handle), server_path(url, server_url)) else {
+        return;
+    };
+    let server_url = server_url.to_string();
+    std::thread::spawn(move || {
+        if let Err(e) = save_page(&dir, &server_url, &path, token.as_deref()) {
+            tracing::debug!(%path, "Failed to keep an offline copy: {}", e);
+        }
+    });
+}
+
+fn fetch(server_url: &str, path: &str, token: Option<&str>) -> Result<(String, Vec<u8>), String> {
+    let request = tls::backend_client()?.get(format!("{}{}", server_url, path));
+    let request = match token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    };
+    let response = request.send().map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    let body = response.bytes().map_err(|e| e.to_string())?;
+    Ok((content_type, body.to_vec()))
+}
+
+fn save_page(dir: &Path, server_url: &str, path: &str, token: Option<&str>) -> Result<(), String> {
+    let (content_type, body) = fetch(server_url, path, token)?;
+    if !content_type.starts_with("text/html") {
+        return Ok(());
+    }
+    let html = String::from_utf8_lossy(&body);
+    // Stylesheets only change with a new server version, one copy does for every page
+    let stylesheets: Vec<String> = stylesheet_paths(&html)
+        .into_iter()
+        .filter(|stylesheet| !dir.join(file_name(stylesheet)).exists())
+        .collect();
+    let mut saved = vec![(path.to_string(), content_type, body.clone(), true)];
+    for stylesheet in stylesheets {
+        match fetch(server_url, &stylesheet, token) {
+            Ok((content_type, body)) => saved.push((stylesheet, content_type, body, false)),
+            Err(e) => tracing::debug!(%stylesheet, "Failed to keep an offline copy: {}", e),
+        }
+    }
+
+    let _lock = INDEX_LOCK.lock().unwrap();
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let mut index = read_index(dir);
+    for (path, content_type, body, page) in saved {
+        let file = dir.join(file_name(&path));
+        std::fs::write(&file, body).map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+        index.retain(|entry| entry.path != path);
+        index.insert(
+            0,
+            Entry {
+                path,
+                content_type,
+                fetched_at: now(),
+                page,
+            },
+        );
+    }
+
+    let mut pages = 0;
+    index.retain(|entry| {
+        if !entry.page {
+            return true;
+        }
+        pages += 1;
+        if pages <= MAX_PAGES {
+            return true;
+        }
+        let _ = std::fs::remove_file(dir.join(file_name(&entry.path)));
+        false
+    });
+    let contents = serde_json::to_string_pretty(&index).map_err(|e| e.to_string())?;
+    let index_path = dir.join(INDEX_FILE_NAME);
+    std::fs::write(&index_path, contents).map_err(|e| format!("Failed to write {}: {}", index_path.display(), e))
+}
+
+/// Server paths of the `<link rel="stylesheet">` tags in `html`
+fn stylesheet_paths(html: &str) -> Vec<String> {
+    html.split("<link")
+        .skip(1)
+        .filter_map(|tag| {
+            let tag = tag.split('>').next()?;
+            if !tag.contains("stylesheet") {
+                return None;
+            }
+            let href = tag.split("href=\"").nth(1)?.split('"').next()?;
+            href.starts_with('/').then(|| href.to_string())
+        })
+        .collect()
+}
+
+/// Where the cached copy of `path` is served, each platform puts custom schemes elsewhere
+pub fn url_for(path: &str) -> Url {
+    let base = if cfg!(target_os = "android") {
+        format!("http://{}.localhost", SCHEME)
+    } else {
+        format!("{}://localhost", SCHEME)
+    };
+    Url::parse(&format!("{}{}", base, path)).unwrap()
+}
+
+/// Server path a URL from `url_for` stands for, `None` for other URLs
+pub fn live_path(url: &Url) -> Option<String> {
+    let offline = if cfg!(target_os = "android") {
+        url.scheme() == "http" && url.host_str() == Some(&format!("{}.localhost", SCHEME))
+    } else {
+        url.scheme() == SCHEME
+    };
+    offline.then(|| match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    })
+}
+
+/// Offline URL of the most recently viewed page, if any was kept
+pub fn latest(handle: &AppHandle) -> Option<Url> {
+    let entry = read_index(&cache_dir(handle)?).into_iter().find(|entry| entry.page)?;
+    Some(url_for(&entry.path))
+}
+
+/// Offline URL for `path` when there is a copy of it
+pub fn cached(handle: &AppHandle, path: &str) -> Option<Url> {
+    let dir = cache_dir(handle)?;
+    read_index(&dir)
+        .iter()
+        .any(|entry| entry.page && entry.path == path)
+        .then(|| url_for(path))
+}
+
+/// Remove every kept page, as when forgetting the server
+pub fn clear(handle: &AppHandle) {
+    let Some(dir) = cache_dir(handle) else {
+        return;
+    };
+    let _lock = INDEX_LOCK.lock().unwrap();
+    if dir.exists() {
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            tracing::warn!("Failed to remove {}: {}", dir.display(), e);
+        }
+    }
+}
+
+/// Serve a kept copy read-only, with a banner saying when it is from
+pub fn serve(handle: &AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "/".to_string());
+    let entry = cache_dir(handle).and_then(|dir| {
+        let entry = read_index(&dir).into_iter().find(|entry| entry.path == path)?;
+        let body = std::fs::read(dir.join(file_name(&entry.path))).ok()?;
+        Some((entry, body))
+    });
+
+    let (status, content_type, body) = match entry {
+        Some((entry, body)) if entry.page => {
+            let html = with_banner(&String::from_utf8_lossy(&body), entry.fetched_at);
+            (StatusCode::OK, entry.content_type, html.into_bytes())
+        }
+        Some((entry, body)) => (StatusCode::OK, entry.content_type, body),
+        None => (
+            StatusCode::NOT_FOUND,
+            "text/html".to_string(),
+            with_banner(NOT_CACHED, now()).into_bytes(),
+        ),
+    };
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY)
+        .body(body)
+        .unwrap()
+}
+
+fn with_banner(html: &str, fetched_at: u64) -> String {
+    let age = match now().saturating_sub(fetched_at) / 60 {
+        0 => "just now".to_string(),
+        minutes if minutes < 60 => format!("{} min ago", minutes),
+        minutes if minutes < 48 * 60 => format!("{} h ago", minutes / 60),
+        minutes => format!("{} days ago", minutes / (24 * 60)),
+    };
+    let reconnect = crate::mobile::bundled_url("/connect.html");
+    let banner = format!(
+        "<div style=\"position: sticky; top: 0; z-index: 9999; padding: 0.5rem 1rem; background: #f59e0b; \
+         color: #1e1e2e; font: 600 0.875rem sans-serif\">Offline: a read-only copy from {}. \
+         <a href=\"{}\" style=\"color: inherit\">Reconnect</a></div>",
+        age, reconnect
+    );
+    // Right after the opening body tag, or first thing when there is none
+    match html.find("<body").and_then(|start| html[start..].find('>').map(|end| start + end + 1)) {
+        Some(at) => format!("{}{}{}", &html[..at], banner, &html[at..]),
+        None => format!("{}{}", banner, html),
+    }
+}
+
+const NOT_CACHED: &str = "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"UTF-8\"><title>TSW IO</title></head>\
+<body style=\"font-family: sans-serif; padding: 2rem\"><p>This page wasn't viewed recently, so there is no copy of it.</p></body></html>";
@@ -0,0 +1,58 @@
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+const WINDOW_LABEL: &str = "overlay";
+
+/// Dim `window` with a borderless window over it showing `status`, or update the one showing
+///
+/// The window under it stays open with its page as it was, clicks only reach the overlay.
+pub fn show(window: &WebviewWindow, status: &str) -> tauri::Result<WebviewWindow> {
+    let handle = window.app_handle();
+    if let Some(overlay) = handle.get_webview_window(WINDOW_LABEL) {
+        set_status(&overlay, status);
+        return Ok(overlay);
+    }
+
+    let scale = window.scale_factor()?;
+    let position = window.inner_position()?.to_logical::<f64>(scale);
+    let size = window.inner_size()?.to_logical::<f64>(scale);
+    let overlay_html = include_str!("../overlay.html");
+    let overlay_url = format!("data:text/html,{}", urlencoding::encode(overlay_html));
+
+    let builder = WebviewWindowBuilder::new(
+        handle,
+        WINDOW_LABEL,
+        WebviewUrl::External(overlay_url.parse().unwrap()),
+    )
+    .title("TSW IO")
+    .position(position.x, position.y)
+    .inner_size(size.width, size.height)
+    .resizable(false)
+    .decorations(false)
+    .shadow(false)
+    .skip_taskbar(true)
+    .parent(window)?;
+    // The page shows through, dimmed, where windows can be see-through without private APIs
+    #[cfg(not(target_os = "macos"))]
+    let builder = builder.transparent(true);
+    let overlay = builder.build()?;
+    set_status(&overlay, status);
+    Ok(overlay)
+}
+
+pub fn is_open(handle: &AppHandle) -> bool {
+    handle.get_webview_window(WINDOW_LABEL).is_some()
+}
+
+/// Replace the status line under the spinner
+pub fn set_status(window: &WebviewWindow, status: &str) {
+    let _ = window.eval(format!(
+        "document.getElementById('status').textContent = {}",
+        serde_json::to_string(status).unwrap_or_default()
+    ));
+}
+
+pub fn close(handle: &AppHandle) {
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+}
@@ -0,0 +1,118 @@
+use serde::Serialize;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::api::{self, PairedDevice};
+use crate::profile::ProfileState;
+use crate::{lan, lock, qr};
+
+const WINDOW_LABEL: &str = "pair";
+/// Scheme of the links the mobile app opens, scanned from the QR code
+pub const LINK_SCHEME: &str = "tsw-io";
+
+/// What the pairing window shows
+#[derive(Debug, Serialize)]
+pub struct PairingInfo {
+    pub lan_access: bool,
+    /// Where the phone reaches the backend, `None` until LAN access is on
+    pub url: Option<String>,
+    /// The link in the QR code, for typing into the phone by hand
+    pub link: Option<String>,
+    pub qr_svg: Option<String>,
+    /// Unix seconds
+    pub expires_at: Option<i64>,
+}
+
+/// Show the pairing window, or focus it if it is already open
+pub fn open(handle: &AppHandle) -> tauri::Result<()> {
+    if !lock::may_show(handle) {
+        return Ok(());
+    }
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        return window.set_focus();
+    }
+    WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("pair.html".into()))
+        .title("Connect mobile device")
+        .inner_size(420.0, 600.0)
+        .resizable(false)
+        .build()?;
+    Ok(())
+}
+
+/// The link a phone opens to pair with the backend at `url`
+pub fn link(url: &str, code: &str) -> String {
+    format!(
+        "{}://pair?url={}&code={}",
+        LINK_SCHEME,
+        urlencoding::encode(url),
+        urlencoding::encode(code)
+    )
+}
+
+fn start(handle: &AppHandle) -> Result<PairingInfo, String> {
+    let profile = handle.state::<ProfileState>().get();
+    if profile.backend_url.is_some() {
+        return Err("Phones can only be paired with the server bundled with tsw_io".to_string());
+    }
+    if profile.tls {
+        return Err("Phones can't trust the certificate made for this computer, turn HTTPS off to pair one".to_string());
+    }
+    if !lan::enabled(handle) {
+        return Ok(PairingInfo {
+            lan_access: false,
+            url: None,
+            link: None,
+            qr_svg: None,
+            expires_at: None,
+        });
+    }
+
+    let url = lan::url(handle).ok_or_else(|| {
+        "The server can't be reached from the local network, check the firewall".to_string()
+    })?;
+    let pairing = api::start_pairing(&profile.base_url())?;
+    let link = link(&url, &pairing.code);
+    tracing::info!(%url, "Started pairing a mobile device");
+    Ok(PairingInfo {
+        lan_access: true,
+        qr_svg: Some(qr::svg(&link)?),
+        url: Some(url),
+        link: Some(link),
+        expires_at: Some(pairing.expires_at),
+    })
+}
+
+/// A fresh pairing code as a QR code, each call replaces the previous code
+#[tauri::command]
+pub async fn start_pairing(app: AppHandle) -> Result<PairingInfo, String> {
+    // Scanning the interfaces and asking the backend block
+    tauri::async_runtime::spawn_blocking(move || start(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// The devices paired with the backend, for unpairing them
+#[tauri::command]
+pub async fn get_paired_devices(app: AppHandle) -> Result<Vec<PairedDevice>, String> {
+    let base_url = app.state::<ProfileState>().get().base_url();
+    tauri::async_runtime::spawn_blocking(move || api::paired_devices(&base_url))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Unpair the device `id`, it has to scan a new code to get back in
+#[tauri::command]
+pub async fn unpair_device(app: AppHandle, id: i64) -> Result<Vec<PairedDevice>, String> {
+    let base_url = app.state::<ProfileState>().get().base_url();
+    tauri::async_runtime::spawn_blocking(move || {
+        api::unpair_device(&base_url, id)?;
+        tracing::info!(id, "Unpaired a mobile device");
+        api::paired_devices(&base_url)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn open_pairing_window(app: AppHandle) -> Result<(), String> {
+    open(&app).map_err(|e| e.to_string())
+}
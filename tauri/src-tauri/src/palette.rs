@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+use crate::api::{self, SearchEntry};
+use crate::config::ConfigState;
+use crate::hotkeys;
+use crate::lock;
+use crate::navigation;
+use crate::profile::ProfileState;
+use crate::supervisor::{self, SupervisorState};
+
+const WINDOW_LABEL: &str = "palette";
+/// Results opened in windows of their own are labelled with this and a number
+const PAGE_WINDOW_PREFIX: &str = "page-";
+
+static PAGE_WINDOWS: AtomicU32 = AtomicU32::new(0);
+
+/// Summon the command palette with the hotkey in the config, see `palette_hotkey`
+pub fn start(handle: &AppHandle) {
+    let Some(text) = handle.state::<ConfigState>().get().palette_hotkey else {
+        return;
+    };
+    match hotkeys::register(handle, &text, show) {
+        Ok(()) => tracing::info!(hotkey = text, "Registered the command palette hotkey"),
+        Err(e) => tracing::warn!("Failed to set up the command palette hotkey: {}", e),
+    }
+}
+
+/// Show the palette over whatever has the focus, leaving the main window where it is
+pub fn show(handle: &AppHandle) {
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        lock::reveal(handle, &window);
+        return;
+    }
+    if !lock::may_show(handle) {
+        return;
+    }
+    let result = WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("palette.html".into()))
+        .title("Search tsw_io")
+        .inner_size(600.0, 380.0)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .focused(true)
+        .build();
+    match result {
+        Ok(window) => {
+            let closing = window.clone();
+            // Clicking elsewhere dismisses it, like Spotlight
+            window.on_window_event(move |event| {
+                if let WindowEvent::Focused(false) = event {
+                    let _ = closing.close();
+                }
+            });
+        }
+        Err(e) => tracing::warn!("Failed to open the command palette: {}", e),
+    }
+}
+
+fn close(handle: &AppHandle) {
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+}
+
+/// Open `route` in a window of its own, kept on the backend like the main window
+fn open_in_new_window(handle: &AppHandle, route: &str) -> Result<(), String> {
+    if !route.starts_with('/') || route.starts_with("//") {
+        return Err("Only backend pages can be opened".to_string());
+    }
+    if supervisor::state(handle) != SupervisorState::Running {
+        return Err("The backend is not running".to_string());
+    }
+    if !lock::may_show(handle) {
+        return Err("TSW IO is locked".to_string());
+    }
+    let base_url = handle.state::<ProfileState>().get().base_url();
+    let url = api::backend_url(&base_url, route)
+        .parse()
+        .map_err(|e| format!("Invalid route {}: {}", route, e))?;
+    let label = format!("{}{}", PAGE_WINDOW_PREFIX, PAGE_WINDOWS.fetch_add(1, Ordering::SeqCst) + 1);
+    navigation::restrict(WebviewWindowBuilder::new(handle, label, WebviewUrl::External(url)), &base_url)
+        .title("TSW IO")
+        .inner_size(1000.0, 700.0)
+        .min_inner_size(600.0, 400.0)
+        .build()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {}: {}", route, e))
+}
+
+/// The records matching what was typed, best first
+#[tauri::command]
+pub async fn search_palette(app: AppHandle, query: String) -> Result<Vec<SearchEntry>, String> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    if supervisor::state(&app) != SupervisorState::Running {
+        return Err("The backend is not running".to_string());
+    }
+    let base_url = app.state::<ProfileState>().get().base_url();
+    tauri::async_runtime::spawn_blocking(move || api::search(&base_url, &query))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Open a result in the main window, or in a new one, and dismiss the palette
+///
+/// Async so that building the new window doesn't block the event loop it needs on Windows.
+#[tauri::command]
+pub async fn open_palette_result(app: AppHandle, route: String, new_window: bool) -> Result<(), String> {
+    // Closed first, so the window the result opens in keeps the focus
+    close(&app);
+    if new_window {
+        open_in_new_window(&app, &route)
+    } else {
+        crate::control::open_route(&app, &route)
+    }
+}
+
+#[tauri::command]
+pub fn dismiss_palette(app: AppHandle) {
+    close(&app);
+}
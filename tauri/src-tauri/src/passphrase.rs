@@ -0,0 +1,159 @@
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::wizard::DATABASE_FILE_NAME;
+
+const WINDOW_LABEL: &str = "passphrase";
+/// Every plain SQLite database starts with this, an encrypted one looks random
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+/// Smallest page SQLite and SQLCipher write, a database is always whole pages
+const MIN_PAGE_SIZE: u64 = 512;
+/// Of the first page, how many of the 256 byte values an encrypted one has at least
+///
+/// Encrypted pages are indistinguishable from random bytes, which in a page of 512 already
+/// show about 220 values. Text, zeros or a torn write show far fewer.
+const MIN_DISTINCT_BYTES: usize = 160;
+
+#[derive(Default)]
+struct Inner {
+    passphrase: Option<String>,
+    /// The backend refused the last passphrase
+    rejected: bool,
+}
+
+/// Passphrase of the backend database, only ever kept in memory
+#[derive(Default)]
+pub struct Passphrase(Mutex<Inner>);
+
+/// What the passphrase window shows
+#[derive(Debug, Serialize)]
+pub struct PassphrasePrompt {
+    pub rejected: bool,
+}
+
+/// What the database file in a data directory looks like, before the backend opens it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseFile {
+    /// Not there or empty, a database SQLite hasn't written to yet
+    Missing,
+    Plain,
+    /// Encrypted with SQLCipher, opened with the passphrase
+    Encrypted,
+    /// Neither, like truncated or overwritten
+    Damaged,
+}
+
+/// Look at the database in `data_dir`
+///
+/// A file that can't be read is left to the backend, which reports what is wrong with it.
+pub fn inspect(data_dir: &Path) -> DatabaseFile {
+    let path = data_dir.join(DATABASE_FILE_NAME);
+    let Ok(mut file) = std::fs::File::open(&path) else {
+        return DatabaseFile::Missing;
+    };
+    let len = file.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+    let mut page = Vec::with_capacity(MIN_PAGE_SIZE as usize);
+    if let Err(e) = file.by_ref().take(MIN_PAGE_SIZE).read_to_end(&mut page) {
+        tracing::warn!("Failed to read {}: {}", path.display(), e);
+        return DatabaseFile::Missing;
+    }
+    if page.is_empty() {
+        return DatabaseFile::Missing;
+    }
+    if page.starts_with(SQLITE_HEADER) {
+        return DatabaseFile::Plain;
+    }
+    let mut seen = [false; 256];
+    for byte in &page {
+        seen[*byte as usize] = true;
+    }
+    let distinct = seen.iter().filter(|seen| **seen).count();
+    if len % MIN_PAGE_SIZE == 0 && distinct >= MIN_DISTINCT_BYTES {
+        DatabaseFile::Encrypted
+    } else {
+        DatabaseFile::Damaged
+    }
+}
+
+/// Whether the database in `data_dir` is encrypted and needs a passphrase to open
+pub fn is_encrypted(data_dir: &Path) -> bool {
+    inspect(data_dir) == DatabaseFile::Encrypted
+}
+
+/// Whether the user has to type the passphrase before the backend can start
+pub fn needs_prompt(handle: &AppHandle, data_dir: &Path) -> bool {
+    is_encrypted(data_dir) && handle.state::<Passphrase>().0.lock().unwrap().passphrase.is_none()
+}
+
+/// Environment handing the passphrase to the sidecar, which is never visible in its arguments
+pub fn sidecar_env(handle: &AppHandle) -> Vec<(&'static str, String)> {
+    match &handle.state::<Passphrase>().0.lock().unwrap().passphrase {
+        Some(passphrase) => vec![("TSW_IO_DB_KEY", passphrase.clone())],
+        None => Vec::new(),
+    }
+}
+
+/// Forget a passphrase the backend couldn't open the database with and ask again
+pub fn rejected(handle: &AppHandle) {
+    tracing::warn!("Database passphrase rejected");
+    {
+        let state = handle.state::<Passphrase>();
+        let mut inner = state.0.lock().unwrap();
+        inner.passphrase = None;
+        inner.rejected = true;
+    }
+    show(handle);
+}
+
+/// Ask for the database passphrase
+pub fn show(handle: &AppHandle) {
+    if crate::headless::enabled() {
+        crate::headless::fail(handle, None, "The database is encrypted and needs a passphrase");
+        return;
+    }
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        let _ = window.set_focus();
+        return;
+    }
+    let result = WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("passphrase.html".into()))
+        .title("TSW IO")
+        .inner_size(400.0, 300.0)
+        .resizable(false)
+        .center()
+        .build();
+    if let Err(e) = result {
+        crate::crash::report(&format!("Failed to ask for the database passphrase: {}", e));
+        crate::logging::flush();
+        std::process::exit(1);
+    }
+}
+
+#[tauri::command]
+pub fn get_passphrase_prompt(state: tauri::State<'_, Passphrase>) -> PassphrasePrompt {
+    PassphrasePrompt {
+        rejected: state.0.lock().unwrap().rejected,
+    }
+}
+
+/// Start the backend with the passphrase the user typed
+#[tauri::command]
+pub async fn submit_passphrase(app: AppHandle, passphrase: String) -> Result<(), String> {
+    if passphrase.is_empty() {
+        return Err("Enter the passphrase".to_string());
+    }
+    {
+        let state = app.state::<Passphrase>();
+        let mut inner = state.0.lock().unwrap();
+        inner.passphrase = Some(passphrase);
+        inner.rejected = false;
+    }
+    crate::stop_backend_and_wait(&app)?;
+    crate::launch(&app).map_err(|e| e.to_string())?;
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::cli::CliArgs;
+use crate::config::{ShellConfig, CONFIG_FILE_NAME};
+
+const APP_NAME: &str = "TswIo";
+const APP_NAME_LOWER: &str = "tsw_io";
+/// `identifier` from tauri.conf.json, names the config directory on macOS and Windows like
+/// Tauri's `app_config_dir`
+static IDENTIFIER: OnceLock<String> = OnceLock::new();
+
+/// Where the shell and the backend keep their files
+#[derive(Debug, Clone, Serialize)]
+pub struct AppPaths {
+    pub config_dir: PathBuf,
+    pub log_dir: PathBuf,
+    /// Data directory used when no profile or config sets one
+    pub default_data_dir: PathBuf,
+    /// Data directory forced with `--data-dir`/`TSW_IO_DATA_DIR`, wins over the config
+    pub data_dir_override: Option<PathBuf>,
+}
+
+/// Set once at launch from the app's config, before any directory is resolved
+pub fn set_identifier(identifier: &str) {
+    let _ = IDENTIFIER.set(identifier.to_string());
+}
+
+pub fn identifier() -> &'static str {
+    IDENTIFIER.get().expect("paths::set_identifier wasn't called")
+}
+
+/// Config directory from `--config-dir`, `TSW_IO_CONFIG_DIR` or the platform default
+pub fn config_dir(args: &CliArgs) -> PathBuf {
+    args.config_dir
+        .clone()
+        .or_else(|| env_dir("TSW_IO_CONFIG_DIR"))
+        .unwrap_or_else(platform_config_dir)
+}
+
+impl AppPaths {
+    /// Resolve the remaining directories: CLI flags win over the environment, then the config
+    pub fn resolve(args: &CliArgs, config_dir: PathBuf, config: &ShellConfig) -> AppPaths {
+        AppPaths {
+            config_dir,
+            log_dir: args
+                .log_dir
+                .clone()
+                .or_else(|| env_dir("TSW_IO_LOG_DIR"))
+                .or_else(|| config.log_dir.clone())
+                .unwrap_or_else(platform_log_dir),
+            default_data_dir: platform_data_dir(),
+            data_dir_override: args.data_dir.clone().or_else(|| env_dir("TSW_IO_DATA_DIR")),
+        }
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config_dir.join(CONFIG_FILE_NAME)
+    }
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+pub(crate) fn home_dir() -> PathBuf {
+    env_dir("HOME")
+        .or_else(|| env_dir("USERPROFILE"))
+        .unwrap_or_else(|| PathBuf::from("~"))
+}
+
+/// Roaming app data on Windows, where the backend also looks by default
+fn windows_app_data() -> PathBuf {
+    env_dir("APPDATA")
+        .or_else(|| env_dir("LOCALAPPDATA"))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn macos_app_support() -> PathBuf {
+    home_dir().join("Library").join("Application Support")
+}
+
+/// Where Linux desktops keep per-user configuration, systemd user units included
+pub(crate) fn xdg_config_home() -> PathBuf {
+    env_dir("XDG_CONFIG_HOME").unwrap_or_else(|| home_dir().join(".config"))
+}
+
+/// Apart from the data directory, which gets moved and encrypted without the shell's files
+fn platform_config_dir() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        macos_app_support().join(identifier())
+    } else if cfg!(target_os = "windows") {
+        windows_app_data().join(identifier())
+    } else {
+        xdg_config_home().join(APP_NAME_LOWER)
+    }
+}
+
+/// Matches the fallback in `TswIo.Paths` so existing databases are found
+fn platform_data_dir() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        macos_app_support().join(APP_NAME)
+    } else if cfg!(target_os = "windows") {
+        windows_app_data().join(APP_NAME)
+    } else {
+        env_dir("XDG_DATA_HOME")
+            .unwrap_or_else(|| home_dir().join(".local").join("share"))
+            .join(APP_NAME_LOWER)
+    }
+}
+
+fn platform_log_dir() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        home_dir().join("Library").join("Logs").join(APP_NAME)
+    } else if cfg!(target_os = "windows") {
+        env_dir("LOCALAPPDATA")
+            .unwrap_or_else(windows_app_data)
+            .join(APP_NAME)
+            .join("logs")
+    } else {
+        env_dir("XDG_STATE_HOME")
+            .unwrap_or_else(|| home_dir().join(".local").join("state"))
+            .join(APP_NAME_LOWER)
+            .join("logs")
+    }
+}
@@ -0,0 +1,248 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::webview::PageLoadEvent;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::api;
+use crate::downloads::{self, DownloadKind};
+use crate::navigation;
+use crate::profile::ProfileState;
+
+const WINDOW_PREFIX: &str = "pdf-export-";
+const DEFAULT_FILE_NAME: &str = "tsw_io-report.pdf";
+/// A page that isn't printed by then most likely never finished loading
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Layout width of the page, as if shown in the main window
+const PAGE_WIDTH: f64 = 1024.0;
+const PAGE_HEIGHT: f64 = 768.0;
+
+/// Numbers the hidden windows, so exports can run side by side
+static NEXT_EXPORT: AtomicU32 = AtomicU32::new(1);
+
+type Done = Sender<Result<(), String>>;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PdfOptions {
+    /// Suggested in the save dialog
+    pub file_name: Option<String>,
+    /// Ignored on macOS, which renders the page as one continuous sheet
+    pub landscape: bool,
+}
+
+/// Save a backend page as a PDF where the user picks, without a print dialog
+///
+/// `route` is a path on the backend, like `/trains/1/report`. The page is loaded in a
+/// hidden window and printed once it finished loading. Returns nothing when the user
+/// cancels the save dialog.
+#[tauri::command]
+pub async fn export_page_to_pdf(
+    app: AppHandle,
+    route: String,
+    options: Option<PdfOptions>,
+) -> Result<Option<PathBuf>, String> {
+    tauri::async_runtime::spawn_blocking(move || export(&app, &route, &options.unwrap_or_default()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn export(handle: &AppHandle, route: &str, options: &PdfOptions) -> Result<Option<PathBuf>, String> {
+    if !route.starts_with('/') || route.starts_with("//") {
+        return Err("Only backend pages can be exported".to_string());
+    }
+    let Some(path) = handle
+        .dialog()
+        .file()
+        .set_title("Export as PDF")
+        .set_file_name(options.file_name.as_deref().unwrap_or(DEFAULT_FILE_NAME))
+        .add_filter("PDF", &["pdf"])
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+    else {
+        return Ok(None);
+    };
+
+    let base_url = handle.state::<ProfileState>().get().base_url();
+    let url = api::backend_url(&base_url, route)
+        .parse()
+        .map_err(|e| format!("Invalid route {}: {}", route, e))?;
+    let label = format!("{}{}", WINDOW_PREFIX, NEXT_EXPORT.fetch_add(1, Ordering::SeqCst));
+    let (done, result) = mpsc::channel();
+    let printing = Arc::new(AtomicBool::new(false));
+    let target = path.clone();
+    let landscape = options.landscape;
+
+    let window = navigation::restrict(
+        WebviewWindowBuilder::new(handle, &label, WebviewUrl::External(url)),
+        &base_url,
+    )
+    .visible(false)
+    .inner_size(PAGE_WIDTH, PAGE_HEIGHT)
+    .on_page_load(move |window, payload| {
+        // Redirects finish loading more than once, only the page they end on is printed
+        if payload.event() != PageLoadEvent::Finished || printing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        if let Err(e) = platform::print(&window, &target, landscape, done.clone()) {
+            let _ = done.send(Err(e));
+        }
+    })
+    .build()
+    .map_err(|e| format!("Failed to load {}: {}", route, e))?;
+
+    let printed = result
+        .recv_timeout(EXPORT_TIMEOUT)
+        .unwrap_or_else(|_| Err(format!("{} took too long to load", route)));
+    let _ = window.destroy();
+    printed?;
+
+    tracing::info!(route, "Exported page to {}", path.display());
+    downloads::record(handle, DownloadKind::Report, &path);
+    Ok(Some(path))
+}
+
+/// Print through WebKitGTK with the print-to-file backend
+#[cfg(target_os = "linux")]
+mod platform {
+    use gtk::{PageOrientation, PageSetup, PrintSettings};
+    use std::path::Path;
+    use tauri::{Url, WebviewWindow};
+    use webkit2gtk::{PrintOperation, PrintOperationExt};
+
+    use super::Done;
+
+    pub fn print(window: &WebviewWindow, path: &Path, landscape: bool, done: Done) -> Result<(), String> {
+        let uri = Url::from_file_path(path)
+            .map_err(|_| format!("Can't save to {}", path.display()))?
+            .to_string();
+        window
+            .with_webview(move |webview| {
+                let orientation = if landscape {
+                    PageOrientation::Landscape
+                } else {
+                    PageOrientation::Portrait
+                };
+                let settings = PrintSettings::new();
+                settings.set_printer("Print to File");
+                settings.set(gtk::PRINT_SETTINGS_OUTPUT_FILE_FORMAT, Some("pdf"));
+                settings.set(gtk::PRINT_SETTINGS_OUTPUT_URI, Some(&uri));
+                settings.set_orientation(orientation);
+                let page_setup = PageSetup::new();
+                page_setup.set_orientation(orientation);
+
+                let operation = PrintOperation::new(&webview.inner());
+                operation.set_print_settings(&settings);
+                operation.set_page_setup(&page_setup);
+                // Finished follows failed too, whichever is sent first counts
+                let failed = done.clone();
+                operation.connect_failed(move |_, e| {
+                    let _ = failed.send(Err(format!("Failed to print: {}", e)));
+                });
+                operation.connect_finished(move |_| {
+                    let _ = done.send(Ok(()));
+                });
+                operation.print();
+            })
+            .map_err(|e| format!("Failed to print: {}", e))
+    }
+}
+
+/// Print through WebView2's PrintToPdf
+#[cfg(windows)]
+mod platform {
+    use std::path::Path;
+    use tauri::WebviewWindow;
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        ICoreWebView2Environment6, ICoreWebView2_7, COREWEBVIEW2_PRINT_ORIENTATION_LANDSCAPE,
+    };
+    use webview2_com::PrintToPdfCompletedHandler;
+    use windows_core::{Interface, HSTRING};
+
+    use super::Done;
+
+    pub fn print(window: &WebviewWindow, path: &Path, landscape: bool, done: Done) -> Result<(), String> {
+        let path = HSTRING::from(path.as_os_str());
+        window
+            .with_webview(move |webview| {
+                let started = unsafe {
+                    (|| -> windows_core::Result<()> {
+                        let core = webview.controller().CoreWebView2()?.cast::<ICoreWebView2_7>()?;
+                        let settings = if landscape {
+                            let settings = webview
+                                .environment()
+                                .cast::<ICoreWebView2Environment6>()?
+                                .CreatePrintSettings()?;
+                            settings.SetOrientation(COREWEBVIEW2_PRINT_ORIENTATION_LANDSCAPE)?;
+                            Some(settings)
+                        } else {
+                            None
+                        };
+                        let sender = done.clone();
+                        let handler = PrintToPdfCompletedHandler::create(Box::new(move |result, success| {
+                            let _ = sender.send(match result {
+                                Ok(()) if success => Ok(()),
+                                Ok(()) => Err("Failed to print the page".to_string()),
+                                Err(e) => Err(format!("Failed to print: {}", e)),
+                            });
+                            Ok(())
+                        }));
+                        core.PrintToPdf(&path, settings.as_ref(), &handler)
+                    })()
+                };
+                if let Err(e) = started {
+                    let _ = done.send(Err(format!("Failed to print: {}", e)));
+                }
+            })
+            .map_err(|e| format!("Failed to print: {}", e))
+    }
+}
+
+/// Render through WKWebView's createPDF, which doesn't split the page into sheets
+#[cfg(target_os = "macos")]
+mod platform {
+    use block2::RcBlock;
+    use objc2_foundation::{NSData, NSError};
+    use objc2_web_kit::WKWebView;
+    use std::path::{Path, PathBuf};
+    use tauri::WebviewWindow;
+
+    use super::Done;
+
+    pub fn print(window: &WebviewWindow, path: &Path, _landscape: bool, done: Done) -> Result<(), String> {
+        let path: PathBuf = path.to_path_buf();
+        window
+            .with_webview(move |webview| {
+                let view: &WKWebView = unsafe { &*webview.inner().cast() };
+                let completion = RcBlock::new(move |data: *mut NSData, error: *mut NSError| {
+                    let result = match unsafe { data.as_ref() } {
+                        Some(data) => std::fs::write(&path, data.to_vec())
+                            .map_err(|e| format!("Failed to save {}: {}", path.display(), e)),
+                        None => Err(match unsafe { error.as_ref() } {
+                            Some(error) => format!("Failed to print: {}", error.localizedDescription()),
+                            None => "Failed to print the page".to_string(),
+                        }),
+                    };
+                    let _ = done.send(result);
+                });
+                unsafe { view.createPDFWithConfiguration_completionHandler(None, &completion) };
+            })
+            .map_err(|e| format!("Failed to print: {}", e))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+mod platform {
+    use std::path::Path;
+    use tauri::WebviewWindow;
+
+    use super::Done;
+
+    pub fn print(_window: &WebviewWindow, _path: &Path, _landscape: bool, _done: Done) -> Result<(), String> {
+        Err("Exporting to PDF is not supported on this platform".to_string())
+    }
+}
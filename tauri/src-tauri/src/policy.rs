@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::config::{ShellConfig, UpdateChannel};
+
+#[cfg(target_os = "linux")]
+const POLICY_FILE: &str = "/etc/tsw_io/policy.toml";
+#[cfg(target_os = "macos")]
+const MANAGED_PREFERENCES_DIR: &str = "/Library/Managed Preferences";
+#[cfg(target_os = "windows")]
+const POLICY_REGISTRY_KEY: &str = "SOFTWARE\\Policies\\TswIo";
+
+/// Settings enforced by an administrator, which win over the user's config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManagedPolicy {
+    pub update_channel: Option<UpdateChannel>,
+    pub telemetry: Option<bool>,
+    pub offline: Option<bool>,
+    pub backend_url: Option<String>,
+}
+
+impl ManagedPolicy {
+    pub fn is_empty(&self) -> bool {
+        *self == ManagedPolicy::default()
+    }
+
+    /// Override the user's settings with the enforced ones
+    pub fn apply(&self, config: &mut ShellConfig) {
+        if let Some(channel) = self.update_channel {
+            config.update_channel = channel;
+        }
+        if let Some(telemetry) = self.telemetry {
+            config.telemetry = telemetry;
+        }
+        if let Some(offline) = self.offline {
+            config.offline = offline;
+        }
+        if let Some(url) = &self.backend_url {
+            config.backend_url = Some(url.clone());
+            // Profiles can't point anywhere else either
+            for overrides in config.profiles.values_mut() {
+                overrides.backend_url = None;
+            }
+        }
+    }
+}
+
+/// Load the policy for this platform, treating a missing or unreadable policy as none
+pub fn load() -> ManagedPolicy {
+    match load_platform() {
+        Ok(Some(policy)) if !policy.is_empty() => {
+            tracing::info!("Applying administrator-managed settings");
+            policy
+        }
+        Ok(_) => ManagedPolicy::default(),
+        Err(e) => {
+            tracing::error!("Ignoring managed settings: {}", e);
+            ManagedPolicy::default()
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn load_platform() -> Result<Option<ManagedPolicy>, String> {
+    load_toml(Path::new(POLICY_FILE))
+}
+
+#[cfg(target_os = "macos")]
+fn load_platform() -> Result<Option<ManagedPolicy>, String> {
+    let path = Path::new(MANAGED_PREFERENCES_DIR).join(format!("{}.plist", crate::paths::identifier()));
+    if !path.exists() {
+        return Ok(None);
+    }
+    plist::from_file(&path)
+        .map(Some)
+        .map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
+
+#[cfg(target_os = "windows")]
+fn load_platform() -> Result<Option<ManagedPolicy>, String> {
+    use winreg::enums::HKEY_LOCAL_MACHINE;
+    use winreg::RegKey;
+
+    let key = match RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey(POLICY_REGISTRY_KEY) {
+        Ok(key) => key,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to open HKLM\\{}: {}", POLICY_REGISTRY_KEY, e)),
+    };
+
+    let update_channel = match key.get_value::<String, _>("UpdateChannel").ok().as_deref() {
+        Some("stable") => Some(UpdateChannel::Stable),
+        Some("beta") => Some(UpdateChannel::Beta),
+        Some(other) => return Err(format!("Unknown UpdateChannel '{}'", other)),
+        None => None,
+    };
+
+    Ok(Some(ManagedPolicy {
+        update_channel,
+        telemetry: key.get_value::<u32, _>("Telemetry").ok().map(|v| v != 0),
+        offline: key.get_value::<u32, _>("Offline").ok().map(|v| v != 0),
+        backend_url: key.get_value::<String, _>("BackendUrl").ok(),
+    }))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn load_platform() -> Result<Option<ManagedPolicy>, String> {
+    Ok(None)
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn load_toml(path: &Path) -> Result<Option<ManagedPolicy>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    toml::from_str(&contents)
+        .map(Some)
+        .map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
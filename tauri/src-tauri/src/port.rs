@@ -0,0 +1,116 @@
+use std::net::TcpListener;
+use tauri::{AppHandle, Manager};
+
+use crate::config;
+use crate::health;
+use crate::loopback;
+use crate::maintenance;
+use crate::navigation;
+use crate::profile::{self, Profile, ProfileState};
+use crate::proxy;
+use crate::supervisor::{self, SupervisorState};
+use crate::timing;
+
+/// Ports below this need elevated rights on most systems
+const MIN_PORT: u16 = 1024;
+
+/// Whether the bundled backend could listen on `port`
+fn check_available(port: u16) -> Result<(), String> {
+    if port < MIN_PORT {
+        return Err(format!("Pick a port from {} to {}", MIN_PORT, u16::MAX));
+    }
+    TcpListener::bind((loopback::BIND_ADDRESS, port))
+        .map(|_| ())
+        .map_err(|e| format!("Port {} can't be used: {}", port, e))
+}
+
+/// Move the bundled backend to another port and remember it, without the user relaunching
+///
+/// A running backend keeps serving until the one on the new port is ready, then the main
+/// window switches over and the old one is stopped. If the new one doesn't come up,
+/// everything stays as it was.
+#[tauri::command]
+pub async fn change_port(app: AppHandle, port: u16) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || switch(&app, port))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn switch(handle: &AppHandle, port: u16) -> Result<(), String> {
+    let current = handle.state::<ProfileState>().get();
+    if current.backend_url.is_some() {
+        return Err("An external backend's port is set where it runs".to_string());
+    }
+    if port == current.port {
+        return Ok(());
+    }
+    check_available(port)?;
+    let _exclusive = maintenance::exclusive("Changing the port")?;
+
+    let moved = Profile {
+        port,
+        ..current.clone()
+    };
+    match supervisor::state(handle) {
+        SupervisorState::Running => warm_switch(handle, &current, &moved)?,
+        // Nothing to keep serving, as after failing to start on a taken port
+        SupervisorState::Idle => {
+            handle.state::<ProfileState>().0.write().unwrap().port = port;
+            crate::launch(handle).map_err(|e| e.to_string())?;
+        }
+        SupervisorState::Starting | SupervisorState::Restarting | SupervisorState::CrashLoop => {
+            return Err("Wait for the server to finish starting".to_string());
+        }
+    }
+
+    config::update(handle, |config| profile::set_port(config, &moved.name, port))?;
+    tracing::info!(from = current.port, to = port, "Backend port changed");
+    Ok(())
+}
+
+/// Start a second backend on the new port and swap it in once it is ready
+fn warm_switch(handle: &AppHandle, current: &Profile, moved: &Profile) -> Result<(), String> {
+    let standby_url = moved.base_url();
+    let old = crate::detach_backend(handle);
+    tracing::info!(%standby_url, "Starting backend on the new port");
+    timing::start(handle);
+    supervisor::backend_launching(handle);
+
+    let ready = match crate::spawn_sidecar(handle, moved) {
+        Ok(()) => health::wait_for_backend(handle, &standby_url, true, &timing::budget(handle))
+            .map_err(|attempts| format!("The server didn't start on port {} ({} checks)", moved.port, attempts)),
+        Err(e) => Err(format!("Failed to start the server on port {}: {}", moved.port, e)),
+    };
+
+    if let Err(e) = ready {
+        tracing::error!("{}", e);
+        // Keep serving from the old port as if nothing happened
+        if let Some(standby) = crate::detach_backend(handle) {
+            let _ = standby.kill();
+        }
+        proxy::stop(moved.port);
+        if let Some(old) = old {
+            crate::attach_backend(handle, old);
+        }
+        supervisor::backend_launching(handle);
+        supervisor::backend_ready(handle);
+        tracing::info!(port = current.port, "Kept the backend on its old port");
+        return Err(e);
+    }
+
+    handle.state::<ProfileState>().0.write().unwrap().port = moved.port;
+    navigation::set_backend(&standby_url);
+    if let Some(window) = handle.get_webview_window("main") {
+        if let Err(e) = window.navigate(standby_url.parse().unwrap()) {
+            tracing::error!("Failed to load the backend in the main window: {}", e);
+        }
+    }
+    supervisor::backend_ready(handle);
+    if let Some(old) = old {
+        if let Err(e) = old.kill() {
+            tracing::warn!("Failed to stop the backend on the old port: {}", e);
+        }
+    }
+    proxy::stop(current.port);
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Extension of the configuration exports, see `integrations`
+const EXPORT_EXTENSION: &str = "tswx";
+/// Names longer than this are cut short on the thumbnail
+const MAX_TITLE: usize = 40;
+
+/// Give an exported `.tswx` file a thumbnail of what it holds, for Finder and Quick Look
+///
+/// There is no Quick Look extension in the bundle, so the export carries its preview: the
+/// app icon with the configuration's name over it, as the file's custom icon. Other files
+/// and other platforms are left alone.
+pub fn attach(handle: &AppHandle, path: &Path) {
+    let is_export = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(EXPORT_EXTENSION));
+    if !is_export {
+        return;
+    }
+    let title = title(path);
+    platform::attach(handle, path, &title);
+}
+
+/// The configuration's name from an export, which is JSON, or the file name otherwise
+fn title(path: &Path) -> String {
+    let fallback = || {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    let title = std::fs::read(path)
+        .ok()
+        .and_then(|contents| serde_json::from_slice::<serde_json::Value>(&contents).ok())
+        .and_then(|export| {
+            ["name", "title"]
+                .iter()
+                .find_map(|key| export[key].as_str().map(str::to_string))
+        })
+        .filter(|title| !title.trim().is_empty())
+        .unwrap_or_else(fallback);
+    match title.char_indices().nth(MAX_TITLE) {
+        Some((end, _)) => format!("{}…", title[..end].trim_end()),
+        None => title,
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2::rc::{Allocated, Retained};
+    use objc2::runtime::{AnyObject, Bool};
+    use objc2::{class, msg_send};
+    use objc2_foundation::{NSDictionary, NSPoint, NSRect, NSSize, NSString};
+    use std::path::Path;
+    use tauri::AppHandle;
+
+    /// Side of the thumbnail, Finder scales it down for smaller icons
+    const SIZE: f64 = 512.0;
+    const TITLE_FONT_SIZE: f64 = 44.0;
+    const NS_COMPOSITING_OPERATION_SOURCE_OVER: usize = 2;
+    const NS_TEXT_ALIGNMENT_CENTER: isize = 2;
+
+    pub fn attach(handle: &AppHandle, path: &Path, title: &str) {
+        let path = path.to_path_buf();
+        let title = title.to_string();
+        // AppKit draws on the main thread
+        let result = handle.run_on_main_thread(move || {
+            if let Err(e) = set_icon(&path, &title) {
+                tracing::warn!("{}", e);
+            }
+        });
+        if let Err(e) = result {
+            tracing::warn!("Failed to draw the export's preview: {}", e);
+        }
+    }
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> NSRect {
+        NSRect::new(NSPoint::new(x, y), NSSize::new(width, height))
+    }
+
+    /// The app icon with a band carrying `title` across its lower part
+    fn thumbnail(title: &str) -> Retained<AnyObject> {
+        unsafe {
+            let image: Allocated<AnyObject> = msg_send![class!(NSImage), alloc];
+            let image: Retained<AnyObject> = msg_send![image, initWithSize: NSSize::new(SIZE, SIZE)];
+            let _: () = msg_send![&image, lockFocus];
+
+            let application: Retained<AnyObject> = msg_send![class!(NSApplication), sharedApplication];
+            let icon: Option<Retained<AnyObject>> = msg_send![&application, applicationIconImage];
+            if let Some(icon) = icon {
+                let _: () = msg_send![
+                    &icon,
+                    drawInRect: rect(0.0, 0.0, SIZE, SIZE),
+                    fromRect: NSRect::ZERO,
+                    operation: NS_COMPOSITING_OPERATION_SOURCE_OVER,
+                    fraction: 1.0f64
+                ];
+            }
+
+            let band = rect(SIZE * 0.06, SIZE * 0.08, SIZE * 0.88, TITLE_FONT_SIZE * 2.2);
+            let background: Retained<AnyObject> = msg_send![class!(NSColor), colorWithWhite: 0.1f64, alpha: 0.8f64];
+            let _: () = msg_send![&background, setFill];
+            let shape: Retained<AnyObject> = msg_send![
+                class!(NSBezierPath),
+                bezierPathWithRoundedRect: band,
+                xRadius: 24.0f64,
+                yRadius: 24.0f64
+            ];
+            let _: () = msg_send![&shape, fill];
+
+            let font: Retained<AnyObject> = msg_send![class!(NSFont), boldSystemFontOfSize: TITLE_FONT_SIZE];
+            let color: Retained<AnyObject> = msg_send![class!(NSColor), whiteColor];
+            let paragraph: Retained<AnyObject> = msg_send![class!(NSMutableParagraphStyle), new];
+            let _: () = msg_send![&paragraph, setAlignment: NS_TEXT_ALIGNMENT_CENTER];
+            // The values of NSFontAttributeName and the other attribute name constants
+            let keys = ["NSFont", "NSColor", "NSParagraphStyle"].map(NSString::from_str);
+            let keys: Vec<&NSString> = keys.iter().map(|key| &**key).collect();
+            let attributes = NSDictionary::<NSString, AnyObject>::from_retained_objects(&keys, &[font, color, paragraph]);
+            let text_area = rect(
+                band.origin.x + 16.0,
+                band.origin.y + (band.size.height - TITLE_FONT_SIZE * 1.25) / 2.0,
+                band.size.width - 32.0,
+                TITLE_FONT_SIZE * 1.25,
+            );
+            let _: () = msg_send![&*NSString::from_str(title), drawInRect: text_area, withAttributes: &*attributes];
+
+            let _: () = msg_send![&image, unlockFocus];
+            image
+        }
+    }
+
+    fn set_icon(path: &Path, title: &str) -> Result<(), String> {
+        let image = thumbnail(title);
+        let workspace: Retained<AnyObject> = unsafe { msg_send![class!(NSWorkspace), sharedWorkspace] };
+        let file = NSString::from_str(&path.to_string_lossy());
+        let set: Bool = unsafe { msg_send![&workspace, setIcon: &*image, forFile: &*file, options: 0usize] };
+        if !set.as_bool() {
+            return Err(format!("Failed to set the preview of {}", path.display()));
+        }
+        tracing::debug!("Added a preview to {}", path.display());
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    use std::path::Path;
+    use tauri::AppHandle;
+
+    pub fn attach(_handle: &AppHandle, _path: &Path, _title: &str) {}
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::Url;
+
+use crate::config::ShellConfig;
+use crate::paths::AppPaths;
+
+pub const DEFAULT_PROFILE: &str = "prod";
+
+/// Per-profile settings from a `[profiles.<name>]` table, falling back to the base config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileOverrides {
+    pub port: Option<u16>,
+    /// Connect to this backend instead of spawning the bundled one
+    pub backend_url: Option<String>,
+    pub data_dir: Option<PathBuf>,
+}
+
+/// The environment the backend is launched with, selected with `--env`
+#[derive(Debug, Clone, Serialize)]
+pub struct Profile {
+    pub name: String,
+    pub port: u16,
+    /// Externally managed backend; `None` means the bundled sidecar is spawned
+    pub backend_url: Option<String>,
+    pub data_dir: PathBuf,
+    /// Whether the bundled backend serves HTTPS
+    pub tls: bool,
+}
+
+impl Profile {
+    /// URL the shell talks to and loads in the main window
+    pub fn base_url(&self) -> String {
+        self.backend_url
+            .clone()
+            .unwrap_or_else(|| {
+                let scheme = if self.tls { "https" } else { "http" };
+                format!("{}://localhost:{}", scheme, self.port)
+            })
+    }
+}
+
+pub struct ProfileState(pub RwLock<Profile>);
+
+impl ProfileState {
+    pub fn get(&self) -> Profile {
+        self.0.read().unwrap().clone()
+    }
+}
+
+/// Defaults for the profiles that work without any config
+///
+/// They all run the same release build of the backend, on their own port and data.
+fn builtin(name: &str, paths: &AppPaths) -> Option<ProfileOverrides> {
+    let port = match name {
+        DEFAULT_PROFILE => return Some(ProfileOverrides::default()),
+        "staging" => 4100,
+        "dev" => 4200,
+        _ => return None,
+    };
+    Some(ProfileOverrides {
+        port: Some(port),
+        backend_url: None,
+        // Keep other environments from touching the real database
+        data_dir: Some(sibling_data_dir(&paths.default_data_dir, name)),
+    })
+}
+
+fn sibling_data_dir(default: &Path, name: &str) -> PathBuf {
+    let dir_name = default
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    default.with_file_name(format!("{}-{}", dir_name, name))
+}
+
+/// Combine the launch overrides, the config's profile table, the built-in defaults and the base config
+pub fn resolve(config: &ShellConfig, name: &str, paths: &AppPaths) -> Result<Profile, String> {
+    let builtin = builtin(name, paths);
+    let configured = config.profiles.get(name);
+    if builtin.is_none() && configured.is_none() {
+        return Err(format!("Unknown profile '{}'", name));
+    }
+
+    let builtin = builtin.unwrap_or_default();
+    let configured = configured.cloned().unwrap_or_default();
+
+    let backend_url = configured
+        .backend_url
+        .or(builtin.backend_url)
+        .or_else(|| config.backend_url.clone());
+    if let Some(url) = &backend_url {
+        Url::parse(url).map_err(|e| format!("Invalid backend URL '{}': {}", url, e))?;
+    }
+
+    Ok(Profile {
+        name: name.to_string(),
+        port: configured.port.or(builtin.port).unwrap_or(config.port),
+        tls: config.tls && backend_url.is_none(),
+        backend_url,
+        data_dir: paths
+            .data_dir_override
+            .clone()
+            .or(configured.data_dir)
+            .or(builtin.data_dir)
+            .or_else(|| config.data_dir.clone())
+            .unwrap_or_else(|| paths.default_data_dir.clone()),
+    })
+}
+
+/// Move the profile to a new port in the config file
+pub fn set_port(config: &mut ShellConfig, name: &str, port: u16) {
+    if name == DEFAULT_PROFILE {
+        config.port = port;
+    } else {
+        config.profiles.entry(name.to_string()).or_default().port = Some(port);
+    }
+}
+
+/// Point the profile at a new data directory in the config file
+pub fn set_data_dir(config: &mut ShellConfig, name: &str, data_dir: PathBuf) {
+    if name == DEFAULT_PROFILE {
+        config.data_dir = Some(data_dir);
+    } else {
+        config.profiles.entry(name.to_string()).or_default().data_dir = Some(data_dir);
+    }
+}
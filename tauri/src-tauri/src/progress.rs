@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::AppHandle;
+#[cfg(not(any(target_os = "linux", windows)))]
+use tauri_plugin_notification::NotificationExt;
+
+/// Progress at which a job counts as done
+const DONE: u8 = 100;
+/// Action of the button on the completion notification
+#[cfg(any(target_os = "linux", windows))]
+const OPEN_ACTION: &str = "open";
+
+/// Native notification shown for each job, keyed by the id the backend gave it
+#[cfg(target_os = "linux")]
+static SHOWN: Mutex<BTreeMap<String, u32>> = Mutex::new(BTreeMap::new());
+#[cfg(not(target_os = "linux"))]
+static SHOWN: Mutex<BTreeMap<String, ()>> = Mutex::new(BTreeMap::new());
+
+/// Show the progress of a long backend job in a notification, updated on each call
+///
+/// At 100 percent the notification is replaced by one saying the job is done, with a button
+/// to open the app. Where notifications can't show progress, only the start and the end are
+/// notified.
+#[tauri::command]
+pub fn notify_progress(app: AppHandle, id: String, title: String, percent: u8) -> Result<(), String> {
+    let percent = percent.min(DONE);
+    if percent == DONE {
+        let shown = SHOWN.lock().unwrap().remove(&id);
+        tracing::debug!(id, "Job finished");
+        imp::finish(&app, &id, &title, shown)
+    } else {
+        imp::update(&app, &id, &title, percent)
+    }
+}
+
+#[cfg(any(target_os = "linux", windows))]
+fn open_app(handle: &AppHandle, action: &str) {
+    if action == OPEN_ACTION {
+        crate::open_main_window(handle);
+    }
+}
+
+/// Notifications through the desktop's notification server, which replaces them by id
+#[cfg(target_os = "linux")]
+mod imp {
+    use notify_rust::{Hint, Notification};
+    use tauri::AppHandle;
+
+    use super::{open_app, OPEN_ACTION, SHOWN};
+
+    fn notification(title: &str, body: &str, replaces: Option<u32>) -> Notification {
+        let mut notification = Notification::new();
+        notification.summary(title).body(body).auto_icon();
+        if let Some(replaces) = replaces {
+            notification.id(replaces);
+        }
+        notification
+    }
+
+    pub fn update(_app: &AppHandle, id: &str, title: &str, percent: u8) -> Result<(), String> {
+        let mut shown = SHOWN.lock().unwrap();
+        let mut notification = notification(title, &format!("{}%", percent), shown.get(id).copied());
+        // Servers that don't draw a progress bar still show the percentage in the body
+        notification.hint(Hint::CustomInt("value".to_string(), percent.into()));
+        let handle = notification
+            .show()
+            .map_err(|e| format!("Failed to show notification: {}", e))?;
+        shown.insert(id.to_string(), handle.id());
+        Ok(())
+    }
+
+    pub fn finish(app: &AppHandle, _id: &str, title: &str, shown: Option<u32>) -> Result<(), String> {
+        let mut notification = notification(title, "Done", shown);
+        notification.action(OPEN_ACTION, "Open");
+        let app = app.clone();
+        // Waiting for the button blocks until the notification is closed
+        std::thread::spawn(move || match notification.show() {
+            Ok(handle) => handle.wait_for_action(|action| open_app(&app, action)),
+            Err(e) => tracing::warn!("Failed to show notification: {}", e),
+        });
+        Ok(())
+    }
+}
+
+/// Toasts with a progress bar, tagged with the job id so later ones replace them
+#[cfg(windows)]
+mod imp {
+    use tauri::AppHandle;
+    use tauri_winrt_notification::{Progress, Toast};
+
+    use super::{open_app, OPEN_ACTION, SHOWN};
+
+    fn toast(app: &AppHandle) -> Toast {
+        // Only the installed app is registered under its identifier
+        if tauri::is_dev() {
+            Toast::new(Toast::POWERSHELL_APP_ID)
+        } else {
+            Toast::new(&app.config().identifier)
+        }
+    }
+
+    fn progress(id: &str, title: &str, status: &str, percent: u8) -> Progress {
+        Progress {
+            tag: id.to_string(),
+            title: title.to_string(),
+            status: status.to_string(),
+            value: f32::from(percent) / 100.0,
+            value_string: format!("{}%", percent),
+        }
+    }
+
+    pub fn update(app: &AppHandle, id: &str, title: &str, percent: u8) -> Result<(), String> {
+        let mut shown = SHOWN.lock().unwrap();
+        let progress = progress(id, title, "In progress...", percent);
+        let result = if shown.contains_key(id) {
+            toast(app).set_progress(&progress).map(|_| ())
+        } else {
+            toast(app).title(title).progress(&progress).show()
+        };
+        result.map_err(|e| format!("Failed to show notification: {}", e))?;
+        shown.insert(id.to_string(), ());
+        Ok(())
+    }
+
+    pub fn finish(app: &AppHandle, id: &str, title: &str, _shown: Option<()>) -> Result<(), String> {
+        let handle = app.clone();
+        toast(app)
+            .title(title)
+            .progress(&progress(id, title, "Done", super::DONE))
+            .add_button("Open", OPEN_ACTION)
+            .on_activated(move |action| {
+                open_app(&handle, action.as_deref().unwrap_or(OPEN_ACTION));
+                Ok(())
+            })
+            .show()
+            .map_err(|e| format!("Failed to show notification: {}", e))
+    }
+}
+
+/// Plain notifications, which can't be updated or carry buttons
+#[cfg(not(any(target_os = "linux", windows)))]
+mod imp {
+    use tauri::AppHandle;
+
+    use super::{NotificationExt, SHOWN};
+
+    fn show(app: &AppHandle, title: &str, body: &str) -> Result<(), String> {
+        app.notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show()
+            .map_err(|e| format!("Failed to show notification: {}", e))
+    }
+
+    pub fn update(app: &AppHandle, id: &str, title: &str, _percent: u8) -> Result<(), String> {
+        if SHOWN.lock().unwrap().insert(id.to_string(), ()).is_some() {
+            return Ok(());
+        }
+        show(app, title, "Started")
+    }
+
+    pub fn finish(app: &AppHandle, _id: &str, title: &str, _shown: Option<()>) -> Result<(), String> {
+        show(app, title, "Done")
+    }
+}
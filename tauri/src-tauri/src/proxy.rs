@@ -0,0 +1,607 @@
+use bytes::Bytes;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::client;
+use hyper::header::{
+    AsHeaderName, HeaderMap, HeaderValue, AUTHORIZATION, CACHE_CONTROL, CONNECTION, CONTENT_ENCODING,
+    CONTENT_LENGTH, CONTENT_TYPE, COOKIE, UPGRADE,
+};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::upgrade::OnUpgrade;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use std::convert::Infallible;
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpListener as StdTcpListener};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_native_tls::TlsAcceptor;
+
+use crate::assets::{self, AssetCache};
+use crate::loopback;
+use crate::secrets;
+use crate::tls::LocalCertificate;
+
+/// Request heads past this size are refused, nothing the app sends comes close
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+const MAX_HEADERS: usize = 100;
+/// Clients get this long to send a whole request head
+const HEAD_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often the accept loop looks whether it should stop
+const ACCEPT_POLL: Duration = Duration::from_millis(250);
+/// Idempotent requests wait this long for a restarting backend before getting an error
+const RESTART_HOLD: Duration = Duration::from_secs(10);
+/// How often a held request tries the backend again
+const RESTART_POLL: Duration = Duration::from_millis(100);
+/// Requests wait this long for a backend being swapped, as migrating it can take a while
+const QUIESCE_HOLD: Duration = Duration::from_secs(60);
+/// Requests held at once past this get an error right away, as during a long outage
+const MAX_HELD: usize = 64;
+/// Methods that are safe to send once the backend is back, whatever the client did meanwhile
+const IDEMPOTENT: &[&str] = &["GET", "HEAD", "OPTIONS", "PUT", "DELETE"];
+/// How the shell tells whether the backend is up, which must not wait for it
+const HEALTH_PATH: &str = "/api/health";
+
+/// Paths only the shell itself calls, never other machines
+const SHELL_PATHS: &[&str] = &["/api/admin", "/shell"];
+/// Paths other machines reach without a device token, as in the backend's `LanAuth`
+const OPEN_PATHS: &[&str] = &["/api/health", "/api/pairing", "/pairing/session"];
+/// Cookie the backend keeps the session, with a paired device's token, in
+const SESSION_COOKIE: &str = "_tsw_io_key";
+
+/// Sent with every response that doesn't set them itself
+const SECURITY_HEADERS: &[(&str, &str)] = &[
+    ("x-content-type-options", "nosniff"),
+    ("x-frame-options", "SAMEORIGIN"),
+    ("referrer-policy", "strict-origin-when-cross-origin"),
+    ("x-permitted-cross-domain-policies", "none"),
+];
+/// Only meant for one connection, the proxy sets its own
+const HOP_BY_HOP: &[&str] = &["connection", "keep-alive", "proxy-connection", "proxy-authorization", "te", "trailer", "upgrade"];
+/// Only the proxy says who the client is, whatever the client claims
+const FORWARDED: &[&str] = &["forwarded", "x-forwarded-for", "x-forwarded-proto", "x-forwarded-host"];
+/// Left out when fetching an asset to keep, so the backend sends the whole file as is
+const NEGOTIATION: &[&str] = &["accept-encoding", "range", "if-range", "if-none-match", "if-modified-since"];
+
+static PROXIES: Mutex<Vec<Proxy>> = Mutex::new(Vec::new());
+
+type Body = BoxBody<Bytes, hyper::Error>;
+
+struct Proxy {
+    port: u16,
+    bind_address: String,
+    context: Arc<Context>,
+    /// Where a second backend starts while the current one keeps serving, see `standby`
+    standby: Option<u16>,
+    stopping: Arc<AtomicBool>,
+    stopped: mpsc::Receiver<()>,
+}
+
+struct Context {
+    /// Port the backend listens on at `loopback::BIND_ADDRESS`, changed by `promote`
+    backend_port: AtomicU16,
+    /// Swapped when a restarted backend keeps the proxy, as for a renewed certificate
+    acceptor: Mutex<Option<TlsAcceptor>>,
+    assets: Option<AssetCache>,
+    /// Requests waiting for the backend to come back
+    held: AtomicUsize,
+    /// New requests wait while the backend is swapped, see `quiesce`
+    quiesced: AtomicBool,
+}
+
+impl Context {
+    fn backend(&self) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], self.backend_port.load(Ordering::SeqCst)))
+    }
+}
+
+/// Put the shell in front of the bundled backend on `bind_address`:`port`, terminating TLS
+/// with `certificate`
+///
+/// Requests are checked and forwarded to the backend on the returned port, which it must
+/// listen on at `loopback::BIND_ADDRESS` over plain HTTP. The proxy is the one place that
+/// decides who gets through:
+///
+/// - The shell's admin API and event bridge only answer this machine and the shell token.
+/// - Other machines need a device token from pairing or a session, except for the health
+///   check and pairing itself. The backend still checks the token, with the client's real
+///   address in `x-forwarded-for`.
+///
+/// Every response gets the basic security headers the backend didn't set. Connections are
+/// kept alive between requests, until one is upgraded to a WebSocket.
+///
+/// The proxy outlives the backend it was started for. Starting it again on the same address
+/// keeps it, and the backend port, so idempotent requests held while the backend restarted
+/// are sent on to the new one and quick restarts go unnoticed. With `assets_dir`, fingerprinted static
+/// assets are kept there and served without the backend, see `AssetCache`.
+pub fn start(
+    bind_address: &str,
+    port: u16,
+    certificate: Option<&LocalCertificate>,
+    assets_dir: Option<PathBuf>,
+) -> Result<u16, String> {
+    let acceptor = certificate.map(acceptor).transpose()?;
+    let replaced = {
+        let proxies = PROXIES.lock().unwrap();
+        let running = proxies.iter().find(|proxy| proxy.port == port);
+        if let Some(proxy) = running.filter(|proxy| proxy.bind_address == bind_address) {
+            *proxy.context.acceptor.lock().unwrap() = acceptor;
+            let backend_port = proxy
+                .standby
+                .unwrap_or_else(|| proxy.context.backend_port.load(Ordering::SeqCst));
+            tracing::info!(port, backend_port, "Proxy kept for the restarted backend");
+            return Ok(backend_port);
+        }
+        running.map(|proxy| proxy.context.backend_port.load(Ordering::SeqCst))
+    };
+    // Also lets a restarted backend take the port over again right away
+    stop(port);
+
+    let listener = StdTcpListener::bind((bind_address, port))
+        .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+        .map_err(|e| match e.kind() {
+            // Worded for `failure::classify` on every platform
+            io::ErrorKind::AddrInUse => format!("Failed to listen on port {}: address already in use", port),
+            _ => format!("Failed to listen on port {}: {}", port, e),
+        })?;
+    // Requests held by the replaced proxy still find the backend
+    let backend_port = match replaced {
+        Some(backend_port) => backend_port,
+        None => free_port()?,
+    };
+    let context = Arc::new(Context {
+        backend_port: AtomicU16::new(backend_port),
+        acceptor: Mutex::new(acceptor),
+        assets: assets_dir.map(AssetCache::open),
+        held: AtomicUsize::new(0),
+        quiesced: AtomicBool::new(false),
+    });
+
+    let stopping = Arc::new(AtomicBool::new(false));
+    let (done, stopped) = mpsc::channel();
+    tauri::async_runtime::spawn(accept(listener, context.clone(), stopping.clone(), done));
+    PROXIES.lock().unwrap().push(Proxy {
+        port,
+        bind_address: bind_address.to_string(),
+        context,
+        standby: None,
+        stopping,
+        stopped,
+    });
+    tracing::info!(port, backend_port, tls = certificate.is_some(), "Proxy listening in front of the backend");
+    Ok(backend_port)
+}
+
+fn free_port() -> Result<u16, String> {
+    StdTcpListener::bind((loopback::BIND_ADDRESS, 0))
+        .and_then(|free| free.local_addr())
+        .map(|address| address.port())
+        .map_err(|e| format!("Failed to find a port for the backend: {}", e))
+}
+
+/// Pick a port for a second backend to start on while the current one keeps serving
+///
+/// Only for the proxy on `port` that stays on `bind_address`, `None` if there is none. The
+/// next `start` for it hands out the standby port, and `promote` then sends connections
+/// there.
+pub fn standby(port: u16, bind_address: &str) -> Option<u16> {
+    let mut proxies = PROXIES.lock().unwrap();
+    let proxy = proxies
+        .iter_mut()
+        .find(|proxy| proxy.port == port && proxy.bind_address == bind_address)?;
+    let backend_port = free_port().ok()?;
+    proxy.standby = Some(backend_port);
+    Some(backend_port)
+}
+
+/// Port the backend behind the proxy on `port` listens on
+pub fn backend_port(port: u16) -> Option<u16> {
+    let proxies = PROXIES.lock().unwrap();
+    let proxy = proxies.iter().find(|proxy| proxy.port == port)?;
+    Some(proxy.context.backend_port.load(Ordering::SeqCst))
+}
+
+/// Send new connections on `port` to the standby backend, which must be ready
+pub fn promote(port: u16) {
+    let mut proxies = PROXIES.lock().unwrap();
+    if let Some(proxy) = proxies.iter_mut().find(|proxy| proxy.port == port) {
+        if let Some(backend_port) = proxy.standby.take() {
+            proxy.context.backend_port.store(backend_port, Ordering::SeqCst);
+            tracing::info!(port, backend_port, "Proxy switched to the standby backend");
+        }
+    }
+}
+
+/// Hold new requests on `port` instead of sending them to the backend, or stop doing so
+///
+/// Requests already sent run until done. For swapping the backend underneath the pages:
+/// nothing changes its data between the snapshot and the new backend taking over.
+pub fn quiesce(port: u16, quiesced: bool) {
+    let proxies = PROXIES.lock().unwrap();
+    if let Some(proxy) = proxies.iter().find(|proxy| proxy.port == port) {
+        proxy.context.quiesced.store(quiesced, Ordering::SeqCst);
+        tracing::info!(port, quiesced, "Proxy quiesced");
+    }
+}
+
+/// Forget the standby port, as when its backend didn't come up
+pub fn discard_standby(port: u16) {
+    let mut proxies = PROXIES.lock().unwrap();
+    if let Some(proxy) = proxies.iter_mut().find(|proxy| proxy.port == port) {
+        proxy.standby = None;
+    }
+}
+
+/// Stop listening on `port`, connections already open run until either side closes
+pub fn stop(port: u16) {
+    let stopped: Vec<Proxy> = {
+        let mut proxies = PROXIES.lock().unwrap();
+        let (stopped, kept) = proxies.drain(..).partition(|proxy| proxy.port == port);
+        *proxies = kept;
+        stopped
+    };
+    for proxy in stopped {
+        proxy.stopping.store(true, Ordering::SeqCst);
+        // The port is free once the accept loop dropped the listener
+        let _ = proxy.stopped.recv_timeout(ACCEPT_POLL * 4);
+        tracing::info!(port, "Proxy stopped");
+    }
+}
+
+/// Stop every proxy, as when the shell exits
+pub fn stop_all() {
+    let ports: Vec<u16> = PROXIES.lock().unwrap().iter().map(|proxy| proxy.port).collect();
+    for port in ports {
+        stop(port);
+    }
+}
+
+fn acceptor(certificate: &LocalCertificate) -> Result<TlsAcceptor, String> {
+    let read = |path: &std::path::Path| {
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    };
+    let identity = native_tls::Identity::from_pkcs8(&read(&certificate.cert_path)?, &read(&certificate.key_path)?)
+        .map_err(|e| format!("Failed to load the TLS certificate: {}", e))?;
+    native_tls::TlsAcceptor::new(identity)
+        .map(TlsAcceptor::from)
+        .map_err(|e| format!("Failed to set up TLS: {}", e))
+}
+
+async fn accept(listener: StdTcpListener, context: Arc<Context>, stopping: Arc<AtomicBool>, done: mpsc::Sender<()>) {
+    let listener = match TcpListener::from_std(listener) {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to start the proxy: {}", e);
+            let _ = done.send(());
+            return;
+        }
+    };
+    while !stopping.load(Ordering::SeqCst) {
+        let Ok(accepted) = tokio::time::timeout(ACCEPT_POLL, listener.accept()).await else {
+            continue;
+        };
+        match accepted {
+            Ok((stream, peer)) => {
+                let context = context.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = serve(stream, peer, context).await {
+                        tracing::debug!(%peer, "Proxied connection failed: {}", e);
+                    }
+                });
+            }
+            Err(e) => tracing::debug!("Failed to accept a connection: {}", e),
+        }
+    }
+    drop(listener);
+    let _ = done.send(());
+}
+
+async fn serve(stream: TcpStream, peer: SocketAddr, context: Arc<Context>) -> Result<(), String> {
+    let _ = stream.set_nodelay(true);
+    let acceptor = context.acceptor.lock().unwrap().clone();
+    match acceptor {
+        Some(acceptor) => {
+            let stream = acceptor.accept(stream).await.map_err(|e| e.to_string())?;
+            relay(stream, peer, context, "https").await
+        }
+        None => relay(stream, peer, context, "http").await,
+    }
+}
+
+/// Serve the client's requests until it closes the connection or it's upgraded
+async fn relay<S: AsyncRead + AsyncWrite + Unpin + Send + 'static>(
+    client: S,
+    peer: SocketAddr,
+    context: Arc<Context>,
+    scheme: &'static str,
+) -> Result<(), String> {
+    let service = service_fn(move |request| {
+        let context = context.clone();
+        async move { Ok::<_, Infallible>(forward(request, peer, &context, scheme).await) }
+    });
+    http1::Builder::new()
+        .timer(TokioTimer::new())
+        .header_read_timeout(HEAD_TIMEOUT)
+        .max_buf_size(MAX_HEAD_BYTES)
+        .max_headers(MAX_HEADERS)
+        .serve_connection(TokioIo::new(client), service)
+        .with_upgrades()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn header(headers: &HeaderMap, name: impl AsHeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Whether the client asks to switch protocols, as for the LiveView WebSocket
+fn upgrade_requested(headers: &HeaderMap) -> bool {
+    headers.contains_key(UPGRADE)
+        && header(headers, CONNECTION)
+            .is_some_and(|value| value.split(',').any(|token| token.trim().eq_ignore_ascii_case("upgrade")))
+}
+
+/// The path as the backend's router matches it, `None` for `.` and `..` segments
+///
+/// Decoded and without empty segments, so `//api/admin` or `/api/%61dmin` are checked as
+/// what they reach.
+fn normalize(path: &str) -> Option<String> {
+    let decoded = urlencoding::decode_binary(path.as_bytes());
+    let decoded = String::from_utf8_lossy(&decoded);
+    let mut segments = Vec::new();
+    for segment in decoded.split('/').filter(|segment| !segment.is_empty()) {
+        if segment == "." || segment == ".." {
+            return None;
+        }
+        segments.push(segment);
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+fn under(path: &str, prefixes: &[&str]) -> bool {
+    prefixes
+        .iter()
+        .any(|prefix| path == *prefix || path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/')))
+}
+
+fn local(ip: IpAddr) -> bool {
+    ip.to_canonical().is_loopback()
+}
+
+/// Status and error code to turn the request for the normalized `path` away with, `None`
+/// to forward it
+fn refusal(headers: &HeaderMap, path: &str, peer: SocketAddr) -> Option<(StatusCode, &'static str)> {
+    let bearer = header(headers, AUTHORIZATION).and_then(|value| value.strip_prefix("Bearer "));
+    if under(path, SHELL_PATHS) {
+        if !local(peer.ip()) {
+            return Some((StatusCode::FORBIDDEN, "forbidden"));
+        }
+        // The event bridge checks its token when the socket connects
+        let shell = bearer.is_some_and(|token| secrets::constant_time_eq(token, secrets::shell_token()));
+        if under(path, &["/api/admin"]) && !shell {
+            return Some((StatusCode::UNAUTHORIZED, "unauthorized"));
+        }
+        return None;
+    }
+    if local(peer.ip()) || under(path, OPEN_PATHS) {
+        return None;
+    }
+    let session = headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .any(|cookie| cookie.trim().starts_with(&format!("{}=", SESSION_COOKIE)));
+    if bearer.is_none() && !session {
+        return Some((StatusCode::UNAUTHORIZED, "not_paired"));
+    }
+    None
+}
+
+fn full(body: impl Into<Bytes>) -> Body {
+    Full::new(body.into()).map_err(|never| match never {}).boxed()
+}
+
+fn reply(status: StatusCode, error: &str) -> Response<Body> {
+    let mut response = Response::new(full(format!("{{\"error\":\"{}\"}}", error)));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+async fn forward(mut request: Request<Incoming>, peer: SocketAddr, context: &Context, scheme: &'static str) -> Response<Body> {
+    let Some(path) = normalize(request.uri().path()) else {
+        return reply(StatusCode::BAD_REQUEST, "bad_request");
+    };
+    if let Some((status, error)) = refusal(request.headers(), &path, peer) {
+        tracing::debug!(%peer, path, status = status.as_u16(), "Proxy refused a request");
+        return reply(status, error);
+    }
+
+    // Fingerprinted assets never change, so a kept copy is good even while the backend restarts
+    let asset = context
+        .assets
+        .as_ref()
+        .filter(|_| request.method() == Method::GET)
+        .and_then(|cache| Some((cache, cache.key(&path)?)));
+    if let Some((cache, key)) = &asset {
+        if let Some((content_type, body)) = cache.get(key).await {
+            return serve_asset(&content_type, body);
+        }
+        for name in NEGOTIATION {
+            request.headers_mut().remove(*name);
+        }
+    }
+
+    let upgrade = upgrade_requested(request.headers());
+    let upgrade_to = request.headers().get(UPGRADE).cloned();
+    let headers = request.headers_mut();
+    for name in HOP_BY_HOP.iter().chain(FORWARDED) {
+        headers.remove(*name);
+    }
+    if let Ok(address) = HeaderValue::from_str(&peer.ip().to_canonical().to_string()) {
+        headers.insert("x-forwarded-for", address);
+    }
+    headers.insert("x-forwarded-proto", HeaderValue::from_static(scheme));
+    let client_upgrade = match upgrade_to.filter(|_| upgrade) {
+        Some(protocol) => {
+            let headers = request.headers_mut();
+            headers.insert(CONNECTION, HeaderValue::from_static("upgrade"));
+            headers.insert(UPGRADE, protocol);
+            Some(hyper::upgrade::on(&mut request))
+        }
+        None => None,
+    };
+
+    let health = path == HEALTH_PATH;
+    if !health && !resumed(context).await {
+        return reply(StatusCode::SERVICE_UNAVAILABLE, "backend_upgrading");
+    }
+    let hold = IDEMPOTENT.contains(&request.method().as_str()) && !health;
+    let Some(backend) = connect(context, hold).await else {
+        // Still starting or restarting, the shell's health checks tell this from a backend error
+        return reply(StatusCode::BAD_GATEWAY, "backend_unavailable");
+    };
+    let _ = backend.set_nodelay(true);
+    let (mut sender, connection) = match client::conn::http1::handshake(TokioIo::new(backend)).await {
+        Ok(handshake) => handshake,
+        Err(e) => {
+            tracing::debug!("Failed to reach the backend: {}", e);
+            return reply(StatusCode::BAD_GATEWAY, "backend_unavailable");
+        }
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = connection.with_upgrades().await {
+            tracing::debug!("Backend connection failed: {}", e);
+        }
+    });
+    let mut response = match sender.send_request(request).await {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::debug!("The backend didn't answer: {}", e);
+            return reply(StatusCode::BAD_GATEWAY, "bad_response");
+        }
+    };
+
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+        if let Some(client_upgrade) = client_upgrade {
+            let backend_upgrade = hyper::upgrade::on(&mut response);
+            tauri::async_runtime::spawn(tunnel(client_upgrade, backend_upgrade));
+        }
+    } else {
+        for name in HOP_BY_HOP {
+            response.headers_mut().remove(*name);
+        }
+    }
+    for (name, value) in SECURITY_HEADERS {
+        if !response.headers().contains_key(*name) {
+            response.headers_mut().insert(*name, HeaderValue::from_static(value));
+        }
+    }
+
+    // A whole, plain 200 for an asset is read first and kept in the cache
+    let length = header(response.headers(), CONTENT_LENGTH)
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|length| *length <= assets::MAX_ASSET_BYTES);
+    if let (Some((cache, key)), StatusCode::OK, Some(_), false) = (
+        &asset,
+        response.status(),
+        length,
+        response.headers().contains_key(CONTENT_ENCODING),
+    ) {
+        let (parts, body) = response.into_parts();
+        let body = match body.collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(e) => {
+                tracing::debug!("Failed to read an asset from the backend: {}", e);
+                return reply(StatusCode::BAD_GATEWAY, "bad_response");
+            }
+        };
+        let content_type = header(&parts.headers, CONTENT_TYPE).unwrap_or("application/octet-stream");
+        cache.put(key, content_type, &body).await;
+        return Response::from_parts(parts, full(body));
+    }
+    response.map(BodyExt::boxed)
+}
+
+/// Pass the WebSocket frames both ways until either side closes
+async fn tunnel(client: OnUpgrade, backend: OnUpgrade) {
+    let upgraded = async { Ok::<_, hyper::Error>((backend.await?, client.await?)) };
+    match upgraded.await {
+        Ok((backend, client)) => {
+            let _ = tokio::io::copy_bidirectional(&mut TokioIo::new(client), &mut TokioIo::new(backend)).await;
+        }
+        Err(e) => tracing::debug!("Failed to upgrade a proxied connection: {}", e),
+    }
+}
+
+/// Wait up to `QUIESCE_HOLD` while the proxy is quiesced, `false` if it still is
+///
+/// Any method waits, as nothing of the request reached the backend yet.
+async fn resumed(context: &Context) -> bool {
+    if !context.quiesced.load(Ordering::SeqCst) {
+        return true;
+    }
+    if context.held.fetch_add(1, Ordering::SeqCst) >= MAX_HELD {
+        context.held.fetch_sub(1, Ordering::SeqCst);
+        return false;
+    }
+    let deadline = Instant::now() + QUIESCE_HOLD;
+    while context.quiesced.load(Ordering::SeqCst) && Instant::now() < deadline {
+        tokio::time::sleep(RESTART_POLL).await;
+    }
+    context.held.fetch_sub(1, Ordering::SeqCst);
+    !context.quiesced.load(Ordering::SeqCst)
+}
+
+/// Connect to the backend, with `hold` waiting up to `RESTART_HOLD` for it to come back
+///
+/// Nothing of the request reached the backend until then, so it is sent as if the backend
+/// had been there all along.
+async fn connect(context: &Context, hold: bool) -> Option<TcpStream> {
+    if let Ok(backend) = TcpStream::connect(context.backend()).await {
+        return Some(backend);
+    }
+    if !hold {
+        return None;
+    }
+    if context.held.fetch_add(1, Ordering::SeqCst) >= MAX_HELD {
+        context.held.fetch_sub(1, Ordering::SeqCst);
+        return None;
+    }
+    let deadline = Instant::now() + RESTART_HOLD;
+    let backend = loop {
+        tokio::time::sleep(RESTART_POLL).await;
+        if let Ok(backend) = TcpStream::connect(context.backend()).await {
+            break Some(backend);
+        }
+        if Instant::now() >= deadline {
+            break None;
+        }
+    };
+    context.held.fetch_sub(1, Ordering::SeqCst);
+    tracing::debug!(replayed = backend.is_some(), "Held a request while the backend was down");
+    backend
+}
+
+/// Answer with a kept asset, cached by the webview for good as its name changes with it
+fn serve_asset(content_type: &str, body: Vec<u8>) -> Response<Body> {
+    let mut response = Response::new(full(body));
+    let headers = response.headers_mut();
+    if let Ok(content_type) = HeaderValue::from_str(content_type) {
+        headers.insert(CONTENT_TYPE, content_type);
+    }
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static("public, max-age=31536000, immutable"));
+    for (name, value) in SECURITY_HEADERS {
+        headers.insert(*name, HeaderValue::from_static(value));
+    }
+    response
+}
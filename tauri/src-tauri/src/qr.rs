@@ -0,0 +1,23 @@
+use qrcode::{Color, EcLevel, QrCode};
+
+/// Blank modules around the code, scanners need them to find its edges
+const QUIET_ZONE: usize = 4;
+
+/// `text` as a QR code in an SVG image, black on white
+pub fn svg(text: &str) -> Result<String, String> {
+    let code = QrCode::with_error_correction_level(text.as_bytes(), EcLevel::M)
+        .map_err(|e| format!("Failed to make a QR code: {}", e))?;
+    let size = code.width();
+    let mut path = String::new();
+    for (index, color) in code.to_colors().into_iter().enumerate() {
+        if color == Color::Dark {
+            let (row, col) = (index / size, index % size);
+            path.push_str(&format!("M{} {}h1v1h-1z", col + QUIET_ZONE, row + QUIET_ZONE));
+        }
+    }
+    let side = size + 2 * QUIET_ZONE;
+    Ok(format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {side} {side}\" shape-rendering=\"crispEdges\">\
+         <rect width=\"{side}\" height=\"{side}\" fill=\"#fff\"/><path d=\"{path}\" fill=\"#000\"/></svg>"
+    ))
+}
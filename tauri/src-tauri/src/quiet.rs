@@ -0,0 +1,135 @@
+use chrono::{Datelike, Local, NaiveTime, Weekday};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::config::{ConfigState, NotificationConfig};
+
+/// How often the end of the quiet hours is looked for
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Titles listed in the summary, the rest are counted
+const SUMMARY_TITLES: usize = 3;
+
+/// Titles of the notifications held back, oldest first
+static HELD: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Hold a notification back if it's quiet hours, showing it is up to the caller otherwise
+///
+/// Held notifications are summed up in one once the quiet hours end, their buttons are lost.
+pub fn hold(handle: &AppHandle, title: &str) -> bool {
+    if !active(&handle.state::<ConfigState>().get().notifications) {
+        return false;
+    }
+    tracing::debug!(title, "Held back a notification during quiet hours");
+    HELD.lock().unwrap().push(title.to_string());
+    true
+}
+
+/// Whether notifications are held back right now
+fn active(config: &NotificationConfig) -> bool {
+    scheduled(config, Local::now().naive_local()) || (config.follow_os_focus && platform::focus_on())
+}
+
+fn scheduled(config: &NotificationConfig, now: chrono::NaiveDateTime) -> bool {
+    if config.quiet_weekends && matches!(now.weekday(), Weekday::Sat | Weekday::Sun) {
+        return true;
+    }
+    let (Some(from), Some(until)) = (&config.quiet_from, &config.quiet_until) else {
+        return false;
+    };
+    let (Some(from), Some(until)) = (parse_time(from), parse_time(until)) else {
+        return false;
+    };
+    let time = now.time();
+    if from <= until {
+        from <= time && time < until
+    } else {
+        // Spans midnight, like 20:00 to 08:00
+        time >= from || time < until
+    }
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    let time = NaiveTime::parse_from_str(value.trim(), "%H:%M").ok();
+    if time.is_none() {
+        tracing::warn!("Ignored quiet hours time {:?}, it should look like 20:00", value);
+    }
+    time
+}
+
+/// Wait for the quiet hours to end and show what was held back meanwhile
+pub fn start(handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(CHECK_INTERVAL);
+        if HELD.lock().unwrap().is_empty() || active(&handle.state::<ConfigState>().get().notifications) {
+            continue;
+        }
+        let held = std::mem::take(&mut *HELD.lock().unwrap());
+        let title = match held.len() {
+            1 => "1 notification during quiet hours".to_string(),
+            count => format!("{} notifications during quiet hours", count),
+        };
+        let mut body = held.iter().take(SUMMARY_TITLES).cloned().collect::<Vec<_>>().join("\n");
+        if held.len() > SUMMARY_TITLES {
+            body.push_str(&format!("\nand {} more", held.len() - SUMMARY_TITLES));
+        }
+        if let Err(e) = handle.notification().builder().title(title).body(body).show() {
+            tracing::warn!("Failed to show the quiet hours summary: {}", e);
+        }
+    });
+}
+
+/// Focus keeps its state in a file, the app may not be allowed to read it
+#[cfg(target_os = "macos")]
+mod platform {
+    pub fn focus_on() -> bool {
+        let Some(home) = std::env::var_os("HOME") else {
+            return false;
+        };
+        let path = std::path::Path::new(&home).join("Library/DoNotDisturb/DB/Assertions.json");
+        let Some(assertions) = std::fs::read(path)
+            .ok()
+            .and_then(|contents| serde_json::from_slice::<serde_json::Value>(&contents).ok())
+        else {
+            return false;
+        };
+        // A Focus that is on leaves an assertion record behind
+        assertions["data"].as_array().is_some_and(|data| {
+            data.iter()
+                .any(|entry| entry["storeAssertionRecords"].as_array().is_some_and(|records| !records.is_empty()))
+        })
+    }
+}
+
+/// Focus assist, presentations, full screen games and the locked screen all keep quiet
+#[cfg(windows)]
+mod platform {
+    use windows::Win32::UI::Shell::{SHQueryUserNotificationState, QUNS_ACCEPTS_NOTIFICATIONS};
+
+    pub fn focus_on() -> bool {
+        unsafe { SHQueryUserNotificationState() }.is_ok_and(|state| state != QUNS_ACCEPTS_NOTIFICATIONS)
+    }
+}
+
+/// GNOME's do-not-disturb, other desktops don't share theirs
+#[cfg(target_os = "linux")]
+mod platform {
+    use gtk::gio;
+    use gtk::prelude::*;
+
+    const SCHEMA: &str = "org.gnome.desktop.notifications";
+
+    pub fn focus_on() -> bool {
+        // Creating settings for a schema that isn't installed aborts
+        let installed = gio::SettingsSchemaSource::default().is_some_and(|source| source.lookup(SCHEMA, true).is_some());
+        installed && !gio::Settings::new(SCHEMA).boolean("show-banners")
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+mod platform {
+    pub fn focus_on() -> bool {
+        false
+    }
+}
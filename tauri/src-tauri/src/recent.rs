@@ -0,0 +1,189 @@
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::cli::LINK_SCHEME;
+
+/// Extension of the files macOS keeps recent pages as, declared in `Info.plist`
+const LINK_EXTENSION: &str = "tswlink";
+
+/// `tsw-io://` link to a backend route like `/trains/1`
+fn link(route: &str) -> String {
+    format!("{}://{}", LINK_SCHEME, route.trim_start_matches('/'))
+}
+
+/// Put a file the app imported or exported in the OS's recent documents
+pub fn add_file(handle: &AppHandle, path: &Path) {
+    let path = path.to_path_buf();
+    on_main_thread(handle, move |handle| platform::add_file(handle, &path));
+}
+
+/// Put a backend page in the OS's recent documents, opening it goes through its `tsw-io://` link
+pub fn add_workspace(handle: &AppHandle, route: &str, title: &str) {
+    let (route, title) = (route.to_string(), title.to_string());
+    on_main_thread(handle, move |handle| platform::add_workspace(handle, &route, &title));
+}
+
+/// AppKit and GTK keep their lists on the main thread, and Windows wants COM set up
+fn on_main_thread(handle: &AppHandle, add: impl FnOnce(&AppHandle) -> Result<(), String> + Send + 'static) {
+    let main = handle.clone();
+    let result = handle.run_on_main_thread(move || {
+        if let Err(e) = add(&main) {
+            tracing::warn!("{}", e);
+        }
+    });
+    if let Err(e) = result {
+        tracing::warn!("Failed to add to the recent documents: {}", e);
+    }
+}
+
+/// The route of a link written for the recent documents, `None` for any other file
+pub fn link_route(path: &Path) -> Option<String> {
+    let is_link = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(LINK_EXTENSION));
+    if !is_link {
+        return None;
+    }
+    let contents = std::fs::read_to_string(path).ok()?;
+    let rest = contents.trim().strip_prefix(LINK_SCHEME)?.strip_prefix("://")?;
+    Some(format!("/{}", rest.trim_start_matches('/')))
+}
+
+/// Where the link to `route` is written, named after the page so the list reads well
+#[cfg(target_os = "macos")]
+fn link_path(dir: &Path, title: &str) -> std::path::PathBuf {
+    let name: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || " -_.()".contains(c) { c } else { '_' })
+        .collect();
+    let name = name.trim().trim_start_matches('.');
+    let name = if name.is_empty() { "tsw_io" } else { name };
+    dir.join(format!("{}.{}", name, LINK_EXTENSION))
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSString;
+    use std::path::Path;
+    use tauri::{AppHandle, Manager};
+
+    /// Opening an entry comes back as a file to open, see `automation::opened`
+    pub fn add_file(_handle: &AppHandle, path: &Path) -> Result<(), String> {
+        let path = NSString::from_str(&path.to_string_lossy());
+        unsafe {
+            let url: Retained<AnyObject> = msg_send![class!(NSURL), fileURLWithPath: &*path];
+            let controller: Retained<AnyObject> = msg_send![class!(NSDocumentController), sharedDocumentController];
+            let _: () = msg_send![&controller, noteNewRecentDocumentURL: &*url];
+        }
+        Ok(())
+    }
+
+    /// The list only takes files, so the page goes in as a link file holding its `tsw-io://` link
+    pub fn add_workspace(handle: &AppHandle, route: &str, title: &str) -> Result<(), String> {
+        let dir = handle
+            .path()
+            .app_local_data_dir()
+            .map_err(|e| format!("Failed to find the app data directory: {}", e))?
+            .join("recent");
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let path = super::link_path(&dir, title);
+        std::fs::write(&path, format!("{}\n", super::link(route)))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        add_file(handle, &path)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::ffi::c_void;
+    use std::path::Path;
+    use tauri::AppHandle;
+    use windows::core::{Interface, HSTRING};
+    use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+    use windows::Win32::System::Com::StructuredStorage::PROPVARIANT;
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+    use windows::Win32::UI::Shell::{IShellLinkW, SHAddToRecentDocs, ShellLink, SHARD_LINK, SHARD_PATHW};
+
+    pub fn add_file(_handle: &AppHandle, path: &Path) -> Result<(), String> {
+        let path = HSTRING::from(path.as_os_str());
+        unsafe { SHAddToRecentDocs(SHARD_PATHW.0 as u32, Some(path.as_ptr() as *const c_void)) };
+        Ok(())
+    }
+
+    /// A shortcut starting the app with the page's `tsw-io://` link, which `cli` picks up
+    pub fn add_workspace(_handle: &AppHandle, route: &str, title: &str) -> Result<(), String> {
+        let exe = std::env::current_exe()
+            .map_err(|e| format!("Failed to find the app executable: {}", e))?;
+        let failed = |e: windows::core::Error| format!("Failed to add {} to the recent documents: {}", title, e);
+        unsafe {
+            let shortcut: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER).map_err(failed)?;
+            shortcut.SetPath(&HSTRING::from(exe.as_os_str())).map_err(failed)?;
+            shortcut.SetArguments(&HSTRING::from(super::link(route))).map_err(failed)?;
+            shortcut.SetDescription(&HSTRING::from(title)).map_err(failed)?;
+            // What the list shows, it would be the executable's name otherwise
+            let properties: IPropertyStore = shortcut.cast().map_err(failed)?;
+            properties.SetValue(&PKEY_Title, &PROPVARIANT::from(title)).map_err(failed)?;
+            properties.Commit().map_err(failed)?;
+            SHAddToRecentDocs(SHARD_LINK.0 as u32, Some(shortcut.as_raw() as *const c_void));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use gtk::prelude::*;
+    use std::path::Path;
+    use tauri::{AppHandle, Url};
+
+    fn manager() -> Result<gtk::RecentManager, String> {
+        gtk::RecentManager::default().ok_or_else(|| "No recent documents list to add to".to_string())
+    }
+
+    pub fn add_file(_handle: &AppHandle, path: &Path) -> Result<(), String> {
+        let uri = Url::from_file_path(path)
+            .map_err(|_| format!("{} is not an absolute path", path.display()))?;
+        if !manager()?.add_item(uri.as_str()) {
+            return Err(format!("Failed to add {} to the recent documents", path.display()));
+        }
+        Ok(())
+    }
+
+    /// Recorded as the `tsw-io://` link itself, with the app to start it with
+    pub fn add_workspace(_handle: &AppHandle, route: &str, title: &str) -> Result<(), String> {
+        let exe = std::env::current_exe()
+            .map_err(|e| format!("Failed to find the app executable: {}", e))?;
+        let data = gtk::RecentData {
+            display_name: Some(title.to_string()),
+            description: None,
+            mime_type: format!("x-scheme-handler/{}", super::LINK_SCHEME),
+            app_name: "tsw_io".to_string(),
+            app_exec: format!("\"{}\" %u", exe.display()),
+            groups: Vec::new(),
+            is_private: false,
+        };
+        if !manager()?.add_full(&super::link(route), &data) {
+            return Err(format!("Failed to add {} to the recent documents", title));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+mod platform {
+    use std::path::Path;
+    use tauri::AppHandle;
+
+    pub fn add_file(_handle: &AppHandle, _path: &Path) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn add_workspace(_handle: &AppHandle, _route: &str, _title: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
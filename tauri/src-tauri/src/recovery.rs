@@ -0,0 +1,104 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::audit::{self, AuditAction};
+use crate::passphrase::{self, DatabaseFile};
+use crate::profile::ProfileState;
+use crate::{supervisor, wizard};
+
+const WINDOW_LABEL: &str = "recovery";
+
+/// Whether the window shows for a damaged database rather than a crash loop
+static DAMAGED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the database in `data_dir` is there but neither a SQLite nor an encrypted database
+pub fn database_damaged(data_dir: &Path) -> bool {
+    passphrase::inspect(data_dir) == DatabaseFile::Damaged
+}
+
+/// Offer ways out of a damaged database, the same as out of a crash loop
+pub fn show_damaged(handle: &AppHandle) {
+    DAMAGED.store(true, Ordering::SeqCst);
+    show(handle);
+}
+
+/// Offer ways out of a crash loop
+pub fn show(handle: &AppHandle) {
+    if crate::headless::enabled() {
+        crate::headless::fail(handle, None, "The server keeps crashing");
+        return;
+    }
+    if handle.get_webview_window(WINDOW_LABEL).is_some() {
+        return;
+    }
+    let result = WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("recovery.html".into()))
+        .title("TSW IO")
+        .inner_size(520.0, 420.0)
+        .resizable(false)
+        .center()
+        .build();
+    if let Err(e) = result {
+        crate::crash::report(&format!("The server keeps crashing: {}", e));
+        crate::logging::flush();
+        std::process::exit(1);
+    }
+}
+
+/// Why the recovery window is showing, `crash_loop` or `damaged_database`
+#[tauri::command]
+pub fn get_recovery_reason() -> &'static str {
+    if DAMAGED.load(Ordering::SeqCst) {
+        "damaged_database"
+    } else {
+        "crash_loop"
+    }
+}
+
+fn relaunch(app: &AppHandle, safe_mode: bool) -> Result<(), String> {
+    DAMAGED.store(false, Ordering::SeqCst);
+    let detail = if safe_mode {
+        "Restarted in safe mode"
+    } else {
+        "Restarted after a crash loop"
+    };
+    audit::record(app, AuditAction::BackendRestart, detail);
+    supervisor::set_safe_mode(app, safe_mode);
+    crate::stop_backend(app);
+    crate::launch(app).map_err(|e| e.to_string())?;
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+    Ok(())
+}
+
+/// Start without migrations and with a read-only database
+#[tauri::command]
+pub async fn start_safe_mode(app: AppHandle) -> Result<(), String> {
+    tracing::warn!("Starting backend in safe mode");
+    relaunch(&app, true)
+}
+
+/// Replace the database with a user-picked backup and start normally
+///
+/// Returns `false` when the user cancelled the file picker.
+#[tauri::command]
+pub async fn restore_and_restart(app: AppHandle) -> Result<bool, String> {
+    let Some(backup) = app
+        .dialog()
+        .file()
+        .set_title("Choose a backup to restore")
+        .add_filter("TSW IO database", &["db"])
+        .blocking_pick_file()
+        .and_then(|path| path.into_path().ok())
+    else {
+        return Ok(false);
+    };
+
+    crate::stop_backend(&app);
+    let data_dir = app.state::<ProfileState>().get().data_dir;
+    wizard::restore_backup(&app, &backup, &data_dir)?;
+    relaunch(&app, false)?;
+    Ok(true)
+}
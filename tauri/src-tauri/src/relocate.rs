@@ -0,0 +1,213 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, WebviewWindow};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::audit::{self, AuditAction};
+use crate::config::{self, ConfigState};
+use crate::maintenance;
+use crate::profile::{self, ProfileState};
+use crate::service;
+use crate::splash;
+use crate::wizard::{DATABASE_FILE_NAME, DATABASE_SIDE_FILES};
+
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+/// Folders the backend keeps next to the database, see `TswIo.Uploads` and `TswIo.SyncedFolders`
+const BACKEND_DIRS: &[&str] = &["uploads", "synced"];
+
+/// Move the backend data to a folder chosen by the user and relaunch the backend there
+#[tauri::command]
+pub async fn relocate_data_dir(app: AppHandle) -> Result<Option<PathBuf>, String> {
+    if app.state::<ProfileState>().get().backend_url.is_some() {
+        return Err("The data belongs to an external server, move it there".to_string());
+    }
+    if service::in_use() {
+        return Err("The background service keeps the data open, stop it before moving the data".to_string());
+    }
+    // The encrypted volume stays where it is, the files would be moved out of it unencrypted
+    if app.state::<ConfigState>().get().encrypt_data_dir {
+        return Err("Turn off data encryption before moving the data".to_string());
+    }
+    let target = match app
+        .dialog()
+        .file()
+        .set_title("Choose a new location for TSW IO data")
+        .blocking_pick_folder()
+        .and_then(|path| path.into_path().ok())
+    {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let profile = app.state::<ProfileState>().get();
+    let source = profile.data_dir.clone();
+    validate_target(&source, &target)?;
+    let _exclusive = maintenance::exclusive("Moving the data")?;
+
+    // Bring up the progress window before closing the main one, so the app stays open
+    let splash_window = splash::open(&app).map_err(|e| e.to_string())?;
+    if let Some(main_window) = app.get_webview_window("main") {
+        let _ = main_window.close();
+    }
+    splash::set_status(&splash_window, "Stopping server...");
+    // The database can't be copied while the backend still writes to it
+    if let Err(e) = crate::stop_backend_and_wait(&app) {
+        crate::launch(&app).map_err(|e| e.to_string())?;
+        return Err(format!("Failed to stop the server: {}", e));
+    }
+
+    let result = move_data(&source, &target, &splash_window);
+    if result.is_ok() {
+        config::update(&app, |config| {
+            profile::set_data_dir(config, &profile.name, target.clone())
+        })?;
+        app.state::<ProfileState>().0.write().unwrap().data_dir = target.clone();
+        tracing::info!("Moved data from {} to {}", source.display(), target.display());
+        audit::record(
+            &app,
+            AuditAction::DataDirMove,
+            format!("Moved data from {} to {}", source.display(), target.display()),
+        );
+    }
+
+    // Relaunch either way: at the new location, or at the old one if the move failed
+    crate::launch(&app).map_err(|e| e.to_string())?;
+    result.map(|_| Some(target))
+}
+
+/// What the backend keeps in its data directory, the only things moved
+///
+/// The data directory may hold other files too, the default one did hold the shell's config.
+fn backend_entries() -> Vec<String> {
+    std::iter::once(DATABASE_FILE_NAME.to_string())
+        .chain(DATABASE_SIDE_FILES.iter().map(|suffix| format!("{}{}", DATABASE_FILE_NAME, suffix)))
+        .chain(BACKEND_DIRS.iter().map(|dir| dir.to_string()))
+        .collect()
+}
+
+/// `path` with `..` and links resolved, as far as it exists already
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn validate_target(source: &Path, target: &Path) -> Result<(), String> {
+    let (source, target) = (&canonical(source), &canonical(target));
+    if target == source {
+        return Err("The data is already stored in that folder".to_string());
+    }
+    if target.starts_with(source) {
+        return Err("The new location can't be inside the current data folder".to_string());
+    }
+    let is_empty = match std::fs::read_dir(target) {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(_) => true,
+    };
+    if !is_empty {
+        return Err(format!("{} is not empty", target.display()));
+    }
+    Ok(())
+}
+
+/// Copy the backend's files to `target`, then remove the originals
+fn move_data(source: &Path, target: &Path, splash_window: &WebviewWindow) -> Result<(), String> {
+    let entries = backend_entries();
+    let mut files = Vec::new();
+    for entry in &entries {
+        let path = source.join(entry);
+        if path.is_dir() {
+            collect_files(source, &path, &mut files)?;
+        } else if let Ok(metadata) = std::fs::metadata(&path) {
+            files.push((PathBuf::from(entry), metadata.len()));
+        }
+    }
+    let total_bytes: u64 = files.iter().map(|(_, size)| size).sum();
+
+    let mut copied_bytes = 0;
+    for (relative, _) in &files {
+        let from = source.join(relative);
+        let to = target.join(relative);
+        let copy_result = copy_file(&from, &to, |bytes| {
+            copied_bytes += bytes;
+            let percent = (copied_bytes * 100).checked_div(total_bytes).unwrap_or(100);
+            splash::set_status(splash_window, &format!("Moving data... {}%", percent));
+        });
+
+        if let Err(e) = copy_result {
+            // Leave the original data untouched and drop the partial copy
+            remove_entries(target, &entries);
+            return Err(e);
+        }
+    }
+
+    remove_entries(source, &entries);
+    // Only once nothing else is left in it
+    let _ = std::fs::remove_dir(source);
+    Ok(())
+}
+
+fn remove_entries(dir: &Path, entries: &[String]) {
+    for entry in entries {
+        let path = dir.join(entry);
+        let result = match std::fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(&path),
+            Ok(_) => std::fs::remove_file(&path),
+            Err(_) => continue,
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<(PathBuf, u64)>) -> Result<(), String> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        // Nothing stored yet
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+    };
+
+    for entry in entries {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+        let metadata = entry.metadata().map_err(|e| e.to_string())?;
+        if metadata.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root).unwrap().to_path_buf();
+            files.push((relative, metadata.len()));
+        }
+    }
+    Ok(())
+}
+
+fn copy_file(from: &Path, to: &Path, mut on_progress: impl FnMut(u64)) -> Result<(), String> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    let mut reader =
+        File::open(from).map_err(|e| format!("Failed to open {}: {}", from.display(), e))?;
+    let mut writer =
+        File::create(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        writer
+            .write_all(&buffer[..read])
+            .map_err(|e| format!("Failed to write {}: {}", to.display(), e))?;
+        on_progress(read as u64);
+    }
+
+    writer
+        .sync_all()
+        .map_err(|e| format!("Failed to write {}: {}", to.display(), e))
+}
@@ -0,0 +1,181 @@
+use serde::Serialize;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::config::ConfigState;
+use crate::downloads::{self, DownloadKind};
+use crate::{crash, diagnostics, lock, monitor};
+
+const WINDOW_LABEL: &str = "report";
+/// Endpoint release builds submit to when the config doesn't name one
+const BUILT_IN_REPORT_URL: Option<&str> = option_env!("TSW_IO_BUG_REPORT_URL");
+const SUBMIT_TIMEOUT_SECS: u64 = 30;
+
+/// What the report window shows before anything is submitted
+#[derive(Debug, Serialize)]
+pub struct BugReportPreview {
+    /// System info and logs exactly as they will be attached
+    pub attachment: String,
+    /// Whether the report can be sent, rather than only saved to a file
+    pub can_send: bool,
+}
+
+/// Where a bug report ended up
+#[derive(Debug, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum BugReportOutcome {
+    Sent,
+    Saved {
+        path: PathBuf,
+        /// Why the report was saved although it could have been sent
+        send_error: Option<String>,
+    },
+}
+
+/// Show the bug report window, or focus it if it is already open
+pub fn open(handle: &AppHandle) -> tauri::Result<()> {
+    if !lock::may_show(handle) {
+        return Ok(());
+    }
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        return window.set_focus();
+    }
+    WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("report.html".into()))
+        .title("Report a problem")
+        .inner_size(560.0, 680.0)
+        .build()?;
+    Ok(())
+}
+
+/// Where reports are sent, `None` when they can only be saved
+fn endpoint(handle: &AppHandle) -> Option<String> {
+    let config = handle.state::<ConfigState>().get();
+    if config.offline {
+        return None;
+    }
+    config
+        .bug_report_url
+        .or_else(|| BUILT_IN_REPORT_URL.map(str::to_string))
+}
+
+/// Debug info and recent logs with the user's home directory and email addresses removed
+fn attachment(handle: &AppHandle, include_logs: bool) -> String {
+    let mut sections = vec![diagnostics::debug_info(handle)];
+    if include_logs {
+        sections.push(format!("--- Shell log ---\n{}", crash::shell_log_tail().join("\n")));
+        sections.push(format!(
+            "--- Backend output ---\n{}",
+            monitor::recent_output(handle).join("\n")
+        ));
+    }
+    crash::sanitize(&sections.join("\n"))
+}
+
+#[tauri::command]
+pub async fn get_bug_report_preview(
+    app: AppHandle,
+    include_logs: bool,
+) -> Result<BugReportPreview, String> {
+    tauri::async_runtime::spawn_blocking(move || BugReportPreview {
+        attachment: attachment(&app, include_logs),
+        can_send: endpoint(&app).is_some(),
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+fn send(url: String, body: serde_json::Value) -> Result<(), String> {
+    let response = crate::network::client()
+        .and_then(|client| {
+            client
+                .post(url)
+                .timeout(Duration::from_secs(SUBMIT_TIMEOUT_SECS))
+                .json(&body)
+                .send()
+        })
+        .map_err(|e| format!("Failed to send bug report: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Bug report rejected: {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Send the report, or save it to a file to email when there's nowhere to send it
+///
+/// Returns `None` when the user cancelled saving the file.
+#[tauri::command]
+pub async fn submit_bug_report(
+    app: AppHandle,
+    description: String,
+    contact: Option<String>,
+    include_logs: bool,
+) -> Result<Option<BugReportOutcome>, String> {
+    let description = description.trim().to_string();
+    if description.is_empty() {
+        return Err("Please describe the problem".to_string());
+    }
+    let contact = contact.filter(|contact| !contact.trim().is_empty());
+
+    let (url, attachment) = {
+        let app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || (endpoint(&app), attachment(&app, include_logs)))
+            .await
+            .map_err(|e| e.to_string())?
+    };
+
+    let send_error = match url {
+        Some(url) => {
+            let body = json!({
+                "kind": "bug_report",
+                "description": description,
+                "contact": contact,
+                "version": env!("CARGO_PKG_VERSION"),
+                "os": std::env::consts::OS,
+                "arch": std::env::consts::ARCH,
+                "attachment": attachment,
+            });
+            // The blocking client can't run on an async runtime thread
+            let result = tauri::async_runtime::spawn_blocking(move || send(url, body))
+                .await
+                .map_err(|e| e.to_string())?;
+            match result {
+                Ok(()) => {
+                    tracing::info!("Sent bug report");
+                    return Ok(Some(BugReportOutcome::Sent));
+                }
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    Some(e)
+                }
+            }
+        }
+        None => None,
+    };
+
+    let Some(path) = app
+        .dialog()
+        .file()
+        .set_title("Save bug report")
+        .set_file_name("tsw_io-bug-report.txt")
+        .add_filter("Text", &["txt"])
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+    else {
+        return Ok(None);
+    };
+
+    let contents = format!(
+        "TSW IO bug report\n\n{}\n\nContact: {}\n\n{}\n",
+        description,
+        contact.as_deref().unwrap_or("none"),
+        attachment
+    );
+    std::fs::write(&path, contents).map_err(|e| format!("Failed to save bug report: {}", e))?;
+    tracing::info!("Saved bug report to {}", path.display());
+    downloads::record(&app, DownloadKind::Report, &path);
+    let _ = tauri_plugin_opener::reveal_item_in_dir(&path);
+    Ok(Some(BugReportOutcome::Saved { path, send_error }))
+}
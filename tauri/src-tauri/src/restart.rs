@@ -0,0 +1,144 @@
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::config::ConfigState;
+use crate::failure;
+use crate::health;
+use crate::lan;
+use crate::loopback;
+use crate::monitor;
+use crate::overlay;
+use crate::profile::ProfileState;
+use crate::proxy;
+use crate::supervisor::{self, SupervisorState};
+use crate::timing;
+
+/// How long a relaunched backend gets to be running again
+const RESTART_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long a stopped backend gets to let go of its port
+const RELEASE_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Restart the bundled backend, returning once it is running again
+///
+/// An open main window stays open, dimmed by an overlay, and only reloads its page once the
+/// new backend is ready. Behind the proxy the new backend starts while the old one keeps
+/// serving, and takes over once ready, otherwise it replaces the old one in place. Without a
+/// main window showing there is nothing to keep, and the backend is launched the usual way.
+pub fn restart(handle: &AppHandle) -> Result<(), String> {
+    let profile = handle.state::<ProfileState>().get();
+    let window = handle
+        .get_webview_window("main")
+        .filter(|window| window.is_visible().unwrap_or(false));
+    let running = supervisor::state(handle) == SupervisorState::Running;
+    let (Some(window), None, true) = (window, &profile.backend_url, running) else {
+        crate::stop_backend(handle);
+        crate::launch(handle).map_err(|e| e.to_string())?;
+        return wait_until_running(handle);
+    };
+
+    if let Err(e) = overlay::show(&window, "Restarting the server...") {
+        tracing::warn!("Failed to open the restart overlay: {}", e);
+    }
+    let shell_config = handle.state::<ConfigState>().get();
+    let listen_address = crate::listen_address(&shell_config);
+    let standby = shell_config
+        .proxy
+        .then(|| proxy::standby(profile.port, listen_address))
+        .flatten();
+    tracing::info!(standby, "Restarting backend under the main window");
+    timing::start(handle);
+    supervisor::backend_launching(handle);
+
+    let mut old = crate::detach_backend(handle);
+    if standby.is_none() {
+        if let Some(old) = old.take() {
+            if let Err(e) = old.kill() {
+                tracing::warn!("Failed to stop the backend: {}", e);
+            }
+        }
+        // The proxy holds on to its port, only a backend listening itself has to let go
+        if !shell_config.proxy {
+            wait_until_free(listen_address, profile.port);
+        }
+    }
+
+    // The proxy still sends everything to the old backend, so the standby is checked directly
+    let ready_url = match standby {
+        Some(backend_port) => format!("http://{}:{}", loopback::BIND_ADDRESS, backend_port),
+        None => profile.base_url(),
+    };
+    let ready = crate::spawn_sidecar(handle, &profile)
+        .map_err(|e| e.to_string())
+        .and_then(|()| {
+            health::wait_for_backend(handle, &ready_url, true, &timing::budget(handle))
+                .map(|_attempts| ())
+                .map_err(|attempts| format!("The server didn't start again ({} checks)", attempts))
+        });
+
+    match (ready, old) {
+        (Ok(()), old) => {
+            proxy::promote(profile.port);
+            supervisor::backend_ready(handle);
+            lan::indicate(handle);
+            // Only the page loads again, from the new backend
+            if let Err(e) = window.reload() {
+                tracing::error!("Failed to reload the main window: {}", e);
+            }
+            overlay::close(handle);
+            if let Some(old) = old {
+                if let Err(e) = old.kill() {
+                    tracing::warn!("Failed to stop the replaced backend: {}", e);
+                }
+            }
+            tracing::info!("Backend restarted under the main window");
+            Ok(())
+        }
+        // The old backend never stopped serving, as if nothing happened
+        (Err(e), Some(old)) => {
+            tracing::error!("{}", e);
+            if let Some(standby) = crate::detach_backend(handle) {
+                let _ = standby.kill();
+            }
+            proxy::discard_standby(profile.port);
+            crate::attach_backend(handle, old);
+            supervisor::backend_launching(handle);
+            supervisor::backend_ready(handle);
+            overlay::close(handle);
+            Err(e)
+        }
+        // Nothing serves anymore, as when a launch fails
+        (Err(e), None) => {
+            tracing::error!("{}", e);
+            let kind = failure::classify(
+                &monitor::recent_output(handle),
+                monitor::backend_exit(handle).as_ref(),
+            );
+            overlay::close(handle);
+            if supervisor::state(handle) != SupervisorState::CrashLoop {
+                failure::show(handle, kind);
+            }
+            let _ = window.destroy();
+            Err(e)
+        }
+    }
+}
+
+fn wait_until_free(address: &str, port: u16) {
+    let started_at = Instant::now();
+    while TcpListener::bind((address, port)).is_err() && started_at.elapsed() < RELEASE_TIMEOUT {
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn wait_until_running(handle: &AppHandle) -> Result<(), String> {
+    let started_at = Instant::now();
+    while supervisor::state(handle) != SupervisorState::Running {
+        if started_at.elapsed() > RESTART_TIMEOUT || supervisor::state(handle) == SupervisorState::Idle {
+            return Err("The server did not start again".to_string());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    Ok(())
+}
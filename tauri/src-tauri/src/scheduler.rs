@@ -0,0 +1,497 @@
+use chrono::{DateTime, Datelike, Local, LocalResult, NaiveDate, TimeDelta, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::api;
+use crate::config::{self, ConfigState, ScheduledTask};
+use crate::lock;
+use crate::paths::AppPaths;
+use crate::profile::ProfileState;
+use crate::supervisor::{self, SupervisorState};
+
+const WINDOW_LABEL: &str = "schedules";
+/// Every run of a scheduled task, appended to like the audit log
+const HISTORY_FILE: &str = "schedule-history.jsonl";
+/// Runs the diagnostics window shows, newest first
+const SHOWN_RUNS: usize = 100;
+/// How often the schedules are checked, runs start up to this late
+const TICK: Duration = Duration::from_secs(30);
+/// A run noticed later than this was missed, the app wasn't running or the machine slept
+const MISSED_AFTER: TimeDelta = TimeDelta::minutes(2);
+
+/// Serializes appends so runs finishing together don't interleave
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+/// Names of the tasks running now, a slow one isn't started again until it is done
+static RUNNING: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+/// When each task last started, or was first seen, in seconds since the Unix epoch
+static LAST_RUN: Mutex<BTreeMap<String, i64>> = Mutex::new(BTreeMap::new());
+
+/// What started a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    Schedule,
+    /// The run was missed and made up for, see `ScheduledTask::catch_up`
+    CatchUp,
+    /// "Run now" in the settings
+    Manual,
+}
+
+/// One line of the history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Execution {
+    pub name: String,
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    /// When the schedule said to run, none for manual runs
+    pub scheduled_for: Option<u64>,
+    pub trigger: Trigger,
+    pub ok: bool,
+    /// What the backend said, or why the call failed
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+/// A task as the settings show it
+#[derive(Debug, Serialize)]
+pub struct ScheduleStatus {
+    #[serde(flatten)]
+    pub task: ScheduledTask,
+    /// Seconds since the Unix epoch
+    pub next_run: Option<u64>,
+    pub last_run: Option<Execution>,
+    pub running: bool,
+}
+
+/// A cron expression, each field as the set of values it matches
+#[derive(Debug, Clone, Copy)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    /// Sunday is 0
+    weekdays: u64,
+    /// Restricting both days and weekdays matches either, as cron does
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    fn parse(expression: &str) -> Result<Cron, String> {
+        let expanded = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!(
+                "Invalid schedule {}: expected minute hour day month weekday",
+                expression
+            ));
+        };
+        let invalid = |e: String| format!("Invalid schedule {}: {}", expression, e);
+        let weekday_bits = field(weekdays, 0, 7).map_err(invalid)?;
+        Ok(Cron {
+            minutes: field(minutes, 0, 59).map_err(invalid)?,
+            hours: field(hours, 0, 23).map_err(invalid)?,
+            days: field(days, 1, 31).map_err(invalid)?,
+            months: field(months, 1, 12).map_err(invalid)?,
+            // 7 is Sunday too
+            weekdays: (weekday_bits | (weekday_bits >> 7)) & 0x7f,
+            any_day: days.starts_with('*'),
+            any_weekday: weekdays.starts_with('*'),
+        })
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        let day = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+        day && self.months & (1 << date.month()) != 0
+    }
+
+    /// First time after `after` the expression matches, none if it never does
+    ///
+    /// Times skipped when the clocks go forward don't run, those repeated when they go back
+    /// run once.
+    fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        let mut date = start.date();
+        // Four years go through every date, the 29th of February included
+        for _ in 0..4 * 366 {
+            if self.matches_day(date) {
+                for hour in (0..24).filter(|hour| self.hours & (1 << hour) != 0) {
+                    for minute in (0..60).filter(|minute| self.minutes & (1 << minute) != 0) {
+                        let time = date.and_hms_opt(hour, minute, 0)?;
+                        if time < start {
+                            continue;
+                        }
+                        match Local.from_local_datetime(&time) {
+                            LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => return Some(time),
+                            LocalResult::None => {}
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+}
+
+/// Values of a field from `min` to `max` as bits, from `*`, `5`, `1-5`, `*/15` or lists of those
+fn field(spec: &str, min: u32, max: u32) -> Result<u64, String> {
+    let number = |text: &str| {
+        text.parse::<u32>()
+            .map_err(|_| format!("{} is not a number", text))
+    };
+    let mut bits = 0u64;
+    for item in spec.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => match number(step)? {
+                0 => return Err(format!("{} steps by nothing", item)),
+                step => (range, step),
+            },
+            None => (item, 1),
+        };
+        let (low, high) = if range == "*" {
+            (min, max)
+        } else if let Some((low, high)) = range.split_once('-') {
+            (number(low)?, number(high)?)
+        } else {
+            let value = number(range)?;
+            // `5/10` counts from 5 to the end
+            (value, if step > 1 { max } else { value })
+        };
+        if low < min || high > max || low > high {
+            return Err(format!("{} is outside {}-{}", item, min, max));
+        }
+        for value in (low..=high).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+fn history_path(handle: &AppHandle) -> PathBuf {
+    handle.state::<AppPaths>().log_dir.join(HISTORY_FILE)
+}
+
+fn append(path: &Path, execution: &Execution) -> Result<(), String> {
+    let line = serde_json::to_string(execution).map_err(|e| e.to_string())?;
+    let _guard = WRITE_LOCK.lock().unwrap();
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Every recorded run, oldest first
+fn read_history(path: &Path) -> Vec<Execution> {
+    std::fs::read_to_string(path)
+        .map(|contents| {
+            contents
+                .lines()
+                // A line cut short by a crash is skipped rather than failing the whole file
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn recent(path: &Path) -> Vec<Execution> {
+    let mut runs = read_history(path);
+    runs.reverse();
+    runs.truncate(SHOWN_RUNS);
+    runs
+}
+
+/// Check the config's schedules and call the endpoints of those that are due
+///
+/// Runs wait while the backend isn't running. A run found due more than `MISSED_AFTER`
+/// late, as once the machine wakes or the app starts again, is made up for once, however
+/// many were missed, unless the task turned `catch_up` off.
+pub fn start(handle: AppHandle) {
+    std::thread::spawn(move || {
+        {
+            let mut last_run = LAST_RUN.lock().unwrap();
+            for execution in read_history(&history_path(&handle)) {
+                last_run.insert(execution.name, execution.at as i64);
+            }
+        }
+        loop {
+            std::thread::sleep(TICK);
+            if supervisor::state(&handle) != SupervisorState::Running {
+                continue;
+            }
+            let now = Local::now();
+            for task in handle.state::<ConfigState>().get().schedules {
+                // Rejected when it was saved, or broken by hand in the config
+                let Ok(cron) = Cron::parse(&task.cron) else {
+                    continue;
+                };
+                // A task never run before counts from when it is first seen
+                let last = *LAST_RUN
+                    .lock()
+                    .unwrap()
+                    .entry(task.name.clone())
+                    .or_insert(now.timestamp());
+                let Some(due) = Local
+                    .timestamp_opt(last, 0)
+                    .single()
+                    .and_then(|last| cron.next_after(last))
+                else {
+                    continue;
+                };
+                if due > now {
+                    continue;
+                }
+                let missed = now - due > MISSED_AFTER;
+                if missed && !task.catch_up {
+                    tracing::info!("Skipping the missed run of {} due at {}", task.name, due);
+                    LAST_RUN.lock().unwrap().insert(task.name.clone(), now.timestamp());
+                    continue;
+                }
+                let trigger = if missed { Trigger::CatchUp } else { Trigger::Schedule };
+                if let Err(e) = run(&handle, task, trigger, Some(due.timestamp() as u64)) {
+                    tracing::debug!("{}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Call the endpoint of `task` in the background, recording how it went in the history
+fn run(handle: &AppHandle, task: ScheduledTask, trigger: Trigger, scheduled_for: Option<u64>) -> Result<(), String> {
+    if !RUNNING.lock().unwrap().insert(task.name.clone()) {
+        return Err(format!("{} is still running", task.name));
+    }
+    let at = Local::now().timestamp();
+    LAST_RUN.lock().unwrap().insert(task.name.clone(), at);
+    let handle = handle.clone();
+    std::thread::spawn(move || {
+        tracing::info!(?trigger, "Running the scheduled task {}", task.name);
+        let started = Instant::now();
+        let base_url = handle.state::<ProfileState>().get().base_url();
+        let result = api::run_scheduled(&base_url, &task);
+        match &result {
+            Ok(message) => tracing::info!("Scheduled task {} done: {}", task.name, message),
+            Err(e) => tracing::warn!("Scheduled task {} failed: {}", task.name, e),
+        }
+        let execution = Execution {
+            name: task.name.clone(),
+            at: at as u64,
+            scheduled_for,
+            trigger,
+            ok: result.is_ok(),
+            message: result.unwrap_or_else(|e| e),
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        if let Err(e) = append(&history_path(&handle), &execution) {
+            tracing::warn!("Failed to record the run of {}: {}", task.name, e);
+        }
+        RUNNING.lock().unwrap().remove(&task.name);
+    });
+    Ok(())
+}
+
+fn validate(task: &ScheduledTask) -> Result<(), String> {
+    if task.name.trim().is_empty() {
+        return Err("The task needs a name".to_string());
+    }
+    if !task.path.starts_with("/api/") {
+        return Err("Only backend endpoints under /api/ can be scheduled".to_string());
+    }
+    reqwest::Method::from_bytes(task.method.to_uppercase().as_bytes())
+        .map_err(|_| format!("Invalid method {}", task.method))?;
+    if let Some(body) = &task.body {
+        serde_json::from_str::<serde_json::Value>(body).map_err(|e| format!("The body is not JSON: {}", e))?;
+    }
+    if Cron::parse(&task.cron)?.next_after(Local::now()).is_none() {
+        return Err(format!("{} never runs", task.cron));
+    }
+    Ok(())
+}
+
+/// Every task of the config with when it runs next and how it went last
+pub fn statuses(handle: &AppHandle) -> Vec<ScheduleStatus> {
+    let history = read_history(&history_path(handle));
+    let running = RUNNING.lock().unwrap().clone();
+    let now = Local::now();
+    handle
+        .state::<ConfigState>()
+        .get()
+        .schedules
+        .into_iter()
+        .map(|task| ScheduleStatus {
+            next_run: Cron::parse(&task.cron)
+                .ok()
+                .and_then(|cron| cron.next_after(now))
+                .map(|next| next.timestamp() as u64),
+            last_run: history.iter().rev().find(|run| run.name == task.name).cloned(),
+            running: running.contains(&task.name),
+            task,
+        })
+        .collect()
+}
+
+/// Show the scheduled tasks window, or focus it if it is already open
+pub fn open(handle: &AppHandle) -> tauri::Result<()> {
+    if !lock::may_show(handle) {
+        return Ok(());
+    }
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        return window.set_focus();
+    }
+    WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("schedules.html".into()))
+        .title("TSW IO Scheduled Tasks")
+        .inner_size(560.0, 640.0)
+        .build()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_schedules(app: AppHandle) -> Result<Vec<ScheduleStatus>, String> {
+    tauri::async_runtime::spawn_blocking(move || statuses(&app))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Add `task`, or replace the one named `previous`, as edited in the settings
+#[tauri::command]
+pub fn save_schedule(app: AppHandle, task: ScheduledTask, previous: Option<String>) -> Result<(), String> {
+    validate(&task)?;
+    let replaced = previous.unwrap_or_else(|| task.name.clone());
+    let schedules = app.state::<ConfigState>().get().schedules;
+    if task.name != replaced && schedules.iter().any(|existing| existing.name == task.name) {
+        return Err(format!("There is already a task named {}", task.name));
+    }
+    config::update(&app, |config| {
+        match config.schedules.iter_mut().find(|existing| existing.name == replaced) {
+            Some(existing) => *existing = task.clone(),
+            None => config.schedules.push(task.clone()),
+        }
+    })?;
+    tracing::info!("Scheduled {} at {}", task.name, task.cron);
+    Ok(())
+}
+
+/// Stop running the task named `name`, its history stays
+#[tauri::command]
+pub fn remove_schedule(app: AppHandle, name: String) -> Result<(), String> {
+    config::update(&app, |config| config.schedules.retain(|task| task.name != name))?;
+    tracing::info!("Unscheduled {}", name);
+    Ok(())
+}
+
+/// Run the task named `name` now, besides its schedule
+#[tauri::command]
+pub fn run_schedule_now(app: AppHandle, name: String) -> Result<(), String> {
+    if supervisor::state(&app) != SupervisorState::Running {
+        return Err("The backend is not running".to_string());
+    }
+    let task = app
+        .state::<ConfigState>()
+        .get()
+        .schedules
+        .into_iter()
+        .find(|task| task.name == name)
+        .ok_or_else(|| format!("No task named {}", name))?;
+    run(&app, task, Trigger::Manual, None)
+}
+
+#[tauri::command]
+pub async fn get_schedule_history(app: AppHandle) -> Result<Vec<Execution>, String> {
+    tauri::async_runtime::spawn_blocking(move || recent(&history_path(&app)))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    fn next(expression: &str, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        Cron::parse(expression).unwrap().next_after(after)
+    }
+
+    #[test]
+    fn reads_fields() {
+        assert_eq!(field("*", 0, 3), Ok(0b1111));
+        assert_eq!(field("2", 0, 59), Ok(1 << 2));
+        assert_eq!(field("1-3", 0, 59), Ok(0b1110));
+        assert_eq!(field("*/15", 0, 59), Ok(1 | 1 << 15 | 1 << 30 | 1 << 45));
+        assert_eq!(field("50/5", 0, 59), Ok(1 << 50 | 1 << 55));
+        assert_eq!(field("1,4-5", 1, 12), Ok(1 << 1 | 1 << 4 | 1 << 5));
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        assert!(field("60", 0, 59).is_err());
+        assert!(field("0", 1, 31).is_err());
+        assert!(field("5-1", 0, 59).is_err());
+        assert!(field("*/0", 0, 59).is_err());
+        assert!(field("mon", 0, 7).is_err());
+        assert!(Cron::parse("0 0 * *").is_err());
+        assert!(Cron::parse("0 0 * * * *").is_err());
+    }
+
+    #[test]
+    fn counts_7_as_sunday() {
+        let cron = Cron::parse("0 0 * * 7").unwrap();
+
+        assert_eq!(cron.weekdays, 1);
+    }
+
+    #[test]
+    fn matches_either_day_when_both_are_restricted() {
+        let cron = Cron::parse("0 0 13 * 5").unwrap();
+
+        // Friday the 6th, Monday the 13th, Tuesday the 14th
+        assert!(cron.matches_day(NaiveDate::from_ymd_opt(2026, 3, 6).unwrap()));
+        assert!(cron.matches_day(NaiveDate::from_ymd_opt(2026, 4, 13).unwrap()));
+        assert!(!cron.matches_day(NaiveDate::from_ymd_opt(2026, 4, 14).unwrap()));
+    }
+
+    #[test]
+    fn runs_next_at_the_first_match_after() {
+        let after = local(2026, 1, 14, 10, 7);
+
+        assert_eq!(next("*/15 * * * *", after), Some(local(2026, 1, 14, 10, 15)));
+        assert_eq!(next("@daily", after), Some(local(2026, 1, 15, 0, 0)));
+        assert_eq!(next("30 9 * * 1-5", after), Some(local(2026, 1, 15, 9, 30)));
+        assert_eq!(next("0 0 1 * *", after), Some(local(2026, 2, 1, 0, 0)));
+    }
+
+    #[test]
+    fn runs_next_strictly_after() {
+        let after = local(2026, 1, 14, 10, 15);
+
+        assert_eq!(next("15 10 * * *", after), Some(local(2026, 1, 15, 10, 15)));
+    }
+
+    #[test]
+    fn finds_leap_days() {
+        assert_eq!(next("0 0 29 2 *", local(2026, 3, 1, 0, 0)), Some(local(2028, 2, 29, 0, 0)));
+        assert_eq!(next("0 0 31 2 *", local(2026, 3, 1, 0, 0)), None);
+    }
+}
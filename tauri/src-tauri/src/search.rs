@@ -0,0 +1,351 @@
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::api::{self, SearchEntry};
+use crate::config::ConfigState;
+use crate::profile::ProfileState;
+use crate::supervisor::{self, SupervisorState};
+
+/// How often the setting and the backend are looked at
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the backend is asked for its records again
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Keep the backend's configurations and trains in the OS search, Spotlight on macOS and
+/// Windows Search on Windows
+///
+/// The records are pulled from the backend every `REFRESH_INTERVAL` and handed to the OS
+/// when they changed. Picking a result opens the app on the record's page. Turning
+/// `os_search` off takes them out again.
+pub fn start(handle: AppHandle) {
+    if !platform::SUPPORTED {
+        return;
+    }
+    platform::start(&handle);
+    std::thread::spawn(move || {
+        let mut indexed: Option<Vec<SearchEntry>> = None;
+        let mut refreshed: Option<Instant> = None;
+        // An earlier run may have left records, before the setting was turned off
+        let mut listed = true;
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            if !handle.state::<ConfigState>().get().os_search {
+                if listed {
+                    match platform::clear(&handle) {
+                        Ok(()) => tracing::info!("Removed the records from the OS search"),
+                        Err(e) => tracing::warn!("Failed to remove the records from the OS search: {}", e),
+                    }
+                    listed = false;
+                    indexed = None;
+                    refreshed = None;
+                }
+                continue;
+            }
+            if supervisor::state(&handle) != SupervisorState::Running
+                || refreshed.is_some_and(|at| at.elapsed() < REFRESH_INTERVAL)
+            {
+                continue;
+            }
+            refreshed = Some(Instant::now());
+
+            let base_url = handle.state::<ProfileState>().get().base_url();
+            let entries = match api::search_index(&base_url) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    tracing::warn!("Failed to get the records for the OS search: {}", e);
+                    continue;
+                }
+            };
+            if indexed.as_ref() == Some(&entries) {
+                continue;
+            }
+            match platform::index(&handle, &entries) {
+                Ok(()) => {
+                    tracing::info!(records = entries.len(), "Updated the OS search");
+                    indexed = Some(entries);
+                    listed = true;
+                }
+                Err(e) => tracing::warn!("Failed to update the OS search: {}", e),
+            }
+        }
+    });
+}
+
+/// Core Spotlight, picked results come back as a user activity to the app delegate
+#[cfg(target_os = "macos")]
+mod platform {
+    use block2::RcBlock;
+    use objc2::rc::{Allocated, Retained};
+    use objc2::runtime::{AnyObject, Bool, Imp, Sel};
+    use objc2::{class, msg_send, sel};
+    use objc2_foundation::{NSArray, NSDictionary, NSError, NSString};
+    use std::sync::OnceLock;
+    use tauri::AppHandle;
+
+    use crate::api::SearchEntry;
+    use crate::cli::Target;
+    use crate::companion;
+
+    pub const SUPPORTED: bool = true;
+    /// Groups the app's records, so they can be replaced all at once
+    const DOMAIN: &str = "records";
+    const CONTENT_TYPE: &str = "public.content";
+    /// `CSSearchableItemActionType`, the activity Spotlight continues a picked result with
+    const ACTIVITY_TYPE: &str = "com.apple.corespotlightitem";
+    /// `CSSearchableItemActivityIdentifier`, the picked record's identifier in the activity
+    const ACTIVITY_IDENTIFIER: &str = "kCSSearchableItemActivityIdentifier";
+
+    type ContinueActivity =
+        unsafe extern "C-unwind" fn(&AnyObject, Sel, &AnyObject, &AnyObject, &AnyObject) -> Bool;
+
+    static HANDLE: OnceLock<AppHandle> = OnceLock::new();
+    /// tao's own handler, which takes the activities of links
+    static ORIGINAL: OnceLock<ContinueActivity> = OnceLock::new();
+
+    #[link(name = "CoreSpotlight", kind = "framework")]
+    extern "C" {}
+
+    pub fn start(handle: &AppHandle) {
+        let _ = HANDLE.set(handle.clone());
+        // The delegate is set up on the main thread
+        let _ = handle.run_on_main_thread(hook);
+    }
+
+    /// Open the record of a picked result, passing other activities on to tao
+    unsafe extern "C-unwind" fn continue_activity(
+        delegate: &AnyObject,
+        selector: Sel,
+        application: &AnyObject,
+        activity: &AnyObject,
+        restoration_handler: &AnyObject,
+    ) -> Bool {
+        let activity_type: Retained<NSString> = unsafe { msg_send![activity, activityType] };
+        if activity_type.to_string() != ACTIVITY_TYPE {
+            return match ORIGINAL.get() {
+                Some(original) => unsafe { original(delegate, selector, application, activity, restoration_handler) },
+                None => Bool::NO,
+            };
+        }
+        let user_info: Option<Retained<NSDictionary<NSString, AnyObject>>> = unsafe { msg_send![activity, userInfo] };
+        // The identifier is the record's route
+        let route = user_info
+            .and_then(|info| info.objectForKey(&NSString::from_str(ACTIVITY_IDENTIFIER)))
+            .and_then(|route| route.downcast::<NSString>().ok())
+            .map(|route| route.to_string());
+        match (HANDLE.get(), route) {
+            (Some(handle), Some(route)) => {
+                tracing::info!("Opening {} from Spotlight", route);
+                companion::open(handle, Target::Route(route));
+                Bool::YES
+            }
+            _ => Bool::NO,
+        }
+    }
+
+    fn hook() {
+        let selector = sel!(application:continueUserActivity:restorationHandler:);
+        let application: Retained<AnyObject> = unsafe { msg_send![class!(NSApplication), sharedApplication] };
+        let delegate: Option<Retained<AnyObject>> = unsafe { msg_send![&application, delegate] };
+        let Some(method) = delegate.and_then(|delegate| delegate.class().instance_method(selector)) else {
+            tracing::warn!("Spotlight results can't open records, the app delegate doesn't continue activities");
+            return;
+        };
+        // SAFETY: both have the signature of the selector
+        unsafe {
+            let ours: Imp = std::mem::transmute::<ContinueActivity, Imp>(continue_activity);
+            let original = method.set_implementation(ours);
+            let _ = ORIGINAL.set(std::mem::transmute::<Imp, ContinueActivity>(original));
+        }
+    }
+
+    fn searchable_index() -> Retained<AnyObject> {
+        unsafe { msg_send![class!(CSSearchableIndex), defaultSearchableIndex] }
+    }
+
+    fn searchable_item(entry: &SearchEntry) -> Retained<AnyObject> {
+        unsafe {
+            let attributes: Allocated<AnyObject> = msg_send![class!(CSSearchableItemAttributeSet), alloc];
+            let attributes: Retained<AnyObject> =
+                msg_send![attributes, initWithItemContentType: &*NSString::from_str(CONTENT_TYPE)];
+            let _: () = msg_send![&attributes, setTitle: &*NSString::from_str(&entry.title)];
+            let _: () = msg_send![&attributes, setContentDescription: &*NSString::from_str(&entry.snippet)];
+            let item: Allocated<AnyObject> = msg_send![class!(CSSearchableItem), alloc];
+            msg_send![
+                item,
+                initWithUniqueIdentifier: &*NSString::from_str(&entry.route),
+                domainIdentifier: &*NSString::from_str(DOMAIN),
+                attributeSet: &*attributes
+            ]
+        }
+    }
+
+    /// Completion handler that logs what went wrong, Spotlight answers on its own queue
+    fn report(action: &'static str) -> RcBlock<dyn Fn(*mut NSError)> {
+        RcBlock::new(move |error: *mut NSError| {
+            if let Some(error) = unsafe { error.as_ref() } {
+                tracing::warn!("Spotlight failed to {}: {}", action, error.localizedDescription());
+            }
+        })
+    }
+
+    fn delete_domain(then: &RcBlock<dyn Fn(*mut NSError)>) {
+        let domains = NSArray::from_retained_slice(&[NSString::from_str(DOMAIN)]);
+        unsafe {
+            let _: () = msg_send![
+                &searchable_index(),
+                deleteSearchableItemsWithDomainIdentifiers: &*domains,
+                completionHandler: &**then
+            ];
+        }
+    }
+
+    /// Replace the app's records with `entries`, dropping those deleted on the backend
+    pub fn index(_handle: &AppHandle, entries: &[SearchEntry]) -> Result<(), String> {
+        let items: Vec<Retained<AnyObject>> = entries.iter().map(searchable_item).collect();
+        let items = NSArray::from_retained_slice(&items);
+        let then = RcBlock::new(move |error: *mut NSError| {
+            if let Some(error) = unsafe { error.as_ref() } {
+                tracing::warn!("Spotlight failed to remove the records: {}", error.localizedDescription());
+                return;
+            }
+            let done = report("index the records");
+            unsafe {
+                let _: () = msg_send![&searchable_index(), indexSearchableItems: &*items, completionHandler: &*done];
+            }
+        });
+        delete_domain(&then);
+        Ok(())
+    }
+
+    pub fn clear(_handle: &AppHandle) -> Result<(), String> {
+        delete_domain(&report("remove the records"));
+        Ok(())
+    }
+}
+
+/// Internet shortcuts to `tsw-io://` links in a folder Windows Search indexes
+///
+/// Opening one launches the app through the URL scheme registration, see `integrations`.
+#[cfg(windows)]
+mod platform {
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+    use tauri::{AppHandle, Manager};
+    use windows::core::HSTRING;
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_LOCAL_SERVER, COINIT_MULTITHREADED};
+    use windows::Win32::System::Search::{CSearchManager, ISearchManager, FF_INDEXCOMPLEXURLS};
+
+    use crate::api::SearchEntry;
+    use crate::cli;
+
+    pub const SUPPORTED: bool = true;
+    /// Folder under the app's local data directory the shortcuts are kept in
+    const SHORTCUTS_DIR: &str = "search";
+    /// Longest title kept in a shortcut's name, paths are limited in length
+    const MAX_TITLE: usize = 100;
+
+    pub fn start(_handle: &AppHandle) {}
+
+    fn shortcuts_dir(handle: &AppHandle) -> Result<PathBuf, String> {
+        handle
+            .path()
+            .app_local_data_dir()
+            .map(|dir| dir.join(SHORTCUTS_DIR))
+            .map_err(|e| format!("Failed to find the app data directory: {}", e))
+    }
+
+    /// Add `dir` to what Windows Search crawls, it leaves the app data directory out
+    fn include(dir: &Path) -> Result<(), String> {
+        let failed = |e: windows::core::Error| format!("Failed to add {} to Windows Search: {}", dir.display(), e);
+        unsafe {
+            // Initialized already on this thread is fine too
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            let manager: ISearchManager = CoCreateInstance(&CSearchManager, None, CLSCTX_LOCAL_SERVER).map_err(failed)?;
+            let scope = manager
+                .GetCatalog(&HSTRING::from("SystemIndex"))
+                .and_then(|catalog| catalog.GetCrawlScopeManager())
+                .map_err(failed)?;
+            let url = HSTRING::from(format!("file:///{}\\", dir.display()));
+            if scope.IncludedInCrawlScope(&url).map_err(failed)?.as_bool() {
+                return Ok(());
+            }
+            scope
+                .AddUserScopeRule(&url, true, true, FF_INDEXCOMPLEXURLS.0 as u32)
+                .map_err(failed)?;
+            scope.SaveAll().map_err(failed)
+        }
+    }
+
+    /// What Windows Search shows as the result's name, without the characters file names can't have
+    fn file_name(title: &str) -> String {
+        let name: String = title
+            .chars()
+            .map(|c| if c.is_control() || r#"<>:"/\|?*"#.contains(c) { '_' } else { c })
+            .take(MAX_TITLE)
+            .collect();
+        name.trim_end_matches(['.', ' ']).to_string()
+    }
+
+    /// Write a shortcut per record and remove those of records deleted on the backend
+    pub fn index(handle: &AppHandle, entries: &[SearchEntry]) -> Result<(), String> {
+        let dir = shortcuts_dir(handle)?;
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        include(&dir)?;
+
+        let mut shortcuts = BTreeMap::new();
+        for entry in entries {
+            let mut name = format!("{} ({}).url", file_name(&entry.title), entry.kind);
+            // Records of the same name are told apart by their id
+            if shortcuts.contains_key(&name) {
+                name = format!("{} ({}).url", file_name(&entry.title), entry.id);
+            }
+            let link = format!("{}://{}", cli::LINK_SCHEME, entry.route.trim_start_matches('/'));
+            shortcuts.insert(name, format!("[InternetShortcut]\r\nURL={}\r\n", link));
+        }
+
+        for existing in std::fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))? {
+            let existing = existing.map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+            let name = existing.file_name().to_string_lossy().into_owned();
+            if !shortcuts.contains_key(&name) {
+                let _ = std::fs::remove_file(existing.path());
+            }
+        }
+        for (name, contents) in shortcuts {
+            let path = dir.join(name);
+            // Rewriting an unchanged shortcut would only have it indexed again
+            if std::fs::read_to_string(&path).is_ok_and(|current| current == contents) {
+                continue;
+            }
+            std::fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+
+    pub fn clear(handle: &AppHandle) -> Result<(), String> {
+        let dir = shortcuts_dir(handle)?;
+        if !dir.exists() {
+            return Ok(());
+        }
+        std::fs::remove_dir_all(&dir).map_err(|e| format!("Failed to remove {}: {}", dir.display(), e))
+    }
+}
+
+/// There is no OS search to feed elsewhere
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use tauri::AppHandle;
+
+    use crate::api::SearchEntry;
+
+    pub const SUPPORTED: bool = false;
+
+    pub fn start(_handle: &AppHandle) {}
+
+    pub fn index(_handle: &AppHandle, _entries: &[SearchEntry]) -> Result<(), String> {
+        Err("The OS search is not supported on this platform".to_string())
+    }
+
+    pub fn clear(_handle: &AppHandle) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use keyring::Entry;
+use std::sync::OnceLock;
+
+/// Service name the secrets are filed under in the OS credential store
+const SERVICE: &str = "tsw_io";
+
+/// Backend secrets, by environment variable and credential store account
+///
+/// Each is generated on first use and only ever handed to the sidecar.
+const SECRETS: &[(&str, &str)] = &[("SECRET_KEY_BASE", "secret_key_base")];
+/// Random bytes in a generated secret
+const SECRET_BYTES: usize = 64;
+
+static SHELL_TOKEN: OnceLock<String> = OnceLock::new();
+
+/// Hex string of `len` bytes from the OS random number generator
+pub fn random_hex(len: usize) -> Result<String, String> {
+    let mut bytes = vec![0u8; len];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Failed to generate random bytes: {}", e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Compare secrets without giving away through timing how much of them matched
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Token the shell authenticates to the backend with, a new one every run
+///
+/// The backend only accepts the one it was started with. A dev backend was started by
+/// hand, so the shell takes the `TSW_IO_SHELL_TOKEN` it was given too.
+pub fn shell_token() -> &'static str {
+    SHELL_TOKEN.get_or_init(|| {
+        let given = std::env::var("TSW_IO_SHELL_TOKEN").ok().filter(|token| !token.is_empty());
+        if let Some(token) = given.filter(|_| crate::devbackend::enabled()) {
+            return token;
+        }
+        random_hex(32).unwrap_or_else(|e| {
+            // Without a token the backend refuses the shell's admin calls, nothing else breaks
+            tracing::warn!("{}", e);
+            String::new()
+        })
+    })
+}
+
+/// Authenticate with the token of a backend another process started, the backend service's
+///
+/// Only before the shell used its own token, the backend it started wouldn't take another.
+pub fn adopt_shell_token(token: String) {
+    if SHELL_TOKEN.set(token).is_err() {
+        tracing::warn!("Shell token already in use, the backend will refuse the shell's admin calls");
+    }
+}
+
+/// Read a secret from the OS credential store, creating it if there is none yet
+pub(crate) fn get_or_create(account: &str) -> Result<String, String> {
+    let entry = Entry::new(SERVICE, account)
+        .map_err(|e| format!("Failed to open credential store: {}", e))?;
+    match entry.get_password() {
+        Ok(secret) => Ok(secret),
+        Err(keyring::Error::NoEntry) => {
+            let secret = random_hex(SECRET_BYTES)?;
+            entry
+                .set_password(&secret)
+                .map_err(|e| format!("Failed to store {}: {}", account, e))?;
+            tracing::info!(account, "Generated backend secret");
+            Ok(secret)
+        }
+        Err(e) => Err(format!("Failed to read {}: {}", account, e)),
+    }
+}
+
+/// Environment for the sidecar with the secrets from the OS credential store and the shell token
+///
+/// Secrets that can't be read are left out and the backend falls back to its own
+/// defaults, so a locked or missing keychain doesn't stop the app from starting.
+pub fn sidecar_env() -> Vec<(&'static str, String)> {
+    SECRETS
+        .iter()
+        .filter_map(|(env, account)| match get_or_create(account) {
+            Ok(secret) => Some((*env, secret)),
+            Err(e) => {
+                tracing::warn!("{}", e);
+                None
+            }
+        })
+        .chain(Some(("TSW_IO_SHELL_TOKEN", shell_token().to_string())))
+        .collect()
+}
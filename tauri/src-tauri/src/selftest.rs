@@ -0,0 +1,141 @@
+use serde::Serialize;
+use std::fmt::Write;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::monitor;
+use crate::profile::{Profile, ProfileState};
+
+const SIDECAR_NAME: &str = "tsw_io_backend";
+/// Free space needed for the database, its migrations and backups
+const MIN_FREE_BYTES: u64 = 200 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    pub checks: Vec<Check>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    /// One line per check, for printing to the terminal
+    pub fn render(&self) -> String {
+        let mut report = String::new();
+        for check in &self.checks {
+            let status = if check.passed { "PASS" } else { "FAIL" };
+            let _ = writeln!(report, "[{}] {}: {}", status, check.name, check.detail);
+        }
+        let summary = if self.passed() { "All checks passed" } else { "Some checks failed" };
+        let _ = writeln!(report, "{}", summary);
+        report
+    }
+}
+
+pub fn check(name: &'static str, result: Result<String, String>) -> Check {
+    match result {
+        Ok(detail) => Check { name, passed: true, detail },
+        Err(detail) => Check { name, passed: false, detail },
+    }
+}
+
+/// Check that everything the backend needs to start is in place
+///
+/// `backend_running` tells the port check that a busy port is our own backend.
+pub fn run(profile: &Profile, backend_running: bool) -> SelfTestReport {
+    let (sidecar, port) = match &profile.backend_url {
+        Some(url) => {
+            let not_needed = format!("Not needed, using the backend at {}", url);
+            (Ok(not_needed.clone()), Ok(not_needed))
+        }
+        None => (check_sidecar(), check_port(profile.port, backend_running)),
+    };
+
+    SelfTestReport {
+        checks: vec![
+            check("Backend binary", sidecar),
+            check("Port", port),
+            check("Data directory", check_writable(&profile.data_dir)),
+            check("Disk space", check_disk_space(&profile.data_dir)),
+            check("Webview runtime", check_webview()),
+        ],
+    }
+}
+
+fn sidecar_path() -> Result<PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate the app: {}", e))?;
+    let dir = exe.parent().ok_or("Failed to locate the app directory")?;
+    Ok(dir.join(format!("{}{}", SIDECAR_NAME, std::env::consts::EXE_SUFFIX)))
+}
+
+fn check_sidecar() -> Result<String, String> {
+    let path = sidecar_path()?;
+    let metadata = std::fs::metadata(&path)
+        .map_err(|e| format!("{} is missing: {}", path.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(format!("{} is not executable", path.display()));
+        }
+    }
+    Ok(path.display().to_string())
+}
+
+fn check_port(port: u16, backend_running: bool) -> Result<String, String> {
+    match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(_) => Ok(format!("{} is free", port)),
+        Err(_) if backend_running => Ok(format!("{} is used by the running backend", port)),
+        Err(e) => Err(format!("{} is not available: {}", port, e)),
+    }
+}
+
+fn check_writable(dir: &Path) -> Result<String, String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let probe = dir.join(".tsw_io-write-test");
+    std::fs::write(&probe, b"ok")
+        .map_err(|e| format!("{} is not writable: {}", dir.display(), e))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(dir.display().to_string())
+}
+
+fn check_disk_space(dir: &Path) -> Result<String, String> {
+    let available = monitor::available_space(dir)
+        .ok_or_else(|| format!("Failed to find the disk holding {}", dir.display()))?;
+    let available_mb = available / (1024 * 1024);
+    if available < MIN_FREE_BYTES {
+        return Err(format!(
+            "Only {} MB free, at least {} MB needed",
+            available_mb,
+            MIN_FREE_BYTES / (1024 * 1024)
+        ));
+    }
+    Ok(format!("{} MB free", available_mb))
+}
+
+fn check_webview() -> Result<String, String> {
+    tauri::webview_version().map_err(|e| format!("Not found: {}", e))
+}
+
+#[tauri::command]
+pub async fn run_self_test(app: AppHandle) -> Result<SelfTestReport, String> {
+    let profile = app.state::<ProfileState>().get();
+    let backend_running = crate::backend_running(&app);
+    tauri::async_runtime::spawn_blocking(move || run(&profile, backend_running))
+        .await
+        .map_err(|e| e.to_string())
+}
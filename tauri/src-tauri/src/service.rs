@@ -0,0 +1,483 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
+
+use crate::audit::{self, AuditAction};
+use crate::companion::{self, RunningBackend};
+use crate::headless;
+use crate::paths::AppPaths;
+use crate::profile::{Profile, ProfileState};
+use crate::secrets;
+use crate::tray;
+use crate::upgrade;
+
+/// How long a started service gets to answer on its control endpoint
+const START_TIMEOUT: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Set when this app uses a backend already running instead of its own
+static IN_USE: AtomicBool = AtomicBool::new(false);
+/// The backend attached to, while its version differs from the app's and nobody was asked
+static MISMATCH: Mutex<Option<RunningBackend>> = Mutex::new(None);
+
+/// Whether the backend runs as a service, shown in the tray and settings
+#[derive(Debug, Serialize)]
+pub struct ServiceStatus {
+    pub installed: bool,
+    /// This app talks to the service's backend instead of running its own
+    pub in_use: bool,
+}
+
+/// The backend keeps running as a service of the user's session while the app is closed
+///
+/// The service is `tsw_io backend start`, the same supervisor as the GUI without windows,
+/// registered with the platform's per-user service manager so no administrator rights are
+/// needed: a systemd user unit on Linux, a launchd agent on macOS, and a logon entry on
+/// Windows. It starts with the user's session, and when the app quits after running the
+/// backend itself.
+pub fn installed() -> bool {
+    platform::installed()
+}
+
+pub fn in_use() -> bool {
+    IN_USE.load(Ordering::SeqCst)
+}
+
+/// Register the service for `profile`, it first starts at the next login or when the app quits
+pub fn install(profile: &Profile, paths: &AppPaths) -> Result<(), String> {
+    let (exe, args) = program(profile, paths)?;
+    platform::install(&exe, &args)?;
+    tracing::info!("Installed the backend service");
+    Ok(())
+}
+
+/// Unregister the service, without stopping a backend it runs
+pub fn uninstall() -> Result<(), String> {
+    platform::uninstall()?;
+    tracing::info!("Uninstalled the backend service");
+    Ok(())
+}
+
+pub fn start() -> Result<(), String> {
+    platform::start()
+}
+
+fn program(profile: &Profile, paths: &AppPaths) -> Result<(PathBuf, Vec<String>), String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to find the app: {}", e))?;
+    let mut args = vec![
+        "backend".to_string(),
+        "start".to_string(),
+        "--env".to_string(),
+        profile.name.clone(),
+        // Where `attach` looks for the running service
+        "--config-dir".to_string(),
+        paths.config_dir.to_string_lossy().into_owned(),
+    ];
+    if let Some(data_dir) = &paths.data_dir_override {
+        args.push("--data-dir".to_string());
+        args.push(data_dir.to_string_lossy().into_owned());
+    }
+    Ok((exe, args))
+}
+
+/// Use a backend already running without windows, the service's or one left by `backend start`
+///
+/// An installed service that isn't running is started first. Returns the backend URL for
+/// the profile to use as an external backend. The app takes the shell token of that backend,
+/// which only accepts the one it was started with.
+pub fn attach(config_dir: &Path) -> Option<String> {
+    let running = companion::running_backend(config_dir).or_else(|| {
+        if !installed() {
+            return None;
+        }
+        if let Err(e) = start() {
+            tracing::warn!("Failed to start the backend service: {}", e);
+            return None;
+        }
+        let started_at = Instant::now();
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            if let Some(running) = companion::running_backend(config_dir) {
+                return Some(running);
+            }
+            if started_at.elapsed() > START_TIMEOUT {
+                tracing::warn!("The backend service didn't come up, running the backend in the app");
+                return None;
+            }
+        }
+    })?;
+    if let Some(token) = running.shell_token.clone() {
+        secrets::adopt_shell_token(token);
+    }
+    IN_USE.store(true, Ordering::SeqCst);
+    tracing::info!(
+        url = %running.url,
+        version = %running.version,
+        backend_version = %running.backend_version,
+        "Using the backend already running"
+    );
+    let url = running.url.clone();
+    if running.backend_version != upgrade::backend_version() {
+        *MISMATCH.lock().unwrap() = Some(running);
+    }
+    Some(url)
+}
+
+/// Offer to replace a running backend of another version than the app's, once it is ready
+///
+/// The app's pages expect the backend they came with. Kept, the app works as well as that
+/// backend allows. Replaced, the app runs its own backend until it quits, and the service
+/// updated to this version takes over from there.
+pub fn check_version(handle: &AppHandle) {
+    let Some(running) = MISMATCH.lock().unwrap().take() else {
+        return;
+    };
+    tracing::warn!(
+        running = %running.backend_version,
+        ours = %upgrade::backend_version(),
+        "The running backend is another version than the app's"
+    );
+    let restart_handle = handle.clone();
+    handle
+        .dialog()
+        .message(format!(
+            "The server already running is version {}, this version of tsw_io comes with {}. \
+             Restart the server with the version of the app?",
+            running.backend_version,
+            upgrade::backend_version()
+        ))
+        .title("Different server version")
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Restart server".to_string(),
+            "Keep using it".to_string(),
+        ))
+        .show(move |restart| {
+            if !restart {
+                return;
+            }
+            // Stopping the running backend blocks
+            std::thread::spawn(move || {
+                audit::record(
+                    &restart_handle,
+                    AuditAction::BackendRestart,
+                    format!("Replaced the running server {}", running.backend_version),
+                );
+                let profile = restart_handle.state::<ProfileState>().get();
+                // Started from this app's executable from now on
+                if installed() {
+                    if let Err(e) = install(&profile, &restart_handle.state::<AppPaths>()) {
+                        tracing::warn!("Failed to update the backend service: {}", e);
+                    }
+                }
+                if let Err(e) = take_over(&restart_handle) {
+                    tracing::error!("Failed to restart the server: {}", e);
+                }
+            });
+        });
+}
+
+/// Stop the backend the app attached to and run the app's own in its place
+fn take_over(handle: &AppHandle) -> Result<(), String> {
+    companion::stop_running(&handle.state::<AppPaths>().config_dir)?;
+    IN_USE.store(false, Ordering::SeqCst);
+    // The adopted token stays, the backend now started by the app is given it too
+    handle.state::<ProfileState>().0.write().unwrap().backend_url = None;
+    crate::launch(handle).map_err(|e| e.to_string())
+}
+
+/// Let the service take over once the app quits, as it had the backend to itself until then
+///
+/// Not when the app ran without windows: that is the service itself, or a one-off command.
+pub fn hand_over(handle: &AppHandle) {
+    let bundled = handle.state::<ProfileState>().get().backend_url.is_none();
+    if in_use() || !bundled || headless::enabled() || !installed() {
+        return;
+    }
+    match start() {
+        Ok(()) => tracing::info!("Backend service started for when the app is closed"),
+        Err(e) => tracing::warn!("Failed to start the backend service: {}", e),
+    }
+}
+
+pub fn status() -> ServiceStatus {
+    ServiceStatus {
+        installed: installed(),
+        in_use: in_use(),
+    }
+}
+
+/// Run the backend as a service from the next time the app quits, or stop doing so
+///
+/// Uninstalling while the app uses the service's backend stops it, and the app runs its
+/// own backend again right away.
+pub fn set_installed(handle: &AppHandle, enabled: bool) -> Result<ServiceStatus, String> {
+    let profile = handle.state::<ProfileState>().get();
+    if profile.backend_url.is_some() && !in_use() {
+        return Err("Only the server bundled with tsw_io can run as a service".to_string());
+    }
+    if enabled == installed() {
+        return Ok(status());
+    }
+
+    if enabled {
+        install(&profile, &handle.state::<AppPaths>())?;
+        audit::record(handle, AuditAction::ConfigChange, "Installed the backend as a service");
+        return Ok(status());
+    }
+
+    uninstall()?;
+    audit::record(handle, AuditAction::ConfigChange, "Uninstalled the backend service");
+    // launchd already stopped it along with the agent
+    if in_use() {
+        take_over(handle)?;
+    }
+    Ok(status())
+}
+
+#[tauri::command]
+pub fn get_service_status() -> ServiceStatus {
+    status()
+}
+
+#[tauri::command]
+pub async fn set_backend_service(app: AppHandle, installed: bool) -> Result<ServiceStatus, String> {
+    // The service managers and stopping the service block
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = set_installed(&app, installed);
+        tray::sync_service(&app);
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn run(program: &str, args: &[&str]) -> Result<(), String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    use super::run;
+
+    const UNIT_NAME: &str = "tsw-io-backend.service";
+
+    fn unit_path() -> PathBuf {
+        crate::paths::xdg_config_home().join("systemd").join("user").join(UNIT_NAME)
+    }
+
+    /// systemd only splits `ExecStart` on spaces, quoting each argument keeps paths whole
+    fn quote(arg: &str) -> String {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\"").replace('%', "%%"))
+    }
+
+    pub fn installed() -> bool {
+        unit_path().exists()
+    }
+
+    pub fn install(exe: &Path, args: &[String]) -> Result<(), String> {
+        let command: Vec<String> = std::iter::once(exe.to_string_lossy().into_owned())
+            .chain(args.iter().cloned())
+            .map(|arg| quote(&arg))
+            .collect();
+        // The shell needs the session's display server even without windows, so the unit
+        // belongs to the graphical session
+        let unit = format!(
+            "[Unit]\n\
+             Description=TSW IO backend\n\
+             After=graphical-session.target\n\
+             PartOf=graphical-session.target\n\
+             \n\
+             [Service]\n\
+             ExecStart={}\n\
+             Restart=on-failure\n\
+             \n\
+             [Install]\n\
+             WantedBy=graphical-session.target\n",
+            command.join(" ")
+        );
+        let path = unit_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(&path, unit).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        run("systemctl", &["--user", "daemon-reload"])?;
+        run("systemctl", &["--user", "enable", UNIT_NAME])
+    }
+
+    pub fn uninstall() -> Result<(), String> {
+        let _ = run("systemctl", &["--user", "disable", UNIT_NAME]);
+        let path = unit_path();
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        run("systemctl", &["--user", "daemon-reload"])
+    }
+
+    pub fn start() -> Result<(), String> {
+        run("systemctl", &["--user", "start", UNIT_NAME])
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    use super::run;
+
+    fn label() -> String {
+        format!("{}.backend", crate::paths::identifier())
+    }
+
+    fn plist_path() -> PathBuf {
+        crate::paths::home_dir()
+            .join("Library")
+            .join("LaunchAgents")
+            .join(format!("{}.plist", label()))
+    }
+
+    fn domain() -> Result<String, String> {
+        let output = Command::new("id")
+            .arg("-u")
+            .output()
+            .map_err(|e| format!("Failed to find the user id: {}", e))?;
+        Ok(format!("gui/{}", String::from_utf8_lossy(&output.stdout).trim()))
+    }
+
+    fn escape(value: &str) -> String {
+        value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+    }
+
+    pub fn installed() -> bool {
+        plist_path().exists()
+    }
+
+    /// Loaded at the next login, or by `start`, as the app runs the backend until it quits
+    pub fn install(exe: &Path, args: &[String]) -> Result<(), String> {
+        let arguments: String = std::iter::once(exe.to_string_lossy().into_owned())
+            .chain(args.iter().cloned())
+            .map(|arg| format!("    <string>{}</string>\n", escape(&arg)))
+            .collect();
+        let plist = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n\
+             <dict>\n\
+             \x20 <key>Label</key>\n\
+             \x20 <string>{}</string>\n\
+             \x20 <key>ProgramArguments</key>\n\
+             \x20 <array>\n\
+             {}\
+             \x20 </array>\n\
+             \x20 <key>RunAtLoad</key>\n\
+             \x20 <true/>\n\
+             \x20 <key>KeepAlive</key>\n\
+             \x20 <dict>\n\
+             \x20   <key>SuccessfulExit</key>\n\
+             \x20   <false/>\n\
+             \x20 </dict>\n\
+             </dict>\n\
+             </plist>\n",
+            label(),
+            arguments
+        );
+        let path = plist_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(&path, plist).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn uninstall() -> Result<(), String> {
+        let _ = run("launchctl", &["bootout", &format!("{}/{}", domain()?, label())]);
+        let path = plist_path();
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))
+    }
+
+    pub fn start() -> Result<(), String> {
+        let domain = domain()?;
+        // Loading it runs it, an agent loaded since login only needs a kick
+        run("launchctl", &["bootstrap", &domain, &plist_path().to_string_lossy()])
+            .or_else(|_| run("launchctl", &["kickstart", &format!("{}/{}", domain, label())]))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::os::windows::process::CommandExt;
+    use std::path::Path;
+    use std::process::Command;
+
+    use super::run;
+
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    const VALUE_NAME: &str = "TSW IO backend";
+    /// Keeps the backend running after the app that started it quits
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+
+    /// The command line Windows runs at logon
+    fn command_line() -> Option<String> {
+        let output = Command::new("reg")
+            .args(["query", RUN_KEY, "/v", VALUE_NAME])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        // `    TSW IO backend    REG_SZ    "C:\...\tsw_io.exe" backend start ...`
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        stdout
+            .lines()
+            .find_map(|line| line.split_once("REG_SZ").map(|(_, value)| value.trim().to_string()))
+    }
+
+    pub fn installed() -> bool {
+        command_line().is_some()
+    }
+
+    pub fn install(exe: &Path, args: &[String]) -> Result<(), String> {
+        let command: Vec<String> = std::iter::once(exe.to_string_lossy().into_owned())
+            .chain(args.iter().cloned())
+            .map(|arg| format!("\"{}\"", arg))
+            .collect();
+        run("reg", &["add", RUN_KEY, "/v", VALUE_NAME, "/t", "REG_SZ", "/d", &command.join(" "), "/f"])
+    }
+
+    pub fn uninstall() -> Result<(), String> {
+        run("reg", &["delete", RUN_KEY, "/v", VALUE_NAME, "/f"])
+    }
+
+    /// Logon entries have no service manager to ask, so the app starts it itself
+    pub fn start() -> Result<(), String> {
+        let exe = std::env::current_exe().map_err(|e| format!("Failed to find the app: {}", e))?;
+        let command_line = command_line().ok_or_else(|| "The backend service is not installed".to_string())?;
+        // Everything after the quoted program
+        let args = command_line
+            .strip_prefix('"')
+            .and_then(|rest| rest.split_once('"'))
+            .map(|(_, args)| args.trim().to_string())
+            .unwrap_or_default();
+        Command::new(exe)
+            .raw_arg(args)
+            .creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP)
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to start the backend service: {}", e))
+    }
+}
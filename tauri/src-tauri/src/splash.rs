@@ -0,0 +1,43 @@
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+
+const WINDOW_LABEL: &str = "splash";
+
+/// Open the splash screen, or return it if it's already showing
+pub fn open(handle: &AppHandle) -> tauri::Result<WebviewWindow> {
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        return Ok(window);
+    }
+
+    let splash_html = include_str!("../splash.html");
+    let splash_url = format!("data:text/html,{}", urlencoding::encode(splash_html));
+
+    WebviewWindowBuilder::new(
+        handle,
+        WINDOW_LABEL,
+        WebviewUrl::External(splash_url.parse().unwrap()),
+    )
+    .title("TSW IO")
+    .inner_size(400.0, 300.0)
+    .resizable(false)
+    .decorations(false)
+    .center()
+    .build()
+}
+
+pub fn is_open(handle: &AppHandle) -> bool {
+    handle.get_webview_window(WINDOW_LABEL).is_some()
+}
+
+/// Replace the status line under the spinner
+pub fn set_status(window: &WebviewWindow, status: &str) {
+    let _ = window.eval(format!(
+        "document.getElementById('status').textContent = {}",
+        serde_json::to_string(status).unwrap_or_default()
+    ));
+}
+
+pub fn close(handle: &AppHandle) {
+    if let Some(window) = handle.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+}
@@ -0,0 +1,279 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use keyring::Entry;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::webview::cookie::{Cookie, SameSite};
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::accounts;
+use crate::config::{ConfigState, ShellConfig, SsoConfig};
+use crate::navigation;
+use crate::network;
+use crate::profile::ProfileState;
+use crate::{secrets, tls};
+
+/// Where the identity provider sends the browser back to, the OS hands it to the app
+pub const REDIRECT_URI: &str = "tsw-io://sso/callback";
+/// Service the token is filed under in the OS credential store, one account per app account
+const SERVICE: &str = "tsw_io";
+const TOKEN_ACCOUNT: &str = "sso_token";
+/// A login not finished by then is given up on
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// For identity providers other than the backend, which are reached through `network`
+const EXCHANGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The login started in the system browser, only one at a time
+struct PendingLogin {
+    state: String,
+    /// PKCE secret, only its hash went to the browser
+    verifier: String,
+    started: Instant,
+}
+
+static PENDING: Mutex<Option<PendingLogin>> = Mutex::new(None);
+
+fn config(handle: &AppHandle) -> Result<SsoConfig, String> {
+    handle
+        .state::<ConfigState>()
+        .get()
+        .sso
+        .ok_or_else(|| "Single sign-on isn't set up, see `sso` in the settings".to_string())
+}
+
+/// `url` as configured, on the backend when it starts with `/`
+fn resolve(handle: &AppHandle, url: &str) -> Result<Url, String> {
+    let url = match url.starts_with('/') {
+        true => crate::api::backend_url(&handle.state::<ProfileState>().get().base_url(), url),
+        false => url.to_string(),
+    };
+    let parsed = url.parse::<Url>().map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("{} must start with http:// or https://", url));
+    }
+    Ok(parsed)
+}
+
+/// Each account signs in on its own, like it keeps its own cookies
+fn token_entry(handle: &AppHandle) -> Result<Entry, String> {
+    let account = match accounts::active(handle) {
+        Some(name) => format!("{}:{}", TOKEN_ACCOUNT, name),
+        None => TOKEN_ACCOUNT.to_string(),
+    };
+    Entry::new(SERVICE, &account).map_err(|e| format!("Failed to open credential store: {}", e))
+}
+
+fn stored_token(handle: &AppHandle) -> Option<String> {
+    match token_entry(handle).and_then(|entry| entry.get_password().map_err(|e| e.to_string())) {
+        Ok(token) => Some(token),
+        Err(e) => {
+            tracing::debug!("No single sign-on token stored: {}", e);
+            None
+        }
+    }
+}
+
+/// Tells the backend which cookie its pages get the token in, see `TswIo.Sso`
+pub fn sidecar_env(config: &ShellConfig) -> Vec<(&'static str, String)> {
+    match &config.sso {
+        Some(sso) => vec![("TSW_IO_SSO_COOKIE", sso.cookie_name.clone())],
+        None => Vec::new(),
+    }
+}
+
+/// Start the login in the system browser, it comes back through `REDIRECT_URI`
+pub fn sign_in(handle: &AppHandle) -> Result<(), String> {
+    let config = config(handle)?;
+    let verifier = secrets::random_hex(32)?;
+    let state = secrets::random_hex(16)?;
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+    let mut url = resolve(handle, &config.authorize_url)?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("redirect_uri", REDIRECT_URI)
+        .append_pair("state", &state)
+        .append_pair("code_challenge", &challenge)
+        .append_pair("code_challenge_method", "S256");
+    if let Some(client_id) = &config.client_id {
+        url.query_pairs_mut().append_pair("client_id", client_id);
+    }
+    *PENDING.lock().unwrap() = Some(PendingLogin {
+        state,
+        verifier,
+        started: Instant::now(),
+    });
+    navigation::open_external(&url)?;
+    tracing::info!("Started single sign-on in the system browser");
+    Ok(())
+}
+
+/// Finish the login the system browser handed back, `query` being that of the `REDIRECT_URI` link
+///
+/// Talks to the identity provider, so it runs on a thread of its own.
+pub fn complete(handle: &AppHandle, query: String) {
+    let handle = handle.clone();
+    std::thread::spawn(move || match finish(&handle, &query) {
+        Ok(()) => tracing::info!("Signed in with single sign-on"),
+        Err(e) => {
+            tracing::warn!("Single sign-on failed: {}", e);
+            handle
+                .dialog()
+                .message(e)
+                .title("Sign-in failed")
+                .kind(MessageDialogKind::Error)
+                .show(|_| {});
+        }
+    });
+}
+
+fn finish(handle: &AppHandle, query: &str) -> Result<(), String> {
+    let params: HashMap<String, String> = Url::parse(&format!("{}?{}", REDIRECT_URI, query))
+        .map_err(|e| format!("Invalid sign-in link: {}", e))?
+        .query_pairs()
+        .into_owned()
+        .collect();
+    if let Some(error) = params.get("error") {
+        let reason = params.get("error_description").unwrap_or(error);
+        return Err(format!("The identity provider turned the sign-in down: {}", reason));
+    }
+    // Only a link with the login's state uses it up, a forged one leaves it waiting
+    let pending = {
+        let mut pending = PENDING.lock().unwrap();
+        let started = pending
+            .as_ref()
+            .ok_or_else(|| "No sign-in was started from this app".to_string())?;
+        let state = params.get("state").map(String::as_str).unwrap_or_default();
+        if !secrets::constant_time_eq(state, &started.state) {
+            return Err("The sign-in doesn't match the one started here, start it again".to_string());
+        }
+        pending.take().unwrap()
+    };
+    if pending.started.elapsed() > LOGIN_TIMEOUT {
+        return Err("The sign-in took too long, start it again".to_string());
+    }
+    let code = params
+        .get("code")
+        .ok_or_else(|| "The identity provider sent no code".to_string())?;
+
+    let token = exchange(handle, &config(handle)?, code, &pending.verifier)?;
+    token_entry(handle)?
+        .set_password(&token)
+        .map_err(|e| format!("Failed to store the token: {}", e))?;
+    crate::open_main_window(handle);
+    if inject(handle) {
+        if let Some(window) = handle.get_webview_window("main") {
+            let _ = window.reload();
+        }
+    }
+    Ok(())
+}
+
+/// Trade the code for a token, proving with the verifier that this app started the login
+fn exchange(handle: &AppHandle, config: &SsoConfig, code: &str, verifier: &str) -> Result<String, String> {
+    let url = resolve(handle, &config.token_url)?;
+    let on_backend = config.token_url.starts_with('/');
+    // The backend client only trusts the backend's own certificate under TLS
+    let client = match on_backend {
+        true => tls::backend_client()?,
+        false => network::client_builder()
+            .timeout(EXCHANGE_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?,
+    };
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("code_verifier", verifier),
+        ("redirect_uri", REDIRECT_URI),
+    ];
+    if let Some(client_id) = &config.client_id {
+        form.push(("client_id", client_id));
+    }
+    let response = client
+        .post(url.clone())
+        .form(&form)
+        .send()
+        .map_err(|e| format!("Failed to reach {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} rejected the sign-in: {}", url, response.status()));
+    }
+    let body: serde_json::Value = response
+        .json()
+        .map_err(|e| format!("Invalid response from {}: {}", url, e))?;
+    body["access_token"]
+        .as_str()
+        .or_else(|| body["token"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("Invalid response from {}: no token", url))
+}
+
+/// The cookie the backend pages get the token in, without the token when removing it
+fn cookie(handle: &AppHandle, config: &SsoConfig, token: String) -> Option<Cookie<'static>> {
+    let base_url = handle.state::<ProfileState>().get().base_url().parse::<Url>().ok()?;
+    Some(
+        Cookie::build((config.cookie_name.clone(), token))
+            .domain(base_url.host_str()?.to_string())
+            .path("/")
+            .http_only(true)
+            .secure(base_url.scheme() == "https")
+            .same_site(SameSite::Strict)
+            .build(),
+    )
+}
+
+/// Put the stored token in the main window's session, returning whether there was one
+///
+/// Webviews wait on the event loop to change cookies, never call it from there.
+pub fn inject(handle: &AppHandle) -> bool {
+    let (Ok(config), Some(token)) = (config(handle), stored_token(handle)) else {
+        return false;
+    };
+    let Some(window) = handle.get_webview_window("main") else {
+        return false;
+    };
+    let Some(cookie) = cookie(handle, &config, token) else {
+        return false;
+    };
+    match window.set_cookie(cookie) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!("Failed to hand the single sign-on token to the main window: {}", e);
+            false
+        }
+    }
+}
+
+/// Forget the token, in the credential store and the main window
+fn sign_out(handle: &AppHandle) -> Result<(), String> {
+    match token_entry(handle)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(format!("Failed to remove the token: {}", e)),
+    }
+    let config = config(handle)?;
+    let cookie = cookie(handle, &config, String::new());
+    if let (Some(window), Some(cookie)) = (handle.get_webview_window("main"), cookie) {
+        window
+            .delete_cookie(cookie)
+            .map_err(|e| format!("Failed to sign the main window out: {}", e))?;
+        let _ = window.reload();
+    }
+    tracing::info!("Signed out of single sign-on");
+    Ok(())
+}
+
+#[tauri::command]
+pub fn sso_sign_in(app: AppHandle) -> Result<(), String> {
+    sign_in(&app)
+}
+
+/// Async so that changing cookies doesn't wait on the event loop it runs on
+#[tauri::command]
+pub async fn sso_sign_out(app: AppHandle) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || sign_out(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,284 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use sysinfo::{Pid, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+const LOCK_FILE: &str = "tsw_io.lock";
+/// How often the lock file is rewritten to show it is still held
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// A lock nobody refreshed for this long was left behind by a crash
+const STALE_AFTER: Duration = Duration::from_secs(120);
+
+/// Folder names (lowercase prefixes) that sync clients create, with the client's name
+const CLOUD_FOLDERS: &[(&str, &str)] = &[
+    ("dropbox", "Dropbox"),
+    ("onedrive", "OneDrive"),
+    ("google drive", "Google Drive"),
+    ("googledrive", "Google Drive"),
+    ("icloud drive", "iCloud Drive"),
+    ("mobile documents", "iCloud Drive"),
+];
+
+/// File systems that store their files on another machine
+#[cfg(unix)]
+const NETWORK_FILE_SYSTEMS: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smbfs", "smb3", "afpfs", "webdav", "davfs", "fuse.sshfs", "9p",
+];
+
+/// Why other machines may access the data directory too
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SharedLocation {
+    /// Mounted from another machine, with the file system type when known
+    NetworkShare(Option<String>),
+    /// Inside a folder a sync client copies to other machines
+    CloudSync(&'static str),
+}
+
+impl fmt::Display for SharedLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SharedLocation::NetworkShare(Some(file_system)) => {
+                write!(f, "a network share ({})", file_system)
+            }
+            SharedLocation::NetworkShare(None) => write!(f, "a network share"),
+            SharedLocation::CloudSync(client) => write!(f, "a folder synced by {}", client),
+        }
+    }
+}
+
+/// Whether `dir` is on a network share or in a cloud-synced folder
+pub fn detect(dir: &Path) -> Option<SharedLocation> {
+    let cloud = dir.components().find_map(|component| {
+        let name = component.as_os_str().to_string_lossy().to_lowercase();
+        CLOUD_FOLDERS
+            .iter()
+            .find(|(prefix, _)| name.starts_with(prefix))
+            .map(|(_, client)| SharedLocation::CloudSync(client))
+    });
+    cloud.or_else(|| network_file_system(dir))
+}
+
+#[cfg(target_os = "linux")]
+fn network_file_system(dir: &Path) -> Option<SharedLocation> {
+    // Lines are `device mount_point type options ...` with spaces in paths escaped as \040
+    let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+    let mounts = mounts.lines().filter_map(|line| {
+        let mut fields = line.split(' ');
+        let _device = fields.next()?;
+        let mount_point = fields.next()?.replace("\\040", " ");
+        Some((PathBuf::from(mount_point), fields.next()?.to_string()))
+    });
+    network_mount(dir, mounts)
+}
+
+#[cfg(target_os = "macos")]
+fn network_file_system(dir: &Path) -> Option<SharedLocation> {
+    // Lines are `device on mount_point (type, options...)`
+    let output = std::process::Command::new("mount").output().ok()?;
+    let mounts = String::from_utf8_lossy(&output.stdout).into_owned();
+    let mounts = mounts.lines().filter_map(|line| {
+        let (_device, rest) = line.split_once(" on ")?;
+        let (mount_point, details) = rest.rsplit_once(" (")?;
+        let file_system = details.split([',', ')']).next()?;
+        Some((PathBuf::from(mount_point), file_system.to_string()))
+    });
+    network_mount(dir, mounts)
+}
+
+/// File system of the mount holding `dir`, the one with the longest matching mount point
+#[cfg(unix)]
+fn network_mount(
+    dir: &Path,
+    mounts: impl Iterator<Item = (PathBuf, String)>,
+) -> Option<SharedLocation> {
+    let (_, file_system) = mounts
+        .filter(|(mount_point, _)| dir.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.as_os_str().len())?;
+    NETWORK_FILE_SYSTEMS
+        .contains(&file_system.as_str())
+        .then_some(SharedLocation::NetworkShare(Some(file_system)))
+}
+
+#[cfg(windows)]
+fn network_file_system(dir: &Path) -> Option<SharedLocation> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Component, Prefix};
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::GetDriveTypeW;
+
+    /// `GetDriveTypeW` result for mapped network drives
+    const DRIVE_REMOTE: u32 = 4;
+
+    match dir.components().next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::UNC(..) | Prefix::VerbatimUNC(..) => Some(SharedLocation::NetworkShare(None)),
+            Prefix::Disk(_) | Prefix::VerbatimDisk(_) => {
+                let root: Vec<u16> = Path::new(prefix.as_os_str())
+                    .join("\\")
+                    .as_os_str()
+                    .encode_wide()
+                    .chain(Some(0))
+                    .collect();
+                let drive_type = unsafe { GetDriveTypeW(PCWSTR(root.as_ptr())) };
+                (drive_type == DRIVE_REMOTE).then_some(SharedLocation::NetworkShare(None))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn network_file_system(_dir: &Path) -> Option<SharedLocation> {
+    None
+}
+
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Tell the user once per run that a shared data directory can get corrupted
+pub fn warn_shared(handle: &AppHandle, dir: &Path, location: &SharedLocation) {
+    if WARNED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    tracing::warn!(dir = %dir.display(), "Data directory is in {}", location);
+    if crate::headless::enabled() {
+        return;
+    }
+    handle
+        .dialog()
+        .message(format!(
+            "Your TSW IO data is stored in {}: {}. If TSW IO runs on another computer \
+             with the same folder, the database can get damaged. TSW IO only starts while \
+             no other computer uses the folder, but moving the data to a local folder is safer.",
+            location,
+            dir.display()
+        ))
+        .title("TSW IO")
+        .kind(MessageDialogKind::Warning)
+        .show(|_| {});
+}
+
+/// Who holds the lock on a data directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct LockOwner {
+    host: String,
+    pid: u32,
+}
+
+impl LockOwner {
+    fn current() -> LockOwner {
+        LockOwner {
+            host: System::host_name().unwrap_or_else(|| "unknown".to_string()),
+            pid: std::process::id(),
+        }
+    }
+
+    /// Whether the owner is a process on this machine that no longer runs
+    fn is_dead_local_process(&self) -> bool {
+        if self.host != LockOwner::current().host {
+            return false;
+        }
+        let pid = Pid::from_u32(self.pid);
+        let mut system = System::new();
+        system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+        system.process(pid).is_none()
+    }
+}
+
+/// Lock file of the data directory the backend runs against, while one is held
+#[derive(Default)]
+pub struct DataDirLock(Mutex<Option<PathBuf>>);
+
+/// Take the lock on `data_dir` so no other machine runs a backend against the same files
+///
+/// A lock another machine (or another copy on this one) keeps refreshing can't be taken.
+/// One left behind by a crash is taken over once it goes stale.
+pub fn acquire(handle: &AppHandle, data_dir: &Path) -> Result<(), String> {
+    let path = data_dir.join(LOCK_FILE);
+    let owner = LockOwner::current();
+
+    if let Some(existing) = read_owner(&path) {
+        let age = std::fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .unwrap_or_default();
+        let ours = existing == owner;
+        if !ours && age < STALE_AFTER && !existing.is_dead_local_process() {
+            return Err(format!(
+                "{} is in use by TSW IO on {}",
+                data_dir.display(),
+                existing.host
+            ));
+        }
+        if !ours {
+            tracing::warn!(host = %existing.host, pid = existing.pid, "Taking over stale data directory lock");
+        }
+    }
+
+    std::fs::create_dir_all(data_dir)
+        .map_err(|e| format!("Failed to create {}: {}", data_dir.display(), e))?;
+    write_owner(&path, &owner)?;
+    tracing::info!(path = %path.display(), "Locked data directory");
+    *handle.state::<DataDirLock>().0.lock().unwrap() = Some(path);
+    start_refresh(handle.clone());
+    Ok(())
+}
+
+/// Remove the lock file, if one is held
+pub fn release(handle: &AppHandle) {
+    let Some(lock) = handle.try_state::<DataDirLock>() else {
+        return;
+    };
+    let held = lock.0.lock().unwrap().take();
+    if let Some(path) = held {
+        // Only remove the lock if it wasn't taken over in the meantime
+        if read_owner(&path) == Some(LockOwner::current()) {
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::warn!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
+        tracing::info!(path = %path.display(), "Unlocked data directory");
+    }
+}
+
+/// Whether a data directory lock is held, meaning the directory is shared
+pub fn is_locked(handle: &AppHandle) -> bool {
+    handle
+        .try_state::<DataDirLock>()
+        .is_some_and(|lock| lock.0.lock().unwrap().is_some())
+}
+
+fn read_owner(path: &Path) -> Option<LockOwner> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn write_owner(path: &Path, owner: &LockOwner) -> Result<(), String> {
+    let contents = serde_json::to_string(owner).map_err(|e| e.to_string())?;
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+static REFRESH_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Keep rewriting the held lock file so other machines see it is in use
+fn start_refresh(handle: AppHandle) {
+    if REFRESH_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(REFRESH_INTERVAL);
+        let held = handle.state::<DataDirLock>().0.lock().unwrap().clone();
+        if let Some(path) = held {
+            if let Err(e) = write_owner(&path, &LockOwner::current()) {
+                tracing::warn!("Failed to refresh data directory lock: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,257 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::audit::{self, AuditAction};
+use crate::recovery;
+
+/// Crashes within `CRASH_WINDOW` that count as a crash loop
+const MAX_CRASHES: usize = 3;
+const CRASH_WINDOW: Duration = Duration::from_secs(5 * 60);
+const RESTART_DELAY: Duration = Duration::from_secs(2);
+
+/// Lifecycle of the bundled backend as the shell sees it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupervisorState {
+    /// Not running, either not started yet or stopped on purpose
+    Idle,
+    Starting,
+    Running,
+    /// Crashed while running and is about to be relaunched
+    Restarting,
+    /// Crashed too often, waiting for the user to pick a recovery option
+    CrashLoop,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Event {
+    Launching,
+    Ready,
+    Exited,
+    Stopped,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    None,
+    Restart,
+    Recover,
+}
+
+struct Inner {
+    state: SupervisorState,
+    crashes: VecDeque<Instant>,
+    /// Start the backend without migrations and with a read-only database
+    safe_mode: bool,
+}
+
+impl Inner {
+    fn transition(&mut self, event: Event, now: Instant) -> Action {
+        let (state, action) = match (self.state, event) {
+            (_, Event::Launching) => (SupervisorState::Starting, Action::None),
+            (_, Event::Stopped) => (SupervisorState::Idle, Action::None),
+            (SupervisorState::Starting, Event::Ready) => (SupervisorState::Running, Action::None),
+            (SupervisorState::Starting | SupervisorState::Running, Event::Exited) => {
+                self.crashes.push_back(now);
+                while self
+                    .crashes
+                    .front()
+                    .is_some_and(|crash| now.duration_since(*crash) > CRASH_WINDOW)
+                {
+                    self.crashes.pop_front();
+                }
+
+                if self.crashes.len() >= MAX_CRASHES {
+                    (SupervisorState::CrashLoop, Action::Recover)
+                } else if self.state == SupervisorState::Running {
+                    (SupervisorState::Restarting, Action::Restart)
+                } else {
+                    // The startup failure screen takes it from here
+                    (SupervisorState::Idle, Action::None)
+                }
+            }
+            (state, _) => (state, Action::None),
+        };
+        if state != self.state {
+            tracing::info!(from = ?self.state, to = ?state, "Backend supervisor state changed");
+        }
+        self.state = state;
+        action
+    }
+}
+
+/// Restarts the backend after crashes, until it crashes too often
+pub struct Supervisor(Mutex<Inner>);
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Supervisor(Mutex::new(Inner {
+            state: SupervisorState::Idle,
+            crashes: VecDeque::new(),
+            safe_mode: false,
+        }))
+    }
+}
+
+fn send(handle: &AppHandle, event: Event) -> Action {
+    handle
+        .state::<Supervisor>()
+        .0
+        .lock()
+        .unwrap()
+        .transition(event, Instant::now())
+}
+
+pub fn state(handle: &AppHandle) -> SupervisorState {
+    handle.state::<Supervisor>().0.lock().unwrap().state
+}
+
+pub fn safe_mode(handle: &AppHandle) -> bool {
+    handle.state::<Supervisor>().0.lock().unwrap().safe_mode
+}
+
+/// Pick the mode for the next launch, forgetting past crashes
+pub fn set_safe_mode(handle: &AppHandle, safe_mode: bool) {
+    let supervisor = handle.state::<Supervisor>();
+    let mut inner = supervisor.0.lock().unwrap();
+    inner.safe_mode = safe_mode;
+    inner.crashes.clear();
+}
+
+pub fn backend_launching(handle: &AppHandle) {
+    send(handle, Event::Launching);
+}
+
+pub fn backend_ready(handle: &AppHandle) {
+    send(handle, Event::Ready);
+}
+
+pub fn backend_stopped(handle: &AppHandle) {
+    send(handle, Event::Stopped);
+}
+
+/// React to the sidecar exiting on its own
+pub fn backend_exited(handle: &AppHandle) {
+    match send(handle, Event::Exited) {
+        Action::Restart => {
+            tracing::warn!("Backend crashed, restarting");
+            audit::record(handle, AuditAction::BackendRestart, "Restarted after a crash");
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(RESTART_DELAY);
+                // The launch hides the main window until the new backend is ready
+                if let Err(e) = crate::launch(&handle) {
+                    tracing::error!("Failed to restart backend: {}", e);
+                }
+            });
+        }
+        Action::Recover => {
+            tracing::error!(crashes = MAX_CRASHES, "Backend is crash looping");
+            if let Some(window) = handle.get_webview_window("main") {
+                let _ = window.destroy();
+            }
+            recovery::show(handle);
+        }
+        Action::None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inner(state: SupervisorState) -> Inner {
+        Inner {
+            state,
+            crashes: VecDeque::new(),
+            safe_mode: false,
+        }
+    }
+
+    #[test]
+    fn runs_once_ready() {
+        let mut inner = inner(SupervisorState::Idle);
+        let now = Instant::now();
+
+        assert_eq!(inner.transition(Event::Launching, now), Action::None);
+        assert_eq!(inner.state, SupervisorState::Starting);
+        assert_eq!(inner.transition(Event::Ready, now), Action::None);
+        assert_eq!(inner.state, SupervisorState::Running);
+    }
+
+    #[test]
+    fn restarts_after_a_crash() {
+        let mut inner = inner(SupervisorState::Running);
+
+        assert_eq!(inner.transition(Event::Exited, Instant::now()), Action::Restart);
+        assert_eq!(inner.state, SupervisorState::Restarting);
+    }
+
+    #[test]
+    fn leaves_a_failed_start_to_the_failure_screen() {
+        let mut inner = inner(SupervisorState::Starting);
+
+        assert_eq!(inner.transition(Event::Exited, Instant::now()), Action::None);
+        assert_eq!(inner.state, SupervisorState::Idle);
+    }
+
+    #[test]
+    fn gives_up_after_too_many_crashes() {
+        let mut inner = inner(SupervisorState::Running);
+        let now = Instant::now();
+
+        for crash in 1..MAX_CRASHES {
+            inner.transition(Event::Launching, now);
+            inner.transition(Event::Ready, now);
+            assert_eq!(inner.transition(Event::Exited, now), Action::Restart, "crash {}", crash);
+        }
+        inner.transition(Event::Launching, now);
+        inner.transition(Event::Ready, now);
+
+        assert_eq!(inner.transition(Event::Exited, now), Action::Recover);
+        assert_eq!(inner.state, SupervisorState::CrashLoop);
+    }
+
+    #[test]
+    fn forgets_crashes_outside_the_window() {
+        let mut inner = inner(SupervisorState::Running);
+        let start = Instant::now();
+
+        for _ in 1..MAX_CRASHES {
+            inner.transition(Event::Exited, start);
+            inner.transition(Event::Launching, start);
+            inner.transition(Event::Ready, start);
+        }
+        let later = start + CRASH_WINDOW + Duration::from_secs(1);
+
+        assert_eq!(inner.transition(Event::Exited, later), Action::Restart);
+        assert_eq!(inner.crashes.len(), 1);
+    }
+
+    #[test]
+    fn ignores_exits_once_stopped() {
+        let mut inner = inner(SupervisorState::Running);
+        let now = Instant::now();
+
+        inner.transition(Event::Stopped, now);
+
+        assert_eq!(inner.transition(Event::Exited, now), Action::None);
+        assert_eq!(inner.state, SupervisorState::Idle);
+        assert!(inner.crashes.is_empty());
+    }
+
+    #[test]
+    fn stays_in_a_crash_loop_until_launched() {
+        let mut inner = inner(SupervisorState::CrashLoop);
+        let now = Instant::now();
+
+        assert_eq!(inner.transition(Event::Ready, now), Action::None);
+        assert_eq!(inner.transition(Event::Exited, now), Action::None);
+        assert_eq!(inner.state, SupervisorState::CrashLoop);
+        inner.transition(Event::Launching, now);
+        assert_eq!(inner.state, SupervisorState::Starting);
+    }
+}
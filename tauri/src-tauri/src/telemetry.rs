@@ -0,0 +1,78 @@
+use serde::Serialize;
+use serde_json::json;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::config::ConfigState;
+use crate::failure::FailureKind;
+use crate::features::FeatureFlags;
+
+/// Endpoint release builds report to when the config doesn't name one
+const BUILT_IN_TELEMETRY_URL: Option<&str> = option_env!("TSW_IO_TELEMETRY_URL");
+const TELEMETRY_TIMEOUT_SECS: u64 = 10;
+/// Feature flag maintainers can turn off remotely to stop all reporting, taking effect as soon as
+/// the flag file is fetched
+const KILL_SWITCH_FLAG: &str = "telemetry";
+
+/// How a startup attempt ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupOutcome {
+    Ready,
+    /// The backend exited or never answered the health check in time
+    Failed,
+    /// The sidecar couldn't be started at all
+    SpawnFailed,
+}
+
+/// Anonymous summary of one startup, without paths, names or settings
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupReport {
+    pub outcome: StartupOutcome,
+    pub time_to_ready_ms: Option<u64>,
+    pub health_check_attempts: u32,
+    pub failure: Option<FailureKind>,
+}
+
+/// Where telemetry goes, or `None` without consent, when switched off or air-gapped
+fn endpoint(handle: &AppHandle) -> Option<String> {
+    let config = handle.state::<ConfigState>().get();
+    if config.offline
+        || !config.telemetry
+        || !handle.state::<FeatureFlags>().is_enabled_now(KILL_SWITCH_FLAG, &config)
+    {
+        return None;
+    }
+    config
+        .telemetry_url
+        .or_else(|| BUILT_IN_TELEMETRY_URL.map(str::to_string))
+}
+
+/// Send a startup report in the background if the user opted in
+pub fn report_startup(handle: &AppHandle, report: StartupReport) {
+    let Some(url) = endpoint(handle) else {
+        return;
+    };
+
+    let body = json!({
+        "event": "startup",
+        "version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "startup": report,
+    });
+
+    std::thread::spawn(move || {
+        let result = crate::network::client().and_then(|client| {
+            client
+                .post(url)
+                .timeout(Duration::from_secs(TELEMETRY_TIMEOUT_SECS))
+                .json(&body)
+                .send()
+        });
+        // Telemetry is best effort, a failure is never worth more than a debug line
+        if let Err(e) = result {
+            tracing::debug!("Failed to send telemetry: {}", e);
+        }
+    });
+}
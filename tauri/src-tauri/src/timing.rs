@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::paths::AppPaths;
+
+const HISTORY_FILE: &str = "startup_history.json";
+/// Successful launches the budget is based on
+const HISTORY_LEN: usize = 10;
+/// Time to wait when nothing is known, as with an external backend
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+/// Time to wait on the first launch of a new version, which runs its migrations
+const MIGRATION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// Normal launches may take this many times as long as the slowest recent one
+const TIMEOUT_FACTOR: u32 = 3;
+const MIN_TIMEOUT: Duration = Duration::from_secs(20);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Poll interval after a clean previous run, when the backend is expected to be quick
+const FAST_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Milestones of a launch, in the order they normally happen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// The sidecar process was started
+    Spawned,
+    /// The backend answered the health check, ready or not
+    FirstResponse,
+    /// The health check passed
+    Ready,
+    MainWindow,
+}
+
+/// Milliseconds from the start of the launch to each stage, if reached
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StartupTimings {
+    pub spawned_ms: Option<u64>,
+    pub first_response_ms: Option<u64>,
+    pub ready_ms: Option<u64>,
+    pub main_window_ms: Option<u64>,
+}
+
+impl StartupTimings {
+    fn slot(&mut self, stage: Stage) -> &mut Option<u64> {
+        match stage {
+            Stage::Spawned => &mut self.spawned_ms,
+            Stage::FirstResponse => &mut self.first_response_ms,
+            Stage::Ready => &mut self.ready_ms,
+            Stage::MainWindow => &mut self.main_window_ms,
+        }
+    }
+}
+
+/// Timings of the current launch, reset whenever the backend is relaunched
+pub struct StartupClock(pub Mutex<(Instant, StartupTimings)>);
+
+impl Default for StartupClock {
+    fn default() -> Self {
+        StartupClock(Mutex::new((Instant::now(), StartupTimings::default())))
+    }
+}
+
+pub fn start(handle: &AppHandle) {
+    *handle.state::<StartupClock>().0.lock().unwrap() = (Instant::now(), StartupTimings::default());
+}
+
+/// Record the first time `stage` is reached in this launch
+pub fn mark(handle: &AppHandle, stage: Stage) {
+    let clock = handle.state::<StartupClock>();
+    let mut current = clock.0.lock().unwrap();
+    let elapsed_ms = current.0.elapsed().as_millis() as u64;
+    let slot = current.1.slot(stage);
+    if slot.is_none() {
+        *slot = Some(elapsed_ms);
+        tracing::debug!(?stage, elapsed_ms, "Startup stage reached");
+    }
+}
+
+pub fn timings(handle: &AppHandle) -> StartupTimings {
+    handle.state::<StartupClock>().0.lock().unwrap().1.clone()
+}
+
+/// How long to wait for the backend, and how long it usually takes
+#[derive(Debug, Clone)]
+pub struct StartupBudget {
+    pub timeout: Duration,
+    /// Typical time to ready, `None` when there is no history for this version yet
+    pub expected: Option<Duration>,
+    /// Time between health checks
+    pub poll_interval: Duration,
+}
+
+impl StartupBudget {
+    /// Check in short steps, for a backend that started fine last time
+    pub fn polling_fast(self) -> Self {
+        StartupBudget {
+            poll_interval: FAST_POLL_INTERVAL,
+            ..self
+        }
+    }
+}
+
+impl Default for StartupBudget {
+    fn default() -> Self {
+        StartupBudget {
+            timeout: DEFAULT_TIMEOUT,
+            expected: None,
+            poll_interval: POLL_INTERVAL,
+        }
+    }
+}
+
+/// Times to ready of recent launches of the bundled backend
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StartupHistory {
+    /// Shell version the launches were made with, a new one may bring migrations
+    version: String,
+    ready_ms: VecDeque<u64>,
+}
+
+fn history_path(handle: &AppHandle) -> PathBuf {
+    handle.state::<AppPaths>().log_dir.join(HISTORY_FILE)
+}
+
+/// History for the running version, empty after an update
+fn load_history(handle: &AppHandle) -> StartupHistory {
+    std::fs::read_to_string(history_path(handle))
+        .ok()
+        .and_then(|contents| serde_json::from_str::<StartupHistory>(&contents).ok())
+        .filter(|history| history.version == env!("CARGO_PKG_VERSION"))
+        .unwrap_or_else(|| StartupHistory {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            ready_ms: VecDeque::new(),
+        })
+}
+
+/// Budget for launching the bundled backend, from how long recent launches took
+pub fn budget(handle: &AppHandle) -> StartupBudget {
+    let history = load_history(handle);
+    let Some(slowest) = history.ready_ms.iter().max() else {
+        return StartupBudget {
+            timeout: MIGRATION_TIMEOUT,
+            ..StartupBudget::default()
+        };
+    };
+    let mut sorted: Vec<u64> = history.ready_ms.iter().copied().collect();
+    sorted.sort_unstable();
+    StartupBudget {
+        timeout: (Duration::from_millis(*slowest) * TIMEOUT_FACTOR)
+            .clamp(MIN_TIMEOUT, DEFAULT_TIMEOUT),
+        expected: Some(Duration::from_millis(sorted[sorted.len() / 2])),
+        poll_interval: POLL_INTERVAL,
+    }
+}
+
+/// Remember how long the bundled backend took to become ready
+pub fn record_ready(handle: &AppHandle, ready_ms: u64) {
+    let mut history = load_history(handle);
+    if history.ready_ms.len() == HISTORY_LEN {
+        history.ready_ms.pop_front();
+    }
+    history.ready_ms.push_back(ready_ms);
+
+    let path = history_path(handle);
+    let result = serde_json::to_string(&history)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| std::fs::write(&path, contents).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        tracing::warn!("Failed to write {}: {}", path.display(), e);
+    }
+}
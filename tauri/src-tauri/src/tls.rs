@@ -0,0 +1,243 @@
+use rcgen::{CertificateParams, KeyPair};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+use time::OffsetDateTime;
+
+/// Whether the webview can be made to trust the local certificate on this platform
+///
+/// WebKitGTK has no user trust store the shell could add to.
+pub const SUPPORTED: bool = cfg!(any(target_os = "macos", windows));
+
+const CERT_FILE: &str = "localhost.pem";
+const KEY_FILE: &str = "localhost-key.pem";
+/// Webviews reject server certificates valid for more than 825 days
+const VALIDITY_DAYS: i64 = 800;
+/// Age at which the certificate is replaced, well before it expires
+const RENEW_AFTER: Duration = Duration::from_secs(700 * 24 * 60 * 60);
+
+/// Time allowed to connect to the backend, which runs on this machine
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+/// Upper bound for any backend request, callers can set a shorter one per request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Idle connections are kept this long, longer than the heartbeat interval
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// The only certificate the shell accepts from the backend, `None` for plain HTTP
+static PINNED: RwLock<Option<reqwest::Certificate>> = RwLock::new(None);
+/// PEM of the pinned certificate, for connections not made with reqwest
+static PINNED_PEM: RwLock<Option<Vec<u8>>> = RwLock::new(None);
+/// Bumped whenever the pinned certificate changes, so the shared client is rebuilt
+static PIN_GENERATION: AtomicU64 = AtomicU64::new(0);
+/// Client shared by every backend request, with the pin generation it was built for
+static CLIENT: RwLock<Option<(u64, reqwest::blocking::Client)>> = RwLock::new(None);
+
+/// Certificate and key the bundled backend serves HTTPS with
+#[derive(Debug, Clone)]
+pub struct LocalCertificate {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+impl LocalCertificate {
+    /// Environment telling the sidecar to serve HTTPS with this certificate
+    pub fn sidecar_env(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("TSW_IO_TLS_CERT", self.cert_path.display().to_string()),
+            ("TSW_IO_TLS_KEY", self.key_path.display().to_string()),
+        ]
+    }
+}
+
+/// Load the certificate in `dir`, generating and trusting a new one at first run or when it gets old
+pub fn ensure_certificate(dir: &Path) -> Result<LocalCertificate, String> {
+    let certificate = LocalCertificate {
+        cert_path: dir.join(CERT_FILE),
+        key_path: dir.join(KEY_FILE),
+    };
+    let age = std::fs::metadata(&certificate.cert_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    let fresh = age.is_some_and(|age| age < RENEW_AFTER) && certificate.key_path.exists();
+
+    if !fresh {
+        if certificate.cert_path.exists() {
+            untrust(&certificate.cert_path);
+        }
+        generate(&certificate)?;
+        trust(&certificate.cert_path);
+    }
+    pin(&certificate.cert_path)?;
+    Ok(certificate)
+}
+
+fn generate(certificate: &LocalCertificate) -> Result<(), String> {
+    let mut params = CertificateParams::new(vec!["localhost".to_string(), "127.0.0.1".to_string()])
+        .map_err(|e| format!("Failed to create certificate: {}", e))?;
+    let now = OffsetDateTime::now_utc();
+    params.not_before = now - time::Duration::days(1);
+    params.not_after = now + time::Duration::days(VALIDITY_DAYS);
+    let key = KeyPair::generate().map_err(|e| format!("Failed to generate key: {}", e))?;
+    let cert = params
+        .self_signed(&key)
+        .map_err(|e| format!("Failed to create certificate: {}", e))?;
+
+    if let Some(dir) = certificate.cert_path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    write_private(&certificate.key_path, &key.serialize_pem())?;
+    std::fs::write(&certificate.cert_path, cert.pem())
+        .map_err(|e| format!("Failed to write certificate: {}", e))?;
+    tracing::info!(path = %certificate.cert_path.display(), "Generated local TLS certificate");
+    Ok(())
+}
+
+/// Write a file only the current user can read
+pub(crate) fn write_private(path: &Path, contents: &str) -> Result<(), String> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    std::io::Write::write_all(&mut file, contents.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Make the webview accept the certificate by adding it to the user's trust store
+///
+/// The certificate only names this machine, so trusting it vouches for nothing else.
+/// The OS asks the user to confirm.
+fn trust(cert_path: &Path) {
+    let result = if cfg!(target_os = "macos") {
+        let keychain = std::env::var("HOME")
+            .map(|home| format!("{}/Library/Keychains/login.keychain-db", home))
+            .unwrap_or_default();
+        Command::new("security")
+            .args(["add-trusted-cert", "-r", "trustRoot", "-p", "ssl", "-k", &keychain])
+            .arg(cert_path)
+            .status()
+    } else if cfg!(windows) {
+        Command::new("certutil")
+            .args(["-user", "-addstore", "Root"])
+            .arg(cert_path)
+            .status()
+    } else {
+        tracing::warn!("Trusting the local certificate is not supported on this platform");
+        return;
+    };
+
+    match result {
+        Ok(status) if status.success() => tracing::info!("Trusted local TLS certificate"),
+        Ok(status) => tracing::warn!("Failed to trust local TLS certificate: {}", status),
+        Err(e) => tracing::warn!("Failed to trust local TLS certificate: {}", e),
+    }
+}
+
+/// Remove a certificate being replaced from the user's trust store, so renewals don't pile up roots
+fn untrust(cert_path: &Path) {
+    let thumbprint = match thumbprint(cert_path) {
+        Ok(thumbprint) => thumbprint,
+        Err(e) => {
+            tracing::warn!("Failed to untrust the old local TLS certificate: {}", e);
+            return;
+        }
+    };
+    let result = if cfg!(target_os = "macos") {
+        let keychain = std::env::var("HOME")
+            .map(|home| format!("{}/Library/Keychains/login.keychain-db", home))
+            .unwrap_or_default();
+        Command::new("security")
+            .args(["delete-certificate", "-t", "-Z", &thumbprint, &keychain])
+            .status()
+    } else if cfg!(windows) {
+        Command::new("certutil")
+            .args(["-user", "-delstore", "Root", &thumbprint])
+            .status()
+    } else {
+        return;
+    };
+
+    match result {
+        Ok(status) if status.success() => tracing::info!("Untrusted the old local TLS certificate"),
+        Ok(status) => tracing::warn!("Failed to untrust the old local TLS certificate: {}", status),
+        Err(e) => tracing::warn!("Failed to untrust the old local TLS certificate: {}", e),
+    }
+}
+
+/// SHA-1 of the certificate, which is how both trust stores identify it
+fn thumbprint(cert_path: &Path) -> Result<String, String> {
+    let pem = std::fs::read(cert_path).map_err(|e| format!("Failed to read certificate: {}", e))?;
+    let der = native_tls::Certificate::from_pem(&pem)
+        .and_then(|certificate| certificate.to_der())
+        .map_err(|e| format!("Failed to load certificate: {}", e))?;
+    let digest = ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, &der);
+    Ok(digest.as_ref().iter().map(|byte| format!("{:02X}", byte)).collect())
+}
+
+fn pin(cert_path: &Path) -> Result<(), String> {
+    let pem = std::fs::read(cert_path).map_err(|e| format!("Failed to read certificate: {}", e))?;
+    let certificate = reqwest::Certificate::from_pem(&pem)
+        .map_err(|e| format!("Failed to load certificate: {}", e))?;
+    if let Ok(mut pinned) = PINNED.write() {
+        *pinned = Some(certificate);
+        PIN_GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+    if let Ok(mut pinned_pem) = PINNED_PEM.write() {
+        *pinned_pem = Some(pem);
+    }
+    Ok(())
+}
+
+/// HTTP client for talking to the backend, trusting only the local certificate under TLS
+///
+/// Every caller shares one client, so connections are kept alive instead of opened per request.
+/// The blocking client must not be built or dropped on an async runtime thread.
+pub fn backend_client() -> Result<reqwest::blocking::Client, String> {
+    let generation = PIN_GENERATION.load(Ordering::SeqCst);
+    if let Some((built_for, client)) = CLIENT.read().ok().and_then(|cached| cached.clone()) {
+        if built_for == generation {
+            return Ok(client);
+        }
+    }
+
+    let builder = reqwest::blocking::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .pool_max_idle_per_host(2);
+    let builder = match PINNED.read().ok().and_then(|pinned| pinned.clone()) {
+        Some(certificate) => builder
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(certificate),
+        None => builder,
+    };
+    let client = builder
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    if let Ok(mut cached) = CLIENT.write() {
+        *cached = Some((generation, client.clone()));
+    }
+    Ok(client)
+}
+
+/// TLS connector trusting only the local certificate, for the backend WebSocket
+pub fn websocket_connector() -> Result<native_tls::TlsConnector, String> {
+    let pem = PINNED_PEM
+        .read()
+        .ok()
+        .and_then(|pinned| pinned.clone())
+        .ok_or_else(|| "No local certificate to trust".to_string())?;
+    let certificate = native_tls::Certificate::from_pem(&pem)
+        .map_err(|e| format!("Failed to load certificate: {}", e))?;
+    native_tls::TlsConnector::builder()
+        .disable_built_in_roots(true)
+        .add_root_certificate(certificate)
+        .build()
+        .map_err(|e| format!("Failed to create TLS connector: {}", e))
+}
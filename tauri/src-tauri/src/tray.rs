@@ -0,0 +1,402 @@
+use serde::Deserialize;
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::commands;
+use crate::config::{self, ConfigState};
+use crate::{accounts, diagnostics, downloads, folders, lan, license, pairing, scheduler, service, widgets};
+use crate::logging::{self, LogLevel};
+
+const TRAY_ID: &str = "main";
+const LOG_LEVEL_PREFIX: &str = "log_level:";
+const OPEN_ID: &str = "open";
+const DIAGNOSTICS_ID: &str = "diagnostics";
+const DOWNLOADS_ID: &str = "downloads";
+const SCHEDULES_ID: &str = "schedules";
+const WIDGETS_ID: &str = "widgets";
+/// Account items are this and the account's name
+const ACCOUNT_PREFIX: &str = "account:";
+const SHARED_ACCOUNT_ID: &str = "account_shared";
+const ACCOUNTS_ID: &str = "accounts";
+const LICENSE_ID: &str = "license";
+const PAIR_ID: &str = "pair";
+const LAN_ACCESS_ID: &str = "lan_access";
+const SERVICE_ID: &str = "backend_service";
+const CRASH_REPORTS_ID: &str = "crash_reports";
+const QUIT_ID: &str = "quit";
+const DEFAULT_TOOLTIP: &str = "tsw_io";
+/// Diameter of the status dot as a share of the icon size
+const DOT_SIZE: f32 = 0.45;
+
+/// Log level entries, kept so the check marks can follow level changes
+struct LogLevelItems(Vec<(LogLevel, CheckMenuItem<Wry>)>);
+/// Kept to show whether the backend can be reached from the network
+struct LanAccessItem(CheckMenuItem<Wry>);
+/// Kept to show whether the backend runs as a service
+struct ServiceItem(CheckMenuItem<Wry>);
+/// Kept to show whether the user agreed to send crash reports
+struct CrashReportsItem(CheckMenuItem<Wry>);
+/// Lists the watched folders and how their sync is doing
+struct FoldersMenu(Submenu<Wry>);
+/// Switches the account the main window shows
+struct AccountsMenu(Submenu<Wry>);
+
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let current = logging::level();
+    let level_items = LogLevel::ALL
+        .into_iter()
+        .map(|level| {
+            let id = format!("{}{}", LOG_LEVEL_PREFIX, level.as_str());
+            CheckMenuItem::with_id(app, id, level.label(), true, level == current, None::<&str>)
+                .map(|item| (level, item))
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+
+    let level_refs: Vec<&dyn IsMenuItem<Wry>> = level_items
+        .iter()
+        .map(|(_, item)| item as &dyn IsMenuItem<Wry>)
+        .collect();
+    let log_level_menu = Submenu::with_items(app, "Log level", true, &level_refs)?;
+    let open = MenuItem::with_id(app, OPEN_ID, "Open tsw_io", true, None::<&str>)?;
+    let downloads = MenuItem::with_id(app, DOWNLOADS_ID, "Downloads", true, None::<&str>)?;
+    let diagnostics = MenuItem::with_id(app, DIAGNOSTICS_ID, "Diagnostics", true, None::<&str>)?;
+    let schedules = MenuItem::with_id(app, SCHEDULES_ID, "Scheduled tasks", true, None::<&str>)?;
+    let widgets = MenuItem::with_id(app, WIDGETS_ID, "Desktop widgets", true, None::<&str>)?;
+    let folders_menu = Submenu::new(app, "Watched folders", true)?;
+    fill_folders(app, &folders_menu)?;
+    let accounts_menu = Submenu::new(app, "Account", true)?;
+    fill_accounts(app, &accounts_menu)?;
+    let license = MenuItem::with_id(app, LICENSE_ID, "License", true, None::<&str>)?;
+    let pair = MenuItem::with_id(app, PAIR_ID, "Connect mobile device", true, None::<&str>)?;
+    let lan_access = CheckMenuItem::with_id(
+        app,
+        LAN_ACCESS_ID,
+        "LAN access",
+        true,
+        lan::enabled(app),
+        None::<&str>,
+    )?;
+    let backend_service = CheckMenuItem::with_id(
+        app,
+        SERVICE_ID,
+        "Run backend as a service",
+        true,
+        service::installed(),
+        None::<&str>,
+    )?;
+    // Offline mode sends none, whatever the consent says
+    let config = app.state::<ConfigState>().get();
+    let crash_reports = CheckMenuItem::with_id(
+        app,
+        CRASH_REPORTS_ID,
+        "Send crash reports",
+        !config.offline,
+        config.crash_reports,
+        None::<&str>,
+    )?;
+    let quit = MenuItem::with_id(app, QUIT_ID, "Quit tsw_io", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &open,
+            &accounts_menu,
+            &PredefinedMenuItem::separator(app)?,
+            &log_level_menu,
+            &downloads,
+            &diagnostics,
+            &schedules,
+            &widgets,
+            &folders_menu,
+            &pair,
+            &lan_access,
+            &backend_service,
+            &crash_reports,
+            &license,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(full_tooltip(app, None))
+        .menu(&menu)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    app.manage(LogLevelItems(level_items));
+    app.manage(LanAccessItem(lan_access));
+    app.manage(ServiceItem(backend_service));
+    app.manage(CrashReportsItem(crash_reports));
+    app.manage(FoldersMenu(folders_menu));
+    app.manage(AccountsMenu(accounts_menu));
+    Ok(())
+}
+
+/// `text` or the default, always saying when other machines can reach the backend
+fn full_tooltip(app: &AppHandle, text: Option<&str>) -> String {
+    let text = text.unwrap_or(DEFAULT_TOOLTIP);
+    if lan::enabled(app) {
+        format!("{} (LAN access on)", text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    if let Some(level) = id.strip_prefix(LOG_LEVEL_PREFIX).and_then(LogLevel::from_name) {
+        // Talking to the backend blocks, keep it off the event loop
+        let app = app.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = commands::apply_log_level(&app, level) {
+                tracing::warn!("{}", e);
+            }
+        });
+    } else if id == OPEN_ID {
+        crate::open_main_window(app);
+    } else if let Some(name) = id.strip_prefix(ACCOUNT_PREFIX).map(str::to_string) {
+        switch_account(app, Some(name));
+    } else if id == SHARED_ACCOUNT_ID {
+        switch_account(app, None);
+    } else if id == ACCOUNTS_ID {
+        if let Err(e) = accounts::open(app) {
+            tracing::warn!("Failed to open accounts: {}", e);
+        }
+    } else if id == DOWNLOADS_ID {
+        if let Err(e) = downloads::open(app) {
+            tracing::warn!("Failed to open downloads: {}", e);
+        }
+    } else if id == DIAGNOSTICS_ID {
+        if let Err(e) = diagnostics::open(app) {
+            tracing::warn!("Failed to open diagnostics: {}", e);
+        }
+    } else if id == SCHEDULES_ID {
+        if let Err(e) = scheduler::open(app) {
+            tracing::warn!("Failed to open scheduled tasks: {}", e);
+        }
+    } else if id == WIDGETS_ID {
+        if let Err(e) = widgets::open(app) {
+            tracing::warn!("Failed to open desktop widgets: {}", e);
+        }
+    } else if id == LICENSE_ID {
+        if let Err(e) = license::open(app) {
+            tracing::warn!("Failed to open the license: {}", e);
+        }
+    } else if id == PAIR_ID {
+        if let Err(e) = pairing::open(app) {
+            tracing::warn!("Failed to open pairing: {}", e);
+        }
+    } else if id == LAN_ACCESS_ID {
+        // The item checked itself already, what counts is whether the backend restart worked
+        let app = app.clone();
+        let enabled = !lan::enabled(&app);
+        std::thread::spawn(move || {
+            if let Err(e) = lan::set_enabled(&app, enabled) {
+                tracing::warn!("Failed to change LAN access: {}", e);
+            }
+            sync_lan_access(&app, lan::enabled(&app));
+        });
+    } else if id == SERVICE_ID {
+        let app = app.clone();
+        let install = !service::installed();
+        std::thread::spawn(move || {
+            if let Err(e) = service::set_installed(&app, install) {
+                tracing::warn!("Failed to change the backend service: {}", e);
+            }
+            sync_service(&app);
+        });
+    } else if id == CRASH_REPORTS_ID {
+        let enabled = !app.state::<ConfigState>().get().crash_reports;
+        // The item checked itself already, `config::update` puts it right once it is saved
+        if let Err(e) = config::update(app, |config| config.crash_reports = enabled) {
+            tracing::warn!("Failed to change crash report consent: {}", e);
+            sync_crash_reports(app);
+        }
+    } else if id == QUIT_ID {
+        crate::stop_backend(app);
+        app.exit(0);
+    }
+}
+
+/// Rebuilding the main window waits for the old one to go, keep it off the event loop
+fn switch_account(app: &AppHandle, name: Option<String>) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = accounts::switch(&app, name) {
+            tracing::warn!("Failed to switch account: {}", e);
+        }
+        // The item checked itself already, even when the switch didn't happen
+        sync_accounts(&app);
+    });
+}
+
+/// Update the tray check marks to show `level` as the active one
+pub fn sync_log_level(app: &AppHandle, level: LogLevel) {
+    if let Some(items) = app.try_state::<LogLevelItems>() {
+        for (item_level, item) in &items.0 {
+            let _ = item.set_checked(*item_level == level);
+        }
+    }
+}
+
+/// Show whether LAN access is on in the tray menu and tooltip
+pub fn sync_lan_access(app: &AppHandle, enabled: bool) {
+    if let Some(item) = app.try_state::<LanAccessItem>() {
+        let _ = item.0.set_checked(enabled);
+    }
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(full_tooltip(app, None)));
+    }
+}
+
+/// Show whether crash reports are sent in the tray menu
+pub fn sync_crash_reports(app: &AppHandle) {
+    if let Some(item) = app.try_state::<CrashReportsItem>() {
+        let config = app.state::<ConfigState>().get();
+        let _ = item.0.set_checked(config.crash_reports);
+        let _ = item.0.set_enabled(!config.offline);
+    }
+}
+
+/// Show whether the backend runs as a service in the tray menu
+pub fn sync_service(app: &AppHandle) {
+    if let Some(item) = app.try_state::<ServiceItem>() {
+        let _ = item.0.set_checked(service::installed());
+    }
+}
+
+/// One line per watched folder, only there to read
+fn fill_folders(app: &AppHandle, menu: &Submenu<Wry>) -> tauri::Result<()> {
+    let statuses = folders::statuses(app);
+    if statuses.is_empty() {
+        return menu.append(&MenuItem::new(app, "None, add them in the settings", false, None::<&str>)?);
+    }
+    for status in statuses {
+        menu.append(&MenuItem::new(app, status.summary(), false, None::<&str>)?)?;
+    }
+    Ok(())
+}
+
+/// Show how the sync of each watched folder is doing in the tray menu
+pub fn sync_folders(app: &AppHandle) {
+    let Some(menu) = app.try_state::<FoldersMenu>() else {
+        return;
+    };
+    let result = menu.0.items().and_then(|items| {
+        for item in items {
+            menu.0.remove(&item)?;
+        }
+        fill_folders(app, &menu.0)
+    });
+    if let Err(e) = result {
+        tracing::warn!("Failed to update the watched folders in the tray: {}", e);
+    }
+}
+
+/// The shared storage and each account, the one the main window shows checked
+fn fill_accounts(app: &AppHandle, menu: &Submenu<Wry>) -> tauri::Result<()> {
+    let active = accounts::active(app);
+    menu.append(&CheckMenuItem::with_id(
+        app,
+        SHARED_ACCOUNT_ID,
+        "Shared",
+        true,
+        active.is_none(),
+        None::<&str>,
+    )?)?;
+    for status in accounts::statuses(app) {
+        let id = format!("{}{}", ACCOUNT_PREFIX, status.name);
+        menu.append(&CheckMenuItem::with_id(app, id, &status.name, true, status.active, None::<&str>)?)?;
+    }
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&MenuItem::with_id(app, ACCOUNTS_ID, "Manage accounts", true, None::<&str>)?)
+}
+
+/// Show the accounts and which one the main window shows in the tray menu
+pub fn sync_accounts(app: &AppHandle) {
+    let Some(menu) = app.try_state::<AccountsMenu>() else {
+        return;
+    };
+    let result = menu.0.items().and_then(|items| {
+        for item in items {
+            menu.0.remove(&item)?;
+        }
+        fill_accounts(app, &menu.0)
+    });
+    if let Err(e) = result {
+        tracing::warn!("Failed to update the accounts in the tray: {}", e);
+    }
+}
+
+/// What the backend is busy with, shown as a dot on the tray icon
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayStatus {
+    /// The plain icon
+    Idle,
+    /// Something is in progress, like a sync or pending jobs
+    Busy,
+    /// Something needs the user's attention
+    Attention,
+    Error,
+}
+
+impl TrayStatus {
+    fn color(&self) -> Option<[u8; 3]> {
+        match self {
+            TrayStatus::Idle => None,
+            TrayStatus::Busy => Some([0x25, 0x63, 0xeb]),
+            TrayStatus::Attention => Some([0xf5, 0x9e, 0x0b]),
+            TrayStatus::Error => Some([0xdc, 0x26, 0x26]),
+        }
+    }
+}
+
+/// The app icon with a dot of `color` in the bottom right corner
+fn with_dot(icon: &Image<'_>, color: [u8; 3]) -> Image<'static> {
+    let (width, height) = (icon.width(), icon.height());
+    let mut rgba = icon.rgba().to_vec();
+    let radius = width.min(height) as f32 * DOT_SIZE / 2.0;
+    let (center_x, center_y) = (width as f32 - radius, height as f32 - radius);
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f32 + 0.5 - center_x, y as f32 + 0.5 - center_y);
+            if dx * dx + dy * dy <= radius * radius {
+                let pixel = ((y * width + x) * 4) as usize;
+                rgba[pixel..pixel + 4].copy_from_slice(&[color[0], color[1], color[2], 0xff]);
+            }
+        }
+    }
+    Image::new_owned(rgba, width, height)
+}
+
+/// Show what the backend is doing on the tray icon and in its tooltip
+///
+/// Does nothing without a tray icon, and the tooltip is ignored where the platform has none.
+pub fn set_status(app: &AppHandle, status: TrayStatus, tooltip: Option<&str>) -> Result<(), String> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        tracing::debug!(?status, "No tray icon to show the status on");
+        return Ok(());
+    };
+    let icon = app.default_window_icon().map(|icon| match status.color() {
+        Some(color) => with_dot(icon, color),
+        None => icon.clone().to_owned(),
+    });
+    tray.set_icon(icon)
+        .map_err(|e| format!("Failed to set tray icon: {}", e))?;
+    tray.set_tooltip(Some(full_tooltip(app, tooltip)))
+        .map_err(|e| format!("Failed to set tray tooltip: {}", e))
+}
+
+#[tauri::command]
+pub fn set_tray_status(
+    app: AppHandle,
+    state: TrayStatus,
+    tooltip: Option<String>,
+) -> Result<(), String> {
+    set_status(&app, state, tooltip.as_deref())
+}
@@ -0,0 +1,259 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::api;
+use crate::audit::{self, AuditAction};
+use crate::config::ConfigState;
+use crate::health;
+use crate::lan;
+use crate::loopback;
+use crate::maintenance;
+use crate::overlay;
+use crate::profile::{Profile, ProfileState};
+use crate::proxy;
+use crate::supervisor::{self, SupervisorState};
+use crate::timing;
+use crate::wizard;
+
+/// Where upgraded sidecars are kept, under the app's local data directory
+const UPGRADES_DIR: &str = "backend";
+/// Names the sidecar an upgrade left in `UPGRADES_DIR`, for the next launches
+const MARKER_FILE: &str = "upgrade.json";
+/// Base64 Ed25519 key backend releases are signed with, builds without one can't upgrade
+const PUBLIC_KEY: Option<&str> = option_env!("TSW_IO_UPGRADE_PUBLIC_KEY");
+/// Next to a sidecar, its base64 signature
+const SIGNATURE_EXTENSION: &str = "sig";
+
+/// The sidecar spawned instead of the bundled one, once an upgrade took over
+static ACTIVE: Mutex<Option<Upgraded>> = Mutex::new(None);
+
+/// A sidecar newer than the bundled one, from an update that only changed the backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Upgraded {
+    /// The shell it was installed under, an update of the whole app brings its own backend
+    shell_version: String,
+    backend_version: String,
+    sidecar: PathBuf,
+}
+
+fn upgrades_dir(handle: &AppHandle) -> Result<PathBuf, String> {
+    handle
+        .path()
+        .app_local_data_dir()
+        .map(|dir| dir.join(UPGRADES_DIR))
+        .map_err(|e| format!("Failed to find the app data directory: {}", e))
+}
+
+/// Pick up the sidecar of an earlier upgrade, unless the app was updated since
+pub fn load(handle: &AppHandle) {
+    let Ok(marker) = upgrades_dir(handle).map(|dir| dir.join(MARKER_FILE)) else {
+        return;
+    };
+    let Some(upgraded) = std::fs::read_to_string(&marker)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Upgraded>(&contents).ok())
+    else {
+        return;
+    };
+    if upgraded.shell_version != env!("CARGO_PKG_VERSION") || !upgraded.sidecar.is_file() {
+        tracing::info!("Dropping the upgraded backend {}, the app was updated", upgraded.backend_version);
+        let _ = std::fs::remove_file(&marker);
+        return;
+    }
+    tracing::info!(version = %upgraded.backend_version, "Using the upgraded backend");
+    *ACTIVE.lock().unwrap() = Some(upgraded);
+}
+
+/// Program to spawn instead of the bundled sidecar, if the backend was upgraded
+pub fn sidecar() -> Option<PathBuf> {
+    ACTIVE.lock().unwrap().as_ref().map(|upgraded| upgraded.sidecar.clone())
+}
+
+/// Version of the backend the shell spawns, the bundled one's unless it was upgraded
+pub fn backend_version() -> String {
+    ACTIVE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|upgraded| upgraded.backend_version.clone())
+        .unwrap_or_else(|| env!("TSW_IO_BACKEND_VERSION").to_string())
+}
+
+/// Copy the new sidecar next to the earlier upgrades, the downloaded file may go away
+fn stage(handle: &AppHandle, sidecar: &Path, version: &str) -> Result<PathBuf, String> {
+    let file_name = sidecar
+        .file_name()
+        .ok_or_else(|| format!("{} is not a file", sidecar.display()))?;
+    let dir = upgrades_dir(handle)?.join(version);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let staged = dir.join(file_name);
+    std::fs::copy(sidecar, &staged).map_err(|e| format!("Failed to copy {}: {}", sidecar.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make {} executable: {}", staged.display(), e))?;
+    }
+    Ok(staged)
+}
+
+/// Check the staged sidecar was released by us, against the signature next to `sidecar`
+fn verify(sidecar: &Path, staged: &Path) -> Result<(), String> {
+    let key = PUBLIC_KEY.ok_or_else(|| "This build can't upgrade the server on its own".to_string())?;
+    let key = STANDARD
+        .decode(key)
+        .map_err(|e| format!("Invalid upgrade key in this build: {}", e))?;
+    let mut signature_file = sidecar.as_os_str().to_owned();
+    signature_file.push(".");
+    signature_file.push(SIGNATURE_EXTENSION);
+    let signature_file = PathBuf::from(signature_file);
+    let signature = std::fs::read_to_string(&signature_file)
+        .map_err(|e| format!("Failed to read {}: {}", signature_file.display(), e))?;
+    let signature = STANDARD
+        .decode(signature.trim())
+        .map_err(|e| format!("Invalid signature in {}: {}", signature_file.display(), e))?;
+    // The copy is what gets spawned, the original could still change
+    let contents = std::fs::read(staged).map_err(|e| format!("Failed to read {}: {}", staged.display(), e))?;
+    UnparsedPublicKey::new(&ED25519, key)
+        .verify(&contents, &signature)
+        .map_err(|_| format!("{} isn't a server released for tsw_io", sidecar.display()))
+}
+
+fn remember(handle: &AppHandle, upgraded: &Upgraded) -> Result<(), String> {
+    let marker = upgrades_dir(handle)?.join(MARKER_FILE);
+    let contents = serde_json::to_string_pretty(upgraded).map_err(|e| e.to_string())?;
+    std::fs::write(&marker, contents).map_err(|e| format!("Failed to write {}: {}", marker.display(), e))
+}
+
+/// Swap the backend for the signed sidecar at `sidecar`, of `version`, underneath the open pages
+///
+/// New requests wait in the proxy while the database is snapshotted and the old backend
+/// stops, so the new one has the database and ports to itself as it boots and migrates.
+/// Once it answers the held requests go to it. Should it not come up, the snapshot is
+/// restored and the previous backend launched again.
+pub fn upgrade(handle: &AppHandle, sidecar: &Path, version: &str) -> Result<(), String> {
+    let profile = handle.state::<ProfileState>().get();
+    let shell_config = handle.state::<ConfigState>().get();
+    if profile.backend_url.is_some() {
+        return Err("Only the server bundled with tsw_io can be upgraded".to_string());
+    }
+    if !shell_config.proxy {
+        return Err("Upgrading the server in place needs the proxy (proxy = true)".to_string());
+    }
+    if supervisor::state(handle) != SupervisorState::Running {
+        return Err("The server is not running".to_string());
+    }
+    let exclusive = maintenance::exclusive("An upgrade of the server")?;
+    let from = backend_version();
+    let staged = stage(handle, sidecar, version)?;
+    if let Err(e) = verify(sidecar, &staged) {
+        let _ = std::fs::remove_file(&staged);
+        return Err(e);
+    }
+    let backend_port = proxy::backend_port(profile.port).ok_or_else(|| "The proxy is not running".to_string())?;
+    let window = handle
+        .get_webview_window("main")
+        .filter(|window| window.is_visible().unwrap_or(false));
+    if let Some(window) = &window {
+        if let Err(e) = overlay::show(window, "Upgrading the server...") {
+            tracing::warn!("Failed to open the upgrade overlay: {}", e);
+        }
+    }
+    tracing::info!(version, "Upgrading the backend underneath the pages");
+
+    proxy::quiesce(profile.port, true);
+    // Straight from the old backend, the proxy holds requests now
+    let backend_url = format!("http://{}:{}", loopback::BIND_ADDRESS, backend_port);
+    let snapshot = profile
+        .data_dir
+        .join(format!("{}.before-upgrade", wizard::DATABASE_FILE_NAME));
+    if let Err(e) = api::backup(&backend_url, &snapshot) {
+        proxy::quiesce(profile.port, false);
+        overlay::close(handle);
+        return Err(format!("Failed to snapshot the database before upgrading: {}", e));
+    }
+
+    let upgraded = Upgraded {
+        shell_version: env!("CARGO_PKG_VERSION").to_string(),
+        backend_version: version.to_string(),
+        sidecar: staged,
+    };
+    let previous = ACTIVE.lock().unwrap().replace(upgraded.clone());
+    timing::start(handle);
+    supervisor::backend_launching(handle);
+    // Two backends on one database and the same serial ports would step on each other
+    let stopped = match crate::detach_backend(handle) {
+        Some(old) => crate::kill_and_wait(old).map_err(|e| format!("Failed to stop the old server: {}", e)),
+        None => Ok(()),
+    };
+    let spawned = stopped
+        .clone()
+        .and_then(|()| crate::spawn_sidecar(handle, &profile).map_err(|e| e.to_string()));
+    let ready = spawned.clone().and_then(|()| {
+        health::wait_for_backend(handle, &backend_url, true, &timing::budget(handle))
+            .map(|_attempts| ())
+            .map_err(|attempts| format!("The upgraded server didn't start ({} checks)", attempts))
+    });
+
+    if let Err(e) = ready {
+        tracing::error!("{}", e);
+        *ACTIVE.lock().unwrap() = previous;
+        proxy::quiesce(profile.port, false);
+        overlay::close(handle);
+        drop(exclusive);
+        // Never spawned, the database is as the old backend left it
+        fall_back(handle, &profile, spawned.is_ok().then_some(snapshot.as_path()));
+        audit::record(
+            handle,
+            AuditAction::Upgrade {
+                from: from.clone(),
+                to: version.to_string(),
+            },
+            format!("Upgrading the server from {} to {} failed: {}", from, version, e),
+        );
+        return Err(e);
+    }
+
+    proxy::quiesce(profile.port, false);
+    supervisor::backend_ready(handle);
+    lan::indicate(handle);
+    if let Err(e) = remember(handle, &upgraded) {
+        // Only this run uses it, the next launch starts the bundled backend again
+        tracing::warn!("{}", e);
+    }
+    if let Some(window) = &window {
+        if let Err(e) = window.reload() {
+            tracing::error!("Failed to reload the main window: {}", e);
+        }
+    }
+    overlay::close(handle);
+    tracing::info!(version, "Backend upgraded underneath the pages");
+    audit::record(
+        handle,
+        AuditAction::Upgrade {
+            from: from.clone(),
+            to: version.to_string(),
+        },
+        format!("Upgraded the server from {} to {}", from, version),
+    );
+    Ok(())
+}
+
+/// Launch the previous backend, over the snapshot if the new one may have migrated
+fn fall_back(handle: &AppHandle, profile: &Profile, snapshot: Option<&Path>) {
+    if let Err(e) = crate::stop_backend_and_wait(handle) {
+        tracing::error!("Failed to stop the upgraded server: {}", e);
+    }
+    if let Some(snapshot) = snapshot {
+        if let Err(e) = wizard::restore_backup(handle, snapshot, &profile.data_dir) {
+            tracing::error!("Failed to restore the database after the failed upgrade: {}", e);
+        }
+    }
+    if let Err(e) = crate::launch(handle) {
+        tracing::error!("Failed to launch the previous server: {}", e);
+    }
+}
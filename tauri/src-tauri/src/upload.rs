@@ -0,0 +1,252 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Window};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::api::{self, ChunkError, UploadStatus};
+use crate::profile::ProfileState;
+use crate::{recent, secrets};
+
+/// Tauri event the upload progress is emitted as
+const PROGRESS_EVENT: &str = "upload://progress";
+/// Bytes sent per request, only this much of the file is in memory at a time
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+/// Attempts at a chunk before the upload is left for the caller to resume
+const CHUNK_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+/// DOM event a page gets the files dropped on its window as
+const DROPPED_EVENT: &str = "tsw:files-dropped";
+
+/// Files the user picked or dropped this run, by the handle the page was given for them
+///
+/// Pages only ever see handles, so they can't have the shell read anything else.
+static OFFERED: Mutex<Option<HashMap<String, PathBuf>>> = Mutex::new(None);
+
+/// A file the user chose to upload, as the page sees it
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadHandle {
+    /// What to pass `upload_file`
+    pub handle: String,
+    pub name: String,
+    pub size: u64,
+}
+
+/// Payload of `upload://progress`
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadProgress {
+    pub id: String,
+    /// Handle of the file, for those the user picked or dropped
+    pub file: Option<String>,
+    pub name: String,
+    pub sent: u64,
+    pub total: u64,
+}
+
+/// Hand out a handle for a file the user chose
+fn offer(path: PathBuf) -> Result<UploadHandle, String> {
+    let size = std::fs::metadata(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    let upload = UploadHandle {
+        handle: secrets::random_hex(16)?,
+        name: file_name(&path),
+        size,
+    };
+    let mut offered = OFFERED.lock().map_err(|e| e.to_string())?;
+    offered
+        .get_or_insert_with(HashMap::new)
+        .insert(upload.handle.clone(), path);
+    Ok(upload)
+}
+
+fn offered(handle: &str) -> Option<PathBuf> {
+    OFFERED
+        .lock()
+        .ok()?
+        .as_ref()
+        .and_then(|offered| offered.get(handle).cloned())
+}
+
+/// Let the user pick files to upload, empty if they cancelled
+#[tauri::command]
+pub async fn pick_upload_files(app: AppHandle) -> Result<Vec<UploadHandle>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        app.dialog()
+            .file()
+            .set_title("Upload files")
+            .blocking_pick_files()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|path| path.into_path().ok())
+            .map(offer)
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Tell the page in `window` about the files dropped on it, as handles in a `tsw:files-dropped` event
+pub fn dropped(window: &Window, paths: &[PathBuf]) {
+    let handles: Vec<UploadHandle> = paths
+        .iter()
+        .filter(|path| path.is_file())
+        .filter_map(|path| match offer(path.clone()) {
+            Ok(handle) => Some(handle),
+            Err(e) => {
+                tracing::warn!("{}", e);
+                None
+            }
+        })
+        .collect();
+    if handles.is_empty() {
+        return;
+    }
+    let Some(webview) = window.app_handle().get_webview_window(window.label()) else {
+        return;
+    };
+    let detail = serde_json::to_string(&handles).unwrap_or_else(|_| "[]".to_string());
+    let _ = webview.eval(format!(
+        "window.dispatchEvent(new CustomEvent('{}', {{ detail: {} }}))",
+        DROPPED_EVENT, detail
+    ));
+}
+
+/// Upload a file the user picked or dropped to the backend in chunks, returning the id of the
+/// upload once it is complete
+///
+/// Pass the id of an upload that failed part way to resume it where it stopped.
+/// Progress is emitted on `upload://progress`.
+#[tauri::command]
+pub async fn upload_file(app: AppHandle, file: String, upload_id: Option<String>) -> Result<String, String> {
+    let path = offered(&file).ok_or_else(|| "Unknown file, pick or drop it first".to_string())?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let id = upload(&app, &path, Some(&file), upload_id.as_deref())?;
+        // Watched folders upload too, only what the user picked counts as an import
+        recent::add_file(&app, &path);
+        Ok(id)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// `file` is the handle pages know the file by, if they were given one
+pub(crate) fn upload(
+    handle: &AppHandle,
+    path: &Path,
+    file: Option<&str>,
+    upload_id: Option<&str>,
+) -> Result<String, String> {
+    upload_reporting(handle, path, file, upload_id, &mut |_| {})
+}
+
+/// `upload`, telling `report` how many bytes the backend has after every chunk
+pub(crate) fn upload_reporting(
+    handle: &AppHandle,
+    path: &Path,
+    file: Option<&str>,
+    upload_id: Option<&str>,
+    report: &mut dyn FnMut(u64),
+) -> Result<String, String> {
+    let mut reader = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let metadata = reader
+        .metadata()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    let size = metadata.len();
+    let base_url = handle.state::<ProfileState>().get().base_url();
+
+    let mut status = match upload_id {
+        Some(id) => {
+            let status = api::upload_status(&base_url, id)?;
+            if status.size != size {
+                return Err(format!("{} changed since the upload started", path.display()));
+            }
+            tracing::info!(id, received = status.received, "Resuming upload");
+            status
+        }
+        None => api::create_upload(&base_url, &file_name(path), size)?,
+    };
+    tracing::info!(id = status.id, size, "Uploading {}", path.display());
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    while !status.complete {
+        emit(handle, &status, path, file);
+        report(status.received);
+        reader.seek(SeekFrom::Start(status.received))
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let len = read_chunk(&mut reader, &mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if len == 0 {
+            return Err(format!("{} got shorter during the upload", path.display()));
+        }
+        status = send_chunk(&base_url, &status, &buffer[..len])?;
+    }
+    emit(handle, &status, path, file);
+    report(status.received);
+    tracing::info!(id = status.id, "Upload complete");
+    Ok(status.id)
+}
+
+/// Fill as much of `buffer` as the file has left
+fn read_chunk(file: &mut File, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut len = 0;
+    while len < buffer.len() {
+        match file.read(&mut buffer[len..])? {
+            0 => break,
+            read => len += read,
+        }
+    }
+    Ok(len)
+}
+
+/// Send a chunk, retrying on errors, and return how far the upload got
+fn send_chunk(base_url: &str, status: &UploadStatus, chunk: &[u8]) -> Result<UploadStatus, String> {
+    let mut attempt = 1;
+    loop {
+        match api::upload_chunk(base_url, &status.id, status.received, chunk.to_vec()) {
+            Ok(status) => return Ok(status),
+            // Continue from where the backend is, the next chunk is read from there
+            Err(ChunkError::Offset(received)) => {
+                return Ok(UploadStatus {
+                    received,
+                    ..status.clone()
+                })
+            }
+            Err(ChunkError::Failed(e)) if attempt < CHUNK_ATTEMPTS => {
+                tracing::warn!(id = status.id, attempt, "Upload chunk failed: {}", e);
+                attempt += 1;
+                std::thread::sleep(RETRY_DELAY);
+            }
+            Err(ChunkError::Failed(e)) => {
+                return Err(format!("{}, resume the upload with id {}", e, status.id))
+            }
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Pages get the file's handle and name, never where it is on disk
+fn emit(handle: &AppHandle, status: &UploadStatus, path: &Path, file: Option<&str>) {
+    let progress = UploadProgress {
+        id: status.id.clone(),
+        file: file.map(str::to_string),
+        name: file_name(path),
+        sent: status.received,
+        total: status.size,
+    };
+    if let Err(e) = handle.emit(PROGRESS_EVENT, progress) {
+        tracing::warn!("Failed to emit {}: {}", PROGRESS_EVENT, e);
+    }
+}
@@ -0,0 +1,261 @@
+use std::path::Path;
+
+use crate::config::ShellConfig;
+
+/// Whether the data directory can be encrypted on this platform
+pub const SUPPORTED: bool = cfg!(any(target_os = "macos", windows));
+
+/// Make sure the data directory is encrypted and unlocked before the backend uses it
+///
+/// On macOS the data lives in an encrypted disk image whose password is kept in the
+/// keychain and only read after the user authenticates. On Windows the files are
+/// encrypted with EFS, which ties them to the Windows account.
+///
+/// The shell's config has to stay readable while the data is locked, so a data directory
+/// holding `config_dir` isn't encrypted.
+pub fn open(config: &ShellConfig, data_dir: &Path, config_dir: &Path) -> Result<(), String> {
+    if !config.encrypt_data_dir {
+        return Ok(());
+    }
+    if config_dir.starts_with(data_dir) {
+        return Err(format!(
+            "{} holds the app's config, choose another data directory to encrypt it",
+            data_dir.display()
+        ));
+    }
+    platform::open(data_dir)
+}
+
+/// Lock the data directory again, once the backend is gone
+pub fn close(config: &ShellConfig, data_dir: &Path) {
+    if config.encrypt_data_dir {
+        platform::close(data_dir);
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::io::Write;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+    use std::time::Duration;
+
+    use crate::{lock, secrets};
+
+    const KEY_ACCOUNT: &str = "data_dir_key";
+    const UNLOCK_REASON: &str = "unlock your TSW IO data";
+    /// Upper bound, a sparse bundle only takes the space its files use
+    const CONTAINER_SIZE: &str = "20g";
+    const DETACH_ATTEMPTS: u32 = 10;
+
+    fn container_path(data_dir: &Path) -> PathBuf {
+        PathBuf::from(format!("{}.sparsebundle", data_dir.display()))
+    }
+
+    /// Whether a volume is mounted on `dir`
+    fn is_mounted(dir: &Path) -> bool {
+        let device = |path: &Path| std::fs::metadata(path).map(|metadata| metadata.dev()).ok();
+        match (device(dir), dir.parent().and_then(device)) {
+            (Some(dir), Some(parent)) => dir != parent,
+            _ => false,
+        }
+    }
+
+    /// Run hdiutil with the container password on stdin, so it never shows up in `ps`
+    fn hdiutil(args: &[&str], key: &str) -> Result<(), String> {
+        let mut child = Command::new("hdiutil")
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run hdiutil: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // hdiutil reads a NUL terminated password
+            stdin
+                .write_all(format!("{}\0", key).as_bytes())
+                .map_err(|e| format!("Failed to pass password to hdiutil: {}", e))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to run hdiutil: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "hdiutil {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    fn attach(container: &Path, mount_point: &Path, key: &str) -> Result<(), String> {
+        std::fs::create_dir_all(mount_point)
+            .map_err(|e| format!("Failed to create {}: {}", mount_point.display(), e))?;
+        hdiutil(
+            &[
+                "attach",
+                "-stdinpass",
+                "-nobrowse",
+                "-mountpoint",
+                &mount_point.display().to_string(),
+                &container.display().to_string(),
+            ],
+            key,
+        )
+    }
+
+    fn detach(mount_point: &Path) -> Result<(), String> {
+        hdiutil(&["detach", &mount_point.display().to_string()], "")
+    }
+
+    fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+        let entries = std::fs::read_dir(from)
+            .map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+        for entry in entries.flatten() {
+            let target = to.join(entry.file_name());
+            if entry.path().is_dir() {
+                std::fs::create_dir_all(&target)
+                    .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+                copy_dir(&entry.path(), &target)?;
+            } else {
+                std::fs::copy(entry.path(), &target)
+                    .map_err(|e| format!("Failed to copy {}: {}", entry.path().display(), e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Create the encrypted container, moving any existing data into it
+    fn create(container: &Path, data_dir: &Path, key: &str) -> Result<(), String> {
+        hdiutil(
+            &[
+                "create",
+                "-size",
+                CONTAINER_SIZE,
+                "-type",
+                "SPARSEBUNDLE",
+                "-fs",
+                "APFS",
+                "-volname",
+                "TSW IO Data",
+                "-encryption",
+                "AES-256",
+                "-stdinpass",
+                &container.display().to_string(),
+            ],
+            key,
+        )?;
+
+        let has_data = std::fs::read_dir(data_dir).is_ok_and(|mut entries| entries.next().is_some());
+        if has_data {
+            let staging = PathBuf::from(format!("{}-encrypting", data_dir.display()));
+            attach(container, &staging, key)?;
+            let copied = copy_dir(data_dir, &staging);
+            detach(&staging)?;
+            copied?;
+            let _ = std::fs::remove_dir(&staging);
+            // Only the encrypted copy may remain
+            std::fs::remove_dir_all(data_dir)
+                .map_err(|e| format!("Failed to remove unencrypted data: {}", e))?;
+            tracing::info!("Moved data into the encrypted container");
+        }
+        Ok(())
+    }
+
+    pub fn open(data_dir: &Path) -> Result<(), String> {
+        if is_mounted(data_dir) {
+            return Ok(());
+        }
+        if !lock::authenticate(UNLOCK_REASON)? {
+            return Err("The data directory was not unlocked".to_string());
+        }
+        let key = secrets::get_or_create(KEY_ACCOUNT)?;
+        let container = container_path(data_dir);
+        if !container.exists() {
+            create(&container, data_dir, &key)?;
+        }
+        attach(&container, data_dir, &key)?;
+        tracing::info!("Unlocked encrypted data directory");
+        Ok(())
+    }
+
+    pub fn close(data_dir: &Path) {
+        if !is_mounted(data_dir) {
+            return;
+        }
+        // The backend may take a moment to let go of its files after being killed
+        for _ in 0..DETACH_ATTEMPTS {
+            match detach(data_dir) {
+                Ok(()) => {
+                    tracing::info!("Locked encrypted data directory");
+                    return;
+                }
+                Err(e) => tracing::debug!("{}", e),
+            }
+            std::thread::sleep(Duration::from_millis(500));
+        }
+        tracing::warn!("Failed to lock encrypted data directory, it stays mounted");
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::{
+        EncryptFileW, GetFileAttributesW, FILE_ATTRIBUTE_ENCRYPTED, INVALID_FILE_ATTRIBUTES,
+    };
+
+    /// Encrypt a file, or mark a directory so files created in it get encrypted
+    fn encrypt(path: &Path) -> Result<(), String> {
+        let wide: Vec<u16> = path
+            .as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+        let attributes = unsafe { GetFileAttributesW(PCWSTR(wide.as_ptr())) };
+        if attributes != INVALID_FILE_ATTRIBUTES && attributes & FILE_ATTRIBUTE_ENCRYPTED.0 != 0 {
+            return Ok(());
+        }
+        unsafe { EncryptFileW(PCWSTR(wide.as_ptr())) }
+            .map_err(|e| format!("Failed to encrypt {}: {}", path.display(), e))
+    }
+
+    fn encrypt_all(dir: &Path) -> Result<(), String> {
+        encrypt(dir)?;
+        let entries =
+            std::fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                encrypt_all(&entry.path())?;
+            } else {
+                encrypt(&entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn open(data_dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(data_dir)
+            .map_err(|e| format!("Failed to create {}: {}", data_dir.display(), e))?;
+        encrypt_all(data_dir)
+    }
+
+    /// EFS files stay encrypted, there is nothing to lock
+    pub fn close(_data_dir: &Path) {}
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use std::path::Path;
+
+    /// Refused rather than leaving the data readable while the config says it is encrypted
+    pub fn open(_data_dir: &Path) -> Result<(), String> {
+        Err("Data directory encryption is not supported on this platform".to_string())
+    }
+
+    pub fn close(_data_dir: &Path) {}
+}
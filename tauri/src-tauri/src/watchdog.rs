@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::monitor;
+use crate::profile::ProfileState;
+
+const PING_INTERVAL_SECS: u64 = 5;
+/// Unanswered pings before the interface counts as hung
+const MAX_MISSED_PINGS: u64 = 3;
+
+static WATCHDOG_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Ping the main webview and offer a reload when it stops answering
+pub fn start(handle: AppHandle) {
+    // Like the heartbeat, one watchdog covers every main window the app opens
+    if WATCHDOG_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        let last_pong = Arc::new(AtomicU64::new(0));
+        let prompting = Arc::new(AtomicBool::new(false));
+        let mut sent: u64 = 0;
+
+        loop {
+            std::thread::sleep(Duration::from_secs(PING_INTERVAL_SECS));
+
+            let Some(window) = handle.get_webview_window("main") else {
+                // Closed or being recreated, start counting again once it is back
+                sent = last_pong.load(Ordering::SeqCst);
+                continue;
+            };
+
+            // A dead backend explains a blank page, the heartbeat reports that
+            let backend_healthy = monitor::last_check(&handle).is_some_and(|sample| sample.healthy);
+            if !backend_healthy || prompting.load(Ordering::SeqCst) {
+                sent = last_pong.load(Ordering::SeqCst);
+                continue;
+            }
+
+            sent += 1;
+            let seq = sent;
+            let pong = last_pong.clone();
+            let _ = window.eval_with_callback("1", move |_| {
+                pong.fetch_max(seq, Ordering::SeqCst);
+            });
+
+            let missed = sent.saturating_sub(last_pong.load(Ordering::SeqCst));
+            if missed < MAX_MISSED_PINGS {
+                continue;
+            }
+
+            tracing::warn!(missed, "Main window not responding");
+            // Count afresh after the dialog instead of prompting again right away
+            sent = last_pong.load(Ordering::SeqCst);
+            prompting.store(true, Ordering::SeqCst);
+            let prompting = prompting.clone();
+            let reload_handle = handle.clone();
+            let url = handle.state::<ProfileState>().get().base_url();
+            handle
+                .dialog()
+                .message("The interface is not responding. Reload it?")
+                .title("TSW IO")
+                .kind(MessageDialogKind::Warning)
+                .buttons(MessageDialogButtons::OkCancelCustom(
+                    "Reload".to_string(),
+                    "Wait".to_string(),
+                ))
+                .show(move |reload| {
+                    if reload {
+                        force_reload(&reload_handle, &url);
+                    }
+                    prompting.store(false, Ordering::SeqCst);
+                });
+        }
+    });
+}
+
+/// Reload the main window natively, since a hung page can't run a reload script
+fn force_reload(handle: &AppHandle, base_url: &str) {
+    let Some(window) = handle.get_webview_window("main") else {
+        return;
+    };
+    tracing::info!("Reloading unresponsive main window");
+    let result = base_url
+        .parse()
+        .map_err(|e| format!("Invalid URL {}: {}", base_url, e))
+        .and_then(|url| window.navigate(url).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        tracing::error!("Failed to reload main window: {}", e);
+    }
+}
@@ -0,0 +1,52 @@
+// Native features for the backend pages, as `window.__TSW__`
+//
+// Backend code uses this instead of Tauri's invoke, so commands can be renamed
+// without breaking it. Bump `version` when anything here changes incompatibly.
+(function () {
+  if (!/^https?:\/\/localhost(:\d+)?$/.test(window.location.origin)) {
+    return;
+  }
+  const invoke = (command, args) => window.__TAURI_INTERNALS__.invoke(command, args);
+  const bytes = (contents) =>
+    Array.from(typeof contents === 'string' ? new TextEncoder().encode(contents) : contents);
+
+  const api = {
+    version: 2,
+    appInfo: () => invoke('get_app_info'),
+    openExternal: (url) => invoke('open_external', { url: String(url) }),
+    // Resolves with the path the file was saved to, or null when the user cancelled
+    saveFile: (name, contents, options = {}) =>
+      invoke('save_file', {
+        name,
+        contents: bytes(contents),
+        extensions: options.extensions || [],
+      }),
+    // Downloads a backend path like `/exports/1.json`, it shows in the downloads window
+    download: (source, name, kind) =>
+      invoke('start_download', { source, name, kind: kind ?? null }),
+    // Saves a backend route like `/trains/1` as a PDF, resolves with where it went
+    exportPdf: (route, options = {}) => invoke('export_page_to_pdf', { route, options }),
+    // Resolves with `{handle, name, size}` for each file the user picked, files dropped on the
+    // window come as the same in the detail of a `tsw:files-dropped` event on `window`
+    pickFiles: () => invoke('pick_upload_files'),
+    // Streams a picked or dropped file to the backend, resolves with the id of the upload
+    uploadFile: (handle, uploadId) => invoke('upload_file', { file: handle, uploadId: uploadId ?? null }),
+    // Lists a backend route like `/trains/1` in the OS's recent documents under `title`
+    addRecentWorkspace: (route, title) => invoke('add_recent_workspace', { route, title: String(title) }),
+    notify: (title, body) => invoke('notify', { title, body: body ?? null }),
+    // Single sign-on in the system browser, the page reloads signed in once it is done
+    signIn: () => invoke('sso_sign_in'),
+    signOut: () => invoke('sso_sign_out'),
+    // What the license unlocks, `entitled` says whether to unlock it
+    license: () => invoke('get_license'),
+    windowControls: Object.freeze({
+      minimize: () => invoke('window_control', { action: 'minimize' }),
+      toggleMaximize: () => invoke('window_control', { action: 'toggle_maximize' }),
+      toggleFullscreen: () => invoke('window_control', { action: 'toggle_fullscreen' }),
+      close: () => invoke('window_control', { action: 'close' }),
+    }),
+  };
+
+  Object.defineProperty(window, '__TSW__', { value: Object.freeze(api) });
+  window.dispatchEvent(new Event('tsw:ready'));
+})();
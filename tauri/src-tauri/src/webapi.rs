@@ -0,0 +1,100 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, Url, WebviewWindow};
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::downloads::{self, DownloadKind};
+use crate::navigation;
+use crate::{preview, recent};
+
+/// Init script exposing `window.__TSW__` to the backend pages
+pub const SCRIPT: &str = include_str!("webapi.js");
+
+/// What `windowControls` does to the window the page is in
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowAction {
+    Minimize,
+    ToggleMaximize,
+    ToggleFullscreen,
+    Close,
+}
+
+#[tauri::command]
+pub fn open_external(url: String) -> Result<(), String> {
+    let url = url
+        .parse::<Url>()
+        .map_err(|e| format!("Invalid link {}: {}", url, e))?;
+    navigation::open_external(&url)
+}
+
+/// Save `contents` where the user picks, returning where it went
+#[tauri::command]
+pub async fn save_file(
+    app: AppHandle,
+    name: String,
+    contents: Vec<u8>,
+    extensions: Vec<String>,
+) -> Result<Option<PathBuf>, String> {
+    let mut dialog = app.dialog().file().set_title("Save file").set_file_name(&name);
+    if !extensions.is_empty() {
+        let extensions: Vec<&str> = extensions.iter().map(String::as_str).collect();
+        dialog = dialog.add_filter(&name, &extensions);
+    }
+    let Some(path) = dialog
+        .blocking_save_file()
+        .and_then(|path| path.into_path().ok())
+    else {
+        return Ok(None);
+    };
+
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+    tracing::info!("Saved {}", path.display());
+    preview::attach(&app, &path);
+    recent::add_file(&app, &path);
+    downloads::record(&app, DownloadKind::File, &path);
+    Ok(Some(path))
+}
+
+/// Put the backend page at `route`, like `/trains/1`, in the OS's recent documents
+#[tauri::command]
+pub fn add_recent_workspace(app: AppHandle, route: String, title: String) -> Result<(), String> {
+    if !route.starts_with('/') || route.starts_with("//") {
+        return Err(format!("{} is not a backend route", route));
+    }
+    recent::add_workspace(&app, &route, &title);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn notify(app: AppHandle, title: String, body: Option<String>) -> Result<(), String> {
+    if crate::quiet::hold(&app, &title) {
+        return Ok(());
+    }
+    let mut notification = app.notification().builder().title(title);
+    if let Some(body) = body {
+        notification = notification.body(body);
+    }
+    notification
+        .show()
+        .map_err(|e| format!("Failed to show notification: {}", e))
+}
+
+#[tauri::command]
+pub fn window_control(window: WebviewWindow, action: WindowAction) -> Result<(), String> {
+    let result = match action {
+        WindowAction::Minimize => window.minimize(),
+        WindowAction::ToggleMaximize => match window.is_maximized() {
+            Ok(true) => window.unmaximize(),
+            Ok(false) => window.maximize(),
+            Err(e) => Err(e),
+        },
+        WindowAction::ToggleFullscreen => window
+            .is_fullscreen()
+            .and_then(|fullscreen| window.set_fullscreen(!fullscreen)),
+        WindowAction::Close => window.close(),
+    };
+    result.map_err(|e| format!("Failed to {:?} window: {}", action, e))
+}
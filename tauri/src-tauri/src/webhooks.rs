@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use crate::localhttp;
+use crate::paths::AppPaths;
+use crate::profile::ProfileState;
+use crate::supervisor::{self, SupervisorState};
+use crate::{api, loopback, secrets, tls};
+
+/// Tells local tools where the webhook receiver listens and the token it wants
+pub const WEBHOOK_FILE_NAME: &str = "webhook.json";
+/// Random bytes in the token
+const TOKEN_BYTES: usize = 32;
+/// Bodies past this are refused, a clipped page with its text fits well within
+const MAX_BODY: usize = 1024 * 1024;
+/// Origins of browser extensions, the only pages allowed to post events
+const EXTENSION_SCHEMES: [&str; 3] = ["chrome-extension://", "moz-extension://", "safari-web-extension://"];
+
+/// Contents of `webhook.json`, only readable by the user
+#[derive(Debug, Serialize, Deserialize)]
+struct WebhookFile {
+    url: String,
+    token: String,
+}
+
+/// The token of an earlier run, so tools only have to be set up once
+fn token(path: &Path) -> Result<String, String> {
+    let earlier = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<WebhookFile>(&contents).ok())
+        .map(|file| file.token)
+        .filter(|token| token.len() == TOKEN_BYTES * 2);
+    match earlier {
+        Some(token) => Ok(token),
+        None => secrets::random_hex(TOKEN_BYTES),
+    }
+}
+
+/// Event names the backend takes: lowercase words like `clip` or `page.clipped`
+fn valid_name(name: &str) -> bool {
+    name.len() <= 64
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "_.-".contains(c))
+}
+
+/// Take events from other local tools on `port` and forward them to the backend
+///
+/// Tools read the URL and token from `webhook.json` in the config directory and call
+/// `POST /events/<name>` with `Authorization: Bearer <token>` and any JSON as the body. The
+/// backend gets it at `/api/admin/external_events`, so its own port never has to be opened
+/// to them. Port and token stay the same across runs, for extensions set up with them.
+pub fn start(handle: &AppHandle, port: u16) -> Result<(), String> {
+    let listener = TcpListener::bind((loopback::BIND_ADDRESS, port))
+        .map_err(|e| format!("Failed to start the webhook receiver on port {}: {}", port, e))?;
+    let path = handle.state::<AppPaths>().config_dir.join(WEBHOOK_FILE_NAME);
+    let token = token(&path)?;
+    let contents = WebhookFile {
+        url: format!("http://{}:{}", loopback::BIND_ADDRESS, port),
+        token: token.clone(),
+    };
+    let contents = serde_json::to_string_pretty(&contents).map_err(|e| e.to_string())?;
+    tls::write_private(&path, &contents)?;
+    tracing::info!(port, "Webhook receiver listening, see {}", path.display());
+
+    let handle = handle.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let handle = handle.clone();
+            let token = token.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = serve(&handle, &token, stream) {
+                    tracing::debug!("Webhook request failed: {}", e);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn serve(handle: &AppHandle, token: &str, stream: TcpStream) -> std::io::Result<()> {
+    let request = match localhttp::read_request_with_body(&stream, MAX_BODY) {
+        Err(e) if e.kind() == ErrorKind::InvalidData => {
+            return reply(stream, 413, json!({ "error": "too_large" }));
+        }
+        request => request?,
+    };
+    // Browsers send an Origin, other tools don't: of the pages, only extensions may post
+    let source = match request.header("origin") {
+        None => "local".to_string(),
+        Some(origin) if EXTENSION_SCHEMES.iter().any(|scheme| origin.starts_with(scheme)) => origin.to_string(),
+        Some(_) => return reply(stream, 403, json!({ "error": "forbidden" })),
+    };
+    let authorized = request
+        .header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| secrets::constant_time_eq(given, token));
+    if !authorized {
+        return reply(stream, 401, json!({ "error": "unauthorized" }));
+    }
+
+    let name = Some(request.path())
+        .filter(|_| request.method == "POST")
+        .and_then(|path| path.strip_prefix("/events/"))
+        .filter(|name| valid_name(name));
+    let Some(name) = name else {
+        return reply(stream, 404, json!({ "error": format!("No webhook {} {}", request.method, request.path()) }));
+    };
+    let payload = match request.body.as_slice() {
+        [] => Value::Null,
+        body => match serde_json::from_slice(body) {
+            Ok(payload) => payload,
+            Err(e) => return reply(stream, 400, json!({ "error": format!("The body is not JSON: {}", e) })),
+        },
+    };
+    if supervisor::state(handle) != SupervisorState::Running {
+        return reply(stream, 503, json!({ "error": "The backend is not running" }));
+    }
+
+    let base_url = handle.state::<ProfileState>().get().base_url();
+    match api::forward_event(&base_url, name, &source, &payload) {
+        Ok(()) => {
+            tracing::debug!(source, "Forwarded the {} event", name);
+            reply(stream, 200, json!({ "event": name }))
+        }
+        Err(e) => {
+            tracing::warn!("Failed to forward the {} event: {}", name, e);
+            reply(stream, 502, json!({ "error": e }))
+        }
+    }
+}
+
+fn reply(stream: TcpStream, status: u16, body: Value) -> std::io::Result<()> {
+    localhttp::respond(stream, status, "application/json", &body.to_string())
+}
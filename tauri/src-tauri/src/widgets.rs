@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, LogicalPosition, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+use crate::api;
+use crate::config::{self, ConfigState, DesktopWidget};
+use crate::lock;
+use crate::navigation;
+use crate::profile::ProfileState;
+use crate::supervisor::{self, SupervisorState};
+
+const MANAGER_LABEL: &str = "widgets";
+/// Widget windows are labelled with this and their name, see `capabilities/widget-pages.json`
+pub const WINDOW_PREFIX: &str = "widget-";
+/// How often widgets are opened and closed to match the config, and moves saved
+const TICK: Duration = Duration::from_secs(2);
+/// Smaller than this and a widget can't show anything nor be found to be moved
+const MIN_SIZE: f64 = 80.0;
+
+/// Each open widget as it was opened, by window label
+static OPEN: Mutex<BTreeMap<String, DesktopWidget>> = Mutex::new(BTreeMap::new());
+/// Where widgets were dragged to since the last save, by name
+static MOVED: Mutex<BTreeMap<String, LogicalPosition<f64>>> = Mutex::new(BTreeMap::new());
+
+fn label(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}{}", WINDOW_PREFIX, name)
+}
+
+/// Keep the widgets of the config on the desktop while the backend runs, see `widgets`
+pub fn start(handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        sync(&handle);
+        save_moves(&handle);
+        std::thread::sleep(TICK);
+    });
+}
+
+/// Open the widgets of the config that aren't, close the others
+///
+/// A widget whose page or size changed is opened again, its position is left to the user.
+fn sync(handle: &AppHandle) {
+    // Widgets show backend pages, there's nothing to show without it, nor while locked
+    let widgets = if supervisor::state(handle) == SupervisorState::Running && !lock::is_locked(handle) {
+        handle.state::<ConfigState>().get().widgets
+    } else {
+        Vec::new()
+    };
+    let wanted: BTreeMap<String, DesktopWidget> =
+        widgets.into_iter().map(|widget| (label(&widget.name), widget)).collect();
+
+    let mut open = OPEN.lock().unwrap();
+    open.retain(|label, opened| {
+        let Some(window) = handle.get_webview_window(label) else {
+            return false;
+        };
+        let keep = wanted.get(label).is_some_and(|widget| {
+            widget.route == opened.route && widget.width == opened.width && widget.height == opened.height
+        });
+        if !keep {
+            let _ = window.close();
+        }
+        keep
+    });
+    for (label, widget) in wanted {
+        match open.get_mut(&label) {
+            Some(opened) if opened.click_through != widget.click_through => {
+                if let Some(window) = handle.get_webview_window(&label) {
+                    let _ = window.set_ignore_cursor_events(widget.click_through);
+                }
+                *opened = widget;
+            }
+            Some(_) => {}
+            None => match build(handle, &label, &widget) {
+                Ok(()) => {
+                    open.insert(label, widget);
+                }
+                Err(e) => tracing::warn!("Failed to open the {} widget: {}", widget.name, e),
+            },
+        }
+    }
+}
+
+fn build(handle: &AppHandle, label: &str, widget: &DesktopWidget) -> Result<(), String> {
+    let base_url = handle.state::<ProfileState>().get().base_url();
+    let url = api::backend_url(&base_url, &widget.route)
+        .parse()
+        .map_err(|e| format!("Invalid route {}: {}", widget.route, e))?;
+    let builder = WebviewWindowBuilder::new(handle, label, WebviewUrl::External(url));
+    let mut builder = navigation::restrict(builder, &base_url)
+        .title(&widget.name)
+        .inner_size(widget.width, widget.height)
+        .resizable(false)
+        .decorations(false)
+        .always_on_bottom(true)
+        .visible_on_all_workspaces(true)
+        .skip_taskbar(true)
+        .focused(false);
+    if let (Some(x), Some(y)) = (widget.x, widget.y) {
+        builder = builder.position(x, y);
+    }
+    let window = builder.build().map_err(|e| e.to_string())?;
+    if widget.click_through {
+        window
+            .set_ignore_cursor_events(true)
+            .map_err(|e| format!("Failed to let clicks through: {}", e))?;
+    }
+
+    let name = widget.name.clone();
+    let moved = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Moved(position) = event {
+            let Ok(scale) = moved.scale_factor() else {
+                return;
+            };
+            // Saved on the next tick, not on every step of the drag
+            MOVED.lock().unwrap().insert(name.clone(), position.to_logical(scale));
+        }
+    });
+    Ok(())
+}
+
+/// Remember where widgets were dragged to, so they open there next time
+fn save_moves(handle: &AppHandle) {
+    let moved = std::mem::take(&mut *MOVED.lock().unwrap());
+    if moved.is_empty() {
+        return;
+    }
+    let result = config::update(handle, |config| {
+        for widget in &mut config.widgets {
+            if let Some(position) = moved.get(&widget.name) {
+                widget.x = Some(position.x);
+                widget.y = Some(position.y);
+            }
+        }
+    });
+    if let Err(e) = result {
+        tracing::warn!("Failed to save where the widgets are: {}", e);
+    }
+}
+
+fn validate(widget: &DesktopWidget) -> Result<(), String> {
+    if widget.name.trim().is_empty() {
+        return Err("Give the widget a name".to_string());
+    }
+    if !widget.route.starts_with('/') || widget.route.starts_with("//") {
+        return Err("Widgets can only show backend pages".to_string());
+    }
+    if widget.width < MIN_SIZE || widget.height < MIN_SIZE {
+        return Err(format!("Widgets are at least {} by {} pixels", MIN_SIZE, MIN_SIZE));
+    }
+    Ok(())
+}
+
+/// Show the widget manager, or focus it if it is already open
+pub fn open(handle: &AppHandle) -> tauri::Result<()> {
+    if !lock::may_show(handle) {
+        return Ok(());
+    }
+    if let Some(window) = handle.get_webview_window(MANAGER_LABEL) {
+        return window.set_focus();
+    }
+    WebviewWindowBuilder::new(handle, MANAGER_LABEL, WebviewUrl::App("widgets.html".into()))
+        .title("TSW IO Desktop Widgets")
+        .inner_size(520.0, 560.0)
+        .build()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_widgets(app: AppHandle) -> Vec<DesktopWidget> {
+    app.state::<ConfigState>().get().widgets
+}
+
+/// Add `widget`, or replace the one named `previous`, as edited in the manager
+///
+/// A replaced widget stays where it was left.
+#[tauri::command]
+pub fn save_widget(app: AppHandle, widget: DesktopWidget, previous: Option<String>) -> Result<(), String> {
+    validate(&widget)?;
+    let replaced = previous.unwrap_or_else(|| widget.name.clone());
+    let widgets = app.state::<ConfigState>().get().widgets;
+    if widgets
+        .iter()
+        .any(|existing| existing.name != replaced && label(&existing.name) == label(&widget.name))
+    {
+        return Err(format!("There is already a widget named {}", widget.name));
+    }
+    config::update(&app, |config| {
+        match config.widgets.iter_mut().find(|existing| existing.name == replaced) {
+            Some(existing) => {
+                *existing = DesktopWidget {
+                    x: existing.x,
+                    y: existing.y,
+                    ..widget.clone()
+                }
+            }
+            None => config.widgets.push(widget.clone()),
+        }
+    })?;
+    tracing::info!("Saved the {} widget showing {}", widget.name, widget.route);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn remove_widget(app: AppHandle, name: String) -> Result<(), String> {
+    config::update(&app, |config| config.widgets.retain(|widget| widget.name != name))?;
+    tracing::info!("Removed the {} widget", name);
+    Ok(())
+}
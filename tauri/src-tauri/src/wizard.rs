@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+use tauri_plugin_autostart::ManagerExt;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::audit::{self, AuditAction};
+use crate::config::{self, UpdateChannel};
+use crate::maintenance;
+use crate::paths::AppPaths;
+use crate::policy::ManagedPolicy;
+use crate::profile::{self, ProfileState};
+
+const WINDOW_LABEL: &str = "setup";
+pub(crate) const DATABASE_FILE_NAME: &str = "tsw_io.db";
+/// What SQLite keeps next to the database, named after it with these suffixes
+pub(crate) const DATABASE_SIDE_FILES: &[&str] = &["-wal", "-shm"];
+
+/// Set while the wizard is open and the backend waits for it
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Choices made in the first-run wizard
+#[derive(Debug, Deserialize)]
+pub struct SetupChoices {
+    /// `None` keeps the platform default location
+    pub data_dir: Option<PathBuf>,
+    pub autostart: bool,
+    pub update_channel: UpdateChannel,
+    pub telemetry: bool,
+    pub crash_reports: bool,
+    /// Database backup to restore into the data directory before first start
+    pub restore_from: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetupDefaults {
+    pub data_dir: PathBuf,
+    pub update_channel: UpdateChannel,
+    pub telemetry: bool,
+    /// Settings an administrator enforces, shown but not editable
+    pub update_channel_locked: bool,
+    pub telemetry_locked: bool,
+}
+
+pub fn open(handle: &AppHandle) -> tauri::Result<()> {
+    PENDING.store(true, Ordering::SeqCst);
+    WebviewWindowBuilder::new(handle, WINDOW_LABEL, WebviewUrl::App("setup.html".into()))
+        .title("Welcome to TSW IO")
+        .inner_size(560.0, 480.0)
+        .resizable(false)
+        .center()
+        .build()?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_setup_defaults(
+    profile: State<'_, ProfileState>,
+    policy: State<'_, ManagedPolicy>,
+) -> SetupDefaults {
+    SetupDefaults {
+        data_dir: profile.get().data_dir,
+        update_channel: policy.update_channel.unwrap_or(UpdateChannel::Stable),
+        telemetry: policy.telemetry.unwrap_or(false),
+        update_channel_locked: policy.update_channel.is_some(),
+        telemetry_locked: policy.telemetry.is_some(),
+    }
+}
+
+#[tauri::command]
+pub async fn pick_data_dir(app: AppHandle) -> Option<PathBuf> {
+    app.dialog()
+        .file()
+        .set_title("Choose where TSW IO stores its data")
+        .blocking_pick_folder()
+        .and_then(|path| path.into_path().ok())
+}
+
+#[tauri::command]
+pub async fn pick_backup_file(app: AppHandle) -> Option<PathBuf> {
+    app.dialog()
+        .file()
+        .set_title("Choose a backup to restore")
+        .add_filter("TSW IO database", &["db"])
+        .blocking_pick_file()
+        .and_then(|path| path.into_path().ok())
+}
+
+/// Persist the wizard choices, then start the backend
+///
+/// Only the setup window may, and only once, or a second backend would be launched.
+#[tauri::command]
+pub async fn complete_setup(app: AppHandle, window: WebviewWindow, choices: SetupChoices) -> Result<(), String> {
+    if window.label() != WINDOW_LABEL {
+        return Err("Setup can only be completed from the setup window".to_string());
+    }
+    if !PENDING.swap(false, Ordering::SeqCst) {
+        return Err("Setup was already completed".to_string());
+    }
+    if let Err(e) = apply_choices(&app, choices) {
+        // Left open, the wizard can try again
+        PENDING.store(true, Ordering::SeqCst);
+        return Err(e);
+    }
+    crate::launch(&app).map_err(|e| e.to_string())?;
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let _ = window.close();
+    }
+    Ok(())
+}
+
+fn apply_choices(app: &AppHandle, choices: SetupChoices) -> Result<(), String> {
+    let profile_name = app.state::<ProfileState>().get().name;
+    let shell_config = config::update(app, |config| {
+        if let Some(data_dir) = &choices.data_dir {
+            profile::set_data_dir(config, &profile_name, data_dir.clone());
+        }
+        config.update_channel = choices.update_channel;
+        config.telemetry = choices.telemetry;
+        config.crash_reports = choices.crash_reports;
+    })?;
+
+    // The chosen data directory changes where the active profile lives
+    let active_profile = {
+        let state = app.state::<ProfileState>();
+        let mut current = state.0.write().unwrap();
+        let mut resolved = profile::resolve(&shell_config, &current.name, &app.state::<AppPaths>())?;
+        // The mock or dev backend stays in place of whatever the profile would run
+        if crate::mock::enabled() || crate::devbackend::enabled() {
+            resolved.backend_url = current.backend_url.clone();
+            resolved.tls = false;
+        }
+        *current = resolved;
+        current.clone()
+    };
+
+    if let Some(backup) = &choices.restore_from {
+        // Nothing should run yet, but a backend must not have the database open underneath
+        crate::stop_backend_and_wait(app).map_err(|e| format!("Failed to stop the server: {}", e))?;
+        restore_backup(app, backup, &active_profile.data_dir)?;
+    }
+
+    let autolaunch = app.autolaunch();
+    let autostart_result = if choices.autostart {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    if let Err(e) = autostart_result {
+        // Not worth blocking the first launch over
+        tracing::warn!("Failed to update autostart: {}", e);
+    }
+    Ok(())
+}
+
+/// The database and its write-ahead log, which only make sense as a set
+fn database_files(data_dir: &Path) -> Vec<PathBuf> {
+    std::iter::once(DATABASE_FILE_NAME.to_string())
+        .chain(DATABASE_SIDE_FILES.iter().map(|suffix| format!("{}{}", DATABASE_FILE_NAME, suffix)))
+        .map(|name| data_dir.join(name))
+        .collect()
+}
+
+fn before_restore(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".before-restore");
+    PathBuf::from(name)
+}
+
+/// Put the database files moved aside by `restore_backup` back
+fn put_back(moved: &[PathBuf]) {
+    for file in moved {
+        if let Err(e) = std::fs::rename(before_restore(file), file) {
+            tracing::error!("Failed to put {} back: {}", file.display(), e);
+        }
+    }
+}
+
+/// Copy a database backup into place, keeping any database already there
+pub(crate) fn restore_backup(
+    handle: &AppHandle,
+    backup: &Path,
+    data_dir: &Path,
+) -> Result<(), String> {
+    let _exclusive = maintenance::exclusive("Restoring a backup")?;
+    std::fs::create_dir_all(data_dir)
+        .map_err(|e| format!("Failed to create {}: {}", data_dir.display(), e))?;
+
+    // A log left next to the restored database would be replayed into it
+    let files = database_files(data_dir);
+    for file in &files {
+        let _ = std::fs::remove_file(before_restore(file));
+    }
+    let mut moved = Vec::new();
+    for file in files.iter().filter(|file| file.exists()) {
+        if let Err(e) = std::fs::rename(file, before_restore(file)) {
+            put_back(&moved);
+            return Err(format!("Failed to move existing database aside: {}", e));
+        }
+        moved.push(file.clone());
+    }
+    let target = &files[0];
+    if !moved.is_empty() {
+        audit::record(
+            handle,
+            AuditAction::Backup,
+            format!("Kept the previous database as {}", before_restore(target).display()),
+        );
+    }
+
+    if let Err(e) = std::fs::copy(backup, target) {
+        let _ = std::fs::remove_file(target);
+        put_back(&moved);
+        return Err(format!("Failed to restore {}: {}", backup.display(), e));
+    }
+    tracing::info!("Restored database from {}", backup.display());
+    audit::record(
+        handle,
+        AuditAction::Restore,
+        format!("Restored database from {}", backup.display()),
+    );
+    Ok(())
+}
@@ -0,0 +1,161 @@
+//! Launch, restart and failure paths of the shell, against the scripted fake sidecar
+//!
+//! Each test runs the shell `--headless` in its own directories and reads the events it prints.
+//! GTK still needs a display on Linux, run these under `xvfb-run`.
+
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+use tempfile::TempDir;
+
+const EVENT_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// A headless shell running `script` as its backend
+struct Shell {
+    child: Child,
+    stdin: ChildStdin,
+    events: Receiver<Value>,
+    _dir: TempDir,
+}
+
+impl Shell {
+    fn start(script: &str) -> Shell {
+        let dir = tempfile::tempdir().unwrap();
+        let config_dir = dir.path().join("config");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        // Tests run in parallel, each backend gets a port of its own
+        std::fs::write(config_dir.join("config.toml"), format!("port = {}\n", free_port())).unwrap();
+        // A quick earlier launch keeps the startup timeout at its shortest
+        let log_dir = dir.path().join("logs");
+        std::fs::create_dir_all(&log_dir).unwrap();
+        let history = format!(r#"{{"version":"{}","ready_ms":[100]}}"#, env!("CARGO_PKG_VERSION"));
+        std::fs::write(log_dir.join("startup_history.json"), history).unwrap();
+        let script_path = dir.path().join("sidecar.script");
+        std::fs::write(&script_path, script).unwrap();
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_tsw-io"))
+            .arg("--headless")
+            .arg("--config-dir")
+            .arg(&config_dir)
+            .arg("--data-dir")
+            .arg(dir.path().join("data"))
+            .arg("--log-dir")
+            .arg(&log_dir)
+            .env("TSW_IO_FAKE_SIDECAR", &script_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Failed to start the shell");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, events) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                // Anything that isn't an event is the fake's own output
+                if let Ok(event) = serde_json::from_str::<Value>(&line) {
+                    if sender.send(event).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Shell {
+            child,
+            stdin,
+            events,
+            _dir: dir,
+        }
+    }
+
+    /// The next event named `name`, skipping the others
+    fn expect(&self, name: &str) -> Value {
+        loop {
+            let event = self
+                .events
+                .recv_timeout(EVENT_TIMEOUT)
+                .unwrap_or_else(|_| panic!("No {} event", name));
+            if event["event"] == name {
+                return event;
+            }
+        }
+    }
+
+    fn send(&mut self, command: &str) {
+        writeln!(self.stdin, "{}", command).unwrap();
+    }
+}
+
+impl Drop for Shell {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "quit");
+        std::thread::sleep(Duration::from_millis(500));
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+#[test]
+fn launch_waits_for_the_backend_to_be_healthy() {
+    let mut shell = Shell::start("listen\nsleep 500\nhealthy\nready\n");
+
+    let ready = shell.expect("ready");
+    let url = ready["url"].as_str().unwrap();
+    let health = reqwest::blocking::get(format!("{}/api/health", url)).unwrap();
+    assert_eq!(health.status(), 200);
+
+    shell.send("status");
+    let status = shell.expect("status");
+    assert_eq!(status["state"], "running");
+    assert_eq!(status["pid"], ready["pid"]);
+}
+
+#[test]
+fn supervisor_restarts_a_crashed_backend() {
+    let shell = Shell::start("listen\nhealthy\nready\nsleep 1000\ncrash\n");
+
+    let first = shell.expect("ready");
+    let second = shell.expect("ready");
+    assert_ne!(first["pid"], second["pid"]);
+}
+
+#[test]
+fn supervisor_gives_up_on_a_crash_loop() {
+    // Up long enough to count as running, so every exit is a crash and not a failed start
+    let shell = Shell::start("listen\nhealthy\nready\nsleep 1500\nexit 1\n");
+
+    let failed = shell.expect("failed");
+    assert_eq!(failed["message"], "The server keeps crashing");
+}
+
+#[test]
+fn startup_failure_is_classified_from_the_output() {
+    let shell = Shell::start("stderr ** (Exqlite.Error) database is locked\nexit 1\n");
+
+    let failed = shell.expect("failed");
+    assert_eq!(failed["failure"], "database_locked");
+}
+
+#[test]
+fn startup_failure_without_output_is_a_crash() {
+    let shell = Shell::start("sleep 200\nexit 3\n");
+
+    let failed = shell.expect("failed");
+    assert_eq!(failed["failure"], "crashed");
+}
+
+#[test]
+fn backend_that_never_gets_healthy_times_out() {
+    let shell = Shell::start("listen\n");
+
+    let failed = shell.expect("failed");
+    assert_eq!(failed["failure"], "timeout");
+}
@@ -6,5 +6,6 @@ Mimic.copy(TswIo.Train.Identifier)
 Mimic.copy(TswIo.Simulator.Client)
 Mimic.copy(TswIo.Simulator)
 
-ExUnit.start()
+# The SQLCipher tests need exqlite built against it, see the sqlcipher CI job
+ExUnit.start(exclude: [:sqlcipher])
 Ecto.Adapters.SQL.Sandbox.mode(TswIo.Repo, :manual)
@@ -0,0 +1,43 @@
+defmodule TswIo.AppVersionTest do
+  use ExUnit.Case, async: false
+
+  alias TswIo.AppVersion
+
+  setup do
+    on_exit(fn -> Application.delete_env(:tsw_io, :update_channel) end)
+  end
+
+  describe "update_channel/0" do
+    test "follows stable releases unless the shell picked beta" do
+      assert AppVersion.update_channel() == :stable
+
+      Application.put_env(:tsw_io, :update_channel, :beta)
+
+      assert AppVersion.update_channel() == :beta
+    end
+  end
+
+  describe "newest_release/1" do
+    test "takes the newest release, pre-releases included" do
+      releases = [
+        %{"tag_name" => "v1.3.0-beta.1", "draft" => false, "prerelease" => true},
+        %{"tag_name" => "v1.2.0", "draft" => false, "prerelease" => false}
+      ]
+
+      assert AppVersion.newest_release(releases) == {:ok, "1.3.0-beta.1"}
+    end
+
+    test "skips drafts" do
+      releases = [
+        %{"tag_name" => "v1.4.0", "draft" => true, "prerelease" => false},
+        %{"tag_name" => "v1.3.0", "draft" => false, "prerelease" => false}
+      ]
+
+      assert AppVersion.newest_release(releases) == {:ok, "1.3.0"}
+    end
+
+    test "reports a repository without releases" do
+      assert AppVersion.newest_release([]) == {:error, :no_releases}
+    end
+  end
+end
@@ -0,0 +1,58 @@
+defmodule TswIo.DatabaseEncryptionTest do
+  use ExUnit.Case, async: true
+
+  alias Exqlite.Sqlite3
+
+  @moduletag :sqlcipher
+  @moduletag :tmp_dir
+
+  defp create_encrypted(path, key) do
+    {:ok, conn} = Sqlite3.open(path)
+    :ok = Sqlite3.execute(conn, "PRAGMA key = '#{key}'")
+    :ok = Sqlite3.execute(conn, "CREATE TABLE devices (name TEXT)")
+    :ok = Sqlite3.execute(conn, "INSERT INTO devices (name) VALUES ('throttle')")
+    :ok = Sqlite3.close(conn)
+  end
+
+  defp first_row(conn, sql) do
+    {:ok, statement} = Sqlite3.prepare(conn, sql)
+    result = Sqlite3.step(conn, statement)
+    :ok = Sqlite3.release(conn, statement)
+    result
+  end
+
+  test "exqlite is linked against SQLCipher", %{tmp_dir: tmp_dir} do
+    {:ok, conn} = Sqlite3.open(Path.join(tmp_dir, "version.db"))
+
+    assert {:row, [version]} = first_row(conn, "PRAGMA cipher_version")
+    assert version != ""
+
+    :ok = Sqlite3.close(conn)
+  end
+
+  test "a keyed database is unreadable without its passphrase", %{tmp_dir: tmp_dir} do
+    path = Path.join(tmp_dir, "tsw_io.db")
+    create_encrypted(path, "correct horse")
+
+    header = path |> File.read!() |> binary_part(0, 16)
+    refute header == "SQLite format 3\0"
+
+    {:ok, conn} = Sqlite3.open(path)
+    :ok = Sqlite3.execute(conn, "PRAGMA key = 'wrong'")
+    assert {:error, message} = Sqlite3.execute(conn, "SELECT count(*) FROM devices")
+    assert message =~ "file is not a database"
+    :ok = Sqlite3.close(conn)
+  end
+
+  test "the repo opens it with the passphrase as its key", %{tmp_dir: tmp_dir} do
+    path = Path.join(tmp_dir, "tsw_io.db")
+    create_encrypted(path, "correct horse")
+
+    # What config/runtime.exs passes TswIo.Repo from TSW_IO_DB_KEY
+    assert {:ok, state} = Exqlite.Connection.connect(database: path, key: "correct horse")
+
+    assert {:row, ["throttle"]} = first_row(state.db, "SELECT name FROM devices")
+
+    :ok = Exqlite.Connection.disconnect(:normal, state)
+  end
+end
@@ -342,4 +342,17 @@ defmodule TswIo.Firmware.DownloaderTest do
                Downloader.download_firmware(uno_file.id)
     end
   end
+
+  describe "in air-gapped mode" do
+    setup do
+      Application.put_env(:tsw_io, :offline, true)
+      on_exit(fn -> Application.delete_env(:tsw_io, :offline) end)
+    end
+
+    test "check_for_updates/0 doesn't contact GitHub" do
+      reject(&Req.get/2)
+
+      assert {:error, :offline} = Downloader.check_for_updates()
+    end
+  end
 end
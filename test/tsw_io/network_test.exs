@@ -0,0 +1,52 @@
+defmodule TswIo.NetworkTest do
+  use ExUnit.Case, async: false
+
+  import ExUnit.CaptureLog
+
+  alias TswIo.Network
+
+  setup do
+    on_exit(fn ->
+      for key <- [:http_proxy, :ca_certificates, :os_certificates] do
+        Application.delete_env(:tsw_io, key)
+      end
+    end)
+  end
+
+  describe "req_options/0" do
+    test "leaves Req to its defaults when nothing is configured" do
+      assert Network.req_options() == []
+    end
+
+    test "goes through the configured proxy" do
+      Application.put_env(:tsw_io, :http_proxy, "http://proxy.corp:3128")
+
+      assert Network.req_options() == [connect_options: [proxy: {:http, "proxy.corp", 3128, []}]]
+    end
+
+    test "ignores a proxy that isn't an HTTP URL" do
+      Application.put_env(:tsw_io, :http_proxy, "socks5://proxy.corp:1080")
+
+      assert capture_log(fn -> assert Network.req_options() == [] end) =~ "Ignoring the proxy"
+    end
+
+    @tag :tmp_dir
+    test "trusts the certificates in the configured file", %{tmp_dir: tmp_dir} do
+      path = Path.join(tmp_dir, "corp.pem")
+      File.write!(path, :public_key.pem_encode([{:Certificate, "corp-root", :not_encrypted}]))
+      Application.put_env(:tsw_io, :ca_certificates, path)
+      Application.put_env(:tsw_io, :os_certificates, false)
+
+      assert Network.req_options() == [connect_options: [transport_opts: [cacerts: ["corp-root"]]]]
+    end
+
+    test "skips a certificate file that can't be read" do
+      Application.put_env(:tsw_io, :ca_certificates, "/nonexistent/corp.pem")
+      Application.put_env(:tsw_io, :os_certificates, false)
+
+      assert capture_log(fn ->
+               assert Network.req_options() == [connect_options: [transport_opts: [cacerts: []]]]
+             end) =~ "Failed to read the certificates"
+    end
+  end
+end
@@ -0,0 +1,90 @@
+defmodule TswIo.PairingTest do
+  # There is one pending code for the whole backend
+  use TswIo.DataCase, async: false
+
+  alias TswIo.Pairing
+  alias TswIo.Pairing.Device
+
+  test "a code can be redeemed once for a device token" do
+    {:ok, code, expires_at} = Pairing.start()
+
+    assert expires_at > System.os_time(:second)
+    assert {:ok, token} = Pairing.redeem(code, "Phone")
+    assert Pairing.valid_device_token?(token)
+    assert {:error, :invalid} = Pairing.redeem(code, "Phone")
+  end
+
+  test "a new code replaces the previous one" do
+    {:ok, old_code, _} = Pairing.start()
+    {:ok, code, _} = Pairing.start()
+
+    assert {:error, :invalid} = Pairing.redeem(old_code, "Phone")
+    assert {:ok, _token} = Pairing.redeem(code, "Phone")
+  end
+
+  test "a wrong code leaves the current one usable" do
+    {:ok, code, _} = Pairing.start()
+
+    assert {:error, :invalid} = Pairing.redeem("guess", "Phone")
+    assert {:ok, _token} = Pairing.redeem(code, "Phone")
+  end
+
+  test "expired codes are refused" do
+    Agent.update(Pairing, fn _ -> {"old-code", System.os_time(:second) - 1} end)
+
+    assert {:error, :expired} = Pairing.redeem("old-code", "Phone")
+  end
+
+  test "guest tokens are device tokens until they get old" do
+    token = Pairing.guest_token()
+
+    assert Pairing.valid_device_token?(token)
+
+    old =
+      Phoenix.Token.sign(TswIoWeb.Endpoint, "paired device", %{"guest" => true},
+        signed_at: System.system_time(:second) - 13 * 60 * 60
+      )
+
+    refute Pairing.valid_device_token?(old)
+  end
+
+  test "only signed tokens are device tokens" do
+    refute Pairing.valid_device_token?("made-up")
+    refute Pairing.valid_device_token?(nil)
+  end
+
+  test "redeeming a code pairs the device" do
+    {:ok, code, _} = Pairing.start()
+    {:ok, _token} = Pairing.redeem(code, "Phone")
+
+    assert [%Device{name: "Phone", expires_at: expires_at}] = Pairing.list_devices()
+    assert DateTime.diff(expires_at, DateTime.utc_now(), :day) in 89..90
+  end
+
+  test "unpairing a device revokes its token" do
+    {:ok, code, _} = Pairing.start()
+    {:ok, token} = Pairing.redeem(code, "Phone")
+    [device] = Pairing.list_devices()
+
+    assert :ok = Pairing.unpair(device.id)
+    refute Pairing.valid_device_token?(token)
+    assert Pairing.list_devices() == []
+    assert {:error, :not_found} = Pairing.unpair(device.id)
+  end
+
+  test "tokens of expired devices are refused" do
+    {:ok, code, _} = Pairing.start()
+    {:ok, token} = Pairing.redeem(code, "Phone")
+
+    Repo.update_all(Device, set: [expires_at: DateTime.add(DateTime.utc_now(:second), -1)])
+
+    refute Pairing.valid_device_token?(token)
+    assert Pairing.list_devices() == []
+  end
+
+  test "tokens that name no stored device are refused" do
+    unstored = Phoenix.Token.sign(TswIoWeb.Endpoint, "paired device", %{"name" => "Phone"})
+
+    refute Pairing.valid_device_token?(unstored)
+  end
+end
@@ -0,0 +1,26 @@
+defmodule TswIo.ReadinessTest do
+  use ExUnit.Case, async: false
+
+  import ExUnit.CaptureIO
+
+  alias TswIo.Readiness
+
+  setup do
+    on_exit(fn -> Application.delete_env(:tsw_io, :ready_signal) end)
+  end
+
+  describe "announce/0" do
+    test "prints the marker on its own line when the shell asked for it" do
+      Application.put_env(:tsw_io, :ready_signal, true)
+
+      assert capture_io(fn -> assert :ok = Readiness.announce() end) ==
+               Readiness.marker() <> "\n"
+    end
+
+    test "prints nothing otherwise" do
+      Application.put_env(:tsw_io, :ready_signal, false)
+
+      assert capture_io(fn -> assert :ok = Readiness.announce() end) == ""
+    end
+  end
+end
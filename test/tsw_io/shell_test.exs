@@ -0,0 +1,88 @@
+defmodule TswIo.ShellTest do
+  use ExUnit.Case, async: false
+
+  alias TswIo.Shell
+
+  setup do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    on_exit(fn -> Application.delete_env(:tsw_io, :shell_token) end)
+  end
+
+  defp confirmation(reason, opts \\ []) do
+    token = Keyword.get(opts, :token, "shell-token")
+    issued_at = Keyword.get(opts, :issued_at, System.os_time(:second))
+    nonce = Keyword.get_lazy(opts, :nonce, fn -> Base.encode16(:crypto.strong_rand_bytes(8)) end)
+
+    %{
+      "reason" => reason,
+      "issued_at" => issued_at,
+      "nonce" => nonce,
+      "signature" => Shell.confirmation_signature(token, reason, issued_at, nonce)
+    }
+  end
+
+  describe "feature_enabled?/1" do
+    setup do
+      Application.put_env(:tsw_io, :shell_features, ["tray", "telemetry"])
+      on_exit(fn -> Application.delete_env(:tsw_io, :shell_features) end)
+    end
+
+    test "is true for the flags the shell turned on" do
+      assert Shell.feature_enabled?("telemetry")
+      refute Shell.feature_enabled?("widgets")
+    end
+
+    test "is false for every flag without a shell" do
+      Application.delete_env(:tsw_io, :shell_features)
+
+      refute Shell.feature_enabled?("tray")
+    end
+  end
+
+  describe "verify_confirmation/2" do
+    test "accepts a fresh confirmation for the reason" do
+      assert :ok = Shell.verify_confirmation(confirmation("delete workspace"), "delete workspace")
+    end
+
+    test "refuses a confirmation used before" do
+      used = confirmation("delete workspace")
+
+      assert :ok = Shell.verify_confirmation(used, "delete workspace")
+      assert {:error, :invalid} = Shell.verify_confirmation(used, "delete workspace")
+    end
+
+    test "refuses a confirmation for another reason" do
+      assert {:error, :invalid} =
+               Shell.verify_confirmation(confirmation("export all data"), "delete workspace")
+    end
+
+    test "refuses a confirmation signed with another token" do
+      assert {:error, :invalid} =
+               Shell.verify_confirmation(
+                 confirmation("delete workspace", token: "guess"),
+                 "delete workspace"
+               )
+    end
+
+    test "refuses a tampered confirmation" do
+      tampered = %{confirmation("delete workspace") | "nonce" => "other"}
+
+      assert {:error, :invalid} = Shell.verify_confirmation(tampered, "delete workspace")
+    end
+
+    test "refuses an old confirmation" do
+      old = confirmation("delete workspace", issued_at: System.os_time(:second) - 120)
+
+      assert {:error, :expired} = Shell.verify_confirmation(old, "delete workspace")
+    end
+
+    test "refuses everything when the shell didn't pass a token" do
+      Application.delete_env(:tsw_io, :shell_token)
+
+      assert {:error, :invalid} =
+               Shell.verify_confirmation(confirmation("delete workspace"), "delete workspace")
+
+      assert {:error, :invalid} = Shell.verify_confirmation(%{}, "delete workspace")
+    end
+  end
+end
@@ -0,0 +1,64 @@
+defmodule TswIo.SsoTest do
+  use ExUnit.Case, async: false
+
+  alias TswIo.Sso
+
+  setup do
+    Application.put_env(:tsw_io, :sso, cookie: "tsw_io_sso", secret: "sso-secret")
+    on_exit(fn -> Application.delete_env(:tsw_io, :sso) end)
+  end
+
+  defp encode(map), do: map |> Jason.encode!() |> Base.url_encode64(padding: false)
+
+  defp token(claims, opts \\ []) do
+    secret = Keyword.get(opts, :secret, "sso-secret")
+    alg = Keyword.get(opts, :alg, "HS256")
+    signed = encode(%{"alg" => alg, "typ" => "JWT"}) <> "." <> encode(claims)
+    signature = :crypto.mac(:hmac, :sha256, secret, signed) |> Base.url_encode64(padding: false)
+    signed <> "." <> signature
+  end
+
+  defp in_an_hour, do: System.os_time(:second) + 3600
+
+  test "accepts a token signed with the secret" do
+    claims = %{"sub" => "driver", "exp" => in_an_hour()}
+
+    assert Sso.verify(token(claims)) == {:ok, claims}
+  end
+
+  test "rejects a token signed with another secret" do
+    assert Sso.verify(token(%{"sub" => "driver"}, secret: "other")) == {:error, :invalid}
+  end
+
+  test "rejects a token whose claims were changed" do
+    [header, _claims, signature] = String.split(token(%{"sub" => "driver"}), ".")
+    forged = Enum.join([header, encode(%{"sub" => "admin"}), signature], ".")
+
+    assert Sso.verify(forged) == {:error, :invalid}
+  end
+
+  test "rejects an algorithm without a key" do
+    assert Sso.verify(token(%{"sub" => "driver"}, alg: "none")) == {:error, :invalid}
+    assert Sso.verify(token(%{"sub" => "driver"}, alg: "RS256")) == {:error, :invalid}
+  end
+
+  test "rejects expired and not yet valid tokens" do
+    now = System.os_time(:second)
+
+    assert Sso.verify(token(%{"exp" => now - 1})) == {:error, :expired}
+    assert Sso.verify(token(%{"nbf" => now + 60})) == {:error, :invalid}
+  end
+
+  test "rejects what isn't a token" do
+    assert Sso.verify(nil) == {:error, :invalid}
+    assert Sso.verify("not-a-token") == {:error, :invalid}
+  end
+
+  test "is off without a cookie name" do
+    assert Sso.enabled?()
+
+    Application.put_env(:tsw_io, :sso, cookie: nil)
+
+    refute Sso.enabled?()
+  end
+end
@@ -0,0 +1,91 @@
+defmodule TswIoWeb.ShellChannelTest do
+  use ExUnit.Case, async: false
+
+  import Phoenix.ChannelTest
+
+  alias TswIo.Shell
+  alias TswIoWeb.ShellSocket
+
+  @endpoint TswIoWeb.Endpoint
+
+  setup do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    on_exit(fn -> Application.delete_env(:tsw_io, :shell_token) end)
+  end
+
+  describe "connect/3" do
+    test "accepts the shell's token" do
+      assert {:ok, _socket} = connect(ShellSocket, %{"token" => "shell-token"})
+    end
+
+    test "refuses any other token" do
+      assert :error = connect(ShellSocket, %{"token" => "guess"})
+      assert :error = connect(ShellSocket, %{})
+    end
+
+    test "refuses everyone when the shell didn't pass a token" do
+      Application.delete_env(:tsw_io, :shell_token)
+
+      assert :error = connect(ShellSocket, %{"token" => ""})
+    end
+  end
+
+  describe "events" do
+    setup do
+      {:ok, socket} = connect(ShellSocket, %{"token" => "shell-token"})
+      {:ok, _reply, socket} = subscribe_and_join(socket, Shell.topic(), %{})
+      %{socket: socket}
+    end
+
+    test "emitted events are pushed to the shell" do
+      Shell.emit("notification", %{"title" => "Done"})
+
+      assert_push "notification", %{"title" => "Done"}
+    end
+
+    test "notifications carry their actions" do
+      Shell.notify("Firmware ready",
+        id: "firmware-1",
+        body: "Version 1.2 can be installed",
+        target: "/firmware",
+        actions: [
+          %{id: "install", title: "Install", target: "/firmware"},
+          [id: "snooze", title: "Snooze"]
+        ]
+      )
+
+      assert_push "notification", %{
+        title: "Firmware ready",
+        id: "firmware-1",
+        body: "Version 1.2 can be installed",
+        target: "/firmware",
+        actions: [
+          %{id: "install", title: "Install", target: "/firmware"},
+          %{id: "snooze", title: "Snooze"}
+        ]
+      }
+    end
+
+    test "chosen notification actions reach subscribers", %{socket: socket} do
+      :ok = Shell.subscribe()
+
+      push(socket, "notification_action", %{"id" => "firmware-1", "action" => "snooze"})
+
+      assert_receive {:shell_event, "notification_action", %{"id" => "firmware-1", "action" => "snooze"}}
+    end
+
+    test "events from the shell reach subscribers", %{socket: socket} do
+      :ok = Shell.subscribe()
+
+      push(socket, "window_focused", %{"focused" => true})
+
+      assert_receive {:shell_event, "window_focused", %{"focused" => true}}
+    end
+
+    test "events without an object payload are rejected", %{socket: socket} do
+      ref = push(socket, "window_focused", "yes")
+
+      assert_reply ref, :error, %{reason: _}
+    end
+  end
+end
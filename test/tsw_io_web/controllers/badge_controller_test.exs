@@ -0,0 +1,39 @@
+defmodule TswIoWeb.BadgeControllerTest do
+  # The shell token and the badge are global state
+  use TswIoWeb.ConnCase, async: false
+
+  alias TswIo.Badge
+
+  setup %{conn: conn} do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+
+    on_exit(fn ->
+      Application.delete_env(:tsw_io, :shell_token)
+      Badge.set(0)
+    end)
+
+    %{conn: put_req_header(conn, "authorization", "Bearer shell-token")}
+  end
+
+  describe "GET /api/admin/badge" do
+    test "is 0 until something sets it", %{conn: conn} do
+      conn = get(conn, ~p"/api/admin/badge")
+
+      assert json_response(conn, 200) == %{"count" => 0}
+    end
+
+    test "holds the last count set", %{conn: conn} do
+      Badge.set(3)
+
+      conn = get(conn, ~p"/api/admin/badge")
+
+      assert json_response(conn, 200) == %{"count" => 3}
+    end
+
+    test "needs the shell token" do
+      conn = get(build_conn(), ~p"/api/admin/badge")
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+end
@@ -0,0 +1,49 @@
+defmodule TswIoWeb.ExportControllerTest do
+  # The shell token is global state
+  use TswIoWeb.ConnCase, async: false
+
+  alias TswIo.{Hardware, Repo, Train}
+  alias TswIo.Simulator.Config
+
+  setup %{conn: conn} do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    on_exit(fn -> Application.delete_env(:tsw_io, :shell_token) end)
+
+    %{conn: put_req_header(conn, "authorization", "Bearer shell-token")}
+  end
+
+  describe "GET /api/admin/export" do
+    test "holds the rows of every table", %{conn: conn} do
+      {:ok, device} = Hardware.create_device(%{name: "Desk stand"})
+      {:ok, train} = Train.create_train(%{name: "Class 66", identifier: "BR_Class_66"})
+
+      conn = get(conn, ~p"/api/admin/export")
+
+      assert %{"format_version" => 1, "backend_version" => version, "tables" => tables} =
+               json_response(conn, 200)
+
+      assert get_resp_header(conn, "x-tsw-io-version") == [version]
+      assert [%{"id" => device_id, "name" => "Desk stand"}] = tables["devices"]
+      assert device_id == device.id
+      assert [%{"identifier" => "BR_Class_66"} = exported_train] = tables["trains"]
+      assert exported_train["id"] == train.id
+      assert tables["device_inputs"] == []
+    end
+
+    test "leaves the simulator API key out", %{conn: conn} do
+      Repo.insert!(%Config{url: "http://localhost:31270", api_key: "secret-key"})
+
+      conn = get(conn, ~p"/api/admin/export")
+
+      assert [config] = json_response(conn, 200)["tables"]["simulator_configs"]
+      assert config["url"] == "http://localhost:31270"
+      refute Map.has_key?(config, "api_key")
+    end
+
+    test "requires the shell token" do
+      conn = get(build_conn(), ~p"/api/admin/export")
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+end
@@ -0,0 +1,57 @@
+defmodule TswIoWeb.ExternalEventControllerTest do
+  # The shell token is global state
+  use TswIoWeb.ConnCase, async: false
+
+  alias TswIo.ExternalEvents
+
+  setup %{conn: conn} do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    on_exit(fn -> Application.delete_env(:tsw_io, :shell_token) end)
+
+    %{conn: put_req_header(conn, "authorization", "Bearer shell-token")}
+  end
+
+  describe "POST /api/admin/external_events" do
+    test "hands the event to the subscribers", %{conn: conn} do
+      :ok = ExternalEvents.subscribe()
+
+      conn =
+        post(conn, ~p"/api/admin/external_events", %{
+          event: "clip",
+          source: "chrome-extension://abcdef",
+          payload: %{url: "https://example.com/class-66"}
+        })
+
+      assert %{"event" => "clip", "received_at" => _} = json_response(conn, 200)
+
+      assert_receive {:external_event,
+                      %{
+                        name: "clip",
+                        source: "chrome-extension://abcdef",
+                        payload: %{"url" => "https://example.com/class-66"}
+                      }}
+    end
+
+    test "says it came from a local tool when the shell doesn't know better", %{conn: conn} do
+      :ok = ExternalEvents.subscribe()
+
+      post(conn, ~p"/api/admin/external_events", %{event: "ping"})
+
+      assert_receive {:external_event, %{name: "ping", source: "local", payload: nil}}
+    end
+
+    test "refuses events without a usable name", %{conn: conn} do
+      assert json_response(post(conn, ~p"/api/admin/external_events", %{}), 422) ==
+               %{"error" => "invalid_event"}
+
+      assert json_response(post(conn, ~p"/api/admin/external_events", %{event: "Not a name"}), 422) ==
+               %{"error" => "invalid_event"}
+    end
+
+    test "requires the shell token" do
+      conn = post(build_conn(), ~p"/api/admin/external_events", %{event: "clip"})
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+end
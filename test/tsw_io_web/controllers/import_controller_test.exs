@@ -0,0 +1,141 @@
+defmodule TswIoWeb.ImportControllerTest do
+  # The shell token and the data directory are global state
+  use TswIoWeb.ConnCase, async: false
+
+  alias TswIo.{Train, Uploads}
+
+  @moduletag :tmp_dir
+
+  setup %{conn: conn, tmp_dir: tmp_dir} do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    previous_data_dir = System.get_env("TSW_IO_DATA_DIR")
+    System.put_env("TSW_IO_DATA_DIR", tmp_dir)
+
+    on_exit(fn ->
+      Application.delete_env(:tsw_io, :shell_token)
+
+      if previous_data_dir,
+        do: System.put_env("TSW_IO_DATA_DIR", previous_data_dir),
+        else: System.delete_env("TSW_IO_DATA_DIR")
+    end)
+
+    %{conn: put_req_header(conn, "authorization", "Bearer shell-token")}
+  end
+
+  defp upload(filename, contents) do
+    {:ok, %{id: id}} = Uploads.create(filename, byte_size(contents))
+    {:ok, %{complete: true}} = Uploads.append(id, 0, contents)
+    id
+  end
+
+  defp import(conn, filename, contents) do
+    post(conn, ~p"/api/admin/imports", %{upload_id: upload(filename, contents)})
+  end
+
+  defp xlsx(shared_strings, rows) do
+    shared = Enum.map_join(shared_strings, &"<si><t>#{&1}</t></si>")
+
+    {:ok, {_name, workbook}} =
+      :zip.create(
+        ~c"trains.xlsx",
+        [
+          {~c"xl/sharedStrings.xml", "<sst>#{shared}</sst>"},
+          {~c"xl/worksheets/sheet1.xml", "<worksheet><sheetData>#{rows}</sheetData></worksheet>"}
+        ],
+        [:memory]
+      )
+
+    workbook
+  end
+
+  describe "POST /api/admin/imports" do
+    test "creates the trains of a CSV file", %{conn: conn} do
+      csv = """
+      Name,Identifier,Description,Notes
+      Class 66,BR_Class_66,"Freight, diesel",ignored
+      "Class 43 ""HST""",BR_Class_43,,
+      """
+
+      assert %{"filename" => "trains.csv", "created" => 2, "skipped" => 0, "failed" => []} =
+               conn |> import("trains.csv", csv) |> json_response(200)
+
+      assert {:ok, %{name: "Class 66", description: "Freight, diesel"}} =
+               Train.get_train_by_identifier("BR_Class_66")
+
+      assert {:ok, %{name: ~s(Class 43 "HST"), description: nil}} =
+               Train.get_train_by_identifier("BR_Class_43")
+    end
+
+    test "reads files Excel wrote with semicolons", %{conn: conn} do
+      csv = "\uFEFFname;identifier\r\nClass 66;BR_Class_66\r\n"
+
+      assert %{"created" => 1} = conn |> import("trains.csv", csv) |> json_response(200)
+      assert {:ok, _train} = Train.get_train_by_identifier("BR_Class_66")
+    end
+
+    test "skips trains that are known and lists the rows that failed", %{conn: conn} do
+      {:ok, _train} = Train.create_train(%{name: "Class 66", identifier: "BR_Class_66"})
+
+      csv = """
+      name,identifier
+      Class 66 again,BR_Class_66
+
+      Class 43,
+      """
+
+      assert %{"created" => 0, "skipped" => 1, "failed" => [failure]} =
+               conn |> import("trains.csv", csv) |> json_response(200)
+
+      assert failure == %{"row" => 4, "errors" => ["identifier can't be blank"]}
+      assert {:ok, %{name: "Class 66"}} = Train.get_train_by_identifier("BR_Class_66")
+    end
+
+    test "reads the first sheet of an Excel workbook", %{conn: conn} do
+      rows = """
+      <row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="inlineStr"><is><t>identifier</t></is></c><c r="C1" t="s"><v>1</v></c></row>
+      <row r="2"/>
+      <row r="3"><c r="A3" t="inlineStr"><is><t>Class 43 &amp; HST</t></is></c><c r="B3" t="inlineStr"><is><t>BR_Class_43</t></is></c></row>
+      <row r="4"><c r="A4" t="inlineStr"><is><t>Class 66</t></is></c><c r="C4" t="inlineStr"><is><t>No identifier</t></is></c></row>
+      """
+
+      assert %{"created" => 1, "failed" => [%{"row" => 4}]} =
+               conn |> import("trains.xlsx", xlsx(["name", "description"], rows)) |> json_response(200)
+
+      assert {:ok, %{name: "Class 43 & HST"}} = Train.get_train_by_identifier("BR_Class_43")
+    end
+
+    test "removes the upload once it is imported", %{conn: conn} do
+      id = upload("trains.csv", "name,identifier\nClass 66,BR_Class_66\n")
+
+      post(conn, ~p"/api/admin/imports", %{upload_id: id})
+
+      assert Uploads.status(id) == {:error, :not_found}
+    end
+
+    test "refuses files without the required columns", %{conn: conn} do
+      conn = import(conn, "trains.csv", "name,description\nClass 66,Freight\n")
+
+      assert json_response(conn, 422) == %{"error" => "missing_columns", "columns" => ["identifier"]}
+    end
+
+    test "refuses other kinds of files", %{conn: conn} do
+      assert json_response(import(conn, "trains.ods", "name"), 422) == %{"error" => "unsupported_format"}
+      assert json_response(import(conn, "trains.xlsx", "not a zip"), 422) == %{"error" => "invalid_file"}
+      assert json_response(import(conn, "trains.csv", "name,\"identifier"), 422) == %{"error" => "invalid_file"}
+    end
+
+    test "refuses uploads that aren't complete", %{conn: conn} do
+      {:ok, %{id: id}} = Uploads.create("trains.csv", 6)
+
+      conn = post(conn, ~p"/api/admin/imports", %{upload_id: id})
+
+      assert json_response(conn, 422) == %{"error" => "incomplete_upload"}
+    end
+
+    test "requires the shell token" do
+      conn = post(build_conn(), ~p"/api/admin/imports", %{upload_id: "0"})
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+end
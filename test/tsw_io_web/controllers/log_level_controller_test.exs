@@ -0,0 +1,53 @@
+defmodule TswIoWeb.LogLevelControllerTest do
+  # Logger level and the shell token are global state, so these tests can't run concurrently
+  use TswIoWeb.ConnCase, async: false
+
+  setup %{conn: conn} do
+    original_level = Logger.level()
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+
+    on_exit(fn ->
+      Logger.configure(level: original_level)
+      Application.delete_env(:tsw_io, :shell_token)
+    end)
+
+    %{conn: put_req_header(conn, "authorization", "Bearer shell-token")}
+  end
+
+  describe "GET /api/admin/log_level" do
+    test "returns the current level", %{conn: conn} do
+      Logger.configure(level: :info)
+
+      conn = get(conn, ~p"/api/admin/log_level")
+
+      assert json_response(conn, 200) == %{"level" => "info"}
+    end
+  end
+
+  describe "PUT /api/admin/log_level" do
+    test "changes the logger level", %{conn: conn} do
+      conn = put(conn, ~p"/api/admin/log_level", %{level: "debug"})
+
+      assert json_response(conn, 200) == %{"level" => "debug"}
+      assert Logger.level() == :debug
+    end
+
+    test "rejects unknown levels", %{conn: conn} do
+      Logger.configure(level: :info)
+
+      conn = put(conn, ~p"/api/admin/log_level", %{level: "verbose"})
+
+      assert %{"error" => "invalid_level"} = json_response(conn, 422)
+      assert Logger.level() == :info
+    end
+
+    test "needs the shell token" do
+      Logger.configure(level: :info)
+
+      conn = put(build_conn(), ~p"/api/admin/log_level", %{level: "debug"})
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+      assert Logger.level() == :info
+    end
+  end
+end
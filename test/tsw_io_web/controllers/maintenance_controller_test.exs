@@ -0,0 +1,72 @@
+defmodule TswIoWeb.MaintenanceControllerTest do
+  # The shell token is global state
+  use TswIoWeb.ConnCase, async: false
+
+  setup %{conn: conn} do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    on_exit(fn -> Application.delete_env(:tsw_io, :shell_token) end)
+
+    %{conn: put_req_header(conn, "authorization", "Bearer shell-token")}
+  end
+
+  describe "POST /api/admin/maintenance/:task" do
+    test "runs the task", %{conn: conn} do
+      conn = post(conn, ~p"/api/admin/maintenance/reindex")
+
+      assert %{"task" => "reindex", "message" => _} = json_response(conn, 200)
+    end
+
+    test "rejects unknown tasks", %{conn: conn} do
+      conn = post(conn, ~p"/api/admin/maintenance/defragment")
+
+      assert %{"error" => "unknown_task", "valid_tasks" => tasks} = json_response(conn, 422)
+      assert "vacuum" in tasks
+    end
+
+    test "requires the shell token" do
+      conn = post(build_conn(), ~p"/api/admin/maintenance/reindex")
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+
+    test "rejects other tokens" do
+      conn =
+        build_conn()
+        |> put_req_header("authorization", "Bearer guess")
+        |> post(~p"/api/admin/maintenance/reindex")
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+
+  describe "POST /api/admin/backup" do
+    test "needs a path", %{conn: conn} do
+      conn = post(conn, ~p"/api/admin/backup", %{})
+
+      assert json_response(conn, 422) == %{"error" => "missing_path"}
+    end
+
+    test "rejects relative paths", %{conn: conn} do
+      conn = post(conn, ~p"/api/admin/backup", %{path: "backup.db"})
+
+      assert json_response(conn, 422) == %{"error" => "relative_path"}
+    end
+
+    @tag :tmp_dir
+    test "never overwrites a file", %{conn: conn, tmp_dir: tmp_dir} do
+      path = Path.join(tmp_dir, "backup.db")
+      File.write!(path, "keep me")
+
+      conn = post(conn, ~p"/api/admin/backup", %{path: path})
+
+      assert json_response(conn, 422) == %{"error" => "already_exists"}
+      assert File.read!(path) == "keep me"
+    end
+
+    test "requires the shell token" do
+      conn = post(build_conn(), ~p"/api/admin/backup", %{path: "/tmp/backup.db"})
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+end
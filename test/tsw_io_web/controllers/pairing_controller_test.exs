@@ -0,0 +1,136 @@
+defmodule TswIoWeb.PairingControllerTest do
+  # The shell token and the pending pairing code are global state
+  use TswIoWeb.ConnCase, async: false
+
+  setup do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    on_exit(fn -> Application.delete_env(:tsw_io, :shell_token) end)
+  end
+
+  defp start_pairing do
+    build_conn()
+    |> put_req_header("authorization", "Bearer shell-token")
+    |> post(~p"/api/admin/pairing")
+    |> json_response(200)
+  end
+
+  describe "POST /api/admin/pairing" do
+    test "makes a code" do
+      assert %{"code" => code, "expires_at" => expires_at} = start_pairing()
+      assert is_binary(code)
+      assert expires_at > System.os_time(:second)
+    end
+
+    test "requires the shell token", %{conn: conn} do
+      conn = post(conn, ~p"/api/admin/pairing")
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+
+  describe "POST /api/admin/pairing/guest" do
+    test "makes a guest token", %{conn: conn} do
+      conn =
+        conn
+        |> put_req_header("authorization", "Bearer shell-token")
+        |> post(~p"/api/admin/pairing/guest")
+
+      assert %{"token" => token} = json_response(conn, 200)
+      assert TswIo.Pairing.valid_device_token?(token)
+    end
+
+    test "requires the shell token", %{conn: conn} do
+      conn = post(conn, ~p"/api/admin/pairing/guest")
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+
+  describe "GET /api/admin/paired_devices" do
+    test "lists the paired devices", %{conn: conn} do
+      %{"code" => code} = start_pairing()
+      {:ok, _token} = TswIo.Pairing.redeem(code, "Phone")
+
+      conn =
+        conn
+        |> put_req_header("authorization", "Bearer shell-token")
+        |> get(~p"/api/admin/paired_devices")
+
+      assert %{"devices" => [%{"name" => "Phone", "expires_at" => _}]} = json_response(conn, 200)
+    end
+
+    test "requires the shell token", %{conn: conn} do
+      conn = get(conn, ~p"/api/admin/paired_devices")
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+
+  describe "DELETE /api/admin/paired_devices/:id" do
+    test "unpairs the device", %{conn: conn} do
+      %{"code" => code} = start_pairing()
+      {:ok, token} = TswIo.Pairing.redeem(code, "Phone")
+      [device] = TswIo.Pairing.list_devices()
+
+      conn =
+        conn
+        |> put_req_header("authorization", "Bearer shell-token")
+        |> delete(~p"/api/admin/paired_devices/#{device.id}")
+
+      assert response(conn, 204)
+      refute TswIo.Pairing.valid_device_token?(token)
+    end
+
+    test "reports devices that aren't paired", %{conn: conn} do
+      conn =
+        conn
+        |> put_req_header("authorization", "Bearer shell-token")
+        |> delete(~p"/api/admin/paired_devices/12345")
+
+      assert json_response(conn, 404) == %{"error" => "not_found"}
+    end
+  end
+
+  describe "POST /api/pairing" do
+    test "trades the code for a device token", %{conn: conn} do
+      %{"code" => code} = start_pairing()
+
+      conn = post(conn, ~p"/api/pairing", %{code: code, name: "Phone"})
+
+      assert %{"token" => token} = json_response(conn, 200)
+      assert TswIo.Pairing.valid_device_token?(token)
+    end
+
+    test "refuses a wrong code", %{conn: conn} do
+      start_pairing()
+
+      conn = post(conn, ~p"/api/pairing", %{code: "guess"})
+
+      assert json_response(conn, 401) == %{"error" => "invalid"}
+    end
+
+    test "needs a code", %{conn: conn} do
+      conn = post(conn, ~p"/api/pairing", %{})
+
+      assert json_response(conn, 422) == %{"error" => "missing_code"}
+    end
+  end
+
+  describe "GET /pairing/session" do
+    test "keeps the device token in the session", %{conn: conn} do
+      %{"code" => code} = start_pairing()
+      {:ok, token} = TswIo.Pairing.redeem(code, "Phone")
+
+      conn = get(conn, ~p"/pairing/session?#{[token: token]}")
+
+      assert redirected_to(conn) == ~p"/"
+      assert get_session(conn, :device_token) == token
+    end
+
+    test "refuses other tokens", %{conn: conn} do
+      conn = get(conn, ~p"/pairing/session?#{[token: "made-up"]}")
+
+      assert json_response(conn, 401) == %{"error" => "not_paired"}
+    end
+  end
+end
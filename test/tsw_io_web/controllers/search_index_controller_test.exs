@@ -0,0 +1,52 @@
+defmodule TswIoWeb.SearchIndexControllerTest do
+  # The shell token is global state
+  use TswIoWeb.ConnCase, async: false
+
+  alias TswIo.{Hardware, Train}
+
+  setup %{conn: conn} do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    on_exit(fn -> Application.delete_env(:tsw_io, :shell_token) end)
+
+    %{conn: put_req_header(conn, "authorization", "Bearer shell-token")}
+  end
+
+  describe "GET /api/admin/search_index" do
+    test "lists configurations and trains with the pages they open on", %{conn: conn} do
+      {:ok, device} = Hardware.create_device(%{name: "Desk stand", description: "Left lever box"})
+      {:ok, train} = Train.create_train(%{name: "Class 66", identifier: "BR_Class_66"})
+
+      conn = get(conn, ~p"/api/admin/search_index")
+
+      assert %{"entries" => [configuration, train_entry]} = json_response(conn, 200)
+
+      assert configuration == %{
+               "id" => "configuration-#{device.config_id}",
+               "kind" => "configuration",
+               "title" => "Desk stand",
+               "snippet" => "Left lever box",
+               "route" => "/configurations/#{device.config_id}"
+             }
+
+      assert train_entry["route"] == "/trains/#{train.id}"
+      assert train_entry["snippet"] == "Train BR_Class_66"
+    end
+
+    test "with q, lists the matches best first", %{conn: conn} do
+      {:ok, _device} = Hardware.create_device(%{name: "Desk stand", description: "Class 66 levers"})
+      {:ok, _train} = Train.create_train(%{name: "Class 66", identifier: "BR_Class_66"})
+      {:ok, _other} = Train.create_train(%{name: "Class 43 HST", identifier: "BR_Class_43"})
+
+      conn = get(conn, ~p"/api/admin/search_index", q: "class 66")
+
+      assert %{"entries" => entries} = json_response(conn, 200)
+      assert Enum.map(entries, & &1["title"]) == ["Class 66", "Desk stand"]
+    end
+
+    test "requires the shell token" do
+      conn = get(build_conn(), ~p"/api/admin/search_index")
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+end
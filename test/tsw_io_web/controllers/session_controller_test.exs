@@ -0,0 +1,40 @@
+defmodule TswIoWeb.SessionControllerTest do
+  # The shell token is global state
+  use TswIoWeb.ConnCase, async: false
+
+  alias TswIoWeb.SessionController
+
+  setup do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    on_exit(fn -> Application.delete_env(:tsw_io, :shell_token) end)
+  end
+
+  describe "POST /api/admin/session/expire" do
+    test "disconnects every LiveView socket", %{conn: conn} do
+      TswIoWeb.Endpoint.subscribe(SessionController.live_socket_id())
+
+      conn =
+        conn
+        |> put_req_header("authorization", "Bearer shell-token")
+        |> post(~p"/api/admin/session/expire")
+
+      assert json_response(conn, 200) == %{"status" => "expired"}
+      assert_receive %Phoenix.Socket.Broadcast{event: "disconnect"}
+    end
+
+    test "needs the shell token" do
+      TswIoWeb.Endpoint.subscribe(SessionController.live_socket_id())
+
+      conn = post(build_conn(), ~p"/api/admin/session/expire")
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+      refute_receive %Phoenix.Socket.Broadcast{event: "disconnect"}
+    end
+  end
+
+  test "browser sessions get the shared socket id", %{conn: conn} do
+    conn = get(conn, ~p"/")
+
+    assert get_session(conn, :live_socket_id) == SessionController.live_socket_id()
+  end
+end
@@ -0,0 +1,42 @@
+defmodule TswIoWeb.SummaryControllerTest do
+  # The shell token is global state
+  use TswIoWeb.ConnCase, async: false
+
+  alias TswIo.{Hardware, Train}
+
+  setup %{conn: conn} do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    on_exit(fn -> Application.delete_env(:tsw_io, :shell_token) end)
+
+    %{conn: put_req_header(conn, "authorization", "Bearer shell-token")}
+  end
+
+  describe "GET /api/admin/summary" do
+    test "has today's numbers with the pages they open on", %{conn: conn} do
+      {:ok, _device} = Hardware.create_device(%{name: "Desk stand"})
+      {:ok, _train} = Train.create_train(%{name: "Class 66", identifier: "BR_Class_66"})
+
+      conn = get(conn, ~p"/api/admin/summary")
+
+      assert %{"generated_at" => _, "stats" => stats} = json_response(conn, 200)
+      stats = Map.new(stats, &{&1["id"], &1})
+
+      assert stats["trains_today"] == %{
+               "id" => "trains_today",
+               "label" => "Trains added today",
+               "value" => "1",
+               "route" => "/trains",
+               "headline" => false
+             }
+
+      assert stats["configurations"]["value"] == "1"
+      assert stats["active_train"]["headline"]
+    end
+
+    test "requires the shell token" do
+      conn = get(build_conn(), ~p"/api/admin/summary")
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+end
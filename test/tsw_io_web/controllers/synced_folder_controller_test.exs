@@ -0,0 +1,107 @@
+defmodule TswIoWeb.SyncedFolderControllerTest do
+  # The shell token and the data directory are global state
+  use TswIoWeb.ConnCase, async: false
+
+  alias TswIo.{SyncedFolders, Uploads}
+
+  @moduletag :tmp_dir
+
+  setup %{conn: conn, tmp_dir: tmp_dir} do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    previous_data_dir = System.get_env("TSW_IO_DATA_DIR")
+    System.put_env("TSW_IO_DATA_DIR", tmp_dir)
+
+    on_exit(fn ->
+      Application.delete_env(:tsw_io, :shell_token)
+
+      if previous_data_dir,
+        do: System.put_env("TSW_IO_DATA_DIR", previous_data_dir),
+        else: System.delete_env("TSW_IO_DATA_DIR")
+    end)
+
+    %{conn: put_req_header(conn, "authorization", "Bearer shell-token")}
+  end
+
+  defp upload(contents) do
+    {:ok, %{id: id}} = Uploads.create("file", byte_size(contents))
+    if contents != "", do: {:ok, %{complete: true}} = Uploads.append(id, 0, contents)
+    id
+  end
+
+  defp ingest(conn, contents, path, base_hash \\ nil) do
+    post(conn, ~p"/api/admin/synced_folders/ingest", %{
+      upload_id: upload(contents),
+      folder: "routes-1a2b3c4d",
+      path: path,
+      base_hash: base_hash
+    })
+  end
+
+  defp synced_file(path), do: Path.join(SyncedFolders.folder_dir("routes-1a2b3c4d"), path)
+
+  describe "POST /api/admin/synced_folders/ingest" do
+    test "files a new file under its folder", %{conn: conn} do
+      assert %{"status" => "created", "path" => "east/route.json", "hash" => hash} =
+               conn |> ingest("one", "east/route.json") |> json_response(200)
+
+      assert File.read!(synced_file("east/route.json")) == "one"
+      assert hash == Base.encode16(:crypto.hash(:sha256, "one"), case: :lower)
+    end
+
+    test "replaces a file the shell saw last", %{conn: conn} do
+      %{"hash" => hash} = conn |> ingest("one", "route.json") |> json_response(200)
+
+      assert %{"status" => "updated"} =
+               conn |> ingest("two", "route.json", hash) |> json_response(200)
+
+      assert File.read!(synced_file("route.json")) == "two"
+    end
+
+    test "leaves a file with the same contents alone", %{conn: conn} do
+      ingest(conn, "one", "route.json")
+
+      assert %{"status" => "unchanged"} =
+               conn |> ingest("one", "route.json") |> json_response(200)
+    end
+
+    test "keeps both versions of a file that changed on both sides", %{conn: conn} do
+      %{"hash" => kept} = conn |> ingest("one", "route.json") |> json_response(200)
+
+      assert %{"status" => "conflict", "path" => copy, "current" => ^kept} =
+               conn |> ingest("two", "route.json", "stale") |> json_response(200)
+
+      assert copy =~ ~r/\Aroute \(conflict \d{8}-\d{6}\)\.json\z/
+      assert File.read!(synced_file("route.json")) == "one"
+      assert File.read!(synced_file(copy)) == "two"
+    end
+
+    test "refuses paths outside the folder", %{conn: conn} do
+      assert json_response(ingest(conn, "one", "../route.json"), 422) == %{"error" => "invalid_file"}
+      assert json_response(ingest(conn, "one", "/etc/route.json"), 422) == %{"error" => "invalid_file"}
+    end
+
+    test "refuses uploads that aren't complete", %{conn: conn} do
+      {:ok, %{id: id}} = Uploads.create("file", 6)
+
+      conn =
+        post(conn, ~p"/api/admin/synced_folders/ingest", %{
+          upload_id: id,
+          folder: "routes-1a2b3c4d",
+          path: "route.json"
+        })
+
+      assert json_response(conn, 422) == %{"error" => "incomplete_upload"}
+    end
+
+    test "requires the shell token" do
+      conn =
+        post(build_conn(), ~p"/api/admin/synced_folders/ingest", %{
+          upload_id: "0",
+          folder: "routes",
+          path: "route.json"
+        })
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+end
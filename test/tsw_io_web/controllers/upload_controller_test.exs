@@ -0,0 +1,89 @@
+defmodule TswIoWeb.UploadControllerTest do
+  # The shell token and the data directory are global state
+  use TswIoWeb.ConnCase, async: false
+
+  alias TswIo.Uploads
+
+  @moduletag :tmp_dir
+
+  setup %{conn: conn, tmp_dir: tmp_dir} do
+    Application.put_env(:tsw_io, :shell_token, "shell-token")
+    previous_data_dir = System.get_env("TSW_IO_DATA_DIR")
+    System.put_env("TSW_IO_DATA_DIR", tmp_dir)
+
+    on_exit(fn ->
+      Application.delete_env(:tsw_io, :shell_token)
+
+      if previous_data_dir,
+        do: System.put_env("TSW_IO_DATA_DIR", previous_data_dir),
+        else: System.delete_env("TSW_IO_DATA_DIR")
+    end)
+
+    %{conn: put_req_header(conn, "authorization", "Bearer shell-token")}
+  end
+
+  defp create_upload(conn, size) do
+    conn
+    |> post(~p"/api/admin/uploads", %{filename: "route.bin", size: size})
+    |> json_response(201)
+  end
+
+  defp put_chunk(conn, id, offset, chunk) do
+    conn
+    |> put_req_header("content-type", "application/octet-stream")
+    |> put(~p"/api/admin/uploads/#{id}?offset=#{offset}", chunk)
+  end
+
+  describe "POST /api/admin/uploads" do
+    test "starts an upload", %{conn: conn} do
+      assert %{"id" => _, "filename" => "route.bin", "size" => 6, "received" => 0, "complete" => false} =
+               create_upload(conn, 6)
+    end
+
+    test "rejects uploads without a name or size", %{conn: conn} do
+      conn = post(conn, ~p"/api/admin/uploads", %{filename: "route.bin"})
+
+      assert json_response(conn, 422) == %{"error" => "invalid_upload"}
+    end
+
+    test "requires the shell token" do
+      conn = post(build_conn(), ~p"/api/admin/uploads", %{filename: "route.bin", size: 6})
+
+      assert json_response(conn, 401) == %{"error" => "unauthorized"}
+    end
+  end
+
+  describe "PUT /api/admin/uploads/:id" do
+    test "appends chunks until the upload is complete", %{conn: conn} do
+      %{"id" => id} = create_upload(conn, 6)
+
+      assert %{"received" => 3, "complete" => false} =
+               conn |> put_chunk(id, 0, "abc") |> json_response(200)
+
+      assert %{"received" => 6, "complete" => true} =
+               conn |> put_chunk(id, 3, "def") |> json_response(200)
+
+      assert File.read!(Uploads.path(id)) == "abcdef"
+    end
+
+    test "refuses chunks at the wrong offset with where to resume", %{conn: conn} do
+      %{"id" => id} = create_upload(conn, 6)
+      put_chunk(conn, id, 0, "abc")
+
+      assert json_response(put_chunk(conn, id, 0, "abc"), 409) ==
+               %{"error" => "offset_mismatch", "received" => 3}
+
+      assert %{"received" => 3} = conn |> get(~p"/api/admin/uploads/#{id}") |> json_response(200)
+    end
+
+    test "refuses more bytes than the upload has", %{conn: conn} do
+      %{"id" => id} = create_upload(conn, 2)
+
+      assert json_response(put_chunk(conn, id, 0, "abc"), 422) == %{"error" => "too_large"}
+    end
+
+    test "returns 404 for unknown uploads", %{conn: conn} do
+      assert json_response(put_chunk(conn, "../../etc", 0, "abc"), 404) == %{"error" => "not_found"}
+    end
+  end
+end
@@ -0,0 +1,33 @@
+defmodule TswIoWeb.TodayWidgetLiveTest do
+  # Non-async because the Connection GenServers need database access
+  use TswIoWeb.ConnCase, async: false
+
+  import Phoenix.LiveViewTest
+
+  alias TswIo.Train
+
+  setup do
+    Ecto.Adapters.SQL.Sandbox.mode(TswIo.Repo, {:shared, self()})
+    :ok
+  end
+
+  test "shows today's numbers", %{conn: conn} do
+    {:ok, _train} = Train.create_train(%{name: "Class 66", identifier: "BR_Class_66"})
+
+    {:ok, _view, html} = live(conn, ~p"/widgets/today")
+
+    assert html =~ "Trains added today"
+    assert html =~ "Connected devices"
+    assert html =~ "data-tauri-drag-region"
+  end
+
+  test "picks up changes when it refreshes", %{conn: conn} do
+    {:ok, view, html} = live(conn, ~p"/widgets/today")
+    assert html =~ ~r/Trains added today<\/dt>\s*<dd[^>]*>0</
+
+    {:ok, _train} = Train.create_train(%{name: "Class 43", identifier: "BR_Class_43"})
+    send(view.pid, :refresh)
+
+    assert render(view) =~ ~r/Trains added today<\/dt>\s*<dd[^>]*>1</
+  end
+end
@@ -0,0 +1,31 @@
+defmodule TswIoWeb.Plugs.ContentSecurityPolicyTest do
+  # The policy is global application config, so these tests can't run concurrently
+  use TswIoWeb.ConnCase, async: false
+
+  alias TswIoWeb.Plugs.ContentSecurityPolicy
+
+  setup do
+    original = Application.get_env(:tsw_io, :content_security_policy)
+    on_exit(fn -> Application.put_env(:tsw_io, :content_security_policy, original) end)
+    :ok
+  end
+
+  test "sends the policy with a fresh nonce", %{conn: conn} do
+    Application.put_env(:tsw_io, :content_security_policy, "script-src 'self' 'nonce-{nonce}'")
+
+    conn = ContentSecurityPolicy.call(conn, [])
+
+    nonce = conn.assigns.csp_nonce
+    assert get_resp_header(conn, "content-security-policy") == ["script-src 'self' 'nonce-#{nonce}'"]
+    refute ContentSecurityPolicy.call(build_conn(), []).assigns.csp_nonce == nonce
+  end
+
+  test "sends nothing without a policy", %{conn: conn} do
+    Application.put_env(:tsw_io, :content_security_policy, nil)
+
+    conn = ContentSecurityPolicy.call(conn, [])
+
+    assert get_resp_header(conn, "content-security-policy") == []
+    refute Map.has_key?(conn.assigns, :csp_nonce)
+  end
+end
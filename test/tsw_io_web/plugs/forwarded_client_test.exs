@@ -0,0 +1,47 @@
+defmodule TswIoWeb.Plugs.ForwardedClientTest do
+  # Whether the backend is behind the proxy is global application config
+  use TswIoWeb.ConnCase, async: false
+
+  alias TswIoWeb.Plugs.ForwardedClient
+
+  setup do
+    original = Application.get_env(:tsw_io, :behind_proxy)
+    on_exit(fn -> Application.put_env(:tsw_io, :behind_proxy, original) end)
+    Application.put_env(:tsw_io, :behind_proxy, true)
+    :ok
+  end
+
+  defp forwarded(conn, address), do: put_req_header(conn, "x-forwarded-for", address)
+
+  test "takes the address the proxy forwarded", %{conn: conn} do
+    conn = conn |> forwarded("192.168.1.20") |> ForwardedClient.call([])
+
+    assert conn.remote_ip == {192, 168, 1, 20}
+  end
+
+  test "keeps other machines out of the paired-only pages", %{conn: conn} do
+    conn = conn |> forwarded("192.168.1.20") |> get(~p"/trains")
+
+    assert json_response(conn, 401) == %{"error" => "not_paired"}
+  end
+
+  test "ignores the header from other machines", %{conn: conn} do
+    conn = %{conn | remote_ip: {192, 168, 1, 30}} |> forwarded("127.0.0.1") |> ForwardedClient.call([])
+
+    assert conn.remote_ip == {192, 168, 1, 30}
+  end
+
+  test "ignores the header without the proxy", %{conn: conn} do
+    Application.put_env(:tsw_io, :behind_proxy, false)
+
+    conn = conn |> forwarded("192.168.1.20") |> ForwardedClient.call([])
+
+    assert conn.remote_ip == {127, 0, 0, 1}
+  end
+
+  test "ignores addresses it can't read", %{conn: conn} do
+    conn = conn |> forwarded("192.168.1.20, 10.0.0.1") |> ForwardedClient.call([])
+
+    assert conn.remote_ip == {127, 0, 0, 1}
+  end
+end
@@ -0,0 +1,47 @@
+defmodule TswIoWeb.Plugs.LanAuthTest do
+  use TswIoWeb.ConnCase, async: false
+
+  defp from_lan(conn), do: %{conn | remote_ip: {192, 168, 1, 20}}
+
+  defp device_token do
+    {:ok, code, _} = TswIo.Pairing.start()
+    {:ok, token} = TswIo.Pairing.redeem(code, "Phone")
+    token
+  end
+
+  test "lets this machine in", %{conn: conn} do
+    conn = get(conn, ~p"/trains")
+
+    assert html_response(conn, 200)
+  end
+
+  test "keeps unpaired devices out", %{conn: conn} do
+    conn = conn |> from_lan() |> get(~p"/trains")
+
+    assert json_response(conn, 401) == %{"error" => "not_paired"}
+  end
+
+  test "lets paired devices in with a bearer token", %{conn: conn} do
+    conn =
+      conn
+      |> from_lan()
+      |> put_req_header("authorization", "Bearer #{device_token()}")
+      |> get(~p"/trains")
+
+    assert html_response(conn, 200)
+  end
+
+  test "lets paired devices in with the token in the session", %{conn: conn} do
+    conn = conn |> from_lan() |> get(~p"/pairing/session?#{[token: device_token()]}")
+
+    conn = conn |> recycle() |> from_lan() |> get(~p"/trains")
+
+    assert html_response(conn, 200)
+  end
+
+  test "leaves the health check open", %{conn: conn} do
+    conn = conn |> from_lan() |> get(~p"/api/health")
+
+    refute conn.status == 401
+  end
+end
@@ -0,0 +1,48 @@
+defmodule TswIoWeb.Plugs.SsoAuthTest do
+  use TswIoWeb.ConnCase, async: false
+
+  setup do
+    Application.put_env(:tsw_io, :sso, cookie: "tsw_io_sso", secret: "sso-secret")
+    on_exit(fn -> Application.delete_env(:tsw_io, :sso) end)
+  end
+
+  defp token(claims) do
+    encode = fn map -> map |> Jason.encode!() |> Base.url_encode64(padding: false) end
+    signed = encode.(%{"alg" => "HS256"}) <> "." <> encode.(claims)
+    signature = :crypto.mac(:hmac, :sha256, "sso-secret", signed)
+    signed <> "." <> Base.url_encode64(signature, padding: false)
+  end
+
+  test "lets those signed in in", %{conn: conn} do
+    conn =
+      conn
+      |> put_req_cookie("tsw_io_sso", token(%{"sub" => "driver"}))
+      |> get(~p"/trains")
+
+    assert html_response(conn, 200)
+    assert conn.assigns.sso_claims == %{"sub" => "driver"}
+  end
+
+  test "keeps those without a token out", %{conn: conn} do
+    conn = get(conn, ~p"/trains")
+
+    assert response(conn, 401) =~ "Sign in"
+  end
+
+  test "keeps expired tokens out", %{conn: conn} do
+    conn =
+      conn
+      |> put_req_cookie("tsw_io_sso", token(%{"exp" => System.os_time(:second) - 1}))
+      |> get(~p"/trains")
+
+    assert response(conn, 401) =~ "expired"
+  end
+
+  test "serves pages as before when single sign-on is off", %{conn: conn} do
+    Application.delete_env(:tsw_io, :sso)
+
+    conn = get(conn, ~p"/trains")
+
+    assert html_response(conn, 200)
+  end
+end